@@ -1,7 +1,8 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 use matching_core::api::*;
-use matching_core::core::orderbook::{DirectOrderBook, NaiveOrderBook, OrderBook};
+use matching_core::core::orderbook::{AdvancedOrderBook, DirectOrderBook, NaiveOrderBook, OrderBook};
 use matching_core::core::processors::risk_engine::RiskEngine;
+use matching_core::workload::{OrderFlowGenerator, WorkloadConfig};
 
 fn bench_orderbook(c: &mut Criterion) {
     let spec = CoreSymbolSpecification {
@@ -56,6 +57,39 @@ fn bench_orderbook(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_mixed_flow(c: &mut Criterion) {
+    let spec = CoreSymbolSpecification {
+        symbol_id: 1,
+        base_currency: 1,
+        quote_currency: 2,
+        ..Default::default()
+    };
+    let config = WorkloadConfig {
+        seed: 42,
+        uids: (1..=100).collect(),
+        order_types: vec![(OrderType::Gtc, 80), (OrderType::Ioc, 10), (OrderType::PostOnly, 5), (OrderType::Iceberg, 5)],
+        cancel_rate: 0.3,
+        move_rate: 0.1,
+        ..Default::default()
+    };
+    let commands = OrderFlowGenerator::new(config).take_commands(10_000);
+
+    c.bench_function("AdvancedOrderBook_mixed_flow_10k", |b| {
+        b.iter(|| {
+            let mut book = AdvancedOrderBook::new(spec.clone());
+            for cmd in &commands {
+                let mut cmd = cmd.clone();
+                match cmd.command {
+                    OrderCommandType::PlaceOrder => book.new_order(&mut cmd),
+                    OrderCommandType::CancelOrder => book.cancel_order(&mut cmd),
+                    OrderCommandType::MoveOrder => book.move_order(&mut cmd),
+                    _ => continue,
+                };
+            }
+        })
+    });
+}
+
 fn bench_risk_engine(c: &mut Criterion) {
     let mut engine = RiskEngine::new(0, 1);
     engine.add_symbol(CoreSymbolSpecification {
@@ -93,5 +127,5 @@ fn bench_risk_engine(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_orderbook, bench_risk_engine);
+criterion_group!(benches, bench_orderbook, bench_mixed_flow, bench_risk_engine);
 criterion_main!(benches);
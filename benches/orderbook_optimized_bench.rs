@@ -1,6 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use matching_core::api::*;
-use matching_core::core::orderbook::{DirectOrderBook, DirectOrderBookOptimized, NaiveOrderBook, OrderBook};
+use matching_core::core::orderbook::{
+    BTreePriceIndex, DirectOrderBook, DirectOrderBookOptimized, NaiveOrderBook, OrderBook, PriceIndex, PriceLadder,
+};
 
 fn bench_naive_orderbook(c: &mut Criterion) {
     let spec = CoreSymbolSpecification::default();
@@ -68,11 +70,147 @@ fn bench_direct_optimized_orderbook(c: &mut Criterion) {
     });
 }
 
+fn ask(order_id: OrderId, price: Price) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1001,
+        order_id,
+        symbol: 100,
+        price,
+        size: 10,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    }
+}
+
+/// 卖盘 50 档、每档 8 笔挂单；每轮买单吃掉前 20 档后按原样补回
+fn bench_direct_optimized_sweep(c: &mut Criterion) {
+    for (name, use_simd) in [("DirectOrderBookOptimized_Sweep_Batch", true), ("DirectOrderBookOptimized_Sweep_Scalar", false)] {
+        let mut orderbook = DirectOrderBookOptimized::new(CoreSymbolSpecification::default());
+        orderbook.set_simd_enabled(use_simd);
+        for order_id in 0..400 {
+            orderbook.new_order(&mut ask(order_id, 10_000 + (order_id / 8) as Price));
+        }
+
+        let mut events = Vec::with_capacity(256);
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                let mut taker = OrderCommand {
+                    command: OrderCommandType::PlaceOrder,
+                    uid: 2001,
+                    order_id: 100_000,
+                    symbol: 100,
+                    price: black_box(10_019),
+                    reserve_price: 10_019,
+                    size: black_box(1_600),
+                    action: OrderAction::Bid,
+                    order_type: OrderType::Ioc,
+                    matcher_events: std::mem::take(&mut events),
+                    ..Default::default()
+                };
+                orderbook.new_order(&mut taker);
+                events = taker.matcher_events;
+                events.clear();
+
+                for order_id in 0..160 {
+                    orderbook.new_order(&mut ask(order_id, 10_000 + (order_id / 8) as Price));
+                }
+            });
+        });
+    }
+}
+
+/// 密集盘口：买卖各 1000 个连续档位，每档 2 笔挂单
+fn dense_book<I: PriceIndex>() -> DirectOrderBookOptimized<I>
+where
+    DirectOrderBookOptimized<I>: OrderBook,
+{
+    let mut orderbook = DirectOrderBookOptimized::<I>::with_price_index(CoreSymbolSpecification::default());
+    for level in 0..1000 {
+        for n in 0..2 {
+            let order_id = level * 2 + n;
+            orderbook.new_order(&mut ask(order_id, 10_001 + level as Price));
+            let mut bid = ask(10_000 + order_id, 10_000 - level as Price);
+            bid.action = OrderAction::Bid;
+            bid.reserve_price = bid.price;
+            orderbook.new_order(&mut bid);
+        }
+    }
+    orderbook
+}
+
+/// 价格索引对比：密集盘口上的挂单/撤单（档位查找、增删）与逐档扫单
+fn bench_price_index_dense(c: &mut Criterion) {
+    fn place_cancel<I: PriceIndex>(c: &mut Criterion, name: &str)
+    where
+        DirectOrderBookOptimized<I>: OrderBook,
+    {
+        let mut orderbook = dense_book::<I>();
+        let mut level = 0;
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                // 在已有档位和盘口外新档位之间轮换
+                level = (level + 37) % 1200;
+                let mut cmd = ask(50_000, 10_001 + black_box(level) as Price);
+                orderbook.new_order(&mut cmd);
+                let mut cancel = OrderCommand {
+                    command: OrderCommandType::CancelOrder,
+                    uid: 1001,
+                    order_id: 50_000,
+                    symbol: 100,
+                    ..Default::default()
+                };
+                OrderBook::cancel_order(&mut orderbook, &mut cancel);
+            });
+        });
+    }
+
+    fn sweep<I: PriceIndex>(c: &mut Criterion, name: &str)
+    where
+        DirectOrderBookOptimized<I>: OrderBook,
+    {
+        let mut orderbook = dense_book::<I>();
+        let mut events = Vec::with_capacity(256);
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                let mut taker = OrderCommand {
+                    command: OrderCommandType::PlaceOrder,
+                    uid: 2001,
+                    order_id: 100_000,
+                    symbol: 100,
+                    price: black_box(10_050),
+                    reserve_price: 10_050,
+                    size: black_box(1_000),
+                    action: OrderAction::Bid,
+                    order_type: OrderType::Ioc,
+                    matcher_events: std::mem::take(&mut events),
+                    ..Default::default()
+                };
+                orderbook.new_order(&mut taker);
+                events = taker.matcher_events;
+                events.clear();
+
+                for order_id in 0..100 {
+                    orderbook.new_order(&mut ask(order_id, 10_001 + (order_id / 2) as Price));
+                }
+            });
+        });
+    }
+
+    place_cancel::<BTreePriceIndex>(c, "PriceIndex_Dense_PlaceCancel_BTree");
+    place_cancel::<PriceLadder>(c, "PriceIndex_Dense_PlaceCancel_Ladder");
+    sweep::<BTreePriceIndex>(c, "PriceIndex_Dense_Sweep_BTree");
+    sweep::<PriceLadder>(c, "PriceIndex_Dense_Sweep_Ladder");
+}
+
 criterion_group!(
     benches,
     bench_naive_orderbook,
     bench_direct_orderbook,
-    bench_direct_optimized_orderbook
+    bench_direct_optimized_orderbook,
+    bench_direct_optimized_sweep,
+    bench_price_index_dense
 );
 criterion_main!(benches);
 
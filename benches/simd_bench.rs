@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use matching_core::core::orderbook::simd_utils::{self, scalar};
+
+/// SIMD 分派实现与标量实现对比（1024 个元素，两者都包含结果分配）
+fn bench_simd_vs_scalar(c: &mut Criterion) {
+    let prices: Vec<i64> = (0..1024).map(|i| 10_000 + (i * 37) % 500).collect();
+    let sizes: Vec<i64> = (0..1024).map(|i| 10 + i % 90).collect();
+    let filled: Vec<i64> = (0..1024).map(|i| i % 10).collect();
+    let need_size = sizes.iter().zip(&filled).map(|(s, f)| s - f).sum::<i64>() - 100;
+
+    let mut group = c.benchmark_group(format!("Simd_{:?}", simd_utils::simd_level()));
+    let mut values = Vec::with_capacity(1024);
+
+    group.bench_function(BenchmarkId::new("price_compare_le", "simd"), |b| {
+        b.iter(|| simd_utils::simd_price_compare_le(black_box(&prices), 10_250))
+    });
+    group.bench_function(BenchmarkId::new("price_compare_le", "scalar"), |b| {
+        b.iter(|| {
+            let mut result = Vec::with_capacity(prices.len());
+            scalar::price_compare_le(black_box(&prices), 10_250, &mut result);
+            result
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("sum_sizes", "simd"), |b| {
+        b.iter(|| simd_utils::simd_sum_sizes(black_box(&sizes)))
+    });
+    group.bench_function(BenchmarkId::new("sum_sizes", "scalar"), |b| {
+        b.iter(|| scalar::sum_sizes(black_box(&sizes)))
+    });
+
+    group.bench_function(BenchmarkId::new("min_pairs", "simd"), |b| {
+        b.iter(|| simd_utils::simd_min_pairs(black_box(&sizes), black_box(&filled)))
+    });
+    group.bench_function(BenchmarkId::new("min_pairs", "scalar"), |b| {
+        b.iter(|| {
+            let mut result = Vec::with_capacity(sizes.len());
+            scalar::min_pairs(black_box(&sizes), black_box(&filled), &mut result);
+            result
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("batch_match_prepare", "simd"), |b| {
+        b.iter(|| simd_utils::simd_batch_match_prepare_into(black_box(&sizes), black_box(&filled), need_size, &mut values))
+    });
+    group.bench_function(BenchmarkId::new("batch_match_prepare", "scalar"), |b| {
+        b.iter(|| {
+            values.clear();
+            scalar::batch_match_prepare(black_box(&sizes), black_box(&filled), need_size, &mut values)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_simd_vs_scalar);
+criterion_main!(benches);
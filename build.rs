@@ -0,0 +1,49 @@
+fn main() {
+    #[cfg(feature = "gateway")]
+    gateway::compile();
+}
+
+/// 生成 proto/exchange.proto 中 ExchangeGateway 服务的 tonic 代码
+///
+/// 消息类型在 src/gateway/proto.rs 中手写（构建不依赖 protoc），这里只生成服务端/客户端
+#[cfg(feature = "gateway")]
+mod gateway {
+    use tonic_build::manual::{Method, Service};
+
+    const CODEC: &str = "tonic::codec::ProstCodec";
+
+    fn method(name: &str, route: &str, input: &str, output: &str) -> Method {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::gateway::proto::{}", input))
+            .output_type(format!("crate::gateway::proto::{}", output))
+            .codec_path(CODEC)
+            .build()
+    }
+
+    pub fn compile() {
+        println!("cargo:rerun-if-changed=build.rs");
+        let service = Service::builder()
+            .name("ExchangeGateway")
+            .package("matching_core.gateway")
+            .method(method("place_order", "PlaceOrder", "PlaceOrderRequest", "CommandReply"))
+            .method(method("cancel_order", "CancelOrder", "CancelOrderRequest", "CommandReply"))
+            .method(method("move_order", "MoveOrder", "MoveOrderRequest", "CommandReply"))
+            .method(method("reduce_order", "ReduceOrder", "ReduceOrderRequest", "CommandReply"))
+            .method(method("adjust_balance", "AdjustBalance", "BalanceAdjustmentRequest", "CommandReply"))
+            .method(method("get_order_book", "GetOrderBook", "OrderBookRequest", "OrderBookReply"))
+            .method(
+                Method::builder()
+                    .name("subscribe_market_data")
+                    .route_name("SubscribeMarketData")
+                    .input_type("crate::gateway::proto::MarketDataSubscription")
+                    .output_type("crate::gateway::proto::MarketDataEvent")
+                    .codec_path(CODEC)
+                    .server_streaming()
+                    .build(),
+            )
+            .build();
+        tonic_build::manual::Builder::new().compile(&[service]);
+    }
+}
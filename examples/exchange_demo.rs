@@ -1,5 +1,6 @@
 use matching_core::api::*;
 use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::journal::Journaler;
 use std::sync::Arc;
 
 fn main() {
@@ -22,6 +23,7 @@ fn main() {
         maker_fee: 5,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     });
 
     // 添加用户
@@ -196,7 +198,7 @@ fn main() {
     let journal_path = "exchange.wal";
     
     // 如果文件已存在则删除，确保干净测试
-    let _ = std::fs::remove_file(journal_path);
+    let _ = Journaler::remove_all(journal_path);
 
     let mut core_wal = ExchangeCore::new(ExchangeConfig::default());
     core_wal.add_symbol(CoreSymbolSpecification {
@@ -245,7 +247,7 @@ fn main() {
     println!("WAL 恢复成功");
 
     // 清理测试文件
-    let _ = std::fs::remove_file(journal_path);
+    let _ = Journaler::remove_all(journal_path);
 
     // 8. 测试 Snapshotting Mechanism
     println!("\n=== 测试状态快照 (Snapshotting) ===\n");
@@ -269,8 +271,8 @@ fn main() {
         ..Default::default()
     });
 
-    println!("生成快照 (ID: 1)...");
-    core_snap.take_snapshot(1).expect("生成快照失败");
+    println!("生成快照...");
+    core_snap.create_snapshot().expect("生成快照失败");
 
     println!("从快照目录恢复到新核心...");
     let mut core_restored = ExchangeCore::new(ExchangeConfig::default());
@@ -1,6 +1,6 @@
 use matching_core::api::*;
 use matching_core::core::exchange::{ExchangeCore, ExchangeConfig, ProducerType, WaitStrategyType};
-use std::time::{Duration, Instant};
+use std::time::Instant;
 use std::sync::Arc;
 
 /// 压力测试配置
@@ -17,7 +17,7 @@ fn main() {
         batch_size: 1000,
     };
 
-    println!("测试规模: {} 订单", config.num_orders);
+    println!("测试规模: {} 订单 (批次 {})", config.num_orders, config.batch_size);
 
     // 1. 测试 DirectOrderBook (核心业务流水线)
     run_load_test("DirectOrderBook + Pipeline", &config);
@@ -33,6 +33,17 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
         risk_engines_num: 1,
         producer_type: ProducerType::Single,
         wait_strategy: WaitStrategyType::BusySpin,
+        invariant_check_interval: 0,
+        msgs_in_group_limit: 256,
+        consumer_cores: Vec::new(),
+        thread_affinity: Default::default(),
+        order_book_kind: OrderBookKind::Direct,
+        risk_limits: Default::default(),
+        rate_limit: None,
+        event_pool_size: 1024,
+        trading_day_length: 0,
+        spread_groups: Vec::new(),
+        idempotency_cache_size: 0,
     };
     
     let mut core = ExchangeCore::new(exchange_config);
@@ -76,6 +87,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
             uid,
             symbol: 2,
             price: 10_000_000,
+            order_id: uid as OrderId * 10 + 2,
             ..Default::default()
         });
         core.submit_command(OrderCommand {
@@ -83,6 +95,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
             uid,
             symbol: 1,
             price: 10_000_000,
+            order_id: uid as OrderId * 10 + 1,
             ..Default::default()
         });
     }
@@ -104,7 +117,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
     
     // 等待所有异步消息处理完毕 (init + warmup + num_orders)
     // 注意：init 包含 3*init_user_count
-    let expected = 3 * (init_user_count as usize) + (warmup_count as usize) + (config.num_orders as usize);
+    let expected = 3 * (init_user_count as usize) + (warmup_count as usize) + config.num_orders;
     while processed_count.load(Ordering::Acquire) < expected {
         std::hint::spin_loop();
     }
@@ -122,7 +135,7 @@ fn run_load_test(name: &str, config: &LoadTestConfig) {
 #[inline(always)]
 fn simulate_order(core: &mut ExchangeCore, i: u64) {
     let uid = (i % 10000) + 1;
-    let action = if i % 2 == 0 { OrderAction::Bid } else { OrderAction::Ask };
+    let action = if i.is_multiple_of(2) { OrderAction::Bid } else { OrderAction::Ask };
     
     // 模拟真实的买卖交替，订单 ID 递增
     core.submit_command(OrderCommand {
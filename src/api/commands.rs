@@ -1,5 +1,6 @@
 use super::types::*;
 use super::events::*;
+use super::market_data::L2MarketData;
 use serde::{Deserialize, Serialize};
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 
@@ -24,8 +25,37 @@ pub enum OrderCommandType {
     PersistStateRisk,
     GroupingControl,
     ShutdownSignal,
+    ExpireOrders, // 定时过期扫描并激活到期的 GoodAfterTime 订单（timestamp 为当前时间）
+    UserOrdersRequest, // 查询用户在 symbol 上的挂单
+    SetMarkPrice,      // 更新标记价格（price 为标记价）
+    ApplyFunding,      // 永续合约资金费结算（price 为资金费率，单位见 FUNDING_RATE_SCALE）
+    SetSessionState,   // 切换交易对交易时段（service_flags 为 TradingSessionState 编码）
+    ReferencePriceUpdate, // 推送外部参考价格（price 为价格，service_flags 为 StopTriggerSource 编码：标记价或指数价）
+    HoldFunds,         // 提现冻结（symbol 为币种，price 为金额，order_id 为交易号）
+    ConfirmWithdrawal, // 确认提现，扣除 order_id 对应的冻结资金
+    ReleaseHold,       // 取消提现，冻结资金返还余额
+    SetFeeTier,        // 设置用户手续费等级（price 为 taker 费率、size 为 maker 费率，单位为交易对费率的万分比）
+    AdvanceTime,       // 推进引擎时钟到 timestamp 并对所有交易对做过期扫描与 GoodAfterTime 激活（由 ExchangeCore 展开，不进入流水线、不写日志）
+    DelistSymbol,      // 下架交易对：撤销全部挂单并返还冻结资金，移除订单簿与交易对规格
+    ClearOrderBook,    // 清空订单簿：撤销全部挂单并返还冻结资金，保留交易对规格
+    AmendOrder,        // 改单：price 为新价格（0 保持原价），size 为新的剩余数量；只减少数量时保留时间优先级，增加数量或改价重新排队
+    PlaceBasket,       // 组合下单：binary_data 为 bincode 编码的各腿新单，全部腿通过风控才一并进入撮合（见 OrderCommand::place_basket）
+    ExerciseOption,    // 期权行权：uid 按管理端推送的指数价平掉 size 手多头，同等数量按持仓比例指派给空头（price 须为 0）
+    ExpireSeries,      // 期权到期：撤销 symbol 全部挂单，按结算价 price 的内在价值平掉本系列全部多空持仓，移除交易对
 }
 
+/// SuspendUser 的 service_flags 标记：暂停的同时撤销该用户全部挂单
+pub const SUSPEND_USER_CANCEL_ORDERS: i32 = 1;
+
+/// 引擎分配的订单号均不小于该值，调用方自选的订单号应小于该值以免冲突
+pub const ASSIGNED_ORDER_ID_BASE: OrderId = 1 << 62;
+
+/// BinaryDataCommand 的命令类型（service_flags）：批量添加交易对，binary_data 为 bincode 编码的交易对列表
+pub const BINARY_COMMAND_ADD_SYMBOLS: i32 = 1;
+
+/// BinaryDataCommand 的命令类型（service_flags）：重置 maker 成交量统计，各用户当前周期结转为上一周期并开始新周期
+pub const BINARY_COMMAND_RESET_MAKER_VOLUME: i32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
 #[archive(check_bytes)]
 #[archive_attr(derive(Debug))]
@@ -49,10 +79,112 @@ pub struct OrderCommand {
     // 扩展字段
     pub stop_price: Option<Price>,      // 止损触发价
     pub visible_size: Option<Size>,     // 冰山单显示数量
+    pub replenish_size: Option<Size>,   // 冰山单刷新数量（None 时同 visible_size）
     pub expire_time: Option<i64>,       // 过期时间（GTD）
+    pub stp_mode: StpMode,              // 自成交预防策略（None 时使用交易对默认）
+    pub stop_trigger: StopTriggerSource, // 止损单触发价格来源
+    pub client_order_id: u64,           // 客户端订单号（随挂单保存并在事件、查询中原样回传，0 表示未设置）
+    pub sequence: u64,                  // 撮合分片分配的命令序号（交易对命令；0 表示未经撮合）
+    pub engine_timestamp: i64,          // 撮合引擎时间（单调不减）
+    pub amend_hold: Size,               // AmendOrder：R1 为增加的数量追加冻结的手数（撮合最多增加该数量）
+    pub min_fill_size: Option<Size>,    // 最小成交数量：下单时立即可成交的数量不足时不撮合（只有 Advanced 订单簿支持）
+    pub hidden: bool,                   // 隐藏单：数量不在 L2/L3 行情中显示，仍可撮合（只有 Advanced 订单簿支持）
+    pub assign_order_id: bool,          // PlaceOrder：由引擎分配订单号（忽略传入的 order_id，分配结果写回 order_id）
+    pub activate_time: Option<i64>,     // GoodAfterTime：晚于下单时间时订单暂存至该时间再进入撮合（只有 Advanced 订单簿支持）
+    pub post_only_slide: bool,          // PostOnly：会立即成交时不拒绝，改挂在对手方最优价内侧一个最小变动单位（新价格写回 price，只有 Advanced 订单簿支持）
     
-    // 撮合事件列表（预分配容量）
+    // 撮合事件列表（按需分配，或由事件缓冲区池 / 环形缓冲区槽位提供）
     pub matcher_events: Vec<MatcherTradeEvent>,
+
+    // OrderBookRequest 的应答（size 为请求深度）
+    pub market_data: Option<L2MarketData>,
+    // UserOrdersRequest 的应答；批量撤单、组合下单与发生隐含成交的下单时为各撮合事件所属的订单
+    pub open_orders: Vec<OpenOrder>,
+    // BinaryDataQuery/BinaryDataCommand 的二进制负载，PlaceBasket 的各腿
+    pub binary_data: Vec<u8>,
+}
+
+impl OrderCommand {
+    /// 批量添加交易对命令（全部校验通过才生效）
+    pub fn add_symbols(specs: &[CoreSymbolSpecification]) -> Self {
+        Self {
+            command: OrderCommandType::BinaryDataCommand,
+            service_flags: BINARY_COMMAND_ADD_SYMBOLS,
+            binary_data: bincode::serialize(specs).expect("交易对序列化失败"),
+            ..Default::default()
+        }
+    }
+
+    /// 切换交易对交易时段命令
+    pub fn set_session_state(symbol: SymbolId, state: TradingSessionState) -> Self {
+        Self {
+            command: OrderCommandType::SetSessionState,
+            symbol,
+            service_flags: state.code(),
+            ..Default::default()
+        }
+    }
+
+    /// 推送外部参考价格命令（标记价或指数价），用于触发对应来源的止损单
+    pub fn reference_price_update(symbol: SymbolId, source: StopTriggerSource, price: Price, timestamp: i64) -> Self {
+        Self {
+            command: OrderCommandType::ReferencePriceUpdate,
+            symbol,
+            price,
+            service_flags: source.code(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    /// 组合下单命令：legs 为各腿新单（只使用订单字段，uid 与时间戳取自组合命令）
+    ///
+    /// 风控逐腿冻结，任一腿未通过时整单拒绝且不冻结任何资金；全部通过后各腿在同一流水线轮次进入各自订单簿，
+    /// 撮合产生的事件以所属的腿为 taker，并与 cmd.open_orders 中的腿订单一一对应
+    pub fn place_basket(uid: UserId, legs: &[OrderCommand], timestamp: i64) -> Self {
+        Self {
+            command: OrderCommandType::PlaceBasket,
+            uid,
+            timestamp,
+            binary_data: bincode::serialize(legs).expect("组合订单序列化失败"),
+            ..Default::default()
+        }
+    }
+
+    /// 解码组合下单的各腿（命令类型、uid 与时间戳取自组合命令）；撮合后各腿的 result_code 为该腿的撮合结果
+    pub fn decode_basket(&self) -> Result<Vec<OrderCommand>, bincode::Error> {
+        let mut legs: Vec<OrderCommand> = bincode::deserialize(&self.binary_data)?;
+        for leg in &mut legs {
+            leg.command = OrderCommandType::PlaceOrder;
+            leg.uid = self.uid;
+            leg.timestamp = self.timestamp;
+        }
+        Ok(legs)
+    }
+
+    /// 第 index 个撮合事件所属的交易对：批量撤单、组合下单与隐含成交的事件属于 open_orders 中对应的订单
+    pub fn event_symbol(&self, index: usize) -> SymbolId {
+        match self.command {
+            OrderCommandType::SuspendUser | OrderCommandType::PlaceBasket | OrderCommandType::PlaceOrder => {
+                self.open_orders.get(index).map_or(self.symbol, |order| order.symbol)
+            }
+            _ => self.symbol,
+        }
+    }
+
+    /// 第 index 个撮合事件是否为命令自身订单在价差组内其他交易对上的隐含成交腿
+    pub fn is_implied_leg(&self, index: usize) -> bool {
+        self.command == OrderCommandType::PlaceOrder
+            && self.event_symbol(index) != self.symbol
+            && self.matcher_events.get(index).is_some_and(|event| {
+                event.taker_action.is_some() && event.taker_uid == self.uid && event.taker_order_id == self.order_id
+            })
+    }
+
+    /// 解码 binary_data 中的交易对列表（添加交易对命令、交易对查询的应答）
+    pub fn decode_symbols(&self) -> Result<Vec<CoreSymbolSpecification>, bincode::Error> {
+        bincode::deserialize(&self.binary_data)
+    }
 }
 
 impl Default for OrderCommand {
@@ -73,8 +205,23 @@ impl Default for OrderCommand {
             service_flags: 0,
             stop_price: None,
             visible_size: None,
+            replenish_size: None,
             expire_time: None,
-            matcher_events: Vec::with_capacity(4), // 预分配 4 个事件容量
+            stp_mode: StpMode::None,
+            stop_trigger: StopTriggerSource::LastPrice,
+            client_order_id: 0,
+            sequence: 0,
+            engine_timestamp: 0,
+            amend_hold: 0,
+            min_fill_size: None,
+            hidden: false,
+            assign_order_id: false,
+            activate_time: None,
+            post_only_slide: false,
+            matcher_events: Vec::new(),
+            market_data: None,
+            open_orders: Vec::new(),
+            binary_data: Vec::new(),
         }
     }
 }
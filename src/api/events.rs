@@ -10,6 +10,7 @@ pub enum MatcherEventType {
     Trade,      // 成交
     Reject,     // 拒绝
     Reduce,     // 减少
+    Amend,      // 改单（size 为返还冻结资金的数量）
 }
 
 /// 撮合事件
@@ -22,7 +23,18 @@ pub struct MatcherTradeEvent {
     pub price: Price,
     pub matched_order_id: OrderId,
     pub matched_order_uid: UserId,
+    pub matched_client_order_id: u64, // maker 订单的客户端订单号
     pub bidder_hold_price: Price, // 买单预留价格
+    pub remaining_size: Size,     // 减量/撤单后订单的剩余数量（0 表示订单已移除），R2 据此核对挂单与冻结资金
+    pub maker_action: Option<OrderAction>, // 事件作用于挂单时的挂单方向（撤销/过期等）
+    pub maker_completed: bool,             // maker 订单是否已完结（全部成交或被移除）
+    pub taker_action: Option<OrderAction>, // 止损单激活、组合下单或隐含成交腿的事件：taker 订单的方向（None 时 taker 为命令自身的订单）
+    pub taker_order_id: OrderId,
+    pub taker_uid: UserId,
+    pub taker_client_order_id: u64,
+    pub sequence: u64, // 撮合分片分配的事件序号（分片内单调递增）
+    pub timestamp: i64, // 撮合引擎时间（单调不减）
+    pub trade_id: u64, // 成交编号（按交易对从 1 递增，非成交事件为 0）
 }
 
 impl Default for MatcherTradeEvent {
@@ -33,18 +45,45 @@ impl Default for MatcherTradeEvent {
             price: 0,
             matched_order_id: 0,
             matched_order_uid: 0,
+            matched_client_order_id: 0,
             bidder_hold_price: 0,
+            remaining_size: 0,
+            maker_action: None,
+            maker_completed: false,
+            taker_action: None,
+            taker_order_id: 0,
+            taker_uid: 0,
+            taker_client_order_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            trade_id: 0,
         }
     }
 }
 
 impl MatcherTradeEvent {
+    /// 附带 maker 订单的客户端订单号
+    pub fn with_client_order_id(mut self, client_order_id: u64) -> Self {
+        self.matched_client_order_id = client_order_id;
+        self
+    }
+
+    /// 事件的 taker（用户, 订单号, 方向）：止损单激活产生的事件为被激活的订单，组合下单的事件为所属的腿，
+    /// 隐含成交的腿为命令自身的订单按该腿的方向，否则为命令自身的订单
+    pub fn taker(&self, cmd: &OrderCommand) -> (UserId, OrderId, OrderAction) {
+        match self.taker_action {
+            Some(action) => (self.taker_uid, self.taker_order_id, action),
+            None => (cmd.uid, cmd.order_id, cmd.action),
+        }
+    }
+
     pub fn new_trade(
         size: Size,
         price: Price,
         matched_order_id: OrderId,
         matched_order_uid: UserId,
         bidder_hold_price: Price,
+        maker_completed: bool,
     ) -> Self {
         Self {
             event_type: MatcherEventType::Trade,
@@ -52,18 +91,133 @@ impl MatcherTradeEvent {
             price,
             matched_order_id,
             matched_order_uid,
+            matched_client_order_id: 0,
             bidder_hold_price,
+            remaining_size: 0,
+            maker_action: None,
+            maker_completed,
+            taker_action: None,
+            taker_order_id: 0,
+            taker_uid: 0,
+            taker_client_order_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            trade_id: 0,
         }
     }
 
-    pub fn new_reject(size: Size, price: Price) -> Self {
+    /// 拒绝/撤单事件，bidder_hold_price 为该订单下单时的冻结价格（买单按此返还）
+    pub fn new_reject(size: Size, price: Price, bidder_hold_price: Price) -> Self {
         Self {
             event_type: MatcherEventType::Reject,
             size,
             price,
             matched_order_id: 0,
             matched_order_uid: 0,
-            bidder_hold_price: 0,
+            matched_client_order_id: 0,
+            bidder_hold_price,
+            remaining_size: 0,
+            maker_action: None,
+            maker_completed: false,
+            taker_action: None,
+            taker_order_id: 0,
+            taker_uid: 0,
+            taker_client_order_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            trade_id: 0,
+        }
+    }
+
+    /// 减量事件：size 为减少的数量（按 bidder_hold_price 返还冻结资金），remaining_size 为减量后的剩余数量
+    pub fn new_reduce(size: Size, price: Price, bidder_hold_price: Price, remaining_size: Size) -> Self {
+        Self {
+            event_type: MatcherEventType::Reduce,
+            size,
+            price,
+            matched_order_id: 0,
+            matched_order_uid: 0,
+            matched_client_order_id: 0,
+            bidder_hold_price,
+            remaining_size,
+            maker_action: None,
+            maker_completed: false,
+            taker_action: None,
+            taker_order_id: 0,
+            taker_uid: 0,
+            taker_client_order_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            trade_id: 0,
+        }
+    }
+
+    /// 改单事件：price 为新价格，size 为返还冻结资金的数量（减少的数量与未用完的追加冻结），remaining_size 为改单后的剩余数量
+    pub fn new_amend(size: Size, price: Price, bidder_hold_price: Price, remaining_size: Size) -> Self {
+        Self {
+            event_type: MatcherEventType::Amend,
+            ..Self::new_reduce(size, price, bidder_hold_price, remaining_size)
+        }
+    }
+
+    /// maker 挂单被扣减/撤销（如自成交预防），携带 maker 订单信息与扣减后的剩余数量
+    pub fn new_maker_reduce(
+        size: Size,
+        price: Price,
+        matched_order_id: OrderId,
+        matched_order_uid: UserId,
+        maker_action: OrderAction,
+        bidder_hold_price: Price,
+        remaining_size: Size,
+    ) -> Self {
+        Self {
+            event_type: MatcherEventType::Reduce,
+            size,
+            price,
+            matched_order_id,
+            matched_order_uid,
+            matched_client_order_id: 0,
+            bidder_hold_price,
+            remaining_size,
+            maker_action: Some(maker_action),
+            maker_completed: remaining_size == 0,
+            taker_action: None,
+            taker_order_id: 0,
+            taker_uid: 0,
+            taker_client_order_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            trade_id: 0,
+        }
+    }
+
+    /// maker 挂单被系统移除（如过期），携带 maker 订单信息
+    pub fn new_maker_reject(
+        size: Size,
+        price: Price,
+        matched_order_id: OrderId,
+        matched_order_uid: UserId,
+        maker_action: OrderAction,
+        bidder_hold_price: Price,
+    ) -> Self {
+        Self {
+            event_type: MatcherEventType::Reject,
+            size,
+            price,
+            matched_order_id,
+            matched_order_uid,
+            matched_client_order_id: 0,
+            bidder_hold_price,
+            remaining_size: 0,
+            maker_action: Some(maker_action),
+            maker_completed: true,
+            taker_action: None,
+            taker_order_id: 0,
+            taker_uid: 0,
+            taker_client_order_id: 0,
+            sequence: 0,
+            timestamp: 0,
+            trade_id: 0,
         }
     }
 }
@@ -1,7 +1,11 @@
 use crate::api::*;
+use serde::{Deserialize, Serialize};
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 
 /// L2 市场深度数据
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
 pub struct L2MarketData {
     pub ask_prices: Vec<Price>,
     pub ask_volumes: Vec<Size>,
@@ -19,3 +23,254 @@ impl L2MarketData {
         }
     }
 }
+
+/// 按数量询价结果：假设以市价单吃掉对手盘 size 数量的预估成交
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SizeQuote {
+    pub filled: Size,               // 可成交数量（对手盘深度不足时小于请求数量）
+    pub notional: i64,              // 成交额（Σ 价格 × 数量）
+    pub average_price: Price,       // 成交均价（买入向上取整、卖出向下取整），无成交时为 0
+    pub worst_price: Option<Price>, // 最差成交价，无成交时为 None
+}
+
+impl SizeQuote {
+    /// 按价格优先顺序遍历对手盘档位 (价格, 数量) 计算询价结果
+    pub fn from_levels(action: OrderAction, size: Size, levels: impl Iterator<Item = (Price, Size)>) -> Self {
+        let mut quote = Self::default();
+        for (price, volume) in levels {
+            if quote.filled >= size {
+                break;
+            }
+            let take = volume.min(size - quote.filled);
+            quote.filled += take;
+            quote.notional += price * take;
+            quote.worst_price = Some(price);
+        }
+        if quote.filled > 0 {
+            quote.average_price = match action {
+                OrderAction::Bid => (quote.notional + quote.filled - 1) / quote.filled,
+                OrderAction::Ask => quote.notional / quote.filled,
+            };
+        }
+        quote
+    }
+}
+
+/// 最近一笔成交
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastTrade {
+    pub price: Price,
+    pub size: Size,
+    pub timestamp: i64, // 成交所在命令的时间
+}
+
+impl LastTrade {
+    /// 以 events 中最后一笔成交更新 last，无成交时保持不变
+    pub fn update(last: &mut Option<Self>, events: &[MatcherTradeEvent], timestamp: i64) {
+        if let Some(e) = events.iter().rev().find(|e| e.event_type == MatcherEventType::Trade) {
+            *last = Some(Self { price: e.price, size: e.size, timestamp });
+        }
+    }
+}
+
+/// 订单池占用（仅预分配订单池的订单簿）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolUtilization {
+    pub used: usize,
+    pub capacity: usize,
+}
+
+impl PoolUtilization {
+    pub fn ratio(&self) -> f64 {
+        if self.capacity == 0 { 0.0 } else { self.used as f64 / self.capacity as f64 }
+    }
+}
+
+/// 订单簿统计（运维看板用，无需反复拉取 L2 深度）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OrderBookStats {
+    pub symbol: SymbolId,
+    pub order_count: usize,                 // 挂单数（不含未触发的止损单）
+    pub stop_order_count: usize,            // 未触发的止损单数
+    pub ask_levels: usize,
+    pub bid_levels: usize,
+    pub total_ask_volume: Size,             // 含冰山单隐藏数量
+    pub total_bid_volume: Size,
+    pub visible_ask_volume: Size,           // 显示量
+    pub visible_bid_volume: Size,
+    pub ask_price_range: Option<(Price, Price)>, // 卖盘 (最低价, 最高价)
+    pub bid_price_range: Option<(Price, Price)>, // 买盘 (最低价, 最高价)
+    pub pool: Option<PoolUtilization>,
+    pub last_trade: Option<LastTrade>,
+}
+
+/// 订单簿只读视图：撮合线程处理命令后发布，查询线程无锁读取（见 core::book_view）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookView {
+    pub symbol: SymbolId,
+    pub sequence: u64,            // 发布时撮合分片最后分配的序号
+    pub timestamp: i64,           // 发布时的引擎时间
+    pub asks: Vec<(Price, Size)>, // 卖盘档位（价格升序，最多发布深度档，冰山单为显示量）
+    pub bids: Vec<(Price, Size)>, // 买盘档位（价格降序）
+    pub stats: OrderBookStats,
+}
+
+impl BookView {
+    pub fn best_ask(&self) -> Option<(Price, Size)> {
+        self.asks.first().copied()
+    }
+
+    pub fn best_bid(&self) -> Option<(Price, Size)> {
+        self.bids.first().copied()
+    }
+
+    /// 按 L2 格式输出前 depth 档（不超过发布深度）
+    pub fn l2(&self, depth: usize) -> L2MarketData {
+        let mut l2 = L2MarketData::new(depth.min(self.asks.len().max(self.bids.len())));
+        for &(price, volume) in self.asks.iter().take(depth) {
+            l2.ask_prices.push(price);
+            l2.ask_volumes.push(volume);
+        }
+        for &(price, volume) in self.bids.iter().take(depth) {
+            l2.bid_prices.push(price);
+            l2.bid_volumes.push(volume);
+        }
+        l2
+    }
+}
+
+/// L3 逐笔挂单
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L3Order {
+    pub order_id: OrderId,
+    pub uid: Option<UserId>, // 脱敏时为 None
+    pub price: Price,
+    pub remaining: Size,     // 剩余（冰山单为显示）数量
+    pub timestamp: i64,
+}
+
+impl L3Order {
+    pub fn new(order_id: OrderId, uid: UserId, price: Price, remaining: Size, timestamp: i64, mask_uid: bool) -> Self {
+        Self {
+            order_id,
+            uid: if mask_uid { None } else { Some(uid) },
+            price,
+            remaining,
+            timestamp,
+        }
+    }
+}
+
+/// L3 价格档位（订单按时间优先顺序排列）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L3Level {
+    pub price: Price,
+    pub volume: Size,
+    pub order_count: usize, // 档位总订单数（不受单档数量限制）
+    pub orders: Vec<L3Order>,
+}
+
+/// L3 市场深度数据（逐笔）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L3MarketData {
+    pub asks: Vec<L3Level>,
+    pub bids: Vec<L3Level>,
+}
+
+impl L3MarketData {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            asks: Vec::with_capacity(depth),
+            bids: Vec::with_capacity(depth),
+        }
+    }
+}
+
+/// L2 增量类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L2DeltaKind {
+    Add,    // 新增价格档位
+    Update, // 档位数量变化
+    Remove, // 档位移除
+}
+
+/// L2 增量（单个价格档位的变化）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2Delta {
+    pub seq: u64,           // 交易对内递增序号，用于检测丢包
+    pub symbol: SymbolId,
+    pub action: OrderAction, // 档位方向
+    pub kind: L2DeltaKind,
+    pub price: Price,
+    pub volume: Size,        // 变化后的档位数量（Remove 时为 0）
+}
+
+/// 订单簿价格档位的显示数量变化，由订单簿在档位合计变化时产生（档位移除时 volume 为 0）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelUpdate {
+    pub action: OrderAction, // 档位方向
+    pub price: Price,
+    pub volume: Size,        // 变化后的显示数量
+}
+
+/// 逐笔成交
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeTick {
+    pub symbol: SymbolId,
+    pub price: Price,
+    pub size: Size,
+    pub taker_action: OrderAction,
+    pub taker_order_id: OrderId,
+    pub taker_uid: UserId,
+    pub maker_order_id: OrderId,
+    pub maker_uid: UserId,
+    pub taker_client_order_id: u64,
+    pub maker_client_order_id: u64,
+    pub trade_id: u64, // 成交编号（交易对内唯一）
+    pub sequence: u64, // 成交事件的引擎序号，可用于排序与去重
+    pub timestamp: i64, // 撮合引擎时间
+}
+
+/// K 线（OHLCV）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candle {
+    pub symbol: SymbolId,
+    pub interval: i64,  // 周期（与 timestamp 同单位）
+    pub open_time: i64, // 周期起始时间（interval 的整数倍）
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Size,
+    pub trade_count: u64,
+}
+
+/// 最优买卖价（BBO）变化：买一/卖一价格或数量变化时发布
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BboUpdate {
+    pub symbol: SymbolId,
+    pub bid: Option<Price>, // 买一价（无买盘时为 None）
+    pub bid_size: Size,     // 买一（显示）数量，无买盘时为 0
+    pub ask: Option<Price>,
+    pub ask_size: Size,
+    pub seq: u64,           // 交易对内递增序号，用于检测丢包
+}
+
+/// 行情事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketDataEvent {
+    Trade(TradeTick),
+    Bbo(BboUpdate),
+    L2(L2Delta),
+    Implied(BboUpdate), // 价差组推出的隐含最优价（只用于展示，seq 独立于 BBO 递增）
+}
+
+impl MarketDataEvent {
+    pub fn symbol(&self) -> SymbolId {
+        match self {
+            MarketDataEvent::Trade(tick) => tick.symbol,
+            MarketDataEvent::Bbo(bbo) | MarketDataEvent::Implied(bbo) => bbo.symbol,
+            MarketDataEvent::L2(delta) => delta.symbol,
+        }
+    }
+}
@@ -2,8 +2,14 @@ pub mod commands;
 pub mod types;
 pub mod events;
 pub mod market_data;
+pub mod reports;
+pub mod validation;
+pub mod scaling;
 
 pub use commands::*;
 pub use types::*;
 pub use events::*;
 pub use market_data::*;
+pub use reports::*;
+pub use validation::*;
+pub use scaling::*;
@@ -0,0 +1,117 @@
+use crate::api::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// BinaryDataQuery 的查询类型（service_flags）：手续费报表
+pub const BINARY_QUERY_FEE_REPORT: i32 = 1;
+
+/// BinaryDataQuery 的查询类型（service_flags）：已注册的交易对，按 symbol_id 排序
+pub const BINARY_QUERY_SYMBOLS: i32 = 2;
+
+/// BinaryDataQuery 的查询类型（service_flags）：cmd.uid 的余额调整流水，按入账顺序排列
+pub const BINARY_QUERY_BALANCE_LEDGER: i32 = 3;
+
+/// BinaryDataQuery 的查询类型（service_flags）：各交易对的标记价与指数价，按 symbol_id 排序
+pub const BINARY_QUERY_REFERENCE_PRICES: i32 = 4;
+
+/// BinaryDataQuery 的查询类型（service_flags）：cmd.uid 的保证金持仓及按标记价计算的未实现盈亏，按 symbol_id 排序
+pub const BINARY_QUERY_POSITIONS: i32 = 5;
+
+/// BinaryDataQuery 的查询类型（service_flags）：cmd.uid 在当前与上一统计周期内各交易对的 maker 成交量
+pub const BINARY_QUERY_MAKER_VOLUME: i32 = 6;
+
+/// 交易对的外部参考价格（ReferencePriceUpdate 推送，SetMarkPrice 同样更新标记价）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferencePrices {
+    pub symbol: SymbolId,
+    pub mark_price: Option<Price>,
+    pub index_price: Option<Price>,
+}
+
+/// 用户单个交易对的持仓
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionReport {
+    pub symbol: SymbolId,
+    pub currency: Currency,           // 结算币种（quote）
+    pub net_position: i64,            // 带符号净持仓（多头为正）
+    pub open_cost: i64,               // 净开仓成本（quote 币，空头为负）
+    pub mark_price: Option<Price>,
+    pub unrealized_pnl: Option<i64>,  // 按标记价计算（未推送标记价时为 None）
+}
+
+/// 单个交易对的 maker 成交量（做市返佣统计）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakerVolume {
+    pub volume: Size,  // 成交手数
+    pub notional: i64, // 成交额（quote 币）
+    pub trades: u64,   // 成交笔数
+}
+
+impl MakerVolume {
+    pub fn record(&mut self, size: Size, notional: i64) {
+        self.volume += size;
+        self.notional = self.notional.saturating_add(notional);
+        self.trades += 1;
+    }
+}
+
+/// 用户的 maker 成交量：epoch 为当前统计周期（重置次数），重置时当前周期结转为 previous
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakerVolumeReport {
+    pub epoch: u64,
+    pub current: BTreeMap<SymbolId, MakerVolume>,
+    pub previous: BTreeMap<SymbolId, MakerVolume>,
+}
+
+/// 余额调整流水（只追加）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub transaction_id: OrderId,
+    pub currency: Currency,
+    pub amount: i64,
+    pub balance: i64, // 调整后余额
+    pub timestamp: i64,
+}
+
+/// 单个交易对的手续费收入
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolFees {
+    pub currency: Currency, // 计费币种（quote）
+    pub maker_fees: i64,
+    pub taker_fees: i64,
+}
+
+/// 手续费报表（按币种、按交易对汇总）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeReport {
+    pub by_currency: BTreeMap<Currency, i64>,
+    pub by_symbol: BTreeMap<SymbolId, SymbolFees>,
+}
+
+impl FeeReport {
+    /// 记录一笔手续费
+    pub fn record(&mut self, symbol: SymbolId, currency: Currency, maker_fee: i64, taker_fee: i64) {
+        *self.by_currency.entry(currency).or_insert(0) += maker_fee + taker_fee;
+        let fees = self.by_symbol.entry(symbol).or_insert_with(|| SymbolFees {
+            currency,
+            ..Default::default()
+        });
+        fees.maker_fees += maker_fee;
+        fees.taker_fees += taker_fee;
+    }
+
+    /// 合并其他分片的报表
+    pub fn merge(&mut self, other: &FeeReport) {
+        for (&symbol, fees) in &other.by_symbol {
+            self.record(symbol, fees.currency, fees.maker_fees, fees.taker_fees);
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("手续费报表序列化失败")
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
@@ -0,0 +1,108 @@
+use super::*;
+
+/// 交易对的数量/金额换算
+///
+/// 乘积先在 i128 中计算，再按用途收窄为 i64：下单、入金等可拒绝的路径用 checked 返回溢出结果码，
+/// 撮合后的结算（金额不超过下单时已校验的冻结）用 saturating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolScale {
+    pub base_scale_k: i64,
+    pub quote_scale_k: i64,
+}
+
+impl SymbolScale {
+    pub fn new(spec: &CoreSymbolSpecification) -> Self {
+        Self {
+            base_scale_k: spec.base_scale_k,
+            quote_scale_k: spec.quote_scale_k,
+        }
+    }
+
+    /// size 手对应的 base 币数量
+    pub fn base_value(&self, size: Size) -> i128 {
+        size as i128 * self.base_scale_k as i128
+    }
+
+    /// size 手按 price 成交的 quote 币金额（超出 i128 时取边界值）
+    pub fn quote_value(&self, size: Size, price: Price) -> i128 {
+        (size as i128 * price as i128).saturating_mul(self.quote_scale_k as i128)
+    }
+
+    /// size 手按每手费用 fee 计的手续费
+    pub fn fee_value(size: Size, fee: i64) -> i128 {
+        size as i128 * fee as i128
+    }
+
+    pub fn base_amount(&self, size: Size) -> Result<i64, CommandResultCode> {
+        checked_amount(self.base_value(size))
+    }
+
+    pub fn quote_amount(&self, size: Size, price: Price) -> Result<i64, CommandResultCode> {
+        checked_amount(self.quote_value(size, price))
+    }
+}
+
+/// 收窄为 i64，超出范围返回 RiskAmountOverflow
+pub fn checked_amount(value: i128) -> Result<i64, CommandResultCode> {
+    i64::try_from(value).map_err(|_| CommandResultCode::RiskAmountOverflow)
+}
+
+/// 收窄为 i64，超出范围时取边界值
+pub fn saturating_amount(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// 余额入账，结果超出 i64 时返回 RiskAmountOverflow 且余额不变
+pub fn checked_credit(balance: &mut i64, amount: i64) -> Result<(), CommandResultCode> {
+    *balance = balance.checked_add(amount).ok_or(CommandResultCode::RiskAmountOverflow)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF_MAX: i64 = i64::MAX / 2;
+
+    fn scale(base_scale_k: i64, quote_scale_k: i64) -> SymbolScale {
+        SymbolScale { base_scale_k, quote_scale_k }
+    }
+
+    #[test]
+    fn test_large_price_within_range() {
+        let s = scale(1, 1);
+        assert_eq!(s.quote_amount(1, HALF_MAX), Ok(HALF_MAX));
+        assert_eq!(s.quote_amount(2, HALF_MAX), Ok(HALF_MAX * 2));
+        assert_eq!(s.base_amount(HALF_MAX), Ok(HALF_MAX));
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let s = scale(100, 10);
+        assert_eq!(s.quote_amount(3, HALF_MAX), Err(CommandResultCode::RiskAmountOverflow));
+        assert_eq!(s.quote_amount(1, HALF_MAX), Err(CommandResultCode::RiskAmountOverflow));
+        assert_eq!(s.base_amount(HALF_MAX), Err(CommandResultCode::RiskAmountOverflow));
+        assert_eq!(s.quote_amount(-3, HALF_MAX), Err(CommandResultCode::RiskAmountOverflow));
+        // 中间结果超出 i64 但最终值在范围内时不误报
+        let unit = scale(1, 1);
+        assert_eq!(checked_amount(unit.quote_value(HALF_MAX, 4) - unit.quote_value(HALF_MAX, 3)), Ok(HALF_MAX));
+    }
+
+    #[test]
+    fn test_saturating_amount() {
+        let s = scale(1, 1_000);
+        assert_eq!(saturating_amount(s.quote_value(HALF_MAX, HALF_MAX)), i64::MAX);
+        assert_eq!(saturating_amount(s.quote_value(-HALF_MAX, HALF_MAX)), i64::MIN);
+        assert_eq!(saturating_amount(s.quote_value(7, 3) + SymbolScale::fee_value(7, 2)), 21_014);
+    }
+
+    #[test]
+    fn test_checked_credit() {
+        let mut balance = HALF_MAX;
+        assert_eq!(checked_credit(&mut balance, HALF_MAX), Ok(()));
+        assert_eq!(checked_credit(&mut balance, 2), Err(CommandResultCode::RiskAmountOverflow));
+        assert_eq!(balance, HALF_MAX * 2);
+        assert_eq!(checked_credit(&mut balance, -HALF_MAX), Ok(()));
+        assert_eq!(balance, HALF_MAX);
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use super::scaling::{checked_amount, saturating_amount, SymbolScale};
 
 pub type UserId = u64;
 pub type OrderId = u64;
@@ -40,6 +41,143 @@ pub enum OrderType {
     Iceberg,          // 冰山单
     Day,              // 当日有效
     Gtd(i64),         // Good-Till-Date (时间戳)
+    Market,           // 市价单（无限价，受价格保护带约束）
+    Gtx,              // Good-Till-Crossing：只挂单，下单或改价会与对手盘成交时撤销剩余部分
+    MarketToLimit,    // 按对手方最优价成交，剩余部分以该价格挂单
+}
+
+/// 自成交预防（STP）策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum StpMode {
+    None,               // 不启用
+    CancelTaker,        // 撤销 taker 剩余部分
+    CancelMaker,        // 撤销 maker 挂单
+    CancelBoth,         // 同时撤销双方
+    DecrementAndCancel, // 双方扣减较小数量，较小一方被撤销
+}
+
+impl StpMode {
+    /// 命令未指定时回退到交易对默认策略
+    pub fn or(self, fallback: StpMode) -> StpMode {
+        if self == StpMode::None { fallback } else { self }
+    }
+
+    /// 计算 maker / taker 各自需要撤销的数量
+    pub fn cancel_sizes(self, maker_remaining: Size, taker_remaining: Size) -> (Size, Size) {
+        match self {
+            StpMode::None => (0, 0),
+            StpMode::CancelTaker => (0, taker_remaining),
+            StpMode::CancelMaker => (maker_remaining, 0),
+            StpMode::CancelBoth => (maker_remaining, taker_remaining),
+            StpMode::DecrementAndCancel => {
+                let qty = maker_remaining.min(taker_remaining);
+                (qty, qty)
+            }
+        }
+    }
+}
+
+/// 订单簿实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum OrderBookKind {
+    Naive,                 // BTreeMap 实现，逻辑最简单
+    #[default]
+    Direct,                // 价格档位数组 + 订单池
+    DirectOptimized,       // Direct 的 SIMD/缓存优化版本
+    Advanced,              // 支持止损、冰山、GTD 等高级订单类型
+    DirectOptimizedLadder, // DirectOptimized + 连续价格阶梯索引（适合价格区间有限的密集盘口）
+}
+
+/// 同价位订单的成交分配算法（非 FIFO 只有 Advanced 订单簿支持）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum MatchingAlgorithm {
+    #[default]
+    Fifo,        // 时间优先
+    ProRata,     // 按各订单可成交数量的比例分配
+    FifoProRata, // 队首订单优先成交，剩余数量按比例分配
+}
+
+/// 按比例分配的取整规则：各订单先按比例向下取整，取整剩余的数量按规则分配（结果只取决于挂单队列，重放一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum ProRataRounding {
+    #[default]
+    TimePriority,     // 剩余数量按队列顺序分配，每笔补足到可成交数量
+    LargestRemainder, // 剩余数量按取整余数从大到小每笔分配 1，余数相同时按队列顺序
+}
+
+/// 交易对交易时段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum TradingSessionState {
+    PreOpen,           // 盘前：只接受不会成交的限价挂单
+    ContinuousTrading, // 连续交易
+    Halted,            // 临时停牌：只允许撤单/减量
+    CloseOnly,         // 只允许撤单/减量及不挂单的即时成交订单
+    Closed,            // 收盘：只允许撤单/减量
+}
+
+impl TradingSessionState {
+    /// SetSessionState 命令的 service_flags 编码
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(TradingSessionState::PreOpen),
+            1 => Some(TradingSessionState::ContinuousTrading),
+            2 => Some(TradingSessionState::Halted),
+            3 => Some(TradingSessionState::CloseOnly),
+            4 => Some(TradingSessionState::Closed),
+            _ => None,
+        }
+    }
+
+    /// 是否允许切换到目标时段（收盘后只能进入盘前或直接开盘）
+    pub fn can_transition_to(self, next: TradingSessionState) -> bool {
+        match self {
+            TradingSessionState::Closed => matches!(
+                next,
+                TradingSessionState::Closed | TradingSessionState::PreOpen | TradingSessionState::ContinuousTrading
+            ),
+            _ => true,
+        }
+    }
+}
+
+/// 止损单触发价格来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub enum StopTriggerSource {
+    LastPrice,  // 本订单簿最新成交价
+    MarkPrice,  // 标记价格（ReferencePriceUpdate 推送）
+    IndexPrice, // 指数价格（ReferencePriceUpdate 推送）
+}
+
+impl StopTriggerSource {
+    /// ReferencePriceUpdate 命令的 service_flags 编码
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(StopTriggerSource::LastPrice),
+            1 => Some(StopTriggerSource::MarkPrice),
+            2 => Some(StopTriggerSource::IndexPrice),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
@@ -64,12 +202,41 @@ pub enum CommandResultCode {
     
     // Auth
     AuthInvalidUser,
+    AuthUserSuspended,
+
+    // Validation（命令字段校验，风控之前拒绝）
+    ValidationInvalidPrice,        // 价格为负，限价/预算订单价格为 0，或行权自带了结算价
+    ValidationInvalidReservePrice, // 预留价格为负
+    ValidationInvalidSize,         // 数量不为正
+    ValidationMissingStopPrice,    // 止损单缺少触发价
+    ValidationInvalidStopPrice,    // 触发价不为正
+    ValidationMissingVisibleSize,  // 冰山单缺少显示数量
+    ValidationInvalidVisibleSize,  // 显示/刷新数量不在 (0, size] 内，或隐藏单指定了显示数量
+    ValidationOrderExpired,        // 过期时间早于命令时间
+    ValidationInvalidTriggerSource, // 参考价格来源无效（只能推送标记价或指数价）
+    ValidationInvalidBasket,        // 组合订单无法解码或没有腿
+    ValidationInvalidMinFillSize,   // 最小成交数量不在 (0, size] 内，或用于不立即撮合的订单类型
+    ValidationInvalidAction,        // 订单类型不支持该买卖方向（IOC_BUDGET 只能为买单）
     
     // Risk
     RiskNsf,
     RiskInvalidReserveBidPrice,
     RiskAskPriceLowerThanFee,
     RiskMarginTradingDisabled,
+    RiskMarkPriceNotSet,
+    RiskFundingNotDue,
+    RiskInvalidPriceStep,   // 价格不是 tick_size 的整数倍
+    RiskInvalidLotSize,     // 数量不是 lot_size 的整数倍
+    RiskOrderSizeTooSmall,
+    RiskOrderSizeTooLarge,
+    RiskUserOrderLimitExceeded,   // 用户挂单数超过上限
+    RiskSymbolOrderLimitExceeded, // 用户在该交易对的挂单数超过上限
+    RiskMaxOrderSizeExceeded,     // 超过风控单笔数量上限
+    RiskMaxNotionalExceeded,      // 超过风控单笔金额上限
+    RiskAmountOverflow,           // 数量、价格与 scale_k 换算后的金额超出 i64
+    RiskOptionOutOfTheMoney,      // 按结算价期权没有内在价值，不能行权
+    RiskIndexPriceNotSet,         // 期权未推送指数价（行权结算价），不能行权
+    RiskInsufficientPosition,     // 行权数量超过持有的期权多头
     
     // Matching
     MatchingInvalidOrderBookId,
@@ -78,6 +245,10 @@ pub enum CommandResultCode {
     MatchingMoveFailedPriceOverRiskLimit,
     MatchingReduceFailedWrongSize,
     MatchingInvalidOrderSize,
+    MatchingSessionRejected,          // 当前交易时段不允许该操作
+    MatchingInvalidSessionTransition,
+    MatchingDuplicateOrderId,         // 订单号与未完成订单重复，订单簿不变
+    MatchingUnauthorizedAction,       // 订单不属于该用户，拒绝撤单/改价/减量/改单
     
     // State
     StatePersistRiskEngineFailed,
@@ -85,11 +256,27 @@ pub enum CommandResultCode {
     
     // User
     UserMgmtUserAlreadyExists,
+    UserMgmtUserAlreadySuspended,
+    UserMgmtUserNotSuspended,
+    UserMgmtAdjustmentAlreadyApplied, // 交易号已入账（相同币种与金额），余额不变
+    UserMgmtAdjustmentConflict,       // 交易号已用于不同币种或金额的调整
+
+    // Withdrawal
+    WithdrawalUnknownTransaction,  // 交易号没有对应的提现冻结
+    WithdrawalTransactionConflict, // 交易号已用于不同币种/金额的冻结，或冻结已进入另一终态
+    
+    // Symbol
+    SymbolMgmtSymbolAlreadyExists,
+    SymbolMgmtInvalidSpecification,
+    SymbolMgmtOpenPositions, // 交易对仍有未平仓持仓，不能下架
     
     // Other
     InvalidSymbol,
     UnsupportedSymbolType,
     BinaryCommandFailed,
+    RateLimitExceeded, // 超过命令频率限制
+    DuplicateCommand,  // 重复提交的下单（幂等键已受理，原命令的结果已不在缓存中）
+    JournalWriteFailed, // 命令日志写入失败，命令未处理
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
@@ -106,6 +293,22 @@ pub struct CoreSymbolSpecification {
     pub maker_fee: i64,
     pub margin_buy: i64,
     pub margin_sell: i64,
+    pub market_max_slippage_bps: i64, // 市价单最大滑点（基点，0 表示不限制）
+    pub stp_mode: StpMode,            // 默认自成交预防策略
+    pub funding_interval: i64,        // 永续合约资金费结算间隔（与 timestamp 同单位，0 表示不限制）
+    pub tick_size: i64,               // 价格最小变动单位
+    pub lot_size: i64,                // 数量最小变动单位
+    pub min_size: i64,                // 单笔最小数量（0 表示不限制）
+    pub max_size: i64,                // 单笔最大数量（0 表示不限制）
+    pub order_book: Option<OrderBookKind>, // 订单簿实现（None 时使用 ExchangeConfig 的默认值，非 FIFO 分配时为 Advanced）
+    #[serde(default)]
+    pub strike_price: Price,               // 期权行权价（只用于 CallOption/PutOption）
+    #[serde(default)]
+    pub hidden_yield: bool,                // 同价位隐藏单让位于显示订单（false 时与显示订单按时间优先）
+    #[serde(default)]
+    pub matching_algorithm: MatchingAlgorithm, // 同价位成交分配算法
+    #[serde(default)]
+    pub pro_rata_rounding: ProRataRounding,    // 按比例分配的取整规则
 }
 
 impl Default for CoreSymbolSpecification {
@@ -121,6 +324,181 @@ impl Default for CoreSymbolSpecification {
             maker_fee: 0,
             margin_buy: 0,
             margin_sell: 0,
+            market_max_slippage_bps: 0,
+            stp_mode: StpMode::None,
+            funding_interval: 0,
+            tick_size: 1,
+            lot_size: 1,
+            min_size: 0,
+            max_size: 0,
+            order_book: None,
+            strike_price: 0,
+            hidden_yield: false,
+            matching_algorithm: MatchingAlgorithm::Fifo,
+            pro_rata_rounding: ProRataRounding::TimePriority,
+        }
+    }
+}
+
+impl CoreSymbolSpecification {
+    /// 校验交易对配置是否合法
+    ///
+    /// maker 费率可为负（返佣）：返佣不超过 taker 费率，保证每笔成交的手续费净额非负；
+    /// 收费不超过 taker 费率，买单按 taker 费率冻结的手续费足以覆盖 maker 成交的收费
+    pub fn is_valid(&self) -> bool {
+        self.base_scale_k > 0
+            && self.quote_scale_k > 0
+            && self.taker_fee >= 0
+            && (-self.taker_fee..=self.taker_fee).contains(&self.maker_fee)
+            && self.tick_size > 0
+            && self.lot_size > 0
+            && self.min_size >= 0
+            && self.max_size >= 0
+            && (self.max_size == 0 || self.max_size >= self.min_size)
+            && self.strike_price >= 0
+            && (self.matching_algorithm == MatchingAlgorithm::Fifo || self.order_book.is_none_or(|kind| kind == OrderBookKind::Advanced))
+    }
+
+    /// 订单簿实现：未指定时使用 default；非 FIFO 分配只有 Advanced 订单簿支持
+    pub fn order_book_kind(&self, default: OrderBookKind) -> OrderBookKind {
+        match self.order_book {
+            Some(kind) => kind,
+            None if self.matching_algorithm != MatchingAlgorithm::Fifo => OrderBookKind::Advanced,
+            None => default,
+        }
+    }
+
+    /// 是否按持仓保证金模式结算（期货、永续合约，以及按期货方式结算权利金的期权）
+    pub fn is_margin_trading(&self) -> bool {
+        matches!(
+            self.symbol_type,
+            SymbolType::FuturesContract | SymbolType::PerpetualSwap | SymbolType::CallOption | SymbolType::PutOption
+        )
+    }
+
+    pub fn is_option(&self) -> bool {
+        matches!(self.symbol_type, SymbolType::CallOption | SymbolType::PutOption)
+    }
+
+    /// 期权按结算价每手的内在价值（quote 价格单位，虚值为 0）；不是期权时返回 None
+    pub fn option_payoff(&self, settlement_price: Price) -> Option<Price> {
+        match self.symbol_type {
+            SymbolType::CallOption => Some((settlement_price - self.strike_price).max(0)),
+            SymbolType::PutOption => Some((self.strike_price - settlement_price).max(0)),
+            _ => None,
+        }
+    }
+
+    /// 每手初始保证金（quote 币）
+    pub fn initial_margin(&self, action: OrderAction) -> i64 {
+        match action {
+            OrderAction::Bid => self.margin_buy,
+            OrderAction::Ask => self.margin_sell,
+        }
+    }
+
+    pub fn scale(&self) -> SymbolScale {
+        SymbolScale::new(self)
+    }
+
+    /// 挂单冻结资金：返回 (币种, 数量)，金额超出 i64 时返回 RiskAmountOverflow
+    ///
+    /// 现货买单按 reserve_price 冻结 quote 并预留 taker 手续费，卖单冻结 base；
+    /// 期货/永续按每手初始保证金 + taker 手续费冻结 quote
+    pub fn order_hold(&self, action: OrderAction, size: Size, reserve_price: Price) -> Result<(Currency, i64), CommandResultCode> {
+        let scale = self.scale();
+        let (currency, hold) = if self.is_margin_trading() {
+            let per_lot = self.initial_margin(action) as i128 + self.taker_fee as i128;
+            (self.quote_currency, size as i128 * per_lot)
+        } else {
+            match action {
+                OrderAction::Bid => (
+                    self.quote_currency,
+                    scale.quote_value(size, reserve_price).saturating_add(SymbolScale::fee_value(size, self.taker_fee)),
+                ),
+                OrderAction::Ask => (self.base_currency, scale.base_value(size)),
+            }
+        };
+        Ok((currency, checked_amount(hold)?))
+    }
+
+    /// 预算单冻结资金：现货买单按总预算（budget 为 quote 金额）冻结并预留每手 taker 手续费，
+    /// 成交价不受 reserve_price 限制；卖单与期货/永续与 order_hold 相同
+    pub fn budget_order_hold(&self, action: OrderAction, size: Size, budget: Price) -> Result<(Currency, i64), CommandResultCode> {
+        if self.is_margin_trading() || action == OrderAction::Ask {
+            return self.order_hold(action, size, budget);
+        }
+        let hold = self.scale().quote_value(1, budget).saturating_add(SymbolScale::fee_value(size, self.taker_fee));
+        Ok((self.quote_currency, checked_amount(hold)?))
+    }
+
+    /// 计算市价单保护价（基于对手方最优价与最大滑点）
+    pub fn market_protection_price(&self, action: OrderAction, best_opposite: Price) -> Price {
+        if self.market_max_slippage_bps <= 0 {
+            return match action {
+                OrderAction::Bid => Price::MAX,
+                OrderAction::Ask => Price::MIN,
+            };
+        }
+
+        let band = (best_opposite as i128 * self.market_max_slippage_bps as i128 / 10_000) as Price;
+        match action {
+            OrderAction::Bid => best_opposite.saturating_add(band),
+            OrderAction::Ask => best_opposite.saturating_sub(band),
+        }
+    }
+}
+
+/// 手续费等级的费率单位：交易对费率的万分比
+pub const FEE_TIER_SCALE: i64 = 10_000;
+
+/// 用户手续费等级，按交易对费率的比例收取（默认 FEE_TIER_SCALE 即原费率）
+///
+/// 比例不超过 FEE_TIER_SCALE，实际收费不会超过下单时按交易对 taker 费率冻结的手续费
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub taker_ratio: i64,
+    pub maker_ratio: i64,
+}
+
+impl Default for FeeTier {
+    fn default() -> Self {
+        Self {
+            taker_ratio: FEE_TIER_SCALE,
+            maker_ratio: FEE_TIER_SCALE,
         }
     }
 }
+
+impl FeeTier {
+    /// size 手成交的手续费（负数为返佣）
+    ///
+    /// 向上取整：收费不少收，返佣不多付
+    pub fn fee(&self, spec: &CoreSymbolSpecification, size: Size, is_taker: bool) -> i64 {
+        let (fee_per_lot, ratio) = if is_taker {
+            (spec.taker_fee, self.taker_ratio)
+        } else {
+            (spec.maker_fee, self.maker_ratio)
+        };
+        let raw = SymbolScale::fee_value(size, fee_per_lot) * ratio as i128;
+        saturating_amount(-((-raw).div_euclid(FEE_TIER_SCALE as i128)))
+    }
+}
+
+/// 用户当前挂单（含未触发的止损单）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct OpenOrder {
+    pub order_id: OrderId,
+    pub uid: UserId,
+    pub symbol: SymbolId,
+    pub action: OrderAction,
+    pub order_type: OrderType,
+    pub price: Price,
+    pub reserve_price: Price, // 冻结价（买单按此价冻结资金）
+    pub size: Size,      // 原始数量
+    pub remaining: Size, // 剩余未成交数量
+    pub timestamp: i64,
+    pub client_order_id: u64, // 客户端订单号
+}
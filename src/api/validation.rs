@@ -0,0 +1,122 @@
+use super::*;
+
+/// 命令字段校验：在限流之后、风控之前拒绝字段不合法的命令
+///
+/// 只检查命令自身字段的取值与组合，不依赖交易对、用户等状态；任意字段取值都不会 panic
+pub fn validate_command(cmd: &OrderCommand) -> CommandResultCode {
+    match cmd.command {
+        OrderCommandType::PlaceOrder => validate_place_order(cmd),
+        OrderCommandType::PlaceBasket => validate_basket(cmd),
+        OrderCommandType::MoveOrder if cmd.price <= 0 => CommandResultCode::ValidationInvalidPrice,
+        OrderCommandType::ReduceOrder if cmd.size <= 0 => CommandResultCode::ValidationInvalidSize,
+        OrderCommandType::AmendOrder if cmd.price < 0 => CommandResultCode::ValidationInvalidPrice,
+        OrderCommandType::AmendOrder if cmd.size <= 0 => CommandResultCode::ValidationInvalidSize,
+        OrderCommandType::ReferencePriceUpdate => validate_reference_price(cmd),
+        // 行权按管理端推送的指数价结算，不接受用户指定的结算价
+        OrderCommandType::ExerciseOption if cmd.price != 0 => CommandResultCode::ValidationInvalidPrice,
+        OrderCommandType::ExpireSeries if cmd.price <= 0 => CommandResultCode::ValidationInvalidPrice,
+        OrderCommandType::ExerciseOption if cmd.size <= 0 => CommandResultCode::ValidationInvalidSize,
+        OrderCommandType::HoldFunds if cmd.price <= 0 => CommandResultCode::ValidationInvalidPrice,
+        OrderCommandType::SetFeeTier if !(0..=FEE_TIER_SCALE).contains(&cmd.price) => CommandResultCode::ValidationInvalidPrice,
+        OrderCommandType::SetFeeTier if !(0..=FEE_TIER_SCALE).contains(&cmd.size) => CommandResultCode::ValidationInvalidSize,
+        _ => CommandResultCode::Success,
+    }
+}
+
+/// 结果码是否为字段校验失败（后续阶段跳过该命令）
+pub fn is_validation_error(code: CommandResultCode) -> bool {
+    matches!(
+        code,
+        CommandResultCode::ValidationInvalidPrice
+            | CommandResultCode::ValidationInvalidReservePrice
+            | CommandResultCode::ValidationInvalidSize
+            | CommandResultCode::ValidationMissingStopPrice
+            | CommandResultCode::ValidationInvalidStopPrice
+            | CommandResultCode::ValidationMissingVisibleSize
+            | CommandResultCode::ValidationInvalidVisibleSize
+            | CommandResultCode::ValidationOrderExpired
+            | CommandResultCode::ValidationInvalidTriggerSource
+            | CommandResultCode::ValidationInvalidBasket
+            | CommandResultCode::ValidationInvalidMinFillSize
+            | CommandResultCode::ValidationInvalidAction
+    )
+}
+
+fn validate_reference_price(cmd: &OrderCommand) -> CommandResultCode {
+    match StopTriggerSource::from_code(cmd.service_flags) {
+        Some(StopTriggerSource::MarkPrice | StopTriggerSource::IndexPrice) if cmd.price > 0 => CommandResultCode::Success,
+        Some(StopTriggerSource::MarkPrice | StopTriggerSource::IndexPrice) => CommandResultCode::ValidationInvalidPrice,
+        _ => CommandResultCode::ValidationInvalidTriggerSource,
+    }
+}
+
+/// 组合订单：各腿按新单校验，返回首个不合法腿的原因
+fn validate_basket(cmd: &OrderCommand) -> CommandResultCode {
+    let legs = match cmd.decode_basket() {
+        Ok(legs) if !legs.is_empty() => legs,
+        _ => return CommandResultCode::ValidationInvalidBasket,
+    };
+    legs.iter()
+        .map(validate_place_order)
+        .find(|&code| code != CommandResultCode::Success)
+        .unwrap_or(CommandResultCode::Success)
+}
+
+fn validate_place_order(cmd: &OrderCommand) -> CommandResultCode {
+    if cmd.size <= 0 {
+        return CommandResultCode::ValidationInvalidSize;
+    }
+
+    // 市价单不需要限价（price 可为 0），其余订单的 price 为限价或总预算
+    let price_valid = match cmd.order_type {
+        OrderType::Market | OrderType::StopMarket | OrderType::MarketToLimit => cmd.price >= 0,
+        _ => cmd.price > 0,
+    };
+    if !price_valid {
+        return CommandResultCode::ValidationInvalidPrice;
+    }
+    if cmd.reserve_price < 0 {
+        return CommandResultCode::ValidationInvalidReservePrice;
+    }
+    // IOC_BUDGET 的 price 为买入可花费的总金额，卖单没有对应含义
+    if cmd.order_type == OrderType::IocBudget && cmd.action == OrderAction::Ask {
+        return CommandResultCode::ValidationInvalidAction;
+    }
+
+    match (cmd.order_type, cmd.stop_price) {
+        (OrderType::StopLimit | OrderType::StopMarket, None) => return CommandResultCode::ValidationMissingStopPrice,
+        (_, Some(stop_price)) if stop_price <= 0 => return CommandResultCode::ValidationInvalidStopPrice,
+        _ => {}
+    }
+
+    if cmd.order_type == OrderType::Iceberg && cmd.visible_size.is_none() {
+        return CommandResultCode::ValidationMissingVisibleSize;
+    }
+    let clip_valid = |size: Size| size > 0 && size <= cmd.size;
+    if cmd.visible_size.is_some_and(|size| !clip_valid(size)) || cmd.replenish_size.is_some_and(|size| !clip_valid(size)) {
+        return CommandResultCode::ValidationInvalidVisibleSize;
+    }
+    // 隐藏单不显示任何数量，不能同时为冰山单
+    if cmd.hidden && cmd.visible_size.is_some() {
+        return CommandResultCode::ValidationInvalidVisibleSize;
+    }
+
+    // 最小成交数量只作用于下单时的立即撮合：止损单、只挂单与预算单不适用
+    let min_fill_applies = !matches!(
+        cmd.order_type,
+        OrderType::StopLimit | OrderType::StopMarket | OrderType::PostOnly | OrderType::Gtx | OrderType::FokBudget | OrderType::IocBudget
+    );
+    if cmd.min_fill_size.is_some_and(|size| !min_fill_applies || !clip_valid(size)) {
+        return CommandResultCode::ValidationInvalidMinFillSize;
+    }
+
+    let expire_time = match cmd.order_type {
+        OrderType::Gtd(expire) => cmd.expire_time.or(Some(expire)),
+        _ => cmd.expire_time,
+    };
+    if expire_time.is_some_and(|expire| expire < cmd.timestamp) {
+        return CommandResultCode::ValidationOrderExpired;
+    }
+
+    CommandResultCode::Success
+}
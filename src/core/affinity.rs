@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// 单个线程的放置：绑定的 CPU 核、线程名与调度优先级
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadPlacement {
+    pub core: Option<usize>,  // 绑定的 CPU 核（None 时沿用 consumer_cores 的顺序分配）
+    pub name: Option<String>, // 线程名（None 时按角色与分片生成，如 matching-1）
+    pub nice: Option<i32>,    // nice 值，越小优先级越高（仅 Linux，调高优先级需要相应权限）
+}
+
+/// 流水线各线程的放置（ExchangeConfig.thread_affinity）
+///
+/// 分片列表按分片号对应，未列出的分片使用默认放置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadAffinityConfig {
+    pub grouping: ThreadPlacement,
+    pub risk_pre: Vec<ThreadPlacement>,
+    pub matching: Vec<ThreadPlacement>,
+    pub risk_post: Vec<ThreadPlacement>,
+    pub results: ThreadPlacement,
+    /// 日志在提交命令的线程上同步写入：startup 时应用于调用线程（不修改线程名）
+    pub journal: ThreadPlacement,
+}
+
+/// 线程角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ThreadRole {
+    Grouping,
+    RiskPre,
+    Matching,
+    RiskPost,
+    Results,
+    Journal,
+}
+
+impl ThreadRole {
+    fn label(self) -> &'static str {
+        match self {
+            ThreadRole::Grouping => "grouping",
+            ThreadRole::RiskPre => "risk-pre",
+            ThreadRole::Matching => "matching",
+            ThreadRole::RiskPost => "risk-post",
+            ThreadRole::Results => "results",
+            ThreadRole::Journal => "journal",
+        }
+    }
+}
+
+/// 线程实际放置情况，线程启动时登记
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadReport {
+    pub role: ThreadRole,
+    pub shard: usize,
+    pub name: Option<String>,
+    pub core: Option<usize>,        // 成功绑定的 CPU 核
+    pub current_cpu: Option<usize>, // 登记时实际运行的 CPU（仅 Linux）
+    pub nice: Option<i32>,          // 成功设置的 nice 值
+    pub errors: Vec<String>,        // 绑核或设置优先级失败的原因
+}
+
+/// 各线程登记的放置报告
+pub type PlacementReport = Arc<Mutex<Vec<ThreadReport>>>;
+
+/// 解析后的线程放置
+#[derive(Debug, Clone)]
+pub(crate) struct ResolvedPlacement {
+    pub role: ThreadRole,
+    pub shard: usize,
+    pub name: String,
+    pub core: Option<usize>,
+    pub nice: Option<i32>,
+}
+
+impl ResolvedPlacement {
+    /// 在当前线程上绑核、设置优先级，返回实际放置
+    pub fn apply(&self) -> ThreadReport {
+        let mut report = ThreadReport {
+            role: self.role,
+            shard: self.shard,
+            name: std::thread::current().name().map(str::to_string),
+            core: None,
+            current_cpu: None,
+            nice: None,
+            errors: Vec::new(),
+        };
+        if let Some(core) = self.core {
+            if core_affinity::set_for_current(core_affinity::CoreId { id: core }) {
+                report.core = Some(core);
+            } else {
+                report.errors.push(format!("无法绑定 CPU {}", core));
+            }
+        }
+        if let Some(nice) = self.nice {
+            match set_nice(nice) {
+                Ok(()) => report.nice = Some(nice),
+                Err(e) => report.errors.push(e),
+            }
+        }
+        report.current_cpu = current_cpu();
+        report
+    }
+}
+
+impl ThreadAffinityConfig {
+    /// 按角色与分片取放置；core 未指定时使用 fallback_core（consumer_cores 的顺序分配）
+    pub(crate) fn resolve(&self, role: ThreadRole, shard: usize, fallback_core: Option<usize>) -> ResolvedPlacement {
+        let placement = match role {
+            ThreadRole::Grouping => Some(&self.grouping),
+            ThreadRole::RiskPre => self.risk_pre.get(shard),
+            ThreadRole::Matching => self.matching.get(shard),
+            ThreadRole::RiskPost => self.risk_post.get(shard),
+            ThreadRole::Results => Some(&self.results),
+            ThreadRole::Journal => Some(&self.journal),
+        };
+        let placement = placement.cloned().unwrap_or_default();
+        ResolvedPlacement {
+            role,
+            shard,
+            name: placement.name.unwrap_or_else(|| format!("{}-{}", role.label(), shard)),
+            core: placement.core.or(fallback_core),
+            nice: placement.nice,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_nice(nice: i32) -> Result<(), String> {
+    // Linux 的 nice 值是线程属性，who = 0 只作用于调用线程
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(format!("无法设置 nice {}: {}", nice, std::io::Error::last_os_error()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_nice(nice: i32) -> Result<(), String> {
+    Err(format!("当前平台不支持设置 nice {}", nice))
+}
+
+#[cfg(target_os = "linux")]
+fn current_cpu() -> Option<usize> {
+    let cpu = unsafe { libc::sched_getcpu() };
+    (cpu >= 0).then_some(cpu as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_cpu() -> Option<usize> {
+    None
+}
@@ -0,0 +1,198 @@
+use crate::api::*;
+use crate::core::orderbook::OrderBook;
+use crate::core::processors::matching_engine::MatchingEngineRouter;
+use ahash::AHashMap;
+use smallvec::{smallvec, SmallVec};
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+type ViewMap = AHashMap<SymbolId, Arc<BookView>>;
+
+/// 左右双副本（left-right）
+///
+/// 读者只读取 active 副本；唯一的写者（撮合线程）修改另一份后备副本，等其上的读者全部离开后写入，
+/// 再切换 active。每次修改依次写入两份副本：本次写入后备副本，下次发布时补写到切换下来的副本。
+struct LeftRight {
+    copies: [UnsafeCell<ViewMap>; 2],
+    active: AtomicUsize,       // 读者可见的副本
+    readers: [AtomicUsize; 2], // 各副本上正在读取的读者数
+}
+
+// 后备副本只由写者在没有读者时修改，读者只访问登记时仍为 active 的副本
+unsafe impl Sync for LeftRight {}
+
+impl LeftRight {
+    fn new() -> Self {
+        Self {
+            copies: [UnsafeCell::new(ViewMap::new()), UnsafeCell::new(ViewMap::new())],
+            active: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+        }
+    }
+
+    fn read<R>(&self, f: impl FnOnce(&ViewMap) -> R) -> R {
+        let index = loop {
+            let index = self.active.load(Ordering::SeqCst);
+            self.readers[index].fetch_add(1, Ordering::SeqCst);
+            // 登记后 active 未变：写者切换后修改该副本前必然等待本读者离开
+            if self.active.load(Ordering::SeqCst) == index {
+                break index;
+            }
+            self.readers[index].fetch_sub(1, Ordering::SeqCst);
+        };
+        let _guard = ReaderGuard(&self.readers[index]);
+        f(unsafe { &*self.copies[index].get() })
+    }
+}
+
+/// 读取结束（含 panic）时注销读者
+struct ReaderGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ReaderGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 视图发布者，由撮合分片持有
+pub struct BookViewPublisher {
+    shared: Arc<LeftRight>,
+    depth: usize,
+    pending: Vec<(SymbolId, Option<Arc<BookView>>)>, // 已写入 active 副本、尚未补写到后备副本的修改
+}
+
+impl BookViewPublisher {
+    /// depth 为每侧发布的最大档位数
+    pub fn new(depth: usize) -> (Self, BookViews) {
+        let shared = Arc::new(LeftRight::new());
+        let views = BookViews { shards: vec![shared.clone()] };
+        (Self { shared, depth, pending: Vec::new() }, views)
+    }
+
+    /// 命令处理完成后发布其触及交易对的视图，只读查询不发布
+    pub fn on_command(&mut self, cmd: &OrderCommand, books: &AHashMap<SymbolId, Box<dyn OrderBook>>, sequence: u64, timestamp: i64) {
+        let symbols: SmallVec<[SymbolId; 4]> = match cmd.command {
+            OrderCommandType::OrderBookRequest | OrderCommandType::UserOrdersRequest => return,
+            // 暂停用户批量撤单可能触及多个交易对
+            OrderCommandType::SuspendUser => cmd.open_orders.iter().map(|order| order.symbol).collect(),
+            // 组合下单的腿可能不产生事件，按各腿的交易对发布
+            OrderCommandType::PlaceBasket => cmd.decode_basket().unwrap_or_default().iter().map(|leg| leg.symbol).collect(),
+            // 隐含成交同时触及价差组内的其他订单簿
+            OrderCommandType::PlaceOrder if !cmd.open_orders.is_empty() => {
+                let mut symbols: SmallVec<[SymbolId; 4]> = smallvec![cmd.symbol];
+                for order in &cmd.open_orders {
+                    if !symbols.contains(&order.symbol) {
+                        symbols.push(order.symbol);
+                    }
+                }
+                symbols
+            }
+            command if MatchingEngineRouter::is_symbol_command(command) => smallvec![cmd.symbol],
+            _ => return,
+        };
+        self.publish(books, symbols, sequence, timestamp);
+    }
+
+    /// 发布 symbols 的最新视图，订单簿已不存在（下架）的交易对移除视图
+    pub fn publish(
+        &mut self,
+        books: &AHashMap<SymbolId, Box<dyn OrderBook>>,
+        symbols: impl IntoIterator<Item = SymbolId>,
+        sequence: u64,
+        timestamp: i64,
+    ) {
+        let active = self.shared.active.load(Ordering::SeqCst);
+        // 只有写者修改副本，写者读取 active 副本无需登记
+        let current = unsafe { &*self.shared.copies[active].get() };
+        let mut changes = Vec::new();
+        for symbol in symbols {
+            match books.get(&symbol) {
+                Some(book) => changes.push((symbol, Some(Arc::new(capture(symbol, book.as_ref(), self.depth, sequence, timestamp))))),
+                None if current.contains_key(&symbol) => changes.push((symbol, None)),
+                None => {}
+            }
+        }
+        if changes.is_empty() {
+            return;
+        }
+
+        let standby = 1 - active;
+        while self.shared.readers[standby].load(Ordering::SeqCst) != 0 {
+            std::hint::spin_loop();
+        }
+        // standby 不是 active 且已没有读者，此后登记的读者会发现 active 不是 standby 而重试
+        let copy = unsafe { &mut *self.shared.copies[standby].get() };
+        for (symbol, view) in self.pending.drain(..).chain(changes.iter().cloned()) {
+            match view {
+                Some(view) => copy.insert(symbol, view),
+                None => copy.remove(&symbol),
+            };
+        }
+        self.shared.active.store(standby, Ordering::SeqCst);
+        self.pending = changes;
+    }
+}
+
+fn capture(symbol: SymbolId, book: &dyn OrderBook, depth: usize, sequence: u64, timestamp: i64) -> BookView {
+    let levels = |side| {
+        let mut out = Vec::new();
+        book.visit_levels(side, &mut |price, volume| {
+            if out.len() >= depth {
+                return false;
+            }
+            out.push((price, volume));
+            true
+        });
+        out
+    };
+    BookView {
+        symbol,
+        sequence,
+        timestamp,
+        asks: levels(OrderAction::Ask),
+        bids: levels(OrderAction::Bid),
+        stats: book.stats(),
+    }
+}
+
+/// 订单簿只读视图句柄，可克隆并在任意线程无锁查询；按撮合分片路由到对应分片发布的视图
+#[derive(Clone)]
+pub struct BookViews {
+    shards: Vec<Arc<LeftRight>>,
+}
+
+impl BookViews {
+    /// 按分片顺序合并各撮合分片的句柄
+    pub fn from_shards(shards: impl IntoIterator<Item = BookViews>) -> Self {
+        Self { shards: shards.into_iter().flat_map(|views| views.shards).collect() }
+    }
+
+    /// 交易对最新发布的视图
+    pub fn get(&self, symbol: SymbolId) -> Option<Arc<BookView>> {
+        if self.shards.is_empty() {
+            return None;
+        }
+        let shard = MatchingEngineRouter::shard_for_symbol(symbol, self.shards.len());
+        self.shards[shard].read(|views| views.get(&symbol).cloned())
+    }
+
+    pub fn l2(&self, symbol: SymbolId, depth: usize) -> Option<L2MarketData> {
+        self.get(symbol).map(|view| view.l2(depth))
+    }
+
+    pub fn stats(&self, symbol: SymbolId) -> Option<OrderBookStats> {
+        self.get(symbol).map(|view| view.stats)
+    }
+
+    /// 已发布视图的交易对（升序）
+    pub fn symbols(&self) -> Vec<SymbolId> {
+        let mut symbols: Vec<SymbolId> = self
+            .shards
+            .iter()
+            .flat_map(|shard| shard.read(|views| views.keys().copied().collect::<Vec<_>>()))
+            .collect();
+        symbols.sort_unstable();
+        symbols
+    }
+}
@@ -0,0 +1,93 @@
+use crate::api::*;
+use crate::core::market_data::MarketDataConsumer;
+use ahash::AHashMap;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// K 线聚合器
+///
+/// 消费逐笔成交，按配置的周期维护各交易对最近的 K 线。没有成交的周期不生成 K 线。
+pub struct CandleAggregator {
+    intervals: Vec<i64>, // 周期（与 timestamp 同单位）
+    history: usize,      // 每个交易对每个周期保留的 K 线数量
+    series: AHashMap<(SymbolId, i64), VecDeque<Candle>>,
+}
+
+/// 注册为行情消费者后由撮合线程更新、查询方读取的共享聚合器
+pub type SharedCandles = Arc<Mutex<CandleAggregator>>;
+
+impl CandleAggregator {
+    pub fn new(intervals: &[i64], history: usize) -> Self {
+        assert!(intervals.iter().all(|&interval| interval > 0), "K 线周期必须为正");
+        assert!(history > 0);
+        Self {
+            intervals: intervals.to_vec(),
+            history,
+            series: AHashMap::new(),
+        }
+    }
+
+    /// 包装为行情消费者，返回共享聚合器与对应回调
+    pub fn into_consumer(self) -> (SharedCandles, MarketDataConsumer) {
+        let candles = Arc::new(Mutex::new(self));
+        let sink = candles.clone();
+        let consumer: MarketDataConsumer = Arc::new(move |event| sink.lock().unwrap().on_event(event));
+        (candles, consumer)
+    }
+
+    pub fn on_event(&mut self, event: &MarketDataEvent) {
+        if let MarketDataEvent::Trade(tick) = event {
+            self.on_trade(tick);
+        }
+    }
+
+    /// 成交计入各周期的当前 K 线，跨周期时开新 K 线并淘汰超出保留数量的旧 K 线
+    pub fn on_trade(&mut self, tick: &TradeTick) {
+        for &interval in &self.intervals {
+            let open_time = tick.timestamp - tick.timestamp.rem_euclid(interval);
+            let series = self.series.entry((tick.symbol, interval)).or_default();
+            match series.back_mut() {
+                // 引擎时间单调不减，早于当前 K 线的成交同样计入当前 K 线
+                Some(candle) if candle.open_time >= open_time => {
+                    candle.high = candle.high.max(tick.price);
+                    candle.low = candle.low.min(tick.price);
+                    candle.close = tick.price;
+                    candle.volume += tick.size;
+                    candle.trade_count += 1;
+                }
+                _ => {
+                    if series.len() == self.history {
+                        series.pop_front();
+                    }
+                    series.push_back(Candle {
+                        symbol: tick.symbol,
+                        interval,
+                        open_time,
+                        open: tick.price,
+                        high: tick.price,
+                        low: tick.price,
+                        close: tick.price,
+                        volume: tick.size,
+                        trade_count: 1,
+                    });
+                }
+            }
+        }
+    }
+
+    /// 最近 limit 根 K 线（按时间升序，最后一根可能尚未收盘），未配置的周期返回空
+    pub fn recent(&self, symbol: SymbolId, interval: i64, limit: usize) -> Vec<Candle> {
+        self.series.get(&(symbol, interval)).map_or_else(Vec::new, |series| {
+            series.iter().skip(series.len().saturating_sub(limit)).copied().collect()
+        })
+    }
+
+    /// 当前（最新）K 线
+    pub fn latest(&self, symbol: SymbolId, interval: i64) -> Option<Candle> {
+        self.series.get(&(symbol, interval))?.back().copied()
+    }
+
+    pub fn intervals(&self) -> &[i64] {
+        &self.intervals
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 引擎时钟：为未指定 timestamp（为 0）的命令提供时间，并驱动 Day 订单到期、过期扫描与定时快照
+///
+/// 撮合结果只取决于命令中的 timestamp（写入日志后重放一致），时钟只在提交时使用
+pub trait Clock: Send + Sync {
+    /// 当前时间（与命令 timestamp 同单位）
+    fn now(&self) -> i64;
+
+    /// AdvanceTime 命令把时钟推进到 time；实时时钟忽略
+    fn advance_to(&self, _time: i64) {}
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 系统时间，单位毫秒
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealTimeClock;
+
+impl Clock for RealTimeClock {
+    fn now(&self) -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64)
+    }
+}
+
+/// 手动推进的模拟时钟（回测用），时间只增不减
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: AtomicI64,
+}
+
+impl ManualClock {
+    pub fn new(start: i64) -> Self {
+        Self { now: AtomicI64::new(start) }
+    }
+
+    /// 前进 delta
+    pub fn advance(&self, delta: i64) -> i64 {
+        self.now.fetch_add(delta.max(0), Ordering::AcqRel) + delta.max(0)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> i64 {
+        self.now.load(Ordering::Acquire)
+    }
+
+    fn advance_to(&self, time: i64) {
+        self.now.fetch_max(time, Ordering::AcqRel);
+    }
+}
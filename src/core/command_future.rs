@@ -0,0 +1,127 @@
+use crate::api::*;
+use crate::core::event_pool::EventBufferPool;
+use ahash::AHashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Default)]
+struct Slot {
+    result: Option<OrderCommand>,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Shared {
+    slot: Mutex<Slot>,
+    ready: Condvar,
+}
+
+impl Shared {
+    fn complete(&self, cmd: OrderCommand) {
+        let mut slot = self.slot.lock().unwrap();
+        slot.result = Some(cmd);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+        self.ready.notify_all();
+    }
+}
+
+/// 异步提交命令的处理结果，由结果输出阶段写入处理完成的命令
+///
+/// 可以 await，也可以在同步代码中调用 wait 阻塞等待
+pub struct CommandFuture {
+    shared: Arc<Shared>,
+}
+
+impl CommandFuture {
+    /// 已完成的结果（同步处理模式）
+    pub fn ready(cmd: OrderCommand) -> Self {
+        let shared = Arc::new(Shared::default());
+        shared.complete(cmd);
+        Self { shared }
+    }
+
+    /// 结果是否已就绪
+    pub fn is_ready(&self) -> bool {
+        self.shared.slot.lock().unwrap().result.is_some()
+    }
+
+    /// 阻塞等待处理结果
+    pub fn wait(self) -> OrderCommand {
+        let mut slot = self.shared.slot.lock().unwrap();
+        loop {
+            if let Some(cmd) = slot.result.take() {
+                return cmd;
+            }
+            slot = self.shared.ready.wait(slot).unwrap();
+        }
+    }
+}
+
+impl Future for CommandFuture {
+    type Output = OrderCommand;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<OrderCommand> {
+        let mut slot = self.shared.slot.lock().unwrap();
+        match slot.result.take() {
+            Some(cmd) => Poll::Ready(cmd),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// 等待结果的命令，按 Disruptor 序号登记
+pub struct PendingResults {
+    pending: Mutex<AHashMap<i64, Arc<Shared>>>,
+    pool: Arc<EventBufferPool>, // 结果复制使用的事件缓冲区
+    completed: Mutex<i64>, // 已输出结果的最后一个事件序号
+    advanced: Condvar, // 已完成序号推进时通知 wait_completed
+}
+
+impl PendingResults {
+    pub fn new(pool: Arc<EventBufferPool>) -> Self {
+        Self {
+            pending: Mutex::new(AHashMap::new()),
+            pool,
+            completed: Mutex::new(-1),
+            advanced: Condvar::new(),
+        }
+    }
+
+    /// 在发布命令之前登记
+    pub fn register(&self, sequence: i64) -> CommandFuture {
+        let shared = Arc::new(Shared::default());
+        self.pending.lock().unwrap().insert(sequence, shared.clone());
+        CommandFuture { shared }
+    }
+
+    /// 结果输出阶段调用：若该序号有等待者则写入结果，并推进已完成序号
+    pub fn complete(&self, sequence: i64, cmd: &OrderCommand) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(shared) = pending.remove(&sequence) {
+            drop(pending);
+            shared.complete(self.pool.clone_command(cmd));
+        }
+        *self.completed.lock().unwrap() = sequence;
+        self.advanced.notify_all();
+    }
+
+    /// 已输出结果的最后一个事件序号（尚无时为 -1）
+    pub fn completed(&self) -> i64 {
+        *self.completed.lock().unwrap()
+    }
+
+    /// 阻塞等待序号不超过 sequence 的命令全部输出结果
+    pub fn wait_completed(&self, sequence: i64) {
+        let mut completed = self.completed.lock().unwrap();
+        while *completed < sequence {
+            completed = self.advanced.wait(completed).unwrap();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::api::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// 事件缓冲区池统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventPoolStats {
+    pub acquired: u64,  // 取出的缓冲区总数
+    pub allocated: u64, // 池为空时新分配的缓冲区数
+    pub released: u64,  // 归还并放回池中的缓冲区数
+    pub discarded: u64, // 池已满或无容量而丢弃的归还缓冲区数
+    pub pooled: usize,  // 当前池中的缓冲区数
+}
+
+impl EventPoolStats {
+    /// 复用率（取出的缓冲区中来自池的比例）
+    pub fn hit_rate(&self) -> f64 {
+        if self.acquired == 0 {
+            return 0.0;
+        }
+        (self.acquired - self.allocated) as f64 / self.acquired as f64
+    }
+}
+
+/// MatcherTradeEvent 缓冲区池
+///
+/// 同步模式下为未携带缓冲区的命令提供事件列表，异步模式下用于复制结果；
+/// 调用方通过 ExchangeCore::recycle_command 归还，稳态下不再分配
+pub struct EventBufferPool {
+    buffers: Mutex<Vec<Vec<MatcherTradeEvent>>>,
+    max_pooled: usize,
+    buffer_capacity: usize,
+    acquired: AtomicU64,
+    allocated: AtomicU64,
+    released: AtomicU64,
+    discarded: AtomicU64,
+}
+
+impl EventBufferPool {
+    /// 新分配缓冲区的初始容量
+    pub const DEFAULT_BUFFER_CAPACITY: usize = 16;
+
+    /// max_pooled 为池中最多保留的缓冲区数（0 表示不池化）
+    pub fn new(max_pooled: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max_pooled)),
+            max_pooled,
+            buffer_capacity: Self::DEFAULT_BUFFER_CAPACITY,
+            acquired: AtomicU64::new(0),
+            allocated: AtomicU64::new(0),
+            released: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }
+    }
+
+    /// 取出一个空缓冲区，池为空时新分配
+    pub fn acquire(&self) -> Vec<MatcherTradeEvent> {
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+        if let Some(buffer) = self.buffers.lock().unwrap().pop() {
+            return buffer;
+        }
+        self.allocated.fetch_add(1, Ordering::Relaxed);
+        Vec::with_capacity(self.buffer_capacity)
+    }
+
+    /// 归还缓冲区（清空后放回池中）
+    pub fn release(&self, mut buffer: Vec<MatcherTradeEvent>) {
+        if buffer.capacity() == 0 {
+            return;
+        }
+        buffer.clear();
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max_pooled {
+            buffers.push(buffer);
+            self.released.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.discarded.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 复制命令（事件列表使用池中的缓冲区）
+    pub fn clone_command(&self, cmd: &OrderCommand) -> OrderCommand {
+        let mut events = self.acquire();
+        events.extend_from_slice(&cmd.matcher_events);
+        OrderCommand {
+            matcher_events: events,
+            command: cmd.command,
+            result_code: cmd.result_code,
+            uid: cmd.uid,
+            order_id: cmd.order_id,
+            symbol: cmd.symbol,
+            price: cmd.price,
+            reserve_price: cmd.reserve_price,
+            size: cmd.size,
+            action: cmd.action,
+            order_type: cmd.order_type,
+            timestamp: cmd.timestamp,
+            events_group: cmd.events_group,
+            service_flags: cmd.service_flags,
+            stop_price: cmd.stop_price,
+            visible_size: cmd.visible_size,
+            replenish_size: cmd.replenish_size,
+            expire_time: cmd.expire_time,
+            stp_mode: cmd.stp_mode,
+            stop_trigger: cmd.stop_trigger,
+            client_order_id: cmd.client_order_id,
+            sequence: cmd.sequence,
+            engine_timestamp: cmd.engine_timestamp,
+            amend_hold: cmd.amend_hold,
+            min_fill_size: cmd.min_fill_size,
+            hidden: cmd.hidden,
+            assign_order_id: cmd.assign_order_id,
+            activate_time: cmd.activate_time,
+            post_only_slide: cmd.post_only_slide,
+            market_data: cmd.market_data.clone(),
+            open_orders: cmd.open_orders.clone(),
+            binary_data: cmd.binary_data.clone(),
+        }
+    }
+
+    pub fn stats(&self) -> EventPoolStats {
+        EventPoolStats {
+            acquired: self.acquired.load(Ordering::Relaxed),
+            allocated: self.allocated.load(Ordering::Relaxed),
+            released: self.released.load(Ordering::Relaxed),
+            discarded: self.discarded.load(Ordering::Relaxed),
+            pooled: self.buffers.lock().unwrap().len(),
+        }
+    }
+}
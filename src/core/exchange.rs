@@ -1,6 +1,20 @@
 use crate::api::*;
-use crate::core::pipeline::Pipeline;
-use std::sync::Arc;
+use crate::core::affinity::{PlacementReport, ResolvedPlacement, ThreadAffinityConfig, ThreadPlacement, ThreadReport, ThreadRole};
+use crate::core::book_view::BookViews;
+use crate::core::candles::{CandleAggregator, SharedCandles};
+use crate::core::clock::SharedClock;
+use crate::core::command_future::{CommandFuture, PendingResults};
+use crate::core::event_pool::{EventBufferPool, EventPoolStats};
+use crate::core::implied::SpreadGroup;
+use crate::core::listener::OrderBookListener;
+use crate::core::surveillance::{SurveillanceConfig, SurveillanceConsumer, SurveillanceProcessor};
+use crate::core::pipeline::{CommandEvent, Pipeline, PipelineStages, StageHandler};
+use crate::core::processors::{rate_limiter::RateLimitConfig, risk_engine::RiskLimits};
+use disruptor::wait_strategies::WaitStrategy;
+use disruptor::ProcessorSettings;
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 
 /// 交易所核心配置
@@ -11,6 +25,17 @@ pub struct ExchangeConfig {
     pub risk_engines_num: usize,
     pub producer_type: ProducerType,
     pub wait_strategy: WaitStrategyType,
+    pub invariant_check_interval: u64, // debug 构建下每 N 条命令自动资金对账（0 关闭）
+    pub msgs_in_group_limit: usize, // 每组最多命令数
+    pub consumer_cores: Vec<usize>, // 消费者线程依次绑定的 CPU 核（分组、R1、撮合、R2、结果输出顺序），不足的线程不绑定
+    pub thread_affinity: ThreadAffinityConfig, // 按角色与分片指定线程的绑核、线程名与优先级（优先于 consumer_cores）
+    pub order_book_kind: OrderBookKind, // 默认订单簿实现，交易对可单独指定
+    pub risk_limits: RiskLimits,
+    pub rate_limit: Option<RateLimitConfig>, // 按 uid 限流（None 关闭）
+    pub event_pool_size: usize, // 事件缓冲区池最多保留的缓冲区数（0 不池化）
+    pub trading_day_length: i64, // 交易日长度（与 timestamp 同单位），Day 订单在所在交易日结束时到期（0 表示不到期）
+    pub spread_groups: Vec<SpreadGroup>, // 日历价差组，配置后计算并发布隐含报价，组内限价单可与隐含流动性成交（为空时关闭）
+    pub idempotency_cache_size: usize, // 每个风控分片按 (uid, client_order_id) 保留的下单幂等键数，重复提交返回原结果（0 关闭）
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -25,15 +50,83 @@ pub enum WaitStrategyType {
     Yielding,
     Blocking,
     Sleeping,
+    Adaptive, // 按连续空闲时间依次自旋、让出时间片、休眠
 }
 
 impl ExchangeConfig {
     // 这里不再需要同步转换方法，因为 startup 内部已经处理了配置到具体类型的映射
 }
 
+/// 让出时间片的等待策略
+#[derive(Clone, Copy)]
+struct YieldingWait;
+
+impl WaitStrategy for YieldingWait {
+    fn wait_for(&self, _sequence: disruptor::Sequence) {
+        std::thread::yield_now();
+    }
+}
+
+/// 短暂休眠的等待策略，降低空闲时的 CPU 占用
+#[derive(Clone, Copy)]
+struct SleepingWait;
+
+impl WaitStrategy for SleepingWait {
+    fn wait_for(&self, _sequence: disruptor::Sequence) {
+        std::thread::sleep(std::time::Duration::from_micros(50));
+    }
+}
+
+/// 阻塞等待策略（Disruptor 3.6.1 没有唤醒通知，以毫秒级休眠近似）
+#[derive(Clone, Copy)]
+struct BlockingWait;
+
+impl WaitStrategy for BlockingWait {
+    fn wait_for(&self, _sequence: disruptor::Sequence) {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
+
+/// 自适应等待策略：空闲时间短时自旋，随后让出时间片，长时间空闲后休眠
+///
+/// 空闲时间按线程记录，等待的序号变化（前一事件已到达）时重新计时
+#[derive(Clone, Copy)]
+struct AdaptiveWait;
+
+impl AdaptiveWait {
+    const SPIN: Duration = Duration::from_micros(20);   // 自旋阶段
+    const YIELD: Duration = Duration::from_micros(500); // 自旋后让出时间片的阶段
+    const PARK: Duration = Duration::from_micros(100);  // 之后每次休眠的时长
+}
+
+thread_local! {
+    /// 当前线程等待的序号与开始等待的时间
+    static ADAPTIVE_IDLE: Cell<Option<(disruptor::Sequence, Instant)>> = const { Cell::new(None) };
+}
+
+impl WaitStrategy for AdaptiveWait {
+    fn wait_for(&self, sequence: disruptor::Sequence) {
+        let idle = ADAPTIVE_IDLE.with(|state| match state.get() {
+            Some((waiting, since)) if waiting == sequence => since.elapsed(),
+            _ => {
+                state.set(Some((sequence, Instant::now())));
+                Duration::ZERO
+            }
+        });
+        if idle < Self::SPIN {
+            std::hint::spin_loop();
+        } else if idle < Self::YIELD {
+            std::thread::yield_now();
+        } else {
+            std::thread::park_timeout(Self::PARK);
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ExchangeState {
     pub config: ExchangeConfig,
+    pub seq_id: u64, // 快照包含的最后一条命令序列号
     pub pipeline_state: crate::core::pipeline::PipelineState,
 }
 
@@ -45,6 +138,17 @@ impl Default for ExchangeConfig {
             risk_engines_num: 1,
             producer_type: ProducerType::Single,
             wait_strategy: WaitStrategyType::BusySpin,
+            invariant_check_interval: 1024,
+            msgs_in_group_limit: 256,
+            consumer_cores: Vec::new(),
+            thread_affinity: ThreadAffinityConfig::default(),
+            order_book_kind: OrderBookKind::Direct,
+            risk_limits: RiskLimits::default(),
+            rate_limit: None,
+            event_pool_size: 1024,
+            trading_day_length: 0,
+            spread_groups: Vec::new(),
+            idempotency_cache_size: 0,
         }
     }
 }
@@ -52,48 +156,181 @@ impl Default for ExchangeConfig {
 /// 结果消费者回调
 pub type ResultConsumer = Arc<dyn Fn(&OrderCommand) + Send + Sync>;
 
-use crate::core::journal::Journaler;
+use crate::core::journal::{JournalConfig, JournalReader, JournalSink, Journaler, ResultJournaler};
 use std::path::Path;
 
-use crate::core::snapshot::SnapshotStore;
+use crate::core::replication::{ReplicaLinkStatus, ReplicationPublisher, ReplicationTransport};
+use crate::core::snapshot::{SnapshotConfig, SnapshotStore};
 
-/// 内部接口，用于类型抹除 Disruptor 的泛型 Producer
-trait Publisher {
+/// 内部接口，用于类型抹除 Disruptor 的泛型 Producer（Send：ExchangeCore 可移交给服务线程）
+trait Publisher: Send {
     fn publish(&mut self, cmd: OrderCommand);
 }
 
-struct ProducerWrapper<P: disruptor::Producer<OrderCommand>>(P);
+struct ProducerWrapper<P: disruptor::Producer<CommandEvent>>(P);
 
-impl<P: disruptor::Producer<OrderCommand>> Publisher for ProducerWrapper<P> {
+impl<P: disruptor::Producer<CommandEvent> + Send> Publisher for ProducerWrapper<P> {
     fn publish(&mut self, cmd: OrderCommand) {
         self.0.publish(|event| {
-            *event = cmd;
+            // 命令未携带事件缓冲区时沿用槽位中上一条命令的缓冲区
+            let slot = event.get_mut().unwrap();
+            let mut events = std::mem::take(&mut slot.matcher_events);
+            *slot = cmd;
+            if slot.matcher_events.capacity() == 0 {
+                events.clear();
+                slot.matcher_events = events;
+            }
         });
     }
 }
 
+/// 构建流水线时按消费者顺序解析各线程的放置
+struct PlacementPlan<'a> {
+    affinity: &'a ThreadAffinityConfig,
+    cores: std::slice::Iter<'a, usize>, // consumer_cores 按消费者线程顺序分配，显式指定的核优先
+    report: PlacementReport,
+}
+
+impl PlacementPlan<'_> {
+    fn next(&mut self, role: ThreadRole, shard: usize) -> ResolvedPlacement {
+        let fallback = self.cores.next().copied();
+        self.affinity.resolve(role, shard, fallback)
+    }
+}
+
+/// 以下一个放置注册消费者：按放置命名线程，线程启动时绑核、设置优先级并登记到放置报告
+macro_rules! place_next {
+    ($builder:expr, $handler:expr, $plan:ident, $role:expr, $shard:expr) => {{
+        let placement = $plan.next($role, $shard);
+        let report = $plan.report.clone();
+        let mut handler: StageHandler = $handler;
+        // Disruptor 要求 'static 线程名，每次启动每个线程泄漏一次
+        let name: &'static str = Box::leak(placement.name.clone().into_boxed_str());
+        $builder.thread_name(name).handle_events_and_state_with(
+            move |_: &mut (), event: &CommandEvent, sequence: i64, end_of_batch: bool| handler(event, sequence, end_of_batch),
+            move || report.lock().unwrap().push(placement.apply()),
+        )
+    }};
+}
+
+/// 注册一个阶段的全部分片处理器，随后以 |$b| 绑定的构建器继续构建后续阶段
+///
+/// Disruptor 构建器按消费者数量（单个/多个）区分类型，两种情况分别展开
+macro_rules! stage {
+    ($builder:expr, $handlers:expr, $role:expr, $plan:ident, |$b:ident| $rest:expr) => {{
+        let mut handlers = $handlers.into_iter().enumerate();
+        let (shard, handler) = handlers.next().expect("流水线阶段至少需要一个处理器");
+        let $b = place_next!($builder, handler, $plan, $role, shard);
+        match handlers.next() {
+            None => $rest,
+            Some((shard, handler)) => {
+                let mut $b = place_next!($b, handler, $plan, $role, shard);
+                for (shard, handler) in handlers {
+                    $b = place_next!($b, handler, $plan, $role, shard);
+                }
+                $rest
+            }
+        }
+    }};
+}
+
+/// 按 分组 -> R1 -> 撮合 -> R2 -> 结果输出 串联各阶段并构建生产者
+macro_rules! build_stages {
+    ($builder:expr, $stages:expr, $plan:ident) => {{
+        let PipelineStages { grouping, risk_pre, matching, risk_post, results } = $stages;
+        stage!($builder, grouping, ThreadRole::Grouping, $plan, |b| {
+            stage!(b.and_then(), risk_pre, ThreadRole::RiskPre, $plan, |b| {
+                stage!(b.and_then(), matching, ThreadRole::Matching, $plan, |b| {
+                    stage!(b.and_then(), risk_post, ThreadRole::RiskPost, $plan, |b| {
+                        stage!(b.and_then(), results, ThreadRole::Results, $plan, |b| Box::new(ProducerWrapper(b.build())) as Box<dyn Publisher>)
+                    })
+                })
+            })
+        })
+    }};
+}
+
+/// 以指定等待策略构建生产者及各阶段消费者
+fn build_producer<W: WaitStrategy + 'static>(
+    config: &ExchangeConfig,
+    stages: PipelineStages,
+    wait_strategy: W,
+    report: PlacementReport,
+) -> Box<dyn Publisher> {
+    let ring_size = config.ring_buffer_size;
+    let factory = || Mutex::new(OrderCommand::default());
+    let mut plan = PlacementPlan {
+        affinity: &config.thread_affinity,
+        cores: config.consumer_cores.iter(),
+        report,
+    };
+    match config.producer_type {
+        ProducerType::Single => build_stages!(
+            disruptor::build_single_producer(ring_size, factory, wait_strategy),
+            stages,
+            plan
+        ),
+        ProducerType::Multi => build_stages!(
+            disruptor::build_multi_producer(ring_size, factory, wait_strategy),
+            stages,
+            plan
+        ),
+    }
+}
+
 /// 交易所核心
 pub struct ExchangeCore {
     config: ExchangeConfig,
     // 使用 Publisher trait 对象隐藏具体的扰乱器生产者类型
     producer: Option<Box<dyn Publisher>>,
     pipeline: Option<Pipeline>,
-    journaler: Option<Journaler>,
+    journaler: Option<Box<dyn JournalSink>>,
+    result_journaler: Option<ResultJournaler>, // 审计用结果日志（仅同步处理模式）
     snapshot_store: Option<SnapshotStore>,
+    replication: Option<ReplicationPublisher>, // 主节点向副本发送命令
+    last_seq: u64, // 最后处理的命令序列号（启用日志时与日志序列号一致）
+    published: i64, // 已发布到 Disruptor 的命令数，即下一条命令的 Disruptor 序号
+    pending_results: Arc<PendingResults>,
+    event_pool: Arc<EventBufferPool>,
+    clock: Option<SharedClock>, // 为 timestamp 为 0 的命令补时间
+    snapshot_schedule: Option<SnapshotSchedule>,
+    placements: PlacementReport, // 流水线线程启动时登记的实际放置
+}
+
+/// 按时钟时间定时生成快照
+#[derive(Debug, Clone, Copy)]
+struct SnapshotSchedule {
+    interval: i64,
+    next_at: Option<i64>, // 首条命令时按其时间确定
 }
 
 impl ExchangeCore {
     pub fn new(config: ExchangeConfig) -> Self {
         let pipeline = Pipeline::new(&config);
+        let event_pool = Arc::new(EventBufferPool::new(config.event_pool_size));
         Self { 
             config, 
             pipeline: Some(pipeline),
             producer: None,
             journaler: None,
+            result_journaler: None,
             snapshot_store: None,
+            replication: None,
+            last_seq: 0,
+            published: 0,
+            pending_results: Arc::new(PendingResults::new(event_pool.clone())),
+            event_pool,
+            clock: None,
+            snapshot_schedule: None,
+            placements: PlacementReport::default(),
         }
     }
 
+    /// 最后处理的命令序列号
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
     /// 启动 Disruptor 流水线
     pub fn startup(&mut self) {
         if self.producer.is_some() {
@@ -101,68 +338,184 @@ impl ExchangeCore {
         }
 
         if let Some(mut pipeline) = self.pipeline.take() {
-            let ring_size = self.config.ring_buffer_size;
-            
-            // 封装事件处理逻辑
-            // Disruptor 3.6.1 的 handler 接收的是 &E (不可变)
-            // 为了维持原有 Pipeline 的可变逻辑，我们在处理前进行克隆
-            let handler = move |event: &OrderCommand, sequence: i64, end_of_batch: bool| {
-                let mut cmd_mut = event.clone();
-                pipeline.handle_event(&mut cmd_mut, sequence, end_of_batch);
-            };
+            if let Some(store) = &self.snapshot_store {
+                pipeline.set_snapshot_target(store.clone(), self.config.clone());
+            }
 
-            // 使用 build_single_producer / build_multi_producer
-            // 目前 3.6.1 仅显式支持 BusySpin 等几种策略在 wait_strategies 下
-            let producer: Box<dyn Publisher> = match self.config.producer_type {
-                ProducerType::Single => {
-                    Box::new(ProducerWrapper(disruptor::build_single_producer(ring_size, || OrderCommand::default(), disruptor::wait_strategies::BusySpin)
-                        .handle_events_with(handler)
-                        .build()))
-                },
-                ProducerType::Multi => {
-                    Box::new(ProducerWrapper(disruptor::build_multi_producer(ring_size, || OrderCommand::default(), disruptor::wait_strategies::BusySpin)
-                        .handle_events_with(handler)
-                        .build()))
-                }
+            // 分组、R1 风控、撮合、R2 风控分别作为串联的 Disruptor 消费者阶段，阶段内按分片并行
+            let stages = pipeline.into_stages(self.pending_results.clone());
+            // 日志在提交命令的线程上写入，调用 startup 的线程即日志线程
+            if self.config.thread_affinity.journal != ThreadPlacement::default() {
+                let placement = self.config.thread_affinity.resolve(ThreadRole::Journal, 0, None);
+                self.placements.lock().unwrap().push(placement.apply());
+            }
+            let producer = match self.config.wait_strategy {
+                WaitStrategyType::BusySpin => build_producer(&self.config, stages, disruptor::wait_strategies::BusySpin, self.placements.clone()),
+                WaitStrategyType::Yielding => build_producer(&self.config, stages, YieldingWait, self.placements.clone()),
+                WaitStrategyType::Blocking => build_producer(&self.config, stages, BlockingWait, self.placements.clone()),
+                WaitStrategyType::Sleeping => build_producer(&self.config, stages, SleepingWait, self.placements.clone()),
+                WaitStrategyType::Adaptive => build_producer(&self.config, stages, AdaptiveWait, self.placements.clone()),
             };
 
             self.producer = Some(producer);
         }
     }
 
+    /// 流水线线程的实际放置（线程启动后登记，按角色与分片排序）
+    pub fn thread_placements(&self) -> Vec<ThreadReport> {
+        let mut reports = self.placements.lock().unwrap().clone();
+        reports.sort_by_key(|report| (report.role, report.shard));
+        reports
+    }
+
     /// 启用快照管理
     pub fn enable_snapshotting<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
-        self.snapshot_store = Some(SnapshotStore::new(path)?);
+        self.enable_snapshotting_with_config(path, SnapshotConfig::default())
+    }
+
+    /// 启用快照管理，并指定压缩方式与是否后台写入
+    pub fn enable_snapshotting_with_config<P: AsRef<Path>>(&mut self, path: P, config: SnapshotConfig) -> anyhow::Result<()> {
+        self.snapshot_store = Some(SnapshotStore::with_config(path, config)?);
         Ok(())
     }
 
-    /// 生成当前状态快照
-    pub fn take_snapshot(&self, seq_id: u64) -> anyhow::Result<()> {
-        if let Some(store) = &self.snapshot_store {
+    /// 等待后台写入的快照全部落盘，返回写入失败的错误
+    pub fn flush_snapshots(&self) -> anyhow::Result<()> {
+        match &self.snapshot_store {
+            Some(store) => store.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// 按时间定时生成快照：提交命令时若时钟时间（未设置时钟时为命令时间）距上次快照达到 interval 则生成快照
+    pub fn schedule_snapshots(&mut self, interval: i64) {
+        self.snapshot_schedule = (interval > 0).then_some(SnapshotSchedule { interval, next_at: None });
+    }
+
+    /// 生成当前状态快照，以最后处理的命令序列号为快照 ID 并返回
+    ///
+    /// 启动后改为提交 PersistStateMatching 命令，由处理线程在处理到该命令时异步落盘，
+    /// 快照 ID 为该命令自身的序列号
+    pub fn create_snapshot(&mut self) -> anyhow::Result<u64> {
+        let Some(store) = &self.snapshot_store else {
+            return Ok(self.last_seq);
+        };
+        if self.pipeline.is_some() {
             let state = self.serialize_state();
-            store.save_snapshot(&state, seq_id)?;
+            store.save_snapshot(&state, state.seq_id)?;
+            return Ok(self.last_seq);
         }
-        Ok(())
+
+        let seq_id = self.last_seq + 1;
+        self.submit_command(OrderCommand {
+            command: OrderCommandType::PersistStateMatching,
+            order_id: seq_id,
+            ..Default::default()
+        });
+        if self.last_seq != seq_id {
+            anyhow::bail!("快照命令写入日志失败");
+        }
+        Ok(seq_id)
     }
 
-    /// 加载最新的快照并恢复状态
+    /// 生成当前状态快照（旧接口）：快照 ID 改为由引擎按命令序列号分配，传入的 seq_id 不再使用
+    #[deprecated(note = "改用 create_snapshot，快照 ID 由引擎分配并返回")]
+    pub fn take_snapshot(&mut self, _seq_id: u64) -> anyhow::Result<()> {
+        self.create_snapshot().map(|_| ())
+    }
+
+    /// 加载最新的快照并恢复状态（保留已启用的日志与快照配置）
     pub fn load_latest_snapshot(&mut self) -> anyhow::Result<bool> {
-        if let Some(store) = &self.snapshot_store {
-            if let Some(seq_id) = store.get_latest_seq_id()? {
-                let state = store.load_snapshot(seq_id)?;
-                *self = Self::from_state(state);
-                return Ok(true);
+        let Some(store) = &self.snapshot_store else {
+            return Ok(false);
+        };
+        store.flush()?;
+        let Some(seq_id) = store.get_latest_seq_id()? else {
+            return Ok(false);
+        };
+
+        let state = store.load_snapshot(seq_id)?;
+        let journaler = self.journaler.take();
+        let result_journaler = self.result_journaler.take();
+        let snapshot_store = self.snapshot_store.take();
+        let (clock, snapshot_schedule) = (self.clock.take(), self.snapshot_schedule.take());
+        *self = Self::from_state(state);
+        self.journaler = journaler;
+        self.result_journaler = result_journaler;
+        self.snapshot_store = snapshot_store;
+        self.clock = clock;
+        self.snapshot_schedule = snapshot_schedule;
+        Ok(true)
+    }
+
+    /// 故障恢复：加载最新快照，再重放日志中快照之后的命令，返回恢复到的序列号
+    ///
+    /// 需在 enable_snapshotting / enable_journaling 之后、startup 之前调用
+    pub fn recover(&mut self) -> anyhow::Result<u64> {
+        if self.pipeline.is_none() {
+            anyhow::bail!("只能在启动前恢复");
+        }
+        self.load_latest_snapshot()?;
+
+        // 流式读取日志尾部，逐条送入流水线
+        if let Some(journaler) = &self.journaler {
+            let records = journaler.read_after(self.last_seq)?;
+            let pipeline = self.pipeline.as_mut().expect("只能在启动前恢复");
+            for record in records {
+                let (seq, mut cmd) = record?;
+                pipeline.handle_event(&mut cmd, seq as i64, true);
+                self.last_seq = seq;
             }
         }
-        Ok(false)
+        Ok(self.last_seq)
     }
 
     /// 启用日志持久化
     pub fn enable_journaling<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
-        self.journaler = Some(Journaler::new(path)?);
+        self.journaler = Some(Box::new(Journaler::new(path)?));
+        Ok(())
+    }
+
+    /// 启用日志持久化（自定义分段大小与刷盘策略）
+    pub fn enable_journaling_with_config<P: AsRef<Path>>(&mut self, path: P, config: JournalConfig) -> anyhow::Result<()> {
+        self.journaler = Some(Box::new(Journaler::with_config(path, config)?));
+        Ok(())
+    }
+
+    /// 使用自定义的日志输出端（网络、消息队列等），命令序列号由输出端分配
+    pub fn enable_journal_sink<S: JournalSink + 'static>(&mut self, sink: S) {
+        self.journaler = Some(Box::new(sink));
+    }
+
+    /// 启用结果日志：记录每条命令的结果码与撮合事件（序列号与命令日志一致）
+    ///
+    /// 仅在 startup 之前的同步处理模式下记录；异步模式请使用结果消费者
+    pub fn enable_result_journaling<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        self.result_journaler = Some(ResultJournaler::new(path)?);
         Ok(())
     }
 
+    /// 压缩日志：删除已被最新快照覆盖的分段，返回删除的分段数
+    pub fn compact_journal(&mut self) -> anyhow::Result<usize> {
+        let (Some(journaler), Some(store)) = (&mut self.journaler, &self.snapshot_store) else {
+            return Ok(0);
+        };
+        match store.get_latest_seq_id()? {
+            Some(seq_id) => journaler.compact(seq_id),
+            None => Ok(0),
+        }
+    }
+
+    /// 设置引擎时钟（实时或回测用的模拟时钟）
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = Some(clock);
+    }
+
+    /// 时钟当前时间，未设置时钟时为 None
+    pub fn now(&self) -> Option<i64> {
+        self.clock.as_ref().map(|clock| clock.now())
+    }
+
     /// 结果消费者回调
     pub fn set_result_consumer(&mut self, consumer: ResultConsumer) {
         if let Some(p) = &mut self.pipeline {
@@ -170,35 +523,327 @@ impl ExchangeCore {
         }
     }
 
-    pub fn add_symbol(&mut self, spec: CoreSymbolSpecification) {
+    /// 行情消费者回调（需在 startup 之前注册）
+    pub fn add_market_data_consumer(&mut self, consumer: crate::core::market_data::MarketDataConsumer) {
         if let Some(p) = &mut self.pipeline {
-            p.add_symbol(spec);
+            p.add_market_data_consumer(consumer);
         }
     }
 
+    /// 订单簿监听器（需在 startup 之前注册）：factory 按撮合分片号为每个分片创建一个实例，在撮合线程内同步回调
+    pub fn add_order_book_listener(&mut self, factory: impl Fn(usize) -> Box<dyn OrderBookListener>) {
+        if let Some(p) = &mut self.pipeline {
+            p.add_order_book_listener(factory);
+        }
+    }
+
+    /// 开启交易监控（需在 startup 之前调用）：各撮合分片检测自成交、对倒与高撤单率，告警在撮合线程内交给 consumer
+    pub fn enable_surveillance(&mut self, config: SurveillanceConfig, consumer: SurveillanceConsumer) {
+        self.add_order_book_listener(move |_| Box::new(SurveillanceProcessor::new(config, consumer.clone())));
+    }
+
+    /// 开启 K 线聚合（需在 startup 之前调用），intervals 为各周期（与 timestamp 同单位），history 为每个周期保留的 K 线数量
+    pub fn enable_candles(&mut self, intervals: &[i64], history: usize) -> SharedCandles {
+        let (candles, consumer) = CandleAggregator::new(intervals, history).into_consumer();
+        self.add_market_data_consumer(consumer);
+        candles
+    }
+
+    /// 开启订单簿只读视图（需在 startup 之前调用）：撮合线程每条命令后发布其触及交易对的视图（每侧最多 depth 档），
+    /// 返回的句柄可在任意线程无锁查询深度与统计，不经过流水线
+    pub fn enable_book_views(&mut self, depth: usize) -> anyhow::Result<BookViews> {
+        match &mut self.pipeline {
+            Some(p) => Ok(p.enable_book_views(depth)),
+            None => anyhow::bail!("只能在启动前开启订单簿视图"),
+        }
+    }
+
+    /// 添加交易对
+    ///
+    /// 启动前直接写入各引擎（不写日志，恢复前需重新添加）；启动后以添加交易对命令提交并等待结果，写入日志并可重放
+    pub fn add_symbol(&mut self, spec: CoreSymbolSpecification) -> CommandResultCode {
+        match &mut self.pipeline {
+            Some(_) if !spec.is_valid() => CommandResultCode::SymbolMgmtInvalidSpecification,
+            Some(p) => {
+                p.add_symbol(spec);
+                CommandResultCode::Success
+            }
+            None => self.submit_command_async(OrderCommand::add_symbols(&[spec])).wait().result_code,
+        }
+    }
+
+    /// 查询已注册的交易对（按 symbol_id 排序）
+    pub fn symbols(&mut self) -> anyhow::Result<Vec<CoreSymbolSpecification>> {
+        let result = self
+            .submit_command_async(OrderCommand {
+                command: OrderCommandType::BinaryDataQuery,
+                service_flags: BINARY_QUERY_SYMBOLS,
+                ..Default::default()
+            })
+            .wait();
+        if result.result_code != CommandResultCode::Success {
+            anyhow::bail!("查询交易对失败: {:?}", result.result_code);
+        }
+        Ok(result.decode_symbols()?)
+    }
+
+    /// 查询用户的余额调整流水（按入账顺序）
+    pub fn balance_ledger(&mut self, uid: UserId) -> anyhow::Result<Vec<LedgerEntry>> {
+        let result = self
+            .submit_command_async(OrderCommand {
+                command: OrderCommandType::BinaryDataQuery,
+                service_flags: BINARY_QUERY_BALANCE_LEDGER,
+                uid,
+                ..Default::default()
+            })
+            .wait();
+        if result.result_code != CommandResultCode::Success {
+            anyhow::bail!("查询余额流水失败: {:?}", result.result_code);
+        }
+        Ok(bincode::deserialize(&result.binary_data)?)
+    }
+
+    /// 查询各交易对当前的标记价与指数价（按 symbol_id 排序，从未推送的交易对不返回）
+    pub fn reference_prices(&mut self) -> anyhow::Result<Vec<ReferencePrices>> {
+        let result = self
+            .submit_command_async(OrderCommand {
+                command: OrderCommandType::BinaryDataQuery,
+                service_flags: BINARY_QUERY_REFERENCE_PRICES,
+                ..Default::default()
+            })
+            .wait();
+        if result.result_code != CommandResultCode::Success {
+            anyhow::bail!("查询参考价格失败: {:?}", result.result_code);
+        }
+        Ok(bincode::deserialize(&result.binary_data)?)
+    }
+
+    /// 查询用户的保证金持仓及按标记价计算的未实现盈亏（按 symbol_id 排序）
+    pub fn positions(&mut self, uid: UserId) -> anyhow::Result<Vec<PositionReport>> {
+        let result = self
+            .submit_command_async(OrderCommand {
+                command: OrderCommandType::BinaryDataQuery,
+                service_flags: BINARY_QUERY_POSITIONS,
+                uid,
+                ..Default::default()
+            })
+            .wait();
+        if result.result_code != CommandResultCode::Success {
+            anyhow::bail!("查询持仓失败: {:?}", result.result_code);
+        }
+        Ok(bincode::deserialize(&result.binary_data)?)
+    }
+
+    /// 查询用户当前与上一统计周期的 maker 成交量
+    pub fn maker_volume(&mut self, uid: UserId) -> anyhow::Result<MakerVolumeReport> {
+        let result = self
+            .submit_command_async(OrderCommand {
+                command: OrderCommandType::BinaryDataQuery,
+                service_flags: BINARY_QUERY_MAKER_VOLUME,
+                uid,
+                ..Default::default()
+            })
+            .wait();
+        if result.result_code != CommandResultCode::Success {
+            anyhow::bail!("查询 maker 成交量失败: {:?}", result.result_code);
+        }
+        Ok(bincode::deserialize(&result.binary_data)?)
+    }
+
+    /// 重置 maker 成交量统计（开始新周期），此前提交的命令的成交计入旧周期
+    pub fn reset_maker_volume(&mut self) -> CommandResultCode {
+        let cmd = OrderCommand {
+            command: OrderCommandType::BinaryDataCommand,
+            service_flags: BINARY_COMMAND_RESET_MAKER_VOLUME,
+            ..Default::default()
+        };
+        self.submit_command_async(cmd).wait().result_code
+    }
+
     /// 提交命令
+    ///
+    /// 启动后命令异步处理，返回的是未处理的原命令；需要结果时使用 submit_command_async
     pub fn submit_command(&mut self, mut cmd: OrderCommand) -> OrderCommand {
-        if let Some(j) = &mut self.journaler {
-            let _ = j.write_command(&cmd);
+        if cmd.command == OrderCommandType::AdvanceTime {
+            return self.advance_time(cmd);
         }
-        
+        if !self.accept(&mut cmd) {
+            return cmd;
+        }
+        let cmd = self.process_command(cmd);
+        self.run_snapshot_schedule(cmd.timestamp);
+        cmd
+    }
+
+    /// 补全时间并写入命令日志；日志写入失败时命令不处理、不复制，结果码置为 JournalWriteFailed
+    fn accept(&mut self, cmd: &mut OrderCommand) -> bool {
+        self.stamp(cmd);
+        if let Err(e) = self.journal_command(cmd) {
+            tracing::error!("写入命令日志失败，拒绝命令: {}", e);
+            cmd.result_code = CommandResultCode::JournalWriteFailed;
+            return false;
+        }
+        true
+    }
+
+    /// 发布到流水线（启动后）或同步处理（启动前）
+    fn process_command(&mut self, mut cmd: OrderCommand) -> OrderCommand {
         if let Some(producer) = &mut self.producer {
+            // 快照命令与之前的命令不能落在 R1 的同一批次中，否则 R1 等不到它们输出结果
+            if matches!(cmd.command, OrderCommandType::PersistStateMatching | OrderCommandType::PersistStateRisk) {
+                self.pending_results.wait_completed(self.published - 1);
+            }
             producer.publish(cmd.clone());
+            self.published += 1;
             cmd
         } else if let Some(pipeline) = &mut self.pipeline {
-            pipeline.handle_event(&mut cmd, 0, true);
+            if cmd.matcher_events.capacity() == 0 {
+                cmd.matcher_events = self.event_pool.acquire();
+            }
+            pipeline.handle_event(&mut cmd, self.last_seq as i64, true);
+            if let Some(r) = &mut self.result_journaler {
+                let _ = r.write_outcome(self.last_seq, &cmd);
+            }
             cmd
         } else {
             panic!("ExchangeCore 未就绪");
         }
     }
 
-    /// 从日志重放
+    /// 提交命令并返回处理结果的 future，由结果输出阶段写入处理完成的命令
+    ///
+    /// 启动前同步处理，返回的 future 已就绪
+    pub fn submit_command_async(&mut self, mut cmd: OrderCommand) -> CommandFuture {
+        if self.producer.is_none() || cmd.command == OrderCommandType::AdvanceTime {
+            return CommandFuture::ready(self.submit_command(cmd));
+        }
+        // 未写入日志的命令不发布，直接返回拒绝结果
+        if !self.accept(&mut cmd) {
+            return CommandFuture::ready(cmd);
+        }
+        // 先登记再发布，避免结果先于登记到达
+        let future = self.pending_results.register(self.published);
+        let timestamp = cmd.timestamp;
+        self.process_command(cmd);
+        self.run_snapshot_schedule(timestamp);
+        future
+    }
+
+    /// 提交前补全时间：timestamp 为 0 时取时钟时间；Day 订单的过期时间为所在交易日结束
+    fn stamp(&self, cmd: &mut OrderCommand) {
+        if cmd.timestamp == 0 {
+            if let Some(clock) = &self.clock {
+                cmd.timestamp = clock.now();
+            }
+        }
+        let day = self.config.trading_day_length;
+        if cmd.command == OrderCommandType::PlaceOrder && cmd.order_type == OrderType::Day && cmd.expire_time.is_none() && day > 0 {
+            cmd.expire_time = Some(cmd.timestamp.div_euclid(day) * day + day - 1);
+        }
+    }
+
+    /// AdvanceTime：推进时钟，再对每个交易对提交 timestamp 为目标时间的过期扫描与 GoodAfterTime 激活（扫描命令写入日志，重放结果一致）
+    ///
+    /// 返回的命令结果码为 Success，size 为过期订单数
+    fn advance_time(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        if let Some(clock) = &self.clock {
+            clock.advance_to(cmd.timestamp);
+        }
+        let symbols = match self.symbols() {
+            Ok(symbols) => symbols,
+            Err(_) => {
+                cmd.result_code = CommandResultCode::BinaryCommandFailed;
+                return cmd;
+            }
+        };
+        let futures: Vec<_> = symbols
+            .iter()
+            .map(|spec| {
+                self.submit_command_async(OrderCommand {
+                    command: OrderCommandType::ExpireOrders,
+                    symbol: spec.symbol_id,
+                    timestamp: cmd.timestamp,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        cmd.size = 0;
+        for future in futures {
+            let expired = future.wait();
+            // 激活的 GoodAfterTime 订单产生的事件以该订单为 taker，不计入过期数
+            cmd.size += expired.matcher_events.iter().filter(|e| e.event_type == MatcherEventType::Reject && e.taker_action.is_none()).count() as Size;
+            self.recycle_command(expired);
+        }
+        cmd.result_code = CommandResultCode::Success;
+        cmd
+    }
+
+    fn run_snapshot_schedule(&mut self, timestamp: i64) {
+        let Some(schedule) = &mut self.snapshot_schedule else {
+            return;
+        };
+        let now = self.clock.as_ref().map_or(timestamp, |clock| clock.now());
+        let next_at = *schedule.next_at.get_or_insert(now + schedule.interval);
+        if now < next_at {
+            return;
+        }
+        // 先更新下次时间，快照命令本身经过 submit_command 时不再触发
+        schedule.next_at = Some(now + schedule.interval);
+        if let Err(e) = self.create_snapshot() {
+            tracing::warn!("定时快照失败: {}", e);
+        }
+    }
+
+    /// 归还处理完的命令，其事件缓冲区回到池中供后续命令复用
+    pub fn recycle_command(&self, cmd: OrderCommand) {
+        self.event_pool.release(cmd.matcher_events);
+    }
+
+    /// 事件缓冲区池统计
+    pub fn event_pool_stats(&self) -> EventPoolStats {
+        self.event_pool.stats()
+    }
+
+    /// 写入命令日志并更新序列号，分配了序列号的命令同时发送给副本；写入失败时序列号不前进
+    fn journal_command(&mut self, cmd: &OrderCommand) -> anyhow::Result<()> {
+        self.last_seq = match &mut self.journaler {
+            Some(j) => j.append(cmd)?,
+            None => self.last_seq + 1,
+        };
+        if let Some(replication) = &mut self.replication {
+            replication.publish(self.last_seq, cmd);
+        }
+        Ok(())
+    }
+
+    /// 添加热备副本：之后提交的命令按序列号发送给副本
+    ///
+    /// 副本的 ExchangeCore 须与当前状态一致（从同一快照恢复或同为空状态）
+    pub fn add_replica(&mut self, transport: Box<dyn ReplicationTransport>) {
+        self.replication.get_or_insert_with(ReplicationPublisher::default).add_replica(transport, self.last_seq);
+    }
+
+    /// 各副本的复制状态（按添加顺序）
+    pub fn replication_status(&self) -> Vec<ReplicaLinkStatus> {
+        self.replication.as_ref().map_or_else(Vec::new, ReplicationPublisher::status)
+    }
+
+    /// 资金对账（余额 + 冻结 + 保证金 + 手续费 与净入金逐币种比对），需在启动前调用
+    pub fn verify_invariants(&self) -> anyhow::Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or_else(|| anyhow::anyhow!("只能在启动前对账"))?;
+        pipeline.verify_invariants()?;
+        Ok(())
+    }
+
+    /// 从日志重放（流式读取，内存占用与日志大小无关）
+    ///
+    /// 遇到损坏记录时返回错误，之前的记录已经重放
     pub fn replay_journal<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
-        let commands = Journaler::read_commands(path)?;
-        for mut cmd in commands {
+        for record in JournalReader::open(path)? {
+            let (seq, mut cmd) = record?;
             if let Some(pipeline) = &mut self.pipeline {
-                pipeline.handle_event(&mut cmd, 0, true);
+                pipeline.handle_event(&mut cmd, seq as i64, true);
+                self.last_seq = seq;
             } else {
                 self.submit_command(cmd);
             }
@@ -209,17 +854,29 @@ impl ExchangeCore {
     pub fn serialize_state(&self) -> ExchangeState {
         ExchangeState {
             config: self.config.clone(),
+            seq_id: self.last_seq,
             pipeline_state: self.pipeline.as_ref().expect("只能在启动前序列化").serialize_state(),
         }
     }
 
     pub fn from_state(state: ExchangeState) -> Self {
+        let pipeline = Pipeline::from_state(state.pipeline_state, &state.config);
+        let event_pool = Arc::new(EventBufferPool::new(state.config.event_pool_size));
         Self {
             config: state.config,
-            pipeline: Some(Pipeline::from_state(state.pipeline_state)),
+            pipeline: Some(pipeline),
             producer: None,
             journaler: None,
+            result_journaler: None,
             snapshot_store: None,
+            replication: None,
+            last_seq: state.seq_id,
+            published: 0,
+            pending_results: Arc::new(PendingResults::new(event_pool.clone())),
+            event_pool,
+            clock: None,
+            snapshot_schedule: None,
+            placements: PlacementReport::default(),
         }
     }
 }
@@ -0,0 +1,196 @@
+use crate::api::*;
+use crate::core::orderbook::{OrderBook, TopOfBook};
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+/// 日历价差组：spread 为买近月 front、卖远月 back 的价差合约，价格为 front - back
+///
+/// 三个交易对均需为期货合约（FuturesContract）、以同一币种计价，并属于同一撮合分片
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpreadGroup {
+    pub spread: SymbolId,
+    pub front: SymbolId,
+    pub back: SymbolId,
+}
+
+/// 隐含成交的一条腿：以 action 方向在 symbol 的订单簿吃单，成交价按 sign（±1）计入合成价格
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImpliedLeg {
+    pub symbol: SymbolId,
+    pub action: OrderAction,
+    pub sign: Price,
+}
+
+/// 可吃到的隐含流动性：两条腿分别按对手方最优价（legs 中的价格）成交 size 手，合成价格为 price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImpliedOffer {
+    pub price: Price,
+    pub size: Size,
+    pub legs: [(ImpliedLeg, Price); 2],
+}
+
+impl SpreadGroup {
+    fn contains(&self, symbol: SymbolId) -> bool {
+        self.spread == symbol || self.front == symbol || self.back == symbol
+    }
+
+    /// 三个交易对均为期货合约且计价币种相同时才计算隐含价格、进行隐含成交
+    pub fn is_tradable<'a>(&self, spec: impl Fn(SymbolId) -> Option<&'a CoreSymbolSpecification>) -> bool {
+        let specs = [self.spread, self.front, self.back].map(spec);
+        let Some(quote_currency) = specs[0].map(|spec| spec.quote_currency) else {
+            return false;
+        };
+        specs
+            .iter()
+            .all(|spec| spec.is_some_and(|spec| spec.symbol_type == SymbolType::FuturesContract && spec.quote_currency == quote_currency))
+    }
+
+    /// symbol 上 action 方向的订单以隐含方式成交时的两条腿（不属于本组时返回 None）
+    ///
+    /// 价差 = 近月 - 远月，近月 = 价差 + 远月，远月 = 近月 - 价差；减号一侧的腿反向成交
+    pub fn implied_legs(&self, symbol: SymbolId, action: OrderAction) -> Option<[ImpliedLeg; 2]> {
+        let terms = if symbol == self.spread {
+            [(self.front, 1), (self.back, -1)]
+        } else if symbol == self.front {
+            [(self.spread, 1), (self.back, 1)]
+        } else if symbol == self.back {
+            [(self.front, 1), (self.spread, -1)]
+        } else {
+            return None;
+        };
+        Some(terms.map(|(symbol, sign)| ImpliedLeg {
+            symbol,
+            action: if sign > 0 { action } else { action.opposite() },
+            sign,
+        }))
+    }
+
+    /// symbol 上 action 方向的订单可吃到的隐含流动性：各腿对手方最优价按符号合成，数量取两者较小值
+    ///
+    /// 买方吃到的即隐含卖价，卖方吃到的即隐含买价。例如价差的隐含买价 = 近月买一 - 远月卖一，
+    /// 近月的隐含卖价 = 价差卖一 + 远月卖一，远月的隐含买价 = 近月买一 - 价差卖一
+    fn implied_offer(&self, symbol: SymbolId, action: OrderAction, top: impl Fn(SymbolId) -> TopOfBook) -> Option<ImpliedOffer> {
+        let legs = self.implied_legs(symbol, action)?;
+        let quote = |leg: ImpliedLeg| {
+            let (bid, ask) = top(leg.symbol);
+            match leg.action {
+                OrderAction::Bid => ask,
+                OrderAction::Ask => bid,
+            }
+        };
+        let ((first_price, first_size), (second_price, second_size)) = (quote(legs[0])?, quote(legs[1])?);
+        Some(ImpliedOffer {
+            price: legs[0].sign * first_price + legs[1].sign * second_price,
+            size: first_size.min(second_size),
+            legs: [(legs[0], first_price), (legs[1], second_price)],
+        })
+    }
+}
+
+/// 隐含报价（可选模块）：按价差组计算各交易对由组内其他订单簿推出的隐含最优价，变化时发布
+///
+/// 一个交易对属于多个价差组时取各组的最优价，同价数量累加；价差可以为零或负数，照常发布。
+/// 可成交的隐含流动性由 best_offer 给出，撮合见 MatchingEngineRouter
+#[derive(Default)]
+pub struct ImpliedPricer {
+    groups: Vec<SpreadGroup>,
+    last: AHashMap<SymbolId, BboUpdate>,
+}
+
+impl ImpliedPricer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_group(&mut self, group: SpreadGroup) {
+        if !self.groups.contains(&group) {
+            self.groups.push(group);
+        }
+    }
+
+    /// 交易对当前的隐含最优价（不属于任何价差组时返回 None）
+    pub fn quote(&self, symbol: SymbolId) -> Option<BboUpdate> {
+        self.last.get(&symbol).copied()
+    }
+
+    /// symbol 的订单簿可能发生变化后调用：重新计算与其同组的交易对，变化时写入 out
+    pub fn on_symbol(&mut self, symbol: SymbolId, books: &AHashMap<SymbolId, Box<dyn OrderBook>>, mut out: Option<&mut Vec<BboUpdate>>) {
+        let mut affected: SmallVec<[SymbolId; 6]> = SmallVec::new();
+        for group in self.groups.iter().filter(|group| group.contains(symbol)) {
+            for member in [group.spread, group.front, group.back] {
+                if !affected.contains(&member) {
+                    affected.push(member);
+                }
+            }
+        }
+        for member in affected {
+            let (bid, ask) = self.implied(member, books);
+            let (bid, bid_size) = bid.map_or((None, 0), |(price, size)| (Some(price), size));
+            let (ask, ask_size) = ask.map_or((None, 0), |(price, size)| (Some(price), size));
+            let last = self.last.entry(member).or_insert(BboUpdate { symbol: member, bid: None, bid_size: 0, ask: None, ask_size: 0, seq: 0 });
+            if (last.bid, last.bid_size, last.ask, last.ask_size) == (bid, bid_size, ask, ask_size) {
+                continue;
+            }
+            last.bid = bid;
+            last.bid_size = bid_size;
+            last.ask = ask;
+            last.ask_size = ask_size;
+            last.seq += 1;
+            if let Some(out) = out.as_deref_mut() {
+                out.push(*last);
+            }
+        }
+    }
+
+    /// symbol 上 action 方向的订单当前可吃到的最优隐含流动性（同价取先配置的价差组），ready 过滤可参与隐含成交的腿
+    pub fn best_offer(
+        &self,
+        symbol: SymbolId,
+        action: OrderAction,
+        books: &AHashMap<SymbolId, Box<dyn OrderBook>>,
+        ready: impl Fn(SymbolId) -> bool,
+    ) -> Option<ImpliedOffer> {
+        let top = |symbol| books.get(&symbol).map_or((None, None), |book| book.best_bid_offer());
+        self.tradable_groups(symbol, books)
+            .filter_map(|group| group.implied_offer(symbol, action, top))
+            .filter(|offer| offer.legs.iter().all(|(leg, _)| ready(leg.symbol)))
+            .reduce(|best, offer| {
+                let better = match action {
+                    OrderAction::Bid => offer.price < best.price,
+                    OrderAction::Ask => offer.price > best.price,
+                };
+                if better { offer } else { best }
+            })
+    }
+
+    /// symbol 所在、且组内订单簿齐全并可隐含成交的价差组
+    fn tradable_groups<'a>(
+        &'a self,
+        symbol: SymbolId,
+        books: &'a AHashMap<SymbolId, Box<dyn OrderBook>>,
+    ) -> impl Iterator<Item = &'a SpreadGroup> {
+        self.groups
+            .iter()
+            .filter(move |group| group.contains(symbol) && group.is_tradable(|symbol| books.get(&symbol).map(|book| book.get_symbol_spec())))
+    }
+
+    /// 合并 symbol 所在各价差组的隐含买一、卖一
+    fn implied(&self, symbol: SymbolId, books: &AHashMap<SymbolId, Box<dyn OrderBook>>) -> TopOfBook {
+        let top = |symbol| books.get(&symbol).map_or((None, None), |book| book.best_bid_offer());
+        let merge = |best: Option<(Price, Size)>, next: Option<(Price, Size)>, better: fn(Price, Price) -> bool| match (best, next) {
+            (_, None) => best,
+            (None, next) => next,
+            (Some((price, size)), Some((next_price, next_size))) if price == next_price => Some((price, size + next_size)),
+            (Some((price, _)), Some((next_price, _))) => if better(next_price, price) { next } else { best },
+        };
+
+        let (mut bid, mut ask) = (None, None);
+        for group in self.tradable_groups(symbol, books) {
+            let offer = |action| group.implied_offer(symbol, action, top).map(|offer| (offer.price, offer.size));
+            bid = merge(bid, offer(OrderAction::Ask), |a, b| a > b);
+            ask = merge(ask, offer(OrderAction::Bid), |a, b| a < b);
+        }
+        (bid, ask)
+    }
+}
@@ -0,0 +1,109 @@
+use crate::api::*;
+use std::collections::BTreeMap;
+
+/// 单个币种的对账差异
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyImbalance {
+    pub currency: Currency,
+    pub expected: i64, // 净入金（余额调整累计）
+    pub actual: i64,   // 余额 + 冻结 + 保证金 + 手续费等合计
+}
+
+/// 风控仍有冻结记录、但订单簿中已不存在的订单（撤单路径未经 R2 返还冻结）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StuckHold {
+    pub uid: UserId,
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub currency: Currency,
+    pub hold: i64,
+}
+
+/// 资金不变量校验失败
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("资金不变量校验失败: {imbalances:?}, 滞留冻结: {stuck_holds:?}")]
+pub struct InvariantViolation {
+    pub imbalances: Vec<CurrencyImbalance>,
+    pub stuck_holds: Vec<StuckHold>,
+}
+
+/// 按币种汇总的对账数据，由各风控分片累加
+#[derive(Debug, Clone, Default)]
+pub struct BalanceTotals {
+    deposits: BTreeMap<Currency, i64>,
+    accounted: BTreeMap<Currency, i64>,
+    stuck_holds: Vec<StuckHold>,
+}
+
+impl BalanceTotals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 累加净入金
+    pub fn add_deposit(&mut self, currency: Currency, amount: i64) {
+        *self.deposits.entry(currency).or_insert(0) += amount;
+    }
+
+    /// 累加系统内可追溯的资金（余额、冻结、保证金、手续费等）
+    pub fn add_accounted(&mut self, currency: Currency, amount: i64) {
+        *self.accounted.entry(currency).or_insert(0) += amount;
+    }
+
+    /// 记录滞留的挂单冻结
+    pub fn add_stuck_hold(&mut self, stuck: StuckHold) {
+        self.stuck_holds.push(stuck);
+    }
+
+    /// 逐币种比对，返回全部不一致的币种与滞留的冻结
+    pub fn check(&self) -> Result<(), InvariantViolation> {
+        let mut currencies: Vec<Currency> = self.deposits.keys().chain(self.accounted.keys()).copied().collect();
+        currencies.sort_unstable();
+        currencies.dedup();
+
+        let imbalances: Vec<CurrencyImbalance> = currencies
+            .into_iter()
+            .filter_map(|currency| {
+                let expected = self.deposits.get(&currency).copied().unwrap_or(0);
+                let actual = self.accounted.get(&currency).copied().unwrap_or(0);
+                (expected != actual).then_some(CurrencyImbalance { currency, expected, actual })
+            })
+            .collect();
+
+        if imbalances.is_empty() && self.stuck_holds.is_empty() {
+            Ok(())
+        } else {
+            let mut stuck_holds = self.stuck_holds.clone();
+            stuck_holds.sort_by_key(|stuck| (stuck.uid, stuck.order_id));
+            Err(InvariantViolation { imbalances, stuck_holds })
+        }
+    }
+}
+
+/// 订单簿内部不变量校验失败（debug-invariants 特性）
+#[cfg(feature = "debug-invariants")]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("订单簿 {symbol} 不变量校验失败: {reason}")]
+pub struct BookInvariantViolation {
+    pub symbol: SymbolId,
+    pub reason: String,
+}
+
+/// 条件不成立时返回订单簿不变量错误
+#[cfg(feature = "debug-invariants")]
+pub(crate) fn ensure_book(symbol: SymbolId, holds: bool, reason: impl FnOnce() -> String) -> Result<(), BookInvariantViolation> {
+    if holds {
+        Ok(())
+    } else {
+        Err(BookInvariantViolation { symbol, reason: reason() })
+    }
+}
+
+/// 两侧都有挂单时买一必须低于卖一
+#[cfg(feature = "debug-invariants")]
+pub(crate) fn ensure_not_crossed(symbol: SymbolId, best_bid: Option<Price>, best_ask: Option<Price>) -> Result<(), BookInvariantViolation> {
+    match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) => ensure_book(symbol, bid < ask, || format!("盘口交叉: 买一 {bid} >= 卖一 {ask}")),
+        _ => Ok(()),
+    }
+}
@@ -1,75 +1,685 @@
-use crate::api::OrderCommand;
-use std::fs::{File, OpenOptions};
+use crate::api::{ArchivedOrderCommand, CommandResultCode, MatcherTradeEvent, OrderCommand};
+use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write, BufWriter, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use anyhow::Result;
-use rkyv::Deserialize;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, CheckBytes, Deserialize};
+
+/// 刷盘 (fsync) 策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// 每条命令写入操作系统缓存，由操作系统决定落盘时机
+    Never,
+    /// 每条命令 fsync
+    Always,
+    /// 每 N 条命令批量写入并 fsync
+    EveryN(u64),
+    /// 距上次 fsync 超过指定时长后，在下一次写入时批量写入并 fsync
+    Interval(Duration),
+}
+
+/// 日志配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalConfig {
+    pub segment_max_bytes: u64,    // 单个分段最大字节数（0 表示不限制）
+    pub segment_max_commands: u64, // 单个分段最大命令数（0 表示不限制）
+    pub fsync_policy: FsyncPolicy,
+    pub truncate_corrupted_tail: bool, // 打开日志时截断最后分段中损坏的尾部（否则报错）
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            segment_max_bytes: 64 * 1024 * 1024,
+            segment_max_commands: 0,
+            fsync_policy: FsyncPolicy::Never,
+            truncate_corrupted_tail: false,
+        }
+    }
+}
+
+/// 日志损坏位置（首个无效记录）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalCorruption {
+    pub segment: PathBuf,
+    pub offset: u64, // 无效记录在分段内的起始偏移
+    pub reason: String,
+}
+
+impl std::fmt::Display for JournalCorruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "日志损坏: {} 偏移 {}: {}", self.segment.display(), self.offset, self.reason)
+    }
+}
+
+/// 容错读取结果：损坏位置之前的全部有效记录
+#[derive(Debug, Clone)]
+pub struct JournalScan<T = OrderCommand> {
+    pub records: Vec<(u64, T)>,
+    pub corruption: Option<JournalCorruption>,
+}
+
+/// 记录头：长度 (u32) + CRC32 (u32) + 序列号 (u64)
+const RECORD_HEADER_LEN: u64 = 16;
+
+/// CRC32 (IEEE 802.3) 查找表
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// 记录校验和：覆盖序列号与数据
+fn record_crc(seq: u64, data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in seq.to_le_bytes().iter().chain(data) {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// 回读的日志记录 (序列号, 命令)
+pub type JournalRecords = Box<dyn Iterator<Item = Result<(u64, OrderCommand)>>>;
+
+/// 命令日志输出端：按提交顺序追加命令并分配连续的序列号
+///
+/// 默认实现为本地文件日志 `Journaler`；网络、消息队列等输出端实现该 trait 后
+/// 通过 `ExchangeCore::enable_journal_sink` 接入
+pub trait JournalSink: Send {
+    /// 追加一条命令，返回分配的序列号；失败时序列号不前进
+    fn append(&mut self, cmd: &OrderCommand) -> Result<u64>;
+
+    /// 最后写入的序列号（空日志为 0）
+    fn last_seq(&self) -> u64;
+
+    /// 确保已追加的命令持久化
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 回读序列号大于 after_seq 的记录（故障恢复用），不支持回读的输出端返回错误
+    fn read_after(&self, after_seq: u64) -> Result<JournalRecords> {
+        let _ = after_seq;
+        anyhow::bail!("该日志输出端不支持回读")
+    }
+
+    /// 删除已被 snapshot_seq 快照覆盖的记录，返回删除的单元数（分段、分区等）
+    fn compact(&mut self, snapshot_seq: u64) -> Result<usize> {
+        let _ = snapshot_seq;
+        Ok(0)
+    }
+}
 
 /// 高性能预写日志 (WAL) 实现 - 使用 rkyv 零拷贝序列化
+///
+/// 记录格式：长度 (u32) + CRC32 (u32) + 序列号 (u64) + rkyv 数据，序列号从 1 开始连续递增。
+/// 日志按分段存储：`<path>.<首条序列号>`，写满后切换到新分段
 pub struct Journaler {
     writer: BufWriter<File>,
+    path: PathBuf,
+    config: JournalConfig,
+    last_seq: u64,
+    segment_bytes: u64,    // 当前分段已写入字节数
+    segment_commands: u64, // 当前分段已写入命令数
+    unsynced: u64,         // 上次 fsync 之后写入的命令数
+    last_sync: Instant,
+    truncated: Option<JournalCorruption>, // 打开时截断的损坏尾部
 }
 
 impl Journaler {
-    /// 创建或打开日志文件
+    /// 创建或打开日志（已有分段时从最后一条记录的序列号继续）
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_config(path, JournalConfig::default())
+    }
+
+    pub fn with_config<P: AsRef<Path>>(path: P, config: JournalConfig) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        // 只需读取最后一个分段即可确定序列号
+        let (first_seq, segment_path) = match Self::segments(&path)?.pop() {
+            Some(segment) => segment,
+            None => (1, Self::segment_path(&path, 1)),
+        };
+        let scan = Self::read_segment(&segment_path)?;
+        let truncated = match scan.corruption {
+            Some(corruption) if config.truncate_corrupted_tail => {
+                Self::truncate_at(&path, &corruption)?;
+                Some(corruption)
+            }
+            Some(corruption) => anyhow::bail!("{}", corruption),
+            None => None,
+        };
+        let records = scan.records;
+        let last_seq = records.last().map_or(first_seq - 1, |(seq, _)| *seq);
+
         let file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open(path)?;
-        
+            .open(&segment_path)?;
+        let segment_bytes = file.metadata()?.len();
+
         Ok(Self {
             writer: BufWriter::with_capacity(64 * 1024, file), // 64KB 缓冲
+            path,
+            config,
+            last_seq,
+            segment_bytes,
+            segment_commands: records.len() as u64,
+            unsynced: 0,
+            last_sync: Instant::now(),
+            truncated,
         })
     }
 
-    /// 写入命令到日志（使用 rkyv，比 bincode 快 2.5 倍）
-    pub fn write_command(&mut self, cmd: &OrderCommand) -> Result<()> {
+    /// 打开时被截断的损坏尾部（仅 truncate_corrupted_tail 开启时）
+    pub fn truncated_corruption(&self) -> Option<&JournalCorruption> {
+        self.truncated.as_ref()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 最后写入的序列号（空日志为 0）
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// 写入命令到日志（使用 rkyv，比 bincode 快 2.5 倍），返回分配的序列号
+    pub fn write_command(&mut self, cmd: &OrderCommand) -> Result<u64> {
         // rkyv 序列化
         let bytes = rkyv::to_bytes::<_, 256>(cmd)
             .map_err(|e| anyhow::anyhow!("rkyv 序列化失败: {}", e))?;
-        
-        // 写入长度前缀 (u32) + 数据
-        let len = bytes.len() as u32;
-        self.writer.write_all(&len.to_le_bytes())?;
-        self.writer.write_all(&bytes)?;
-        
-        // 批量刷盘（由 BufWriter 控制）
+
+        if self.segment_full() {
+            self.rotate()?;
+        }
+
+        let seq = self.last_seq + 1;
+        write_frame(&mut self.writer, seq, &bytes)?;
+
+        self.last_seq = seq;
+        self.segment_bytes += RECORD_HEADER_LEN + bytes.len() as u64;
+        self.segment_commands += 1;
+        self.unsynced += 1;
+
+        match self.config.fsync_policy {
+            FsyncPolicy::Never => self.writer.flush()?,
+            FsyncPolicy::Always => self.sync()?,
+            FsyncPolicy::EveryN(n) if self.unsynced >= n => self.sync()?,
+            FsyncPolicy::Interval(interval) if self.last_sync.elapsed() >= interval => self.sync()?,
+            _ => {}
+        }
+
+        Ok(seq)
+    }
+
+    /// 写入缓冲区并 fsync
+    pub fn sync(&mut self) -> Result<()> {
         self.writer.flush()?;
-        
+        self.writer.get_ref().sync_data()?;
+        self.unsynced = 0;
+        self.last_sync = Instant::now();
         Ok(())
     }
 
-    /// 从日志文件读取并重放所有命令
-    pub fn read_commands<P: AsRef<Path>>(path: P) -> Result<Vec<OrderCommand>> {
-        if !path.as_ref().exists() {
+    fn segment_full(&self) -> bool {
+        self.segment_commands > 0
+            && ((self.config.segment_max_bytes > 0 && self.segment_bytes >= self.config.segment_max_bytes)
+                || (self.config.segment_max_commands > 0 && self.segment_commands >= self.config.segment_max_commands))
+    }
+
+    /// 关闭当前分段并切换到以下一条序列号命名的新分段
+    fn rotate(&mut self) -> Result<()> {
+        self.sync()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::segment_path(&self.path, self.last_seq + 1))?;
+        self.writer = BufWriter::with_capacity(64 * 1024, file);
+        self.segment_bytes = 0;
+        self.segment_commands = 0;
+        Ok(())
+    }
+
+    /// 压缩：删除记录全部不晚于 snapshot_seq 的分段（当前写入的分段保留），返回删除的分段数
+    pub fn compact(&mut self, snapshot_seq: u64) -> Result<usize> {
+        let segments = Self::segments(&self.path)?;
+        let mut removed = 0;
+        // 下一分段的首条序列号 - 1 即本分段的最后一条序列号
+        for pair in segments.windows(2) {
+            if pair[1].0 - 1 > snapshot_seq {
+                break;
+            }
+            fs::remove_file(&pair[0].1)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// 列出日志的全部分段 (首条序列号, 文件路径)，按序列号排序
+    ///
+    /// `<path>` 本身存在时为分段之前的单文件日志（没有序列号与校验，命令格式也已变化），无法作为分段重放：
+    /// 返回错误而不是忽略其中的命令
+    pub fn segments<P: AsRef<Path>>(path: P) -> Result<Vec<(u64, PathBuf)>> {
+        let path = path.as_ref();
+        if path.is_file() {
+            anyhow::bail!(
+                "发现旧版单文件日志 {}：无法按分段格式重放，请先用旧版本恢复并生成快照，再移走该文件",
+                path.display()
+            );
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
             return Ok(Vec::new());
+        };
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let prefix = format!("{}.", file_name);
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(Ok(first_seq)) = name.strip_prefix(&prefix).map(str::parse::<u64>) {
+                segments.push((first_seq, entry.path()));
+            }
+        }
+
+        segments.sort_unstable_by_key(|(first_seq, _)| *first_seq);
+        Ok(segments)
+    }
+
+    /// 删除日志的全部分段
+    pub fn remove_all<P: AsRef<Path>>(path: P) -> Result<()> {
+        for (_, segment) in Self::segments(path)? {
+            fs::remove_file(segment)?;
         }
+        Ok(())
+    }
 
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        let mut commands = Vec::new();
+    fn segment_path(path: &Path, first_seq: u64) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(format!(".{:020}", first_seq));
+        PathBuf::from(name)
+    }
+
+    /// 从日志读取并重放所有命令
+    pub fn read_commands<P: AsRef<Path>>(path: P) -> Result<Vec<OrderCommand>> {
+        Ok(Self::read_records(path)?.into_iter().map(|(_, cmd)| cmd).collect())
+    }
+
+    /// 读取序列号大于 after_seq 的记录（快照之后的日志尾部），跳过整段早于 after_seq 的分段
+    pub fn read_commands_after<P: AsRef<Path>>(path: P, after_seq: u64) -> Result<Vec<(u64, OrderCommand)>> {
+        JournalReader::open_after(path, after_seq)?.collect()
+    }
+
+    /// 读取全部分段的记录 (序列号, 命令)，遇到损坏记录时报错
+    pub fn read_records<P: AsRef<Path>>(path: P) -> Result<Vec<(u64, OrderCommand)>> {
+        let scan = Self::scan(path)?;
+        match scan.corruption {
+            Some(corruption) => anyhow::bail!("{}", corruption),
+            None => Ok(scan.records),
+        }
+    }
 
-        loop {
-            let mut len_buf = [0u8; 4];
-            if reader.read_exact(&mut len_buf).is_err() {
-                break; // 到达文件末尾
+    /// 容错读取：返回首个损坏记录之前的全部有效记录及损坏位置
+    pub fn scan<P: AsRef<Path>>(path: P) -> Result<JournalScan> {
+        let mut result = JournalScan { records: Vec::new(), corruption: None };
+        for (_, segment) in Self::segments(path)? {
+            let scan = Self::read_segment(&segment)?;
+            result.records.extend(scan.records);
+            if scan.corruption.is_some() {
+                result.corruption = scan.corruption;
+                break;
             }
-            
-            let len = u32::from_le_bytes(len_buf) as usize;
-            let mut data = vec![0u8; len];
-            reader.read_exact(&mut data)?;
-            
-            // rkyv 反序列化（带校验）
-            let archived = rkyv::check_archived_root::<OrderCommand>(&data)
-                .map_err(|e| anyhow::anyhow!("rkyv 数据校验失败: {}", e))?;
-            
-            let cmd: OrderCommand = archived.deserialize(&mut rkyv::Infallible)
-                .map_err(|_| anyhow::anyhow!("rkyv 反序列化失败"))?;
-            
-            commands.push(cmd);
-        }
-
-        Ok(commands)
+        }
+        Ok(result)
+    }
+
+    /// 容错恢复：读取有效记录，truncate 为 true 时截断损坏位置之后的数据（含后续分段）
+    pub fn recover_records<P: AsRef<Path>>(path: P, truncate: bool) -> Result<JournalScan> {
+        let scan = Self::scan(&path)?;
+        if let (true, Some(corruption)) = (truncate, &scan.corruption) {
+            Self::truncate_at(path.as_ref(), corruption)?;
+        }
+        Ok(scan)
+    }
+
+    /// 从损坏位置截断所在分段，并删除之后的分段
+    fn truncate_at(path: &Path, corruption: &JournalCorruption) -> Result<()> {
+        OpenOptions::new().write(true).open(&corruption.segment)?.set_len(corruption.offset)?;
+
+        let mut after = false;
+        for (_, segment) in Self::segments(path)? {
+            if after {
+                fs::remove_file(&segment)?;
+            }
+            after |= segment == corruption.segment;
+        }
+        Ok(())
+    }
+
+    /// 读取单个分段，遇到不完整或校验失败的记录时停止
+    fn read_segment(path: &Path) -> Result<JournalScan> {
+        read_frames(path)
+    }
+}
+
+impl JournalSink for Journaler {
+    fn append(&mut self, cmd: &OrderCommand) -> Result<u64> {
+        self.write_command(cmd)
+    }
+
+    fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.sync()
+    }
+
+    fn read_after(&self, after_seq: u64) -> Result<JournalRecords> {
+        Ok(Box::new(JournalReader::open_after(&self.path, after_seq)?))
+    }
+
+    fn compact(&mut self, snapshot_seq: u64) -> Result<usize> {
+        Journaler::compact(self, snapshot_seq)
+    }
+}
+
+/// 流式日志读取：逐条读取各分段的记录，内存占用与日志大小无关
+///
+/// `next_archived` 直接访问缓冲区中的归档命令（零拷贝，下次读取前有效）；
+/// 迭代器接口按条反序列化为 OrderCommand。遇到损坏记录时返回错误并结束
+pub struct JournalReader {
+    segments: std::vec::IntoIter<(u64, PathBuf)>,
+    current: Option<FrameReader>,
+    after_seq: u64,
+    failed: bool, // 遇到损坏记录后不再读取
+}
+
+impl JournalReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_after(path, 0)
+    }
+
+    /// 只读取序列号大于 after_seq 的记录（快照之后的日志尾部），跳过整段早于 after_seq 的分段
+    pub fn open_after<P: AsRef<Path>>(path: P, after_seq: u64) -> Result<Self> {
+        let mut segments = Journaler::segments(path)?;
+        // 下一分段的首条序列号 - 1 即本分段的最后一条序列号
+        let skipped = segments.windows(2).take_while(|pair| pair[1].0 - 1 <= after_seq).count();
+        segments.drain(..skipped);
+        Ok(Self {
+            segments: segments.into_iter(),
+            current: None,
+            after_seq,
+            failed: false,
+        })
+    }
+
+    /// 读取下一条记录 (序列号, 归档命令)，日志结束时返回 None
+    pub fn next_archived(&mut self) -> Result<Option<(u64, &ArchivedOrderCommand)>> {
+        if self.failed {
+            return Ok(None);
+        }
+        let seq = loop {
+            let Some(reader) = &mut self.current else {
+                match self.segments.next() {
+                    Some((_, segment)) => self.current = Some(FrameReader::open(&segment)?),
+                    None => return Ok(None),
+                }
+                continue;
+            };
+            match reader.next_frame()? {
+                Frame::End => self.current = None,
+                Frame::Record(seq) if seq <= self.after_seq => {}
+                Frame::Record(seq) => break seq,
+                Frame::Corrupted(corruption) => {
+                    self.failed = true;
+                    anyhow::bail!("{}", corruption);
+                }
+            }
+        };
+
+        let reader = self.current.as_ref().expect("已读取记录");
+        match reader.archived::<OrderCommand>() {
+            Ok(archived) => Ok(Some((seq, archived))),
+            Err(corruption) => {
+                self.failed = true;
+                anyhow::bail!("{}", corruption)
+            }
+        }
+    }
+}
+
+impl Iterator for JournalReader {
+    type Item = Result<(u64, OrderCommand)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (seq, archived) = match self.next_archived() {
+            Ok(Some(record)) => record,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(
+            archived
+                .deserialize(&mut rkyv::Infallible)
+                .map(|cmd| (seq, cmd))
+                .map_err(|_| anyhow::anyhow!("rkyv 反序列化失败")),
+        )
+    }
+}
+
+/// 命令处理结果（审计日志记录），按序列号与命令日志对应
+#[derive(Debug, Clone, Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct CommandOutcome {
+    pub result_code: CommandResultCode,
+    pub events_group: u64, // 命令所在分组，快照只在分组边界生成
+    pub matcher_events: Vec<MatcherTradeEvent>,
+}
+
+impl CommandOutcome {
+    pub fn from_command(cmd: &OrderCommand) -> Self {
+        Self {
+            result_code: cmd.result_code,
+            events_group: cmd.events_group,
+            matcher_events: cmd.matcher_events.clone(),
+        }
+    }
+}
+
+/// 结果日志：记录每条命令的结果码与撮合事件，供审计时无需重放流水线即可还原成交
+pub struct ResultJournaler {
+    writer: BufWriter<File>,
+}
+
+impl ResultJournaler {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Self {
+            writer: BufWriter::with_capacity(64 * 1024, file), // 64KB 缓冲
+        })
+    }
+
+    /// 写入已处理命令的结果，seq 为该命令在命令日志中的序列号
+    pub fn write_outcome(&mut self, seq: u64, cmd: &OrderCommand) -> Result<()> {
+        let bytes = rkyv::to_bytes::<_, 256>(&CommandOutcome::from_command(cmd))
+            .map_err(|e| anyhow::anyhow!("rkyv 序列化失败: {}", e))?;
+        write_frame(&mut self.writer, seq, &bytes)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// 读取全部结果 (序列号, 结果)，遇到损坏记录时报错
+    pub fn read_outcomes<P: AsRef<Path>>(path: P) -> Result<Vec<(u64, CommandOutcome)>> {
+        let scan = read_frames(path.as_ref())?;
+        match scan.corruption {
+            Some(corruption) => anyhow::bail!("{}", corruption),
+            None => Ok(scan.records),
+        }
+    }
+
+    /// 审计：按序列号关联命令日志与结果日志，返回 (序列号, 命令, 结果)；没有结果的命令跳过
+    pub fn read_audit_trail<P: AsRef<Path>, Q: AsRef<Path>>(
+        journal_path: P,
+        results_path: Q,
+    ) -> Result<Vec<(u64, OrderCommand, CommandOutcome)>> {
+        let mut outcomes = Self::read_outcomes(results_path)?.into_iter().peekable();
+        let mut trail = Vec::new();
+        for (seq, cmd) in Journaler::read_records(journal_path)? {
+            while outcomes.next_if(|(outcome_seq, _)| *outcome_seq < seq).is_some() {}
+            if let Some((_, outcome)) = outcomes.next_if(|(outcome_seq, _)| *outcome_seq == seq) {
+                trail.push((seq, cmd, outcome));
+            }
+        }
+        Ok(trail)
+    }
+}
+
+/// 写入一条记录：长度 (u32) + CRC32 (u32) + 序列号 (u64) + 数据
+fn write_frame<W: Write>(writer: &mut W, seq: u64, data: &[u8]) -> Result<()> {
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(&record_crc(seq, data).to_le_bytes())?;
+    writer.write_all(&seq.to_le_bytes())?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+/// 单个日志文件的逐条记录读取，数据读入复用的对齐缓冲区
+struct FrameReader {
+    reader: BufReader<File>,
+    path: PathBuf,
+    offset: u64,        // 下一条记录的起始偏移
+    record_offset: u64, // 最近读取的记录的起始偏移
+    data: AlignedVec,
+}
+
+enum Frame {
+    End,
+    Record(u64),
+    Corrupted(JournalCorruption),
+}
+
+impl FrameReader {
+    fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(path)?),
+            path: path.to_path_buf(),
+            offset: 0,
+            record_offset: 0,
+            data: AlignedVec::new(),
+        })
+    }
+
+    /// 读取下一条记录到缓冲区，不完整或校验失败时返回损坏位置
+    fn next_frame(&mut self) -> Result<Frame> {
+        let mut header = [0u8; RECORD_HEADER_LEN as usize];
+        let read = read_full(&mut self.reader, &mut header)?;
+        if read == 0 {
+            return Ok(Frame::End);
+        }
+        if read < header.len() {
+            return Ok(Frame::Corrupted(self.corruption(self.offset, "记录头不完整")));
+        }
+
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let seq = u64::from_le_bytes(header[8..16].try_into().unwrap());
+
+        self.data.clear();
+        self.data.resize(len, 0);
+        if read_full(&mut self.reader, self.data.as_mut_slice())? < len {
+            return Ok(Frame::Corrupted(self.corruption(self.offset, "记录数据不完整")));
+        }
+        if record_crc(seq, &self.data) != crc {
+            return Ok(Frame::Corrupted(self.corruption(self.offset, "CRC 校验失败")));
+        }
+
+        self.record_offset = self.offset;
+        self.offset += RECORD_HEADER_LEN + len as u64;
+        Ok(Frame::Record(seq))
+    }
+
+    /// 校验并访问最近读取的记录（零拷贝）
+    fn archived<T>(&self) -> std::result::Result<&T::Archived, JournalCorruption>
+    where
+        T: Archive,
+        T::Archived: for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        rkyv::check_archived_root::<T>(&self.data)
+            .map_err(|e| self.corruption(self.record_offset, &format!("rkyv 数据校验失败: {}", e)))
+    }
+
+    fn corruption(&self, offset: u64, reason: &str) -> JournalCorruption {
+        JournalCorruption {
+            segment: self.path.clone(),
+            offset,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// 读取日志文件中的记录 (序列号, 数据)，遇到不完整或校验失败的记录时停止并返回损坏位置
+fn read_frames<T>(path: &Path) -> Result<JournalScan<T>>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, rkyv::Infallible>,
+{
+    let mut records = Vec::new();
+    if !path.exists() {
+        return Ok(JournalScan { records, corruption: None });
+    }
+
+    let mut reader = FrameReader::open(path)?;
+    loop {
+        let seq = match reader.next_frame()? {
+            Frame::End => break,
+            Frame::Corrupted(corruption) => return Ok(JournalScan { records, corruption: Some(corruption) }),
+            Frame::Record(seq) => seq,
+        };
+        // rkyv 反序列化（带校验）
+        let record: T = match reader.archived::<T>() {
+            Ok(archived) => archived.deserialize(&mut rkyv::Infallible)
+                .map_err(|_| anyhow::anyhow!("rkyv 反序列化失败"))?,
+            Err(corruption) => return Ok(JournalScan { records, corruption: Some(corruption) }),
+        };
+        records.push((seq, record));
+    }
+
+    Ok(JournalScan { records, corruption: None })
+}
+
+/// 尽量读满缓冲区，返回实际读取的字节数（小于缓冲区长度表示到达文件末尾）
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
     }
+    Ok(filled)
 }
@@ -0,0 +1,130 @@
+use crate::api::*;
+use crate::core::market_data::L2DeltaTracker;
+use crate::core::orderbook::OrderBook;
+
+/// 挂单离开订单簿的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderRemoval {
+    Filled,    // 全部成交
+    Cancelled, // 撤单、减量至 0、自成交预防撤销或剩余数量被拒绝
+    Expired,   // 到期
+    Purged,    // 批量撤单（暂停用户、下架交易对、期权到期、清空订单簿）
+}
+
+/// 订单簿监听器：撮合线程处理命令时同步回调，用于在撮合循环内做自定义统计或监控
+///
+/// 每个撮合分片持有各自的监听器，只收到本分片交易对的回调；回调耗时直接计入撮合延迟。
+/// 回调时事件尚未分配序号、成交编号与引擎时间，时间取 cmd.timestamp
+pub trait OrderBookListener: Send {
+    /// 成交，taker 由 event.taker(cmd) 确定（止损单激活产生的成交 taker 为被激活的订单）
+    fn on_trade(&mut self, _cmd: &OrderCommand, _event: &MatcherTradeEvent) {}
+
+    /// 订单进入订单簿（含未触发的止损单）
+    fn on_order_added(&mut self, _cmd: &OrderCommand, _order: &OpenOrder) {}
+
+    /// 订单离开订单簿，cmd.symbol 为所在交易对
+    fn on_order_removed(&mut self, _cmd: &OrderCommand, _uid: UserId, _order_id: OrderId, _reason: OrderRemoval) {}
+
+    /// 价格档位的显示数量变化（delta.volume 为变化后的数量）
+    fn on_level_changed(&mut self, _cmd: &OrderCommand, _delta: &L2Delta) {}
+}
+
+/// 撮合分片上注册的监听器，及其独立的档位跟踪
+#[derive(Default)]
+pub struct OrderBookListeners {
+    listeners: Vec<Box<dyn OrderBookListener>>,
+    levels: L2DeltaTracker,
+    deltas: Vec<L2Delta>,
+}
+
+impl OrderBookListeners {
+    pub fn add(&mut self, listener: Box<dyn OrderBookListener>) {
+        self.listeners.push(listener);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// 命令作用于订单簿后调用
+    ///
+    /// from 为本命令产生的第一个事件下标；before 为处理前的目标订单（cmd.order_id），
+    /// activated 为本命令激活的止损单，updates 为订单簿产生的档位变化
+    pub fn on_command(
+        &mut self,
+        book: &dyn OrderBook,
+        cmd: &OrderCommand,
+        from: usize,
+        before: Option<&OpenOrder>,
+        activated: &[OrderId],
+        updates: &[LevelUpdate],
+    ) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        let events = &cmd.matcher_events[from..];
+
+        for event in events.iter().filter(|event| event.event_type == MatcherEventType::Trade) {
+            self.listeners.iter_mut().for_each(|listener| listener.on_trade(cmd, event));
+        }
+        for event in events.iter().filter(|event| event.maker_completed) {
+            let reason = match event.event_type {
+                MatcherEventType::Trade => OrderRemoval::Filled,
+                MatcherEventType::Reject => OrderRemoval::Expired, // 挂单的拒绝事件只由过期产生（过期扫描或撮合时遇到已过期的挂单）
+                _ => OrderRemoval::Cancelled,
+            };
+            self.notify_removed(cmd, event.matched_order_uid, event.matched_order_id, reason);
+        }
+
+        // 目标订单与被激活的止损单：按处理前后是否在订单簿中判断进入或离开
+        let targets_order = matches!(
+            cmd.command,
+            OrderCommandType::PlaceOrder
+                | OrderCommandType::MoveOrder
+                | OrderCommandType::CancelOrder
+                | OrderCommandType::ReduceOrder
+                | OrderCommandType::AmendOrder
+        );
+        // 止损单处理前均在订单簿中，离开时 uid 取自其作为 taker 的事件
+        let target = targets_order.then_some((cmd.order_id, before.map(|order| order.uid)));
+        for (order_id, prior_uid) in target.into_iter().chain(activated.iter().map(|&order_id| (order_id, Some(0)))) {
+            match (prior_uid, book.get_open_order(order_id)) {
+                (None, Some(order)) => self.listeners.iter_mut().for_each(|listener| listener.on_order_added(cmd, &order)),
+                (Some(prior_uid), None) => {
+                    // 以该订单为 taker 的最后一个事件决定原因：成交为全部成交，其余（撤单、减量、拒绝）为撤销
+                    let last = events.iter().rev().find(|event| event.taker(cmd).1 == order_id);
+                    let uid = last.map_or(prior_uid, |event| event.taker(cmd).0);
+                    let reason = match last {
+                        Some(event) if event.event_type == MatcherEventType::Trade => OrderRemoval::Filled,
+                        _ => OrderRemoval::Cancelled,
+                    };
+                    self.notify_removed(cmd, uid, order_id, reason);
+                }
+                _ => {}
+            }
+        }
+
+        self.levels.on_level_updates(cmd.symbol, updates, &mut self.deltas);
+        for delta in self.deltas.drain(..) {
+            self.listeners.iter_mut().for_each(|listener| listener.on_level_changed(cmd, &delta));
+        }
+    }
+
+    /// 批量撤单撤销一笔挂单后调用，cancel 为针对该订单的撤单命令
+    pub fn on_purged(&mut self, cancel: &OrderCommand, order: &OpenOrder, updates: &[LevelUpdate]) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        self.notify_removed(cancel, order.uid, order.order_id, OrderRemoval::Purged);
+        self.levels.on_level_updates(cancel.symbol, updates, &mut self.deltas);
+        for delta in self.deltas.drain(..) {
+            self.listeners.iter_mut().for_each(|listener| listener.on_level_changed(cancel, &delta));
+        }
+    }
+
+    fn notify_removed(&mut self, cmd: &OrderCommand, uid: UserId, order_id: OrderId, reason: OrderRemoval) {
+        self.listeners
+            .iter_mut()
+            .for_each(|listener| listener.on_order_removed(cmd, uid, order_id, reason));
+    }
+}
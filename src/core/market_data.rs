@@ -0,0 +1,187 @@
+use crate::api::*;
+use crate::core::orderbook::OrderBook;
+use crate::core::processors::matching_engine::MatchingEngineRouter;
+use ahash::AHashMap;
+use std::sync::Arc;
+
+/// 单个交易对已发布的档位视图
+#[derive(Default)]
+struct SymbolLevels {
+    seq: u64,
+    asks: AHashMap<Price, Size>,
+    bids: AHashMap<Price, Size>,
+}
+
+/// L2 增量跟踪器
+///
+/// 以订单簿产生的档位变化（LevelUpdate）为来源，与已发布视图比较生成增量，避免重建整本深度。
+#[derive(Default)]
+pub struct L2DeltaTracker {
+    symbols: AHashMap<SymbolId, SymbolLevels>,
+}
+
+impl L2DeltaTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 订单簿取出档位变化后调用，与已发布视图比较确定增量类型
+    pub fn on_level_updates(&mut self, symbol: SymbolId, updates: &[LevelUpdate], out: &mut Vec<L2Delta>) {
+        if updates.is_empty() {
+            return;
+        }
+
+        let levels = self.symbols.entry(symbol).or_default();
+        for &LevelUpdate { action, price, volume } in updates {
+            let side = match action {
+                OrderAction::Ask => &mut levels.asks,
+                OrderAction::Bid => &mut levels.bids,
+            };
+
+            let kind = match side.get(&price).copied() {
+                None if volume == 0 => continue,
+                None => L2DeltaKind::Add,
+                Some(_) if volume == 0 => L2DeltaKind::Remove,
+                Some(old) if old == volume => continue,
+                Some(_) => L2DeltaKind::Update,
+            };
+
+            if kind == L2DeltaKind::Remove {
+                side.remove(&price);
+            } else {
+                side.insert(price, volume);
+            }
+
+            levels.seq += 1;
+            out.push(L2Delta {
+                seq: levels.seq,
+                symbol,
+                action,
+                kind,
+                price,
+                volume,
+            });
+        }
+    }
+
+    /// 当前交易对的最新增量序号
+    pub fn last_seq(&self, symbol: SymbolId) -> u64 {
+        self.symbols.get(&symbol).map_or(0, |levels| levels.seq)
+    }
+
+}
+
+/// BBO 变化跟踪器
+///
+/// 每条命令处理后读取订单簿的买一/卖一，与上次发布的值比较，价格或数量变化时生成 BboUpdate，消费者无需自行比对深度快照。
+#[derive(Default)]
+pub struct BboTracker {
+    last: AHashMap<SymbolId, BboUpdate>,
+}
+
+impl BboTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 订单簿可能发生变化后调用
+    pub fn on_command(&mut self, symbol: SymbolId, book: &dyn OrderBook, out: &mut Vec<BboUpdate>) {
+        let (bid, ask) = book.best_bid_offer();
+        let (bid, bid_size) = bid.map_or((None, 0), |(price, size)| (Some(price), size));
+        let (ask, ask_size) = ask.map_or((None, 0), |(price, size)| (Some(price), size));
+
+        let last = self.last.entry(symbol).or_insert(BboUpdate {
+            symbol,
+            bid: None,
+            bid_size: 0,
+            ask: None,
+            ask_size: 0,
+            seq: 0,
+        });
+        if (last.bid, last.bid_size, last.ask, last.ask_size) == (bid, bid_size, ask, ask_size) {
+            return;
+        }
+        last.bid = bid;
+        last.bid_size = bid_size;
+        last.ask = ask;
+        last.ask_size = ask_size;
+        last.seq += 1;
+        out.push(*last);
+    }
+
+    /// 当前交易对的最新 BBO 序号
+    pub fn last_seq(&self, symbol: SymbolId) -> u64 {
+        self.last.get(&symbol).map_or(0, |bbo| bbo.seq)
+    }
+}
+
+/// 行情消费者回调
+pub type MarketDataConsumer = Arc<dyn Fn(&MarketDataEvent) + Send + Sync>;
+
+/// 行情发布器
+///
+/// 从撮合结果中提取逐笔成交、L2 增量、BBO 与隐含报价的变化，分发给所有已注册的消费者，与 ResultConsumer 相互独立。
+#[derive(Default)]
+pub struct MarketDataPublisher {
+    consumers: Vec<MarketDataConsumer>,
+}
+
+impl MarketDataPublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_consumer(&mut self, consumer: MarketDataConsumer) {
+        self.consumers.push(consumer);
+    }
+
+    /// 命令处理完成后调用（撮合引擎需已开启 L2 增量与 BBO 跟踪），发布 events_from 之后的成交
+    ///
+    /// 各撮合分片分别发布时只发布本分片产生的事件（广播命令的事件可能来自多个分片）
+    pub fn on_command(&mut self, cmd: &OrderCommand, events_from: usize, engines: &mut [MatchingEngineRouter]) {
+        // 1. 逐笔成交
+        for (index, event) in cmd.matcher_events.iter().enumerate().skip(events_from) {
+            if event.event_type == MatcherEventType::Trade {
+                let (taker_uid, taker_order_id, taker_action) = event.taker(cmd);
+                self.dispatch(&MarketDataEvent::Trade(TradeTick {
+                    symbol: cmd.event_symbol(index),
+                    price: event.price,
+                    size: event.size,
+                    taker_action,
+                    taker_order_id,
+                    taker_uid,
+                    maker_order_id: event.matched_order_id,
+                    maker_uid: event.matched_order_uid,
+                    taker_client_order_id: if event.taker_action.is_some() {
+                        event.taker_client_order_id
+                    } else {
+                        cmd.client_order_id
+                    },
+                    maker_client_order_id: event.matched_client_order_id,
+                    trade_id: event.trade_id,
+                    sequence: event.sequence,
+                    timestamp: event.timestamp,
+                }));
+            }
+        }
+
+        // 2. L2 增量  3. BBO  4. 隐含报价（仅在变化时发布）
+        for engine in engines.iter_mut() {
+            for delta in engine.drain_l2_deltas() {
+                self.dispatch(&MarketDataEvent::L2(delta));
+            }
+            for bbo in engine.drain_bbo_updates() {
+                self.dispatch(&MarketDataEvent::Bbo(bbo));
+            }
+            for quote in engine.drain_implied_updates() {
+                self.dispatch(&MarketDataEvent::Implied(quote));
+            }
+        }
+    }
+
+    fn dispatch(&self, event: &MarketDataEvent) {
+        for consumer in &self.consumers {
+            consumer(event);
+        }
+    }
+}
@@ -1,7 +1,19 @@
 pub mod users;
 pub mod orderbook;
+pub mod market_data;
+pub mod implied;
+pub mod listener;
+pub mod surveillance;
+pub mod book_view;
+pub mod candles;
+pub mod invariants;
 pub mod processors;
 pub mod exchange;
 pub mod pipeline;
 pub mod journal;
 pub mod snapshot;
+pub mod replication;
+pub mod command_future;
+pub mod event_pool;
+pub mod clock;
+pub mod affinity;
@@ -5,12 +5,14 @@ pub mod naive;
 pub mod direct;
 pub mod direct_optimized;
 pub mod advanced;
+pub mod price_index;
 pub mod simd_utils;
 
 pub use naive::NaiveOrderBook;
 pub use direct::DirectOrderBook;
 pub use direct_optimized::DirectOrderBookOptimized;
 pub use advanced::AdvancedOrderBook;
+pub use price_index::{BTreePriceIndex, PriceBucket, PriceIndex, PriceLadder};
 
 #[derive(Serialize, Deserialize)]
 pub enum OrderBookState {
@@ -18,22 +20,271 @@ pub enum OrderBookState {
     Direct(DirectOrderBook),
     DirectOptimized(DirectOrderBookOptimized),
     Advanced(AdvancedOrderBook),
+    DirectOptimizedLadder(DirectOrderBookOptimized<PriceLadder>),
 }
 
+impl From<DirectOrderBookOptimized> for OrderBookState {
+    fn from(book: DirectOrderBookOptimized) -> Self {
+        Self::DirectOptimized(book)
+    }
+}
+
+impl From<DirectOrderBookOptimized<PriceLadder>> for OrderBookState {
+    fn from(book: DirectOrderBookOptimized<PriceLadder>) -> Self {
+        Self::DirectOptimizedLadder(book)
+    }
+}
+
+/// 按实现类型创建订单簿
+pub fn new_order_book(kind: OrderBookKind, spec: CoreSymbolSpecification) -> Box<dyn OrderBook> {
+    match kind {
+        OrderBookKind::Naive => Box::new(NaiveOrderBook::new(spec)),
+        OrderBookKind::Direct => Box::new(DirectOrderBook::new(spec)),
+        OrderBookKind::DirectOptimized => Box::new(DirectOrderBookOptimized::new(spec)),
+        OrderBookKind::Advanced => Box::new(AdvancedOrderBook::new(spec)),
+        OrderBookKind::DirectOptimizedLadder => Box::new(DirectOrderBookOptimized::<PriceLadder>::with_price_index(spec)),
+    }
+}
+
+impl OrderBookState {
+    /// 状态对应的订单簿实现
+    pub fn kind(&self) -> OrderBookKind {
+        match self {
+            Self::Naive(_) => OrderBookKind::Naive,
+            Self::Direct(_) => OrderBookKind::Direct,
+            Self::DirectOptimized(_) => OrderBookKind::DirectOptimized,
+            Self::Advanced(_) => OrderBookKind::Advanced,
+            Self::DirectOptimizedLadder(_) => OrderBookKind::DirectOptimizedLadder,
+        }
+    }
+}
+
+/// 有序档位价格的 (最低价, 最高价)，无档位时为 None
+pub(crate) fn price_range<'a>(mut prices: impl DoubleEndedIterator<Item = &'a Price>) -> Option<(Price, Price)> {
+    let low = *prices.next()?;
+    Some((low, prices.next_back().copied().unwrap_or(low)))
+}
+
+/// 订单簿记录的档位变化：档位数量变化前记录其原显示数量，取出时只输出显示数量确实变化的档位
+///
+/// 未开启时不记录，撮合热路径只多一次判断
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LevelChanges {
+    enabled: bool,
+    touched: Vec<(OrderAction, Price, Size)>, // (方向, 价格, 首次变化前的显示数量)
+}
+
+impl LevelChanges {
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// 已开启且尚未记录该档位
+    #[inline]
+    pub(crate) fn should_touch(&self, action: OrderAction, price: Price) -> bool {
+        self.enabled && !self.touched.iter().any(|&(a, p, _)| a == action && p == price)
+    }
+
+    /// 档位即将变化，volume 为当前显示数量（同一档位只记录首次）
+    #[inline]
+    pub(crate) fn touch(&mut self, action: OrderAction, price: Price, volume: Size) {
+        if self.should_touch(action, price) {
+            self.touched.push((action, price, volume));
+        }
+    }
+
+    /// 按首次变化的顺序输出显示数量变化的档位
+    pub(crate) fn drain(&mut self, book: &dyn OrderBook, out: &mut Vec<LevelUpdate>) {
+        for (action, price, before) in self.touched.drain(..) {
+            let volume = book.get_level_volume(action, price);
+            if volume != before {
+                out.push(LevelUpdate { action, price, volume });
+            }
+        }
+    }
+}
+
+/// IOC_BUDGET 买单的剩余预算（下单时 price 字段为可花费的总金额），逐档撮合时扣减
+///
+/// 每档成交数量不超过 剩余预算 / 价格，累计成交金额不会超过预算，也不会溢出
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BidBudget(i64);
+
+impl BidBudget {
+    pub(crate) fn new(budget: i64) -> Self {
+        Self(budget)
+    }
+
+    /// 价格 price 上剩余预算可买的数量，不超过 size（非正价格不消耗预算）
+    pub(crate) fn affordable(self, price: Price, size: Size) -> Size {
+        if price > 0 { size.min(self.0 / price) } else { size }
+    }
+
+    /// 扣减按 price 成交 matched 数量的金额
+    pub(crate) fn spend(&mut self, price: Price, matched: Size) {
+        self.0 = self.0.saturating_sub(matched.saturating_mul(price));
+    }
+}
+
+/// 买一、卖一的 (价格, 数量)，无挂单的一侧为 None
+pub type TopOfBook = (Option<(Price, Size)>, Option<(Price, Size)>);
+
 pub trait OrderBook: Send {
     fn new_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
     fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
     fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode;
+
+    /// 改单：cmd.price 为新价格（0 保持原价），cmd.size 为新的剩余数量；成功时先生成一条改单事件，
+    /// 重新排队的订单随后撮合产生的事件追加在其后
+    ///
+    /// 价格不变且只减少数量时原地减量、保留时间优先级；否则撤销后按新价格与数量重新下单（保留订单号、类型与冻结价）
+    fn amend_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(order) = self.get_open_order(cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        if order.uid != cmd.uid {
+            return CommandResultCode::MatchingUnauthorizedAction;
+        }
+        let price = if cmd.price == 0 { order.price } else { cmd.price };
+        // 现货买单新价格不能超过冻结价格
+        if self.get_symbol_spec().symbol_type == SymbolType::CurrencyExchangePair
+            && order.action == OrderAction::Bid
+            && price > order.reserve_price
+        {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        let amend = MatcherTradeEvent::new_amend((order.remaining - cmd.size).max(0), price, order.reserve_price, cmd.size);
+        let request = |command, size| OrderCommand { command, uid: order.uid, order_id: order.order_id, symbol: order.symbol, size, ..Default::default() };
+        cmd.action = order.action;
+        if price == order.price && cmd.size <= order.remaining {
+            if cmd.size < order.remaining {
+                self.reduce_order(&mut request(OrderCommandType::ReduceOrder, order.remaining - cmd.size));
+            }
+            cmd.matcher_events.push(amend);
+            return CommandResultCode::Success;
+        }
+
+        self.cancel_order(&mut request(OrderCommandType::CancelOrder, 0));
+        cmd.matcher_events.push(amend);
+        let mut place = OrderCommand {
+            price,
+            reserve_price: order.reserve_price,
+            action: order.action,
+            order_type: order.order_type,
+            timestamp: cmd.timestamp,
+            client_order_id: order.client_order_id,
+            stp_mode: cmd.stp_mode,
+            ..request(OrderCommandType::PlaceOrder, cmd.size)
+        };
+        self.new_order(&mut place);
+        cmd.matcher_events.append(&mut place.matcher_events);
+        CommandResultCode::Success
+    }
+
+    /// 移除 now 时刻已过期的挂单（Day/GTD），为每笔过期订单生成拒绝事件以释放冻结资金
+    ///
+    /// 返回过期订单数量；不支持过期时间的订单簿不做处理
+    fn expire_orders(&mut self, _now: i64, _events: &mut Vec<MatcherTradeEvent>) -> usize {
+        0
+    }
+
+    /// 更新外部参考价格（标记价/指数价），并激活以该价格为触发来源且达到触发价的止损单
+    ///
+    /// 不支持止损单的订单簿不做处理
+    fn update_reference_price(&mut self, _source: StopTriggerSource, _price: Price, _cmd: &mut OrderCommand) {}
+
+    /// 激活生效时间不晚于 cmd.timestamp 的 GoodAfterTime 订单，激活产生的事件以被激活的订单为 taker 追加到 cmd 中
+    ///
+    /// 不支持 GoodAfterTime 的订单簿不做处理
+    fn activate_pending_orders(&mut self, _cmd: &mut OrderCommand) {}
+
+    /// 开启档位变化记录：此后修改订单簿时记录显示数量变化的档位，由 take_level_updates 取出
+    fn enable_level_updates(&mut self);
+
+    /// 取出上次调用以来显示数量变化的档位（按首次变化的顺序），追加到 out；未开启时不输出
+    fn take_level_updates(&mut self, out: &mut Vec<LevelUpdate>);
+
+    /// 取出上一条命令中被激活的止损单与 GoodAfterTime 订单号（激活产生的事件已追加到该命令中），不支持的订单簿始终为空
+    fn take_activated_stops(&mut self) -> Vec<OrderId> {
+        Vec::new()
+    }
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification;
     fn get_l2_data(&self, depth: usize) -> L2MarketData;
+    /// 买一、卖一的 (价格, 显示数量)，用于 BBO 变化检测
+    fn best_bid_offer(&self) -> TopOfBook;
+    /// 预估 action 方向的市价单成交 size 数量的均价、最差价与可成交数量（含冰山单隐藏数量），不修改订单簿
+    fn quote_for_size(&self, action: OrderAction, size: Size) -> SizeQuote;
+    /// L3 逐笔深度：每侧最多 depth 档，每档最多 max_orders_per_level 笔，mask_uid 时隐藏用户 ID
+    fn get_l3_data(&self, depth: usize, max_orders_per_level: usize, mask_uid: bool) -> L3MarketData;
     
     // 查询方法
     fn get_order_by_id(&self, order_id: OrderId) -> Option<(Price, OrderAction)>;
+    /// 挂单详情（含剩余数量与订单类型）
+    fn get_open_order(&self, order_id: OrderId) -> Option<OpenOrder>;
+    /// 全部挂单（对账用，顺序不保证）
+    fn get_all_orders(&self) -> Vec<OpenOrder>;
     fn get_total_ask_volume(&self) -> Size;
     fn get_total_bid_volume(&self) -> Size;
     fn get_ask_buckets_count(&self) -> usize;
     fn get_bid_buckets_count(&self) -> usize;
+    /// 指定方向、价格档位的（显示）挂单量，档位不存在时为 0
+    fn get_level_volume(&self, action: OrderAction, price: Price) -> Size;
+    /// 挂单数、档位数、总量/显示量、价格区间、订单池占用与最近成交
+    fn stats(&self) -> OrderBookStats;
+
+    /// 从最优价起逐档访问 side 一侧的 (价格, 显示数量)，visitor 返回 false 时停止
+    fn visit_levels(&self, side: OrderAction, visitor: &mut dyn FnMut(Price, Size) -> bool);
+
+    /// 前 levels 档的显示量合计，O(levels)
+    fn depth_volume(&self, side: OrderAction, levels: usize) -> Size {
+        let (mut volume, mut remaining) = (0, levels);
+        self.visit_levels(side, &mut |_, size| {
+            if remaining == 0 {
+                return false;
+            }
+            remaining -= 1;
+            volume += size;
+            true
+        });
+        volume
+    }
+
+    /// 距中间价 bps 基点以内的显示量合计（含边界），单边无挂单时以另一侧最优价为参考；O(区间内档位数)
+    fn volume_within_bps(&self, side: OrderAction, bps: i64) -> Size {
+        let (bid, ask) = self.best_bid_offer();
+        // 以 2 倍中间价计算，避免取整
+        let mid2 = match (bid, ask) {
+            (Some((bid, _)), Some((ask, _))) => bid as i128 + ask as i128,
+            (Some((price, _)), None) | (None, Some((price, _))) => 2 * price as i128,
+            (None, None) => return 0,
+        };
+        let mut volume = 0;
+        self.visit_levels(side, &mut |price, size| {
+            let within = match side {
+                OrderAction::Ask => price as i128 * 20_000 <= mid2 * (10_000 + bps as i128),
+                OrderAction::Bid => price as i128 * 20_000 >= mid2 * (10_000 - bps as i128),
+            };
+            if within {
+                volume += size;
+            }
+            within
+        });
+        volume
+    }
+
+    /// 前 levels 档的买卖失衡度 (买量 - 卖量) / (买量 + 卖量)，取值 [-1, 1]，两侧都无挂单时为 None
+    fn imbalance(&self, levels: usize) -> Option<f64> {
+        let bid = self.depth_volume(OrderAction::Bid, levels);
+        let ask = self.depth_volume(OrderAction::Ask, levels);
+        let total = bid + ask;
+        (total > 0).then(|| (bid - ask) as f64 / total as f64)
+    }
+
+    /// 校验内部不变量：两侧都有挂单时买一低于卖一、档位数量等于档内订单剩余数量之和、订单索引只指向有效挂单
+    #[cfg(feature = "debug-invariants")]
+    fn check_invariants(&self) -> Result<(), crate::core::invariants::BookInvariantViolation>;
 
     // 序列化支持
     fn serialize_state(&self) -> OrderBookState;
@@ -1,4 +1,5 @@
 use crate::api::*;
+use crate::core::orderbook::LevelChanges;
 use ahash::AHashMap;
 use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
@@ -16,12 +17,82 @@ struct AdvancedOrder {
     order_type: OrderType,
     reserve_price: Price,
     timestamp: i64,
+    client_order_id: u64,
     
     // 扩展字段
     stop_price: Option<Price>,      // 止损触发价
+    stop_trigger: StopTriggerSource, // 止损触发价格来源
     visible_size: Option<Size>,     // 冰山单显示数量
+    replenish_size: Option<Size>,   // 冰山单每次刷新显示数量（默认同 visible_size）
+    display_remaining: Size,        // 冰山单当前显示切片剩余数量
     expire_time: Option<i64>,       // 过期时间
     is_triggered: bool,             // 止损单是否已触发
+    #[serde(default)]
+    hidden: bool,                   // 隐藏单（显示数量为 0）
+}
+
+impl AdvancedOrder {
+    #[inline]
+    fn remaining(&self) -> Size {
+        self.size - self.filled
+    }
+
+    /// 当前显示数量（冰山单为当前切片，隐藏单为 0）
+    #[inline]
+    fn displayed(&self) -> Size {
+        if self.hidden {
+            0
+        } else if self.visible_size.is_some() {
+            self.display_remaining.min(self.remaining())
+        } else {
+            self.remaining()
+        }
+    }
+
+    /// 冰山单刷新：显示下一切片
+    #[inline]
+    fn replenish(&mut self) {
+        let clip = self.replenish_size.or(self.visible_size).unwrap_or(0);
+        self.display_remaining = clip.min(self.remaining());
+    }
+}
+
+/// 同价位撮合参数（来自 taker 与交易对规格）
+#[derive(Debug, Clone, Copy)]
+struct MatchContext {
+    taker_uid: UserId,
+    stp_mode: StpMode,
+    current_time: i64,
+    hidden_yield: bool,
+    algorithm: MatchingAlgorithm,
+    rounding: ProRataRounding,
+}
+
+/// 按比例分配 quantity（不超过可成交总量）：各订单按可成交数量的比例向下取整，取整剩余按 rounding 分配
+fn pro_rata(available: &[Size], quantity: Size, rounding: ProRataRounding) -> SmallVec<[Size; 8]> {
+    let total = available.iter().sum::<Size>().max(1) as i128;
+    let quantity = quantity.min(available.iter().sum());
+    let shares: SmallVec<[i128; 8]> = available.iter().map(|&size| quantity as i128 * size as i128).collect();
+    let mut allocations: SmallVec<[Size; 8]> = shares.iter().map(|&share| (share / total) as Size).collect();
+    let mut residual = quantity - allocations.iter().sum::<Size>();
+    match rounding {
+        ProRataRounding::TimePriority => {
+            for (allocation, &size) in allocations.iter_mut().zip(available) {
+                let extra = residual.min(size - *allocation);
+                *allocation += extra;
+                residual -= extra;
+            }
+        }
+        ProRataRounding::LargestRemainder => {
+            // 剩余数量不超过余数非零的订单数，这些订单向下取整后均低于可成交数量，各补 1 不会超出
+            let mut order: SmallVec<[usize; 8]> = (0..available.len()).collect();
+            order.sort_by_key(|&i| (std::cmp::Reverse(shares[i] % total), i));
+            for i in order.into_iter().take(residual as usize) {
+                allocations[i] += 1;
+            }
+        }
+    }
+    allocations
 }
 
 /// 价格档位（支持冰山单）
@@ -43,91 +114,253 @@ impl AdvancedBucket {
         }
     }
 
-    fn add(&mut self, order: AdvancedOrder) {
-        let remaining = order.size - order.filled;
-        self.total_volume += remaining;
-        
+    fn add(&mut self, mut order: AdvancedOrder, hidden_yield: bool) {
         // 冰山单只显示部分数量
         if let Some(visible) = order.visible_size {
-            self.visible_volume += visible.min(remaining);
-        } else {
-            self.visible_volume += remaining;
+            order.display_remaining = visible.min(order.remaining());
         }
-        
-        self.orders.push(order);
+
+        self.total_volume += order.remaining();
+        self.visible_volume += order.displayed();
+        self.enqueue(order, hidden_yield);
+    }
+
+    /// 排到队尾；隐藏单让位时显示订单排在所有隐藏单之前
+    fn enqueue(&mut self, order: AdvancedOrder, hidden_yield: bool) {
+        let position = if hidden_yield && !order.hidden {
+            self.orders.iter().position(|o| o.hidden).unwrap_or(self.orders.len())
+        } else {
+            self.orders.len()
+        };
+        self.orders.insert(position, order);
     }
 
     fn remove(&mut self, order_id: OrderId) -> Option<AdvancedOrder> {
         if let Some(pos) = self.orders.iter().position(|o| o.order_id == order_id) {
             let order = self.orders.remove(pos);
-            let remaining = order.size - order.filled;
-            self.total_volume -= remaining;
-            
-            if let Some(visible) = order.visible_size {
-                self.visible_volume -= visible.min(remaining);
-            } else {
-                self.visible_volume -= remaining;
-            }
-            
+            self.total_volume -= order.remaining();
+            self.visible_volume -= order.displayed();
             Some(order)
         } else {
             None
         }
     }
 
-    /// 撮合订单（支持冰山单）
-    fn match_order(&mut self, taker_size: Size, _taker_uid: UserId, current_time: i64) 
-        -> (Size, SmallVec<[MatcherTradeEvent; 4]>) 
-    {
-        let mut matched_size = 0;
+    /// L3 档位快照（冰山单只展示当前切片，不展示隐藏单）
+    fn to_l3_level(&self, max_orders: usize, mask_uid: bool) -> L3Level {
+        L3Level {
+            price: self.price,
+            volume: self.visible_volume,
+            order_count: self.orders.iter().filter(|o| !o.hidden).count(),
+            orders: self
+                .orders
+                .iter()
+                .filter(|o| !o.hidden)
+                .take(max_orders)
+                .map(|o| L3Order::new(o.order_id, o.uid, o.price, o.displayed(), o.timestamp, mask_uid))
+                .collect(),
+        }
+    }
+
+    /// 移除已过期订单，为每笔订单生成 maker 拒绝事件
+    fn remove_expired(&mut self, now: i64, events: &mut Vec<MatcherTradeEvent>) -> usize {
+        let price = self.price;
+        let total_volume = &mut self.total_volume;
+        let visible_volume = &mut self.visible_volume;
+        let before = self.orders.len();
+
+        self.orders.retain(|order| {
+            if order.expire_time.is_none_or(|expire| now <= expire) {
+                return true;
+            }
+
+            let remaining = order.remaining();
+            *total_volume -= remaining;
+            *visible_volume -= order.displayed();
+
+            events.push(MatcherTradeEvent::new_maker_reject(
+                remaining,
+                price,
+                order.order_id,
+                order.uid,
+                order.action,
+                order.reserve_price,
+            ).with_client_order_id(order.client_order_id));
+            false
+        });
+
+        before - self.orders.len()
+    }
+
+    /// 撮合订单（支持冰山单、自成交预防与按比例分配）
+    ///
+    /// FIFO 按队列顺序逐笔成交；按比例分配时每轮先按各订单当前可成交数量（冰山单为当前切片）计算成交上限，
+    /// 再按队列顺序成交，冰山单刷新后的切片参与下一轮分配，直到 taker 成交完毕或本档没有可成交的订单。
+    /// 返回 (成交量, taker 因 STP 被撤销的数量, 事件)
+    fn match_order(&mut self, taker_size: Size, ctx: &MatchContext) -> (Size, Size, SmallVec<[MatcherTradeEvent; 4]>) {
         let mut events = SmallVec::new();
+        let (mut matched_size, mut taker_cancelled) = (0, 0);
+        loop {
+            let taker_remaining = taker_size - matched_size - taker_cancelled;
+            let mut caps = self.allocate(taker_remaining, ctx);
+            let (matched, cancelled) = self.match_pass(taker_remaining, ctx, caps.as_deref_mut(), &mut events);
+            matched_size += matched;
+            taker_cancelled += cancelled;
+            if caps.is_none() || matched + cancelled == 0 || matched_size + taker_cancelled >= taker_size {
+                return (matched_size, taker_cancelled, events);
+            }
+        }
+    }
+
+    /// 按比例分配时本档各订单的成交上限（按队列顺序），FIFO 不限制
+    ///
+    /// 已过期、与 taker 自成交（启用 STP 时）的订单不参与分配
+    fn allocate(&self, quantity: Size, ctx: &MatchContext) -> Option<SmallVec<[(OrderId, Size); 8]>> {
+        if ctx.algorithm == MatchingAlgorithm::Fifo {
+            return None;
+        }
+        let eligible: SmallVec<[(OrderId, Size); 8]> = self
+            .orders
+            .iter()
+            .filter(|o| o.expire_time.is_none_or(|expire| ctx.current_time <= expire))
+            .filter(|o| ctx.stp_mode == StpMode::None || o.uid != ctx.taker_uid)
+            .map(|o| (o.order_id, if o.visible_size.is_some() { o.displayed() } else { o.remaining() }))
+            .filter(|&(_, size)| size > 0)
+            .collect();
+        let available: SmallVec<[Size; 8]> = eligible.iter().map(|&(_, size)| size).collect();
+        let allocations = match ctx.algorithm {
+            MatchingAlgorithm::FifoProRata if !available.is_empty() => {
+                let top = quantity.min(available[0]);
+                let mut allocations: SmallVec<[Size; 8]> = SmallVec::new();
+                allocations.push(top);
+                allocations.extend(pro_rata(&available[1..], quantity - top, ctx.rounding));
+                allocations
+            }
+            _ => pro_rata(&available, quantity, ctx.rounding),
+        };
+        Some(eligible.iter().zip(allocations).map(|(&(order_id, _), allocation)| (order_id, allocation)).collect())
+    }
+
+    /// 按队列顺序撮合一轮，caps 为各订单的成交上限（None 不限制），返回 (成交量, taker 因 STP 被撤销的数量)
+    fn match_pass(&mut self, taker_size: Size, ctx: &MatchContext, mut caps: Option<&mut [(OrderId, Size)]>, events: &mut SmallVec<[MatcherTradeEvent; 4]>) -> (Size, Size) {
+        let MatchContext { taker_uid, stp_mode, current_time, hidden_yield, .. } = *ctx;
+        let mut matched_size = 0;
+        let mut taker_cancelled = 0;
         let mut to_remove = SmallVec::<[OrderId; 4]>::new();
 
-        for order in &mut self.orders {
-            // 检查订单是否过期
-            if let Some(expire) = order.expire_time {
-                if current_time > expire {
-                    to_remove.push(order.order_id);
-                    continue;
+        let mut i = 0;
+        while i < self.orders.len() && matched_size + taker_cancelled < taker_size {
+            let order = &mut self.orders[i];
+
+            // 已过期但尚未被过期扫描移除的订单：移出本档并以拒绝事件返还冻结
+            if order.expire_time.is_some_and(|expire| current_time > expire) {
+                self.total_volume -= order.remaining();
+                self.visible_volume -= order.displayed();
+                events.push(MatcherTradeEvent::new_maker_reject(
+                    order.remaining(),
+                    self.price,
+                    order.order_id,
+                    order.uid,
+                    order.action,
+                    order.reserve_price,
+                ).with_client_order_id(order.client_order_id));
+                to_remove.push(order.order_id);
+                i += 1;
+                continue;
+            }
+
+            let remaining = order.remaining();
+            if remaining == 0 {
+                i += 1;
+                continue;
+            }
+            let old_visible = order.displayed();
+
+            // 自成交预防
+            if stp_mode != StpMode::None && order.uid == taker_uid {
+                let taker_remaining = taker_size - matched_size - taker_cancelled;
+                let (maker_cancel, taker_cancel) = stp_mode.cancel_sizes(remaining, taker_remaining);
+
+                if maker_cancel > 0 {
+                    order.size -= maker_cancel;
+                    self.total_volume -= maker_cancel;
+                    self.visible_volume = self.visible_volume - old_visible + order.displayed();
+
+                    events.push(MatcherTradeEvent::new_maker_reduce(
+                        maker_cancel,
+                        self.price,
+                        order.order_id,
+                        order.uid,
+                        order.action,
+                        order.reserve_price,
+                        order.remaining(),
+                    ).with_client_order_id(order.client_order_id));
+
+                    if order.remaining() == 0 {
+                        to_remove.push(order.order_id);
+                    }
                 }
+
+                taker_cancelled += taker_cancel;
+                i += 1;
+                continue;
             }
 
-            let remaining = order.size - order.filled;
-            let match_size = remaining.min(taker_size - matched_size);
+            // 冰山单只能成交当前显示切片
+            let available = if order.visible_size.is_some() { old_visible } else { remaining };
+            let cap = caps.as_deref_mut().and_then(|caps| caps.iter_mut().find(|(order_id, _)| *order_id == order.order_id));
+            let match_size = available.min(taker_size - matched_size - taker_cancelled).min(cap.as_ref().map_or(Size::MAX, |(_, cap)| *cap));
+            if match_size == 0 {
+                i += 1;
+                continue;
+            }
+            if let Some((_, cap)) = cap {
+                *cap -= match_size;
+            }
 
-            if match_size > 0 {
-                order.filled += match_size;
-                matched_size += match_size;
-                
-                // 更新总量
-                self.total_volume -= match_size;
-                
-                // 更新显示量（冰山单特殊处理）
-                if let Some(visible) = order.visible_size {
-                    let old_visible = visible.min(remaining);
-                    let new_remaining = order.size - order.filled;
-                    let new_visible = visible.min(new_remaining);
-                    self.visible_volume = self.visible_volume.saturating_sub(old_visible) + new_visible;
-                } else {
-                    self.visible_volume -= match_size;
-                }
+            order.filled += match_size;
+            matched_size += match_size;
+            self.total_volume -= match_size;
 
-                events.push(MatcherTradeEvent::new_trade(
+            // 同一 maker 连续成交（冰山刷新后再次成交）合并为一个事件
+            let maker_completed = order.filled >= order.size;
+            match events.last_mut() {
+                Some(last) if last.event_type == MatcherEventType::Trade && last.matched_order_id == order.order_id => {
+                    last.size += match_size;
+                    last.maker_completed = maker_completed;
+                }
+                _ => events.push(MatcherTradeEvent::new_trade(
                     match_size,
                     self.price,
                     order.order_id,
                     order.uid,
                     order.reserve_price,
-                ));
+                    maker_completed,
+                ).with_client_order_id(order.client_order_id)),
+            }
 
-                if order.filled >= order.size {
-                    to_remove.push(order.order_id);
-                }
+            if order.filled >= order.size {
+                self.visible_volume -= old_visible;
+                to_remove.push(order.order_id);
+                i += 1;
+                continue;
+            }
 
-                if matched_size >= taker_size {
-                    break;
+            if order.visible_size.is_some() {
+                order.display_remaining -= match_size;
+                if order.display_remaining == 0 {
+                    // 切片耗尽：刷新并移到队尾
+                    order.replenish();
+                    order.timestamp = current_time;
+                    self.visible_volume = self.visible_volume - old_visible + order.displayed();
+                    let order = self.orders.remove(i);
+                    self.enqueue(order, hidden_yield);
+                    continue;
                 }
             }
+
+            self.visible_volume = self.visible_volume - old_visible + self.orders[i].displayed();
+            i += 1;
         }
 
         // 移除完成的订单（不更新总量，已在上面更新）
@@ -137,7 +370,7 @@ impl AdvancedBucket {
             }
         }
 
-        (matched_size, events)
+        (matched_size, taker_cancelled)
     }
 }
 
@@ -149,17 +382,30 @@ pub struct AdvancedOrderBook {
     // 活跃订单
     ask_buckets: BTreeMap<Price, AdvancedBucket>,
     bid_buckets: BTreeMap<Price, AdvancedBucket>,
-    order_map: AHashMap<OrderId, (Price, OrderAction)>,
+    order_map: AHashMap<OrderId, (Price, OrderAction, UserId)>, // 价格、方向、所属用户
     
     // 止损单池（未触发）
     stop_orders: Vec<AdvancedOrder>,
+
+    // GoodAfterTime 待生效订单：按 (生效时间, 订单号) 排列，保存原下单命令
+    #[serde(default)]
+    pending_orders: BTreeMap<(i64, OrderId), OrderCommand>,
     
-    // 最新成交价（用于触发止损单）
-    last_trade_price: Option<Price>,
+    // 最新成交价、外部推送的标记价与指数价（按止损单的触发来源选用）
+    last_trade: Option<LastTrade>,
+    mark_price: Option<Price>,
+    index_price: Option<Price>,
+
+    // 当前命令中被激活的止损单（由撮合引擎取走，不持久化）
+    #[serde(skip)]
+    activated_stops: Vec<OrderId>,
     
     // 最优价格缓存
     best_ask_price: Option<Price>,
     best_bid_price: Option<Price>,
+
+    #[serde(skip)]
+    level_changes: LevelChanges,
 }
 
 impl AdvancedOrderBook {
@@ -170,9 +416,23 @@ impl AdvancedOrderBook {
             bid_buckets: BTreeMap::new(),
             order_map: AHashMap::with_capacity(1024),
             stop_orders: Vec::new(),
-            last_trade_price: None,
+            pending_orders: BTreeMap::new(),
+            last_trade: None,
+            mark_price: None,
+            index_price: None,
+            activated_stops: Vec::new(),
             best_ask_price: None,
             best_bid_price: None,
+            level_changes: LevelChanges::default(),
+        }
+    }
+
+    /// 档位数量即将变化：开启档位变化记录时记录其原显示数量
+    #[inline]
+    fn touch_level(&mut self, action: OrderAction, price: Price) {
+        if self.level_changes.should_touch(action, price) {
+            let volume = super::OrderBook::get_level_volume(self, action, price);
+            self.level_changes.touch(action, price, volume);
         }
     }
 
@@ -202,37 +462,85 @@ impl AdvancedOrderBook {
         }
     }
 
-    /// Post-Only 检查
-    fn check_post_only(&self, cmd: &OrderCommand) -> CommandResultCode {
-        if self.would_match(cmd) {
-            CommandResultCode::MatchingUnsupportedCommand // Post-Only 拒绝
-        } else {
-            CommandResultCode::ValidForMatchingEngine
+    /// Post-Only 检查：会立即成交时拒绝；开启滑价时改为对手方最优价内侧一个最小变动单位
+    ///
+    /// 买单只会降价，不超过冻结价格
+    fn check_post_only(&self, cmd: &mut OrderCommand) -> CommandResultCode {
+        if !self.would_match(cmd) {
+            return CommandResultCode::ValidForMatchingEngine;
         }
+        if cmd.post_only_slide {
+            let tick = self.symbol_spec.tick_size.max(1);
+            let slide_price = match cmd.action {
+                OrderAction::Bid => self.best_ask_price.map(|ask| ask - tick),
+                OrderAction::Ask => self.best_bid_price.map(|bid| bid + tick),
+            };
+            if let Some(price) = slide_price.filter(|&price| price > 0) {
+                cmd.price = price;
+                return CommandResultCode::ValidForMatchingEngine;
+            }
+        }
+        CommandResultCode::MatchingUnsupportedCommand // Post-Only 拒绝
     }
 
-    /// 处理止损单
-    fn process_stop_orders(&mut self, cmd: &mut OrderCommand) {
-        if let Some(last_price) = self.last_trade_price {
+    /// 止损单是否触发
+    ///
+    /// 以最新成交价为来源时，本次命令成交的最高/最低价、最新成交价或最优买卖价越过触发价即触发：
+    /// 买止损看最高成交价与买一，卖止损看最低成交价与卖一；标记价/指数价来源只看对应的外部价格
+    fn should_trigger(&self, order: &AdvancedOrder, traded: Option<(Price, Price)>) -> bool {
+        let Some(stop_price) = order.stop_price else {
+            return false;
+        };
+        let prices = match order.stop_trigger {
+            StopTriggerSource::LastPrice => match order.action {
+                OrderAction::Bid => [traded.map(|(_, high)| high), self.last_trade.map(|t| t.price), self.best_bid_price],
+                OrderAction::Ask => [traded.map(|(low, _)| low), self.last_trade.map(|t| t.price), self.best_ask_price],
+            },
+            StopTriggerSource::MarkPrice => [self.mark_price, None, None],
+            StopTriggerSource::IndexPrice => [self.index_price, None, None],
+        };
+        prices.into_iter().flatten().any(|price| match order.action {
+            OrderAction::Bid => price >= stop_price, // 买止损
+            OrderAction::Ask => price <= stop_price, // 卖止损
+        })
+    }
+
+    /// 处理止损单：根据 cmd.matcher_events[from..] 中的成交更新最新成交价并激活触发的止损单
+    ///
+    /// 激活产生的事件以被激活的订单为 taker 追加到 cmd 中，其成交可能继续触发其他止损单
+    fn process_stop_orders(&mut self, cmd: &mut OrderCommand, mut from: usize) {
+        loop {
+            let mut traded: Option<(Price, Price)> = None;
+            for event in &cmd.matcher_events[from..] {
+                if event.event_type == MatcherEventType::Trade {
+                    let (low, high) = traded.unwrap_or((event.price, event.price));
+                    traded = Some((low.min(event.price), high.max(event.price)));
+                }
+            }
+            LastTrade::update(&mut self.last_trade, &cmd.matcher_events[from..], cmd.timestamp);
+            from = cmd.matcher_events.len();
+
+            // 先整体移出触发的止损单，再逐笔激活
             let mut triggered = Vec::new();
-            
-            for (idx, stop_order) in self.stop_orders.iter_mut().enumerate() {
-                if let Some(stop_price) = stop_order.stop_price {
-                    let should_trigger = match stop_order.action {
-                        OrderAction::Bid => last_price >= stop_price,  // 买止损
-                        OrderAction::Ask => last_price <= stop_price,  // 卖止损
-                    };
-
-                    if should_trigger && !stop_order.is_triggered {
-                        stop_order.is_triggered = true;
-                        triggered.push(idx);
-                    }
+            let mut i = 0;
+            while i < self.stop_orders.len() {
+                if self.should_trigger(&self.stop_orders[i], traded) {
+                    triggered.push(self.stop_orders.remove(i));
+                } else {
+                    i += 1;
                 }
             }
+            if triggered.is_empty() {
+                return;
+            }
 
-            // 激活触发的止损单
-            for idx in triggered.iter().rev() {
-                let order = self.stop_orders.remove(*idx);
+            // 激活触发的止损单：止损市价单按市价单执行（保护价内扫单，剩余不挂单），止损限价单按 GTC 限价挂单
+            for order in triggered {
+                let order_type = match order.order_type {
+                    OrderType::StopMarket => OrderType::Market,
+                    OrderType::StopLimit => OrderType::Gtc,
+                    order_type => order_type,
+                };
                 let mut activate_cmd = OrderCommand {
                     uid: order.uid,
                     order_id: order.order_id,
@@ -240,25 +548,82 @@ impl AdvancedOrderBook {
                     price: order.price,
                     size: order.size,
                     action: order.action,
-                    order_type: order.order_type,
+                    order_type,
                     reserve_price: order.reserve_price,
                     timestamp: order.timestamp,
+                    client_order_id: order.client_order_id,
+                    hidden: order.hidden,
                     ..Default::default()
                 };
-                
                 self.place_order_internal(&mut activate_cmd);
+                self.append_activated(cmd, activate_cmd);
+            }
+        }
+    }
+
+    /// 把被激活订单的事件以该订单为 taker 追加到 cmd 中，并记录激活的订单号
+    fn append_activated(&mut self, cmd: &mut OrderCommand, activate_cmd: OrderCommand) {
+        for mut event in activate_cmd.matcher_events {
+            event.taker_action = Some(activate_cmd.action);
+            event.taker_order_id = activate_cmd.order_id;
+            event.taker_uid = activate_cmd.uid;
+            event.taker_client_order_id = activate_cmd.client_order_id;
+            cmd.matcher_events.push(event);
+        }
+        self.activated_stops.push(activate_cmd.order_id);
+    }
+
+    /// GoodAfterTime：按生效时间先后激活到期的待生效订单（按原下单命令下单），其成交可能继续触发止损单
+    ///
+    /// 激活的订单以激活时间为下单时间，排在此前已挂出的订单之后
+    fn activate_pending_orders(&mut self, cmd: &mut OrderCommand) {
+        let from = cmd.matcher_events.len();
+        while let Some(entry) = self.pending_orders.first_entry() {
+            if entry.key().0 > cmd.timestamp {
+                break;
             }
+            let mut activate_cmd = entry.remove();
+            activate_cmd.activate_time = None;
+            activate_cmd.timestamp = cmd.timestamp;
+            self.place_order(&mut activate_cmd);
+            self.append_activated(cmd, activate_cmd);
         }
+        self.process_stop_orders(cmd, from);
+    }
+
+    /// 待生效订单暂存的下单命令
+    fn pending_order_mut(&mut self, order_id: OrderId) -> Option<&mut OrderCommand> {
+        self.pending_orders.values_mut().find(|o| o.order_id == order_id)
+    }
+
+    /// 修改待生效订单的数量：冰山显示/刷新数量与最小成交数量不超过新数量
+    fn resize_pending(order: &mut OrderCommand, size: Size) {
+        order.size = size;
+        order.visible_size = order.visible_size.map(|visible| visible.min(size));
+        order.replenish_size = order.replenish_size.map(|replenish| replenish.min(size));
+        order.min_fill_size = order.min_fill_size.map(|min_fill| min_fill.min(size));
+    }
+
+    /// 活跃订单、未触发的止损单与待生效订单共用订单号空间
+    fn contains_order(&self, order_id: OrderId) -> bool {
+        self.order_map.contains_key(&order_id)
+            || self.stop_orders.iter().any(|o| o.order_id == order_id)
+            || self.pending_orders.values().any(|o| o.order_id == order_id)
     }
 
     /// 下单（所有类型）
     fn place_order(&mut self, cmd: &mut OrderCommand) {
+        // GoodAfterTime：未到生效时间时暂存，到期激活时再做其余检查
+        if let Some(activate_time) = cmd.activate_time.filter(|&time| time > cmd.timestamp) {
+            let pending = OrderCommand { matcher_events: Vec::new(), ..cmd.clone() };
+            self.pending_orders.insert((activate_time, cmd.order_id), pending);
+            return;
+        }
+
         // Post-Only 检查
-        if cmd.order_type == OrderType::PostOnly {
-            if self.check_post_only(cmd) != CommandResultCode::ValidForMatchingEngine {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
-                return;
-            }
+        if cmd.order_type == OrderType::PostOnly && self.check_post_only(cmd) != CommandResultCode::ValidForMatchingEngine {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+            return;
         }
 
         // 止损单：暂存到止损池
@@ -273,10 +638,15 @@ impl AdvancedOrderBook {
                 order_type: cmd.order_type,
                 reserve_price: cmd.reserve_price,
                 timestamp: cmd.timestamp,
+                client_order_id: cmd.client_order_id,
                 stop_price: cmd.stop_price,
+                stop_trigger: cmd.stop_trigger,
                 visible_size: cmd.visible_size,
-                expire_time: cmd.expire_time,
+                replenish_size: cmd.replenish_size,
+                display_remaining: 0,
+                expire_time: Self::order_expire_time(cmd),
                 is_triggered: false,
+                hidden: cmd.hidden,
             };
             self.stop_orders.push(order);
             return;
@@ -287,40 +657,76 @@ impl AdvancedOrderBook {
 
     /// 内部下单逻辑
     fn place_order_internal(&mut self, cmd: &mut OrderCommand) {
-        // 检查重复订单
-        if self.order_map.contains_key(&cmd.order_id) {
-            let filled = self.try_match(cmd);
-            if filled < cmd.size {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price));
-            }
+        // GTX：下单或改价会与对手盘成交时撤销剩余部分，不吃单
+        if cmd.order_type == OrderType::Gtx && self.would_match(cmd) {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
             return;
         }
 
         // FOK: 全部成交或全部取消
-        if cmd.order_type == OrderType::Fok {
-            if !self.can_fill_completely(cmd) {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+        if cmd.order_type == OrderType::Fok && !self.can_fill(cmd, cmd.size) {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+            return;
+        }
+
+        // 市价单：以保护价作为限价
+        if cmd.order_type == OrderType::Market {
+            let best = match cmd.action {
+                OrderAction::Bid => self.best_ask_price,
+                OrderAction::Ask => self.best_bid_price,
+            };
+            let Some(best) = best else {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
                 return;
+            };
+
+            let mut limit = self.symbol_spec.market_protection_price(cmd.action, best);
+            // 现货买单不能超过风控冻结的预留价格（R1 拒绝未给出预留价格的现货市价买单）
+            if cmd.action == OrderAction::Bid && self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair {
+                limit = limit.min(cmd.reserve_price);
             }
+            cmd.price = limit;
         }
 
-        let filled = self.try_match(cmd);
+        // 市价转限价：以对手方最优价为限价，剩余部分按该价格挂单
+        if cmd.order_type == OrderType::MarketToLimit {
+            let best = match cmd.action {
+                OrderAction::Bid => self.best_ask_price,
+                OrderAction::Ask => self.best_bid_price,
+            };
+            // 现货买单的挂单价不能超过风控冻结的预留价格
+            let within_reserve = |best: &Price| {
+                cmd.action == OrderAction::Ask
+                    || self.symbol_spec.symbol_type != SymbolType::CurrencyExchangePair
+                    || *best <= cmd.reserve_price
+            };
+            let Some(best) = best.filter(within_reserve) else {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+                return;
+            };
+            cmd.price = best;
+        }
 
-        // 更新最新成交价
-        if filled > 0 {
-            self.last_trade_price = Some(cmd.price);
-            self.process_stop_orders(cmd);
+        // 最小成交数量不足：不撮合，IOC/FOK/Market 整单拒绝；挂单类订单没有可成交的对手盘时原样挂出，
+        // 否则挂出会与对手盘交叉，同样整单拒绝
+        if cmd.min_fill_size.is_some_and(|min_fill| !self.can_fill(cmd, min_fill))
+            && (matches!(cmd.order_type, OrderType::Ioc | OrderType::Fok | OrderType::Market) || self.would_match(cmd))
+        {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+            return;
         }
 
-        // IOC/FOK: 不挂单
-        if matches!(cmd.order_type, OrderType::Ioc | OrderType::Fok) {
+        let filled = self.try_match(cmd);
+
+        // IOC/FOK/Market: 不挂单
+        if matches!(cmd.order_type, OrderType::Ioc | OrderType::Fok | OrderType::Market) {
             if filled < cmd.size {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price));
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price, cmd.reserve_price));
             }
             return;
         }
 
-        // GTC/Day/GTD/PostOnly/Iceberg: 挂单
+        // GTC/Day/GTD/PostOnly/GTX/Iceberg/MarketToLimit: 挂单（市价转限价的剩余部分按 GTC 挂单）
         if filled < cmd.size {
             let order = AdvancedOrder {
                 order_id: cmd.order_id,
@@ -329,23 +735,32 @@ impl AdvancedOrderBook {
                 size: cmd.size,
                 filled,
                 action: cmd.action,
-                order_type: cmd.order_type,
+                order_type: match cmd.order_type {
+                    OrderType::MarketToLimit => OrderType::Gtc,
+                    order_type => order_type,
+                },
                 reserve_price: cmd.reserve_price,
                 timestamp: cmd.timestamp,
+                client_order_id: cmd.client_order_id,
                 stop_price: None,
+                stop_trigger: StopTriggerSource::LastPrice,
                 visible_size: cmd.visible_size,
-                expire_time: cmd.expire_time,
+                replenish_size: cmd.replenish_size,
+                display_remaining: 0,
+                expire_time: Self::order_expire_time(cmd),
                 is_triggered: false,
+                hidden: cmd.hidden,
             };
 
-            self.order_map.insert(cmd.order_id, (cmd.price, cmd.action));
+            self.order_map.insert(cmd.order_id, (cmd.price, cmd.action, cmd.uid));
+            self.touch_level(cmd.action, cmd.price);
 
             match cmd.action {
                 OrderAction::Ask => {
                     self.ask_buckets
                         .entry(cmd.price)
                         .or_insert_with(|| AdvancedBucket::new(cmd.price))
-                        .add(order);
+                        .add(order, self.symbol_spec.hidden_yield);
                     if self.best_ask_price.is_none() || cmd.price < self.best_ask_price.unwrap() {
                         self.best_ask_price = Some(cmd.price);
                     }
@@ -354,7 +769,7 @@ impl AdvancedOrderBook {
                     self.bid_buckets
                         .entry(cmd.price)
                         .or_insert_with(|| AdvancedBucket::new(cmd.price))
-                        .add(order);
+                        .add(order, self.symbol_spec.hidden_yield);
                     if self.best_bid_price.is_none() || cmd.price > self.best_bid_price.unwrap() {
                         self.best_bid_price = Some(cmd.price);
                     }
@@ -363,21 +778,38 @@ impl AdvancedOrderBook {
         }
     }
 
-    /// 检查是否可以完全成交（FOK）
-    fn can_fill_completely(&self, cmd: &OrderCommand) -> bool {
-        let buckets = match cmd.action {
-            OrderAction::Bid => &self.ask_buckets,
-            OrderAction::Ask => &self.bid_buckets,
-        };
+    /// 行情可见的档位（只有隐藏单的档位不展示）
+    fn displayed_levels<'a>(levels: impl Iterator<Item = (&'a Price, &'a AdvancedBucket)>) -> impl Iterator<Item = (&'a Price, &'a AdvancedBucket)> {
+        levels.filter(|(_, bucket)| bucket.visible_volume > 0)
+    }
+
+    /// 检查按限价能否立即成交 size（FOK、最小成交数量）
+    ///
+    /// 按撮合顺序逐笔累计对手盘挂单；已过期（撮合时会被移除）的挂单不计入，
+    /// 启用自成交预防时同一用户的挂单不计入，遇到会撤销 taker 的策略则无法全部成交
+    fn can_fill(&self, cmd: &OrderCommand, size: Size) -> bool {
+        let stp_mode = cmd.stp_mode.or(self.symbol_spec.stp_mode);
+        match cmd.action {
+            OrderAction::Bid => Self::fillable(self.ask_buckets.range(..=cmd.price).map(|(_, b)| b), cmd, stp_mode, size),
+            OrderAction::Ask => Self::fillable(self.bid_buckets.range(cmd.price..).rev().map(|(_, b)| b), cmd, stp_mode, size),
+        }
+    }
 
+    fn fillable<'a>(levels: impl Iterator<Item = &'a AdvancedBucket>, cmd: &OrderCommand, stp_mode: StpMode, size: Size) -> bool {
         let mut available = 0;
-        for (price, bucket) in buckets.iter() {
-            if (cmd.action == OrderAction::Bid && *price > cmd.price) ||
-               (cmd.action == OrderAction::Ask && *price < cmd.price) {
-                break;
+        for order in levels.flat_map(|bucket| bucket.orders.iter()) {
+            let remaining = order.remaining();
+            if remaining == 0 || order.expire_time.is_some_and(|expire| cmd.timestamp > expire) {
+                continue;
+            }
+            if stp_mode != StpMode::None && order.uid == cmd.uid {
+                if stp_mode.cancel_sizes(remaining, size - available).1 > 0 {
+                    return false;
+                }
+                continue;
             }
-            available += bucket.total_volume;
-            if available >= cmd.size {
+            available += remaining;
+            if available >= size {
                 return true;
             }
         }
@@ -389,12 +821,19 @@ impl AdvancedOrderBook {
         let mut filled = 0;
 
         // 快速路径检查
-        if (cmd.action == OrderAction::Bid && self.best_ask_price.map_or(true, |p| p > cmd.price)) ||
-           (cmd.action == OrderAction::Ask && self.best_bid_price.map_or(true, |p| p < cmd.price)) {
+        if (cmd.action == OrderAction::Bid && self.best_ask_price.is_none_or(|p| p > cmd.price)) ||
+           (cmd.action == OrderAction::Ask && self.best_bid_price.is_none_or(|p| p < cmd.price)) {
             return 0;
         }
 
-        let current_time = cmd.timestamp;
+        let ctx = MatchContext {
+            taker_uid: cmd.uid,
+            stp_mode: cmd.stp_mode.or(self.symbol_spec.stp_mode),
+            current_time: cmd.timestamp,
+            hidden_yield: self.symbol_spec.hidden_yield,
+            algorithm: self.symbol_spec.matching_algorithm,
+            rounding: self.symbol_spec.pro_rata_rounding,
+        };
 
         match cmd.action {
             OrderAction::Bid => {
@@ -405,10 +844,15 @@ impl AdvancedOrderBook {
                         break;
                     }
 
+                    self.touch_level(OrderAction::Ask, price);
                     if let Some(bucket) = self.ask_buckets.get_mut(&price) {
-                        let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid, current_time);
+                        let (matched, taker_cancelled, events) = bucket.match_order(cmd.size - filled, &ctx);
                         filled += matched;
+                        for event in events.iter().filter(|e| e.maker_completed) {
+                            self.order_map.remove(&event.matched_order_id);
+                        }
                         cmd.matcher_events.extend(events);
+                        Self::cancel_taker_for_stp(cmd, taker_cancelled);
 
                         if bucket.total_volume == 0 {
                             self.ask_buckets.remove(&price);
@@ -425,10 +869,15 @@ impl AdvancedOrderBook {
                         break;
                     }
 
+                    self.touch_level(OrderAction::Bid, price);
                     if let Some(bucket) = self.bid_buckets.get_mut(&price) {
-                        let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid, current_time);
+                        let (matched, taker_cancelled, events) = bucket.match_order(cmd.size - filled, &ctx);
                         filled += matched;
+                        for event in events.iter().filter(|e| e.maker_completed) {
+                            self.order_map.remove(&event.matched_order_id);
+                        }
                         cmd.matcher_events.extend(events);
+                        Self::cancel_taker_for_stp(cmd, taker_cancelled);
 
                         if bucket.total_volume == 0 {
                             self.bid_buckets.remove(&price);
@@ -442,10 +891,93 @@ impl AdvancedOrderBook {
         filled
     }
 
+    /// 自成交预防撤销 taker 数量：从命令数量中扣除并生成拒绝事件
+    #[inline]
+    fn cancel_taker_for_stp(cmd: &mut OrderCommand, cancelled: Size) {
+        if cancelled > 0 {
+            cmd.size -= cancelled;
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cancelled, cmd.price, cmd.reserve_price));
+        }
+    }
+
+    /// 过期扫描：移除活跃订单、未触发止损单与待生效订单中已过期的订单
+    fn expire_orders(&mut self, now: i64, events: &mut Vec<MatcherTradeEvent>) -> usize {
+        let start = events.len();
+        let mut expired = 0;
+
+        let levels = &mut self.level_changes;
+        for (action, buckets) in [(OrderAction::Ask, &mut self.ask_buckets), (OrderAction::Bid, &mut self.bid_buckets)] {
+            buckets.retain(|&price, bucket| {
+                let visible_volume = bucket.visible_volume;
+                let removed = bucket.remove_expired(now, events);
+                if removed > 0 {
+                    levels.touch(action, price, visible_volume);
+                }
+                expired += removed;
+                !bucket.orders.is_empty()
+            });
+        }
+
+        for event in &events[start..] {
+            self.order_map.remove(&event.matched_order_id);
+        }
+
+        self.stop_orders.retain(|order| {
+            if order.expire_time.is_none_or(|expire| now <= expire) {
+                return true;
+            }
+            events.push(MatcherTradeEvent::new_maker_reject(
+                order.size,
+                order.price,
+                order.order_id,
+                order.uid,
+                order.action,
+                order.reserve_price,
+            ).with_client_order_id(order.client_order_id));
+            expired += 1;
+            false
+        });
+
+        self.pending_orders.retain(|_, order| {
+            if Self::order_expire_time(order).is_none_or(|expire| now <= expire) {
+                return true;
+            }
+            events.push(MatcherTradeEvent::new_maker_reject(
+                order.size,
+                order.price,
+                order.order_id,
+                order.uid,
+                order.action,
+                order.reserve_price,
+            ).with_client_order_id(order.client_order_id));
+            expired += 1;
+            false
+        });
+
+        if expired > 0 {
+            self.update_best_prices();
+        }
+        expired
+    }
+
+    /// 订单过期时间：未显式指定时取 GTD 订单类型中的时间戳
+    #[inline]
+    fn order_expire_time(cmd: &OrderCommand) -> Option<i64> {
+        match cmd.order_type {
+            OrderType::Gtd(expire) => cmd.expire_time.or(Some(expire)),
+            _ => cmd.expire_time,
+        }
+    }
+
     /// 取消订单
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
         // 检查活跃订单
-        if let Some((price, action)) = self.order_map.remove(&cmd.order_id) {
+        if let Some((price, action, uid)) = self.order_map.get(&cmd.order_id).copied() {
+            if uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            self.order_map.remove(&cmd.order_id);
+            self.touch_level(action, price);
             let buckets = match action {
                 OrderAction::Ask => &mut self.ask_buckets,
                 OrderAction::Bid => &mut self.bid_buckets,
@@ -455,7 +987,8 @@ impl AdvancedOrderBook {
                 if let Some(order) = bucket.remove(cmd.order_id) {
                     cmd.matcher_events.push(MatcherTradeEvent::new_reject(
                         order.size - order.filled,
-                        price
+                        price,
+                        order.reserve_price,
                     ));
                     cmd.action = action;
 
@@ -471,18 +1004,290 @@ impl AdvancedOrderBook {
 
         // 检查止损单池
         if let Some(pos) = self.stop_orders.iter().position(|o| o.order_id == cmd.order_id) {
+            if self.stop_orders[pos].uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
             let order = self.stop_orders.remove(pos);
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(order.size, order.price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(order.size, order.price, order.reserve_price));
+            cmd.action = order.action;
+            return CommandResultCode::Success;
+        }
+
+        // 检查待生效订单
+        if let Some((&key, order)) = self.pending_orders.iter().find(|(_, o)| o.order_id == cmd.order_id) {
+            if order.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(order.size, order.price, order.reserve_price));
+            cmd.action = order.action;
+            self.pending_orders.remove(&key);
+            return CommandResultCode::Success;
+        }
+
+        CommandResultCode::MatchingUnknownOrderId
+    }
+
+    /// 减量：保留时间优先级，减至 0 时移除订单；冰山单的显示切片不超过剩余数量
+    fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        if cmd.size <= 0 {
+            return CommandResultCode::MatchingInvalidOrderSize;
+        }
+
+        if let Some(&(price, action, uid)) = self.order_map.get(&cmd.order_id) {
+            if uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            self.touch_level(action, price);
+            let buckets = match action {
+                OrderAction::Ask => &mut self.ask_buckets,
+                OrderAction::Bid => &mut self.bid_buckets,
+            };
+            let Some(bucket) = buckets.get_mut(&price) else {
+                return CommandResultCode::MatchingUnknownOrderId;
+            };
+            let Some(order) = bucket.orders.iter_mut().find(|o| o.order_id == cmd.order_id) else {
+                return CommandResultCode::MatchingUnknownOrderId;
+            };
+
+            let remaining = order.remaining();
+            let reduce_by = remaining.min(cmd.size);
+            let reserve_price = order.reserve_price;
+            if reduce_by == remaining {
+                bucket.remove(cmd.order_id);
+                self.order_map.remove(&cmd.order_id);
+                if bucket.total_volume == 0 {
+                    buckets.remove(&price);
+                    self.update_best_prices();
+                }
+            } else {
+                let old_visible = order.displayed();
+                order.size -= reduce_by;
+                bucket.total_volume -= reduce_by;
+                bucket.visible_volume = bucket.visible_volume - old_visible + order.displayed();
+            }
+            cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining - reduce_by));
+            cmd.action = action;
+            return CommandResultCode::Success;
+        }
+
+        // 未触发的止损单
+        if let Some(pos) = self.stop_orders.iter().position(|o| o.order_id == cmd.order_id) {
+            let order = &mut self.stop_orders[pos];
+            if order.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            let reduce_by = order.remaining().min(cmd.size);
+            let (price, reserve_price, action) = (order.price, order.reserve_price, order.action);
+            order.size -= reduce_by;
+            let remaining = order.remaining();
+            if remaining == 0 {
+                self.stop_orders.remove(pos);
+            }
+            cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining));
+            cmd.action = action;
+            return CommandResultCode::Success;
+        }
+
+        // 待生效订单：修改暂存的下单命令，减至 0 时移除
+        if let Some(order) = self.pending_order_mut(cmd.order_id) {
+            if order.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            let reduce_by = order.size.min(cmd.size);
+            let (price, reserve_price, action) = (order.price, order.reserve_price, order.action);
+            let remaining = order.size - reduce_by;
+            Self::resize_pending(order, remaining);
+            if remaining == 0 {
+                self.pending_orders.retain(|_, o| o.order_id != cmd.order_id);
+            }
+            cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining));
+            cmd.action = action;
             return CommandResultCode::Success;
         }
 
         CommandResultCode::MatchingUnknownOrderId
     }
+
+    /// 改单：只减少数量时原地减量，否则先把剩余数量改为新数量再按改价流程重新排队（保留类型、冰山与过期参数）；
+    /// 未触发的止损单与待生效订单原地修改限价与数量
+    fn amend_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(order) = super::OrderBook::get_open_order(self, cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        if order.uid != cmd.uid {
+            return CommandResultCode::MatchingUnauthorizedAction;
+        }
+        let price = if cmd.price == 0 { order.price } else { cmd.price };
+        if self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair
+            && order.action == OrderAction::Bid
+            && price > order.reserve_price
+        {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        let amend = MatcherTradeEvent::new_amend((order.remaining - cmd.size).max(0), price, order.reserve_price, cmd.size);
+        cmd.action = order.action;
+        if let Some(stop_order) = self.stop_orders.iter_mut().find(|o| o.order_id == cmd.order_id) {
+            stop_order.price = price;
+            stop_order.size = stop_order.filled + cmd.size;
+            cmd.matcher_events.push(amend);
+            return CommandResultCode::Success;
+        }
+        if let Some(pending) = self.pending_order_mut(cmd.order_id) {
+            pending.price = price;
+            Self::resize_pending(pending, cmd.size);
+            cmd.matcher_events.push(amend);
+            return CommandResultCode::Success;
+        }
+
+        let request = |command, price, size| OrderCommand {
+            command,
+            uid: order.uid,
+            order_id: order.order_id,
+            symbol: order.symbol,
+            price,
+            size,
+            timestamp: cmd.timestamp,
+            stp_mode: cmd.stp_mode,
+            ..Default::default()
+        };
+        if price == order.price && cmd.size <= order.remaining {
+            if cmd.size < order.remaining {
+                let code = self.reduce_order(&mut request(OrderCommandType::ReduceOrder, 0, order.remaining - cmd.size));
+                if code != CommandResultCode::Success {
+                    return code;
+                }
+            }
+            cmd.matcher_events.push(amend);
+            return CommandResultCode::Success;
+        }
+
+        self.touch_level(order.action, order.price);
+        let buckets = match order.action {
+            OrderAction::Ask => &mut self.ask_buckets,
+            OrderAction::Bid => &mut self.bid_buckets,
+        };
+        let bucket = buckets.get_mut(&order.price).expect("挂单所在档位存在");
+        let resting = bucket.orders.iter_mut().find(|o| o.order_id == cmd.order_id).expect("订单存在于档位中");
+        let old_visible = resting.displayed();
+        resting.size = resting.filled + cmd.size;
+        bucket.total_volume += cmd.size - order.remaining;
+        bucket.visible_volume = bucket.visible_volume - old_visible + resting.displayed();
+
+        cmd.matcher_events.push(amend);
+        let mut move_cmd = request(OrderCommandType::MoveOrder, price, 0);
+        self.move_order(&mut move_cmd);
+        cmd.matcher_events.append(&mut move_cmd.matcher_events);
+        CommandResultCode::Success
+    }
+
+    /// 改价：保留原订单的类型、剩余数量与冰山参数，按新价格重新撮合（失去时间优先级）
+    fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        // 未触发的止损单只更新限价
+        if let Some(stop_order) = self.stop_orders.iter_mut().find(|o| o.order_id == cmd.order_id) {
+            if stop_order.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            if self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair
+                && stop_order.action == OrderAction::Bid
+                && cmd.price > stop_order.reserve_price
+            {
+                return CommandResultCode::RiskInvalidReserveBidPrice;
+            }
+            stop_order.price = cmd.price;
+            cmd.action = stop_order.action;
+            return CommandResultCode::Success;
+        }
+
+        // 待生效订单只更新暂存命令的限价
+        let symbol_type = self.symbol_spec.symbol_type;
+        if let Some(pending) = self.pending_order_mut(cmd.order_id) {
+            if pending.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            if symbol_type == SymbolType::CurrencyExchangePair
+                && pending.action == OrderAction::Bid
+                && cmd.price > pending.reserve_price
+            {
+                return CommandResultCode::RiskInvalidReserveBidPrice;
+            }
+            pending.price = cmd.price;
+            cmd.action = pending.action;
+            return CommandResultCode::Success;
+        }
+
+        let Some(&(price, action, uid)) = self.order_map.get(&cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        if uid != cmd.uid {
+            return CommandResultCode::MatchingUnauthorizedAction;
+        }
+        self.touch_level(action, price);
+        let buckets = match action {
+            OrderAction::Ask => &mut self.ask_buckets,
+            OrderAction::Bid => &mut self.bid_buckets,
+        };
+        let Some(bucket) = buckets.get_mut(&price) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+        let Some(order) = bucket.orders.iter().find(|o| o.order_id == cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+
+        // 现货买单新价格不能超过冻结价格
+        if self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair
+            && action == OrderAction::Bid
+            && cmd.price > order.reserve_price
+        {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        let order = bucket.remove(cmd.order_id).expect("订单存在于档位中");
+        if bucket.total_volume == 0 {
+            buckets.remove(&price);
+        }
+        self.order_map.remove(&cmd.order_id);
+        self.update_best_prices();
+
+        let mut place_cmd = OrderCommand {
+            uid: order.uid,
+            order_id: order.order_id,
+            symbol: cmd.symbol,
+            price: cmd.price,
+            size: order.remaining(),
+            action,
+            order_type: order.order_type,
+            reserve_price: order.reserve_price,
+            timestamp: cmd.timestamp,
+            client_order_id: order.client_order_id,
+            visible_size: order.visible_size,
+            replenish_size: order.replenish_size,
+            expire_time: order.expire_time,
+            stp_mode: cmd.stp_mode,
+            hidden: order.hidden,
+            ..Default::default()
+        };
+        // 按新价格重新下单，Post-Only 等下单检查照常生效
+        self.place_order(&mut place_cmd);
+
+        cmd.matcher_events.extend(place_cmd.matcher_events);
+        cmd.action = action;
+        CommandResultCode::Success
+    }
 }
 
 impl super::OrderBook for AdvancedOrderBook {
     fn new_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        if self.contains_order(cmd.order_id) {
+            return CommandResultCode::MatchingDuplicateOrderId;
+        }
+        // 预算单的 price 为总预算，不能按限价挂单
+        if matches!(cmd.order_type, OrderType::FokBudget | OrderType::IocBudget) {
+            return CommandResultCode::MatchingUnsupportedCommand;
+        }
+        let from = cmd.matcher_events.len();
         self.place_order(cmd);
+        self.process_stop_orders(cmd, from);
         CommandResultCode::Success
     }
 
@@ -491,16 +1296,57 @@ impl super::OrderBook for AdvancedOrderBook {
     }
 
     fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        // 简化：先取消再下单
-        let cancel_result = self.cancel_order(cmd);
-        if cancel_result == CommandResultCode::Success {
-            self.place_order(cmd);
+        let from = cmd.matcher_events.len();
+        let code = self.move_order(cmd);
+        if code == CommandResultCode::Success {
+            self.process_stop_orders(cmd, from);
+        }
+        code
+    }
+
+    fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        self.reduce_order(cmd)
+    }
+
+    fn amend_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let from = cmd.matcher_events.len();
+        let code = self.amend_order(cmd);
+        if code == CommandResultCode::Success {
+            self.process_stop_orders(cmd, from);
         }
-        cancel_result
+        code
+    }
+
+    fn expire_orders(&mut self, now: i64, events: &mut Vec<MatcherTradeEvent>) -> usize {
+        self.expire_orders(now, events)
+    }
+
+    fn update_reference_price(&mut self, source: StopTriggerSource, price: Price, cmd: &mut OrderCommand) {
+        match source {
+            StopTriggerSource::LastPrice => return,
+            StopTriggerSource::MarkPrice => self.mark_price = Some(price),
+            StopTriggerSource::IndexPrice => self.index_price = Some(price),
+        }
+        let from = cmd.matcher_events.len();
+        self.process_stop_orders(cmd, from);
+    }
+
+    fn activate_pending_orders(&mut self, cmd: &mut OrderCommand) {
+        self.activate_pending_orders(cmd)
+    }
+
+    fn enable_level_updates(&mut self) {
+        self.level_changes.enable();
+    }
+
+    fn take_level_updates(&mut self, out: &mut Vec<LevelUpdate>) {
+        let mut changes = std::mem::take(&mut self.level_changes);
+        changes.drain(self, out);
+        self.level_changes = changes;
     }
 
-    fn reduce_order(&mut self, _cmd: &mut OrderCommand) -> CommandResultCode {
-        CommandResultCode::MatchingUnsupportedCommand
+    fn take_activated_stops(&mut self) -> Vec<OrderId> {
+        std::mem::take(&mut self.activated_stops)
     }
 
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification {
@@ -510,12 +1356,12 @@ impl super::OrderBook for AdvancedOrderBook {
     fn get_l2_data(&self, depth: usize) -> L2MarketData {
         let mut data = L2MarketData::new(depth);
 
-        for (price, bucket) in self.ask_buckets.iter().take(depth) {
+        for (price, bucket) in Self::displayed_levels(self.ask_buckets.iter()).take(depth) {
             data.ask_prices.push(*price);
             data.ask_volumes.push(bucket.visible_volume); // 显示量
         }
 
-        for (price, bucket) in self.bid_buckets.iter().rev().take(depth) {
+        for (price, bucket) in Self::displayed_levels(self.bid_buckets.iter().rev()).take(depth) {
             data.bid_prices.push(*price);
             data.bid_volumes.push(bucket.visible_volume); // 显示量
         }
@@ -523,8 +1369,83 @@ impl super::OrderBook for AdvancedOrderBook {
         data
     }
 
+    fn best_bid_offer(&self) -> super::TopOfBook {
+        (
+            Self::displayed_levels(self.bid_buckets.iter().rev()).next().map(|(price, bucket)| (*price, bucket.visible_volume)),
+            Self::displayed_levels(self.ask_buckets.iter()).next().map(|(price, bucket)| (*price, bucket.visible_volume)),
+        )
+    }
+
+    fn quote_for_size(&self, action: OrderAction, size: Size) -> SizeQuote {
+        match action {
+            OrderAction::Bid => SizeQuote::from_levels(action, size, self.ask_buckets.iter().map(|(price, bucket)| (*price, bucket.total_volume))),
+            OrderAction::Ask => SizeQuote::from_levels(action, size, self.bid_buckets.iter().rev().map(|(price, bucket)| (*price, bucket.total_volume))),
+        }
+    }
+
+    fn get_l3_data(&self, depth: usize, max_orders_per_level: usize, mask_uid: bool) -> L3MarketData {
+        let mut data = L3MarketData::new(depth);
+        data.asks.extend(Self::displayed_levels(self.ask_buckets.iter()).take(depth).map(|(_, b)| b.to_l3_level(max_orders_per_level, mask_uid)));
+        data.bids.extend(Self::displayed_levels(self.bid_buckets.iter().rev()).take(depth).map(|(_, b)| b.to_l3_level(max_orders_per_level, mask_uid)));
+        data
+    }
+
     fn get_order_by_id(&self, order_id: OrderId) -> Option<(Price, OrderAction)> {
-        self.order_map.get(&order_id).copied()
+        self.order_map.get(&order_id).map(|&(price, action, _)| (price, action))
+    }
+
+    fn get_open_order(&self, order_id: OrderId) -> Option<OpenOrder> {
+        let order = match self.order_map.get(&order_id).copied() {
+            Some((price, action, _)) => {
+                let buckets = match action {
+                    OrderAction::Ask => &self.ask_buckets,
+                    OrderAction::Bid => &self.bid_buckets,
+                };
+                buckets.get(&price)?.orders.iter().find(|o| o.order_id == order_id)?
+            }
+            // 未触发的止损单与待生效订单不在 order_map 中
+            None => match self.stop_orders.iter().find(|o| o.order_id == order_id) {
+                Some(order) => order,
+                None => {
+                    let cmd = self.pending_orders.values().find(|o| o.order_id == order_id)?;
+                    return Some(OpenOrder {
+                        order_id,
+                        uid: cmd.uid,
+                        symbol: self.symbol_spec.symbol_id,
+                        action: cmd.action,
+                        order_type: cmd.order_type,
+                        price: cmd.price,
+                        reserve_price: cmd.reserve_price,
+                        size: cmd.size,
+                        remaining: cmd.size,
+                        timestamp: cmd.timestamp,
+                        client_order_id: cmd.client_order_id,
+                    });
+                }
+            },
+        };
+        Some(OpenOrder {
+            order_id,
+            uid: order.uid,
+            symbol: self.symbol_spec.symbol_id,
+            action: order.action,
+            order_type: order.order_type,
+            price: order.price,
+            reserve_price: order.reserve_price,
+            size: order.size,
+            remaining: order.remaining(),
+            timestamp: order.timestamp,
+            client_order_id: order.client_order_id,
+        })
+    }
+
+    fn get_all_orders(&self) -> Vec<OpenOrder> {
+        self.order_map
+            .keys()
+            .chain(self.stop_orders.iter().map(|o| &o.order_id))
+            .chain(self.pending_orders.values().map(|o| &o.order_id))
+            .filter_map(|&order_id| self.get_open_order(order_id))
+            .collect()
     }
 
     fn get_total_ask_volume(&self) -> Size {
@@ -543,6 +1464,82 @@ impl super::OrderBook for AdvancedOrderBook {
         self.bid_buckets.len()
     }
 
+    fn get_level_volume(&self, action: OrderAction, price: Price) -> Size {
+        let buckets = match action {
+            OrderAction::Ask => &self.ask_buckets,
+            OrderAction::Bid => &self.bid_buckets,
+        };
+        buckets.get(&price).map_or(0, |b| b.visible_volume)
+    }
+
+    fn stats(&self) -> OrderBookStats {
+        OrderBookStats {
+            symbol: self.symbol_spec.symbol_id,
+            order_count: self.order_map.len(),
+            stop_order_count: self.stop_orders.len(),
+            ask_levels: self.ask_buckets.len(),
+            bid_levels: self.bid_buckets.len(),
+            total_ask_volume: self.get_total_ask_volume(),
+            total_bid_volume: self.get_total_bid_volume(),
+            visible_ask_volume: self.ask_buckets.values().map(|b| b.visible_volume).sum(),
+            visible_bid_volume: self.bid_buckets.values().map(|b| b.visible_volume).sum(),
+            ask_price_range: super::price_range(self.ask_buckets.keys()),
+            bid_price_range: super::price_range(self.bid_buckets.keys()),
+            pool: None,
+            last_trade: self.last_trade,
+        }
+    }
+
+    fn visit_levels(&self, side: OrderAction, visitor: &mut dyn FnMut(Price, Size) -> bool) {
+        match side {
+            OrderAction::Ask => Self::displayed_levels(self.ask_buckets.iter()).all(|(price, bucket)| visitor(*price, bucket.visible_volume)),
+            OrderAction::Bid => Self::displayed_levels(self.bid_buckets.iter().rev()).all(|(price, bucket)| visitor(*price, bucket.visible_volume)),
+        };
+    }
+
+    #[cfg(feature = "debug-invariants")]
+    fn check_invariants(&self) -> Result<(), crate::core::invariants::BookInvariantViolation> {
+        use crate::core::invariants::{ensure_book, ensure_not_crossed};
+        let symbol = self.symbol_spec.symbol_id;
+        let (best_bid, best_ask) = (self.bid_buckets.keys().next_back().copied(), self.ask_buckets.keys().next().copied());
+        ensure_not_crossed(symbol, best_bid, best_ask)?;
+        ensure_book(symbol, (self.best_bid_price, self.best_ask_price) == (best_bid, best_ask), || {
+            format!("最优价缓存 {:?}/{:?} 与档位 {best_bid:?}/{best_ask:?} 不符", self.best_bid_price, self.best_ask_price)
+        })?;
+
+        let mut count = 0;
+        for (action, buckets) in [(OrderAction::Ask, &self.ask_buckets), (OrderAction::Bid, &self.bid_buckets)] {
+            for (&price, bucket) in buckets {
+                let total: Size = bucket.orders.iter().map(AdvancedOrder::remaining).sum();
+                let visible: Size = bucket.orders.iter().map(AdvancedOrder::displayed).sum();
+                let consistent = bucket.total_volume == total && bucket.visible_volume == visible;
+                ensure_book(symbol, bucket.price == price && !bucket.orders.is_empty() && consistent, || {
+                    format!(
+                        "{action:?} 档位 {price}: 数量 {}/{} 与 {} 笔订单剩余合计 {total}/{visible} 不符",
+                        bucket.total_volume,
+                        bucket.visible_volume,
+                        bucket.orders.len()
+                    )
+                })?;
+                for order in &bucket.orders {
+                    let indexed = self.order_map.get(&order.order_id) == Some(&(price, action, order.uid));
+                    ensure_book(symbol, indexed && order.remaining() > 0 && order.price == price && order.action == action, || {
+                        format!("订单 {} 与 {action:?} 档位 {price} 或索引不一致", order.order_id)
+                    })?;
+                }
+                count += bucket.orders.len();
+            }
+        }
+        ensure_book(symbol, count == self.order_map.len(), || format!("索引 {} 条，挂单 {count} 笔", self.order_map.len()))?;
+
+        // 未触发的止损单与待生效订单不在订单簿中
+        let off_book = self.stop_orders.iter().map(|o| o.order_id).chain(self.pending_orders.values().map(|o| o.order_id));
+        for order_id in off_book {
+            ensure_book(symbol, !self.order_map.contains_key(&order_id), || format!("止损/待生效订单 {order_id} 同时在订单簿中"))?;
+        }
+        Ok(())
+    }
+
     fn serialize_state(&self) -> crate::core::orderbook::OrderBookState {
         crate::core::orderbook::OrderBookState::Advanced(self.clone())
     }
@@ -1,4 +1,5 @@
 use crate::api::*;
+use crate::core::orderbook::{BidBudget, LevelChanges};
 use ahash::AHashMap;
 use slab::Slab;
 use std::collections::BTreeMap;
@@ -18,6 +19,7 @@ struct DirectOrder {
     action: OrderAction,
     reserve_price: Price,
     timestamp: i64,
+    client_order_id: u64,
     next: Option<OrderIdx>,
     prev: Option<OrderIdx>,
     parent: BucketIdx,
@@ -52,6 +54,11 @@ pub struct DirectOrderBook {
     // 最优订单快捷引用，类似 LMAX Disruptor 的快速路径
     best_ask_order: Option<OrderIdx>, // 卖一订单
     best_bid_order: Option<OrderIdx>, // 买一订单
+
+    last_trade: Option<LastTrade>,
+
+    #[serde(skip)]
+    level_changes: LevelChanges,
 }
 
 impl DirectOrderBook {
@@ -65,20 +72,22 @@ impl DirectOrderBook {
             order_id_index: AHashMap::new(),
             best_ask_order: None,
             best_bid_order: None,
+            last_trade: None,
+            level_changes: LevelChanges::default(),
         }
     }
 
-    /// GTC 下单
-    fn place_gtc(&mut self, cmd: &mut OrderCommand) {
-        // 检查重复订单
-        if self.order_id_index.contains_key(&cmd.order_id) {
-            let filled = self.try_match(cmd);
-            if filled < cmd.size {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price));
-            }
-            return;
+    /// 档位数量即将变化：开启档位变化记录时记录其原数量
+    #[inline]
+    fn touch_level(&mut self, action: OrderAction, price: Price) {
+        if self.level_changes.should_touch(action, price) {
+            let volume = super::OrderBook::get_level_volume(self, action, price);
+            self.level_changes.touch(action, price, volume);
         }
+    }
 
+    /// GTC 下单
+    fn place_gtc(&mut self, cmd: &mut OrderCommand) {
         // 尝试撮合
         let filled = self.try_match(cmd);
 
@@ -93,6 +102,7 @@ impl DirectOrderBook {
                 action: cmd.action,
                 reserve_price: cmd.reserve_price,
                 timestamp: cmd.timestamp,
+                client_order_id: cmd.client_order_id,
                 next: None,
                 prev: None,
                 parent: 0, // 临时值
@@ -109,8 +119,34 @@ impl DirectOrderBook {
         let rejected = cmd.size - filled;
 
         if rejected > 0 {
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(rejected, cmd.price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(rejected, cmd.price, cmd.reserve_price));
+        }
+    }
+
+    /// FOK 下单：无法全部成交则整单拒绝
+    fn place_fok(&mut self, cmd: &mut OrderCommand) {
+        if self.can_fill_completely(cmd.action, cmd.price, cmd.size) {
+            self.place_ioc(cmd);
+        } else {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+        }
+    }
+
+    /// 检查限价范围内的对手盘是否足以全部成交
+    fn can_fill_completely(&self, action: OrderAction, limit_price: Price, size: Size) -> bool {
+        let bucket_indices: Box<dyn Iterator<Item = &BucketIdx>> = match action {
+            OrderAction::Bid => Box::new(self.ask_price_buckets.range(..=limit_price).map(|(_, b)| b)),
+            OrderAction::Ask => Box::new(self.bid_price_buckets.range(limit_price..).rev().map(|(_, b)| b)),
+        };
+
+        let mut available = 0;
+        for &bucket_idx in bucket_indices {
+            available += self.buckets[bucket_idx].volume;
+            if available >= size {
+                return true;
+            }
         }
+        false
     }
 
     /// FOK_BUDGET 下单
@@ -119,19 +155,70 @@ impl DirectOrderBook {
 
         if let Some(calculated) = budget {
             if self.is_budget_satisfied(cmd.action, calculated, cmd.price) {
+                // 预算已校验，撮合时不限价
+                let budget = cmd.price;
+                cmd.price = if cmd.action == OrderAction::Bid { Price::MAX } else { 0 };
                 self.try_match(cmd);
+                cmd.price = budget;
             } else {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
             }
         } else {
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
         }
     }
 
+    /// IOC_BUDGET 买单：逐档撮合，累计成交金额不超过预算（price 字段为总预算）
+    fn place_ioc_budget(&mut self, cmd: &mut OrderCommand) {
+        let budget = cmd.price;
+        let mut remaining = BidBudget::new(budget);
+        let size = cmd.size;
+        let mut filled = 0;
+
+        while let Some((price, volume)) = self.best_opposite_level(cmd.action) {
+            let step = remaining.affordable(price, (size - filled).min(volume));
+            if step <= 0 {
+                break;
+            }
+
+            // 单档撮合，成交价均为该档价格
+            cmd.size = step;
+            cmd.price = price;
+            let matched = self.try_match(cmd);
+            filled += matched;
+            remaining.spend(price, matched);
+            if matched < step {
+                break;
+            }
+        }
+
+        cmd.size = size;
+        cmd.price = budget;
+        if filled < size {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(size - filled, budget, cmd.reserve_price));
+        }
+    }
+
+    /// 对手方最优档位 (价格, 挂单量)
+    #[inline]
+    fn best_opposite_level(&self, action: OrderAction) -> Option<(Price, Size)> {
+        let best = match action {
+            OrderAction::Bid => self.best_ask_order,
+            OrderAction::Ask => self.best_bid_order,
+        };
+        best.map(|idx| {
+            let order = &self.orders[idx];
+            (order.price, self.buckets[order.parent].volume)
+        })
+    }
+
     fn is_budget_satisfied(&self, action: OrderAction, calculated: i64, limit: i64) -> bool {
         calculated != i64::MAX && (calculated == limit || (action == OrderAction::Bid) != (calculated > limit))
     }
 
+    /// 计算全部成交所需总金额，对手盘不足或金额溢出时返回 None
+    ///
+    /// 本订单簿不做自成交预防，taker 自己的挂单同样会成交，因此一并计入
     fn check_budget_to_fill(&self, mut size: Size, action: OrderAction) -> Option<i64> {
         let mut maker_idx = match action {
             OrderAction::Bid => self.best_ask_order,
@@ -148,12 +235,12 @@ impl DirectOrderBook {
 
             if size > available {
                 size -= available;
-                budget += (available * price) as i64;
+                budget = budget.checked_add(available.checked_mul(price)?)?;
                 // 移动到桶尾部订单的前一个订单
                 let tail_idx = bucket.tail;
                 maker_idx = self.orders[tail_idx].prev;
             } else {
-                return Some(budget + (size * price) as i64);
+                return budget.checked_add(size.checked_mul(price)?);
             }
         }
 
@@ -206,6 +293,7 @@ impl DirectOrderBook {
             };
 
             let trade_size = remaining.min(maker_size - maker_filled);
+            self.touch_level(cmd.action.opposite(), maker_price);
 
             // 更新 maker 订单
             {
@@ -229,7 +317,9 @@ impl DirectOrderBook {
                 self.orders[idx].order_id,
                 self.orders[idx].uid,
                 if is_bid { taker_reserve } else { self.orders[idx].reserve_price },
-            );
+                maker_completed,
+            )
+            .with_client_order_id(self.orders[idx].client_order_id);
             cmd.matcher_events.push(event);
 
             if !maker_completed {
@@ -257,6 +347,11 @@ impl DirectOrderBook {
             maker_idx = next_maker;
         }
 
+        // 新的最优订单不再指向已移除的订单
+        if let Some(idx) = maker_idx {
+            self.orders[idx].next = None;
+        }
+
         // 更新最优订单
         if is_bid {
             self.best_ask_order = maker_idx;
@@ -273,6 +368,7 @@ impl DirectOrderBook {
             let order = &self.orders[order_idx];
             (order.price, order.action)
         };
+        self.touch_level(action, price);
 
         let is_ask = action == OrderAction::Ask;
         let buckets_map = if is_ask { &mut self.ask_price_buckets } else { &mut self.bid_price_buckets };
@@ -342,6 +438,35 @@ impl DirectOrderBook {
             }
         }
     }
+    /// L3 档位快照：从桶尾沿 next 回溯到最早订单，再按时间优先输出
+    fn l3_level(&self, bucket_idx: BucketIdx, max_orders: usize, mask_uid: bool) -> L3Level {
+        let bucket = &self.buckets[bucket_idx];
+        let mut chain = Vec::with_capacity(bucket.num_orders);
+        let mut current = Some(bucket.tail);
+        while let Some(idx) = current {
+            if self.orders[idx].parent != bucket_idx || chain.len() == bucket.num_orders {
+                break;
+            }
+            chain.push(idx);
+            current = self.orders[idx].next;
+        }
+
+        L3Level {
+            price: bucket.price,
+            volume: bucket.volume,
+            order_count: chain.len(),
+            orders: chain
+                .iter()
+                .rev()
+                .take(max_orders)
+                .map(|&idx| {
+                    let o = &self.orders[idx];
+                    L3Order::new(o.order_id, o.uid, o.price, o.size - o.filled, o.timestamp, mask_uid)
+                })
+                .collect(),
+        }
+    }
+
     /// 移除订单
     fn remove_order(&mut self, order_idx: OrderIdx) -> bool {
         let (bucket_idx, remaining, next, prev, price, action) = {
@@ -355,6 +480,7 @@ impl DirectOrderBook {
                 order.action,
             )
         };
+        self.touch_level(action, price);
 
         // 更新桶
         self.buckets[bucket_idx].volume -= remaining;
@@ -406,23 +532,25 @@ impl DirectOrderBook {
 
 impl super::OrderBook for DirectOrderBook {
     fn new_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        if self.order_id_index.contains_key(&cmd.order_id) {
+            return CommandResultCode::MatchingDuplicateOrderId;
+        }
+        // 最小成交数量、隐藏单与 GoodAfterTime 只有 Advanced 订单簿支持
+        if cmd.min_fill_size.is_some() || cmd.hidden || cmd.activate_time.is_some() {
+            return CommandResultCode::MatchingUnsupportedCommand;
+        }
+        let from = cmd.matcher_events.len();
         match cmd.order_type {
-            OrderType::Gtc => {
-                self.place_gtc(cmd);
-                CommandResultCode::Success
-            }
-            OrderType::Ioc => {
-                self.place_ioc(cmd);
-                CommandResultCode::Success
-            }
-            OrderType::FokBudget => {
-                self.place_fok_budget(cmd);
-                CommandResultCode::Success
-            }
-            _ => {
-                return CommandResultCode::MatchingUnsupportedCommand;
-            }
+            OrderType::Gtc => self.place_gtc(cmd),
+            OrderType::Ioc => self.place_ioc(cmd),
+            OrderType::Fok => self.place_fok(cmd),
+            OrderType::FokBudget => self.place_fok_budget(cmd),
+            // 预算为买入可花费的总金额，卖单没有对应含义
+            OrderType::IocBudget if cmd.action == OrderAction::Bid => self.place_ioc_budget(cmd),
+            _ => return CommandResultCode::MatchingUnsupportedCommand,
         }
+        LastTrade::update(&mut self.last_trade, &cmd.matcher_events[from..], cmd.timestamp);
+        CommandResultCode::Success
     }
 
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
@@ -430,12 +558,12 @@ impl super::OrderBook for DirectOrderBook {
             return CommandResultCode::MatchingUnknownOrderId;
         };
 
-        let (action, remaining, price) = {
+        let (action, remaining, price, reserve_price) = {
             let order = &self.orders[order_idx];
             if order.uid != cmd.uid {
-                return CommandResultCode::MatchingUnknownOrderId;
+                return CommandResultCode::MatchingUnauthorizedAction;
             }
-            (order.action, order.size - order.filled, order.price)
+            (order.action, order.size - order.filled, order.price, order.reserve_price)
         };
 
         self.order_id_index.remove(&cmd.order_id);
@@ -443,7 +571,7 @@ impl super::OrderBook for DirectOrderBook {
         self.orders.remove(order_idx);
 
         cmd.action = action;
-        cmd.matcher_events.push(MatcherTradeEvent::new_reject(remaining, price));
+        cmd.matcher_events.push(MatcherTradeEvent::new_reject(remaining, price, reserve_price));
 
         CommandResultCode::Success
     }
@@ -456,7 +584,7 @@ impl super::OrderBook for DirectOrderBook {
         let (uid, action, reserve_price, size) = {
             let order = &self.orders[order_idx];
             if order.uid != cmd.uid {
-                return CommandResultCode::MatchingUnknownOrderId;
+                return CommandResultCode::MatchingUnauthorizedAction;
             }
             (order.uid, order.action, order.reserve_price, order.size)
         };
@@ -489,6 +617,7 @@ impl super::OrderBook for DirectOrderBook {
         };
 
         let filled = self.try_match(&mut temp_cmd);
+        LastTrade::update(&mut self.last_trade, &temp_cmd.matcher_events, cmd.timestamp);
         cmd.matcher_events.extend(temp_cmd.matcher_events);
 
         if filled == self.orders[order_idx].size {
@@ -513,12 +642,12 @@ impl super::OrderBook for DirectOrderBook {
             return CommandResultCode::MatchingInvalidOrderSize;
         }
 
-        let (action, remaining, price, parent_idx) = {
+        let (action, remaining, price, reserve_price, parent_idx) = {
             let order = &self.orders[order_idx];
             if order.uid != cmd.uid {
-                return CommandResultCode::MatchingUnknownOrderId;
+                return CommandResultCode::MatchingUnauthorizedAction;
             }
-            (order.action, order.size - order.filled, order.price, order.parent)
+            (order.action, order.size - order.filled, order.price, order.reserve_price, order.parent)
         };
 
         let reduce_by = remaining.min(cmd.size);
@@ -529,17 +658,28 @@ impl super::OrderBook for DirectOrderBook {
             self.remove_order(order_idx);
             self.orders.remove(order_idx);
         } else {
+            self.touch_level(action, price);
             let order = &mut self.orders[order_idx];
             order.size -= reduce_by;
             self.buckets[parent_idx].volume -= reduce_by;
         }
 
         cmd.action = action;
-        cmd.matcher_events.push(MatcherTradeEvent::new_reject(reduce_by, price));
+        cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining - reduce_by));
 
         CommandResultCode::Success
     }
 
+    fn enable_level_updates(&mut self) {
+        self.level_changes.enable();
+    }
+
+    fn take_level_updates(&mut self, out: &mut Vec<LevelUpdate>) {
+        let mut changes = std::mem::take(&mut self.level_changes);
+        changes.drain(self, out);
+        self.level_changes = changes;
+    }
+
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification {
         &self.symbol_spec
     }
@@ -560,6 +700,28 @@ impl super::OrderBook for DirectOrderBook {
         data
     }
 
+    fn best_bid_offer(&self) -> super::TopOfBook {
+        (
+            self.bid_price_buckets.iter().next_back().map(|(price, &bucket_idx)| (*price, self.buckets[bucket_idx].volume)),
+            self.ask_price_buckets.iter().next().map(|(price, &bucket_idx)| (*price, self.buckets[bucket_idx].volume)),
+        )
+    }
+
+    fn quote_for_size(&self, action: OrderAction, size: Size) -> SizeQuote {
+        let level = |(price, &bucket_idx): (&Price, &BucketIdx)| (*price, self.buckets[bucket_idx].volume);
+        match action {
+            OrderAction::Bid => SizeQuote::from_levels(action, size, self.ask_price_buckets.iter().map(level)),
+            OrderAction::Ask => SizeQuote::from_levels(action, size, self.bid_price_buckets.iter().rev().map(level)),
+        }
+    }
+
+    fn get_l3_data(&self, depth: usize, max_orders_per_level: usize, mask_uid: bool) -> L3MarketData {
+        let mut data = L3MarketData::new(depth);
+        data.asks.extend(self.ask_price_buckets.values().take(depth).map(|&b| self.l3_level(b, max_orders_per_level, mask_uid)));
+        data.bids.extend(self.bid_price_buckets.values().rev().take(depth).map(|&b| self.l3_level(b, max_orders_per_level, mask_uid)));
+        data
+    }
+
     fn get_order_by_id(&self, order_id: OrderId) -> Option<(Price, OrderAction)> {
         self.order_id_index.get(&order_id).map(|&idx| {
             let order = &self.orders[idx];
@@ -567,6 +729,29 @@ impl super::OrderBook for DirectOrderBook {
         })
     }
 
+    fn get_open_order(&self, order_id: OrderId) -> Option<OpenOrder> {
+        self.order_id_index.get(&order_id).map(|&idx| {
+            let order = &self.orders[idx];
+            OpenOrder {
+                order_id,
+                uid: order.uid,
+                symbol: self.symbol_spec.symbol_id,
+                action: order.action,
+                order_type: OrderType::Gtc,
+                price: order.price,
+                reserve_price: order.reserve_price,
+                size: order.size,
+                remaining: order.size - order.filled,
+                timestamp: order.timestamp,
+                client_order_id: order.client_order_id,
+            }
+        })
+    }
+
+    fn get_all_orders(&self) -> Vec<OpenOrder> {
+        self.order_id_index.keys().filter_map(|&order_id| self.get_open_order(order_id)).collect()
+    }
+
     fn get_total_ask_volume(&self) -> Size {
         self.ask_price_buckets.values().map(|&idx| self.buckets[idx].volume).sum()
     }
@@ -583,6 +768,92 @@ impl super::OrderBook for DirectOrderBook {
         self.bid_price_buckets.len()
     }
 
+    fn get_level_volume(&self, action: OrderAction, price: Price) -> Size {
+        let buckets = match action {
+            OrderAction::Ask => &self.ask_price_buckets,
+            OrderAction::Bid => &self.bid_price_buckets,
+        };
+        buckets.get(&price).map_or(0, |&idx| self.buckets[idx].volume)
+    }
+
+    fn stats(&self) -> OrderBookStats {
+        let total_ask_volume = self.get_total_ask_volume();
+        let total_bid_volume = self.get_total_bid_volume();
+        OrderBookStats {
+            symbol: self.symbol_spec.symbol_id,
+            order_count: self.order_id_index.len(),
+            stop_order_count: 0,
+            ask_levels: self.ask_price_buckets.len(),
+            bid_levels: self.bid_price_buckets.len(),
+            total_ask_volume,
+            total_bid_volume,
+            visible_ask_volume: total_ask_volume,
+            visible_bid_volume: total_bid_volume,
+            ask_price_range: super::price_range(self.ask_price_buckets.keys()),
+            bid_price_range: super::price_range(self.bid_price_buckets.keys()),
+            pool: None,
+            last_trade: self.last_trade,
+        }
+    }
+
+    fn visit_levels(&self, side: OrderAction, visitor: &mut dyn FnMut(Price, Size) -> bool) {
+        match side {
+            OrderAction::Ask => self.ask_price_buckets.iter().all(|(price, &idx)| visitor(*price, self.buckets[idx].volume)),
+            OrderAction::Bid => self.bid_price_buckets.iter().rev().all(|(price, &idx)| visitor(*price, self.buckets[idx].volume)),
+        };
+    }
+
+    #[cfg(feature = "debug-invariants")]
+    fn check_invariants(&self) -> Result<(), crate::core::invariants::BookInvariantViolation> {
+        use crate::core::invariants::{ensure_book, ensure_not_crossed};
+        let symbol = self.symbol_spec.symbol_id;
+        let (best_bid, best_ask) = (self.bid_price_buckets.keys().next_back().copied(), self.ask_price_buckets.keys().next().copied());
+        ensure_not_crossed(symbol, best_bid, best_ask)?;
+        // 最优订单为最优档位中最早的订单
+        for (best_order, best_price) in [(self.best_bid_order, best_bid), (self.best_ask_order, best_ask)] {
+            let order = best_order.and_then(|idx| self.orders.get(idx));
+            ensure_book(symbol, order.map(|o| o.price) == best_price && order.is_none_or(|o| o.next.is_none()), || {
+                format!("最优订单 {best_order:?} 与最优档位 {best_price:?} 不符")
+            })?;
+        }
+
+        let mut count = 0;
+        for (action, buckets) in [(OrderAction::Ask, &self.ask_price_buckets), (OrderAction::Bid, &self.bid_price_buckets)] {
+            for (&price, &bucket_idx) in buckets {
+                let Some(bucket) = self.buckets.get(bucket_idx).filter(|b| b.price == price) else {
+                    return ensure_book(symbol, false, || format!("{action:?} 档位 {price} 指向无效的桶 {bucket_idx}"));
+                };
+                // 从桶尾沿 next 遍历本档订单（由新到旧）
+                let (mut volume, mut orders, mut current) = (0, 0, Some(bucket.tail));
+                while let Some(order) = current.and_then(|idx| self.orders.get(idx)).filter(|o| o.parent == bucket_idx) {
+                    if orders == bucket.num_orders {
+                        break;
+                    }
+                    let indexed = self.order_id_index.get(&order.order_id) == current.as_ref();
+                    ensure_book(symbol, indexed && order.size > order.filled && order.price == price && order.action == action, || {
+                        format!("订单 {} 与 {action:?} 档位 {price} 或索引不一致", order.order_id)
+                    })?;
+                    volume += order.size - order.filled;
+                    orders += 1;
+                    current = order.next;
+                }
+                ensure_book(symbol, orders > 0 && orders == bucket.num_orders && volume == bucket.volume, || {
+                    format!(
+                        "{action:?} 档位 {price}: 数量 {} / {} 笔与链表中 {orders} 笔订单剩余合计 {volume} 不符",
+                        bucket.volume, bucket.num_orders
+                    )
+                })?;
+                count += orders;
+            }
+        }
+        let buckets = self.ask_price_buckets.len() + self.bid_price_buckets.len();
+        ensure_book(symbol, self.buckets.len() == buckets, || format!("桶 {} 个，价格索引 {buckets} 档", self.buckets.len()))?;
+        // 每笔挂单都有索引且数量相同，索引不会指向已移除的订单
+        ensure_book(symbol, count == self.order_id_index.len() && count == self.orders.len(), || {
+            format!("索引 {} 条，订单池 {} 笔，挂单 {count} 笔", self.order_id_index.len(), self.orders.len())
+        })
+    }
+
     fn serialize_state(&self) -> crate::core::orderbook::OrderBookState {
         crate::core::orderbook::OrderBookState::Direct(self.clone())
     }
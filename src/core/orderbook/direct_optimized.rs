@@ -1,7 +1,8 @@
 use crate::api::*;
+use crate::core::orderbook::price_index::*;
 use crate::core::orderbook::simd_utils::*;
+use crate::core::orderbook::{BidBudget, LevelChanges};
 use ahash::AHashMap;
-use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 
 type OrderIdx = usize;
@@ -25,6 +26,16 @@ struct OrderColdData {
     action: OrderAction,
     reserve_price: Price,
     timestamp: i64,
+    client_order_id: u64,
+}
+
+/// 撮合热路径复用的临时缓冲区，避免每笔订单分配
+#[derive(Clone, Default)]
+struct MatchScratch {
+    order_indices: Vec<OrderIdx>,
+    sizes: Vec<Size>,
+    filled: Vec<Size>,
+    matched: Vec<Size>,
 }
 
 /// 预分配订单池（零分配）
@@ -59,6 +70,7 @@ impl OrderPool {
                     action: OrderAction::Bid,
                     reserve_price: 0,
                     timestamp: 0,
+                    client_order_id: 0,
                 };
                 capacity
             ],
@@ -75,32 +87,167 @@ impl OrderPool {
     #[inline]
     fn dealloc(&mut self, idx: OrderIdx) {
         self.hot.active[idx] = false;
+        self.hot.next[idx] = None;
+        self.hot.prev[idx] = None;
         self.free_list.push(idx);
     }
 }
 
-/// 价格桶（简化版）
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct PriceBucket {
+/// 自成交预防：处理 taker 与同一用户位于链表头的 maker 相遇
+///
+/// 返回 (taker 需撤销的数量, maker 是否已移除)
+fn apply_stp(
+    pool: &mut OrderPool,
+    order_index: &mut AHashMap<OrderId, OrderIdx>,
+    bucket: &mut PriceBucket,
+    maker_idx: OrderIdx,
+    taker_remaining: Size,
+    stp_mode: StpMode,
+    events: &mut Vec<MatcherTradeEvent>,
+) -> (Size, bool) {
+    let maker_remaining = pool.hot.sizes[maker_idx] - pool.hot.filled[maker_idx];
+    let (maker_cancel, taker_cancel) = stp_mode.cancel_sizes(maker_remaining, taker_remaining);
+
+    if maker_cancel == 0 {
+        return (taker_cancel, false);
+    }
+
+    pool.hot.sizes[maker_idx] -= maker_cancel;
+    bucket.volume -= maker_cancel;
+    events.push(MatcherTradeEvent::new_maker_reduce(
+        maker_cancel,
+        bucket.price,
+        pool.hot.order_ids[maker_idx],
+        pool.cold[maker_idx].uid,
+        pool.cold[maker_idx].action,
+        pool.cold[maker_idx].reserve_price,
+        maker_remaining - maker_cancel,
+    ).with_client_order_id(pool.cold[maker_idx].client_order_id));
+
+    let maker_removed = maker_cancel == maker_remaining;
+    if maker_removed {
+        let next = pool.hot.next[maker_idx];
+        order_index.remove(&pool.hot.order_ids[maker_idx]);
+        pool.dealloc(maker_idx);
+        if let Some(next) = next {
+            pool.hot.prev[next] = None;
+            bucket.head = next;
+        }
+    }
+
+    (taker_cancel, maker_removed)
+}
+
+/// 快照中的挂单记录
+#[derive(Clone, Serialize, Deserialize)]
+struct SnapshotOrder {
+    order_id: OrderId,
+    uid: UserId,
+    action: OrderAction,
     price: Price,
-    volume: Size,
-    head: OrderIdx, // 链表头（最早订单）
+    size: Size,
+    filled: Size,
+    reserve_price: Price,
+    timestamp: i64,
+    client_order_id: u64,
+}
+
+/// 紧凑快照格式：只保存有效挂单（按档位、时间优先顺序），恢复时重建订单池、价格档位与索引
+#[derive(Clone, Serialize, Deserialize)]
+struct OptimizedBookSnapshot {
+    symbol_spec: CoreSymbolSpecification,
+    capacity: usize,
+    use_simd: bool,
+    orders: Vec<SnapshotOrder>,
+    last_trade: Option<LastTrade>,
+}
+
+impl<I: PriceIndex> From<DirectOrderBookOptimized<I>> for OptimizedBookSnapshot {
+    fn from(book: DirectOrderBookOptimized<I>) -> Self {
+        let pool = &book.order_pool;
+        let mut orders = Vec::with_capacity(book.order_index.len());
+        for bucket in book.ask_buckets.iter().chain(book.bid_buckets.iter()) {
+            let mut current = Some(bucket.head);
+            while let Some(idx) = current {
+                orders.push(SnapshotOrder {
+                    order_id: pool.hot.order_ids[idx],
+                    uid: pool.cold[idx].uid,
+                    action: pool.cold[idx].action,
+                    price: pool.hot.prices[idx],
+                    size: pool.hot.sizes[idx],
+                    filled: pool.hot.filled[idx],
+                    reserve_price: pool.cold[idx].reserve_price,
+                    timestamp: pool.cold[idx].timestamp,
+                    client_order_id: pool.cold[idx].client_order_id,
+                });
+                current = pool.hot.next[idx];
+            }
+        }
+
+        Self {
+            capacity: pool.capacity,
+            use_simd: book.use_simd,
+            symbol_spec: book.symbol_spec,
+            orders,
+            last_trade: book.last_trade,
+        }
+    }
+}
+
+impl<I: PriceIndex> From<OptimizedBookSnapshot> for DirectOrderBookOptimized<I> {
+    fn from(snapshot: OptimizedBookSnapshot) -> Self {
+        let capacity = snapshot.capacity.max(snapshot.orders.len());
+        let mut book = Self {
+            symbol_spec: snapshot.symbol_spec,
+            order_pool: OrderPool::new(capacity),
+            ask_buckets: I::default(),
+            bid_buckets: I::default(),
+            order_index: AHashMap::with_capacity(capacity),
+            best_ask: None,
+            best_bid: None,
+            use_simd: snapshot.use_simd,
+            last_trade: snapshot.last_trade,
+            scratch: Box::default(),
+            level_changes: LevelChanges::default(),
+        };
+
+        for order in snapshot.orders {
+            let idx = book.order_pool.alloc().expect("订单池容量不足");
+            book.order_pool.hot.order_ids[idx] = order.order_id;
+            book.order_pool.hot.prices[idx] = order.price;
+            book.order_pool.hot.sizes[idx] = order.size;
+            book.order_pool.hot.filled[idx] = order.filled;
+            book.order_pool.hot.active[idx] = true;
+            book.order_pool.cold[idx] = OrderColdData {
+                uid: order.uid,
+                action: order.action,
+                reserve_price: order.reserve_price,
+                timestamp: order.timestamp,
+                client_order_id: order.client_order_id,
+            };
+            book.order_index.insert(order.order_id, idx);
+            book.insert_to_bucket(idx, order.price, order.action);
+        }
+        book
+    }
 }
 
 /// 高性能撮合引擎（深度优化版）
+///
+/// 价格索引可替换：默认 BTreePriceIndex，档位密集的交易对可使用 PriceLadder
 #[derive(Clone, Serialize, Deserialize)]
-pub struct DirectOrderBookOptimized {
+#[serde(into = "OptimizedBookSnapshot", from = "OptimizedBookSnapshot", bound = "")]
+pub struct DirectOrderBookOptimized<I: PriceIndex = BTreePriceIndex> {
     symbol_spec: CoreSymbolSpecification,
     
     // SOA 订单池（预分配）
     order_pool: OrderPool,
     
-    // 价格索引（ART 可替换 BTreeMap）
-    ask_buckets: BTreeMap<Price, PriceBucket>,
-    bid_buckets: BTreeMap<Price, PriceBucket>,
+    // 价格索引
+    ask_buckets: I,
+    bid_buckets: I,
     
     // SIMD 优化开关
-    #[serde(skip)]
     use_simd: bool,
     
     // 订单 ID 索引
@@ -109,22 +256,50 @@ pub struct DirectOrderBookOptimized {
     // 最优价格缓存
     best_ask: Option<Price>,
     best_bid: Option<Price>,
+
+    last_trade: Option<LastTrade>,
+
+    // 撮合临时缓冲区（不参与序列化）
+    scratch: Box<MatchScratch>,
+
+    // 档位变化记录（不参与序列化）
+    level_changes: LevelChanges,
 }
 
 impl DirectOrderBookOptimized {
     pub fn new(spec: CoreSymbolSpecification) -> Self {
+        Self::with_price_index(spec)
+    }
+}
+
+impl<I: PriceIndex> DirectOrderBookOptimized<I> {
+    /// 使用指定价格索引创建订单簿
+    pub fn with_price_index(spec: CoreSymbolSpecification) -> Self {
         Self {
             symbol_spec: spec,
             order_pool: OrderPool::new(100_000), // 预分配 10 万订单
-            ask_buckets: BTreeMap::new(),
-            bid_buckets: BTreeMap::new(),
+            ask_buckets: I::default(),
+            bid_buckets: I::default(),
             order_index: AHashMap::with_capacity(100_000),
             best_ask: None,
             best_bid: None,
             use_simd: true, // 默认启用 SIMD
+            last_trade: None,
+            scratch: Box::default(),
+            level_changes: LevelChanges::default(),
         }
     }
     
+    /// 档位数量即将变化：开启档位变化记录时记录其原数量
+    #[inline]
+    fn touch_level(&mut self, action: OrderAction, price: Price) {
+        if self.level_changes.should_touch(action, price) {
+            let buckets = if action == OrderAction::Ask { &self.ask_buckets } else { &self.bid_buckets };
+            let volume = buckets.get(price).map_or(0, |b| b.volume);
+            self.level_changes.touch(action, price, volume);
+        }
+    }
+
     /// 设置 SIMD 优化开关
     pub fn set_simd_enabled(&mut self, enabled: bool) {
         self.use_simd = enabled;
@@ -132,23 +307,7 @@ impl DirectOrderBookOptimized {
 
     /// GTC 下单
     fn place_gtc(&mut self, cmd: &mut OrderCommand) {
-        if self.order_index.contains_key(&cmd.order_id) {
-            let filled = if self.use_simd {
-                self.try_match_simd_batch(cmd)
-            } else {
-                self.try_match(cmd)
-            };
-            if filled < cmd.size {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price));
-            }
-            return;
-        }
-
-        let filled = if self.use_simd {
-            self.try_match_simd_batch(cmd)
-        } else {
-            self.try_match(cmd)
-        };
+        let filled = self.match_taker(cmd);
 
         if filled < cmd.size {
             if let Some(idx) = self.order_pool.alloc() {
@@ -165,6 +324,7 @@ impl DirectOrderBookOptimized {
                     action: cmd.action,
                     reserve_price: cmd.reserve_price,
                     timestamp: cmd.timestamp,
+                    client_order_id: cmd.client_order_id,
                 };
 
                 self.order_index.insert(cmd.order_id, idx);
@@ -175,112 +335,205 @@ impl DirectOrderBookOptimized {
 
     /// IOC 下单
     fn place_ioc(&mut self, cmd: &mut OrderCommand) {
-        let filled = if self.use_simd {
-            self.try_match_simd_batch(cmd)
-        } else {
-            self.try_match(cmd)
-        };
+        let filled = self.match_taker(cmd);
         if filled < cmd.size {
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price, cmd.reserve_price));
         }
     }
 
-    /// SIMD 批量撮合（优化版）
-    #[cfg(target_arch = "aarch64")]
-    fn try_match(&mut self, cmd: &mut OrderCommand) -> Size {
-        let is_bid = cmd.action == OrderAction::Bid;
-        let limit_price = cmd.price;
-        let mut filled = 0;
-
-        // 快速路径：检查最优价格
-        let best_price = if is_bid { self.best_ask } else { self.best_bid };
-        if let Some(best) = best_price {
-            if (is_bid && best > limit_price) || (!is_bid && best < limit_price) {
-                return 0;
-            }
+    /// FOK 下单：无法全部成交则整单拒绝
+    fn place_fok(&mut self, cmd: &mut OrderCommand) {
+        let stp_mode = cmd.stp_mode.or(self.symbol_spec.stp_mode);
+        if self.can_fill_completely(cmd.action, cmd.price, cmd.size, cmd.uid, stp_mode) {
+            self.place_ioc(cmd);
         } else {
-            return 0;
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
         }
+    }
 
-        let prices_to_match: Vec<Price> = if is_bid {
-            self.ask_buckets.range(..=limit_price).map(|(p, _)| *p).collect()
-        } else {
-            self.bid_buckets.range(limit_price..).rev().map(|(p, _)| *p).collect()
-        };
+    /// FOK_BUDGET 下单：全部成交的总金额须满足预算（price 字段为总预算）
+    fn place_fok_budget(&mut self, cmd: &mut OrderCommand) {
+        let stp_mode = cmd.stp_mode.or(self.symbol_spec.stp_mode);
+        let satisfied = self
+            .check_budget_to_fill(cmd.action, cmd.size, cmd.uid, stp_mode)
+            .is_some_and(|calculated| match cmd.action {
+                OrderAction::Bid => calculated <= cmd.price,
+                OrderAction::Ask => calculated >= cmd.price,
+            });
 
-        let mut need_update_best = false;
+        if !satisfied {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+            return;
+        }
 
-        for price in prices_to_match {
-            if filled >= cmd.size {
+        // 预算已校验，撮合时不限价
+        let budget = cmd.price;
+        cmd.price = if cmd.action == OrderAction::Bid { Price::MAX } else { 0 };
+        let filled = self.match_taker(cmd);
+        cmd.price = budget;
+        if filled < cmd.size {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price, cmd.reserve_price));
+        }
+    }
+
+    /// IOC_BUDGET 买单：逐档撮合，累计成交金额不超过预算（price 字段为总预算）
+    fn place_ioc_budget(&mut self, cmd: &mut OrderCommand) {
+        let budget = cmd.price;
+        let mut remaining = BidBudget::new(budget);
+        let mut size = cmd.size;
+        let mut filled = 0;
+
+        while let Some((price, volume)) = self.best_opposite_level(cmd.action) {
+            let step = remaining.affordable(price, (size - filled).min(volume));
+            if step <= 0 {
                 break;
             }
 
-            let buckets = if is_bid { &mut self.ask_buckets } else { &mut self.bid_buckets };
-            
-            if let Some(bucket) = buckets.get_mut(&price) {
-                let mut current_idx = bucket.head;
-                
-                while filled < cmd.size && self.order_pool.hot.active[current_idx] {
-                    let remaining = cmd.size - filled;
-                    let order_remaining = self.order_pool.hot.sizes[current_idx] - self.order_pool.hot.filled[current_idx];
-                    let trade_size = remaining.min(order_remaining);
+            // 单档撮合，成交价均为该档价格
+            let events_before = cmd.matcher_events.len();
+            cmd.size = step;
+            cmd.price = price;
+            let matched = self.match_taker(cmd);
+            filled += matched;
+            remaining.spend(price, matched);
+
+            // 自成交预防撤销了 taker 数量（拒绝事件已生成）
+            let cancelled = step - cmd.size;
+            size -= cancelled;
+            if cancelled > 0 || cmd.matcher_events.len() == events_before {
+                break;
+            }
+        }
 
-                    // 更新成交
-                    self.order_pool.hot.filled[current_idx] += trade_size;
-                    bucket.volume -= trade_size;
-                    filled += trade_size;
+        cmd.size = size;
+        cmd.price = budget;
+        if filled < size {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(size - filled, budget, cmd.reserve_price));
+        }
+    }
 
-                    // 生成事件
-                    let maker_uid = self.order_pool.cold[current_idx].uid;
-                    let reserve = if is_bid {
-                        cmd.reserve_price
-                    } else {
-                        self.order_pool.cold[current_idx].reserve_price
-                    };
-                    
-                    cmd.matcher_events.push(MatcherTradeEvent::new_trade(
-                        trade_size,
-                        price,
-                        self.order_pool.hot.order_ids[current_idx],
-                        maker_uid,
-                        reserve,
-                    ));
+    /// 对手方最优档位 (价格, 挂单量)
+    #[inline]
+    fn best_opposite_level(&self, action: OrderAction) -> Option<(Price, Size)> {
+        let level = match action {
+            OrderAction::Bid => self.ask_buckets.next_above(None),
+            OrderAction::Ask => self.bid_buckets.next_below(None),
+        };
+        level.map(|bucket| (bucket.price, bucket.volume))
+    }
 
-                    // 订单完成
-                    if self.order_pool.hot.filled[current_idx] >= self.order_pool.hot.sizes[current_idx] {
-                        let order_id = self.order_pool.hot.order_ids[current_idx];
-                        self.order_index.remove(&order_id);
-                        self.order_pool.dealloc(current_idx);
-                    }
+    /// 检查限价范围内的对手盘是否足以全部成交
+    ///
+    /// 启用自成交预防时逐单累计，同一用户的挂单不计入；遇到会撤销 taker 的策略则无法全部成交
+    fn can_fill_completely(&self, action: OrderAction, limit_price: Price, size: Size, uid: UserId, stp_mode: StpMode) -> bool {
+        let buckets: Box<dyn Iterator<Item = &PriceBucket>> = match action {
+            OrderAction::Bid => Box::new(self.ask_buckets.iter().take_while(|b| b.price <= limit_price)),
+            OrderAction::Ask => Box::new(self.bid_buckets.iter().rev().take_while(|b| b.price >= limit_price)),
+        };
 
-                    if let Some(next) = self.order_pool.hot.next[current_idx] {
-                        current_idx = next;
-                    } else {
-                        break;
+        let pool = &self.order_pool;
+        let mut available = 0;
+        for bucket in buckets {
+            if stp_mode == StpMode::None {
+                available += bucket.volume;
+            } else {
+                let mut current = Some(bucket.head);
+                while let Some(idx) = current {
+                    let remaining = pool.hot.sizes[idx] - pool.hot.filled[idx];
+                    if pool.cold[idx].uid != uid {
+                        available += remaining;
+                    } else if stp_mode.cancel_sizes(remaining, size - available).1 > 0 {
+                        return false;
+                    }
+                    if available >= size {
+                        return true;
                     }
+                    current = pool.hot.next[idx];
                 }
+            }
+            if available >= size {
+                return true;
+            }
+        }
+        false
+    }
 
-                if bucket.volume == 0 {
-                    buckets.remove(&price);
-                    need_update_best = true;
+    /// 计算全部成交所需总金额，对手盘不足或金额溢出时返回 None
+    ///
+    /// 与 can_fill_completely 相同：启用自成交预防时同一用户的挂单不计入，遇到会撤销 taker 的策略则无法全部成交
+    fn check_budget_to_fill(&self, action: OrderAction, mut size: Size, uid: UserId, stp_mode: StpMode) -> Option<i64> {
+        let buckets: Box<dyn Iterator<Item = &PriceBucket>> = match action {
+            OrderAction::Bid => Box::new(self.ask_buckets.iter()),
+            OrderAction::Ask => Box::new(self.bid_buckets.iter().rev()),
+        };
+
+        let pool = &self.order_pool;
+        let mut budget: i64 = 0;
+        for bucket in buckets {
+            if stp_mode == StpMode::None {
+                let take = size.min(bucket.volume);
+                budget = budget.checked_add(take.checked_mul(bucket.price)?)?;
+                size -= take;
+            } else {
+                let mut current = Some(bucket.head);
+                while let Some(idx) = current {
+                    let remaining = pool.hot.sizes[idx] - pool.hot.filled[idx];
+                    if pool.cold[idx].uid != uid {
+                        let take = size.min(remaining);
+                        budget = budget.checked_add(take.checked_mul(bucket.price)?)?;
+                        size -= take;
+                    } else if stp_mode.cancel_sizes(remaining, size).1 > 0 {
+                        return None;
+                    }
+                    if size == 0 {
+                        return Some(budget);
+                    }
+                    current = pool.hot.next[idx];
                 }
             }
+            if size == 0 {
+                return Some(budget);
+            }
         }
+        None
+    }
 
-        if need_update_best {
-            self.update_best_price(is_bid);
+    /// 选择撮合路径（启用自成交预防时走逐单路径）
+    #[inline]
+    fn match_taker(&mut self, cmd: &mut OrderCommand) -> Size {
+        if self.use_simd && cmd.stp_mode.or(self.symbol_spec.stp_mode) == StpMode::None {
+            self.try_match_simd_batch(cmd)
+        } else {
+            self.try_match(cmd)
         }
+    }
 
-        filled
+    /// 市价单：以保护价作为限价，剩余部分不挂单
+    fn place_market(&mut self, cmd: &mut OrderCommand) {
+        let best = if cmd.action == OrderAction::Bid { self.best_ask } else { self.best_bid };
+        let Some(best) = best else {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+            return;
+        };
+
+        let mut limit = self.symbol_spec.market_protection_price(cmd.action, best);
+        // 现货买单不能超过风控冻结的预留价格（R1 拒绝未给出预留价格的现货市价买单）
+        if cmd.action == OrderAction::Bid && self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair {
+            limit = limit.min(cmd.reserve_price);
+        }
+        cmd.price = limit;
+
+        self.place_ioc(cmd);
     }
 
-    /// 非 ARM 架构回退
-    #[cfg(not(target_arch = "aarch64"))]
+    /// 逐单撮合（支持自成交预防）
     fn try_match(&mut self, cmd: &mut OrderCommand) -> Size {
         let is_bid = cmd.action == OrderAction::Bid;
         let limit_price = cmd.price;
+        let stp_mode = cmd.stp_mode.or(self.symbol_spec.stp_mode);
         let mut filled = 0;
 
+        // 快速路径：检查最优价格
         let best_price = if is_bid { self.best_ask } else { self.best_bid };
         if let Some(best) = best_price {
             if (is_bid && best > limit_price) || (!is_bid && best < limit_price) {
@@ -290,33 +543,55 @@ impl DirectOrderBookOptimized {
             return 0;
         }
 
-        let prices_to_match: Vec<Price> = if is_bid {
-            self.ask_buckets.range(..=limit_price).map(|(p, _)| *p).collect()
-        } else {
-            self.bid_buckets.range(limit_price..).rev().map(|(p, _)| *p).collect()
-        };
-
         let mut need_update_best = false;
+        let mut last_price = None;
 
-        for price in prices_to_match {
-            if filled >= cmd.size {
+        while filled < cmd.size {
+            let Some(price) = self.next_level(is_bid, limit_price, last_price) else {
                 break;
-            }
+            };
+            last_price = Some(price);
+            self.touch_level(cmd.action.opposite(), price);
 
             let buckets = if is_bid { &mut self.ask_buckets } else { &mut self.bid_buckets };
             
-            if let Some(bucket) = buckets.get_mut(&price) {
+            if let Some(bucket) = buckets.get_mut(price) {
                 let mut current_idx = bucket.head;
                 
                 while filled < cmd.size && self.order_pool.hot.active[current_idx] {
+                    // 自成交预防
+                    if stp_mode != StpMode::None && self.order_pool.cold[current_idx].uid == cmd.uid {
+                        let next = self.order_pool.hot.next[current_idx];
+                        let (taker_cancel, maker_removed) = apply_stp(
+                            &mut self.order_pool,
+                            &mut self.order_index,
+                            bucket,
+                            current_idx,
+                            cmd.size - filled,
+                            stp_mode,
+                            &mut cmd.matcher_events,
+                        );
+                        if taker_cancel > 0 {
+                            cmd.size -= taker_cancel;
+                            cmd.matcher_events.push(MatcherTradeEvent::new_reject(taker_cancel, cmd.price, cmd.reserve_price));
+                        }
+                        match next {
+                            Some(next) if maker_removed => current_idx = next,
+                            _ => break,
+                        }
+                        continue;
+                    }
+
                     let remaining = cmd.size - filled;
                     let order_remaining = self.order_pool.hot.sizes[current_idx] - self.order_pool.hot.filled[current_idx];
                     let trade_size = remaining.min(order_remaining);
 
+                    // 更新成交
                     self.order_pool.hot.filled[current_idx] += trade_size;
                     bucket.volume -= trade_size;
                     filled += trade_size;
 
+                    // 生成事件
                     let maker_uid = self.order_pool.cold[current_idx].uid;
                     let reserve = if is_bid {
                         cmd.reserve_price
@@ -330,15 +605,23 @@ impl DirectOrderBookOptimized {
                         self.order_pool.hot.order_ids[current_idx],
                         maker_uid,
                         reserve,
-                    ));
+                        self.order_pool.hot.filled[current_idx] >= self.order_pool.hot.sizes[current_idx],
+                    ).with_client_order_id(self.order_pool.cold[current_idx].client_order_id));
 
-                    if self.order_pool.hot.filled[current_idx] >= self.order_pool.hot.sizes[current_idx] {
-                        let order_id = self.order_pool.hot.order_ids[current_idx];
-                        self.order_index.remove(&order_id);
-                        self.order_pool.dealloc(current_idx);
+                    // 订单未完成，taker 已成交完毕
+                    if self.order_pool.hot.filled[current_idx] < self.order_pool.hot.sizes[current_idx] {
+                        break;
                     }
 
-                    if let Some(next) = self.order_pool.hot.next[current_idx] {
+                    // 订单完成：推进链表头并回收
+                    let next = self.order_pool.hot.next[current_idx];
+                    let order_id = self.order_pool.hot.order_ids[current_idx];
+                    self.order_index.remove(&order_id);
+                    self.order_pool.dealloc(current_idx);
+
+                    if let Some(next) = next {
+                        self.order_pool.hot.prev[next] = None;
+                        bucket.head = next;
                         current_idx = next;
                     } else {
                         break;
@@ -346,7 +629,7 @@ impl DirectOrderBookOptimized {
                 }
 
                 if bucket.volume == 0 {
-                    buckets.remove(&price);
+                    buckets.remove(price);
                     need_update_best = true;
                 }
             }
@@ -375,26 +658,22 @@ impl DirectOrderBookOptimized {
             return 0;
         }
 
-        // 收集价格档位
-        let prices_to_match: Vec<Price> = if is_bid {
-            self.ask_buckets.range(..=limit_price).map(|(p, _)| *p).collect()
-        } else {
-            self.bid_buckets.range(limit_price..).rev().map(|(p, _)| *p).collect()
-        };
-
         let mut need_update_best = false;
-        let mut prices_to_remove = Vec::new();
+        let mut last_price = None;
+        let mut order_indices = std::mem::take(&mut self.scratch.order_indices);
 
-        for price in prices_to_match {
-            if filled >= cmd.size {
+        while filled < cmd.size {
+            let Some(price) = self.next_level(is_bid, limit_price, last_price) else {
                 break;
-            }
+            };
+            last_price = Some(price);
+            self.touch_level(cmd.action.opposite(), price);
 
             // 收集该价格档的所有活跃订单
-            let mut order_indices = Vec::new();
+            order_indices.clear();
             {
                 let buckets = if is_bid { &self.ask_buckets } else { &self.bid_buckets };
-                if let Some(bucket) = buckets.get(&price) {
+                if let Some(bucket) = buckets.get(price) {
                     let mut current_idx = bucket.head;
                     
                     while self.order_pool.hot.active[current_idx] {
@@ -449,20 +728,33 @@ impl DirectOrderBookOptimized {
                         self.order_pool.hot.order_ids[idx],
                         maker_uid,
                         reserve,
-                    ));
+                        self.order_pool.hot.filled[idx] >= self.order_pool.hot.sizes[idx],
+                    ).with_client_order_id(self.order_pool.cold[idx].client_order_id));
+                }
+            }
 
-                    if self.order_pool.hot.filled[idx] >= self.order_pool.hot.sizes[idx] {
-                        let order_id = self.order_pool.hot.order_ids[idx];
-                        self.order_index.remove(&order_id);
-                        self.order_pool.dealloc(idx);
-                    }
+            // 回收完全成交的订单（按链表顺序成交，完成的订单必为前缀）
+            let mut new_head = None;
+            for &idx in &order_indices {
+                if self.order_pool.hot.filled[idx] >= self.order_pool.hot.sizes[idx] {
+                    let order_id = self.order_pool.hot.order_ids[idx];
+                    self.order_index.remove(&order_id);
+                    self.order_pool.dealloc(idx);
+                } else {
+                    new_head = Some(idx);
+                    break;
                 }
             }
 
             // 更新桶信息
             {
                 let buckets = if is_bid { &mut self.ask_buckets } else { &mut self.bid_buckets };
-                if let Some(bucket) = buckets.get_mut(&price) {
+                if let Some(bucket) = buckets.get_mut(price) {
+                    if let Some(head) = new_head {
+                        self.order_pool.hot.prev[head] = None;
+                        bucket.head = head;
+                    }
+
                     // 重新计算桶的总量
                     let mut new_volume = 0;
                     for &idx in &order_indices {
@@ -473,21 +765,13 @@ impl DirectOrderBookOptimized {
                     bucket.volume = new_volume;
                     
                     if bucket.volume == 0 {
-                        prices_to_remove.push(price);
+                        buckets.remove(price);
                         need_update_best = true;
                     }
                 }
             }
         }
-
-        // 清理空桶
-        for price in prices_to_remove {
-            if is_bid {
-                self.ask_buckets.remove(&price);
-            } else {
-                self.bid_buckets.remove(&price);
-            }
-        }
+        self.scratch.order_indices = order_indices;
 
         if need_update_best {
             self.update_best_price(is_bid);
@@ -507,17 +791,16 @@ impl DirectOrderBookOptimized {
         taker_reserve: Price,
         events: &mut Vec<MatcherTradeEvent>,
     ) -> Size {
-        // 收集订单数据（SOA 优势）
-        let sizes: Vec<i64> = order_indices.iter()
-            .map(|&idx| self.order_pool.hot.sizes[idx])
-            .collect();
-        
-        let filled: Vec<i64> = order_indices.iter()
-            .map(|&idx| self.order_pool.hot.filled[idx])
-            .collect();
+        // 收集订单数据（SOA 优势），复用缓冲区
+        let MatchScratch { sizes, filled, matched: matched_sizes, .. } = &mut *self.scratch;
+        sizes.clear();
+        sizes.extend(order_indices.iter().map(|&idx| self.order_pool.hot.sizes[idx]));
+        filled.clear();
+        filled.extend(order_indices.iter().map(|&idx| self.order_pool.hot.filled[idx]));
 
         // SIMD 批量计算匹配量
-        let (matched_sizes, _total_matched) = simd_batch_match_prepare(&sizes, &filled, need_size);
+        simd_batch_match_prepare_into(sizes, filled, need_size, matched_sizes);
+        let matched_sizes = std::mem::take(&mut self.scratch.matched);
 
         // 应用匹配结果
         let mut actual_filled = 0i64;
@@ -540,10 +823,12 @@ impl DirectOrderBookOptimized {
                     self.order_pool.hot.order_ids[idx],
                     maker_uid,
                     reserve,
-                ));
+                    self.order_pool.hot.filled[idx] >= self.order_pool.hot.sizes[idx],
+                ).with_client_order_id(self.order_pool.cold[idx].client_order_id));
             }
         }
 
+        self.scratch.matched = matched_sizes;
         actual_filled
     }
 
@@ -551,6 +836,10 @@ impl DirectOrderBookOptimized {
     fn insert_to_bucket(&mut self, order_idx: OrderIdx, price: Price, action: OrderAction) {
         let size = self.order_pool.hot.sizes[order_idx] - self.order_pool.hot.filled[order_idx];
         let is_ask = action == OrderAction::Ask;
+        self.touch_level(action, price);
+        // 改价重挂的订单可能残留旧链接
+        self.order_pool.hot.next[order_idx] = None;
+        self.order_pool.hot.prev[order_idx] = None;
 
         let buckets = if is_ask {
             &mut self.ask_buckets
@@ -558,49 +847,59 @@ impl DirectOrderBookOptimized {
             &mut self.bid_buckets
         };
 
-        let is_new = !buckets.contains_key(&price);
-
-        buckets
-            .entry(price)
-            .and_modify(|bucket| {
-                bucket.volume += size;
-                let old_head = bucket.head;
-                self.order_pool.hot.next[order_idx] = Some(old_head);
-                self.order_pool.hot.prev[old_head] = Some(order_idx);
-                bucket.head = order_idx;
-            })
-            .or_insert_with(|| {
-                PriceBucket {
-                    price,
-                    volume: size,
-                    head: order_idx,
-                }
+        if let Some(bucket) = buckets.get_mut(price) {
+            // 追加到链表尾部，保证同价位时间优先
+            bucket.volume += size;
+            let old_tail = bucket.tail;
+            self.order_pool.hot.prev[order_idx] = Some(old_tail);
+            self.order_pool.hot.next[old_tail] = Some(order_idx);
+            bucket.tail = order_idx;
+        } else {
+            buckets.insert(PriceBucket {
+                price,
+                volume: size,
+                head: order_idx,
+                tail: order_idx,
             });
-
-        if is_new {
             self.update_best_price(is_ask);
         }
     }
 
+    /// 限价范围内、last 之后的下一个对手方档位（买单按价格升序遍历卖盘，卖单按降序遍历买盘）
+    #[inline]
+    fn next_level(&self, is_bid: bool, limit_price: Price, last: Option<Price>) -> Option<Price> {
+        let level = if is_bid {
+            self.ask_buckets.next_above(last).filter(|b| b.price <= limit_price)
+        } else {
+            self.bid_buckets.next_below(last).filter(|b| b.price >= limit_price)
+        };
+        level.map(|bucket| bucket.price)
+    }
+
     /// 更新最优价格缓存
     fn update_best_price(&mut self, is_ask: bool) {
         if is_ask {
-            self.best_ask = self.ask_buckets.keys().next().copied();
+            self.best_ask = self.ask_buckets.next_above(None).map(|b| b.price);
         } else {
-            self.best_bid = self.bid_buckets.keys().next_back().copied();
+            self.best_bid = self.bid_buckets.next_below(None).map(|b| b.price);
         }
     }
 
     /// 取消订单
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
         if let Some(&order_idx) = self.order_index.get(&cmd.order_id) {
+            if self.order_pool.cold[order_idx].uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
             let price = self.order_pool.hot.prices[order_idx];
             let action = self.order_pool.cold[order_idx].action;
             let remaining = self.order_pool.hot.sizes[order_idx] - self.order_pool.hot.filled[order_idx];
+            let reserve_price = self.order_pool.cold[order_idx].reserve_price;
 
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(remaining, price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(remaining, price, reserve_price));
             cmd.action = action;
 
+            self.unlink_order(order_idx);
             self.order_index.remove(&cmd.order_id);
             self.order_pool.dealloc(order_idx);
 
@@ -609,33 +908,230 @@ impl DirectOrderBookOptimized {
             CommandResultCode::MatchingUnknownOrderId
         }
     }
+
+    /// 移动订单（原地改价重挂，不产生中间撤单事件）
+    fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(&order_idx) = self.order_index.get(&cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+
+        let (uid, action, reserve_price) = {
+            let cold = &self.order_pool.cold[order_idx];
+            if cold.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            (cold.uid, cold.action, cold.reserve_price)
+        };
+
+        // 风险检查：买单不能超过预留价格
+        if self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair
+            && action == OrderAction::Bid
+            && cmd.price > reserve_price
+        {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        // 从原价格桶摘除（改价即失去时间优先级）
+        self.unlink_order(order_idx);
+        self.order_pool.hot.prices[order_idx] = cmd.price;
+        cmd.action = action;
+
+        // 新价格穿越盘口时先撮合剩余数量
+        let remaining = self.order_pool.hot.sizes[order_idx] - self.order_pool.hot.filled[order_idx];
+        let mut temp_cmd = OrderCommand {
+            uid,
+            order_id: cmd.order_id,
+            symbol: cmd.symbol,
+            price: cmd.price,
+            size: remaining,
+            action,
+            reserve_price,
+            order_type: OrderType::Gtc,
+            stp_mode: cmd.stp_mode,
+            ..Default::default()
+        };
+
+        let filled = self.match_taker(&mut temp_cmd);
+        cmd.matcher_events.extend(temp_cmd.matcher_events);
+
+        // 自成交预防撤销的部分不再挂单
+        let remaining = temp_cmd.size;
+        self.order_pool.hot.sizes[order_idx] = self.order_pool.hot.filled[order_idx] + remaining;
+
+        if filled == remaining {
+            // 完全成交
+            self.order_index.remove(&cmd.order_id);
+            self.order_pool.dealloc(order_idx);
+        } else {
+            // 剩余部分挂到新价格档位队尾
+            self.order_pool.hot.filled[order_idx] += filled;
+            self.insert_to_bucket(order_idx, cmd.price, action);
+        }
+
+        CommandResultCode::Success
+    }
+
+    /// 减少订单数量（原地修改，减至零时释放订单）
+    fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(&order_idx) = self.order_index.get(&cmd.order_id) else {
+            return CommandResultCode::MatchingUnknownOrderId;
+        };
+
+        if cmd.size <= 0 {
+            return CommandResultCode::MatchingInvalidOrderSize;
+        }
+
+        let (action, reserve_price) = {
+            let cold = &self.order_pool.cold[order_idx];
+            if cold.uid != cmd.uid {
+                return CommandResultCode::MatchingUnauthorizedAction;
+            }
+            (cold.action, cold.reserve_price)
+        };
+
+        let price = self.order_pool.hot.prices[order_idx];
+        let remaining = self.order_pool.hot.sizes[order_idx] - self.order_pool.hot.filled[order_idx];
+        let reduce_by = remaining.min(cmd.size);
+
+        if reduce_by == remaining {
+            self.unlink_order(order_idx);
+            self.order_index.remove(&cmd.order_id);
+            self.order_pool.dealloc(order_idx);
+        } else {
+            self.touch_level(action, price);
+            self.order_pool.hot.sizes[order_idx] -= reduce_by;
+            let buckets = if action == OrderAction::Ask {
+                &mut self.ask_buckets
+            } else {
+                &mut self.bid_buckets
+            };
+            if let Some(bucket) = buckets.get_mut(price) {
+                bucket.volume -= reduce_by;
+            }
+        }
+
+        cmd.action = action;
+        cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining - reduce_by));
+
+        CommandResultCode::Success
+    }
+
+    /// L3 档位快照：从链表头按时间优先遍历
+    fn l3_level(&self, bucket: &PriceBucket, max_orders: usize, mask_uid: bool) -> L3Level {
+        let hot = &self.order_pool.hot;
+        let mut orders = Vec::with_capacity(max_orders.min(16));
+        let mut order_count = 0;
+        let mut current = Some(bucket.head);
+        while let Some(idx) = current {
+            if orders.len() < max_orders {
+                let cold = &self.order_pool.cold[idx];
+                orders.push(L3Order::new(
+                    hot.order_ids[idx],
+                    cold.uid,
+                    hot.prices[idx],
+                    hot.sizes[idx] - hot.filled[idx],
+                    cold.timestamp,
+                    mask_uid,
+                ));
+            }
+            order_count += 1;
+            current = hot.next[idx];
+        }
+
+        L3Level {
+            price: bucket.price,
+            volume: bucket.volume,
+            order_count,
+            orders,
+        }
+    }
+
+    /// 从价格桶链表中摘除订单（维护桶总量、空桶和最优价格缓存）
+    fn unlink_order(&mut self, order_idx: OrderIdx) {
+        let price = self.order_pool.hot.prices[order_idx];
+        let action = self.order_pool.cold[order_idx].action;
+        let is_ask = action == OrderAction::Ask;
+        self.touch_level(action, price);
+        let remaining = self.order_pool.hot.sizes[order_idx] - self.order_pool.hot.filled[order_idx];
+        let prev = self.order_pool.hot.prev[order_idx];
+        let next = self.order_pool.hot.next[order_idx];
+
+        if let Some(prev_idx) = prev {
+            self.order_pool.hot.next[prev_idx] = next;
+        }
+        if let Some(next_idx) = next {
+            self.order_pool.hot.prev[next_idx] = prev;
+        }
+
+        let buckets = if is_ask { &mut self.ask_buckets } else { &mut self.bid_buckets };
+        let mut bucket_emptied = false;
+        if let Some(bucket) = buckets.get_mut(price) {
+            bucket.volume -= remaining;
+            match (prev, next) {
+                (None, None) => bucket_emptied = true,
+                (None, Some(next_idx)) => bucket.head = next_idx,
+                (Some(prev_idx), None) => bucket.tail = prev_idx,
+                (Some(_), Some(_)) => {}
+            }
+        }
+
+        if bucket_emptied {
+            buckets.remove(price);
+            self.update_best_price(is_ask);
+        }
+    }
 }
 
-impl super::OrderBook for DirectOrderBookOptimized {
+impl<I: PriceIndex> super::OrderBook for DirectOrderBookOptimized<I>
+where
+    Self: Into<super::OrderBookState>,
+{
     fn new_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        if self.order_index.contains_key(&cmd.order_id) {
+            return CommandResultCode::MatchingDuplicateOrderId;
+        }
+        // 最小成交数量、隐藏单与 GoodAfterTime 只有 Advanced 订单簿支持
+        if cmd.min_fill_size.is_some() || cmd.hidden || cmd.activate_time.is_some() {
+            return CommandResultCode::MatchingUnsupportedCommand;
+        }
+        let from = cmd.matcher_events.len();
         match cmd.order_type {
-            OrderType::Gtc => {
-                self.place_gtc(cmd);
-                CommandResultCode::Success
-            }
-            OrderType::Ioc => {
-                self.place_ioc(cmd);
-                CommandResultCode::Success
-            }
-            _ => CommandResultCode::MatchingUnsupportedCommand,
+            OrderType::Gtc => self.place_gtc(cmd),
+            OrderType::Ioc => self.place_ioc(cmd),
+            OrderType::Market => self.place_market(cmd),
+            OrderType::Fok => self.place_fok(cmd),
+            OrderType::FokBudget => self.place_fok_budget(cmd),
+            // 预算为买入可花费的总金额，卖单没有对应含义
+            OrderType::IocBudget if cmd.action == OrderAction::Bid => self.place_ioc_budget(cmd),
+            _ => return CommandResultCode::MatchingUnsupportedCommand,
         }
+        LastTrade::update(&mut self.last_trade, &cmd.matcher_events[from..], cmd.timestamp);
+        CommandResultCode::Success
     }
 
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
         self.cancel_order(cmd)
     }
 
-    fn move_order(&mut self, _cmd: &mut OrderCommand) -> CommandResultCode {
-        CommandResultCode::MatchingUnsupportedCommand // 简化实现
+    fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let from = cmd.matcher_events.len();
+        let code = self.move_order(cmd);
+        LastTrade::update(&mut self.last_trade, &cmd.matcher_events[from..], cmd.timestamp);
+        code
     }
 
-    fn reduce_order(&mut self, _cmd: &mut OrderCommand) -> CommandResultCode {
-        CommandResultCode::MatchingUnsupportedCommand // 简化实现
+    fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        self.reduce_order(cmd)
+    }
+
+    fn enable_level_updates(&mut self) {
+        self.level_changes.enable();
+    }
+
+    fn take_level_updates(&mut self, out: &mut Vec<LevelUpdate>) {
+        let mut changes = std::mem::take(&mut self.level_changes);
+        changes.drain(self, out);
+        self.level_changes = changes;
     }
 
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification {
@@ -645,19 +1141,41 @@ impl super::OrderBook for DirectOrderBookOptimized {
     fn get_l2_data(&self, depth: usize) -> L2MarketData {
         let mut data = L2MarketData::new(depth);
 
-        for (price, bucket) in self.ask_buckets.iter().take(depth) {
-            data.ask_prices.push(*price);
+        for bucket in self.ask_buckets.iter().take(depth) {
+            data.ask_prices.push(bucket.price);
             data.ask_volumes.push(bucket.volume);
         }
 
-        for (price, bucket) in self.bid_buckets.iter().rev().take(depth) {
-            data.bid_prices.push(*price);
+        for bucket in self.bid_buckets.iter().rev().take(depth) {
+            data.bid_prices.push(bucket.price);
             data.bid_volumes.push(bucket.volume);
         }
 
         data
     }
 
+    fn best_bid_offer(&self) -> super::TopOfBook {
+        (
+            self.bid_buckets.iter().next_back().map(|bucket| (bucket.price, bucket.volume)),
+            self.ask_buckets.iter().next().map(|bucket| (bucket.price, bucket.volume)),
+        )
+    }
+
+    fn quote_for_size(&self, action: OrderAction, size: Size) -> SizeQuote {
+        let level = |bucket: &PriceBucket| (bucket.price, bucket.volume);
+        match action {
+            OrderAction::Bid => SizeQuote::from_levels(action, size, self.ask_buckets.iter().map(level)),
+            OrderAction::Ask => SizeQuote::from_levels(action, size, self.bid_buckets.iter().rev().map(level)),
+        }
+    }
+
+    fn get_l3_data(&self, depth: usize, max_orders_per_level: usize, mask_uid: bool) -> L3MarketData {
+        let mut data = L3MarketData::new(depth);
+        data.asks.extend(self.ask_buckets.iter().take(depth).map(|b| self.l3_level(b, max_orders_per_level, mask_uid)));
+        data.bids.extend(self.bid_buckets.iter().rev().take(depth).map(|b| self.l3_level(b, max_orders_per_level, mask_uid)));
+        data
+    }
+
     fn get_order_by_id(&self, order_id: OrderId) -> Option<(Price, OrderAction)> {
         self.order_index.get(&order_id).map(|&idx| {
             let price = self.order_pool.hot.prices[idx];
@@ -666,12 +1184,36 @@ impl super::OrderBook for DirectOrderBookOptimized {
         })
     }
 
+    fn get_open_order(&self, order_id: OrderId) -> Option<OpenOrder> {
+        self.order_index.get(&order_id).map(|&idx| {
+            let hot = &self.order_pool.hot;
+            let cold = &self.order_pool.cold[idx];
+            OpenOrder {
+                order_id,
+                uid: cold.uid,
+                symbol: self.symbol_spec.symbol_id,
+                action: cold.action,
+                order_type: OrderType::Gtc,
+                price: hot.prices[idx],
+                reserve_price: cold.reserve_price,
+                size: hot.sizes[idx],
+                remaining: hot.sizes[idx] - hot.filled[idx],
+                timestamp: cold.timestamp,
+                client_order_id: cold.client_order_id,
+            }
+        })
+    }
+
+    fn get_all_orders(&self) -> Vec<OpenOrder> {
+        self.order_index.keys().filter_map(|&order_id| self.get_open_order(order_id)).collect()
+    }
+
     fn get_total_ask_volume(&self) -> Size {
-        self.ask_buckets.values().map(|b| b.volume).sum()
+        self.ask_buckets.iter().map(|b| b.volume).sum()
     }
 
     fn get_total_bid_volume(&self) -> Size {
-        self.bid_buckets.values().map(|b| b.volume).sum()
+        self.bid_buckets.iter().map(|b| b.volume).sum()
     }
 
     fn get_ask_buckets_count(&self) -> usize {
@@ -682,11 +1224,85 @@ impl super::OrderBook for DirectOrderBookOptimized {
         self.bid_buckets.len()
     }
 
+    fn get_level_volume(&self, action: OrderAction, price: Price) -> Size {
+        let buckets = match action {
+            OrderAction::Ask => &self.ask_buckets,
+            OrderAction::Bid => &self.bid_buckets,
+        };
+        buckets.get(price).map_or(0, |b| b.volume)
+    }
+
+    fn stats(&self) -> OrderBookStats {
+        let total_ask_volume = self.get_total_ask_volume();
+        let total_bid_volume = self.get_total_bid_volume();
+        let pool = &self.order_pool;
+        OrderBookStats {
+            symbol: self.symbol_spec.symbol_id,
+            order_count: self.order_index.len(),
+            stop_order_count: 0,
+            ask_levels: self.ask_buckets.len(),
+            bid_levels: self.bid_buckets.len(),
+            total_ask_volume,
+            total_bid_volume,
+            visible_ask_volume: total_ask_volume,
+            visible_bid_volume: total_bid_volume,
+            ask_price_range: super::price_range(self.ask_buckets.iter().map(|b| &b.price)),
+            bid_price_range: super::price_range(self.bid_buckets.iter().map(|b| &b.price)),
+            pool: Some(PoolUtilization { used: pool.capacity - pool.free_list.len(), capacity: pool.capacity }),
+            last_trade: self.last_trade,
+        }
+    }
+
+    fn visit_levels(&self, side: OrderAction, visitor: &mut dyn FnMut(Price, Size) -> bool) {
+        match side {
+            OrderAction::Ask => self.ask_buckets.iter().all(|bucket| visitor(bucket.price, bucket.volume)),
+            OrderAction::Bid => self.bid_buckets.iter().rev().all(|bucket| visitor(bucket.price, bucket.volume)),
+        };
+    }
+
+    #[cfg(feature = "debug-invariants")]
+    fn check_invariants(&self) -> Result<(), crate::core::invariants::BookInvariantViolation> {
+        use crate::core::invariants::{ensure_book, ensure_not_crossed};
+        let symbol = self.symbol_spec.symbol_id;
+        let best_bid = self.bid_buckets.next_below(None).map(|b| b.price);
+        let best_ask = self.ask_buckets.next_above(None).map(|b| b.price);
+        ensure_not_crossed(symbol, best_bid, best_ask)?;
+        ensure_book(symbol, (self.best_bid, self.best_ask) == (best_bid, best_ask), || {
+            format!("最优价缓存 {:?}/{:?} 与档位 {best_bid:?}/{best_ask:?} 不符", self.best_bid, self.best_ask)
+        })?;
+
+        let hot = &self.order_pool.hot;
+        let mut count = 0;
+        for (action, buckets) in [(OrderAction::Ask, &self.ask_buckets), (OrderAction::Bid, &self.bid_buckets)] {
+            for bucket in buckets.iter() {
+                let price = bucket.price;
+                // 从链表头沿 next 遍历到链表尾，步数不超过挂单总数
+                let (mut volume, mut orders, mut current) = (0, 0, Some(bucket.head));
+                while let Some(idx) = current {
+                    let indexed = hot.active[idx] && self.order_index.get(&hot.order_ids[idx]) == Some(&idx);
+                    let remaining = hot.sizes[idx] - hot.filled[idx];
+                    ensure_book(symbol, indexed && remaining > 0 && hot.prices[idx] == price && self.order_pool.cold[idx].action == action, || {
+                        format!("订单 {} 与 {action:?} 档位 {price} 或索引不一致", hot.order_ids[idx])
+                    })?;
+                    volume += remaining;
+                    orders += 1;
+                    current = if idx == bucket.tail || orders > self.order_index.len() { None } else { hot.next[idx] };
+                }
+                ensure_book(symbol, orders <= self.order_index.len() && volume == bucket.volume, || {
+                    format!("{action:?} 档位 {price}: 数量 {} 与链表中 {orders} 笔订单剩余合计 {volume} 不符", bucket.volume)
+                })?;
+                count += orders;
+            }
+        }
+        let used = self.order_pool.capacity - self.order_pool.free_list.len();
+        // 每笔挂单都有索引且数量相同，索引不会指向已移除的订单
+        ensure_book(symbol, count == self.order_index.len() && count == used, || {
+            format!("索引 {} 条，订单池占用 {used}，挂单 {count} 笔", self.order_index.len())
+        })
+    }
+
     fn serialize_state(&self) -> crate::core::orderbook::OrderBookState {
-        // 简化：暂不支持序列化优化版本
-        crate::core::orderbook::OrderBookState::Direct(
-            crate::core::orderbook::DirectOrderBook::new(self.symbol_spec.clone())
-        )
+        self.clone().into()
     }
 }
 
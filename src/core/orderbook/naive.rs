@@ -2,6 +2,7 @@ use crate::api::*;
 use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use crate::core::orderbook::{BidBudget, LevelChanges};
 
 /// 订单记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,7 +15,7 @@ pub struct Order {
     pub action: OrderAction,
     pub reserve_price: Price,
     pub timestamp: i64,
-    pub user_cookie: i64, // 用户自定义标记（对标 exchange-core）
+    pub client_order_id: u64, // 客户端订单号（对标 exchange-core 的 userCookie）
 }
 
 impl Order {
@@ -55,6 +56,21 @@ impl OrdersBucket {
         }
     }
 
+    /// L3 档位快照（按时间优先顺序）
+    fn to_l3_level(&self, max_orders: usize, mask_uid: bool) -> L3Level {
+        L3Level {
+            price: self.price,
+            volume: self.total_volume,
+            order_count: self.orders.len(),
+            orders: self
+                .orders
+                .iter()
+                .take(max_orders)
+                .map(|o| L3Order::new(o.order_id, o.uid, o.price, o.remaining(), o.timestamp, mask_uid))
+                .collect(),
+        }
+    }
+
     /// 撮合：返回成交量和事件
     fn match_order(&mut self, taker_size: Size, _taker_uid: UserId) -> (Size, SmallVec<[MatcherTradeEvent; 4]>) {
         let mut matched_size = 0;
@@ -68,14 +84,19 @@ impl OrdersBucket {
             if match_size > 0 {
                 order.filled += match_size;
                 matched_size += match_size;
-
-                events.push(MatcherTradeEvent::new_trade(
-                    match_size,
-                    self.price,
-                    order.order_id,
-                    order.uid,
-                    order.reserve_price,
-                ));
+                self.total_volume -= match_size;
+
+                events.push(
+                    MatcherTradeEvent::new_trade(
+                        match_size,
+                        self.price,
+                        order.order_id,
+                        order.uid,
+                        order.reserve_price,
+                        order.filled == order.size,
+                    )
+                    .with_client_order_id(order.client_order_id),
+                );
 
                 if order.filled == order.size {
                     to_remove.push(order.order_id);
@@ -104,11 +125,16 @@ pub struct NaiveOrderBook {
     symbol_spec: CoreSymbolSpecification,
     ask_buckets: BTreeMap<Price, OrdersBucket>, // 卖单（价格升序）
     bid_buckets: BTreeMap<Price, OrdersBucket>, // 买单（价格降序）
-    order_map: AHashMap<OrderId, (Price, OrderAction)>, // 运行时使用 AHashMap
+    order_map: AHashMap<OrderId, (Price, OrderAction, UserId)>, // 价格、方向、所属用户
     
     // 性能优化：缓存最优价格
     best_ask_price: Option<Price>,
     best_bid_price: Option<Price>,
+
+    last_trade: Option<LastTrade>,
+
+    #[serde(skip)]
+    level_changes: LevelChanges,
 }
 
 impl NaiveOrderBook {
@@ -120,6 +146,17 @@ impl NaiveOrderBook {
             order_map: AHashMap::with_capacity(1024), // 预分配容量
             best_ask_price: None,
             best_bid_price: None,
+            last_trade: None,
+            level_changes: LevelChanges::default(),
+        }
+    }
+
+    /// 档位数量即将变化：开启档位变化记录时记录其原数量
+    #[inline]
+    fn touch_level(&mut self, action: OrderAction, price: Price) {
+        if self.level_changes.should_touch(action, price) {
+            let volume = super::OrderBook::get_level_volume(self, action, price);
+            self.level_changes.touch(action, price, volume);
         }
     }
     
@@ -131,15 +168,6 @@ impl NaiveOrderBook {
 
     /// GTC 下单
     fn place_gtc(&mut self, cmd: &mut OrderCommand) {
-        // 检查重复订单 ID
-        if self.order_map.contains_key(&cmd.order_id) {
-            let filled = self.try_match(cmd);
-            if filled < cmd.size {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size - filled, cmd.price));
-            }
-            return;
-        }
-
         // 1. 尝试立即撮合
         let filled = self.try_match(cmd);
 
@@ -154,10 +182,11 @@ impl NaiveOrderBook {
                 action: cmd.action,
                 reserve_price: cmd.reserve_price,
                 timestamp: cmd.timestamp,
-                user_cookie: 0,
+                client_order_id: cmd.client_order_id,
             };
 
-            self.order_map.insert(cmd.order_id, (cmd.price, cmd.action));
+            self.order_map.insert(cmd.order_id, (cmd.price, cmd.action, cmd.uid));
+            self.touch_level(cmd.action, cmd.price);
 
             match cmd.action {
                 OrderAction::Ask => {
@@ -188,7 +217,7 @@ impl NaiveOrderBook {
         let rejected = cmd.size - filled;
 
         if rejected > 0 {
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(rejected, cmd.price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(rejected, cmd.price, cmd.reserve_price));
         }
     }
 
@@ -202,37 +231,77 @@ impl NaiveOrderBook {
             if self.is_budget_satisfied(cmd.action, calculated_budget, cmd.price) {
                 self.try_match(cmd);
             } else {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
             }
         } else {
             // 流动性不足
-            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price));
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+        }
+    }
+
+    /// IOC_BUDGET 买单：逐档撮合，累计成交金额不超过预算（price 字段为总预算）
+    fn place_ioc_budget(&mut self, cmd: &mut OrderCommand) {
+        let mut remaining = BidBudget::new(cmd.price);
+        let size = cmd.size;
+        let mut filled = 0;
+
+        while let Some((price, volume)) = self.best_opposite_level(cmd.action) {
+            let step = remaining.affordable(price, (size - filled).min(volume));
+            if step <= 0 {
+                break;
+            }
+
+            // 单档撮合，成交价均为该档价格
+            cmd.size = step;
+            let matched = self.try_match(cmd);
+            filled += matched;
+            remaining.spend(price, matched);
+            if matched < step {
+                break;
+            }
+        }
+
+        cmd.size = size;
+        if filled < size {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(size - filled, cmd.price, cmd.reserve_price));
         }
     }
 
+    /// 对手方最优档位 (价格, 挂单量)
+    #[inline]
+    fn best_opposite_level(&self, action: OrderAction) -> Option<(Price, Size)> {
+        let level = match action {
+            OrderAction::Bid => self.ask_buckets.iter().next(),
+            OrderAction::Ask => self.bid_buckets.iter().next_back(),
+        };
+        level.map(|(price, bucket)| (*price, bucket.total_volume))
+    }
+
     /// 检查预算是否满足
     fn is_budget_satisfied(&self, action: OrderAction, calculated: i64, limit: i64) -> bool {
         calculated == limit || (action == OrderAction::Bid) != (calculated > limit)
     }
 
-    /// 计算填充订单所需的预算
+    /// 计算填充订单所需的预算，对手盘不足或金额溢出时返回 None
+    ///
+    /// 本订单簿不做自成交预防，taker 自己的挂单同样会成交，因此一并计入
     fn check_budget_to_fill(&self, mut size: Size, action: OrderAction) -> Option<i64> {
-        let buckets = match action {
-            OrderAction::Ask => &self.bid_buckets,
-            OrderAction::Bid => &self.ask_buckets,
+        // 按撮合顺序遍历：买单从最低卖价开始，卖单从最高买价开始
+        let buckets: Box<dyn Iterator<Item = (&Price, &OrdersBucket)>> = match action {
+            OrderAction::Ask => Box::new(self.bid_buckets.iter().rev()),
+            OrderAction::Bid => Box::new(self.ask_buckets.iter()),
         };
 
         let mut budget: i64 = 0;
 
-        for (price, bucket) in buckets.iter() {
+        for (price, bucket) in buckets {
             let available = bucket.total_volume;
 
             if size > available {
                 size -= available;
-                budget += (available * price) as i64;
+                budget = budget.checked_add(available.checked_mul(*price)?)?;
             } else {
-                budget += (size * price) as i64;
-                return Some(budget);
+                return budget.checked_add(size.checked_mul(*price)?);
             }
         }
 
@@ -269,9 +338,13 @@ impl NaiveOrderBook {
                         break;
                     }
 
+                    self.touch_level(OrderAction::Ask, price);
                     if let Some(bucket) = self.ask_buckets.get_mut(&price) {
                         let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid);
                         filled += matched;
+                        for event in events.iter().filter(|e| e.maker_completed) {
+                            self.order_map.remove(&event.matched_order_id);
+                        }
                         cmd.matcher_events.extend(events);
 
                         if bucket.total_volume == 0 {
@@ -304,9 +377,13 @@ impl NaiveOrderBook {
                         break;
                     }
 
+                    self.touch_level(OrderAction::Bid, price);
                     if let Some(bucket) = self.bid_buckets.get_mut(&price) {
                         let (matched, events) = bucket.match_order(cmd.size - filled, cmd.uid);
                         filled += matched;
+                        for event in events.iter().filter(|e| e.maker_completed) {
+                            self.order_map.remove(&event.matched_order_id);
+                        }
                         cmd.matcher_events.extend(events);
 
                         if bucket.total_volume == 0 {
@@ -324,6 +401,14 @@ impl NaiveOrderBook {
 
 impl super::OrderBook for NaiveOrderBook {
     fn new_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        if self.order_map.contains_key(&cmd.order_id) {
+            return CommandResultCode::MatchingDuplicateOrderId;
+        }
+        // 最小成交数量、隐藏单与 GoodAfterTime 只有 Advanced 订单簿支持
+        if cmd.min_fill_size.is_some() || cmd.hidden || cmd.activate_time.is_some() {
+            return CommandResultCode::MatchingUnsupportedCommand;
+        }
+        let from = cmd.matcher_events.len();
         match cmd.order_type {
             OrderType::Gtc => {
                 self.place_gtc(cmd);
@@ -334,17 +419,27 @@ impl super::OrderBook for NaiveOrderBook {
             OrderType::FokBudget => {
                 self.place_fok_budget(cmd);
             }
+            // 预算为买入可花费的总金额，卖单没有对应含义
+            OrderType::IocBudget if cmd.action == OrderAction::Bid => {
+                self.place_ioc_budget(cmd);
+            }
             _ => {
                 return CommandResultCode::MatchingUnsupportedCommand;
             }
         }
+        LastTrade::update(&mut self.last_trade, &cmd.matcher_events[from..], cmd.timestamp);
         CommandResultCode::Success
     }
 
     fn cancel_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        let Some((price, action)) = self.order_map.remove(&cmd.order_id) else {
+        let Some((price, action, uid)) = self.order_map.get(&cmd.order_id).copied() else {
             return CommandResultCode::MatchingUnknownOrderId;
         };
+        if uid != cmd.uid {
+            return CommandResultCode::MatchingUnauthorizedAction;
+        }
+        self.order_map.remove(&cmd.order_id);
+        self.touch_level(action, price);
 
         let buckets = match action {
             OrderAction::Ask => &mut self.ask_buckets,
@@ -353,7 +448,7 @@ impl super::OrderBook for NaiveOrderBook {
 
         if let Some(bucket) = buckets.get_mut(&price) {
             if let Some(order) = bucket.remove(cmd.order_id) {
-                cmd.matcher_events.push(MatcherTradeEvent::new_reject(order.remaining(), price));
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(order.remaining(), price, order.reserve_price));
                 cmd.action = action;
 
                 if bucket.total_volume == 0 {
@@ -369,9 +464,12 @@ impl super::OrderBook for NaiveOrderBook {
     }
 
     fn move_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        let Some((old_price, action)) = self.order_map.get(&cmd.order_id).copied() else {
+        let Some((old_price, action, uid)) = self.order_map.get(&cmd.order_id).copied() else {
             return CommandResultCode::MatchingUnknownOrderId;
         };
+        if uid != cmd.uid {
+            return CommandResultCode::MatchingUnauthorizedAction;
+        }
 
         // 风险检查：买单不能超过预留价格
         if self.symbol_spec.symbol_type == SymbolType::CurrencyExchangePair
@@ -388,6 +486,7 @@ impl super::OrderBook for NaiveOrderBook {
         }
 
         // 取出订单
+        self.touch_level(action, old_price);
         let buckets = match action {
             OrderAction::Ask => &mut self.ask_buckets,
             OrderAction::Bid => &mut self.bid_buckets,
@@ -407,7 +506,7 @@ impl super::OrderBook for NaiveOrderBook {
         };
 
         // 更新价格
-        self.order_map.insert(cmd.order_id, (cmd.price, action));
+        self.order_map.insert(cmd.order_id, (cmd.price, action, uid));
         order.price = cmd.price;
         cmd.action = action;
 
@@ -427,10 +526,12 @@ impl super::OrderBook for NaiveOrderBook {
 
         let filled = self.try_match(&mut temp_cmd);
         order.filled = filled_before + filled;
+        LastTrade::update(&mut self.last_trade, &temp_cmd.matcher_events, cmd.timestamp);
         cmd.matcher_events.extend(temp_cmd.matcher_events);
 
         // 如果未完全成交，重新挂单
         if order.filled < order.size {
+            self.touch_level(action, cmd.price);
             match action {
                 OrderAction::Ask => {
                     self.ask_buckets
@@ -454,9 +555,13 @@ impl super::OrderBook for NaiveOrderBook {
     }
 
     fn reduce_order(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
-        let Some((price, action)) = self.order_map.get(&cmd.order_id).copied() else {
+        let Some((price, action, uid)) = self.order_map.get(&cmd.order_id).copied() else {
             return CommandResultCode::MatchingUnknownOrderId;
         };
+        if uid != cmd.uid {
+            return CommandResultCode::MatchingUnauthorizedAction;
+        }
+        self.touch_level(action, price);
 
         let buckets = match action {
             OrderAction::Ask => &mut self.ask_buckets,
@@ -467,11 +572,12 @@ impl super::OrderBook for NaiveOrderBook {
             if let Some(order) = bucket.orders.iter_mut().find(|o| o.order_id == cmd.order_id) {
                 let remaining = order.remaining();
                 let reduce_by = remaining.min(cmd.size);
+                let reserve_price = order.reserve_price;
 
                 if reduce_by == remaining {
                     // 完全移除
                     let _order = bucket.remove(cmd.order_id).unwrap();
-                    cmd.matcher_events.push(MatcherTradeEvent::new_reject(reduce_by, price));
+                    cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining - reduce_by));
                     cmd.action = action;
                     self.order_map.remove(&cmd.order_id);
 
@@ -483,7 +589,7 @@ impl super::OrderBook for NaiveOrderBook {
                     // 部分减少
                     order.size -= reduce_by;
                     bucket.total_volume -= reduce_by;
-                    cmd.matcher_events.push(MatcherTradeEvent::new_reject(reduce_by, price));
+                    cmd.matcher_events.push(MatcherTradeEvent::new_reduce(reduce_by, price, reserve_price, remaining - reduce_by));
                     cmd.action = action;
                 }
 
@@ -494,6 +600,16 @@ impl super::OrderBook for NaiveOrderBook {
         CommandResultCode::MatchingUnknownOrderId
     }
 
+    fn enable_level_updates(&mut self) {
+        self.level_changes.enable();
+    }
+
+    fn take_level_updates(&mut self, out: &mut Vec<LevelUpdate>) {
+        let mut changes = std::mem::take(&mut self.level_changes);
+        changes.drain(self, out);
+        self.level_changes = changes;
+    }
+
     fn get_symbol_spec(&self) -> &CoreSymbolSpecification {
         &self.symbol_spec
     }
@@ -508,7 +624,7 @@ impl super::OrderBook for NaiveOrderBook {
         }
 
         // 买单（从高到低）
-        for (price, bucket) in self.bid_buckets.iter().take(depth) {
+        for (price, bucket) in self.bid_buckets.iter().rev().take(depth) {
             data.bid_prices.push(*price);
             data.bid_volumes.push(bucket.total_volume);
         }
@@ -516,8 +632,55 @@ impl super::OrderBook for NaiveOrderBook {
         data
     }
 
+    fn best_bid_offer(&self) -> super::TopOfBook {
+        (
+            self.bid_buckets.iter().next_back().map(|(price, bucket)| (*price, bucket.total_volume)),
+            self.ask_buckets.iter().next().map(|(price, bucket)| (*price, bucket.total_volume)),
+        )
+    }
+
+    fn quote_for_size(&self, action: OrderAction, size: Size) -> SizeQuote {
+        match action {
+            OrderAction::Bid => SizeQuote::from_levels(action, size, self.ask_buckets.iter().map(|(price, bucket)| (*price, bucket.total_volume))),
+            OrderAction::Ask => SizeQuote::from_levels(action, size, self.bid_buckets.iter().rev().map(|(price, bucket)| (*price, bucket.total_volume))),
+        }
+    }
+
+    fn get_l3_data(&self, depth: usize, max_orders_per_level: usize, mask_uid: bool) -> L3MarketData {
+        let mut data = L3MarketData::new(depth);
+        data.asks.extend(self.ask_buckets.values().take(depth).map(|b| b.to_l3_level(max_orders_per_level, mask_uid)));
+        data.bids.extend(self.bid_buckets.values().rev().take(depth).map(|b| b.to_l3_level(max_orders_per_level, mask_uid)));
+        data
+    }
+
     fn get_order_by_id(&self, order_id: OrderId) -> Option<(Price, OrderAction)> {
-        self.order_map.get(&order_id).copied()
+        self.order_map.get(&order_id).map(|&(price, action, _)| (price, action))
+    }
+
+    fn get_open_order(&self, order_id: OrderId) -> Option<OpenOrder> {
+        let (price, action, _) = self.order_map.get(&order_id).copied()?;
+        let buckets = match action {
+            OrderAction::Ask => &self.ask_buckets,
+            OrderAction::Bid => &self.bid_buckets,
+        };
+        let order = buckets.get(&price)?.orders.iter().find(|o| o.order_id == order_id)?;
+        Some(OpenOrder {
+            order_id,
+            uid: order.uid,
+            symbol: self.symbol_spec.symbol_id,
+            action,
+            order_type: OrderType::Gtc,
+            price,
+            reserve_price: order.reserve_price,
+            size: order.size,
+            remaining: order.remaining(),
+            timestamp: order.timestamp,
+            client_order_id: order.client_order_id,
+        })
+    }
+
+    fn get_all_orders(&self) -> Vec<OpenOrder> {
+        self.order_map.keys().filter_map(|&order_id| self.get_open_order(order_id)).collect()
     }
 
     fn get_total_ask_volume(&self) -> Size {
@@ -536,6 +699,71 @@ impl super::OrderBook for NaiveOrderBook {
         self.bid_buckets.len()
     }
 
+    fn get_level_volume(&self, action: OrderAction, price: Price) -> Size {
+        let buckets = match action {
+            OrderAction::Ask => &self.ask_buckets,
+            OrderAction::Bid => &self.bid_buckets,
+        };
+        buckets.get(&price).map_or(0, |b| b.total_volume)
+    }
+
+    fn stats(&self) -> OrderBookStats {
+        let total_ask_volume = self.get_total_ask_volume();
+        let total_bid_volume = self.get_total_bid_volume();
+        OrderBookStats {
+            symbol: self.symbol_spec.symbol_id,
+            order_count: self.order_map.len(),
+            stop_order_count: 0,
+            ask_levels: self.ask_buckets.len(),
+            bid_levels: self.bid_buckets.len(),
+            total_ask_volume,
+            total_bid_volume,
+            visible_ask_volume: total_ask_volume,
+            visible_bid_volume: total_bid_volume,
+            ask_price_range: super::price_range(self.ask_buckets.keys()),
+            bid_price_range: super::price_range(self.bid_buckets.keys()),
+            pool: None,
+            last_trade: self.last_trade,
+        }
+    }
+
+    fn visit_levels(&self, side: OrderAction, visitor: &mut dyn FnMut(Price, Size) -> bool) {
+        match side {
+            OrderAction::Ask => self.ask_buckets.iter().all(|(price, bucket)| visitor(*price, bucket.total_volume)),
+            OrderAction::Bid => self.bid_buckets.iter().rev().all(|(price, bucket)| visitor(*price, bucket.total_volume)),
+        };
+    }
+
+    #[cfg(feature = "debug-invariants")]
+    fn check_invariants(&self) -> Result<(), crate::core::invariants::BookInvariantViolation> {
+        use crate::core::invariants::{ensure_book, ensure_not_crossed};
+        let symbol = self.symbol_spec.symbol_id;
+        let (best_bid, best_ask) = (self.bid_buckets.keys().next_back().copied(), self.ask_buckets.keys().next().copied());
+        ensure_not_crossed(symbol, best_bid, best_ask)?;
+        ensure_book(symbol, (self.best_bid_price, self.best_ask_price) == (best_bid, best_ask), || {
+            format!("最优价缓存 {:?}/{:?} 与档位 {best_bid:?}/{best_ask:?} 不符", self.best_bid_price, self.best_ask_price)
+        })?;
+
+        let mut count = 0;
+        for (action, buckets) in [(OrderAction::Ask, &self.ask_buckets), (OrderAction::Bid, &self.bid_buckets)] {
+            for (&price, bucket) in buckets {
+                let sum: Size = bucket.orders.iter().map(Order::remaining).sum();
+                ensure_book(symbol, bucket.price == price && !bucket.orders.is_empty() && bucket.total_volume == sum, || {
+                    format!("{action:?} 档位 {price}: 数量 {} 与 {} 笔订单剩余合计 {sum} 不符", bucket.total_volume, bucket.orders.len())
+                })?;
+                for order in &bucket.orders {
+                    let indexed = self.order_map.get(&order.order_id) == Some(&(price, action, order.uid));
+                    ensure_book(symbol, indexed && order.remaining() > 0 && order.price == price && order.action == action, || {
+                        format!("订单 {} 与 {action:?} 档位 {price} 或索引不一致", order.order_id)
+                    })?;
+                }
+                count += bucket.orders.len();
+            }
+        }
+        // 每笔挂单都有索引且数量相同，索引不会指向已移除的订单
+        ensure_book(symbol, count == self.order_map.len(), || format!("索引 {} 条，挂单 {count} 笔", self.order_map.len()))
+    }
+
     fn serialize_state(&self) -> crate::core::orderbook::OrderBookState {
         crate::core::orderbook::OrderBookState::Naive(self.clone())
     }
@@ -0,0 +1,210 @@
+use crate::api::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// 价格档位：同价位订单按时间优先组成链表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceBucket {
+    pub price: Price,
+    pub volume: Size,
+    pub head: usize, // 链表头（最早订单）
+    pub tail: usize, // 链表尾（最新订单）
+}
+
+/// 单侧盘口的价格索引（DirectOrderBookOptimized 的泛型参数）
+///
+/// 撮合热路径只使用 next_above / next_below 逐档遍历，不分配内存
+pub trait PriceIndex: Clone + Default + Send {
+    /// 按价格升序遍历
+    type Iter<'a>: DoubleEndedIterator<Item = &'a PriceBucket>
+    where
+        Self: 'a;
+
+    fn get(&self, price: Price) -> Option<&PriceBucket>;
+    fn get_mut(&mut self, price: Price) -> Option<&mut PriceBucket>;
+    fn insert(&mut self, bucket: PriceBucket);
+    fn remove(&mut self, price: Price) -> Option<PriceBucket>;
+    fn len(&self) -> usize;
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// 价格高于 after 的最低档位（after 为 None 时即最低档位）
+    fn next_above(&self, after: Option<Price>) -> Option<&PriceBucket>;
+    /// 价格低于 before 的最高档位（before 为 None 时即最高档位）
+    fn next_below(&self, before: Option<Price>) -> Option<&PriceBucket>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 基于 BTreeMap 的价格索引（默认），适合价格分布稀疏的交易对
+#[derive(Clone, Default)]
+pub struct BTreePriceIndex {
+    buckets: BTreeMap<Price, PriceBucket>,
+}
+
+impl PriceIndex for BTreePriceIndex {
+    type Iter<'a> = std::collections::btree_map::Values<'a, Price, PriceBucket>;
+
+    fn get(&self, price: Price) -> Option<&PriceBucket> {
+        self.buckets.get(&price)
+    }
+
+    fn get_mut(&mut self, price: Price) -> Option<&mut PriceBucket> {
+        self.buckets.get_mut(&price)
+    }
+
+    fn insert(&mut self, bucket: PriceBucket) {
+        self.buckets.insert(bucket.price, bucket);
+    }
+
+    fn remove(&mut self, price: Price) -> Option<PriceBucket> {
+        self.buckets.remove(&price)
+    }
+
+    fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.buckets.values()
+    }
+
+    fn next_above(&self, after: Option<Price>) -> Option<&PriceBucket> {
+        let lower = after.map_or(Bound::Unbounded, Bound::Excluded);
+        self.buckets.range((lower, Bound::Unbounded)).next().map(|(_, bucket)| bucket)
+    }
+
+    fn next_below(&self, before: Option<Price>) -> Option<&PriceBucket> {
+        let upper = before.map_or(Bound::Unbounded, Bound::Excluded);
+        self.buckets.range((Bound::Unbounded, upper)).next_back().map(|(_, bucket)| bucket)
+    }
+}
+
+/// 连续价格阶梯：每个价格单位一个槽位，按偏移直接寻址
+///
+/// 适合价格集中在有限区间、档位密集的交易对；内存随最高价与最低价之差线性增长
+#[derive(Clone, Default)]
+pub struct PriceLadder {
+    base: Price, // slots[0] 对应的价格
+    slots: Vec<Option<PriceBucket>>,
+    len: usize,
+    lo: usize, // 有效档位所在的槽位范围 [lo, hi]（len > 0 时有效）
+    hi: usize,
+}
+
+impl PriceLadder {
+    /// 扩容时至少预留的槽位数
+    const MIN_GROWTH: usize = 64;
+
+    fn slot(&self, price: Price) -> Option<usize> {
+        let offset = price.checked_sub(self.base)?;
+        usize::try_from(offset).ok().filter(|&slot| slot < self.slots.len())
+    }
+
+    /// 扩展槽位使 price 落在范围内，返回其槽位
+    fn ensure_slot(&mut self, price: Price) -> usize {
+        if self.slots.is_empty() {
+            self.base = price;
+            self.slots.resize(Self::MIN_GROWTH, None);
+            return 0;
+        }
+
+        if price < self.base {
+            let needed = (self.base - price) as usize;
+            let room = self.base.abs_diff(Price::MIN) as usize;
+            let extra = needed.max(self.slots.len()).max(Self::MIN_GROWTH).min(room);
+            self.slots.splice(0..0, std::iter::repeat_n(None, extra));
+            self.base -= extra as Price;
+            self.lo += extra;
+            self.hi += extra;
+        } else if (price - self.base) as usize >= self.slots.len() {
+            let needed = (price - self.base) as usize + 1;
+            let new_len = needed.max(self.slots.len() * 2);
+            self.slots.resize(new_len, None);
+        }
+        (price - self.base) as usize
+    }
+}
+
+impl PriceIndex for PriceLadder {
+    type Iter<'a> = std::iter::Flatten<std::slice::Iter<'a, Option<PriceBucket>>>;
+
+    fn get(&self, price: Price) -> Option<&PriceBucket> {
+        self.slots[self.slot(price)?].as_ref()
+    }
+
+    fn get_mut(&mut self, price: Price) -> Option<&mut PriceBucket> {
+        let slot = self.slot(price)?;
+        self.slots[slot].as_mut()
+    }
+
+    fn insert(&mut self, bucket: PriceBucket) {
+        let slot = self.ensure_slot(bucket.price);
+        if self.slots[slot].replace(bucket).is_none() {
+            if self.len == 0 {
+                (self.lo, self.hi) = (slot, slot);
+            } else {
+                self.lo = self.lo.min(slot);
+                self.hi = self.hi.max(slot);
+            }
+            self.len += 1;
+        }
+    }
+
+    fn remove(&mut self, price: Price) -> Option<PriceBucket> {
+        let slot = self.slot(price)?;
+        let bucket = self.slots[slot].take()?;
+        self.len -= 1;
+        // 收紧有效范围，保证两端槽位非空
+        if self.len > 0 {
+            while self.slots[self.lo].is_none() {
+                self.lo += 1;
+            }
+            while self.slots[self.hi].is_none() {
+                self.hi -= 1;
+            }
+        }
+        Some(bucket)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        let range = if self.len == 0 { &self.slots[..0] } else { &self.slots[self.lo..=self.hi] };
+        range.iter().flatten()
+    }
+
+    fn next_above(&self, after: Option<Price>) -> Option<&PriceBucket> {
+        if self.len == 0 {
+            return None;
+        }
+        let start = match after {
+            None => self.lo,
+            Some(price) if price < self.base => self.lo,
+            Some(price) => ((price - self.base) as usize).saturating_add(1).max(self.lo),
+        };
+        if start > self.hi {
+            return None;
+        }
+        self.slots[start..=self.hi].iter().flatten().next()
+    }
+
+    fn next_below(&self, before: Option<Price>) -> Option<&PriceBucket> {
+        if self.len == 0 {
+            return None;
+        }
+        let end = match before {
+            None => self.hi,
+            Some(price) if price <= self.base => return None,
+            Some(price) => ((price - self.base) as usize - 1).min(self.hi),
+        };
+        if end < self.lo {
+            return None;
+        }
+        self.slots[self.lo..=end].iter().flatten().next_back()
+    }
+}
@@ -1,128 +1,88 @@
-/// SIMD 批量撮合优化工具
-use wide::*;
+//! SIMD 批量撮合优化工具
+//!
+//! x86_64 运行时检测 AVX2，aarch64 使用 NEON（基线指令集），其余情况回退到标量实现
 
-/// SIMD 批量价格比较（i64x4）
+/// 当前 CPU 可用的 SIMD 指令集
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    Scalar,
+    Avx2,
+    Neon,
+}
+
+/// 运行时检测 SIMD 指令集（标准库缓存检测结果）
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub fn simd_level() -> SimdLevel {
+    if std::is_x86_feature_detected!("avx2") {
+        SimdLevel::Avx2
+    } else {
+        SimdLevel::Scalar
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn simd_level() -> SimdLevel {
+    SimdLevel::Neon
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+pub fn simd_level() -> SimdLevel {
+    SimdLevel::Scalar
+}
+
+/// 按指令集分派：AVX2 / NEON 实现均为 unsafe fn，仅在检测通过后调用
+macro_rules! dispatch {
+    ($name:ident($($arg:expr),*)) => {
+        match simd_level() {
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx2 => unsafe { avx2::$name($($arg),*) },
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => unsafe { neon::$name($($arg),*) },
+            _ => scalar::$name($($arg),*),
+        }
+    };
+}
+
+/// SIMD 批量价格比较（小于等于）
 #[inline]
 pub fn simd_price_compare_le(prices: &[i64], limit: i64) -> Vec<bool> {
     let mut result = Vec::with_capacity(prices.len());
-    
-    let chunks = prices.chunks_exact(4);
-    let remainder = chunks.remainder();
-    
-    // 批量处理（4 个一组，展开循环提升性能）
-    for chunk in chunks {
-        result.push(chunk[0] <= limit);
-        result.push(chunk[1] <= limit);
-        result.push(chunk[2] <= limit);
-        result.push(chunk[3] <= limit);
-    }
-    
-    // 处理剩余元素
-    for &price in remainder {
-        result.push(price <= limit);
-    }
-    
+    dispatch!(price_compare_le(prices, limit, &mut result));
     result
 }
 
-/// SIMD 批量价格比较（i64x4，大于等于）
+/// SIMD 批量价格比较（大于等于）
 #[inline]
 pub fn simd_price_compare_ge(prices: &[i64], limit: i64) -> Vec<bool> {
     let mut result = Vec::with_capacity(prices.len());
-    
-    let chunks = prices.chunks_exact(4);
-    let remainder = chunks.remainder();
-    
-    for chunk in chunks {
-        result.push(chunk[0] >= limit);
-        result.push(chunk[1] >= limit);
-        result.push(chunk[2] >= limit);
-        result.push(chunk[3] >= limit);
-    }
-    
-    for &price in remainder {
-        result.push(price >= limit);
-    }
-    
+    dispatch!(price_compare_ge(prices, limit, &mut result));
     result
 }
 
-/// SIMD 批量数量累加（i64x4）
+/// SIMD 批量数量累加
 #[inline]
 pub fn simd_sum_sizes(sizes: &[i64]) -> i64 {
-    let mut sum = i64x4::splat(0);
-    
-    let chunks = sizes.chunks_exact(4);
-    let remainder = chunks.remainder();
-    
-    for chunk in chunks {
-        let size_vec = i64x4::new([chunk[0], chunk[1], chunk[2], chunk[3]]);
-        sum = sum + size_vec;
-    }
-    
-    let arr = sum.to_array();
-    let mut total = arr[0] + arr[1] + arr[2] + arr[3];
-    
-    for &size in remainder {
-        total += size;
-    }
-    
-    total
-}
-
-/// SIMD 批量最小值计算（i64x4）
+    dispatch!(sum_sizes(sizes))
+}
+
+/// SIMD 批量最小值计算
 #[inline]
 pub fn simd_min_pairs(a: &[i64], b: &[i64]) -> Vec<i64> {
     assert_eq!(a.len(), b.len());
     let mut result = Vec::with_capacity(a.len());
-    
-    let chunks_a = a.chunks_exact(4);
-    let chunks_b = b.chunks_exact(4);
-    let remainder_a = chunks_a.remainder();
-    let remainder_b = chunks_b.remainder();
-    
-    for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
-        // 手动最小值计算
-        result.push(chunk_a[0].min(chunk_b[0]));
-        result.push(chunk_a[1].min(chunk_b[1]));
-        result.push(chunk_a[2].min(chunk_b[2]));
-        result.push(chunk_a[3].min(chunk_b[3]));
-    }
-    
-    for (a, b) in remainder_a.iter().zip(remainder_b.iter()) {
-        result.push((*a).min(*b));
-    }
-    
+    dispatch!(min_pairs(a, b, &mut result));
     result
 }
 
-/// SIMD 批量相减（i64x4）
+/// SIMD 批量相减
 #[inline]
 pub fn simd_sub_vectors(a: &[i64], b: &[i64]) -> Vec<i64> {
     assert_eq!(a.len(), b.len());
     let mut result = Vec::with_capacity(a.len());
-    
-    let chunks_a = a.chunks_exact(4);
-    let chunks_b = b.chunks_exact(4);
-    let remainder_a = chunks_a.remainder();
-    let remainder_b = chunks_b.remainder();
-    
-    for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
-        let vec_a = i64x4::new([chunk_a[0], chunk_a[1], chunk_a[2], chunk_a[3]]);
-        let vec_b = i64x4::new([chunk_b[0], chunk_b[1], chunk_b[2], chunk_b[3]]);
-        let diff = vec_a - vec_b;
-        
-        let arr = diff.to_array();
-        result.push(arr[0]);
-        result.push(arr[1]);
-        result.push(arr[2]);
-        result.push(arr[3]);
-    }
-    
-    for (a, b) in remainder_a.iter().zip(remainder_b.iter()) {
-        result.push(a - b);
-    }
-    
+    dispatch!(sub_vectors(a, b, &mut result));
     result
 }
 
@@ -133,44 +93,316 @@ pub fn simd_batch_match_prepare(
     filled: &[i64],
     need_size: i64,
 ) -> (Vec<i64>, i64) {
-    // 计算每个订单的剩余量
-    let remaining = simd_sub_vectors(sizes, filled);
-    
-    // 累加可用量
-    let mut available = 0i64;
-    let mut matched_sizes = Vec::with_capacity(remaining.len());
-    
-    for &rem in &remaining {
-        if available >= need_size {
-            matched_sizes.push(0);
-        } else {
-            let can_match = rem.min(need_size - available);
-            matched_sizes.push(can_match);
-            available += can_match;
+    let mut matched_sizes = Vec::with_capacity(sizes.len());
+    let available = simd_batch_match_prepare_into(sizes, filled, need_size, &mut matched_sizes);
+    (matched_sizes, available)
+}
+
+/// 批量订单匹配预处理，结果写入调用方复用的缓冲区，返回总匹配量
+///
+/// 按时间优先依次分配 need_size：每 4 笔一组计算剩余量，整组可全部成交时直接写入，
+/// 否则从该组起逐笔分配
+#[inline]
+pub fn simd_batch_match_prepare_into(
+    sizes: &[i64],
+    filled: &[i64],
+    need_size: i64,
+    matched_sizes: &mut Vec<i64>,
+) -> i64 {
+    assert_eq!(sizes.len(), filled.len());
+    matched_sizes.clear();
+    dispatch!(batch_match_prepare(sizes, filled, need_size, matched_sizes))
+}
+
+/// 标量实现（无 SIMD 指令集时的回退，也作为基准测试的对照）
+pub mod scalar {
+    pub fn price_compare_le(prices: &[i64], limit: i64, result: &mut Vec<bool>) {
+        result.extend(prices.iter().map(|&price| price <= limit));
+    }
+
+    pub fn price_compare_ge(prices: &[i64], limit: i64, result: &mut Vec<bool>) {
+        result.extend(prices.iter().map(|&price| price >= limit));
+    }
+
+    pub fn sum_sizes(sizes: &[i64]) -> i64 {
+        sizes.iter().sum()
+    }
+
+    pub fn min_pairs(a: &[i64], b: &[i64], result: &mut Vec<i64>) {
+        result.extend(a.iter().zip(b).map(|(&a, &b)| a.min(b)));
+    }
+
+    pub fn sub_vectors(a: &[i64], b: &[i64], result: &mut Vec<i64>) {
+        result.extend(a.iter().zip(b).map(|(&a, &b)| a - b));
+    }
+
+    pub fn batch_match_prepare(sizes: &[i64], filled: &[i64], need_size: i64, matched_sizes: &mut Vec<i64>) -> i64 {
+        fill_remaining(sizes, filled, need_size, 0, matched_sizes)
+    }
+
+    /// 从 available 开始逐笔分配剩余需求量
+    #[inline]
+    pub(super) fn fill_remaining(
+        sizes: &[i64],
+        filled: &[i64],
+        need_size: i64,
+        mut available: i64,
+        matched_sizes: &mut Vec<i64>,
+    ) -> i64 {
+        for (&size, &done) in sizes.iter().zip(filled) {
+            if available >= need_size {
+                matched_sizes.push(0);
+            } else {
+                let can_match = (size - done).min(need_size - available);
+                matched_sizes.push(can_match);
+                available += can_match;
+            }
         }
+        available
+    }
+}
+
+/// AVX2 实现（i64x4）
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn load(values: &[i64]) -> __m256i {
+        _mm256_loadu_si256(values.as_ptr() as *const __m256i)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn store(vector: __m256i) -> [i64; 4] {
+        let mut out = [0i64; 4];
+        _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, vector);
+        out
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn horizontal_sum(vector: __m256i) -> i64 {
+        let pair = _mm_add_epi64(_mm256_castsi256_si128(vector), _mm256_extracti128_si256::<1>(vector));
+        _mm_cvtsi128_si64(pair) + _mm_extract_epi64::<1>(pair)
+    }
+
+    /// 按比较掩码（每通道 1 位）写入结果
+    #[inline]
+    fn push_mask(mask: i32, result: &mut Vec<bool>) {
+        result.extend((0..4).map(|lane| mask & (1 << lane) != 0));
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn price_compare_le(prices: &[i64], limit: i64, result: &mut Vec<bool>) {
+        let limit_vec = _mm256_set1_epi64x(limit);
+        let chunks = prices.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            // price <= limit 即 !(price > limit)
+            let gt = _mm256_cmpgt_epi64(load(chunk), limit_vec);
+            push_mask(!_mm256_movemask_pd(_mm256_castsi256_pd(gt)), result);
+        }
+        super::scalar::price_compare_le(remainder, limit, result);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn price_compare_ge(prices: &[i64], limit: i64, result: &mut Vec<bool>) {
+        let limit_vec = _mm256_set1_epi64x(limit);
+        let chunks = prices.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            let lt = _mm256_cmpgt_epi64(limit_vec, load(chunk));
+            push_mask(!_mm256_movemask_pd(_mm256_castsi256_pd(lt)), result);
+        }
+        super::scalar::price_compare_ge(remainder, limit, result);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sum_sizes(sizes: &[i64]) -> i64 {
+        let mut sum = _mm256_setzero_si256();
+        let chunks = sizes.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            sum = _mm256_add_epi64(sum, load(chunk));
+        }
+        horizontal_sum(sum) + super::scalar::sum_sizes(remainder)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn min_pairs(a: &[i64], b: &[i64], result: &mut Vec<i64>) {
+        let chunks_a = a.chunks_exact(4);
+        let chunks_b = b.chunks_exact(4);
+        let (remainder_a, remainder_b) = (chunks_a.remainder(), chunks_b.remainder());
+        for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+            let (vec_a, vec_b) = (load(chunk_a), load(chunk_b));
+            // AVX2 没有 64 位 min，用比较掩码选择
+            let a_greater = _mm256_cmpgt_epi64(vec_a, vec_b);
+            result.extend(store(_mm256_blendv_epi8(vec_a, vec_b, a_greater)));
+        }
+        super::scalar::min_pairs(remainder_a, remainder_b, result);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub_vectors(a: &[i64], b: &[i64], result: &mut Vec<i64>) {
+        let chunks_a = a.chunks_exact(4);
+        let chunks_b = b.chunks_exact(4);
+        let (remainder_a, remainder_b) = (chunks_a.remainder(), chunks_b.remainder());
+        for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+            result.extend(store(_mm256_sub_epi64(load(chunk_a), load(chunk_b))));
+        }
+        super::scalar::sub_vectors(remainder_a, remainder_b, result);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn batch_match_prepare(sizes: &[i64], filled: &[i64], need_size: i64, matched_sizes: &mut Vec<i64>) -> i64 {
+        let mut available = 0;
+        let mut offset = 0;
+        while offset + 4 <= sizes.len() {
+            let remaining = _mm256_sub_epi64(load(&sizes[offset..]), load(&filled[offset..]));
+            let total = horizontal_sum(remaining);
+            if available + total > need_size {
+                break;
+            }
+            matched_sizes.extend(store(remaining));
+            available += total;
+            offset += 4;
+        }
+        super::scalar::fill_remaining(&sizes[offset..], &filled[offset..], need_size, available, matched_sizes)
+    }
+}
+
+/// NEON 实现（2 × i64x2）
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn load(values: &[i64]) -> (int64x2_t, int64x2_t) {
+        (vld1q_s64(values.as_ptr()), vld1q_s64(values.as_ptr().add(2)))
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn store(low: int64x2_t, high: int64x2_t) -> [i64; 4] {
+        let mut out = [0i64; 4];
+        vst1q_s64(out.as_mut_ptr(), low);
+        vst1q_s64(out.as_mut_ptr().add(2), high);
+        out
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn push_mask(low: uint64x2_t, high: uint64x2_t, result: &mut Vec<bool>) {
+        result.extend([
+            vgetq_lane_u64::<0>(low) != 0,
+            vgetq_lane_u64::<1>(low) != 0,
+            vgetq_lane_u64::<0>(high) != 0,
+            vgetq_lane_u64::<1>(high) != 0,
+        ]);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn price_compare_le(prices: &[i64], limit: i64, result: &mut Vec<bool>) {
+        let limit_vec = vdupq_n_s64(limit);
+        let chunks = prices.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            let (low, high) = load(chunk);
+            push_mask(vcleq_s64(low, limit_vec), vcleq_s64(high, limit_vec), result);
+        }
+        super::scalar::price_compare_le(remainder, limit, result);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn price_compare_ge(prices: &[i64], limit: i64, result: &mut Vec<bool>) {
+        let limit_vec = vdupq_n_s64(limit);
+        let chunks = prices.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            let (low, high) = load(chunk);
+            push_mask(vcgeq_s64(low, limit_vec), vcgeq_s64(high, limit_vec), result);
+        }
+        super::scalar::price_compare_ge(remainder, limit, result);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn sum_sizes(sizes: &[i64]) -> i64 {
+        let mut sum = vdupq_n_s64(0);
+        let chunks = sizes.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            let (low, high) = load(chunk);
+            sum = vaddq_s64(sum, vaddq_s64(low, high));
+        }
+        vaddvq_s64(sum) + super::scalar::sum_sizes(remainder)
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn min_pairs(a: &[i64], b: &[i64], result: &mut Vec<i64>) {
+        let chunks_a = a.chunks_exact(4);
+        let chunks_b = b.chunks_exact(4);
+        let (remainder_a, remainder_b) = (chunks_a.remainder(), chunks_b.remainder());
+        for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+            let ((a_low, a_high), (b_low, b_high)) = (load(chunk_a), load(chunk_b));
+            let low = vbslq_s64(vcltq_s64(a_low, b_low), a_low, b_low);
+            let high = vbslq_s64(vcltq_s64(a_high, b_high), a_high, b_high);
+            result.extend(store(low, high));
+        }
+        super::scalar::min_pairs(remainder_a, remainder_b, result);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn sub_vectors(a: &[i64], b: &[i64], result: &mut Vec<i64>) {
+        let chunks_a = a.chunks_exact(4);
+        let chunks_b = b.chunks_exact(4);
+        let (remainder_a, remainder_b) = (chunks_a.remainder(), chunks_b.remainder());
+        for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+            let ((a_low, a_high), (b_low, b_high)) = (load(chunk_a), load(chunk_b));
+            result.extend(store(vsubq_s64(a_low, b_low), vsubq_s64(a_high, b_high)));
+        }
+        super::scalar::sub_vectors(remainder_a, remainder_b, result);
+    }
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn batch_match_prepare(sizes: &[i64], filled: &[i64], need_size: i64, matched_sizes: &mut Vec<i64>) -> i64 {
+        let mut available = 0;
+        let mut offset = 0;
+        while offset + 4 <= sizes.len() {
+            let ((size_low, size_high), (filled_low, filled_high)) = (load(&sizes[offset..]), load(&filled[offset..]));
+            let (low, high) = (vsubq_s64(size_low, filled_low), vsubq_s64(size_high, filled_high));
+            let total = vaddvq_s64(vaddq_s64(low, high));
+            if available + total > need_size {
+                break;
+            }
+            matched_sizes.extend(store(low, high));
+            available += total;
+            offset += 4;
+        }
+        super::scalar::fill_remaining(&sizes[offset..], &filled[offset..], need_size, available, matched_sizes)
     }
-    
-    (matched_sizes, available)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     fn test_simd_price_compare() {
         let prices = vec![100, 200, 300, 400, 500, 600];
         let result = simd_price_compare_le(&prices, 350);
         assert_eq!(result, vec![true, true, true, false, false, false]);
     }
-    
+
     #[test]
     fn test_simd_sum() {
         let sizes = vec![10, 20, 30, 40, 50];
         let sum = simd_sum_sizes(&sizes);
         assert_eq!(sum, 150);
     }
-    
+
     #[test]
     fn test_simd_min_pairs() {
         let a = vec![10, 20, 30, 40];
@@ -178,5 +410,39 @@ mod tests {
         let result = simd_min_pairs(&a, &b);
         assert_eq!(result, vec![10, 10, 30, 30]);
     }
-}
 
+    #[test]
+    fn test_simd_matches_scalar() {
+        let a: Vec<i64> = (0..37).map(|i| (i * 7919) % 101 - 50).collect();
+        let b: Vec<i64> = (0..37).map(|i| (i * 104729) % 89 - 44).collect();
+
+        let mut expected = Vec::new();
+        scalar::price_compare_le(&a, 3, &mut expected);
+        assert_eq!(simd_price_compare_le(&a, 3), expected);
+        expected.clear();
+        scalar::price_compare_ge(&a, 3, &mut expected);
+        assert_eq!(simd_price_compare_ge(&a, 3), expected);
+
+        let mut expected = Vec::new();
+        scalar::min_pairs(&a, &b, &mut expected);
+        assert_eq!(simd_min_pairs(&a, &b), expected);
+        expected.clear();
+        scalar::sub_vectors(&a, &b, &mut expected);
+        assert_eq!(simd_sub_vectors(&a, &b), expected);
+        assert_eq!(simd_sum_sizes(&a), scalar::sum_sizes(&a));
+    }
+
+    #[test]
+    fn test_batch_match_prepare_matches_scalar() {
+        let sizes: Vec<i64> = (0..23).map(|i| 5 + i % 7).collect();
+        let filled: Vec<i64> = (0..23).map(|i| i % 3).collect();
+        let total: i64 = sizes.iter().zip(&filled).map(|(s, f)| s - f).sum();
+
+        for need_size in [0, 1, 4, 17, 30, 31, total - 1, total, total + 10] {
+            let mut expected = Vec::new();
+            let expected_available = scalar::batch_match_prepare(&sizes, &filled, need_size, &mut expected);
+            let (matched, available) = simd_batch_match_prepare(&sizes, &filled, need_size);
+            assert_eq!((matched, available), (expected, expected_available), "need_size={}", need_size);
+        }
+    }
+}
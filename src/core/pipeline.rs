@@ -1,80 +1,398 @@
 use crate::api::*;
-use crate::core::exchange::{ExchangeConfig, ResultConsumer};
-use crate::core::processors::{matching_engine::{MatchingEngineRouter, MatchingEngineState}, risk_engine::RiskEngine};
+use crate::core::book_view::BookViews;
+use crate::core::command_future::PendingResults;
+use crate::core::exchange::{ExchangeConfig, ExchangeState, ResultConsumer};
+use crate::core::invariants::{BalanceTotals, InvariantViolation};
+use crate::core::listener::OrderBookListener;
+use crate::core::market_data::{MarketDataConsumer, MarketDataPublisher};
+use crate::core::processors::{grouping::GroupingProcessor, matching_engine::{MatchingEngineRouter, MatchingEngineState}, rate_limiter::RateLimiter, risk_engine::RiskEngine};
+use crate::core::snapshot::SnapshotStore;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Barrier, Mutex};
 
 #[derive(Serialize, Deserialize)]
 pub struct PipelineState {
     pub risk_engines: Vec<RiskEngine>,
     pub matching_engines: Vec<MatchingEngineState>,
+    pub events_group: u64, // 恢复后下一条命令的组号（快照总在分组边界）
+}
+
+/// Disruptor 环形缓冲区中的事件，各阶段处理器通过锁修改同一条命令
+pub type CommandEvent = Mutex<OrderCommand>;
+
+/// Disruptor 阶段处理器
+pub type StageHandler = Box<dyn FnMut(&CommandEvent, i64, bool) + Send>;
+
+/// 拆分后的流水线阶段：分组 -> R1 风控 -> 撮合 -> R2 风控 -> 结果输出
+///
+/// 同一阶段内每个分片一个处理器并行执行，阶段之间按顺序串联
+pub struct PipelineStages {
+    pub grouping: Vec<StageHandler>,
+    pub risk_pre: Vec<StageHandler>,
+    pub matching: Vec<StageHandler>,
+    pub risk_post: Vec<StageHandler>,
+    pub results: Vec<StageHandler>,
+}
+
+/// 在线快照的写入目标
+struct SnapshotTarget {
+    store: SnapshotStore,
+    config: ExchangeConfig,
+}
+
+impl SnapshotTarget {
+    /// 保存快照，快照 ID 由命令的 order_id 携带（后台写入时只完成序列化，写盘失败由 flush 报告）
+    fn persist(&self, command: OrderCommandType, seq_id: u64, pipeline_state: PipelineState) -> CommandResultCode {
+        let state = ExchangeState {
+            config: self.config.clone(),
+            seq_id,
+            pipeline_state,
+        };
+        match self.store.save_snapshot(&state, seq_id) {
+            Ok(_) => CommandResultCode::Success,
+            Err(_) if command == OrderCommandType::PersistStateRisk => CommandResultCode::StatePersistRiskEngineFailed,
+            Err(_) => CommandResultCode::StatePersistMatchingEngineFailed,
+        }
+    }
+}
+
+fn is_persist_command(command: OrderCommandType) -> bool {
+    matches!(command, OrderCommandType::PersistStateMatching | OrderCommandType::PersistStateRisk)
+}
+
+/// 字段校验（在限流之后），不合法的命令标记为对应的校验结果码
+fn validate(cmd: &mut OrderCommand) {
+    if RateLimiter::is_rejected(cmd) {
+        return;
+    }
+    let code = validate_command(cmd);
+    if code != CommandResultCode::Success {
+        cmd.result_code = code;
+    }
+}
+
+/// 命令是否已在风控之前被拒绝（限流或字段校验失败），后续阶段跳过
+fn is_rejected(cmd: &OrderCommand) -> bool {
+    RateLimiter::is_rejected(cmd) || is_validation_error(cmd.result_code)
 }
 
 /// 流水线 - 组织各个处理器
 pub struct Pipeline {
+    grouping: GroupingProcessor,
+    msgs_in_group: usize, // 当前分组已有命令数
     risk_engines: Vec<RiskEngine>,
     matching_engines: Vec<MatchingEngineRouter>,
     result_consumer: Option<ResultConsumer>,
+    market_data_publisher: Option<MarketDataPublisher>, // 注册行情消费者后创建
+    invariant_check_interval: u64, // debug 构建下每 N 条命令自动对账（0 关闭）
+    processed_commands: u64,
+    snapshot_target: Option<SnapshotTarget>, // 运行中快照写入目标
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl Pipeline {
     /// 处理单个命令（完整流水线）
     pub fn handle_event(&mut self, cmd: &mut OrderCommand, _sequence: i64, _end_of_batch: bool) {
-        // 1. Risk R1 (预处理)
-        for engine in &mut self.risk_engines {
-            engine.pre_process(cmd);
+        // 0. 分组
+        self.grouping.process(cmd, &mut self.msgs_in_group);
+        if let Some(limiter) = &mut self.rate_limiter {
+            limiter.process(cmd);
         }
+        validate(cmd);
 
-        // 2. Matching Engine
-        for engine in &mut self.matching_engines {
-            engine.process_order(cmd);
+        // 在线快照：此前的命令已全部处理完毕，直接在处理线程内落盘
+        if is_persist_command(cmd.command) {
+            if let Some(target) = &self.snapshot_target {
+                cmd.result_code = target.persist(cmd.command, cmd.order_id, self.serialize_state());
+            }
         }
 
-        // 3. Risk R2 (后处理)
-        for engine in &mut self.risk_engines {
-            engine.post_process(cmd);
+        // 被限流或字段校验失败的命令不进入风控与撮合
+        if !is_rejected(cmd) {
+            // 1. Risk R1 (预处理)
+            for engine in &mut self.risk_engines {
+                engine.pre_process(cmd);
+            }
+
+            // 2. Matching Engine：交易对命令只路由到所属分片，其余命令广播
+            if MatchingEngineRouter::is_symbol_command(cmd.command) {
+                let shard = MatchingEngineRouter::shard_for_symbol(cmd.symbol, self.matching_engines.len());
+                self.matching_engines[shard].process_order(cmd);
+            } else {
+                for engine in &mut self.matching_engines {
+                    engine.process_order(cmd);
+                }
+            }
+
+            // 3. Risk R2 (后处理)
+            for engine in &mut self.risk_engines {
+                engine.post_process(cmd);
+            }
         }
 
         // 4. Result Consumer
         if let Some(consumer) = &self.result_consumer {
             consumer(cmd);
         }
+
+        // 5. Market Data Publisher
+        if let Some(publisher) = &mut self.market_data_publisher {
+            publisher.on_command(cmd, 0, &mut self.matching_engines);
+        }
+
+        // 6. 资金不变量自检（仅 debug 构建）
+        self.processed_commands += 1;
+        if cfg!(debug_assertions)
+            && self.invariant_check_interval > 0
+            && self.processed_commands.is_multiple_of(self.invariant_check_interval)
+        {
+            if let Err(violation) = self.verify_invariants() {
+                panic!("第 {} 条命令后{}", self.processed_commands, violation);
+            }
+        }
     }
+
+    /// 资金对账：各币种 净入金 == 余额 + 挂单冻结 + 提现冻结 + 持仓保证金 + 手续费 + 资金费轧差 - 开仓成本轧差
+    pub fn verify_invariants(&self) -> Result<(), InvariantViolation> {
+        let open_orders: Vec<OpenOrder> = self.matching_engines.iter().flat_map(|e| e.get_all_orders()).collect();
+        let mut totals = BalanceTotals::new();
+        for engine in &self.risk_engines {
+            engine.reconcile(&open_orders, &mut totals);
+        }
+        totals.check()
+    }
+
+    /// 拆分为 Disruptor 阶段处理器（启动时调用）
+    ///
+    /// - R1 各风控分片遇到快照命令时，等待之前的命令全部输出后在屏障处汇合，
+    ///   由 0 号分片在此刻读取所有引擎状态并落盘，之后各阶段才继续处理；
+    ///   Disruptor 消费者在批次结束时才推进进度，提交方须在发布快照命令前排空流水线
+    /// - 行情在撮合阶段由所属分片发布
+    /// - 各阶段进度不同，运行期间不做周期性资金对账
+    /// - 结果输出阶段按 Disruptor 序号唤醒 pending 中等待结果的调用方
+    pub fn into_stages(self, pending: Arc<PendingResults>) -> PipelineStages {
+        let grouping_processor = self.grouping;
+        let mut msgs_in_group = self.msgs_in_group;
+        let mut rate_limiter = self.rate_limiter;
+        let grouping = vec![Box::new(move |event: &CommandEvent, _sequence: i64, _end_of_batch: bool| {
+            let mut cmd = event.lock().unwrap();
+            grouping_processor.process(&mut cmd, &mut msgs_in_group);
+            if let Some(limiter) = &mut rate_limiter {
+                limiter.process(&mut cmd);
+            }
+            validate(&mut cmd);
+        }) as StageHandler];
+
+        let risk_engines: Vec<Arc<Mutex<RiskEngine>>> =
+            self.risk_engines.into_iter().map(|engine| Arc::new(Mutex::new(engine))).collect();
+        let matching_engines: Vec<Arc<Mutex<MatchingEngineRouter>>> =
+            self.matching_engines.into_iter().map(|engine| Arc::new(Mutex::new(engine))).collect();
+        let market_data_publisher = self.market_data_publisher.map(|publisher| Arc::new(Mutex::new(publisher)));
+        let snapshot_target = self.snapshot_target.map(Arc::new);
+        let persist_barrier = Arc::new(Barrier::new(risk_engines.len()));
+
+        let risk_pre = (0..risk_engines.len())
+            .map(|shard_id| {
+                let risk_engines = risk_engines.clone();
+                let matching_engines = matching_engines.clone();
+                let snapshot_target = snapshot_target.clone();
+                let pending = pending.clone();
+                let persist_barrier = persist_barrier.clone();
+                Box::new(move |event: &CommandEvent, sequence: i64, _end_of_batch: bool| {
+                    let (command, seq_id, events_group) = {
+                        let cmd = event.lock().unwrap();
+                        (cmd.command, cmd.order_id, cmd.events_group)
+                    };
+                    if !is_persist_command(command) {
+                        let mut cmd = event.lock().unwrap();
+                        if !is_rejected(&cmd) {
+                            risk_engines[shard_id].lock().unwrap().pre_process(&mut cmd);
+                        }
+                        return;
+                    }
+
+                    pending.wait_completed(sequence - 1);
+                    persist_barrier.wait();
+                    if let (0, Some(target)) = (shard_id, &snapshot_target) {
+                        let state = PipelineState {
+                            risk_engines: risk_engines.iter().map(|engine| engine.lock().unwrap().clone()).collect(),
+                            matching_engines: matching_engines.iter().map(|engine| engine.lock().unwrap().serialize_state()).collect(),
+                            events_group: events_group + 1, // 快照命令独占一组
+                        };
+                        event.lock().unwrap().result_code = target.persist(command, seq_id, state);
+                    }
+                    persist_barrier.wait();
+                }) as StageHandler
+            })
+            .collect();
+
+        let num_shards = matching_engines.len();
+        let matching = matching_engines
+            .iter()
+            .enumerate()
+            .map(|(shard_id, engine)| {
+                let engine = engine.clone();
+                let market_data_publisher = market_data_publisher.clone();
+                Box::new(move |event: &CommandEvent, _sequence: i64, _end_of_batch: bool| {
+                    let mut cmd = event.lock().unwrap();
+                    if is_rejected(&cmd)
+                        || (MatchingEngineRouter::is_symbol_command(cmd.command)
+                            && MatchingEngineRouter::shard_for_symbol(cmd.symbol, num_shards) != shard_id)
+                    {
+                        return;
+                    }
+                    let mut engine = engine.lock().unwrap();
+                    let from = cmd.matcher_events.len();
+                    engine.process_order(&mut cmd);
+                    if let Some(publisher) = &market_data_publisher {
+                        publisher.lock().unwrap().on_command(&cmd, from, std::slice::from_mut(&mut *engine));
+                    }
+                }) as StageHandler
+            })
+            .collect();
+
+        let risk_post = risk_engines
+            .iter()
+            .map(|engine| {
+                let engine = engine.clone();
+                Box::new(move |event: &CommandEvent, _sequence: i64, _end_of_batch: bool| {
+                    let mut cmd = event.lock().unwrap();
+                    if !is_rejected(&cmd) {
+                        engine.lock().unwrap().post_process(&mut cmd);
+                    }
+                }) as StageHandler
+            })
+            .collect();
+
+        let result_consumer = self.result_consumer;
+        let results = vec![Box::new(move |event: &CommandEvent, sequence: i64, _end_of_batch: bool| {
+            let cmd = event.lock().unwrap();
+            if let Some(consumer) = &result_consumer {
+                consumer(&cmd);
+            }
+            pending.complete(sequence, &cmd);
+        }) as StageHandler];
+
+        PipelineStages {
+            grouping,
+            risk_pre,
+            matching,
+            risk_post,
+            results,
+        }
+    }
+
     pub fn serialize_state(&self) -> PipelineState {
         PipelineState {
             risk_engines: self.risk_engines.clone(),
             matching_engines: self.matching_engines.iter().map(|e| e.serialize_state()).collect(),
+            // 当前分组未结束时，恢复后从新组开始
+            events_group: self.grouping.group_counter() + (self.msgs_in_group > 0) as u64,
         }
     }
 
-    pub fn from_state(state: PipelineState) -> Self {
+    pub fn from_state(state: PipelineState, config: &ExchangeConfig) -> Self {
         Self {
-            risk_engines: state.risk_engines,
-            matching_engines: state.matching_engines.into_iter().map(MatchingEngineRouter::from_state).collect(),
+            grouping: GroupingProcessor::with_group_counter(config.msgs_in_group_limit, state.events_group),
+            msgs_in_group: 0,
+            risk_engines: state
+                .risk_engines
+                .into_iter()
+                .map(|mut engine| {
+                    engine.set_limits(config.risk_limits);
+                    engine.set_idempotency_capacity(config.idempotency_cache_size);
+                    config.spread_groups.iter().for_each(|&group| engine.add_spread_group(group));
+                    engine
+                })
+                .collect(),
+            matching_engines: state
+                .matching_engines
+                .into_iter()
+                .map(|state| {
+                    let mut engine = MatchingEngineRouter::from_state(state);
+                    engine.set_order_book_kind(config.order_book_kind);
+                    config.spread_groups.iter().for_each(|&group| engine.add_spread_group(group));
+                    engine
+                })
+                .collect(),
             result_consumer: None,
+            market_data_publisher: None,
+            invariant_check_interval: config.invariant_check_interval,
+            processed_commands: 0,
+            snapshot_target: None,
+            rate_limiter: config.rate_limit.map(RateLimiter::new),
         }
     }
     pub fn new(config: &ExchangeConfig) -> Self {
         // 创建风险引擎分片
         let risk_engines = (0..config.risk_engines_num)
-            .map(|shard_id| RiskEngine::new(shard_id, config.risk_engines_num))
+            .map(|shard_id| {
+                let mut engine = RiskEngine::new(shard_id, config.risk_engines_num);
+                engine.set_limits(config.risk_limits);
+                engine.set_idempotency_capacity(config.idempotency_cache_size);
+                config.spread_groups.iter().for_each(|&group| engine.add_spread_group(group));
+                engine
+            })
             .collect();
 
         // 创建撮合引擎分片
         let matching_engines = (0..config.matching_engines_num)
-            .map(|shard_id| MatchingEngineRouter::new(shard_id, config.matching_engines_num))
+            .map(|shard_id| {
+                let mut engine = MatchingEngineRouter::new(shard_id, config.matching_engines_num);
+                engine.set_order_book_kind(config.order_book_kind);
+                config.spread_groups.iter().for_each(|&group| engine.add_spread_group(group));
+                engine
+            })
             .collect();
 
         Self {
+            grouping: GroupingProcessor::new(config.msgs_in_group_limit),
+            msgs_in_group: 0,
             risk_engines,
             matching_engines,
             result_consumer: None,
+            market_data_publisher: None,
+            invariant_check_interval: config.invariant_check_interval,
+            processed_commands: 0,
+            snapshot_target: None,
+            rate_limiter: config.rate_limit.map(RateLimiter::new),
         }
     }
 
+    /// 设置在线快照的写入目标（PersistState 命令在处理线程内写入）
+    pub fn set_snapshot_target(&mut self, store: SnapshotStore, config: ExchangeConfig) {
+        self.snapshot_target = Some(SnapshotTarget { store, config });
+    }
+
     pub fn set_result_consumer(&mut self, consumer: ResultConsumer) {
         self.result_consumer = Some(consumer);
     }
 
+    /// 注册行情消费者（逐笔成交、BBO、L2 增量、隐含报价）
+    pub fn add_market_data_consumer(&mut self, consumer: MarketDataConsumer) {
+        if self.market_data_publisher.is_none() {
+            for engine in &mut self.matching_engines {
+                engine.enable_l2_deltas();
+                engine.enable_bbo_updates();
+            }
+        }
+        self.market_data_publisher
+            .get_or_insert_with(MarketDataPublisher::new)
+            .add_consumer(consumer);
+    }
+
+    /// 为各撮合分片注册订单簿监听器，factory 按分片号创建各分片的实例
+    pub fn add_order_book_listener(&mut self, factory: impl Fn(usize) -> Box<dyn OrderBookListener>) {
+        for (shard_id, engine) in self.matching_engines.iter_mut().enumerate() {
+            engine.add_listener(factory(shard_id));
+        }
+    }
+
+    /// 开启各撮合分片的订单簿只读视图，返回按分片合并的句柄
+    pub fn enable_book_views(&mut self, depth: usize) -> BookViews {
+        BookViews::from_shards(self.matching_engines.iter_mut().map(|engine| engine.enable_book_views(depth)))
+    }
+
     pub fn add_symbol(&mut self, spec: CoreSymbolSpecification) {
         for engine in &mut self.risk_engines {
             engine.add_symbol(spec.clone());
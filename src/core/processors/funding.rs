@@ -0,0 +1,66 @@
+use crate::api::*;
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+
+/// 资金费率精度：费率以百万分之一为单位
+pub const FUNDING_RATE_SCALE: i64 = 1_000_000;
+
+/// 单个交易对的资金费状态
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FundingState {
+    pub mark_price: Option<Price>,
+    pub last_funding_time: Option<i64>,
+}
+
+/// 资金费引擎：维护标记价格与结算时间，计算每笔持仓的资金费
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FundingEngine {
+    symbols: AHashMap<SymbolId, FundingState>,
+}
+
+impl FundingEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_mark_price(&mut self, symbol: SymbolId, price: Price) {
+        self.symbols.entry(symbol).or_default().mark_price = Some(price);
+    }
+
+    /// 交易对下架时移除其资金费状态
+    pub fn remove_symbol(&mut self, symbol: SymbolId) {
+        self.symbols.remove(&symbol);
+    }
+
+    pub fn get_state(&self, symbol: SymbolId) -> Option<&FundingState> {
+        self.symbols.get(&symbol)
+    }
+
+    /// 开始一次资金费结算：校验标记价格与结算间隔，成功时记录结算时间并返回标记价格
+    pub fn start_funding(&mut self, spec: &CoreSymbolSpecification, now: i64) -> Result<Price, CommandResultCode> {
+        if spec.symbol_type != SymbolType::PerpetualSwap {
+            return Err(CommandResultCode::UnsupportedSymbolType);
+        }
+
+        let state = self.symbols.entry(spec.symbol_id).or_default();
+        let Some(mark_price) = state.mark_price else {
+            return Err(CommandResultCode::RiskMarkPriceNotSet);
+        };
+        if let Some(last) = state.last_funding_time {
+            if now < last + spec.funding_interval {
+                return Err(CommandResultCode::RiskFundingNotDue);
+            }
+        }
+
+        state.last_funding_time = Some(now);
+        Ok(mark_price)
+    }
+
+    /// 单笔持仓的资金费（正数为支付，负数为收取）
+    ///
+    /// 费率为正时多头支付、空头收取；向上取整，保证收取方所得不超过支付方所付
+    pub fn funding_payment(net_position: i64, mark_price: Price, rate: i64, quote_scale_k: i64) -> i64 {
+        let raw = SymbolScale { base_scale_k: 1, quote_scale_k }.quote_value(net_position, mark_price).saturating_mul(rate as i128);
+        saturating_amount(-(raw.saturating_neg().div_euclid(FUNDING_RATE_SCALE as i128)))
+    }
+}
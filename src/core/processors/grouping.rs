@@ -9,23 +9,46 @@ pub struct GroupingProcessor {
 
 impl GroupingProcessor {
     pub fn new(msgs_in_group_limit: usize) -> Self {
+        Self::with_group_counter(msgs_in_group_limit, 0)
+    }
+
+    /// 从快照恢复：从指定组号开始分组
+    pub fn with_group_counter(msgs_in_group_limit: usize, group_counter: u64) -> Self {
         Self {
-            group_counter: AtomicU64::new(0),
-            msgs_in_group_limit,
+            group_counter: AtomicU64::new(group_counter),
+            msgs_in_group_limit: msgs_in_group_limit.max(1),
         }
     }
 
-    /// 处理命令，分配 events_group
-    pub fn process(&self, cmd: &mut OrderCommand, msgs_in_current_group: &mut usize) {
-        // 某些命令需要强制触发新组
-        if matches!(
-            cmd.command,
+    /// 下一条命令将使用的组号
+    pub fn group_counter(&self) -> u64 {
+        self.group_counter.load(Ordering::SeqCst)
+    }
+
+    /// 独占一个分组的命令：之前的组在此结束，之后的命令进入新组
+    pub fn is_group_boundary(command: OrderCommandType) -> bool {
+        matches!(
+            command,
             OrderCommandType::Reset
                 | OrderCommandType::PersistStateMatching
+                | OrderCommandType::PersistStateRisk
                 | OrderCommandType::GroupingControl
-        ) {
-            self.group_counter.fetch_add(1, Ordering::SeqCst);
-            *msgs_in_current_group = 0;
+        )
+    }
+
+    /// 处理命令，分配 events_group
+    pub fn process(&self, cmd: &mut OrderCommand, msgs_in_current_group: &mut usize) {
+        // 某些命令需要强制触发新组
+        if Self::is_group_boundary(cmd.command) {
+            if *msgs_in_current_group > 0 {
+                self.group_counter.fetch_add(1, Ordering::SeqCst);
+                *msgs_in_current_group = 0;
+            }
+            cmd.events_group = self.group_counter.fetch_add(1, Ordering::SeqCst);
+            if cmd.command == OrderCommandType::GroupingControl {
+                cmd.result_code = CommandResultCode::Success;
+            }
+            return;
         }
 
         cmd.events_group = self.group_counter.load(Ordering::SeqCst);
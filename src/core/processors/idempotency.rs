@@ -0,0 +1,76 @@
+use crate::api::*;
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// 已受理下单的结果（R2 结算前为 None）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct IdempotentEntry {
+    order_id: OrderId,
+    result_code: Option<CommandResultCode>,
+}
+
+/// 下单幂等缓存：按 (uid, client_order_id) 记录已受理的下单，重复提交不再进入撮合并返回原命令的结果
+///
+/// 每个风控分片只记录本分片用户的下单，超过容量时淘汰最早的记录；随风控快照保存，容量来自配置（0 关闭）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdempotencyCache {
+    #[serde(skip)]
+    capacity: usize,
+    entries: AHashMap<(UserId, u64), IdempotentEntry>,
+    order: VecDeque<(UserId, u64)>, // 按受理顺序，用于淘汰
+}
+
+impl IdempotencyCache {
+    /// 设置容量，缩小时立即淘汰多出的记录
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn key(cmd: &OrderCommand) -> Option<(UserId, u64)> {
+        (cmd.command == OrderCommandType::PlaceOrder && cmd.client_order_id != 0).then_some((cmd.uid, cmd.client_order_id))
+    }
+
+    /// R1：重复提交时返回原命令的订单号
+    pub fn original_order_id(&self, cmd: &OrderCommand) -> Option<OrderId> {
+        Self::key(cmd).and_then(|key| self.entries.get(&key)).map(|entry| entry.order_id)
+    }
+
+    /// R1：记录首次提交的下单（订单号已确定）
+    pub fn record(&mut self, cmd: &OrderCommand) {
+        let Some(key) = Self::key(cmd).filter(|_| self.capacity > 0) else {
+            return;
+        };
+        self.entries.insert(key, IdempotentEntry { order_id: cmd.order_id, result_code: None });
+        self.order.push_back(key);
+        self.evict();
+    }
+
+    /// R2：原命令记录最终结果码；原命令已结算的重复提交改为原命令的结果
+    ///
+    /// 流水线中重复提交的 R1 可能早于原命令的 R2，但两者的 R2 按提交顺序执行，此时原命令的结果已确定
+    pub fn on_settled(&mut self, cmd: &mut OrderCommand) {
+        let Some(key) = Self::key(cmd) else {
+            return;
+        };
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return;
+        };
+        match entry.result_code {
+            None => entry.result_code = Some(cmd.result_code),
+            Some(code) => {
+                cmd.result_code = code;
+                cmd.order_id = entry.order_id;
+            }
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(key) = self.order.pop_front() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use crate::api::*;
-use crate::core::orderbook::{OrderBook, OrderBookState};
-use ahash::AHashMap;
+use crate::core::book_view::{BookViewPublisher, BookViews};
+use crate::core::implied::{ImpliedOffer, ImpliedPricer, SpreadGroup};
+use crate::core::listener::{OrderBookListener, OrderBookListeners};
+use crate::core::market_data::{BboTracker, L2DeltaTracker};
+use crate::core::orderbook::{new_order_book, OrderBook, OrderBookState};
+use ahash::{AHashMap, AHashSet};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -9,12 +13,87 @@ pub struct MatchingEngineState {
     pub shard_id: usize,
     pub shard_mask: i32,
     pub order_books: HashMap<SymbolId, OrderBookState>, // 序列化使用标准 HashMap
+    pub user_orders: HashMap<UserId, Vec<(SymbolId, OrderId)>>,
+    #[serde(default)]
+    pub sessions: HashMap<SymbolId, TradingSessionState>, // 未记录的交易对处于连续交易
+    #[serde(default)]
+    pub sequence: u64, // 最后分配的序号
+    #[serde(default)]
+    pub engine_time: i64,
+    #[serde(default)]
+    pub trade_ids: HashMap<SymbolId, u64>, // 各交易对最后分配的成交编号
+}
+
+/// 用户挂单索引：uid -> (交易对, 订单ID)
+#[derive(Default)]
+struct UserOrderIndex {
+    by_user: AHashMap<UserId, AHashSet<(SymbolId, OrderId)>>,
+    owners: AHashMap<(SymbolId, OrderId), UserId>,
+}
+
+impl UserOrderIndex {
+    fn insert(&mut self, uid: UserId, symbol: SymbolId, order_id: OrderId) {
+        self.owners.insert((symbol, order_id), uid);
+        self.by_user.entry(uid).or_default().insert((symbol, order_id));
+    }
+
+    fn remove(&mut self, symbol: SymbolId, order_id: OrderId) {
+        let Some(uid) = self.owners.remove(&(symbol, order_id)) else {
+            return;
+        };
+        // 保留用户的空集合，下次挂单复用其容量
+        if let Some(orders) = self.by_user.get_mut(&uid) {
+            orders.remove(&(symbol, order_id));
+        }
+    }
+
+    /// 根据命令处理结果同步索引
+    fn on_command(&mut self, book: &dyn OrderBook, cmd: &OrderCommand, activated: &[OrderId]) {
+        for event in &cmd.matcher_events {
+            if event.maker_completed {
+                self.remove(cmd.symbol, event.matched_order_id);
+            }
+        }
+        for &order_id in activated {
+            match book.get_open_order(order_id) {
+                Some(order) => self.insert(order.uid, cmd.symbol, order_id),
+                None => self.remove(cmd.symbol, order_id),
+            }
+        }
+
+        match cmd.command {
+            OrderCommandType::PlaceOrder
+            | OrderCommandType::MoveOrder
+            | OrderCommandType::CancelOrder
+            | OrderCommandType::ReduceOrder
+            | OrderCommandType::AmendOrder => match book.get_open_order(cmd.order_id) {
+                Some(order) => self.insert(order.uid, cmd.symbol, cmd.order_id),
+                None => self.remove(cmd.symbol, cmd.order_id),
+            },
+            _ => {}
+        }
+    }
 }
 
 pub struct MatchingEngineRouter {
     shard_id: usize,
     shard_mask: i32,
     order_books: AHashMap<SymbolId, Box<dyn OrderBook>>,
+    l2_tracker: Option<L2DeltaTracker>, // 未开启时不产生增量
+    l2_deltas: Vec<L2Delta>,
+    level_updates: Vec<LevelUpdate>, // 订单簿产生的档位变化（开启 L2 增量或注册监听器后记录）
+    bbo_tracker: Option<BboTracker>, // 未开启时不跟踪 BBO
+    bbo_updates: Vec<BboUpdate>,
+    implied: Option<ImpliedPricer>, // 未配置价差组时不计算隐含报价
+    implied_updates: Vec<BboUpdate>,
+    book_views: Option<BookViewPublisher>, // 未开启时不发布只读视图
+    listeners: OrderBookListeners,
+    user_orders: UserOrderIndex,
+    sessions: AHashMap<SymbolId, TradingSessionState>,
+    order_book_kind: OrderBookKind, // 交易对未指定时使用的订单簿实现
+    sequence: u64,    // 最后分配的命令/事件序号
+    engine_time: i64, // 已处理命令的最大时间戳
+    trade_ids: AHashMap<SymbolId, u64>, // 各交易对最后分配的成交编号
 }
 
 impl MatchingEngineRouter {
@@ -27,6 +106,17 @@ impl MatchingEngineRouter {
             shard_id: self.shard_id,
             shard_mask: self.shard_mask,
             order_books: books_state,
+            user_orders: self
+                .user_orders
+                .by_user
+                .iter()
+                .filter(|(_, orders)| !orders.is_empty())
+                .map(|(uid, orders)| (*uid, orders.iter().copied().collect()))
+                .collect(),
+            sessions: self.sessions.iter().map(|(symbol, state)| (*symbol, *state)).collect(),
+            sequence: self.sequence,
+            engine_time: self.engine_time,
+            trade_ids: self.trade_ids.iter().map(|(symbol, id)| (*symbol, *id)).collect(),
         }
     }
 
@@ -38,13 +128,35 @@ impl MatchingEngineRouter {
                 OrderBookState::Direct(book) => Box::new(book),
                 OrderBookState::DirectOptimized(book) => Box::new(book),
                 OrderBookState::Advanced(book) => Box::new(book),
+                OrderBookState::DirectOptimizedLadder(book) => Box::new(book),
             };
             order_books.insert(symbol_id, book);
         }
+        let mut user_orders = UserOrderIndex::default();
+        for (uid, orders) in state.user_orders {
+            for (symbol, order_id) in orders {
+                user_orders.insert(uid, symbol, order_id);
+            }
+        }
         Self {
             shard_id: state.shard_id,
             shard_mask: state.shard_mask,
             order_books,
+            l2_tracker: None,
+            l2_deltas: Vec::new(),
+            level_updates: Vec::new(),
+            bbo_tracker: None,
+            bbo_updates: Vec::new(),
+            implied: None,
+            implied_updates: Vec::new(),
+            book_views: None,
+            listeners: OrderBookListeners::default(),
+            user_orders,
+            sessions: state.sessions.into_iter().collect(),
+            order_book_kind: OrderBookKind::default(),
+            sequence: state.sequence,
+            engine_time: state.engine_time,
+            trade_ids: state.trade_ids.into_iter().collect(),
         }
     }
 
@@ -54,48 +166,751 @@ impl MatchingEngineRouter {
             shard_id,
             shard_mask: (num_shards - 1) as i32,
             order_books: AHashMap::new(),
+            l2_tracker: None,
+            l2_deltas: Vec::new(),
+            level_updates: Vec::new(),
+            bbo_tracker: None,
+            bbo_updates: Vec::new(),
+            implied: None,
+            implied_updates: Vec::new(),
+            book_views: None,
+            listeners: OrderBookListeners::default(),
+            user_orders: UserOrderIndex::default(),
+            sessions: AHashMap::new(),
+            order_book_kind: OrderBookKind::default(),
+            sequence: 0,
+            engine_time: 0,
+            trade_ids: AHashMap::new(),
         }
     }
 
+    /// 设置默认订单簿实现（只影响之后添加的交易对）
+    pub fn set_order_book_kind(&mut self, kind: OrderBookKind) {
+        self.order_book_kind = kind;
+    }
+
+    /// 开启 L2 增量行情
+    pub fn enable_l2_deltas(&mut self) {
+        self.l2_tracker.get_or_insert_with(L2DeltaTracker::new);
+        self.order_books.values_mut().for_each(|book| book.enable_level_updates());
+    }
+
+    /// 是否需要订单簿记录档位变化
+    fn level_updates_enabled(&self) -> bool {
+        self.l2_tracker.is_some() || !self.listeners.is_empty()
+    }
+
+    /// 取出自上次调用以来产生的 L2 增量
+    pub fn drain_l2_deltas(&mut self) -> Vec<L2Delta> {
+        std::mem::take(&mut self.l2_deltas)
+    }
+
+    /// 开启 BBO 变化跟踪
+    pub fn enable_bbo_updates(&mut self) {
+        self.bbo_tracker.get_or_insert_with(BboTracker::new);
+    }
+
+    /// 取出自上次调用以来产生的 BBO 变化
+    pub fn drain_bbo_updates(&mut self) -> Vec<BboUpdate> {
+        std::mem::take(&mut self.bbo_updates)
+    }
+
+    /// 添加日历价差组，开启隐含报价计算；开启 BBO 跟踪时隐含报价的变化随行情发布
+    ///
+    /// 价差组的三个交易对须属于同一撮合分片，均不属于本分片时忽略
+    pub fn add_spread_group(&mut self, group: SpreadGroup) {
+        let owned = [group.spread, group.front, group.back].map(|symbol| self.symbol_for_this_shard(symbol));
+        if owned.iter().all(|owned| !owned) {
+            return;
+        }
+        assert!(owned.iter().all(|&owned| owned), "价差组 {:?} 的交易对不属于同一撮合分片", group);
+        self.implied.get_or_insert_with(ImpliedPricer::new).add_group(group);
+        self.update_implied(group.spread);
+    }
+
+    /// 交易对当前的隐含最优价（不属于本分片的价差组时返回 None）
+    pub fn implied_quote(&self, symbol: SymbolId) -> Option<BboUpdate> {
+        self.implied.as_ref()?.quote(symbol)
+    }
+
+    /// 取出自上次调用以来隐含报价的变化
+    pub fn drain_implied_updates(&mut self) -> Vec<BboUpdate> {
+        std::mem::take(&mut self.implied_updates)
+    }
+
+    /// 交易对订单簿可能发生变化后重新计算所在价差组的隐含报价
+    fn update_implied(&mut self, symbol: SymbolId) {
+        if let Some(pricer) = &mut self.implied {
+            let out = self.bbo_tracker.is_some().then_some(&mut self.implied_updates);
+            pricer.on_symbol(symbol, &self.order_books, out);
+        }
+    }
+
+    /// 开启订单簿只读视图：每条命令处理后发布其触及交易对的视图（每侧最多 depth 档），
+    /// 返回的句柄可在其他线程无锁查询
+    pub fn enable_book_views(&mut self, depth: usize) -> BookViews {
+        let (mut publisher, views) = BookViewPublisher::new(depth);
+        let symbols: Vec<SymbolId> = self.order_books.keys().copied().collect();
+        publisher.publish(&self.order_books, symbols, self.sequence, self.engine_time);
+        self.book_views = Some(publisher);
+        views
+    }
+
+    /// 注册订单簿监听器：本分片处理命令时同步回调（不随快照保存，恢复后需重新注册）
+    pub fn add_listener(&mut self, listener: Box<dyn OrderBookListener>) {
+        self.listeners.add(listener);
+        self.order_books.values_mut().for_each(|book| book.enable_level_updates());
+    }
+
+    /// 查询本分片交易对的 L2 深度，交易对不在本分片时返回 None
+    pub fn get_l2_data(&self, symbol: SymbolId, depth: usize) -> Option<L2MarketData> {
+        self.order_books.get(&symbol).map(|book| {
+            // 按实际档位数截断，避免超大深度预分配
+            let levels = book.get_ask_buckets_count().max(book.get_bid_buckets_count());
+            book.get_l2_data(depth.min(levels))
+        })
+    }
+
+    /// 按数量询价（不修改订单簿），交易对不在本分片时返回 None
+    pub fn quote_for_size(&self, symbol: SymbolId, action: OrderAction, size: Size) -> Option<SizeQuote> {
+        self.order_books.get(&symbol).map(|book| book.quote_for_size(action, size))
+    }
+
+    /// 订单簿统计，交易对不在本分片时返回 None
+    pub fn stats(&self, symbol: SymbolId) -> Option<OrderBookStats> {
+        self.order_books.get(&symbol).map(|book| book.stats())
+    }
+
+    /// 查询用户挂单（symbol 为 None 时返回本分片所有交易对），按交易对、时间排序
+    pub fn get_user_orders(&self, uid: UserId, symbol: Option<SymbolId>) -> Vec<OpenOrder> {
+        let Some(orders) = self.user_orders.by_user.get(&uid) else {
+            return Vec::new();
+        };
+        let mut result: Vec<OpenOrder> = orders
+            .iter()
+            .filter(|(s, _)| symbol.is_none_or(|symbol| symbol == *s))
+            .filter_map(|(s, order_id)| self.order_books.get(s)?.get_open_order(*order_id))
+            .filter(|order| order.uid == uid)
+            .collect();
+        result.sort_by_key(|order| (order.symbol, order.timestamp, order.order_id));
+        result
+    }
+
+    /// 交易对当前交易时段
+    pub fn session_state(&self, symbol: SymbolId) -> TradingSessionState {
+        self.sessions.get(&symbol).copied().unwrap_or(TradingSessionState::ContinuousTrading)
+    }
+
+    /// 本分片全部挂单（含未触发的止损单），用于资金对账
+    pub fn get_all_orders(&self) -> Vec<OpenOrder> {
+        self.order_books
+            .iter()
+            .filter(|(&symbol, _)| self.symbol_for_this_shard(symbol))
+            .flat_map(|(_, book)| book.get_all_orders())
+            .collect()
+    }
+
+    /// 交易对所属的撮合分片（分片数为 2 的幂，等价于 symbol_id % num_shards）
+    pub fn shard_for_symbol(symbol: SymbolId, num_shards: usize) -> usize {
+        (symbol & (num_shards - 1) as i32) as usize
+    }
+
+    /// 只需由交易对所属分片处理的命令
+    pub fn is_symbol_command(command: OrderCommandType) -> bool {
+        matches!(
+            command,
+            OrderCommandType::PlaceOrder
+                | OrderCommandType::CancelOrder
+                | OrderCommandType::MoveOrder
+                | OrderCommandType::ReduceOrder
+                | OrderCommandType::AmendOrder
+                | OrderCommandType::ExpireOrders
+                | OrderCommandType::OrderBookRequest
+                | OrderCommandType::UserOrdersRequest
+                | OrderCommandType::SetSessionState
+                | OrderCommandType::ReferencePriceUpdate
+                | OrderCommandType::DelistSymbol
+                | OrderCommandType::ClearOrderBook
+                | OrderCommandType::ExpireSeries
+        )
+    }
+
     fn symbol_for_this_shard(&self, symbol: SymbolId) -> bool {
         self.shard_mask == 0 || (symbol & self.shard_mask) == self.shard_id as i32
     }
 
+    /// 添加交易对，不属于本分片的交易对直接忽略
     pub fn add_symbol(&mut self, spec: CoreSymbolSpecification) {
-        use crate::core::orderbook::DirectOrderBook;
-        self.order_books.insert(spec.symbol_id, Box::new(DirectOrderBook::new(spec)));
+        if !self.symbol_for_this_shard(spec.symbol_id) {
+            return;
+        }
+        let kind = spec.order_book_kind(self.order_book_kind);
+        let symbol = spec.symbol_id;
+        let mut book = new_order_book(kind, spec);
+        if self.level_updates_enabled() {
+            book.enable_level_updates();
+        }
+        self.order_books.insert(symbol, book);
+        self.update_implied(symbol);
+        if let Some(publisher) = &mut self.book_views {
+            publisher.publish(&self.order_books, [symbol], self.sequence, self.engine_time);
+        }
     }
 
     pub fn process_order(&mut self, cmd: &mut OrderCommand) {
+        let from = cmd.matcher_events.len();
+        self.route_command(cmd);
+        self.assign_sequence(cmd, from);
+        if let Some(publisher) = &mut self.book_views {
+            publisher.on_command(cmd, &self.order_books, self.sequence, self.engine_time);
+        }
+    }
+
+    /// 分配引擎序号与时间：本分片处理的交易对命令先取序号，其后本分片产生的事件依次递增；
+    /// 引擎时间取已处理命令时间戳的最大值，保证重放结果一致且单调不减；成交事件另按交易对分配成交编号
+    fn assign_sequence(&mut self, cmd: &mut OrderCommand, from: usize) {
+        let owned = Self::is_symbol_command(cmd.command) && self.symbol_for_this_shard(cmd.symbol);
+        if !owned && cmd.matcher_events.len() == from {
+            return;
+        }
+        self.engine_time = self.engine_time.max(cmd.timestamp);
+        if owned {
+            self.sequence += 1;
+            cmd.sequence = self.sequence;
+            cmd.engine_timestamp = self.engine_time;
+        }
+        for index in from..cmd.matcher_events.len() {
+            let symbol = cmd.event_symbol(index);
+            let event = &mut cmd.matcher_events[index];
+            self.sequence += 1;
+            event.sequence = self.sequence;
+            event.timestamp = self.engine_time;
+            if event.event_type == MatcherEventType::Trade {
+                let trade_id = self.trade_ids.entry(symbol).or_insert(0);
+                *trade_id += 1;
+                event.trade_id = *trade_id;
+            }
+        }
+    }
+
+    fn route_command(&mut self, cmd: &mut OrderCommand) {
+        // 风控已校验通过的二进制命令，各分片各自执行
+        if cmd.command == OrderCommandType::BinaryDataCommand {
+            if cmd.result_code == CommandResultCode::Success {
+                self.process_binary_command(cmd);
+            }
+            return;
+        }
+
         // 如果已经有结果码（测试用），跳过撮合
         if cmd.result_code == CommandResultCode::Success {
             return;
         }
 
         match cmd.command {
+            OrderCommandType::PlaceOrder if self.implied.is_some() && Self::implied_eligible(cmd) && self.symbol_for_this_shard(cmd.symbol) => {
+                self.place_with_implied(cmd);
+            }
             OrderCommandType::PlaceOrder
             | OrderCommandType::CancelOrder
             | OrderCommandType::MoveOrder
-            | OrderCommandType::ReduceOrder => {
-                if self.symbol_for_this_shard(cmd.symbol) {
-                    self.process_matching_command(cmd);
+            | OrderCommandType::ReduceOrder
+            | OrderCommandType::AmendOrder
+            | OrderCommandType::ExpireOrders
+                if self.symbol_for_this_shard(cmd.symbol) =>
+            {
+                self.process_matching_command(cmd);
+            }
+            OrderCommandType::OrderBookRequest if self.symbol_for_this_shard(cmd.symbol) => {
+                self.process_order_book_request(cmd);
+            }
+            OrderCommandType::SetSessionState
+                if cmd.result_code == CommandResultCode::ValidForMatchingEngine && self.symbol_for_this_shard(cmd.symbol) =>
+            {
+                cmd.result_code = self.set_session_state(cmd.symbol, cmd.service_flags);
+            }
+            OrderCommandType::ReferencePriceUpdate
+                if cmd.result_code == CommandResultCode::ValidForMatchingEngine && self.symbol_for_this_shard(cmd.symbol) =>
+            {
+                self.process_matching_command(cmd);
+            }
+            OrderCommandType::DelistSymbol | OrderCommandType::ClearOrderBook | OrderCommandType::ExpireSeries
+                if cmd.result_code == CommandResultCode::ValidForMatchingEngine && self.symbol_for_this_shard(cmd.symbol) =>
+            {
+                cmd.result_code = self.reset_order_book(cmd);
+            }
+            OrderCommandType::SuspendUser if cmd.result_code == CommandResultCode::ValidForMatchingEngine => {
+                let orders = self.get_user_orders(cmd.uid, None);
+                self.cancel_orders(orders, cmd);
+            }
+            OrderCommandType::PlaceBasket if cmd.result_code == CommandResultCode::ValidForMatchingEngine => {
+                self.place_basket(cmd);
+            }
+            OrderCommandType::UserOrdersRequest if self.symbol_for_this_shard(cmd.symbol) => {
+                if self.order_books.contains_key(&cmd.symbol) {
+                    cmd.open_orders = self.get_user_orders(cmd.uid, Some(cmd.symbol));
+                    cmd.result_code = CommandResultCode::Success;
+                } else {
+                    cmd.result_code = CommandResultCode::MatchingInvalidOrderBookId;
                 }
             }
             _ => {}
         }
     }
 
+    fn process_binary_command(&mut self, cmd: &OrderCommand) {
+        if cmd.service_flags == BINARY_COMMAND_ADD_SYMBOLS {
+            for spec in cmd.decode_symbols().unwrap_or_default() {
+                self.add_symbol(spec);
+            }
+        }
+    }
+
+    fn set_session_state(&mut self, symbol: SymbolId, code: i32) -> CommandResultCode {
+        if !self.order_books.contains_key(&symbol) {
+            return CommandResultCode::MatchingInvalidOrderBookId;
+        }
+        let Some(next) = TradingSessionState::from_code(code) else {
+            return CommandResultCode::MatchingInvalidSessionTransition;
+        };
+        if !self.session_state(symbol).can_transition_to(next) {
+            return CommandResultCode::MatchingInvalidSessionTransition;
+        }
+        self.sessions.insert(symbol, next);
+        CommandResultCode::Success
+    }
+
+    /// 当前交易时段是否允许该命令；撤单、减量和过期扫描始终允许
+    fn session_allows(state: TradingSessionState, book: &dyn OrderBook, cmd: &OrderCommand) -> bool {
+        match cmd.command {
+            OrderCommandType::PlaceOrder => match state {
+                TradingSessionState::ContinuousTrading => true,
+                TradingSessionState::PreOpen => {
+                    matches!(
+                        cmd.order_type,
+                        OrderType::Gtc
+                            | OrderType::PostOnly
+                            | OrderType::Gtx
+                            | OrderType::Iceberg
+                            | OrderType::Day
+                            | OrderType::Gtd(_)
+                    ) && (Self::slides_post_only(cmd) || !Self::crosses_book(book, cmd))
+                }
+                TradingSessionState::CloseOnly => matches!(
+                    cmd.order_type,
+                    OrderType::Ioc | OrderType::Fok | OrderType::FokBudget | OrderType::IocBudget | OrderType::Market
+                ),
+                TradingSessionState::Halted | TradingSessionState::Closed => false,
+            },
+            // 非连续交易时段不激活止损单，参考价格由行情源在恢复交易后重新推送
+            OrderCommandType::MoveOrder | OrderCommandType::AmendOrder | OrderCommandType::ReferencePriceUpdate => {
+                state == TradingSessionState::ContinuousTrading
+            }
+            _ => true,
+        }
+    }
+
+    /// 未进入订单簿的命令：风控已冻结资金的新单与改单追加的冻结以拒绝事件返还
+    fn reject_risk_hold(cmd: &mut OrderCommand) {
+        if cmd.result_code != CommandResultCode::ValidForMatchingEngine {
+            return;
+        }
+        match cmd.command {
+            OrderCommandType::PlaceOrder => {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+            }
+            OrderCommandType::AmendOrder if cmd.amend_hold > 0 => {
+                cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.amend_hold, cmd.price, cmd.reserve_price));
+            }
+            _ => {}
+        }
+    }
+
+    /// 改单：增加的数量不超过风控追加冻结的 amend_hold 手（流水线中尚未结算的成交已减少剩余数量时，新数量相应减少），
+    /// 未用完的追加冻结计入改单事件一并返还；改单失败时以拒绝事件返还
+    fn amend_order(book: &mut dyn OrderBook, cmd: &mut OrderCommand) -> CommandResultCode {
+        let requested = cmd.size;
+        let remaining = book
+            .get_open_order(cmd.order_id)
+            .filter(|order| order.uid == cmd.uid)
+            .map_or(0, |order| order.remaining);
+        cmd.size = requested.min(remaining + cmd.amend_hold);
+        let from = cmd.matcher_events.len();
+        let code = book.amend_order(cmd);
+        let increased = (cmd.size - remaining).max(0);
+        cmd.size = requested;
+
+        if code == CommandResultCode::Success {
+            cmd.matcher_events[from].size += cmd.amend_hold - increased;
+        } else if cmd.amend_hold > 0 {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.amend_hold, cmd.price, cmd.reserve_price));
+        }
+        code
+    }
+
+    /// 修改订单簿后校验内部不变量（debug-invariants 特性），违反时 panic
+    #[cfg(feature = "debug-invariants")]
+    fn check_book(book: &dyn OrderBook, cmd: &OrderCommand) {
+        if let Err(violation) = book.check_invariants() {
+            panic!("{violation}（命令 {:?}，订单 {}）", cmd.command, cmd.order_id);
+        }
+    }
+
+    /// 开启滑价的只挂单：交叉时由订单簿改价挂出，不会成交
+    fn slides_post_only(cmd: &OrderCommand) -> bool {
+        cmd.order_type == OrderType::PostOnly && cmd.post_only_slide
+    }
+
+    /// 限价单是否会与对手盘成交
+    fn crosses_book(book: &dyn OrderBook, cmd: &OrderCommand) -> bool {
+        let top = book.get_l2_data(1);
+        match cmd.action {
+            OrderAction::Bid => top.ask_prices.first().is_some_and(|&ask| cmd.price >= ask),
+            OrderAction::Ask => top.bid_prices.first().is_some_and(|&bid| cmd.price <= bid),
+        }
+    }
+
+    /// 下架交易对、期权到期或清空订单簿：撤销全部挂单（含未触发的止损单），清空时以同一实现重建空订单簿
+    fn reset_order_book(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(book) = self.order_books.get(&cmd.symbol) else {
+            return CommandResultCode::MatchingInvalidOrderBookId;
+        };
+        let mut orders = book.get_all_orders();
+        orders.sort_by_key(|order| (order.timestamp, order.order_id));
+        self.cancel_orders(orders, cmd);
+
+        if matches!(cmd.command, OrderCommandType::DelistSymbol | OrderCommandType::ExpireSeries) {
+            self.order_books.remove(&cmd.symbol);
+            self.sessions.remove(&cmd.symbol);
+        } else {
+            let enabled = self.level_updates_enabled();
+            if let Some(book) = self.order_books.get_mut(&cmd.symbol) {
+                let kind = book.serialize_state().kind();
+                *book = new_order_book(kind, book.get_symbol_spec().clone());
+                if enabled {
+                    book.enable_level_updates();
+                }
+            }
+        }
+        self.update_implied(cmd.symbol);
+        CommandResultCode::Success
+    }
+
+    /// 批量撤单（暂停用户、下架交易对、清空订单簿时使用）
+    ///
+    /// 被撤订单依次写入 cmd.open_orders，与 cmd.matcher_events 中的撤单事件一一对应
+    fn cancel_orders(&mut self, orders: Vec<OpenOrder>, cmd: &mut OrderCommand) {
+        for order in orders {
+            let Some(book) = self.order_books.get_mut(&order.symbol) else {
+                continue;
+            };
+
+            let mut cancel = OrderCommand {
+                command: OrderCommandType::CancelOrder,
+                uid: order.uid,
+                order_id: order.order_id,
+                symbol: order.symbol,
+                timestamp: cmd.timestamp,
+                ..Default::default()
+            };
+            if book.cancel_order(&mut cancel) != CommandResultCode::Success {
+                continue;
+            }
+            #[cfg(feature = "debug-invariants")]
+            Self::check_book(book.as_ref(), &cancel);
+            self.user_orders.remove(order.symbol, order.order_id);
+            self.level_updates.clear();
+            book.take_level_updates(&mut self.level_updates);
+            self.listeners.on_purged(&cancel, &order, &self.level_updates);
+
+            if let Some(tracker) = &mut self.l2_tracker {
+                tracker.on_level_updates(order.symbol, &self.level_updates, &mut self.l2_deltas);
+            }
+            if let Some(tracker) = &mut self.bbo_tracker {
+                tracker.on_command(order.symbol, book.as_ref(), &mut self.bbo_updates);
+            }
+            self.update_implied(order.symbol);
+
+            for mut event in cancel.matcher_events {
+                event.matched_order_id = order.order_id;
+                event.matched_order_uid = order.uid;
+                event.maker_action = Some(order.action);
+                event.maker_completed = true;
+                cmd.matcher_events.push(event);
+                cmd.open_orders.push(order.clone());
+            }
+        }
+    }
+
+    /// 组合下单：依次撮合属于本分片交易对的腿，各腿的结果码写回 binary_data
+    ///
+    /// 腿产生的事件以该腿为 taker（止损单激活的事件保留被激活的订单），写入 cmd.matcher_events 并在 cmd.open_orders 中记录所属的腿
+    fn place_basket(&mut self, cmd: &mut OrderCommand) {
+        let Ok(mut legs) = cmd.decode_basket() else {
+            return;
+        };
+        let mut placed = false;
+        for leg in &mut legs {
+            if !self.symbol_for_this_shard(leg.symbol) {
+                continue;
+            }
+            let mut order = OrderCommand { result_code: CommandResultCode::ValidForMatchingEngine, ..leg.clone() };
+            self.process_matching_command(&mut order);
+            leg.result_code = order.result_code;
+            placed = true;
+
+            let owner = OpenOrder {
+                order_id: order.order_id,
+                uid: order.uid,
+                symbol: order.symbol,
+                action: order.action,
+                order_type: order.order_type,
+                price: order.price,
+                reserve_price: order.reserve_price,
+                size: order.size,
+                remaining: 0,
+                timestamp: order.timestamp,
+                client_order_id: order.client_order_id,
+            };
+            for mut event in order.matcher_events {
+                if event.taker_action.is_none() {
+                    event.taker_action = Some(order.action);
+                    event.taker_order_id = order.order_id;
+                    event.taker_uid = order.uid;
+                    event.taker_client_order_id = order.client_order_id;
+                }
+                cmd.matcher_events.push(event);
+                cmd.open_orders.push(owner.clone());
+            }
+        }
+        if placed {
+            cmd.binary_data = bincode::serialize(&legs).expect("组合订单序列化失败");
+        }
+    }
+
+    /// 可参与隐含撮合的订单：风控已通过、不带附加成交条件的限价单（GTC/IOC/Day/GTD），且不指定自成交预防
+    fn implied_eligible(cmd: &OrderCommand) -> bool {
+        cmd.result_code == CommandResultCode::ValidForMatchingEngine
+            && matches!(cmd.order_type, OrderType::Gtc | OrderType::Ioc | OrderType::Day | OrderType::Gtd(_))
+            && cmd.stop_price.is_none()
+            && cmd.min_fill_size.is_none()
+            && cmd.activate_time.is_none()
+            && cmd.stp_mode == StpMode::None
+    }
+
+    /// 价差组交易对的限价单：按价格优先依次与本订单簿、隐含流动性成交（同价时本订单簿优先），剩余数量按原订单类型进入本订单簿
+    ///
+    /// 隐含成交以命令自身订单的身份在另外两个订单簿同时吃单，随后以减量事件扣减该订单的数量（price 为合成价格）；
+    /// 各腿的成交事件以该订单为 taker，并在 cmd.open_orders 中记录所属的腿。拆分出的各部分分别经过订单簿、监听器与行情处理
+    fn place_with_implied(&mut self, cmd: &mut OrderCommand) {
+        let symbol = cmd.symbol;
+        let mut owners: Vec<OpenOrder> = Vec::new();
+        let mut remaining = cmd.size;
+        let mut split = false;
+        if self.implied_ready(symbol, cmd.order_id) {
+            while let Some(offer) = self.implied_offer(cmd, remaining) {
+                let direct = self.direct_volume(symbol, cmd.action, offer.price);
+                let filled = if direct > 0 {
+                    let owner = Self::owner(cmd, symbol, cmd.action, offer.price, cmd.order_type);
+                    self.take_liquidity(cmd, &mut owners, owner, direct.min(remaining))
+                } else {
+                    self.take_implied(cmd, &mut owners, offer, remaining)
+                };
+                split = true;
+                remaining -= filled;
+                if filled == 0 {
+                    break;
+                }
+            }
+        }
+        if !split {
+            self.process_matching_command(cmd);
+            return;
+        }
+
+        cmd.result_code = CommandResultCode::Success;
+        if remaining > 0 {
+            let events = std::mem::take(&mut cmd.matcher_events);
+            let mut rest = OrderCommand { size: remaining, ..cmd.clone() };
+            rest.result_code = CommandResultCode::ValidForMatchingEngine;
+            self.process_matching_command(&mut rest);
+            cmd.matcher_events = events;
+            cmd.result_code = rest.result_code;
+            for event in rest.matcher_events {
+                cmd.matcher_events.push(event);
+                owners.push(Self::owner(cmd, symbol, cmd.action, cmd.price, cmd.order_type));
+            }
+        }
+        if owners.iter().any(|owner| owner.symbol != symbol) {
+            cmd.open_orders = owners;
+        }
+    }
+
+    /// 交易对处于连续交易、订单簿存在且允许隐含成交（未设默认自成交预防，没有同号挂单）
+    fn implied_ready(&self, symbol: SymbolId, order_id: OrderId) -> bool {
+        self.session_state(symbol) == TradingSessionState::ContinuousTrading
+            && self
+                .order_books
+                .get(&symbol)
+                .is_some_and(|book| book.get_symbol_spec().stp_mode == StpMode::None && book.get_open_order(order_id).is_none())
+    }
+
+    /// 与 cmd 限价相交的最优隐含流动性，数量不超过 remaining
+    fn implied_offer(&self, cmd: &OrderCommand, remaining: Size) -> Option<ImpliedOffer> {
+        if remaining <= 0 {
+            return None;
+        }
+        let pricer = self.implied.as_ref()?;
+        let offer = pricer.best_offer(cmd.symbol, cmd.action, &self.order_books, |leg| self.implied_ready(leg, cmd.order_id))?;
+        let crosses = match cmd.action {
+            OrderAction::Bid => offer.price <= cmd.price,
+            OrderAction::Ask => offer.price >= cmd.price,
+        };
+        crosses.then_some(ImpliedOffer { size: offer.size.min(remaining), ..offer })
+    }
+
+    /// symbol 订单簿中 action 方向的订单按不劣于 price 可吃到的显示数量
+    fn direct_volume(&self, symbol: SymbolId, action: OrderAction, price: Price) -> Size {
+        let mut volume = 0;
+        if let Some(book) = self.order_books.get(&symbol) {
+            book.visit_levels(action.opposite(), &mut |level, size| {
+                let within = match action {
+                    OrderAction::Bid => level <= price,
+                    OrderAction::Ask => level >= price,
+                };
+                if within {
+                    volume += size;
+                }
+                within
+            });
+        }
+        volume
+    }
+
+    /// 隐含成交：两条腿依次按对手方最优价吃单，再以减量事件扣减命令自身订单已成交的数量，返回成交数量
+    ///
+    /// 腿的数量不超过对手方最优档的显示数量，正常情况下全部成交；第二条腿成交不足时只按其成交数量扣减
+    fn take_implied(&mut self, cmd: &mut OrderCommand, owners: &mut Vec<OpenOrder>, offer: ImpliedOffer, remaining: Size) -> Size {
+        let mut filled = offer.size;
+        for (leg, price) in offer.legs {
+            if filled == 0 {
+                break;
+            }
+            let owner = Self::owner(cmd, leg.symbol, leg.action, price, OrderType::Ioc);
+            filled = self.take_liquidity(cmd, owners, owner, filled);
+        }
+        if filled > 0 {
+            cmd.matcher_events.push(MatcherTradeEvent::new_reduce(filled, offer.price, cmd.reserve_price, remaining - filled));
+            owners.push(Self::owner(cmd, cmd.symbol, cmd.action, cmd.price, cmd.order_type));
+        }
+        filled
+    }
+
+    /// 以命令自身订单的身份在 owner 的订单簿按 owner 的方向与价格吃单 size 手（IOC），事件追加到 cmd，返回成交数量
+    ///
+    /// 未成交部分不生成拒绝事件：冻结仍在命令自身的订单上
+    fn take_liquidity(&mut self, cmd: &mut OrderCommand, owners: &mut Vec<OpenOrder>, owner: OpenOrder, size: Size) -> Size {
+        let leg = owner.symbol != cmd.symbol;
+        let mut take = OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            result_code: CommandResultCode::ValidForMatchingEngine,
+            uid: cmd.uid,
+            order_id: cmd.order_id,
+            symbol: owner.symbol,
+            price: owner.price,
+            reserve_price: if leg { owner.price } else { cmd.reserve_price },
+            size,
+            action: owner.action,
+            order_type: OrderType::Ioc,
+            timestamp: cmd.timestamp,
+            client_order_id: cmd.client_order_id,
+            ..Default::default()
+        };
+        self.process_matching_command(&mut take);
+
+        let mut filled = 0;
+        for mut event in take.matcher_events {
+            if event.taker_action.is_none() {
+                if event.event_type != MatcherEventType::Trade {
+                    continue;
+                }
+                filled += event.size;
+                if leg {
+                    event.taker_action = Some(owner.action);
+                    event.taker_order_id = cmd.order_id;
+                    event.taker_uid = cmd.uid;
+                    event.taker_client_order_id = cmd.client_order_id;
+                }
+            }
+            cmd.matcher_events.push(event);
+            owners.push(owner.clone());
+        }
+        filled
+    }
+
+    /// 事件所属的订单：命令自身的订单或其在 symbol 上的隐含成交腿
+    fn owner(cmd: &OrderCommand, symbol: SymbolId, action: OrderAction, price: Price, order_type: OrderType) -> OpenOrder {
+        OpenOrder {
+            order_id: cmd.order_id,
+            uid: cmd.uid,
+            symbol,
+            action,
+            order_type,
+            price,
+            reserve_price: if symbol == cmd.symbol { cmd.reserve_price } else { price },
+            size: cmd.size,
+            remaining: 0,
+            timestamp: cmd.timestamp,
+            client_order_id: cmd.client_order_id,
+        }
+    }
+
+    /// L2 深度查询，深度由 cmd.size 指定
+    fn process_order_book_request(&mut self, cmd: &mut OrderCommand) {
+        let depth = cmd.size.max(0) as usize;
+        match self.get_l2_data(cmd.symbol, depth) {
+            Some(l2) => {
+                cmd.market_data = Some(l2);
+                cmd.result_code = CommandResultCode::Success;
+            }
+            None => {
+                cmd.result_code = CommandResultCode::MatchingInvalidOrderBookId;
+            }
+        }
+    }
+
     fn process_matching_command(&mut self, cmd: &mut OrderCommand) {
         let Some(book) = self.order_books.get_mut(&cmd.symbol) else {
+            // 流水线中风控先于下架命令结算、撮合晚于下架命令的订单同样需要返还冻结
+            Self::reject_risk_hold(cmd);
             cmd.result_code = CommandResultCode::MatchingInvalidOrderBookId;
             return;
         };
 
+        let state = self.sessions.get(&cmd.symbol).copied().unwrap_or(TradingSessionState::ContinuousTrading);
+        if state != TradingSessionState::ContinuousTrading && !Self::session_allows(state, book.as_ref(), cmd) {
+            if matches!(cmd.command, OrderCommandType::PlaceOrder | OrderCommandType::AmendOrder)
+                && cmd.result_code != CommandResultCode::ValidForMatchingEngine
+            {
+                return;
+            }
+            Self::reject_risk_hold(cmd);
+            cmd.result_code = CommandResultCode::MatchingSessionRejected;
+            return;
+        }
+
+        let from = cmd.matcher_events.len();
+        let before = if self.listeners.is_empty() { None } else { book.get_open_order(cmd.order_id) };
+
         match cmd.command {
             OrderCommandType::PlaceOrder => {
                 if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
-                    book.new_order(cmd);
-                    cmd.result_code = CommandResultCode::Success;
+                    let code = book.new_order(cmd);
+                    if code != CommandResultCode::Success {
+                        // 订单簿拒单时不修改订单簿，风控已冻结的资金以拒绝事件返还
+                        cmd.matcher_events.push(MatcherTradeEvent::new_reject(cmd.size, cmd.price, cmd.reserve_price));
+                    }
+                    cmd.result_code = code;
                 }
             }
             OrderCommandType::CancelOrder => {
@@ -107,9 +922,49 @@ impl MatchingEngineRouter {
             OrderCommandType::ReduceOrder => {
                 cmd.result_code = book.reduce_order(cmd);
             }
+            OrderCommandType::AmendOrder => {
+                if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
+                    cmd.result_code = Self::amend_order(book.as_mut(), cmd);
+                }
+            }
+            OrderCommandType::ExpireOrders => {
+                book.expire_orders(cmd.timestamp, &mut cmd.matcher_events);
+                // 过期扫描同时激活到达生效时间的 GoodAfterTime 订单（只在连续交易时段激活，其余时段留待恢复交易后的扫描）
+                if state == TradingSessionState::ContinuousTrading {
+                    book.activate_pending_orders(cmd);
+                }
+                cmd.result_code = CommandResultCode::Success;
+            }
+            OrderCommandType::ReferencePriceUpdate => {
+                cmd.result_code = match StopTriggerSource::from_code(cmd.service_flags) {
+                    Some(source) => {
+                        book.update_reference_price(source, cmd.price, cmd);
+                        CommandResultCode::Success
+                    }
+                    None => CommandResultCode::MatchingUnsupportedCommand,
+                };
+            }
             _ => {
                 cmd.result_code = CommandResultCode::MatchingUnsupportedCommand;
             }
         }
+
+        #[cfg(feature = "debug-invariants")]
+        Self::check_book(book.as_ref(), cmd);
+
+        // 被激活的止损单与 GoodAfterTime 订单可能以限价挂出或已全部完结
+        let activated = book.take_activated_stops();
+        self.user_orders.on_command(book.as_ref(), cmd, &activated);
+        self.level_updates.clear();
+        book.take_level_updates(&mut self.level_updates);
+        self.listeners.on_command(book.as_ref(), cmd, from, before.as_ref(), &activated, &self.level_updates);
+
+        if let Some(tracker) = &mut self.l2_tracker {
+            tracker.on_level_updates(cmd.symbol, &self.level_updates, &mut self.l2_deltas);
+        }
+        if let Some(tracker) = &mut self.bbo_tracker {
+            tracker.on_command(cmd.symbol, book.as_ref(), &mut self.bbo_updates);
+        }
+        self.update_implied(cmd.symbol);
     }
 }
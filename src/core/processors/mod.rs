@@ -1,3 +1,6 @@
 pub mod grouping;
 pub mod risk_engine;
+pub mod funding;
 pub mod matching_engine;
+pub mod rate_limiter;
+pub mod idempotency;
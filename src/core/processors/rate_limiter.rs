@@ -0,0 +1,77 @@
+use crate::api::*;
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+
+/// 限流配置：每个 uid（或 uid + 交易对）一个令牌桶，时间取自命令 timestamp
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub capacity: u64,        // 桶容量（允许的突发命令数）
+    pub refill_interval: i64, // 补充一个令牌的间隔（与 timestamp 同单位，0 表示不补充）
+    pub per_symbol: bool,     // 按交易对分别限流
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: u64,
+    last_refill: i64,
+}
+
+/// 令牌桶限流器，在风控之前拒绝超频的交易命令
+///
+/// 只限制用户发起的下单、改单、撤单、减量；状态不随快照保存，恢复后令牌桶为满
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: AHashMap<(UserId, SymbolId), Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: AHashMap::new(),
+        }
+    }
+
+    fn is_limited_command(command: OrderCommandType) -> bool {
+        matches!(
+            command,
+            OrderCommandType::PlaceOrder
+                | OrderCommandType::MoveOrder
+                | OrderCommandType::CancelOrder
+                | OrderCommandType::ReduceOrder
+                | OrderCommandType::AmendOrder
+        )
+    }
+
+    /// 命令是否已被限流拒绝（后续阶段跳过）
+    pub fn is_rejected(cmd: &OrderCommand) -> bool {
+        cmd.result_code == CommandResultCode::RateLimitExceeded
+    }
+
+    /// 消耗一个令牌，令牌不足时将命令标记为 RateLimitExceeded
+    pub fn process(&mut self, cmd: &mut OrderCommand) {
+        if !Self::is_limited_command(cmd.command) {
+            return;
+        }
+
+        let key = (cmd.uid, if self.config.per_symbol { cmd.symbol } else { 0 });
+        let (capacity, interval) = (self.config.capacity, self.config.refill_interval);
+        let now = cmd.timestamp;
+        let bucket = self.buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+
+        if interval > 0 && now > bucket.last_refill {
+            let refill = ((now - bucket.last_refill) / interval) as u64;
+            bucket.tokens = (bucket.tokens + refill).min(capacity);
+            bucket.last_refill = if bucket.tokens == capacity { now } else { bucket.last_refill + refill as i64 * interval };
+        }
+
+        if bucket.tokens > 0 {
+            bucket.tokens -= 1;
+        } else {
+            cmd.result_code = CommandResultCode::RateLimitExceeded;
+        }
+    }
+}
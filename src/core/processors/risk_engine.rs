@@ -1,7 +1,38 @@
 use crate::api::*;
-use crate::core::users::UserProfileService;
-use ahash::AHashMap;
+use crate::core::implied::SpreadGroup;
+use crate::core::invariants::{BalanceTotals, StuckHold};
+use crate::core::processors::funding::FundingEngine;
+use crate::core::processors::idempotency::IdempotencyCache;
+use crate::core::users::{OpenOrderRecord, SymbolPositionRecord, UserProfile, UserProfileService, UserStatus, WithdrawalState};
+use ahash::{AHashMap, AHashSet};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 风控限额（0 表示不限制）
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RiskLimits {
+    pub max_open_orders_per_user: usize,
+    pub max_open_orders_per_symbol: usize, // 单用户在单个交易对的挂单数
+    pub max_order_size: Size,
+    pub max_order_notional: i64, // 单笔金额（quote 币）
+}
+
+/// R2 中单个撮合事件从挂单记录释放的冻结 (币种, 金额)（None 表示本分片没有该挂单的记录）
+#[derive(Debug, Clone, Copy, Default)]
+struct ReleasedHold {
+    taker: Option<(Currency, i64)>,
+    maker: Option<(Currency, i64)>,
+}
+
+impl ReleasedHold {
+    fn taker_amount(&self) -> Option<i64> {
+        self.taker.map(|(_, amount)| amount)
+    }
+
+    fn maker_amount(&self) -> Option<i64> {
+        self.maker.map(|(_, amount)| amount)
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct RiskEngine {
@@ -9,6 +40,28 @@ pub struct RiskEngine {
     shard_mask: u64,
     user_service: UserProfileService,
     symbols: AHashMap<SymbolId, CoreSymbolSpecification>, // 运行时使用 AHashMap
+    fees: FeeReport, // 本分片用户产生的手续费
+    funding: FundingEngine,
+    deposits: AHashMap<Currency, i64>,          // 净入金（余额调整累计，扣除已提取的手续费）
+    funding_collected: AHashMap<Currency, i64>, // 资金费轧差（支付与收取之差，来自向上取整）
+    #[serde(default)]
+    reference_prices: AHashMap<SymbolId, ReferencePrices>, // 外部推送的标记价与指数价
+    #[serde(default)]
+    expiring: AHashSet<SymbolId>, // 已受理 ExpireSeries、尚未在 R2 结算的期权系列（拒绝新单与行权）
+    #[serde(default)]
+    option_positions: AHashMap<SymbolId, BTreeMap<UserId, Size>>, // 期权全体用户的净持仓（各分片按全部成交维护，用于行权指派）
+    #[serde(default)]
+    assigned_order_ids: u64, // 本分片已分配的订单号个数
+    #[serde(default)]
+    idempotency: IdempotencyCache, // 下单幂等键（容量来自配置，0 关闭）
+    #[serde(default)]
+    maker_epoch: u64, // maker 成交量统计周期（重置次数）
+    #[serde(skip)]
+    limits: RiskLimits, // 来自配置，不随快照保存
+    #[serde(skip)]
+    spread_groups: Vec<SpreadGroup>, // 来自配置：价差组交易对的订单可能隐含成交为组内其他交易对的持仓
+    #[serde(skip)]
+    released: Vec<ReleasedHold>, // R2 逐事件释放的冻结（复用缓冲区，避免结算时分配）
 }
 
 impl RiskEngine {
@@ -19,9 +72,38 @@ impl RiskEngine {
             shard_mask: (num_shards - 1) as u64,
             user_service: UserProfileService::new(),
             symbols: AHashMap::new(),
+            fees: FeeReport::default(),
+            funding: FundingEngine::new(),
+            deposits: AHashMap::new(),
+            funding_collected: AHashMap::new(),
+            reference_prices: AHashMap::new(),
+            expiring: AHashSet::new(),
+            option_positions: AHashMap::new(),
+            assigned_order_ids: 0,
+            idempotency: IdempotencyCache::default(),
+            maker_epoch: 0,
+            limits: RiskLimits::default(),
+            spread_groups: Vec::new(),
+            released: Vec::new(),
         }
     }
 
+    pub fn set_limits(&mut self, limits: RiskLimits) {
+        self.limits = limits;
+    }
+
+    /// 添加日历价差组：组内期货订单按直接成交与隐含成交中较高的保证金冻结
+    pub fn add_spread_group(&mut self, group: SpreadGroup) {
+        if !self.spread_groups.contains(&group) {
+            self.spread_groups.push(group);
+        }
+    }
+
+    /// 设置下单幂等缓存容量（0 关闭）
+    pub fn set_idempotency_capacity(&mut self, capacity: usize) {
+        self.idempotency.set_capacity(capacity);
+    }
+
     fn uid_for_this_shard(&self, uid: UserId) -> bool {
         self.shard_mask == 0 || (uid & self.shard_mask) == self.shard_id as u64
     }
@@ -30,162 +112,1193 @@ impl RiskEngine {
         self.symbols.insert(spec.symbol_id, spec);
     }
 
+    /// 已收取的手续费
+    pub fn fee_report(&self) -> &FeeReport {
+        &self.fees
+    }
+
+    /// 取出并清零已收取的手续费（对账后调用）
+    pub fn reset_fees(&mut self) -> FeeReport {
+        let report = std::mem::take(&mut self.fees);
+        // 提取的手续费离开系统，从净入金中扣除
+        for (&currency, &amount) in &report.by_currency {
+            *self.deposits.entry(currency).or_insert(0) -= amount;
+        }
+        report
+    }
+
+    pub fn funding(&self) -> &FundingEngine {
+        &self.funding
+    }
+
+    /// 交易对当前的标记价与指数价（从未推送时返回 None）
+    pub fn reference_prices(&self, symbol: SymbolId) -> Option<&ReferencePrices> {
+        self.reference_prices.get(&symbol)
+    }
+
+    /// 按标记价计算持仓的未实现盈亏（quote 币）；交易对不存在或未推送标记价时返回 None
+    pub fn unrealized_pnl(&self, position: &SymbolPositionRecord) -> Option<i64> {
+        self.position_report(position)?.unrealized_pnl
+    }
+
+    /// 查询用户资料（余额、状态），用户不在本分片时返回 None
+    pub fn get_user(&self, uid: UserId) -> Option<&UserProfile> {
+        self.user_service.get_user(uid)
+    }
+
     // R1: Pre-process
     pub fn pre_process(&mut self, cmd: &mut OrderCommand) {
         match cmd.command {
-            OrderCommandType::PlaceOrder => {
-                if self.uid_for_this_shard(cmd.uid) {
+            OrderCommandType::PlaceOrder if self.uid_for_this_shard(cmd.uid) => match self.idempotency.original_order_id(cmd) {
+                // 重复提交不冻结资金、不进入撮合，R2 改为原命令的结果
+                Some(order_id) => {
+                    cmd.order_id = order_id;
+                    cmd.result_code = CommandResultCode::DuplicateCommand;
+                }
+                None => {
+                    if cmd.assign_order_id {
+                        cmd.order_id = self.allocate_order_id();
+                    }
                     cmd.result_code = self.place_order_risk_check(cmd);
+                    // 只记录通过风控的下单，被拒绝的命令（如余额不足）可用同一 client_order_id 重试
+                    if cmd.result_code == CommandResultCode::ValidForMatchingEngine {
+                        self.idempotency.record(cmd);
+                    }
                 }
+            },
+            OrderCommandType::AmendOrder if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.amend_order_risk_check(cmd);
             }
-            OrderCommandType::AddUser => {
-                if self.uid_for_this_shard(cmd.uid) {
-                    cmd.result_code = if self.user_service.add_user(cmd.uid) {
-                        CommandResultCode::Success
-                    } else {
-                        CommandResultCode::UserMgmtUserAlreadyExists
-                    };
+            OrderCommandType::PlaceBasket if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.basket_risk_check(cmd);
+            }
+            OrderCommandType::AddUser if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = if self.user_service.add_user(cmd.uid) {
+                    CommandResultCode::Success
+                } else {
+                    CommandResultCode::UserMgmtUserAlreadyExists
+                };
+            }
+            OrderCommandType::SuspendUser if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.user_service.suspend_user(cmd.uid);
+                // 需要撤单时交给撮合引擎处理，结算后置为 Success
+                if cmd.result_code == CommandResultCode::Success
+                    && cmd.service_flags & SUSPEND_USER_CANCEL_ORDERS != 0
+                {
+                    cmd.result_code = CommandResultCode::ValidForMatchingEngine;
                 }
             }
-            OrderCommandType::BalanceAdjustment => {
-                if self.uid_for_this_shard(cmd.uid) {
-                    cmd.result_code = self.user_service.balance_adjustment(
-                        cmd.uid,
-                        cmd.symbol,
-                        cmd.price,
-                        cmd.order_id as i64,
-                    );
+            OrderCommandType::ResumeUser if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.user_service.resume_user(cmd.uid);
+            }
+            OrderCommandType::SetFeeTier if self.uid_for_this_shard(cmd.uid) => {
+                let tier = FeeTier {
+                    taker_ratio: cmd.price,
+                    maker_ratio: cmd.size,
+                };
+                cmd.result_code = self.user_service.set_fee_tier(cmd.uid, tier);
+            }
+            OrderCommandType::SetMarkPrice => {
+                cmd.result_code = if self.symbols.contains_key(&cmd.symbol) {
+                    self.set_reference_price(cmd.symbol, StopTriggerSource::MarkPrice, cmd.price);
+                    CommandResultCode::Success
+                } else {
+                    CommandResultCode::InvalidSymbol
+                };
+            }
+            OrderCommandType::ReferencePriceUpdate => {
+                cmd.result_code = if self.symbols.contains_key(&cmd.symbol) {
+                    if let Some(source) = StopTriggerSource::from_code(cmd.service_flags) {
+                        self.set_reference_price(cmd.symbol, source, cmd.price);
+                    }
+                    CommandResultCode::ValidForMatchingEngine
+                } else {
+                    CommandResultCode::InvalidSymbol
+                };
+            }
+            OrderCommandType::SetSessionState => {
+                cmd.result_code = if self.symbols.contains_key(&cmd.symbol) {
+                    CommandResultCode::ValidForMatchingEngine
+                } else {
+                    CommandResultCode::InvalidSymbol
+                };
+            }
+            // 各分片依次检查本分片用户的持仓，任一分片拒绝即拒绝
+            OrderCommandType::DelistSymbol | OrderCommandType::ClearOrderBook
+                if cmd.result_code != CommandResultCode::SymbolMgmtOpenPositions =>
+            {
+                cmd.result_code = match self.symbols.get(&cmd.symbol) {
+                    None => CommandResultCode::InvalidSymbol,
+                    Some(_) if cmd.command == OrderCommandType::DelistSymbol && self.has_open_positions(cmd.symbol) => {
+                        CommandResultCode::SymbolMgmtOpenPositions
+                    }
+                    Some(_) => CommandResultCode::ValidForMatchingEngine,
+                };
+            }
+            OrderCommandType::ExerciseOption if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.exercise_risk_check(cmd);
+            }
+            OrderCommandType::ExpireSeries => {
+                cmd.result_code = match self.symbols.get(&cmd.symbol) {
+                    None => CommandResultCode::InvalidSymbol,
+                    Some(spec) if spec.option_payoff(cmd.price).is_none() => CommandResultCode::UnsupportedSymbolType,
+                    Some(_) => {
+                        self.expiring.insert(cmd.symbol);
+                        CommandResultCode::ValidForMatchingEngine
+                    }
+                };
+            }
+            OrderCommandType::ApplyFunding => {
+                cmd.result_code = self.apply_funding(cmd.symbol, cmd.price, cmd.timestamp);
+            }
+            OrderCommandType::BinaryDataQuery => {
+                cmd.result_code = self.process_binary_query(cmd);
+            }
+            OrderCommandType::BinaryDataCommand => {
+                cmd.result_code = self.process_binary_command(cmd);
+            }
+            OrderCommandType::BalanceAdjustment if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.user_service.balance_adjustment(
+                    cmd.uid,
+                    cmd.symbol,
+                    cmd.price,
+                    cmd.order_id,
+                    cmd.timestamp,
+                );
+                if cmd.result_code == CommandResultCode::Success {
+                    *self.deposits.entry(cmd.symbol).or_insert(0) += cmd.price;
                 }
             }
+            OrderCommandType::HoldFunds if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.user_service.hold_funds(cmd.uid, cmd.symbol, cmd.price, cmd.order_id);
+            }
+            OrderCommandType::ConfirmWithdrawal | OrderCommandType::ReleaseHold if self.uid_for_this_shard(cmd.uid) => {
+                let state = match cmd.command {
+                    OrderCommandType::ConfirmWithdrawal => WithdrawalState::Confirmed,
+                    _ => WithdrawalState::Released,
+                };
+                cmd.result_code = match self.user_service.finish_withdrawal(cmd.uid, cmd.order_id, state) {
+                    Ok(hold) => {
+                        // 确认提现的资金离开系统，从净入金中扣除
+                        if let Some(hold) = hold.filter(|_| state == WithdrawalState::Confirmed) {
+                            *self.deposits.entry(hold.currency).or_insert(0) -= hold.amount;
+                        }
+                        CommandResultCode::Success
+                    }
+                    Err(code) => code,
+                };
+            }
             _ => {}
         }
     }
 
-    fn place_order_risk_check(&mut self, cmd: &OrderCommand) -> CommandResultCode {
-        let Some(profile) = self.user_service.get_user_mut(cmd.uid) else {
+    /// 记录外部参考价格，标记价同时用于资金费结算
+    fn set_reference_price(&mut self, symbol: SymbolId, source: StopTriggerSource, price: Price) {
+        let prices = self.reference_prices.entry(symbol).or_insert(ReferencePrices { symbol, mark_price: None, index_price: None });
+        match source {
+            StopTriggerSource::MarkPrice => {
+                prices.mark_price = Some(price);
+                self.funding.set_mark_price(symbol, price);
+            }
+            StopTriggerSource::IndexPrice => prices.index_price = Some(price),
+            StopTriggerSource::LastPrice => {}
+        }
+    }
+
+    /// 持仓报表：开仓成本与按标记价计算的未实现盈亏（交易对不存在时返回 None）
+    fn position_report(&self, position: &SymbolPositionRecord) -> Option<PositionReport> {
+        let spec = self.symbols.get(&position.symbol)?;
+        let mark_price = self.reference_prices.get(&position.symbol).and_then(|prices| prices.mark_price);
+        let open_cost = (position.open_price_long - position.open_price_short) * spec.quote_scale_k;
+        let unrealized_pnl = mark_price.map(|price| {
+            saturating_amount(SymbolScale::new(spec).quote_value(position.net_position(), price) - open_cost as i128)
+        });
+        Some(PositionReport {
+            symbol: position.symbol,
+            currency: spec.quote_currency,
+            net_position: position.net_position(),
+            open_cost,
+            mark_price,
+            unrealized_pnl,
+        })
+    }
+
+    /// 本分片是否有用户持有该交易对的未平仓持仓
+    fn has_open_positions(&self, symbol: SymbolId) -> bool {
+        self.user_service.profiles().any(|profile| {
+            profile
+                .positions
+                .get(&symbol)
+                .is_some_and(|position| position.open_volume_long != 0 || position.open_volume_short != 0)
+        })
+    }
+
+    /// 行权：校验期权与持有人，把管理端推送的指数价写入 price 作为结算价，平仓与指派在 R2 按全体持仓进行
+    fn exercise_risk_check(&self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(spec) = self.symbols.get(&cmd.symbol).filter(|_| !self.expiring.contains(&cmd.symbol)) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+        if !spec.is_option() {
+            return CommandResultCode::UnsupportedSymbolType;
+        }
+        let Some(settlement_price) = self.reference_prices.get(&cmd.symbol).and_then(|prices| prices.index_price) else {
+            return CommandResultCode::RiskIndexPriceNotSet;
+        };
+        if spec.option_payoff(settlement_price) == Some(0) {
+            return CommandResultCode::RiskOptionOutOfTheMoney;
+        }
+        let Some(profile) = self.user_service.get_user(cmd.uid) else {
             return CommandResultCode::AuthInvalidUser;
         };
+        if profile.status == UserStatus::Suspended {
+            return CommandResultCode::AuthUserSuspended;
+        }
+        cmd.price = settlement_price;
+        CommandResultCode::ValidForMatchingEngine
+    }
 
-        let Some(spec) = self.symbols.get(&cmd.symbol) else {
+    /// 行权结算：持有人按内在价值平掉 size 手多头，同等数量按比例指派给空头平仓，本分片用户入账盈亏
+    ///
+    /// 各分片的全体持仓一致，指派结果相同；先结算的分片已将结果改为 Success
+    fn settle_exercise(&mut self, cmd: &mut OrderCommand) {
+        if !matches!(cmd.result_code, CommandResultCode::ValidForMatchingEngine | CommandResultCode::Success) {
+            return;
+        }
+        let Some(spec) = self.symbols.get(&cmd.symbol).cloned() else {
+            cmd.result_code = CommandResultCode::InvalidSymbol;
+            return;
+        };
+        let positions = self.option_positions.entry(cmd.symbol).or_default();
+        if positions.get(&cmd.uid).copied().unwrap_or(0) < cmd.size {
+            cmd.result_code = CommandResultCode::RiskInsufficientPosition;
+            return;
+        }
+        let assignments = Self::assign_exercise(positions, cmd.size);
+
+        let payoff = spec.option_payoff(cmd.price).unwrap_or(0);
+        self.close_option_position(cmd.uid, &spec, OrderAction::Ask, cmd.size, payoff);
+        for (uid, size) in assignments {
+            self.close_option_position(uid, &spec, OrderAction::Bid, size, payoff);
+        }
+        cmd.result_code = CommandResultCode::Success;
+    }
+
+    /// 按空头持仓比例指派行权数量，取整后的余数按用户号顺序逐手分配
+    fn assign_exercise(positions: &BTreeMap<UserId, Size>, size: Size) -> Vec<(UserId, Size)> {
+        let total_short: i128 = positions.values().filter(|net| **net < 0).map(|net| -*net as i128).sum();
+        let mut assignments: Vec<(UserId, Size, Size)> = positions
+            .iter()
+            .filter(|(_, net)| **net < 0)
+            .map(|(&uid, &net)| (uid, -net, (-net as i128 * size as i128 / total_short) as Size))
+            .collect();
+        let mut rest = size - assignments.iter().map(|(_, _, assigned)| assigned).sum::<Size>();
+        for (_, short, assigned) in assignments.iter_mut() {
+            if rest == 0 {
+                break;
+            }
+            if *assigned < *short {
+                *assigned += 1;
+                rest -= 1;
+            }
+        }
+        assignments.into_iter().filter(|(_, _, assigned)| *assigned > 0).map(|(uid, _, assigned)| (uid, assigned)).collect()
+    }
+
+    /// 按每手内在价值平掉期权持仓（不收手续费）：更新全体净持仓，本分片用户返还平仓部分的保证金并入账盈亏
+    fn close_option_position(&mut self, uid: UserId, spec: &CoreSymbolSpecification, action: OrderAction, size: Size, payoff: Price) {
+        self.track_option_position(spec.symbol_id, uid, action, size);
+        if !self.uid_for_this_shard(uid) {
+            return;
+        }
+        if let Some(profile) = self.user_service.get_user_mut(uid) {
+            Self::close_at_payoff(profile, spec, action, size, payoff);
+        }
+    }
+
+    fn close_at_payoff(profile: &mut UserProfile, spec: &CoreSymbolSpecification, action: OrderAction, size: Size, payoff: Price) {
+        let Some(position) = profile.positions.get_mut(&spec.symbol_id) else {
+            return;
+        };
+        let (closed, pnl) = position.update_position(action, size, payoff);
+        if position.is_empty() {
+            profile.positions.remove(&spec.symbol_id);
+        }
+        let released = closed as i128 * spec.initial_margin(action.opposite()) as i128;
+        *profile.accounts.entry(spec.quote_currency).or_insert(0) += saturating_amount(released + spec.scale().quote_value(1, pnl));
+    }
+
+    /// 更新期权全体用户的净持仓（各分片都按全部成交维护，不限本分片用户）
+    fn track_option_position(&mut self, symbol: SymbolId, uid: UserId, action: OrderAction, size: Size) {
+        let positions = self.option_positions.entry(symbol).or_default();
+        let net = positions.entry(uid).or_insert(0);
+        *net += match action {
+            OrderAction::Bid => size,
+            OrderAction::Ask => -size,
+        };
+        if *net == 0 {
+            positions.remove(&uid);
+        }
+    }
+
+    /// 期权到期（挂单已撤销并返还冻结）：本分片用户的多空持仓全部按结算价的内在价值平仓
+    ///
+    /// 权利金按成交价计入开仓成本，多头盈亏为 内在价值 - 权利金，与空头盈亏相抵
+    fn settle_expiry(&mut self, symbol: SymbolId, settlement_price: Price) {
+        let Some(spec) = self.symbols.get(&symbol) else {
+            return;
+        };
+        let payoff = spec.option_payoff(settlement_price).unwrap_or(0);
+        for profile in self.user_service.profiles_mut() {
+            let Some(net) = profile.positions.get(&symbol).map(|position| position.net_position()) else {
+                continue;
+            };
+            let action = if net > 0 { OrderAction::Ask } else { OrderAction::Bid };
+            Self::close_at_payoff(profile, spec, action, net.abs(), payoff);
+        }
+        self.option_positions.remove(&symbol);
+    }
+
+    /// 永续合约资金费结算：按标记价格对本分片所有持仓扣收/发放资金费
+    fn apply_funding(&mut self, symbol: SymbolId, rate: i64, now: i64) -> CommandResultCode {
+        let Some(spec) = self.symbols.get(&symbol) else {
             return CommandResultCode::InvalidSymbol;
         };
+        let mark_price = match self.funding.start_funding(spec, now) {
+            Ok(price) => price,
+            Err(code) => return code,
+        };
+
+        for profile in self.user_service.profiles_mut() {
+            let Some(position) = profile.positions.get(&symbol) else {
+                continue;
+            };
+            let payment = FundingEngine::funding_payment(position.net_position(), mark_price, rate, spec.quote_scale_k);
+            if payment != 0 {
+                *profile.accounts.entry(spec.quote_currency).or_insert(0) -= payment;
+                *self.funding_collected.entry(spec.quote_currency).or_insert(0) += payment;
+            }
+        }
+        CommandResultCode::Success
+    }
+
+    /// 对账：累加本分片的净入金，以及余额、挂单冻结、提现冻结、持仓保证金、手续费、资金费轧差
+    ///
+    /// 已实现盈亏在成交时直接入账，全体用户的盈亏之和等于多头开仓成本减空头开仓成本，
+    /// 因此持仓部分按 保证金 - 开仓成本轧差 计入
+    pub fn reconcile(&self, open_orders: &[OpenOrder], totals: &mut BalanceTotals) {
+        for (&currency, &amount) in &self.deposits {
+            totals.add_deposit(currency, amount);
+        }
+        for (&currency, &amount) in self.fees.by_currency.iter().chain(&self.funding_collected) {
+            totals.add_accounted(currency, amount);
+        }
+
+        let resting: AHashSet<(UserId, OrderId)> = open_orders.iter().map(|order| (order.uid, order.order_id)).collect();
+        for profile in self.user_service.profiles() {
+            for (&currency, &balance) in &profile.accounts {
+                totals.add_accounted(currency, balance);
+            }
+            for hold in profile.withdrawals.values().filter(|hold| hold.state == WithdrawalState::Held) {
+                totals.add_accounted(hold.currency, hold.amount);
+            }
+            // 挂单冻结以风控记录为准；订单簿中已不存在的订单说明其移除未经 R2 结算
+            for (&order_id, order) in &profile.open_orders {
+                totals.add_accounted(order.currency, order.hold);
+                if !resting.contains(&(profile.uid, order_id)) {
+                    totals.add_stuck_hold(StuckHold {
+                        uid: profile.uid,
+                        order_id,
+                        symbol: order.symbol,
+                        currency: order.currency,
+                        hold: order.hold,
+                    });
+                }
+            }
+            for position in profile.positions.values() {
+                let Some(spec) = self.symbols.get(&position.symbol) else {
+                    continue;
+                };
+                let margin = position.open_volume_long * spec.margin_buy + position.open_volume_short * spec.margin_sell;
+                let open_cost = (position.open_price_long - position.open_price_short) * spec.quote_scale_k;
+                totals.add_accounted(spec.quote_currency, margin - open_cost);
+            }
+        }
 
-        let currency = match cmd.action {
-            OrderAction::Bid => spec.quote_currency,
-            OrderAction::Ask => spec.base_currency,
+        // 没有风控记录的挂单（如只恢复了订单簿）按下单参数计算冻结
+        let untracked = |order: &&OpenOrder| {
+            self.uid_for_this_shard(order.uid)
+                && !self.user_service.get_user(order.uid).is_some_and(|profile| profile.open_orders.contains_key(&order.order_id))
         };
+        for order in open_orders.iter().filter(untracked) {
+            if let Some(spec) = self.symbols.get(&order.symbol) {
+                // 挂单的冻结金额在下单时已校验不会溢出
+                if let Ok((currency, hold)) = spec.order_hold(order.action, order.remaining, order.reserve_price) {
+                    totals.add_accounted(currency, hold);
+                }
+            }
+        }
+    }
 
-        let hold_amount = match cmd.action {
-            OrderAction::Bid => {
-                let price = if matches!(cmd.order_type, OrderType::FokBudget | OrderType::IocBudget) {
-                    cmd.price
+    /// 二进制查询：各分片依次把结果合并进 cmd.binary_data
+    fn process_binary_query(&self, cmd: &mut OrderCommand) -> CommandResultCode {
+        match cmd.service_flags {
+            BINARY_QUERY_FEE_REPORT => {
+                let mut report = if cmd.binary_data.is_empty() {
+                    FeeReport::default()
                 } else {
-                    cmd.reserve_price
+                    match FeeReport::decode(&cmd.binary_data) {
+                        Ok(report) => report,
+                        Err(_) => return CommandResultCode::BinaryCommandFailed,
+                    }
                 };
-                cmd.size * price * spec.quote_scale_k + cmd.size * spec.taker_fee
+                report.merge(&self.fees);
+                cmd.binary_data = report.encode();
+                CommandResultCode::Success
             }
-            OrderAction::Ask => cmd.size * spec.base_scale_k,
+            BINARY_QUERY_SYMBOLS => {
+                // 各分片交易对相同，由第一个分片填充
+                if cmd.binary_data.is_empty() {
+                    let mut specs: Vec<CoreSymbolSpecification> = self.symbols.values().cloned().collect();
+                    specs.sort_by_key(|spec| spec.symbol_id);
+                    cmd.binary_data = bincode::serialize(&specs).expect("交易对序列化失败");
+                }
+                CommandResultCode::Success
+            }
+            BINARY_QUERY_REFERENCE_PRICES => {
+                // 各分片参考价格相同，由第一个分片填充
+                if cmd.binary_data.is_empty() {
+                    let mut prices: Vec<ReferencePrices> = self.reference_prices.values().copied().collect();
+                    prices.sort_by_key(|prices| prices.symbol);
+                    cmd.binary_data = bincode::serialize(&prices).expect("参考价格序列化失败");
+                }
+                CommandResultCode::Success
+            }
+            BINARY_QUERY_POSITIONS => {
+                if !self.uid_for_this_shard(cmd.uid) {
+                    return cmd.result_code;
+                }
+                let Some(profile) = self.user_service.get_user(cmd.uid) else {
+                    return CommandResultCode::AuthInvalidUser;
+                };
+                let mut positions: Vec<PositionReport> = profile
+                    .positions
+                    .values()
+                    .filter(|position| position.open_volume_long != 0 || position.open_volume_short != 0)
+                    .filter_map(|position| self.position_report(position))
+                    .collect();
+                positions.sort_by_key(|position| position.symbol);
+                cmd.binary_data = bincode::serialize(&positions).expect("持仓序列化失败");
+                CommandResultCode::Success
+            }
+            // 在 R2 按命令顺序查询，结果包含此前全部成交且与重置的先后一致
+            BINARY_QUERY_MAKER_VOLUME => cmd.result_code,
+            BINARY_QUERY_BALANCE_LEDGER => {
+                // 由用户所在分片填充，其他分片保持结果不变
+                if !self.uid_for_this_shard(cmd.uid) {
+                    return cmd.result_code;
+                }
+                match self.user_service.get_user(cmd.uid) {
+                    Some(profile) => {
+                        cmd.binary_data = bincode::serialize(&profile.ledger).expect("流水序列化失败");
+                        CommandResultCode::Success
+                    }
+                    None => CommandResultCode::AuthInvalidUser,
+                }
+            }
+            _ => CommandResultCode::BinaryCommandFailed,
+        }
+    }
+
+    /// 二进制命令：各分片执行相同的变更，撮合引擎在结果为 Success 时跟进
+    fn process_binary_command(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        match cmd.service_flags {
+            BINARY_COMMAND_ADD_SYMBOLS => {
+                let Ok(specs) = cmd.decode_symbols() else {
+                    return CommandResultCode::BinaryCommandFailed;
+                };
+                let mut ids: Vec<SymbolId> = specs.iter().map(|spec| spec.symbol_id).collect();
+                ids.sort_unstable();
+                ids.dedup();
+                if ids.len() != specs.len() || ids.iter().any(|id| self.symbols.contains_key(id)) {
+                    return CommandResultCode::SymbolMgmtSymbolAlreadyExists;
+                }
+                if !specs.iter().all(CoreSymbolSpecification::is_valid) {
+                    return CommandResultCode::SymbolMgmtInvalidSpecification;
+                }
+                for spec in specs {
+                    self.add_symbol(spec);
+                }
+                CommandResultCode::Success
+            }
+            // 在 R2 执行，此前命令的成交计入旧周期
+            BINARY_COMMAND_RESET_MAKER_VOLUME => CommandResultCode::Success,
+            _ => CommandResultCode::BinaryCommandFailed,
+        }
+    }
+
+    /// 分配订单号：本分片第 n 个为 ASSIGNED_ORDER_ID_BASE + n * 分片数 + 分片号，各分片互不重复；
+    /// 计数随快照保存，恢复与日志重放后按相同顺序继续分配
+    fn allocate_order_id(&mut self) -> OrderId {
+        self.assigned_order_ids += 1;
+        ASSIGNED_ORDER_ID_BASE + self.assigned_order_ids * (self.shard_mask + 1) + self.shard_id as OrderId
+    }
+
+    fn place_order_risk_check(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        let Some(profile) = self.user_service.get_user_mut(cmd.uid) else {
+            return CommandResultCode::AuthInvalidUser;
         };
 
-        let balance = profile.accounts.entry(currency).or_insert(0);
-        if *balance >= hold_amount {
-            *balance -= hold_amount;
-            CommandResultCode::ValidForMatchingEngine
+        if profile.status == UserStatus::Suspended {
+            return CommandResultCode::AuthUserSuspended;
+        }
+
+        // 到期中的期权系列不再接受新单
+        let Some(spec) = self.symbols.get(&cmd.symbol).filter(|_| !self.expiring.contains(&cmd.symbol)) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+
+        let granularity = Self::check_granularity(spec, cmd);
+        if granularity != CommandResultCode::Success {
+            return granularity;
+        }
+
+        // 同一用户的未完成订单号不可复用（其他用户的重复订单号由订单簿拒绝）
+        if profile.open_orders.contains_key(&cmd.order_id) {
+            return CommandResultCode::MatchingDuplicateOrderId;
+        }
+
+        let limits = Self::check_limits(&self.limits, profile, spec, cmd);
+        if limits != CommandResultCode::Success {
+            return limits;
+        }
+
+        if spec.is_margin_trading() && spec.initial_margin(cmd.action) <= 0 {
+            return CommandResultCode::RiskMarginTradingDisabled;
+        }
+
+        // 现货市价买单以 reserve_price 为成交价上限并按其冻结，未给出时无法限定花费
+        let market = matches!(cmd.order_type, OrderType::Market | OrderType::StopMarket | OrderType::MarketToLimit);
+        if market && cmd.action == OrderAction::Bid && !spec.is_margin_trading() && cmd.reserve_price <= 0 {
+            return CommandResultCode::RiskInvalidReserveBidPrice;
+        }
+
+        // 现货买单按 reserve_price 冻结，与撮合事件的 bidder_hold_price 一致；预算买单按总预算冻结并记录在挂单上
+        let budget = Self::holds_budget(spec, cmd);
+        let hold = if budget {
+            spec.budget_order_hold(cmd.action, cmd.size, cmd.price)
         } else {
-            CommandResultCode::RiskNsf
+            Self::order_hold(&self.spread_groups, &self.symbols, spec, cmd.action, cmd.size, cmd.reserve_price)
+        };
+        let (currency, hold_amount) = match hold {
+            Ok(hold) => hold,
+            Err(code) => return code,
+        };
+        let balance = profile.accounts.entry(currency).or_insert(0);
+        if *balance < hold_amount {
+            return CommandResultCode::RiskNsf;
+        }
+        *balance -= hold_amount;
+
+        if spec.is_margin_trading() {
+            profile
+                .positions
+                .entry(spec.symbol_id)
+                .or_insert_with(|| SymbolPositionRecord::new(cmd.uid, spec.symbol_id, spec.quote_currency))
+                .add_pending(cmd.action, cmd.size);
+        }
+
+        // 先按全部数量计入挂单，撮合结果在 R2 中扣减，保证流水线中未结算的订单也受限额约束
+        profile.open_orders.insert(
+            cmd.order_id,
+            OpenOrderRecord {
+                symbol: cmd.symbol,
+                remaining: cmd.size,
+                action: cmd.action,
+                reserve_price: cmd.reserve_price,
+                currency,
+                hold: hold_amount,
+                budget,
+            },
+        );
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// 组合下单：逐腿按新单校验并冻结，任一腿未通过时撤销已通过腿的冻结与挂单记录，返回该腿的原因
+    fn basket_risk_check(&mut self, cmd: &OrderCommand) -> CommandResultCode {
+        let Ok(legs) = cmd.decode_basket() else {
+            return CommandResultCode::ValidationInvalidBasket;
+        };
+        for (index, leg) in legs.iter().enumerate() {
+            let code = self.place_order_risk_check(leg);
+            if code != CommandResultCode::ValidForMatchingEngine {
+                for placed in &legs[..index] {
+                    self.revert_order_hold(cmd.uid, placed.order_id);
+                }
+                return code;
+            }
+        }
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// 撤销 R1 已通过订单的冻结、挂单记录与待成交持仓
+    fn revert_order_hold(&mut self, uid: UserId, order_id: OrderId) {
+        let Some(profile) = self.user_service.get_user_mut(uid) else {
+            return;
+        };
+        let Some(order) = profile.open_orders.remove(&order_id) else {
+            return;
+        };
+        *profile.accounts.entry(order.currency).or_insert(0) += order.hold;
+        if let Some(position) = profile.positions.get_mut(&order.symbol) {
+            position.add_pending(order.action, -order.remaining);
+            if position.is_empty() {
+                profile.positions.remove(&order.symbol);
+            }
+        }
+    }
+
+    /// 现货预算买单按总预算冻结（成交价不受 reserve_price 限制），不能按手数返还
+    fn holds_budget(spec: &CoreSymbolSpecification, cmd: &OrderCommand) -> bool {
+        cmd.command == OrderCommandType::PlaceOrder
+            && matches!(cmd.order_type, OrderType::FokBudget | OrderType::IocBudget)
+            && cmd.action == OrderAction::Bid
+            && !spec.is_margin_trading()
+    }
+
+    /// 改单：按风控记录的方向与冻结价为增加的数量追加冻结，冻结的手数写入 amend_hold
+    ///
+    /// 流水线中尚未结算的成交使记录的剩余数量可能偏大，撮合最多增加 amend_hold 手，未用完的部分随改单事件返还
+    fn amend_order_risk_check(&mut self, cmd: &mut OrderCommand) -> CommandResultCode {
+        cmd.amend_hold = 0;
+        let Some(profile) = self.user_service.get_user_mut(cmd.uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        // 不是该用户的挂单时不追加冻结，交给订单簿判定：他人的订单为 MatchingUnauthorizedAction，与撤单、改价、减量一致
+        let Some(order) = profile.open_orders.get(&cmd.order_id).copied().filter(|order| order.symbol == cmd.symbol) else {
+            return CommandResultCode::ValidForMatchingEngine;
+        };
+        let Some(spec) = self.symbols.get(&cmd.symbol) else {
+            return CommandResultCode::InvalidSymbol;
+        };
+
+        let granularity = Self::check_granularity(spec, cmd);
+        if granularity != CommandResultCode::Success {
+            return granularity;
+        }
+
+        // 失败时撮合引擎按原订单的方向与冻结价返还追加冻结
+        cmd.action = order.action;
+        cmd.reserve_price = order.reserve_price;
+        let increase = cmd.size - order.remaining;
+        if increase <= 0 {
+            return CommandResultCode::ValidForMatchingEngine;
+        }
+
+        if profile.status == UserStatus::Suspended {
+            return CommandResultCode::AuthUserSuspended;
+        }
+        if self.limits.max_order_size > 0 && cmd.size > self.limits.max_order_size {
+            return CommandResultCode::RiskMaxOrderSizeExceeded;
+        }
+        let (currency, hold_amount) = match Self::order_hold(&self.spread_groups, &self.symbols, spec, order.action, increase, order.reserve_price) {
+            Ok(hold) => hold,
+            Err(code) => return code,
+        };
+        let balance = profile.accounts.entry(currency).or_insert(0);
+        if *balance < hold_amount {
+            return CommandResultCode::RiskNsf;
+        }
+        *balance -= hold_amount;
+
+        if spec.is_margin_trading() {
+            if let Some(position) = profile.positions.get_mut(&spec.symbol_id) {
+                position.add_pending(order.action, increase);
+            }
+        }
+        if let Some(record) = profile.open_orders.get_mut(&cmd.order_id) {
+            record.remaining += increase;
+            record.hold += hold_amount;
+        }
+        cmd.amend_hold = increase;
+        CommandResultCode::ValidForMatchingEngine
+    }
+
+    /// 挂单冻结：价差组交易对的订单可能隐含成交为另外两个交易对的持仓，每手按直接成交与各组隐含腿的保证金加 taker 手续费中的最大值冻结
+    fn order_hold(
+        groups: &[SpreadGroup],
+        symbols: &AHashMap<SymbolId, CoreSymbolSpecification>,
+        spec: &CoreSymbolSpecification,
+        action: OrderAction,
+        size: Size,
+        reserve_price: Price,
+    ) -> Result<(Currency, i64), CommandResultCode> {
+        let (currency, hold) = spec.order_hold(action, size, reserve_price)?;
+        let implied = groups
+            .iter()
+            .filter(|group| group.is_tradable(|symbol| symbols.get(&symbol)))
+            .filter_map(|group| group.implied_legs(spec.symbol_id, action))
+            .map(|legs| {
+                legs.iter()
+                    .filter_map(|leg| symbols.get(&leg.symbol).map(|leg_spec| leg_spec.initial_margin(leg.action) as i128 + leg_spec.taker_fee as i128))
+                    .sum::<i128>()
+            })
+            .max()
+            .unwrap_or(0);
+        let implied = i64::try_from(implied * size as i128).map_err(|_| CommandResultCode::RiskAmountOverflow)?;
+        Ok((currency, hold.max(implied)))
+    }
+
+    /// 校验挂单数与单笔数量/金额限额
+    fn check_limits(limits: &RiskLimits, profile: &UserProfile, spec: &CoreSymbolSpecification, cmd: &OrderCommand) -> CommandResultCode {
+        if limits.max_order_size > 0 && cmd.size > limits.max_order_size {
+            return CommandResultCode::RiskMaxOrderSizeExceeded;
+        }
+
+        if limits.max_order_notional > 0 {
+            let scale = spec.scale();
+            let notional = match cmd.order_type {
+                OrderType::FokBudget | OrderType::IocBudget => scale.quote_value(1, cmd.price), // price 为总预算
+                OrderType::Market | OrderType::StopMarket | OrderType::MarketToLimit => scale.quote_value(cmd.size, cmd.reserve_price),
+                _ => scale.quote_value(cmd.size, cmd.price),
+            };
+            if notional > limits.max_order_notional as i128 {
+                return CommandResultCode::RiskMaxNotionalExceeded;
+            }
+        }
+
+        if limits.max_open_orders_per_user > 0 && profile.open_orders.len() >= limits.max_open_orders_per_user {
+            return CommandResultCode::RiskUserOrderLimitExceeded;
+        }
+
+        if limits.max_open_orders_per_symbol > 0 {
+            let count = profile.open_orders.values().filter(|order| order.symbol == cmd.symbol).count();
+            if count >= limits.max_open_orders_per_symbol {
+                return CommandResultCode::RiskSymbolOrderLimitExceeded;
+            }
         }
+        CommandResultCode::Success
+    }
+
+    /// 校验价格步长与数量步长、上下限（预算单的 price 为总预算、市价单无限价，不校验价格）
+    fn check_granularity(spec: &CoreSymbolSpecification, cmd: &OrderCommand) -> CommandResultCode {
+        let limit_priced = !matches!(
+            cmd.order_type,
+            OrderType::Market | OrderType::StopMarket | OrderType::MarketToLimit | OrderType::FokBudget | OrderType::IocBudget
+        );
+        let off_tick = |price: Price| price % spec.tick_size != 0;
+        if (limit_priced && off_tick(cmd.price)) || cmd.stop_price.is_some_and(off_tick) {
+            return CommandResultCode::RiskInvalidPriceStep;
+        }
+        if cmd.size % spec.lot_size != 0 || cmd.visible_size.is_some_and(|size| size % spec.lot_size != 0) {
+            return CommandResultCode::RiskInvalidLotSize;
+        }
+        if cmd.size < spec.min_size {
+            return CommandResultCode::RiskOrderSizeTooSmall;
+        }
+        if spec.max_size > 0 && cmd.size > spec.max_size {
+            return CommandResultCode::RiskOrderSizeTooLarge;
+        }
+        CommandResultCode::Success
     }
 
     // R2: Post-process 结算
     pub fn post_process(&mut self, cmd: &mut OrderCommand) {
+        let mut released = std::mem::take(&mut self.released);
+        self.track_open_orders(cmd, &mut released);
+        match cmd.command {
+            OrderCommandType::SuspendUser => self.settle_mass_cancel(cmd, &released),
+            OrderCommandType::PlaceBasket => self.settle_basket(cmd, &released),
+            _ => self.settle_matcher_events(cmd, &released),
+        }
+        released.clear();
+        self.released = released;
+        if self.uid_for_this_shard(cmd.uid) {
+            self.idempotency.on_settled(cmd);
+        }
+
+        match (cmd.command, cmd.service_flags) {
+            (OrderCommandType::BinaryDataQuery, BINARY_QUERY_MAKER_VOLUME) if self.uid_for_this_shard(cmd.uid) => {
+                cmd.result_code = self.maker_volume_report(cmd);
+            }
+            (OrderCommandType::BinaryDataCommand, BINARY_COMMAND_RESET_MAKER_VOLUME) if cmd.result_code == CommandResultCode::Success => {
+                self.reset_maker_volume();
+            }
+            _ => {}
+        }
+
+        if cmd.command == OrderCommandType::ExerciseOption {
+            self.settle_exercise(cmd);
+        }
+        if cmd.command == OrderCommandType::ExpireSeries {
+            self.expiring.remove(&cmd.symbol);
+            if cmd.result_code == CommandResultCode::Success {
+                self.settle_expiry(cmd.symbol, cmd.price);
+            }
+        }
+        // 下架交易对、期权到期：挂单资金已返还，移除交易对规格
+        if matches!(cmd.command, OrderCommandType::DelistSymbol | OrderCommandType::ExpireSeries) && cmd.result_code == CommandResultCode::Success {
+            self.symbols.remove(&cmd.symbol);
+            self.funding.remove_symbol(cmd.symbol);
+            self.reference_prices.remove(&cmd.symbol);
+            self.option_positions.remove(&cmd.symbol);
+        }
+    }
+
+    /// 按撮合事件结算成交与返还冻结资金，released 为各事件从挂单记录释放的冻结
+    fn settle_matcher_events(&mut self, cmd: &mut OrderCommand, released: &[ReleasedHold]) {
         if cmd.matcher_events.is_empty() {
             return;
         }
 
-        let Some(spec) = self.symbols.get(&cmd.symbol).cloned() else {
+        let spec = self.symbols.get(&cmd.symbol).cloned();
+        for (index, (event, released)) in cmd.matcher_events.iter().zip(released).enumerate() {
+            let symbol = cmd.event_symbol(index);
+            if symbol == cmd.symbol {
+                self.settle_event(cmd, event, spec.as_ref(), released);
+                continue;
+            }
+            // 隐含成交的腿及其触发的止损单属于价差组内的其他交易对
+            let leg_spec = self.symbols.get(&symbol).cloned();
+            match leg_spec {
+                Some(leg_spec) if cmd.is_implied_leg(index) => self.settle_implied_leg(cmd, event, &leg_spec, released),
+                _ => self.settle_event(cmd, event, leg_spec.as_ref(), released),
+            }
+        }
+        if spec.is_none() {
+            return;
+        }
+        // 交易时段拒单、订单簿拒单与改单失败同样需要返还冻结资金，但保留拒绝原因
+        if cmd.command != OrderCommandType::AmendOrder && !matches!(
+            cmd.result_code,
+            CommandResultCode::MatchingSessionRejected
+                | CommandResultCode::MatchingDuplicateOrderId
+                | CommandResultCode::MatchingUnsupportedCommand
+        ) {
+            cmd.result_code = CommandResultCode::Success;
+        }
+    }
+
+    /// 结算单个撮合事件（止损单激活与组合下单的事件以事件记录的订单为 taker）
+    ///
+    /// 交易对已下架（撮合晚于下架命令的订单只有拒绝事件）时按记录的币种返还释放的冻结
+    fn settle_event(&mut self, cmd: &OrderCommand, event: &MatcherTradeEvent, spec: Option<&CoreSymbolSpecification>, released: &ReleasedHold) {
+        let (taker_uid, _, taker_action) = event.taker(cmd);
+        let Some(spec) = spec else {
+            for (uid, released) in [(taker_uid, released.taker), (event.matched_order_uid, released.maker)] {
+                if let (Some((currency, amount)), Some(profile)) = (released, self.user_service.get_user_mut(uid)) {
+                    *profile.accounts.entry(currency).or_insert(0) += amount;
+                }
+            }
             return;
         };
+        let taker_sell = taker_action == OrderAction::Ask;
+        match event.event_type {
+            MatcherEventType::Trade => {
+                self.handle_trade_event(taker_uid, event, spec, taker_sell, released);
+            }
+            MatcherEventType::Reject | MatcherEventType::Reduce | MatcherEventType::Amend => {
+                self.handle_reject_event(taker_uid, event, spec, taker_sell, released.taker_amount().or(released.maker_amount()));
+            }
+        }
+    }
 
-        let taker_sell = cmd.action == OrderAction::Ask;
+    /// 根据撮合事件扣减挂单剩余数量并释放冻结：成交与作用于挂单的事件扣减 maker，其余扣减命令自身的订单；
+    /// 减量事件携带剩余数量，剩余为 0 时订单已移除
+    fn track_open_orders(&mut self, cmd: &OrderCommand, released: &mut Vec<ReleasedHold>) {
+        for (index, event) in cmd.matcher_events.iter().enumerate() {
+            let mut hold = ReleasedHold::default();
+            let is_trade = event.event_type == MatcherEventType::Trade;
+            if is_trade || event.maker_action.is_some() {
+                hold.maker = self.release_order_hold(event.matched_order_uid, event.matched_order_id, event, event.maker_completed, false);
+            }
+            // 隐含成交腿的 taker 没有该交易对的挂单，命令自身订单的数量由随后的减量事件扣减
+            if event.maker_action.is_none() && !cmd.is_implied_leg(index) {
+                let (uid, order_id, _) = event.taker(cmd);
+                let completed = cmd.command == OrderCommandType::CancelOrder
+                    || (matches!(event.event_type, MatcherEventType::Reduce | MatcherEventType::Amend) && event.remaining_size == 0);
+                hold.taker = self.release_order_hold(uid, order_id, event, completed, is_trade);
+            }
+            released.push(hold);
+        }
+    }
 
-        for event in &cmd.matcher_events {
-            match event.event_type {
-                MatcherEventType::Trade => {
-                    self.handle_trade_event(cmd, event, &spec, taker_sell);
-                }
-                MatcherEventType::Reject | MatcherEventType::Reduce => {
-                    self.handle_reject_event(cmd, event, &spec, taker_sell);
-                }
+    /// 按事件扣减挂单剩余数量，返回释放的冻结 (币种, 金额)（本分片没有该挂单的记录时返回 None）
+    ///
+    /// 冻结按剩余数量等比例释放，订单完结时释放全部剩余冻结；
+    /// 预算买单的成交只释放实际成交额与手续费，未用完的预算在订单完结时释放
+    fn release_order_hold(
+        &mut self,
+        uid: UserId,
+        order_id: OrderId,
+        event: &MatcherTradeEvent,
+        completed: bool,
+        taker_trade: bool,
+    ) -> Option<(Currency, i64)> {
+        if !self.uid_for_this_shard(uid) {
+            return None;
+        }
+        let profile = self.user_service.get_user_mut(uid)?;
+        let order = profile.open_orders.get_mut(&order_id)?;
+        let before = order.remaining;
+        order.remaining -= event.size;
+        let released = if completed || order.remaining <= 0 {
+            order.hold
+        } else if order.budget && taker_trade {
+            let spec = self.symbols.get(&order.symbol)?;
+            let spent = spec.scale().quote_value(event.size, event.price) + profile.fee_tier.fee(spec, event.size, true) as i128;
+            saturating_amount(spent).clamp(0, order.hold)
+        } else {
+            (order.hold as i128 * event.size as i128 / before as i128) as i64
+        };
+        order.hold -= released;
+        let currency = order.currency;
+        if completed || order.remaining <= 0 {
+            profile.open_orders.remove(&order_id);
+        }
+        Some((currency, released))
+    }
+
+    /// 暂停用户时批量撤单的结算：撤单事件与 cmd.open_orders 一一对应，按各自交易对返还冻结资金
+    fn settle_mass_cancel(&mut self, cmd: &mut OrderCommand, released: &[ReleasedHold]) {
+        if cmd.result_code != CommandResultCode::ValidForMatchingEngine {
+            return;
+        }
+
+        for ((order, event), released) in cmd.open_orders.iter().zip(&cmd.matcher_events).zip(released) {
+            if let Some(spec) = self.symbols.get(&order.symbol).cloned() {
+                self.handle_reject_event(cmd.uid, event, &spec, order.action == OrderAction::Ask, released.maker_amount());
             }
         }
         cmd.result_code = CommandResultCode::Success;
     }
 
-    /// 处理成交事件
+    /// 组合下单的结算：撮合事件与 cmd.open_orders 中的腿订单一一对应，按各腿的交易对结算；
+    /// 各腿的撮合结果写在 binary_data 中，整单结果为 Success
+    fn settle_basket(&mut self, cmd: &mut OrderCommand, released: &[ReleasedHold]) {
+        // 先结算的风控分片已将结果改为 Success，其余分片仍需结算本分片用户
+        if !matches!(cmd.result_code, CommandResultCode::ValidForMatchingEngine | CommandResultCode::Success) {
+            return;
+        }
+        for (index, (event, released)) in cmd.matcher_events.iter().zip(released).enumerate() {
+            let spec = self.symbols.get(&cmd.event_symbol(index)).cloned();
+            self.settle_event(cmd, event, spec.as_ref(), released);
+        }
+        cmd.result_code = CommandResultCode::Success;
+    }
+
+    /// 隐含成交腿：taker 在该交易对没有挂单与冻结（冻结在命令自身订单上，由减量事件返还），
+    /// 先按成交数量计入待成交，再与 maker 一起按普通成交开平仓并扣收保证金与手续费
+    fn settle_implied_leg(&mut self, cmd: &OrderCommand, event: &MatcherTradeEvent, spec: &CoreSymbolSpecification, released: &ReleasedHold) {
+        let (taker_uid, _, taker_action) = event.taker(cmd);
+        if self.uid_for_this_shard(taker_uid) {
+            if let Some(profile) = self.user_service.get_user_mut(taker_uid) {
+                profile
+                    .positions
+                    .entry(spec.symbol_id)
+                    .or_insert_with(|| SymbolPositionRecord::new(taker_uid, spec.symbol_id, spec.quote_currency))
+                    .add_pending(taker_action, event.size);
+            }
+        }
+        let released = ReleasedHold { taker: Some((spec.quote_currency, 0)), ..*released };
+        self.handle_trade_event(taker_uid, event, spec, taker_action == OrderAction::Ask, &released);
+    }
+
+    /// 处理成交事件：taker 与 maker 各自按所属用户的手续费等级结算
     fn handle_trade_event(
         &mut self,
-        cmd: &OrderCommand,
+        taker_uid: UserId,
         event: &MatcherTradeEvent,
         spec: &CoreSymbolSpecification,
         taker_sell: bool,
+        released: &ReleasedHold,
     ) {
-        // Taker 结算
-        if self.uid_for_this_shard(cmd.uid) {
-            if let Some(taker) = self.user_service.get_user_mut(cmd.uid) {
-                if taker_sell {
-                    // 卖单：收入 quote 币
-                    let amount = event.size * event.price * spec.quote_scale_k - event.size * spec.taker_fee;
-                    *taker.accounts.entry(spec.quote_currency).or_insert(0) += amount;
-                } else {
-                    // 买单：返还差价 + 收入 base 币
-                    let price_diff = event.bidder_hold_price - event.price;
-                    let refund = event.size * price_diff * spec.quote_scale_k;
-                    *taker.accounts.entry(spec.quote_currency).or_insert(0) += refund;
-                    *taker.accounts.entry(spec.base_currency).or_insert(0) += event.size * spec.base_scale_k;
-                }
-            }
+        let taker_action = if taker_sell { OrderAction::Ask } else { OrderAction::Bid };
+        if spec.is_margin_trading() {
+            self.settle_margin_trade(taker_uid, taker_action, event, spec, true, released.taker_amount());
+            self.settle_margin_trade(event.matched_order_uid, taker_action.opposite(), event, spec, false, released.maker_amount());
+        } else {
+            self.settle_spot_trade(taker_uid, taker_action, event, spec, true, released.taker_amount());
+            self.settle_spot_trade(event.matched_order_uid, taker_action.opposite(), event, spec, false, released.maker_amount());
+        }
+        if spec.is_option() {
+            self.track_option_position(spec.symbol_id, taker_uid, taker_action, event.size);
+            self.track_option_position(spec.symbol_id, event.matched_order_uid, taker_action.opposite(), event.size);
         }
+        self.record_maker_volume(event.matched_order_uid, event, spec);
+    }
 
-        // Maker 结算
-        if self.uid_for_this_shard(event.matched_order_uid) {
-            if let Some(maker) = self.user_service.get_user_mut(event.matched_order_uid) {
-                if taker_sell {
-                    // Taker 卖 => Maker 买
-                    let price_diff = event.bidder_hold_price - event.price;
-                    let refund = event.size * price_diff * spec.quote_scale_k;
-                    *maker.accounts.entry(spec.quote_currency).or_insert(0) += refund;
-                    *maker.accounts.entry(spec.base_currency).or_insert(0) += event.size * spec.base_scale_k;
-                } else {
-                    // Taker 买 => Maker 卖
-                    let amount = event.size * event.price * spec.quote_scale_k - event.size * spec.maker_fee;
-                    *maker.accounts.entry(spec.quote_currency).or_insert(0) += amount;
+    /// 累计本分片用户的 maker 成交量
+    fn record_maker_volume(&mut self, uid: UserId, event: &MatcherTradeEvent, spec: &CoreSymbolSpecification) {
+        if !self.uid_for_this_shard(uid) {
+            return;
+        }
+        if let Some(profile) = self.user_service.get_user_mut(uid) {
+            let notional = saturating_amount(spec.scale().quote_value(event.size, event.price));
+            profile.maker_volume.entry(spec.symbol_id).or_default().record(event.size, notional);
+        }
+    }
+
+    /// 查询 cmd.uid 的 maker 成交量（用户所在分片填充）
+    fn maker_volume_report(&self, cmd: &mut OrderCommand) -> CommandResultCode {
+        let Some(profile) = self.user_service.get_user(cmd.uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        let report = MakerVolumeReport {
+            epoch: self.maker_epoch,
+            current: profile.maker_volume.iter().map(|(&symbol, &volume)| (symbol, volume)).collect(),
+            previous: profile.previous_maker_volume.iter().map(|(&symbol, &volume)| (symbol, volume)).collect(),
+        };
+        cmd.binary_data = bincode::serialize(&report).expect("maker 成交量序列化失败");
+        CommandResultCode::Success
+    }
+
+    /// 开始新的统计周期：本分片各用户的当前周期结转为上一周期
+    fn reset_maker_volume(&mut self) {
+        self.maker_epoch += 1;
+        for profile in self.user_service.profiles_mut() {
+            profile.previous_maker_volume = std::mem::take(&mut profile.maker_volume);
+        }
+    }
+
+    /// 现货成交结算：买方收入 base，卖方收入 quote
+    ///
+    /// 买单按挂单记录释放的冻结（没有记录时按 bidder_hold_price 与 taker 费率计算）结算，返还价差与多冻结的手续费；
+    /// 卖单释放的 base 超出成交数量的部分返还。卖方的手续费（或返佣）从成交额中扣收，
+    /// 成交金额不超过买方已校验的冻结金额，按 saturating 收窄
+    fn settle_spot_trade(
+        &mut self,
+        uid: UserId,
+        action: OrderAction,
+        event: &MatcherTradeEvent,
+        spec: &CoreSymbolSpecification,
+        is_taker: bool,
+        released: Option<i64>,
+    ) {
+        if !self.uid_for_this_shard(uid) {
+            return;
+        }
+        let Some(profile) = self.user_service.get_user_mut(uid) else {
+            return;
+        };
+
+        let scale = spec.scale();
+        let fee = profile.fee_tier.fee(spec, event.size, is_taker);
+        let notional = scale.quote_value(event.size, event.price);
+        match action {
+            OrderAction::Bid => {
+                let held = match released {
+                    Some(released) => released as i128,
+                    None => scale.quote_value(event.size, event.bidder_hold_price) + SymbolScale::fee_value(event.size, spec.taker_fee),
+                };
+                *profile.accounts.entry(spec.quote_currency).or_insert(0) += saturating_amount(held - notional - fee as i128);
+                *profile.accounts.entry(spec.base_currency).or_insert(0) += saturating_amount(scale.base_value(event.size));
+            }
+            OrderAction::Ask => {
+                *profile.accounts.entry(spec.quote_currency).or_insert(0) += saturating_amount(notional - fee as i128);
+                if let Some(released) = released {
+                    *profile.accounts.entry(spec.base_currency).or_insert(0) += saturating_amount(released as i128 - scale.base_value(event.size));
                 }
             }
         }
+
+        if is_taker {
+            self.fees.record(spec.symbol_id, spec.quote_currency, 0, fee);
+        } else {
+            self.fees.record(spec.symbol_id, spec.quote_currency, fee, 0);
+        }
     }
 
-    /// 处理拒绝/取消事件
+    /// 期货/永续成交结算：更新持仓并结算已实现盈亏，不交换 base/quote
+    ///
+    /// 下单时每手冻结 initial_margin + taker_fee（released 为挂单记录释放的冻结）：开仓部分的保证金转为持仓保证金，
+    /// 平仓部分返还订单冻结及被平仓位的保证金；手续费按实际费率收取，多冻结部分返还
+    fn settle_margin_trade(
+        &mut self,
+        uid: UserId,
+        action: OrderAction,
+        event: &MatcherTradeEvent,
+        spec: &CoreSymbolSpecification,
+        is_taker: bool,
+        released: Option<i64>,
+    ) {
+        if !self.uid_for_this_shard(uid) {
+            return;
+        }
+        let Some(profile) = self.user_service.get_user_mut(uid) else {
+            return;
+        };
+
+        let position = profile
+            .positions
+            .entry(spec.symbol_id)
+            .or_insert_with(|| SymbolPositionRecord::new(uid, spec.symbol_id, spec.quote_currency));
+        position.add_pending(action, -event.size);
+        let (closed, pnl) = position.update_position(action, event.size, event.price);
+        if position.is_empty() {
+            profile.positions.remove(&spec.symbol_id);
+        }
+
+        let fee = profile.fee_tier.fee(spec, event.size, is_taker);
+        let held = match released {
+            Some(released) => released as i128,
+            None => event.size as i128 * (spec.initial_margin(action) as i128 + spec.taker_fee as i128),
+        };
+        let opened_margin = (event.size - closed) as i128 * spec.initial_margin(action) as i128;
+        let closed_margin = closed as i128 * spec.initial_margin(action.opposite()) as i128;
+        let realized = spec.scale().quote_value(1, pnl);
+        *profile.accounts.entry(spec.quote_currency).or_insert(0) +=
+            saturating_amount(held - opened_margin + closed_margin - fee as i128 + realized);
+
+        if is_taker {
+            self.fees.record(spec.symbol_id, spec.quote_currency, 0, fee);
+        } else {
+            self.fees.record(spec.symbol_id, spec.quote_currency, fee, 0);
+        }
+    }
+
+    /// 处理拒绝/取消事件：返还挂单记录释放的冻结（没有记录时按事件的数量与冻结价格计算）
     fn handle_reject_event(
         &mut self,
-        cmd: &OrderCommand,
+        taker_uid: UserId,
         event: &MatcherTradeEvent,
         spec: &CoreSymbolSpecification,
         taker_sell: bool,
+        released: Option<i64>,
     ) {
-        if !self.uid_for_this_shard(cmd.uid) {
+        // 作用于挂单的事件（自成交预防、过期等）返还给挂单用户
+        let (uid, refund_sell) = match event.maker_action {
+            Some(action) => (event.matched_order_uid, action == OrderAction::Ask),
+            None => (taker_uid, taker_sell),
+        };
+
+        if !self.uid_for_this_shard(uid) {
             return;
         }
 
-        let Some(profile) = self.user_service.get_user_mut(cmd.uid) else {
+        let Some(profile) = self.user_service.get_user_mut(uid) else {
             return;
         };
 
         // 返还冻结资金
-        if taker_sell {
-            let refund = event.size * spec.base_scale_k;
+        if spec.is_margin_trading() {
+            let action = if refund_sell { OrderAction::Ask } else { OrderAction::Bid };
+            let per_lot = spec.initial_margin(action) as i128 + spec.taker_fee as i128;
+            let refund = released.unwrap_or_else(|| saturating_amount(event.size as i128 * per_lot));
+            *profile.accounts.entry(spec.quote_currency).or_insert(0) += refund;
+            if let Some(position) = profile.positions.get_mut(&spec.symbol_id) {
+                position.add_pending(action, -event.size);
+                if position.is_empty() {
+                    profile.positions.remove(&spec.symbol_id);
+                }
+            }
+        } else if refund_sell {
+            let refund = released.unwrap_or_else(|| saturating_amount(spec.scale().base_value(event.size)));
             *profile.accounts.entry(spec.base_currency).or_insert(0) += refund;
         } else {
-            let refund = event.size * event.bidder_hold_price * spec.quote_scale_k + event.size * spec.taker_fee;
+            let refund = released.unwrap_or_else(|| {
+                let hold = spec.scale().quote_value(event.size, event.bidder_hold_price);
+                saturating_amount(hold.saturating_add(SymbolScale::fee_value(event.size, spec.taker_fee)))
+            });
             *profile.accounts.entry(spec.quote_currency).or_insert(0) += refund;
         }
     }
@@ -0,0 +1,169 @@
+use crate::api::OrderCommand;
+use crate::core::exchange::ExchangeCore;
+use anyhow::Result;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+/// 复制的命令：主节点分配的序列号（与命令日志一致）+ 命令
+#[derive(Debug, Clone)]
+pub struct ReplicatedCommand {
+    pub seq: u64,
+    pub command: OrderCommand,
+}
+
+/// 复制传输层发送端：主节点按序列号顺序发送
+pub trait ReplicationTransport: Send {
+    fn send(&mut self, record: &ReplicatedCommand) -> Result<()>;
+}
+
+/// 复制传输层接收端：副本按发送顺序读取
+pub trait ReplicationReceiver: Send {
+    /// 非阻塞读取下一条命令，暂无新命令时返回 None
+    fn try_recv(&mut self) -> Result<Option<ReplicatedCommand>>;
+}
+
+/// 进程内通道传输（同机热备、测试）
+pub fn channel() -> (ChannelTransport, ChannelReceiver) {
+    let (sender, receiver) = mpsc::channel();
+    (ChannelTransport(sender), ChannelReceiver(receiver))
+}
+
+pub struct ChannelTransport(Sender<ReplicatedCommand>);
+
+impl ReplicationTransport for ChannelTransport {
+    fn send(&mut self, record: &ReplicatedCommand) -> Result<()> {
+        self.0.send(record.clone()).map_err(|_| anyhow::anyhow!("副本已断开"))
+    }
+}
+
+pub struct ChannelReceiver(Receiver<ReplicatedCommand>);
+
+impl ReplicationReceiver for ChannelReceiver {
+    fn try_recv(&mut self) -> Result<Option<ReplicatedCommand>> {
+        match self.0.try_recv() {
+            Ok(record) => Ok(Some(record)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => anyhow::bail!("主节点已断开"),
+        }
+    }
+}
+
+/// 主节点到单个副本的复制状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaLinkStatus {
+    pub sent_seq: u64,         // 最后发送成功的序列号
+    pub error: Option<String>, // 发送失败后停止复制（副本需从快照重新同步）
+}
+
+struct ReplicaLink {
+    transport: Box<dyn ReplicationTransport>,
+    status: ReplicaLinkStatus,
+}
+
+/// 主节点侧复制：每条分配了序列号的命令发送给全部副本
+#[derive(Default)]
+pub struct ReplicationPublisher {
+    links: Vec<ReplicaLink>,
+}
+
+impl ReplicationPublisher {
+    /// 添加副本，副本状态须与主节点序列号 start_seq 时一致（同一快照或空状态）
+    pub fn add_replica(&mut self, transport: Box<dyn ReplicationTransport>, start_seq: u64) {
+        self.links.push(ReplicaLink {
+            transport,
+            status: ReplicaLinkStatus { sent_seq: start_seq, error: None },
+        });
+    }
+
+    pub fn publish(&mut self, seq: u64, command: &OrderCommand) {
+        let record = ReplicatedCommand { seq, command: command.clone() };
+        for link in self.links.iter_mut().filter(|link| link.status.error.is_none()) {
+            match link.transport.send(&record) {
+                Ok(()) => link.status.sent_seq = seq,
+                Err(e) => link.status.error = Some(e.to_string()),
+            }
+        }
+    }
+
+    pub fn status(&self) -> Vec<ReplicaLinkStatus> {
+        self.links.iter().map(|link| link.status.clone()).collect()
+    }
+}
+
+/// 副本状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplicaStatus {
+    pub applied_seq: u64,  // 已应用的最后序列号
+    pub received_seq: u64, // 已收到的最大序列号
+}
+
+impl ReplicaStatus {
+    /// 落后主节点的命令数
+    pub fn lag(&self, primary_seq: u64) -> u64 {
+        primary_seq.saturating_sub(self.applied_seq)
+    }
+}
+
+/// 热备副本：按序列号顺序把主节点的命令送入本地 ExchangeCore，处理结果与主节点一致
+///
+/// 重复的序列号直接跳过；序列号不连续时报错且不再应用，需从快照重新同步
+pub struct Replica {
+    core: ExchangeCore,
+    receiver: Box<dyn ReplicationReceiver>,
+    received_seq: u64,
+}
+
+impl Replica {
+    /// core 的状态须与主节点添加该副本时一致
+    pub fn new(core: ExchangeCore, receiver: Box<dyn ReplicationReceiver>) -> Self {
+        let received_seq = core.last_seq();
+        Self { core, receiver, received_seq }
+    }
+
+    /// 应用已收到的全部命令，返回本次应用的命令数
+    pub fn poll(&mut self) -> Result<usize> {
+        let mut applied = 0;
+        while let Some(record) = self.receiver.try_recv()? {
+            self.received_seq = self.received_seq.max(record.seq);
+            if self.apply(record)? {
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+
+    fn apply(&mut self, record: ReplicatedCommand) -> Result<bool> {
+        let expected = self.core.last_seq() + 1;
+        if record.seq < expected {
+            return Ok(false);
+        }
+        if record.seq > expected {
+            anyhow::bail!("复制序列号不连续: 期望 {}, 收到 {}", expected, record.seq);
+        }
+        self.core.submit_command(record.command);
+        if self.core.last_seq() != record.seq {
+            anyhow::bail!("副本序列号与主节点不一致: 本地 {}, 主节点 {}", self.core.last_seq(), record.seq);
+        }
+        Ok(true)
+    }
+
+    pub fn status(&self) -> ReplicaStatus {
+        ReplicaStatus {
+            applied_seq: self.core.last_seq(),
+            received_seq: self.received_seq,
+        }
+    }
+
+    /// 只读访问本地状态（副本不接受本地提交的命令，否则序列号与主节点不一致）
+    pub fn core(&self) -> &ExchangeCore {
+        &self.core
+    }
+
+    /// 故障切换：应用剩余命令后把本地 ExchangeCore 提升为主节点
+    pub fn promote(mut self) -> Result<ExchangeCore> {
+        // 主节点已下线时通道断开，已收到的命令仍需应用
+        while let Ok(Some(record)) = self.receiver.try_recv() {
+            self.apply(record)?;
+        }
+        Ok(self.core)
+    }
+}
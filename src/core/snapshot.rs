@@ -1,46 +1,93 @@
 use crate::core::exchange::ExchangeState;
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
 use anyhow::{Context, Result};
 
+/// 快照压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotCompression {
+    #[default]
+    None,
+    Lz4, // LZ4 frame 格式，文件名后缀 .lz4
+}
+
+/// 快照写入配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SnapshotConfig {
+    pub compression: SnapshotCompression,
+    /// 调用方只序列化到内存，压缩与写盘在后台线程完成（`flush` 等待写完）
+    pub async_write: bool,
+}
+
 /// 快照管理器（使用 bincode，兼容性好）
+///
+/// 快照先写入临时文件再改名，未写完的快照不会被 `get_latest_seq_id` 发现
+#[derive(Clone)]
 pub struct SnapshotStore {
     base_path: PathBuf,
+    config: SnapshotConfig,
+    writer: Option<Arc<AsyncWriter>>, // async_write 时的后台写入线程
 }
 
 impl SnapshotStore {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_config(path, SnapshotConfig::default())
+    }
+
+    pub fn with_config<P: AsRef<Path>>(path: P, config: SnapshotConfig) -> Result<Self> {
         let base_path = path.as_ref().to_path_buf();
         if !base_path.exists() {
             fs::create_dir_all(&base_path).context("无法创建快照目录")?;
         }
-        Ok(Self { base_path })
+        let writer = config.async_write.then(|| Arc::new(AsyncWriter::spawn()));
+        Ok(Self { base_path, config, writer })
+    }
+
+    pub fn config(&self) -> SnapshotConfig {
+        self.config
     }
 
-    /// 保存核心状态到快照文件
+    /// 保存核心状态到快照文件，返回快照文件路径
+    ///
+    /// 异步写入时状态序列化完成即返回，写盘失败在下一次 `flush` 时报告
     pub fn save_snapshot(&self, state: &ExchangeState, seq_id: u64) -> Result<PathBuf> {
-        let filename = format!("snapshot_{}.bin", seq_id);
-        let path = self.base_path.join(filename);
-        
-        let file = File::create(&path).context("无法创建快照文件")?;
-        let writer = BufWriter::new(file);
-        
-        bincode::serialize_into(writer, state).context("序列化快照失败")?;
-        
+        let path = self.snapshot_path(seq_id, self.config.compression);
+        let bytes = bincode::serialize(state).context("序列化快照失败")?;
+        match &self.writer {
+            Some(writer) => writer.submit(path.clone(), bytes, self.config.compression),
+            None => write_snapshot_file(&path, &bytes, self.config.compression)?,
+        }
         Ok(path)
     }
 
-    /// 加载指定索引的快照
+    /// 等待后台写入完成，返回此前写入失败的错误（同步写入时直接返回）
+    pub fn flush(&self) -> Result<()> {
+        match &self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// 加载指定索引的快照（压缩与未压缩的快照均可读取）
     pub fn load_snapshot(&self, seq_id: u64) -> Result<ExchangeState> {
-        let filename = format!("snapshot_{}.bin", seq_id);
-        let path = self.base_path.join(filename);
-        
+        let compressed = self.snapshot_path(seq_id, SnapshotCompression::Lz4);
+        let (path, compression) = if compressed.exists() {
+            (compressed, SnapshotCompression::Lz4)
+        } else {
+            (self.snapshot_path(seq_id, SnapshotCompression::None), SnapshotCompression::None)
+        };
+
         let file = File::open(&path).context("无法打开快照文件")?;
-        let reader = BufReader::new(file);
-        
+        let reader: Box<dyn Read> = match compression {
+            SnapshotCompression::None => Box::new(BufReader::new(file)),
+            SnapshotCompression::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(BufReader::new(file))),
+        };
+
         let state: ExchangeState = bincode::deserialize_from(reader).context("反序列化快照失败")?;
-        
+
         Ok(state)
     }
 
@@ -50,14 +97,94 @@ impl SnapshotStore {
         for entry in fs::read_dir(&self.base_path)? {
             let entry = entry?;
             let name = entry.file_name().to_string_lossy().into_owned();
-            if name.starts_with("snapshot_") && name.ends_with(".bin") {
-                if let Ok(id) = name["snapshot_".len()..name.len() - 4].parse::<u64>() {
-                    ids.push(id);
-                }
+            let stem = name.strip_suffix(".lz4").unwrap_or(&name);
+            if let Some(Ok(id)) = stem.strip_prefix("snapshot_").and_then(|s| s.strip_suffix(".bin")).map(str::parse::<u64>) {
+                ids.push(id);
             }
         }
-        
+
         ids.sort_unstable();
         Ok(ids.last().copied())
     }
+
+    fn snapshot_path(&self, seq_id: u64, compression: SnapshotCompression) -> PathBuf {
+        let filename = match compression {
+            SnapshotCompression::None => format!("snapshot_{}.bin", seq_id),
+            SnapshotCompression::Lz4 => format!("snapshot_{}.bin.lz4", seq_id),
+        };
+        self.base_path.join(filename)
+    }
+}
+
+/// 压缩并写入快照：先写临时文件，fsync 后改名
+fn write_snapshot_file(path: &Path, bytes: &[u8], compression: SnapshotCompression) -> Result<()> {
+    let mut tmp_name = path.as_os_str().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let file = File::create(&tmp_path).context("无法创建快照文件")?;
+    let mut writer = BufWriter::new(file);
+    match compression {
+        SnapshotCompression::None => writer.write_all(bytes)?,
+        SnapshotCompression::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(&mut writer);
+            encoder.write_all(bytes)?;
+            encoder.finish().context("压缩快照失败")?;
+        }
+    }
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_data()?;
+    fs::rename(&tmp_path, path).context("无法保存快照文件")?;
+    Ok(())
+}
+
+/// 后台快照写入线程：按提交顺序压缩并写盘
+struct AsyncWriter {
+    sender: Mutex<Sender<(PathBuf, Vec<u8>, SnapshotCompression)>>,
+    state: Arc<(Mutex<WriterState>, Condvar)>,
+}
+
+#[derive(Default)]
+struct WriterState {
+    pending: usize,
+    error: Option<String>, // 尚未报告的写入错误
+}
+
+impl AsyncWriter {
+    fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel::<(PathBuf, Vec<u8>, SnapshotCompression)>();
+        let state = Arc::new((Mutex::new(WriterState::default()), Condvar::new()));
+        let shared = state.clone();
+        std::thread::Builder::new()
+            .name("snapshot-writer".into())
+            .spawn(move || {
+                // 所有 SnapshotStore 副本释放后通道关闭，线程退出
+                for (path, bytes, compression) in receiver {
+                    let result = write_snapshot_file(&path, &bytes, compression);
+                    let (lock, done) = &*shared;
+                    let mut state = lock.lock().unwrap();
+                    state.pending -= 1;
+                    if let Err(e) = result {
+                        state.error = Some(format!("{}: {:#}", path.display(), e));
+                    }
+                    done.notify_all();
+                }
+            })
+            .expect("无法启动快照写入线程");
+        Self { sender: Mutex::new(sender), state }
+    }
+
+    fn submit(&self, path: PathBuf, bytes: Vec<u8>, compression: SnapshotCompression) {
+        self.state.0.lock().unwrap().pending += 1;
+        self.sender.lock().unwrap().send((path, bytes, compression)).expect("快照写入线程已退出");
+    }
+
+    fn flush(&self) -> Result<()> {
+        let (lock, done) = &*self.state;
+        let mut state = done.wait_while(lock.lock().unwrap(), |state| state.pending > 0).unwrap();
+        match state.error.take() {
+            Some(error) => anyhow::bail!("快照写入失败: {}", error),
+            None => Ok(()),
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use crate::api::*;
+use crate::core::listener::{OrderBookListener, OrderRemoval};
+use ahash::AHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// 交易监控配置，时间与 timestamp 同单位
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SurveillanceConfig {
+    pub wash_window: i64,    // 同一用户以同一价格买入与卖出的间隔不超过该值时视为对倒（0 关闭）
+    pub cancel_window: i64,  // 撤单率统计窗口（0 关闭）
+    pub min_orders: u32,     // 窗口内挂单数达到该值才检查撤单率
+    pub max_cancel_pct: u32, // 窗口内撤单数超过挂单数的该百分比时告警
+}
+
+/// 监控告警
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SurveillanceAlert {
+    /// 自成交：taker 与 maker 为同一用户（未启用自成交预防时发生）
+    SelfMatch {
+        symbol: SymbolId,
+        uid: UserId,
+        taker_order_id: OrderId,
+        maker_order_id: OrderId,
+        price: Price,
+        size: Size,
+        timestamp: i64,
+    },
+    /// 对倒：同一用户在窗口内以同一价格先后买入与卖出
+    WashTrade {
+        symbol: SymbolId,
+        uid: UserId,
+        price: Price,
+        buy_order_id: OrderId,
+        sell_order_id: OrderId,
+        timestamp: i64,
+    },
+    /// 撤单率过高（疑似幌骗/分层挂单），每个窗口每个用户最多告警一次
+    ExcessiveCancels {
+        symbol: SymbolId,
+        uid: UserId,
+        placed: u32,
+        cancelled: u32,
+        window_start: i64,
+        timestamp: i64,
+    },
+}
+
+/// 告警消费者，在撮合线程内同步调用
+pub type SurveillanceConsumer = Arc<dyn Fn(&SurveillanceAlert) + Send + Sync>;
+
+/// 同一用户同一价格最近一次买入、卖出的 (时间, 订单)
+type FillSides = [Option<(i64, OrderId)>; 2];
+
+/// 用户在一个统计窗口内的挂单与撤单数（撤单只计用户主动撤单，不含批量撤单与过期）
+#[derive(Debug, Clone, Copy, Default)]
+struct CancelWindow {
+    start: i64,
+    placed: u32,
+    cancelled: u32,
+    alerted: bool,
+}
+
+/// 交易监控（可选模块）：基于订单簿监听器检测自成交、对倒与高撤单率，告警交给消费者
+///
+/// 每个撮合分片一个实例，只统计本分片的交易对
+pub struct SurveillanceProcessor {
+    config: SurveillanceConfig,
+    consumer: SurveillanceConsumer,
+    fills: AHashMap<(SymbolId, UserId, Price), FillSides>,
+    fill_times: VecDeque<(i64, (SymbolId, UserId, Price))>, // 按时间排列，用于清理窗口外的记录
+    cancels: AHashMap<(SymbolId, UserId), CancelWindow>,
+}
+
+impl SurveillanceProcessor {
+    pub fn new(config: SurveillanceConfig, consumer: SurveillanceConsumer) -> Self {
+        Self {
+            config,
+            consumer,
+            fills: AHashMap::new(),
+            fill_times: VecDeque::new(),
+            cancels: AHashMap::new(),
+        }
+    }
+
+    /// 记录一方的成交，窗口内同价有反向成交时告警（告警后清除反向记录，避免同一对成交重复告警）
+    fn on_fill(&mut self, symbol: SymbolId, uid: UserId, order_id: OrderId, action: OrderAction, price: Price, now: i64) {
+        let window = self.config.wash_window;
+        let sides = self.fills.entry((symbol, uid, price)).or_default();
+        let (side, opposite) = match action {
+            OrderAction::Bid => (0, 1),
+            OrderAction::Ask => (1, 0),
+        };
+        sides[side] = Some((now, order_id));
+        if let Some((time, other)) = sides[opposite] {
+            if now - time <= window {
+                sides[opposite] = None;
+                let (buy_order_id, sell_order_id) = if side == 0 { (order_id, other) } else { (other, order_id) };
+                (self.consumer)(&SurveillanceAlert::WashTrade { symbol, uid, price, buy_order_id, sell_order_id, timestamp: now });
+            }
+        }
+        self.fill_times.push_back((now, (symbol, uid, price)));
+    }
+
+    /// 移除两侧均已超出窗口的成交记录
+    fn prune_fills(&mut self, now: i64) {
+        let window = self.config.wash_window;
+        while let Some(&(time, key)) = self.fill_times.front() {
+            if now - time <= window {
+                break;
+            }
+            self.fill_times.pop_front();
+            let expired = self.fills.get(&key).is_some_and(|sides| sides.iter().flatten().all(|&(time, _)| now - time > window));
+            if expired {
+                self.fills.remove(&key);
+            }
+        }
+    }
+
+    /// 取得用户当前的统计窗口，超过窗口长度时开始新窗口
+    fn cancel_window(&mut self, symbol: SymbolId, uid: UserId, now: i64) -> &mut CancelWindow {
+        let length = self.config.cancel_window;
+        let window = self.cancels.entry((symbol, uid)).or_insert(CancelWindow { start: now, ..Default::default() });
+        if now - window.start >= length {
+            *window = CancelWindow { start: now, ..Default::default() };
+        }
+        window
+    }
+}
+
+impl OrderBookListener for SurveillanceProcessor {
+    fn on_trade(&mut self, cmd: &OrderCommand, event: &MatcherTradeEvent) {
+        let (taker_uid, taker_order_id, taker_action) = event.taker(cmd);
+        if taker_uid == event.matched_order_uid {
+            (self.consumer)(&SurveillanceAlert::SelfMatch {
+                symbol: cmd.symbol,
+                uid: taker_uid,
+                taker_order_id,
+                maker_order_id: event.matched_order_id,
+                price: event.price,
+                size: event.size,
+                timestamp: cmd.timestamp,
+            });
+            return;
+        }
+        if self.config.wash_window <= 0 {
+            return;
+        }
+        self.prune_fills(cmd.timestamp);
+        self.on_fill(cmd.symbol, taker_uid, taker_order_id, taker_action, event.price, cmd.timestamp);
+        self.on_fill(cmd.symbol, event.matched_order_uid, event.matched_order_id, taker_action.opposite(), event.price, cmd.timestamp);
+    }
+
+    fn on_order_added(&mut self, cmd: &OrderCommand, order: &OpenOrder) {
+        if self.config.cancel_window > 0 {
+            self.cancel_window(order.symbol, order.uid, cmd.timestamp).placed += 1;
+        }
+    }
+
+    fn on_order_removed(&mut self, cmd: &OrderCommand, uid: UserId, _order_id: OrderId, reason: OrderRemoval) {
+        if self.config.cancel_window <= 0 || reason != OrderRemoval::Cancelled {
+            return;
+        }
+        let (min_orders, max_cancel_pct) = (self.config.min_orders, self.config.max_cancel_pct);
+        let window = self.cancel_window(cmd.symbol, uid, cmd.timestamp);
+        window.cancelled += 1;
+        let excessive = window.placed >= min_orders && window.cancelled as u64 * 100 > window.placed as u64 * max_cancel_pct as u64;
+        if window.alerted || !excessive {
+            return;
+        }
+        window.alerted = true;
+        let alert = SurveillanceAlert::ExcessiveCancels {
+            symbol: cmd.symbol,
+            uid,
+            placed: window.placed,
+            cancelled: window.cancelled,
+            window_start: window.start,
+            timestamp: cmd.timestamp,
+        };
+        (self.consumer)(&alert);
+    }
+}
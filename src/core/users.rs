@@ -1,24 +1,84 @@
 use crate::api::*;
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use serde::{Deserialize, Serialize};
 
+/// 用户状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UserStatus {
+    Active,
+    Suspended, // 暂停：拒绝新订单
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserProfile {
     pub uid: UserId,
+    pub status: UserStatus,
     pub accounts: AHashMap<Currency, i64>, // 运行时使用 AHashMap（性能更好）
     pub positions: AHashMap<SymbolId, SymbolPositionRecord>,
+    pub open_orders: AHashMap<OrderId, OpenOrderRecord>, // 挂单及剩余数量（挂单数限额、改单追加冻结用）
+    #[serde(default)]
+    pub withdrawals: AHashMap<OrderId, WithdrawalHold>, // 按交易号记录的提现冻结（含已完结的，用于幂等）
+    #[serde(default)]
+    pub applied_adjustments: AHashSet<OrderId>, // 已入账的余额调整交易号
+    #[serde(default)]
+    pub ledger: Vec<LedgerEntry>, // 余额调整流水
+    #[serde(default)]
+    pub fee_tier: FeeTier,
+    #[serde(default)]
+    pub maker_volume: AHashMap<SymbolId, MakerVolume>, // 当前统计周期的 maker 成交量
+    #[serde(default)]
+    pub previous_maker_volume: AHashMap<SymbolId, MakerVolume>, // 上一统计周期的 maker 成交量
 }
 
 impl UserProfile {
     pub fn new(uid: UserId) -> Self {
         Self {
             uid,
+            status: UserStatus::Active,
             accounts: AHashMap::new(),
             positions: AHashMap::new(),
+            open_orders: AHashMap::new(),
+            withdrawals: AHashMap::new(),
+            applied_adjustments: AHashSet::new(),
+            ledger: Vec::new(),
+            fee_tier: FeeTier::default(),
+            maker_volume: AHashMap::new(),
+            previous_maker_volume: AHashMap::new(),
         }
     }
 }
 
+/// 风控跟踪的挂单：剩余数量用于挂单数限额，方向与冻结价用于改单时追加冻结
+///
+/// 剩余数量与冻结金额在 R1 按下单/改单计入，在 R2 按撮合事件扣减与释放；
+/// 结算按记录的冻结金额返还，不受下单后手续费或精度设置变更的影响
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenOrderRecord {
+    pub symbol: SymbolId,
+    pub remaining: Size,
+    pub action: OrderAction,
+    pub reserve_price: Price,
+    pub currency: Currency, // 冻结币种
+    pub hold: i64,          // 剩余冻结金额
+    pub budget: bool,       // 现货预算买单：按总预算冻结，成交按实际金额释放
+}
+
+/// 提现冻结状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WithdrawalState {
+    Held,      // 已冻结，等待外部结算
+    Confirmed, // 已确认，资金离开系统
+    Released,  // 已取消，资金返还余额
+}
+
+/// 提现冻结
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalHold {
+    pub currency: Currency,
+    pub amount: i64,
+    pub state: WithdrawalState,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolPositionRecord {
     pub uid: UserId,
@@ -51,6 +111,57 @@ impl SymbolPositionRecord {
         }
     }
 
+    /// 带符号净持仓（多头为正）
+    pub fn net_position(&self) -> i64 {
+        self.open_volume_long - self.open_volume_short
+    }
+
+    /// 成交后更新净持仓：先平反向仓位，剩余部分开仓
+    ///
+    /// 返回 (平仓数量, 已实现盈亏)，盈亏以价格单位计（未乘 quote_scale_k）
+    pub fn update_position(&mut self, action: OrderAction, size: i64, price: i64) -> (i64, i64) {
+        let (open_volume, open_price_sum, sign) = match action {
+            OrderAction::Bid => (&mut self.open_volume_short, &mut self.open_price_short, -1),
+            OrderAction::Ask => (&mut self.open_volume_long, &mut self.open_price_long, 1),
+        };
+
+        let closed = size.min(*open_volume);
+        let mut pnl = 0;
+        if closed > 0 {
+            // 按持仓均价分摊开仓成本
+            let cost = (*open_price_sum as i128 * closed as i128 / *open_volume as i128) as i64;
+            pnl = (closed * price - cost) * sign;
+            *open_price_sum -= cost;
+            *open_volume -= closed;
+        }
+
+        let opened = size - closed;
+        if opened > 0 {
+            match action {
+                OrderAction::Bid => {
+                    self.open_volume_long += opened;
+                    self.open_price_long += opened * price;
+                }
+                OrderAction::Ask => {
+                    self.open_volume_short += opened;
+                    self.open_price_short += opened * price;
+                }
+            }
+        }
+
+        self.profit += pnl;
+        self.direction = self.net_position().signum() as i32;
+        (closed, pnl)
+    }
+
+    /// 调整挂单中的数量
+    pub fn add_pending(&mut self, action: OrderAction, size: i64) {
+        match action {
+            OrderAction::Bid => self.pending_buy_size += size,
+            OrderAction::Ask => self.pending_sell_size += size,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.open_volume_long == 0
             && self.open_volume_short == 0
@@ -64,6 +175,12 @@ pub struct UserProfileService {
     profiles: AHashMap<UserId, UserProfile>, // 运行时使用 AHashMap
 }
 
+impl Default for UserProfileService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UserProfileService {
     pub fn new() -> Self {
         Self {
@@ -88,18 +205,135 @@ impl UserProfileService {
         self.profiles.get_mut(&uid)
     }
 
+    pub fn profiles(&self) -> impl Iterator<Item = &UserProfile> {
+        self.profiles.values()
+    }
+
+    pub fn profiles_mut(&mut self) -> impl Iterator<Item = &mut UserProfile> {
+        self.profiles.values_mut()
+    }
+
+    pub fn suspend_user(&mut self, uid: UserId) -> CommandResultCode {
+        match self.profiles.get_mut(&uid) {
+            None => CommandResultCode::AuthInvalidUser,
+            Some(profile) if profile.status == UserStatus::Suspended => CommandResultCode::UserMgmtUserAlreadySuspended,
+            Some(profile) => {
+                profile.status = UserStatus::Suspended;
+                CommandResultCode::Success
+            }
+        }
+    }
+
+    pub fn resume_user(&mut self, uid: UserId) -> CommandResultCode {
+        match self.profiles.get_mut(&uid) {
+            None => CommandResultCode::AuthInvalidUser,
+            Some(profile) if profile.status != UserStatus::Suspended => CommandResultCode::UserMgmtUserNotSuspended,
+            Some(profile) => {
+                profile.status = UserStatus::Active;
+                CommandResultCode::Success
+            }
+        }
+    }
+
+    /// 设置手续费等级，对之后的成交生效（已挂单的冻结不变）
+    pub fn set_fee_tier(&mut self, uid: UserId, tier: FeeTier) -> CommandResultCode {
+        match self.profiles.get_mut(&uid) {
+            None => CommandResultCode::AuthInvalidUser,
+            Some(profile) => {
+                profile.fee_tier = tier;
+                CommandResultCode::Success
+            }
+        }
+    }
+
+    /// 提现冻结：从余额中冻结 amount；相同交易号、币种与金额的重复请求直接返回成功
+    pub fn hold_funds(&mut self, uid: UserId, currency: Currency, amount: i64, transaction_id: OrderId) -> CommandResultCode {
+        let Some(profile) = self.profiles.get_mut(&uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        if let Some(hold) = profile.withdrawals.get(&transaction_id) {
+            return if hold.currency == currency && hold.amount == amount {
+                CommandResultCode::Success
+            } else {
+                CommandResultCode::WithdrawalTransactionConflict
+            };
+        }
+
+        let balance = profile.accounts.entry(currency).or_insert(0);
+        if *balance < amount {
+            return CommandResultCode::RiskNsf;
+        }
+        *balance -= amount;
+        profile.withdrawals.insert(
+            transaction_id,
+            WithdrawalHold {
+                currency,
+                amount,
+                state: WithdrawalState::Held,
+            },
+        );
+        CommandResultCode::Success
+    }
+
+    /// 完结提现冻结（确认或取消），返回本次完结的冻结；重复完结为同一终态时返回 None
+    pub fn finish_withdrawal(
+        &mut self,
+        uid: UserId,
+        transaction_id: OrderId,
+        state: WithdrawalState,
+    ) -> Result<Option<WithdrawalHold>, CommandResultCode> {
+        let profile = self.profiles.get_mut(&uid).ok_or(CommandResultCode::AuthInvalidUser)?;
+        let hold = profile
+            .withdrawals
+            .get_mut(&transaction_id)
+            .ok_or(CommandResultCode::WithdrawalUnknownTransaction)?;
+        match hold.state {
+            WithdrawalState::Held => {
+                hold.state = state;
+                let hold = *hold;
+                if state == WithdrawalState::Released {
+                    *profile.accounts.entry(hold.currency).or_insert(0) += hold.amount;
+                }
+                Ok(Some(hold))
+            }
+            current if current == state => Ok(None),
+            _ => Err(CommandResultCode::WithdrawalTransactionConflict),
+        }
+    }
+
+    /// 余额调整：每个交易号只入账一次，并追加到用户流水
     pub fn balance_adjustment(
         &mut self,
         uid: UserId,
         currency: Currency,
         amount: i64,
-        _transaction_id: i64,
+        transaction_id: OrderId,
+        timestamp: i64,
     ) -> CommandResultCode {
-        if let Some(profile) = self.profiles.get_mut(&uid) {
-            *profile.accounts.entry(currency).or_insert(0) += amount;
-            CommandResultCode::Success
-        } else {
-            CommandResultCode::AuthInvalidUser
+        let Some(profile) = self.profiles.get_mut(&uid) else {
+            return CommandResultCode::AuthInvalidUser;
+        };
+        if profile.applied_adjustments.contains(&transaction_id) {
+            let applied = profile.ledger.iter().find(|entry| entry.transaction_id == transaction_id);
+            return match applied {
+                Some(entry) if entry.currency == currency && entry.amount == amount => CommandResultCode::UserMgmtAdjustmentAlreadyApplied,
+                _ => CommandResultCode::UserMgmtAdjustmentConflict,
+            };
         }
+
+        // 溢出的调整不入账，交易号可修正金额后重试
+        let balance = profile.accounts.entry(currency).or_insert(0);
+        if let Err(code) = checked_credit(balance, amount) {
+            return code;
+        }
+        profile.applied_adjustments.insert(transaction_id);
+        profile.ledger.push(LedgerEntry {
+            transaction_id,
+            currency,
+            amount,
+            balance: *balance,
+            timestamp,
+        });
+        CommandResultCode::Success
     }
 }
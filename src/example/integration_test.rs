@@ -1,5 +1,6 @@
 use crate::api::*;
 use crate::core::exchange::{ExchangeConfig, ExchangeCore};
+use crate::core::journal::Journaler;
 
 pub fn test_full_flow() {
     let config = ExchangeConfig::default();
@@ -33,7 +34,7 @@ pub fn test_full_flow() {
     // 2. 测试 WAL 和快照组合
     let journal_path = "integration.wal";
     let snapshot_dir = "integration_snapshots";
-    let _ = std::fs::remove_file(journal_path);
+    let _ = Journaler::remove_all(journal_path);
     let _ = std::fs::remove_dir_all(snapshot_dir);
 
     core.enable_journaling(journal_path).unwrap();
@@ -51,7 +52,7 @@ pub fn test_full_flow() {
     });
 
     println!("    Taking snapshot...");
-    core.take_snapshot(1).unwrap();
+    core.create_snapshot().unwrap();
 
     println!("    Recovering into new core...");
     let mut core2 = ExchangeCore::new(ExchangeConfig::default());
@@ -60,7 +61,7 @@ pub fn test_full_flow() {
     assert!(recovered);
 
     // 清理
-    let _ = std::fs::remove_file(journal_path);
+    let _ = Journaler::remove_all(journal_path);
     let _ = std::fs::remove_dir_all(snapshot_dir);
     
     println!("    Integration test passed.");
@@ -0,0 +1,395 @@
+//! C ABI（feature = "ffi"），供 C++/Java 等非 Rust 网关以动态库方式嵌入撮合引擎
+//!
+//! 头文件由 cbindgen 生成（见仓库根目录 cbindgen.toml）。结构体均为平铺的 `#[repr(C)]`，
+//! 枚举以 `MC_*` 整数常量传递，新增取值只追加不复用，保证 ABI 稳定。
+//!
+//! 典型流程：`mc_exchange_create` → `mc_exchange_add_symbol` → `mc_exchange_startup`
+//! → 多次 `mc_exchange_submit` → `mc_exchange_poll_results` 取回结果 → `mc_exchange_destroy`。
+//! 同一个句柄不能被多个线程同时使用；跨越 C 边界的 panic 会终止进程。
+
+use crate::api::*;
+use crate::core::command_future::CommandFuture;
+use crate::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::collections::VecDeque;
+use std::ffi::c_void;
+
+/// 成功
+pub const MC_OK: i32 = 0;
+/// 句柄或指针为空
+pub const MC_ERR_NULL_POINTER: i32 = -1;
+/// 命令类型、方向或订单类型取值无效
+pub const MC_ERR_INVALID_ARGUMENT: i32 = -2;
+
+// 命令类型（McCommand.command）
+pub const MC_CMD_PLACE_ORDER: i32 = 0;
+pub const MC_CMD_MOVE_ORDER: i32 = 1;
+pub const MC_CMD_CANCEL_ORDER: i32 = 2;
+pub const MC_CMD_REDUCE_ORDER: i32 = 3;
+pub const MC_CMD_ADD_USER: i32 = 4;
+pub const MC_CMD_BALANCE_ADJUSTMENT: i32 = 5;
+pub const MC_CMD_SUSPEND_USER: i32 = 6;
+pub const MC_CMD_RESUME_USER: i32 = 7;
+pub const MC_CMD_AMEND_ORDER: i32 = 8;
+
+// 订单方向（McCommand.action、McTradeEvent.maker_action / taker_action）
+pub const MC_ACTION_ASK: i32 = 0;
+pub const MC_ACTION_BID: i32 = 1;
+/// 事件未携带方向
+pub const MC_ACTION_NONE: i32 = -1;
+
+// 订单类型（McCommand.order_type），GTD 的过期时间取 McCommand.expire_time
+pub const MC_ORDER_GTC: i32 = 0;
+pub const MC_ORDER_IOC: i32 = 1;
+pub const MC_ORDER_FOK: i32 = 2;
+pub const MC_ORDER_FOK_BUDGET: i32 = 3;
+pub const MC_ORDER_IOC_BUDGET: i32 = 4;
+pub const MC_ORDER_POST_ONLY: i32 = 5;
+pub const MC_ORDER_STOP_LIMIT: i32 = 6;
+pub const MC_ORDER_STOP_MARKET: i32 = 7;
+pub const MC_ORDER_ICEBERG: i32 = 8;
+pub const MC_ORDER_DAY: i32 = 9;
+pub const MC_ORDER_GTD: i32 = 10;
+pub const MC_ORDER_MARKET: i32 = 11;
+pub const MC_ORDER_GTX: i32 = 12;
+pub const MC_ORDER_MARKET_TO_LIMIT: i32 = 13;
+
+// 撮合事件类型（McTradeEvent.event_type）
+pub const MC_EVENT_TRADE: i32 = 0;
+pub const MC_EVENT_REJECT: i32 = 1;
+pub const MC_EVENT_REDUCE: i32 = 2;
+pub const MC_EVENT_AMEND: i32 = 3;
+
+/// 结果码为 `CommandResultCode` 的声明序号，其中成功为 2（其它取值见 Rust 侧定义）
+pub const MC_RESULT_SUCCESS: i32 = 2;
+
+/// 引擎配置，`mc_exchange_config_default` 返回默认值
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct McExchangeConfig {
+    pub ring_buffer_size: usize,
+    pub matching_engines_num: usize,
+    pub risk_engines_num: usize,
+}
+
+/// 现货交易对规格（其余字段取默认值）
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct McSymbolSpec {
+    pub symbol_id: i32,
+    pub base_currency: i32,
+    pub quote_currency: i32,
+    pub base_scale_k: i64,
+    pub quote_scale_k: i64,
+    pub taker_fee: i64,
+    pub maker_fee: i64,
+    pub tick_size: i64,
+    pub lot_size: i64,
+    pub min_size: i64,
+    pub max_size: i64,
+}
+
+/// 平铺的命令，字段含义与 `OrderCommand` 相同；可选字段以 0 表示未设置
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct McCommand {
+    pub command: i32,
+    pub uid: u64,
+    pub order_id: u64,
+    pub symbol: i32,
+    pub price: i64,
+    pub reserve_price: i64,
+    pub size: i64,
+    pub action: i32,
+    pub order_type: i32,
+    pub stop_price: i64,
+    pub visible_size: i64,
+    pub expire_time: i64,
+    pub client_order_id: u64,
+    pub service_flags: i32,
+    pub timestamp: i64,
+}
+
+/// 撮合事件
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct McTradeEvent {
+    pub event_type: i32,
+    pub size: i64,
+    pub price: i64,
+    pub matched_order_id: u64,
+    pub matched_order_uid: u64,
+    pub matched_client_order_id: u64,
+    pub maker_action: i32,
+    pub maker_completed: bool,
+    pub taker_action: i32,
+    pub taker_order_id: u64,
+    pub taker_uid: u64,
+    pub trade_id: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// 命令处理结果，events 指向的数组只在回调期间有效
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct McResult {
+    pub command: i32,
+    pub result_code: i32,
+    pub uid: u64,
+    pub order_id: u64,
+    pub symbol: i32,
+    pub client_order_id: u64,
+    pub sequence: u64,
+    pub engine_timestamp: i64,
+    pub events: *const McTradeEvent,
+    pub events_len: usize,
+}
+
+/// 结果回调，user_data 为 `mc_exchange_poll_results` 传入的指针
+pub type McResultCallback = extern "C" fn(user_data: *mut c_void, result: *const McResult);
+
+/// 引擎句柄（对 C 侧不透明）
+pub struct McExchange {
+    core: ExchangeCore,
+    pending: VecDeque<(i32, CommandFuture)>, // 按提交顺序排队的结果，附带 C 侧命令类型
+}
+
+impl TryFrom<&McCommand> for OrderCommand {
+    type Error = i32;
+
+    fn try_from(cmd: &McCommand) -> Result<Self, i32> {
+        let command = match cmd.command {
+            MC_CMD_PLACE_ORDER => OrderCommandType::PlaceOrder,
+            MC_CMD_MOVE_ORDER => OrderCommandType::MoveOrder,
+            MC_CMD_CANCEL_ORDER => OrderCommandType::CancelOrder,
+            MC_CMD_REDUCE_ORDER => OrderCommandType::ReduceOrder,
+            MC_CMD_ADD_USER => OrderCommandType::AddUser,
+            MC_CMD_BALANCE_ADJUSTMENT => OrderCommandType::BalanceAdjustment,
+            MC_CMD_SUSPEND_USER => OrderCommandType::SuspendUser,
+            MC_CMD_RESUME_USER => OrderCommandType::ResumeUser,
+            MC_CMD_AMEND_ORDER => OrderCommandType::AmendOrder,
+            _ => return Err(MC_ERR_INVALID_ARGUMENT),
+        };
+        let action = match cmd.action {
+            MC_ACTION_ASK => OrderAction::Ask,
+            MC_ACTION_BID => OrderAction::Bid,
+            _ => return Err(MC_ERR_INVALID_ARGUMENT),
+        };
+        let order_type = match cmd.order_type {
+            MC_ORDER_GTC => OrderType::Gtc,
+            MC_ORDER_IOC => OrderType::Ioc,
+            MC_ORDER_FOK => OrderType::Fok,
+            MC_ORDER_FOK_BUDGET => OrderType::FokBudget,
+            MC_ORDER_IOC_BUDGET => OrderType::IocBudget,
+            MC_ORDER_POST_ONLY => OrderType::PostOnly,
+            MC_ORDER_STOP_LIMIT => OrderType::StopLimit,
+            MC_ORDER_STOP_MARKET => OrderType::StopMarket,
+            MC_ORDER_ICEBERG => OrderType::Iceberg,
+            MC_ORDER_DAY => OrderType::Day,
+            MC_ORDER_GTD => OrderType::Gtd(cmd.expire_time),
+            MC_ORDER_MARKET => OrderType::Market,
+            MC_ORDER_GTX => OrderType::Gtx,
+            MC_ORDER_MARKET_TO_LIMIT => OrderType::MarketToLimit,
+            _ => return Err(MC_ERR_INVALID_ARGUMENT),
+        };
+        let optional = |value: i64| (value != 0).then_some(value);
+        Ok(OrderCommand {
+            command,
+            uid: cmd.uid,
+            order_id: cmd.order_id,
+            symbol: cmd.symbol,
+            price: cmd.price,
+            reserve_price: cmd.reserve_price,
+            size: cmd.size,
+            action,
+            order_type,
+            stop_price: optional(cmd.stop_price),
+            visible_size: optional(cmd.visible_size),
+            expire_time: optional(cmd.expire_time),
+            client_order_id: cmd.client_order_id,
+            service_flags: cmd.service_flags,
+            timestamp: cmd.timestamp,
+            ..Default::default()
+        })
+    }
+}
+
+fn ffi_action(action: Option<OrderAction>) -> i32 {
+    match action {
+        Some(OrderAction::Ask) => MC_ACTION_ASK,
+        Some(OrderAction::Bid) => MC_ACTION_BID,
+        None => MC_ACTION_NONE,
+    }
+}
+
+impl From<&MatcherTradeEvent> for McTradeEvent {
+    fn from(event: &MatcherTradeEvent) -> Self {
+        Self {
+            event_type: match event.event_type {
+                MatcherEventType::Trade => MC_EVENT_TRADE,
+                MatcherEventType::Reject => MC_EVENT_REJECT,
+                MatcherEventType::Reduce => MC_EVENT_REDUCE,
+                MatcherEventType::Amend => MC_EVENT_AMEND,
+            },
+            size: event.size,
+            price: event.price,
+            matched_order_id: event.matched_order_id,
+            matched_order_uid: event.matched_order_uid,
+            matched_client_order_id: event.matched_client_order_id,
+            maker_action: ffi_action(event.maker_action),
+            maker_completed: event.maker_completed,
+            taker_action: ffi_action(event.taker_action),
+            taker_order_id: event.taker_order_id,
+            taker_uid: event.taker_uid,
+            trade_id: event.trade_id,
+            sequence: event.sequence,
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+/// 默认引擎配置
+#[no_mangle]
+pub extern "C" fn mc_exchange_config_default() -> McExchangeConfig {
+    let config = ExchangeConfig::default();
+    McExchangeConfig {
+        ring_buffer_size: config.ring_buffer_size,
+        matching_engines_num: config.matching_engines_num,
+        risk_engines_num: config.risk_engines_num,
+    }
+}
+
+/// 创建引擎，config 为空时使用默认配置；返回的句柄需由 `mc_exchange_destroy` 释放
+///
+/// # Safety
+/// config 为空或指向有效的 `McExchangeConfig`
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_create(config: *const McExchangeConfig) -> *mut McExchange {
+    let mut exchange_config = ExchangeConfig::default();
+    if let Some(config) = config.as_ref() {
+        exchange_config.ring_buffer_size = config.ring_buffer_size;
+        exchange_config.matching_engines_num = config.matching_engines_num;
+        exchange_config.risk_engines_num = config.risk_engines_num;
+    }
+    Box::into_raw(Box::new(McExchange {
+        core: ExchangeCore::new(exchange_config),
+        pending: VecDeque::new(),
+    }))
+}
+
+/// 释放引擎（未取回的结果一并丢弃）
+///
+/// # Safety
+/// exchange 为空或由 `mc_exchange_create` 返回且未释放
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_destroy(exchange: *mut McExchange) {
+    if !exchange.is_null() {
+        drop(Box::from_raw(exchange));
+    }
+}
+
+/// 添加现货交易对，返回结果码（`MC_RESULT_SUCCESS` 表示成功）或负数错误码
+///
+/// # Safety
+/// exchange 由 `mc_exchange_create` 返回，spec 指向有效的 `McSymbolSpec`
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_add_symbol(exchange: *mut McExchange, spec: *const McSymbolSpec) -> i32 {
+    let (Some(exchange), Some(spec)) = (exchange.as_mut(), spec.as_ref()) else {
+        return MC_ERR_NULL_POINTER;
+    };
+    let spec = CoreSymbolSpecification {
+        symbol_id: spec.symbol_id,
+        base_currency: spec.base_currency,
+        quote_currency: spec.quote_currency,
+        base_scale_k: spec.base_scale_k,
+        quote_scale_k: spec.quote_scale_k,
+        taker_fee: spec.taker_fee,
+        maker_fee: spec.maker_fee,
+        tick_size: spec.tick_size,
+        lot_size: spec.lot_size,
+        min_size: spec.min_size,
+        max_size: spec.max_size,
+        ..Default::default()
+    };
+    exchange.core.add_symbol(spec) as i32
+}
+
+/// 启动流水线，之后命令异步处理
+///
+/// # Safety
+/// exchange 为空或由 `mc_exchange_create` 返回
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_startup(exchange: *mut McExchange) -> i32 {
+    let Some(exchange) = exchange.as_mut() else {
+        return MC_ERR_NULL_POINTER;
+    };
+    exchange.core.startup();
+    MC_OK
+}
+
+/// 提交命令，结果通过 `mc_exchange_poll_results` 按提交顺序取回
+///
+/// # Safety
+/// exchange 由 `mc_exchange_create` 返回，cmd 指向有效的 `McCommand`
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_submit(exchange: *mut McExchange, cmd: *const McCommand) -> i32 {
+    let (Some(exchange), Some(cmd)) = (exchange.as_mut(), cmd.as_ref()) else {
+        return MC_ERR_NULL_POINTER;
+    };
+    match OrderCommand::try_from(cmd) {
+        Ok(order_command) => {
+            let future = exchange.core.submit_command_async(order_command);
+            exchange.pending.push_back((cmd.command, future));
+            MC_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// 对已处理完成的命令依次调用回调（遇到未完成的命令即停止，保持提交顺序），返回回调次数；出错时返回负数错误码
+///
+/// # Safety
+/// exchange 由 `mc_exchange_create` 返回；回调中不能再调用该句柄的其它函数
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_poll_results(
+    exchange: *mut McExchange,
+    callback: Option<McResultCallback>,
+    user_data: *mut c_void,
+) -> i64 {
+    let (Some(exchange), Some(callback)) = (exchange.as_mut(), callback) else {
+        return MC_ERR_NULL_POINTER as i64;
+    };
+    let mut delivered = 0;
+    let mut events = Vec::new();
+    while exchange.pending.front().is_some_and(|(_, future)| future.is_ready()) {
+        let (command, future) = exchange.pending.pop_front().unwrap();
+        let cmd = future.wait();
+        events.clear();
+        events.extend(cmd.matcher_events.iter().map(McTradeEvent::from));
+        let result = McResult {
+            command,
+            result_code: cmd.result_code as i32,
+            uid: cmd.uid,
+            order_id: cmd.order_id,
+            symbol: cmd.symbol,
+            client_order_id: cmd.client_order_id,
+            sequence: cmd.sequence,
+            engine_timestamp: cmd.engine_timestamp,
+            events: events.as_ptr(),
+            events_len: events.len(),
+        };
+        callback(user_data, &result);
+        exchange.core.recycle_command(cmd);
+        delivered += 1;
+    }
+    delivered
+}
+
+/// 尚未取回结果的命令数
+///
+/// # Safety
+/// exchange 为空或由 `mc_exchange_create` 返回
+#[no_mangle]
+pub unsafe extern "C" fn mc_exchange_pending_results(exchange: *const McExchange) -> usize {
+    exchange.as_ref().map_or(0, |exchange| exchange.pending.len())
+}
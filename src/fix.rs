@@ -0,0 +1,676 @@
+//! FIX 4.4 消息转换
+//!
+//! 把 NewOrderSingle / OrderCancelRequest / OrderCancelReplaceRequest 转换为 OrderCommand，
+//! 并根据命令处理结果与撮合事件生成 ExecutionReport / OrderCancelReject。
+//! 只负责应用层消息，会话层（登录、心跳、序列号、重传）由接入方的 FIX 引擎处理。
+//!
+//! 价格与数量使用引擎的整数单位；Account(1) 为用户 ID。
+
+use crate::api::*;
+use ahash::AHashMap;
+use std::fmt::Write as _;
+
+pub const SOH: char = '\x01';
+pub const BEGIN_STRING: &str = "FIX.4.4";
+
+/// 使用到的 FIX 标签
+pub mod tags {
+    pub const ACCOUNT: u32 = 1;
+    pub const AVG_PX: u32 = 6;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECK_SUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const CUM_QTY: u32 = 14;
+    pub const EXEC_ID: u32 = 17;
+    pub const EXEC_INST: u32 = 18;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_TYPE: u32 = 35;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const PRICE: u32 = 44;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TEXT: u32 = 58;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const STOP_PX: u32 = 99;
+    pub const CXL_REJ_REASON: u32 = 102;
+    pub const MIN_QTY: u32 = 110;
+    pub const MAX_FLOOR: u32 = 111;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const REF_MSG_TYPE: u32 = 372;
+    pub const BUSINESS_REJECT_REASON: u32 = 380;
+    pub const CXL_REJ_RESPONSE_TO: u32 = 434;
+}
+
+/// FIX 消息类型（MsgType(35) 取值）
+pub mod msg_type {
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+    pub const ORDER_CANCEL_REPLACE_REQUEST: &str = "G";
+    pub const BUSINESS_MESSAGE_REJECT: &str = "j";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FixError {
+    #[error("消息格式错误: {0}")]
+    Malformed(String),
+    #[error("校验和不匹配: 期望 {expected:03}, 消息为 {actual}")]
+    ChecksumMismatch { expected: u8, actual: String },
+    #[error("缺少字段 {0}")]
+    MissingField(u32),
+    #[error("字段 {0} 取值无效: {1}")]
+    InvalidValue(u32, String),
+    #[error("不支持的消息类型: {0}")]
+    UnsupportedMsgType(String),
+    #[error("未知交易对: {0}")]
+    UnknownSymbol(String),
+    #[error("未知订单: {0}")]
+    UnknownOrder(String),
+    #[error("ClOrdID 重复: {0}")]
+    DuplicateClOrdId(String),
+    #[error("改单只能修改价格或减少数量之一")]
+    UnsupportedReplace,
+}
+
+/// FIX 消息：按出现顺序保存的 tag=value 字段
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        let mut msg = Self::default();
+        msg.push(tags::MSG_TYPE, msg_type);
+        msg
+    }
+
+    /// 解析一条消息，字段以 SOH 分隔（最后一个字段可省略结尾的 SOH）；带 CheckSum(10) 时校验
+    ///
+    /// 解析不受信任的网络输入：空字段（连续或开头的分隔符）与其他格式错误均返回 FixError，不会 panic
+    pub fn parse(raw: &str) -> Result<Self, FixError> {
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        for piece in raw.split_inclusive(SOH) {
+            let field = piece.strip_suffix(SOH).unwrap_or(piece);
+            if field.is_empty() {
+                return Err(FixError::Malformed(format!("第 {} 字节处为空字段", offset)));
+            }
+            let (tag, value) = field.split_once('=').ok_or_else(|| FixError::Malformed(field.to_string()))?;
+            let tag: u32 = tag.parse().map_err(|_| FixError::Malformed(field.to_string()))?;
+            if tag == tags::CHECK_SUM {
+                // 校验和覆盖 CheckSum 字段之前的全部字节（offset 始终位于 SOH 之后，是字符边界）
+                let expected = checksum(&raw[..offset]);
+                if value.parse::<u8>().ok() != Some(expected) {
+                    return Err(FixError::ChecksumMismatch { expected, actual: value.to_string() });
+                }
+                break;
+            }
+            offset += piece.len();
+            fields.push((tag, value.to_string()));
+        }
+        let msg = Self {
+            fields: fields.into_iter().filter(|(tag, _)| *tag != tags::BEGIN_STRING && *tag != tags::BODY_LENGTH).collect(),
+        };
+        msg.require(tags::MSG_TYPE)?;
+        Ok(msg)
+    }
+
+    /// 编码为带 BeginString、BodyLength 与 CheckSum 的完整消息
+    pub fn encode(&self) -> String {
+        let mut body = String::new();
+        for (tag, value) in &self.fields {
+            let _ = write!(body, "{}={}{}", tag, value, SOH);
+        }
+        let mut out = format!("8={}{}9={}{}{}", BEGIN_STRING, SOH, body.len(), SOH, body);
+        let sum = checksum(&out);
+        let _ = write!(out, "10={:03}{}", sum, SOH);
+        out
+    }
+
+    pub fn push(&mut self, tag: u32, value: impl ToString) -> &mut Self {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    /// 替换第一个 tag 字段的值，不存在时追加
+    pub fn set(&mut self, tag: u32, value: impl ToString) -> &mut Self {
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.fields.push((tag, value.to_string())),
+        }
+        self
+    }
+
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields.iter().find(|(t, _)| *t == tag).map(|(_, value)| value.as_str())
+    }
+
+    pub fn msg_type(&self) -> &str {
+        self.get(tags::MSG_TYPE).unwrap_or_default()
+    }
+
+    pub fn fields(&self) -> &[(u32, String)] {
+        &self.fields
+    }
+
+    fn require(&self, tag: u32) -> Result<&str, FixError> {
+        self.get(tag).ok_or(FixError::MissingField(tag))
+    }
+
+    fn parse_field<T: std::str::FromStr>(&self, tag: u32) -> Result<Option<T>, FixError> {
+        self.get(tag)
+            .map(|value| value.parse().map_err(|_| FixError::InvalidValue(tag, value.to_string())))
+            .transpose()
+    }
+
+    fn require_field<T: std::str::FromStr>(&self, tag: u32) -> Result<T, FixError> {
+        self.parse_field(tag)?.ok_or(FixError::MissingField(tag))
+    }
+}
+
+/// 字节和模 256
+fn checksum(data: &str) -> u8 {
+    data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b))
+}
+
+/// 撤单/改单请求在得到结果前的状态
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    cl_ord_id: String,
+    orig_cl_ord_id: String,
+    replace: bool,
+    price: Price, // 改单后的价格
+}
+
+/// 转换层跟踪的订单状态，用于计算 CumQty/LeavesQty/AvgPx
+#[derive(Debug, Clone)]
+struct FixOrder {
+    cl_ord_id: String,
+    uid: UserId,
+    symbol: SymbolId,
+    side: OrderAction,
+    price: Price,
+    order_qty: Size,
+    cum_qty: Size,
+    notional: i128, // Σ 成交价 × 成交量
+    pending: Option<PendingRequest>,
+}
+
+impl FixOrder {
+    fn leaves_qty(&self) -> Size {
+        self.order_qty - self.cum_qty
+    }
+
+    fn avg_px(&self) -> Price {
+        if self.cum_qty == 0 { 0 } else { (self.notional / self.cum_qty as i128) as Price }
+    }
+
+    fn fill_status(&self) -> &'static str {
+        match self.cum_qty {
+            0 => ORD_STATUS_NEW,
+            qty if qty >= self.order_qty => ORD_STATUS_FILLED,
+            _ => ORD_STATUS_PARTIALLY_FILLED,
+        }
+    }
+}
+
+const ORD_STATUS_NEW: &str = "0";
+const ORD_STATUS_PARTIALLY_FILLED: &str = "1";
+const ORD_STATUS_FILLED: &str = "2";
+const ORD_STATUS_CANCELED: &str = "4";
+const ORD_STATUS_REPLACED: &str = "5";
+const ORD_STATUS_REJECTED: &str = "8";
+
+const EXEC_TYPE_NEW: &str = "0";
+const EXEC_TYPE_CANCELED: &str = "4";
+const EXEC_TYPE_REPLACED: &str = "5";
+const EXEC_TYPE_REJECTED: &str = "8";
+const EXEC_TYPE_TRADE: &str = "F";
+
+/// FIX 消息与引擎命令的双向转换
+///
+/// 订单号由转换层分配（多个会话共用一个引擎时使用互不重叠的起始值）；
+/// 涉及本会话订单的命令结果（包括其他会话的成交）都需交给 `execution_reports`，订单状态才能保持一致
+pub struct FixTranslator {
+    symbols: AHashMap<String, SymbolId>,
+    symbol_names: AHashMap<SymbolId, String>,
+    orders: AHashMap<OrderId, FixOrder>,
+    cl_ord_ids: AHashMap<String, OrderId>, // 当前有效的 ClOrdID -> 订单号
+    next_order_id: OrderId,
+    next_exec_id: u64,
+}
+
+impl FixTranslator {
+    pub fn new(first_order_id: OrderId) -> Self {
+        Self {
+            symbols: AHashMap::new(),
+            symbol_names: AHashMap::new(),
+            orders: AHashMap::new(),
+            cl_ord_ids: AHashMap::new(),
+            next_order_id: first_order_id,
+            next_exec_id: 1,
+        }
+    }
+
+    /// 登记 Symbol(55) 名称；未登记的名称按数字交易对 ID 解析
+    pub fn add_symbol(&mut self, name: &str, symbol: SymbolId) {
+        self.symbols.insert(name.to_string(), symbol);
+        self.symbol_names.insert(symbol, name.to_string());
+    }
+
+    /// ClOrdID 对应的引擎订单号（订单完结后不再保留）
+    pub fn order_id(&self, cl_ord_id: &str) -> Option<OrderId> {
+        self.cl_ord_ids.get(cl_ord_id).copied()
+    }
+
+    /// 把业务消息转换为引擎命令，timestamp 为命令时间
+    pub fn to_command(&mut self, msg: &FixMessage, timestamp: i64) -> Result<OrderCommand, FixError> {
+        match msg.msg_type() {
+            msg_type::NEW_ORDER_SINGLE => self.new_order(msg, timestamp),
+            msg_type::ORDER_CANCEL_REQUEST => self.cancel_order(msg, timestamp),
+            msg_type::ORDER_CANCEL_REPLACE_REQUEST => self.replace_order(msg, timestamp),
+            other => Err(FixError::UnsupportedMsgType(other.to_string())),
+        }
+    }
+
+    fn new_order(&mut self, msg: &FixMessage, timestamp: i64) -> Result<OrderCommand, FixError> {
+        let cl_ord_id = msg.require(tags::CL_ORD_ID)?.to_string();
+        if self.cl_ord_ids.contains_key(&cl_ord_id) {
+            return Err(FixError::DuplicateClOrdId(cl_ord_id));
+        }
+        let uid: UserId = msg.require_field(tags::ACCOUNT)?;
+        let symbol = self.parse_symbol(msg)?;
+        let side = parse_side(msg)?;
+        let size: Size = msg.require_field(tags::ORDER_QTY)?;
+        let price: Price = msg.parse_field(tags::PRICE)?.unwrap_or(0);
+        let stop_price: Option<Price> = msg.parse_field(tags::STOP_PX)?;
+        let visible_size: Option<Size> = msg.parse_field(tags::MAX_FLOOR)?;
+        let min_fill_size: Option<Size> = msg.parse_field(tags::MIN_QTY)?;
+
+        let ord_type = msg.require(tags::ORD_TYPE)?;
+        let order_type = match ord_type {
+            "1" => OrderType::Market,
+            "2" if visible_size.is_some() => OrderType::Iceberg,
+            "2" => limit_order_type(msg)?,
+            "3" => OrderType::StopMarket,
+            "4" => OrderType::StopLimit,
+            "K" => OrderType::MarketToLimit,
+            other => return Err(FixError::InvalidValue(tags::ORD_TYPE, other.to_string())),
+        };
+        if matches!(ord_type, "2" | "4") && msg.get(tags::PRICE).is_none() {
+            return Err(FixError::MissingField(tags::PRICE));
+        }
+        if matches!(ord_type, "3" | "4") && stop_price.is_none() {
+            return Err(FixError::MissingField(tags::STOP_PX));
+        }
+
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        self.cl_ord_ids.insert(cl_ord_id.clone(), order_id);
+        self.orders.insert(
+            order_id,
+            FixOrder {
+                cl_ord_id: cl_ord_id.clone(),
+                uid,
+                symbol,
+                side,
+                price,
+                order_qty: size,
+                cum_qty: 0,
+                notional: 0,
+                pending: None,
+            },
+        );
+
+        Ok(OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id,
+            symbol,
+            price,
+            // 市价买单以 Price(44) 作为冻结资金的最高价
+            reserve_price: price,
+            size,
+            action: side,
+            order_type,
+            stop_price,
+            visible_size,
+            min_fill_size,
+            client_order_id: cl_ord_id.parse().unwrap_or(0),
+            timestamp,
+            ..Default::default()
+        })
+    }
+
+    fn cancel_order(&mut self, msg: &FixMessage, timestamp: i64) -> Result<OrderCommand, FixError> {
+        let (order_id, order) = self.pending_target(msg)?;
+        order.pending = Some(PendingRequest {
+            cl_ord_id: msg.require(tags::CL_ORD_ID)?.to_string(),
+            orig_cl_ord_id: order.cl_ord_id.clone(),
+            replace: false,
+            price: order.price,
+        });
+        Ok(OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            uid: order.uid,
+            order_id,
+            symbol: order.symbol,
+            timestamp,
+            ..Default::default()
+        })
+    }
+
+    /// 改单：修改价格对应 MoveOrder，减少 OrderQty（总数量）对应 ReduceOrder；不支持增加数量或同时修改两者
+    fn replace_order(&mut self, msg: &FixMessage, timestamp: i64) -> Result<OrderCommand, FixError> {
+        let cl_ord_id = msg.require(tags::CL_ORD_ID)?.to_string();
+        let price: Option<Price> = msg.parse_field(tags::PRICE)?;
+        let order_qty: Option<Size> = msg.parse_field(tags::ORDER_QTY)?;
+        let (order_id, order) = self.pending_target(msg)?;
+
+        let price = price.unwrap_or(order.price);
+        let order_qty = order_qty.unwrap_or(order.order_qty);
+        let mut cmd = OrderCommand {
+            uid: order.uid,
+            order_id,
+            symbol: order.symbol,
+            timestamp,
+            ..Default::default()
+        };
+        match (price != order.price, order_qty != order.order_qty) {
+            (true, false) => {
+                cmd.command = OrderCommandType::MoveOrder;
+                cmd.price = price;
+            }
+            (false, true) if order_qty < order.order_qty && order_qty > order.cum_qty => {
+                cmd.command = OrderCommandType::ReduceOrder;
+                cmd.size = order.order_qty - order_qty;
+            }
+            _ => return Err(FixError::UnsupportedReplace),
+        }
+        order.pending = Some(PendingRequest {
+            cl_ord_id,
+            orig_cl_ord_id: order.cl_ord_id.clone(),
+            replace: true,
+            price,
+        });
+        Ok(cmd)
+    }
+
+    /// 撤单/改单的目标订单（OrigClOrdID 须为当前有效的 ClOrdID，且没有未完成的撤单/改单）
+    fn pending_target(&mut self, msg: &FixMessage) -> Result<(OrderId, &mut FixOrder), FixError> {
+        let orig = msg.require(tags::ORIG_CL_ORD_ID)?;
+        let order_id = *self.cl_ord_ids.get(orig).ok_or_else(|| FixError::UnknownOrder(orig.to_string()))?;
+        let order = self.orders.get_mut(&order_id).ok_or_else(|| FixError::UnknownOrder(orig.to_string()))?;
+        if order.pending.is_some() {
+            return Err(FixError::InvalidValue(tags::ORIG_CL_ORD_ID, orig.to_string()));
+        }
+        Ok((order_id, order))
+    }
+
+    fn parse_symbol(&self, msg: &FixMessage) -> Result<SymbolId, FixError> {
+        let name = msg.require(tags::SYMBOL)?;
+        self.symbols
+            .get(name)
+            .copied()
+            .or_else(|| name.parse().ok())
+            .ok_or_else(|| FixError::UnknownSymbol(name.to_string()))
+    }
+
+    /// 根据处理完成的命令生成本会话订单的执行报告（撤单/改单失败时为 OrderCancelReject）
+    pub fn execution_reports(&mut self, cmd: &OrderCommand) -> Vec<FixMessage> {
+        let mut reports = Vec::new();
+        match cmd.command {
+            OrderCommandType::PlaceOrder if self.orders.contains_key(&cmd.order_id) => {
+                if cmd.result_code == CommandResultCode::Success {
+                    self.report(&mut reports, cmd.order_id, EXEC_TYPE_NEW, None);
+                } else {
+                    self.report(&mut reports, cmd.order_id, EXEC_TYPE_REJECTED, Some(format!("{:?}", cmd.result_code)));
+                    self.remove(cmd.order_id);
+                    return reports;
+                }
+            }
+            OrderCommandType::CancelOrder | OrderCommandType::MoveOrder | OrderCommandType::ReduceOrder => {
+                let Some(order) = self.orders.get_mut(&cmd.order_id) else {
+                    return reports;
+                };
+                if cmd.result_code != CommandResultCode::Success {
+                    if let Some(pending) = order.pending.take() {
+                        reports.push(self.cancel_reject(&pending, Some(cmd.order_id), format!("{:?}", cmd.result_code)));
+                    }
+                    return reports;
+                }
+                if cmd.command == OrderCommandType::MoveOrder {
+                    self.finish_replace(&mut reports, cmd.order_id);
+                }
+            }
+            _ => {}
+        }
+
+        for event in &cmd.matcher_events {
+            match event.event_type {
+                MatcherEventType::Trade => {
+                    let (_, taker_order_id, _) = event.taker(cmd);
+                    self.fill(&mut reports, taker_order_id, event);
+                    self.fill(&mut reports, event.matched_order_id, event);
+                }
+                MatcherEventType::Reject | MatcherEventType::Reduce => {
+                    let order_id = if event.maker_action.is_some() {
+                        event.matched_order_id
+                    } else {
+                        event.taker(cmd).1
+                    };
+                    self.reduce(&mut reports, order_id, event.size);
+                }
+                // 改单事件只由 AmendOrder 产生，FIX 会话的改单请求映射为 MoveOrder/ReduceOrder
+                MatcherEventType::Amend => {}
+            }
+        }
+        reports
+    }
+
+    fn fill(&mut self, reports: &mut Vec<FixMessage>, order_id: OrderId, event: &MatcherTradeEvent) {
+        let Some(order) = self.orders.get_mut(&order_id) else {
+            return;
+        };
+        order.cum_qty += event.size;
+        order.notional += event.price as i128 * event.size as i128;
+        let Some(mut report) = self.base_report(order_id, EXEC_TYPE_TRADE) else {
+            return;
+        };
+        report.push(tags::LAST_QTY, event.size).push(tags::LAST_PX, event.price);
+        reports.push(report);
+        self.remove_if_done(order_id);
+    }
+
+    /// 撤销/减少 size 数量：减量改单完成时报告 Replaced，其余情况报告 Canceled
+    fn reduce(&mut self, reports: &mut Vec<FixMessage>, order_id: OrderId, size: Size) {
+        let Some(order) = self.orders.get_mut(&order_id) else {
+            return;
+        };
+        order.order_qty -= size.min(order.leaves_qty());
+        let replaced = order.leaves_qty() > 0 && order.pending.as_ref().is_some_and(|pending| pending.replace);
+        if replaced {
+            self.finish_replace(reports, order_id);
+            return;
+        }
+        // 撤单请求的应答使用撤单的 ClOrdID，引擎主动撤销（IOC 剩余、STP、过期等）使用订单的 ClOrdID
+        let pending = order.pending.take();
+        let Some(mut report) = self.base_report(order_id, EXEC_TYPE_CANCELED) else {
+            return;
+        };
+        if let Some(pending) = pending {
+            report.set(tags::CL_ORD_ID, pending.cl_ord_id).push(tags::ORIG_CL_ORD_ID, pending.orig_cl_ord_id);
+        }
+        reports.push(report);
+        self.remove_if_done(order_id);
+    }
+
+    /// 改单成功：切换到新的 ClOrdID 并报告 Replaced
+    fn finish_replace(&mut self, reports: &mut Vec<FixMessage>, order_id: OrderId) {
+        let Some(order) = self.orders.get_mut(&order_id) else {
+            return;
+        };
+        let Some(pending) = order.pending.take() else {
+            return;
+        };
+        self.cl_ord_ids.remove(&order.cl_ord_id);
+        order.cl_ord_id = pending.cl_ord_id.clone();
+        order.price = pending.price;
+        self.cl_ord_ids.insert(pending.cl_ord_id, order_id);
+        if let Some(mut report) = self.base_report(order_id, EXEC_TYPE_REPLACED) {
+            report.push(tags::ORIG_CL_ORD_ID, pending.orig_cl_ord_id);
+            reports.push(report);
+        }
+    }
+
+    fn report(&mut self, reports: &mut Vec<FixMessage>, order_id: OrderId, exec_type: &str, text: Option<String>) {
+        let Some(mut report) = self.base_report(order_id, exec_type) else {
+            return;
+        };
+        if let Some(text) = text {
+            report.push(tags::TEXT, text);
+        }
+        reports.push(report);
+    }
+
+    /// 本会话订单的执行报告（订单不在本会话时为 None，不生成报告）
+    fn base_report(&mut self, order_id: OrderId, exec_type: &str) -> Option<FixMessage> {
+        let order = self.orders.get(&order_id)?;
+        let (ord_status, leaves_qty) = match exec_type {
+            EXEC_TYPE_REJECTED => (ORD_STATUS_REJECTED, 0),
+            EXEC_TYPE_CANCELED if order.leaves_qty() <= 0 => (ORD_STATUS_CANCELED, 0),
+            EXEC_TYPE_REPLACED if order.cum_qty == 0 => (ORD_STATUS_REPLACED, order.leaves_qty()),
+            _ => (order.fill_status(), order.leaves_qty()),
+        };
+        let symbol = self.symbol_names.get(&order.symbol).cloned().unwrap_or_else(|| order.symbol.to_string());
+
+        let mut report = FixMessage::new(msg_type::EXECUTION_REPORT);
+        report
+            .push(tags::ORDER_ID, order_id)
+            .push(tags::CL_ORD_ID, &order.cl_ord_id)
+            .push(tags::EXEC_ID, self.next_exec_id)
+            .push(tags::EXEC_TYPE, exec_type)
+            .push(tags::ORD_STATUS, ord_status)
+            .push(tags::ACCOUNT, order.uid)
+            .push(tags::SYMBOL, symbol)
+            .push(tags::SIDE, side_code(order.side))
+            .push(tags::ORDER_QTY, order.order_qty)
+            .push(tags::PRICE, order.price)
+            .push(tags::CUM_QTY, order.cum_qty)
+            .push(tags::LEAVES_QTY, leaves_qty)
+            .push(tags::AVG_PX, order.avg_px());
+        self.next_exec_id += 1;
+        Some(report)
+    }
+
+    fn cancel_reject(&self, pending: &PendingRequest, order_id: Option<OrderId>, text: String) -> FixMessage {
+        let ord_status = order_id
+            .and_then(|id| self.orders.get(&id))
+            .map_or(ORD_STATUS_REJECTED, FixOrder::fill_status);
+        let mut reject = FixMessage::new(msg_type::ORDER_CANCEL_REJECT);
+        reject
+            .push(tags::ORDER_ID, order_id.map_or_else(|| "NONE".to_string(), |id| id.to_string()))
+            .push(tags::CL_ORD_ID, &pending.cl_ord_id)
+            .push(tags::ORIG_CL_ORD_ID, &pending.orig_cl_ord_id)
+            .push(tags::ORD_STATUS, ord_status)
+            .push(tags::CXL_REJ_RESPONSE_TO, if pending.replace { "2" } else { "1" })
+            .push(tags::TEXT, text);
+        reject
+    }
+
+    /// 无法转换的消息对应的拒绝：新订单为 Rejected 执行报告，撤单/改单为 OrderCancelReject，其余为 BusinessMessageReject
+    pub fn reject(&self, msg: &FixMessage, error: &FixError) -> FixMessage {
+        let cl_ord_id = msg.get(tags::CL_ORD_ID).unwrap_or("NONE").to_string();
+        match msg.msg_type() {
+            msg_type::NEW_ORDER_SINGLE => {
+                let mut report = FixMessage::new(msg_type::EXECUTION_REPORT);
+                report
+                    .push(tags::ORDER_ID, "NONE")
+                    .push(tags::CL_ORD_ID, cl_ord_id)
+                    .push(tags::EXEC_ID, 0)
+                    .push(tags::EXEC_TYPE, EXEC_TYPE_REJECTED)
+                    .push(tags::ORD_STATUS, ORD_STATUS_REJECTED)
+                    .push(tags::SYMBOL, msg.get(tags::SYMBOL).unwrap_or_default())
+                    .push(tags::SIDE, msg.get(tags::SIDE).unwrap_or_default())
+                    .push(tags::CUM_QTY, 0)
+                    .push(tags::LEAVES_QTY, 0)
+                    .push(tags::AVG_PX, 0)
+                    .push(tags::TEXT, error);
+                report
+            }
+            msg_type::ORDER_CANCEL_REQUEST | msg_type::ORDER_CANCEL_REPLACE_REQUEST => {
+                let orig_cl_ord_id = msg.get(tags::ORIG_CL_ORD_ID).unwrap_or_default();
+                let pending = PendingRequest {
+                    cl_ord_id,
+                    orig_cl_ord_id: orig_cl_ord_id.to_string(),
+                    replace: msg.msg_type() == msg_type::ORDER_CANCEL_REPLACE_REQUEST,
+                    price: 0,
+                };
+                let mut reject = self.cancel_reject(&pending, self.order_id(orig_cl_ord_id), error.to_string());
+                if matches!(error, FixError::UnknownOrder(_)) {
+                    reject.push(tags::CXL_REJ_REASON, 1);
+                }
+                reject
+            }
+            other => {
+                let mut reject = FixMessage::new(msg_type::BUSINESS_MESSAGE_REJECT);
+                reject
+                    .push(tags::REF_MSG_TYPE, other)
+                    .push(tags::BUSINESS_REJECT_REASON, 3)
+                    .push(tags::TEXT, error);
+                reject
+            }
+        }
+    }
+
+    fn remove(&mut self, order_id: OrderId) {
+        if let Some(order) = self.orders.remove(&order_id) {
+            self.cl_ord_ids.remove(&order.cl_ord_id);
+        }
+    }
+
+    /// 订单已无剩余数量时不再跟踪
+    fn remove_if_done(&mut self, order_id: OrderId) {
+        if self.orders.get(&order_id).is_some_and(|order| order.leaves_qty() <= 0) {
+            self.remove(order_id);
+        }
+    }
+}
+
+fn parse_side(msg: &FixMessage) -> Result<OrderAction, FixError> {
+    match msg.require(tags::SIDE)? {
+        "1" => Ok(OrderAction::Bid),
+        "2" => Ok(OrderAction::Ask),
+        other => Err(FixError::InvalidValue(tags::SIDE, other.to_string())),
+    }
+}
+
+fn side_code(side: OrderAction) -> &'static str {
+    match side {
+        OrderAction::Bid => "1",
+        OrderAction::Ask => "2",
+    }
+}
+
+/// 限价单按 TimeInForce(59) 与 ExecInst(18) 选择订单类型（ExecInst 含 6 时为只做 maker）
+fn limit_order_type(msg: &FixMessage) -> Result<OrderType, FixError> {
+    if msg.get(tags::EXEC_INST).is_some_and(|inst| inst.split(' ').any(|i| i == "6")) {
+        return Ok(OrderType::PostOnly);
+    }
+    match msg.get(tags::TIME_IN_FORCE).unwrap_or("1") {
+        "0" => Ok(OrderType::Day),
+        "1" => Ok(OrderType::Gtc),
+        "3" => Ok(OrderType::Ioc),
+        "4" => Ok(OrderType::Fok),
+        other => Err(FixError::InvalidValue(tags::TIME_IN_FORCE, other.to_string())),
+    }
+}
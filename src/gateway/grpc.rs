@@ -0,0 +1,254 @@
+use super::proto;
+use super::*;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+pub use proto::exchange_gateway_client::ExchangeGatewayClient;
+pub use proto::exchange_gateway_server::{ExchangeGateway, ExchangeGatewayServer};
+
+/// 单个行情订阅在 gRPC 流中缓冲的事件数，客户端读取过慢时撮合侧的分发不受影响，由转发线程等待
+const SUBSCRIPTION_BUFFER: usize = 1024;
+
+/// ExchangeGateway gRPC 服务：把请求转换后交给 `GatewayService`
+///
+/// 命令处理会阻塞等待结果，在 tokio 的阻塞线程池中执行
+#[derive(Clone)]
+pub struct GrpcGateway {
+    service: Arc<GatewayService>,
+}
+
+impl GrpcGateway {
+    pub fn new(service: Arc<GatewayService>) -> Self {
+        Self { service }
+    }
+
+    pub fn into_server(self) -> ExchangeGatewayServer<Self> {
+        ExchangeGatewayServer::new(self)
+    }
+
+    /// 在已绑定的监听端口上提供服务，直到出错
+    pub async fn serve(self, listener: TcpListener) -> Result<(), tonic::transport::Error> {
+        tonic::transport::Server::builder()
+            .add_service(self.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+    }
+
+    async fn call<T, F>(&self, f: F) -> Result<Response<T>, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&GatewayService) -> T + Send + 'static,
+    {
+        let service = self.service.clone();
+        tokio::task::spawn_blocking(move || f(&service))
+            .await
+            .map(Response::new)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl ExchangeGateway for GrpcGateway {
+    async fn place_order(&self, request: Request<proto::PlaceOrderRequest>) -> Result<Response<proto::CommandReply>, Status> {
+        let req = PlaceOrderRequest::try_from(request.into_inner())?;
+        self.call(move |service| service.place_order(req).into()).await
+    }
+
+    async fn cancel_order(&self, request: Request<proto::CancelOrderRequest>) -> Result<Response<proto::CommandReply>, Status> {
+        let req = request.into_inner();
+        let req = CancelOrderRequest { uid: req.uid, order_id: req.order_id, symbol: req.symbol, timestamp: req.timestamp };
+        self.call(move |service| service.cancel_order(req).into()).await
+    }
+
+    async fn move_order(&self, request: Request<proto::MoveOrderRequest>) -> Result<Response<proto::CommandReply>, Status> {
+        let req = request.into_inner();
+        let req = MoveOrderRequest {
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            new_price: req.new_price,
+            timestamp: req.timestamp,
+        };
+        self.call(move |service| service.move_order(req).into()).await
+    }
+
+    async fn reduce_order(&self, request: Request<proto::ReduceOrderRequest>) -> Result<Response<proto::CommandReply>, Status> {
+        let req = request.into_inner();
+        let req = ReduceOrderRequest {
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            reduce_size: req.reduce_size,
+            timestamp: req.timestamp,
+        };
+        self.call(move |service| service.reduce_order(req).into()).await
+    }
+
+    async fn adjust_balance(&self, request: Request<proto::BalanceAdjustmentRequest>) -> Result<Response<proto::CommandReply>, Status> {
+        let req = request.into_inner();
+        let req = BalanceAdjustmentRequest {
+            uid: req.uid,
+            currency: req.currency,
+            amount: req.amount,
+            transaction_id: req.transaction_id,
+            timestamp: req.timestamp,
+        };
+        self.call(move |service| service.adjust_balance(req).into()).await
+    }
+
+    async fn get_order_book(&self, request: Request<proto::OrderBookRequest>) -> Result<Response<proto::OrderBookReply>, Status> {
+        let req = request.into_inner();
+        let depth = usize::try_from(req.depth).map_err(|_| Status::invalid_argument("depth 不能为负"))?;
+        let req = OrderBookRequest { symbol: req.symbol, depth };
+        self.call(move |service| service.order_book(req).into()).await
+    }
+
+    type SubscribeMarketDataStream = ReceiverStream<Result<proto::MarketDataEvent, Status>>;
+
+    async fn subscribe_market_data(
+        &self,
+        request: Request<proto::MarketDataSubscription>,
+    ) -> Result<Response<Self::SubscribeMarketDataStream>, Status> {
+        let receiver = self.service.subscribe(&request.into_inner().symbols);
+        let (sender, stream) = tokio::sync::mpsc::channel(SUBSCRIPTION_BUFFER);
+        // 独立线程转发：客户端断开后在下一条行情时退出，服务释放时通道关闭退出
+        std::thread::Builder::new()
+            .name("gateway-market-data".into())
+            .spawn(move || {
+                while let Ok(event) = receiver.recv() {
+                    if sender.blocking_send(Ok(proto::MarketDataEvent::from(&event))).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+}
+
+impl TryFrom<proto::PlaceOrderRequest> for PlaceOrderRequest {
+    type Error = Status;
+
+    fn try_from(req: proto::PlaceOrderRequest) -> Result<Self, Status> {
+        let action = match proto::OrderAction::try_from(req.action) {
+            Ok(proto::OrderAction::Ask) => OrderAction::Ask,
+            Ok(proto::OrderAction::Bid) => OrderAction::Bid,
+            Err(_) => return Err(Status::invalid_argument(format!("无效的 action: {}", req.action))),
+        };
+        let order_type = match proto::OrderType::try_from(req.order_type) {
+            Ok(proto::OrderType::Gtc) => OrderType::Gtc,
+            Ok(proto::OrderType::Ioc) => OrderType::Ioc,
+            Ok(proto::OrderType::Fok) => OrderType::Fok,
+            Ok(proto::OrderType::FokBudget) => OrderType::FokBudget,
+            Ok(proto::OrderType::IocBudget) => OrderType::IocBudget,
+            Ok(proto::OrderType::PostOnly) => OrderType::PostOnly,
+            Err(_) => return Err(Status::invalid_argument(format!("无效的 order_type: {}", req.order_type))),
+        };
+        Ok(Self {
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            price: req.price,
+            reserve_price: req.reserve_price,
+            size: req.size,
+            action,
+            order_type,
+            client_order_id: req.client_order_id,
+            timestamp: req.timestamp,
+            assign_order_id: req.assign_order_id,
+        })
+    }
+}
+
+fn proto_action(action: OrderAction) -> i32 {
+    match action {
+        OrderAction::Ask => proto::OrderAction::Ask as i32,
+        OrderAction::Bid => proto::OrderAction::Bid as i32,
+    }
+}
+
+impl From<CommandReply> for proto::CommandReply {
+    fn from(reply: CommandReply) -> Self {
+        Self {
+            result_code: format!("{:?}", reply.result_code),
+            sequence: reply.sequence,
+            events: reply
+                .events
+                .iter()
+                .map(|event| proto::TradeEvent {
+                    event_type: format!("{:?}", event.event_type),
+                    size: event.size,
+                    price: event.price,
+                    matched_order_id: event.matched_order_id,
+                    matched_order_uid: event.matched_order_uid,
+                    trade_id: event.trade_id,
+                })
+                .collect(),
+            order_id: reply.order_id,
+        }
+    }
+}
+
+impl From<OrderBookReply> for proto::OrderBookReply {
+    fn from(reply: OrderBookReply) -> Self {
+        let book = reply.book.unwrap_or_else(|| L2MarketData::new(0));
+        Self {
+            result_code: format!("{:?}", reply.result_code),
+            ask_prices: book.ask_prices,
+            ask_volumes: book.ask_volumes,
+            bid_prices: book.bid_prices,
+            bid_volumes: book.bid_volumes,
+        }
+    }
+}
+
+impl From<&MarketDataEvent> for proto::MarketDataEvent {
+    fn from(event: &MarketDataEvent) -> Self {
+        use proto::market_data_event::Event;
+        let event = match event {
+            MarketDataEvent::Trade(tick) => Event::Trade(proto::TradeTick {
+                symbol: tick.symbol,
+                price: tick.price,
+                size: tick.size,
+                taker_action: proto_action(tick.taker_action),
+                taker_order_id: tick.taker_order_id,
+                taker_uid: tick.taker_uid,
+                maker_order_id: tick.maker_order_id,
+                maker_uid: tick.maker_uid,
+                trade_id: tick.trade_id,
+                sequence: tick.sequence,
+                timestamp: tick.timestamp,
+            }),
+            MarketDataEvent::Bbo(bbo) => Event::Bbo(proto::BboUpdate {
+                symbol: bbo.symbol,
+                bid: bbo.bid,
+                bid_size: bbo.bid_size,
+                ask: bbo.ask,
+                ask_size: bbo.ask_size,
+                seq: bbo.seq,
+            }),
+            MarketDataEvent::Implied(quote) => Event::Implied(proto::BboUpdate {
+                symbol: quote.symbol,
+                bid: quote.bid,
+                bid_size: quote.bid_size,
+                ask: quote.ask,
+                ask_size: quote.ask_size,
+                seq: quote.seq,
+            }),
+            MarketDataEvent::L2(delta) => Event::L2(proto::L2Delta {
+                seq: delta.seq,
+                symbol: delta.symbol,
+                action: proto_action(delta.action),
+                kind: match delta.kind {
+                    L2DeltaKind::Add => proto::L2DeltaKind::Add as i32,
+                    L2DeltaKind::Update => proto::L2DeltaKind::Update as i32,
+                    L2DeltaKind::Remove => proto::L2DeltaKind::Remove as i32,
+                },
+                price: delta.price,
+                volume: delta.volume,
+            }),
+        };
+        Self { event: Some(event) }
+    }
+}
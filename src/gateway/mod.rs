@@ -0,0 +1,281 @@
+//! 网关服务（feature = "gateway"）
+//!
+//! `GatewayService` 负责请求与 OrderCommand 的映射、等待处理结果与行情订阅，与传输层无关；
+//! `grpc` 模块按 proto/exchange.proto 提供 tonic gRPC 服务
+
+pub mod grpc;
+pub mod proto;
+
+pub use grpc::*;
+
+use crate::api::*;
+use crate::core::exchange::ExchangeCore;
+use crate::core::market_data::MarketDataConsumer;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// 下单请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceOrderRequest {
+    pub uid: UserId,
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub price: Price,
+    pub reserve_price: Price, // 买单预留价格（0 时同 price）
+    pub size: Size,
+    pub action: OrderAction,
+    pub order_type: OrderType,
+    pub client_order_id: u64,
+    pub timestamp: i64,
+    pub assign_order_id: bool, // 由引擎分配订单号（忽略 order_id），分配结果见应答的 order_id
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CancelOrderRequest {
+    pub uid: UserId,
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveOrderRequest {
+    pub uid: UserId,
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub new_price: Price,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReduceOrderRequest {
+    pub uid: UserId,
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub reduce_size: Size,
+    pub timestamp: i64,
+}
+
+/// 入金/出金请求（amount 为负表示出金，transaction_id 为幂等交易号）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceAdjustmentRequest {
+    pub uid: UserId,
+    pub currency: Currency,
+    pub amount: i64,
+    pub transaction_id: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderBookRequest {
+    pub symbol: SymbolId,
+    pub depth: usize,
+}
+
+impl From<PlaceOrderRequest> for OrderCommand {
+    fn from(req: PlaceOrderRequest) -> Self {
+        OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            price: req.price,
+            reserve_price: if req.reserve_price == 0 { req.price } else { req.reserve_price },
+            size: req.size,
+            action: req.action,
+            order_type: req.order_type,
+            client_order_id: req.client_order_id,
+            timestamp: req.timestamp,
+            assign_order_id: req.assign_order_id,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<CancelOrderRequest> for OrderCommand {
+    fn from(req: CancelOrderRequest) -> Self {
+        OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            timestamp: req.timestamp,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<MoveOrderRequest> for OrderCommand {
+    fn from(req: MoveOrderRequest) -> Self {
+        OrderCommand {
+            command: OrderCommandType::MoveOrder,
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            price: req.new_price,
+            timestamp: req.timestamp,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ReduceOrderRequest> for OrderCommand {
+    fn from(req: ReduceOrderRequest) -> Self {
+        OrderCommand {
+            command: OrderCommandType::ReduceOrder,
+            uid: req.uid,
+            order_id: req.order_id,
+            symbol: req.symbol,
+            size: req.reduce_size,
+            timestamp: req.timestamp,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<BalanceAdjustmentRequest> for OrderCommand {
+    fn from(req: BalanceAdjustmentRequest) -> Self {
+        OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid: req.uid,
+            symbol: req.currency,
+            price: req.amount,
+            order_id: req.transaction_id,
+            timestamp: req.timestamp,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<OrderBookRequest> for OrderCommand {
+    fn from(req: OrderBookRequest) -> Self {
+        OrderCommand {
+            command: OrderCommandType::OrderBookRequest,
+            symbol: req.symbol,
+            size: req.depth as Size,
+            ..Default::default()
+        }
+    }
+}
+
+/// 命令应答
+#[derive(Debug, Clone)]
+pub struct CommandReply {
+    pub result_code: CommandResultCode,
+    pub sequence: u64,
+    pub order_id: OrderId, // 命令的订单号（下单时为引擎分配的订单号）
+    pub events: Vec<MatcherTradeEvent>,
+}
+
+impl From<&OrderCommand> for CommandReply {
+    fn from(cmd: &OrderCommand) -> Self {
+        Self {
+            result_code: cmd.result_code,
+            sequence: cmd.sequence,
+            order_id: cmd.order_id,
+            events: cmd.matcher_events.clone(),
+        }
+    }
+}
+
+/// 订单簿应答（查询失败时 book 为 None）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBookReply {
+    pub result_code: CommandResultCode,
+    pub book: Option<L2MarketData>,
+}
+
+struct Subscriber {
+    symbols: Vec<SymbolId>, // 为空时订阅全部交易对
+    sender: Sender<MarketDataEvent>,
+}
+
+/// 行情分发：撮合线程回调中按交易对分发给订阅者，接收端释放后自动移除
+#[derive(Default)]
+struct MarketDataFanout {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl MarketDataFanout {
+    fn publish(&self, event: &MarketDataEvent) {
+        let symbol = event.symbol();
+        self.subscribers.lock().unwrap().retain(|sub| {
+            if !sub.symbols.is_empty() && !sub.symbols.contains(&symbol) {
+                return true;
+            }
+            sub.sender.send(event.clone()).is_ok()
+        });
+    }
+}
+
+/// 网关服务：串行提交命令并等待处理结果，行情以通道推送给订阅者（对应 proto 的流式 RPC）
+pub struct GatewayService {
+    core: Mutex<ExchangeCore>,
+    market_data: Arc<MarketDataFanout>,
+}
+
+impl GatewayService {
+    /// core 须尚未启动：行情分发在启动前注册，随后启动流水线
+    pub fn new(mut core: ExchangeCore) -> Self {
+        let market_data = Arc::new(MarketDataFanout::default());
+        let fanout = market_data.clone();
+        let consumer: MarketDataConsumer = Arc::new(move |event| fanout.publish(event));
+        core.add_market_data_consumer(consumer);
+        core.startup();
+        Self { core: Mutex::new(core), market_data }
+    }
+
+    /// 提交命令并等待结果
+    pub fn execute(&self, cmd: OrderCommand) -> OrderCommand {
+        let future = self.core.lock().unwrap().submit_command_async(cmd);
+        future.wait()
+    }
+
+    pub fn place_order(&self, req: PlaceOrderRequest) -> CommandReply {
+        CommandReply::from(&self.execute(req.into()))
+    }
+
+    pub fn cancel_order(&self, req: CancelOrderRequest) -> CommandReply {
+        CommandReply::from(&self.execute(req.into()))
+    }
+
+    pub fn move_order(&self, req: MoveOrderRequest) -> CommandReply {
+        CommandReply::from(&self.execute(req.into()))
+    }
+
+    pub fn reduce_order(&self, req: ReduceOrderRequest) -> CommandReply {
+        CommandReply::from(&self.execute(req.into()))
+    }
+
+    pub fn adjust_balance(&self, req: BalanceAdjustmentRequest) -> CommandReply {
+        CommandReply::from(&self.execute(req.into()))
+    }
+
+    pub fn order_book(&self, req: OrderBookRequest) -> OrderBookReply {
+        let mut result = self.execute(req.into());
+        OrderBookReply {
+            result_code: result.result_code,
+            book: result.market_data.take(),
+        }
+    }
+
+    /// 订阅行情，symbols 为空时订阅全部交易对；只推送订阅之后产生的事件
+    pub fn subscribe(&self, symbols: &[SymbolId]) -> Receiver<MarketDataEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.market_data.subscribers.lock().unwrap().push(Subscriber {
+            symbols: symbols.to_vec(),
+            sender,
+        });
+        receiver
+    }
+
+    /// 当前订阅者数量（已断开的订阅者在下一条行情分发时移除）
+    pub fn subscriber_count(&self) -> usize {
+        self.market_data.subscribers.lock().unwrap().len()
+    }
+
+    /// 直接访问 ExchangeCore（添加交易对、快照等管理操作）
+    pub fn with_core<R>(&self, f: impl FnOnce(&mut ExchangeCore) -> R) -> R {
+        f(&mut self.core.lock().unwrap())
+    }
+}
@@ -0,0 +1,244 @@
+//! proto/exchange.proto 的消息类型（与 .proto 文件保持一致，字段标签不可复用）
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum OrderAction {
+    Ask = 0,
+    Bid = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum OrderType {
+    Gtc = 0,
+    Ioc = 1,
+    Fok = 2,
+    FokBudget = 3,
+    IocBudget = 4,
+    PostOnly = 5,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum L2DeltaKind {
+    Add = 0,
+    Update = 1,
+    Remove = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PlaceOrderRequest {
+    #[prost(uint64, tag = "1")]
+    pub uid: u64,
+    #[prost(uint64, tag = "2")]
+    pub order_id: u64,
+    #[prost(int32, tag = "3")]
+    pub symbol: i32,
+    #[prost(int64, tag = "4")]
+    pub price: i64,
+    #[prost(int64, tag = "5")]
+    pub reserve_price: i64,
+    #[prost(int64, tag = "6")]
+    pub size: i64,
+    #[prost(enumeration = "OrderAction", tag = "7")]
+    pub action: i32,
+    #[prost(enumeration = "OrderType", tag = "8")]
+    pub order_type: i32,
+    #[prost(uint64, tag = "9")]
+    pub client_order_id: u64,
+    #[prost(int64, tag = "10")]
+    pub timestamp: i64,
+    #[prost(bool, tag = "11")]
+    pub assign_order_id: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CancelOrderRequest {
+    #[prost(uint64, tag = "1")]
+    pub uid: u64,
+    #[prost(uint64, tag = "2")]
+    pub order_id: u64,
+    #[prost(int32, tag = "3")]
+    pub symbol: i32,
+    #[prost(int64, tag = "4")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MoveOrderRequest {
+    #[prost(uint64, tag = "1")]
+    pub uid: u64,
+    #[prost(uint64, tag = "2")]
+    pub order_id: u64,
+    #[prost(int32, tag = "3")]
+    pub symbol: i32,
+    #[prost(int64, tag = "4")]
+    pub new_price: i64,
+    #[prost(int64, tag = "5")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ReduceOrderRequest {
+    #[prost(uint64, tag = "1")]
+    pub uid: u64,
+    #[prost(uint64, tag = "2")]
+    pub order_id: u64,
+    #[prost(int32, tag = "3")]
+    pub symbol: i32,
+    #[prost(int64, tag = "4")]
+    pub reduce_size: i64,
+    #[prost(int64, tag = "5")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BalanceAdjustmentRequest {
+    #[prost(uint64, tag = "1")]
+    pub uid: u64,
+    #[prost(int32, tag = "2")]
+    pub currency: i32,
+    #[prost(int64, tag = "3")]
+    pub amount: i64,
+    #[prost(uint64, tag = "4")]
+    pub transaction_id: u64,
+    #[prost(int64, tag = "5")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TradeEvent {
+    #[prost(string, tag = "1")]
+    pub event_type: String,
+    #[prost(int64, tag = "2")]
+    pub size: i64,
+    #[prost(int64, tag = "3")]
+    pub price: i64,
+    #[prost(uint64, tag = "4")]
+    pub matched_order_id: u64,
+    #[prost(uint64, tag = "5")]
+    pub matched_order_uid: u64,
+    #[prost(uint64, tag = "6")]
+    pub trade_id: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CommandReply {
+    #[prost(string, tag = "1")]
+    pub result_code: String,
+    #[prost(uint64, tag = "2")]
+    pub sequence: u64,
+    #[prost(message, repeated, tag = "3")]
+    pub events: Vec<TradeEvent>,
+    #[prost(uint64, tag = "4")]
+    pub order_id: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderBookRequest {
+    #[prost(int32, tag = "1")]
+    pub symbol: i32,
+    #[prost(int32, tag = "2")]
+    pub depth: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OrderBookReply {
+    #[prost(string, tag = "1")]
+    pub result_code: String,
+    #[prost(int64, repeated, tag = "2")]
+    pub ask_prices: Vec<i64>,
+    #[prost(int64, repeated, tag = "3")]
+    pub ask_volumes: Vec<i64>,
+    #[prost(int64, repeated, tag = "4")]
+    pub bid_prices: Vec<i64>,
+    #[prost(int64, repeated, tag = "5")]
+    pub bid_volumes: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MarketDataSubscription {
+    #[prost(int32, repeated, tag = "1")]
+    pub symbols: Vec<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TradeTick {
+    #[prost(int32, tag = "1")]
+    pub symbol: i32,
+    #[prost(int64, tag = "2")]
+    pub price: i64,
+    #[prost(int64, tag = "3")]
+    pub size: i64,
+    #[prost(enumeration = "OrderAction", tag = "4")]
+    pub taker_action: i32,
+    #[prost(uint64, tag = "5")]
+    pub taker_order_id: u64,
+    #[prost(uint64, tag = "6")]
+    pub taker_uid: u64,
+    #[prost(uint64, tag = "7")]
+    pub maker_order_id: u64,
+    #[prost(uint64, tag = "8")]
+    pub maker_uid: u64,
+    #[prost(uint64, tag = "9")]
+    pub trade_id: u64,
+    #[prost(uint64, tag = "10")]
+    pub sequence: u64,
+    #[prost(int64, tag = "11")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BboUpdate {
+    #[prost(int32, tag = "1")]
+    pub symbol: i32,
+    #[prost(int64, optional, tag = "2")]
+    pub bid: Option<i64>,
+    #[prost(int64, tag = "3")]
+    pub bid_size: i64,
+    #[prost(int64, optional, tag = "4")]
+    pub ask: Option<i64>,
+    #[prost(int64, tag = "5")]
+    pub ask_size: i64,
+    #[prost(uint64, tag = "6")]
+    pub seq: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct L2Delta {
+    #[prost(uint64, tag = "1")]
+    pub seq: u64,
+    #[prost(int32, tag = "2")]
+    pub symbol: i32,
+    #[prost(enumeration = "OrderAction", tag = "3")]
+    pub action: i32,
+    #[prost(enumeration = "L2DeltaKind", tag = "4")]
+    pub kind: i32,
+    #[prost(int64, tag = "5")]
+    pub price: i64,
+    #[prost(int64, tag = "6")]
+    pub volume: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MarketDataEvent {
+    #[prost(oneof = "market_data_event::Event", tags = "1, 2, 3, 4")]
+    pub event: Option<market_data_event::Event>,
+}
+
+pub mod market_data_event {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Event {
+        #[prost(message, tag = "1")]
+        Trade(super::TradeTick),
+        #[prost(message, tag = "2")]
+        Bbo(super::BboUpdate),
+        #[prost(message, tag = "3")]
+        L2(super::L2Delta),
+        #[prost(message, tag = "4")]
+        Implied(super::BboUpdate),
+    }
+}
+
+// build.rs 生成的 tonic 服务端/客户端（exchange_gateway_server / exchange_gateway_client）
+include!(concat!(env!("OUT_DIR"), "/matching_core.gateway.ExchangeGateway.rs"));
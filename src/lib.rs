@@ -2,5 +2,17 @@ pub mod api;
 pub mod core;
 pub mod utils;
 pub mod example;
+pub mod fix;
+pub mod replay;
+#[cfg(feature = "gateway")]
+pub mod gateway;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "test-util")]
+pub mod workload;
 
 pub use api::*;
@@ -0,0 +1,353 @@
+//! Python 绑定（feature = "python"），用于研究与回测
+//!
+//! `OrderBook` 直接驱动单个 AdvancedOrderBook，`Exchange` 驱动未启动流水线的 ExchangeCore（同步处理，结果确定）。
+//! 订单以 dict 传入（即 `DataFrame.to_dict("records")` 的每一行），成交与深度以 list/dict 返回，
+//! 可直接交给 `pandas.DataFrame` 构造。构建扩展模块：`maturin develop`（features 见 pyproject.toml）。
+
+use crate::api::*;
+use crate::core::exchange::{ExchangeConfig, ExchangeCore};
+use crate::core::orderbook::{AdvancedOrderBook, OrderBook, TopOfBook};
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+fn parse_action(side: &str) -> PyResult<OrderAction> {
+    match side.to_ascii_lowercase().as_str() {
+        "bid" | "buy" => Ok(OrderAction::Bid),
+        "ask" | "sell" => Ok(OrderAction::Ask),
+        _ => Err(PyValueError::new_err(format!("无效的 side: {}", side))),
+    }
+}
+
+fn action_name(action: OrderAction) -> &'static str {
+    match action {
+        OrderAction::Bid => "bid",
+        OrderAction::Ask => "ask",
+    }
+}
+
+fn parse_order_type(name: &str, expire_time: Option<i64>) -> PyResult<OrderType> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "gtc" | "limit" => OrderType::Gtc,
+        "ioc" => OrderType::Ioc,
+        "fok" => OrderType::Fok,
+        "fok_budget" => OrderType::FokBudget,
+        "ioc_budget" => OrderType::IocBudget,
+        "post_only" => OrderType::PostOnly,
+        "stop_limit" => OrderType::StopLimit,
+        "stop_market" => OrderType::StopMarket,
+        "iceberg" => OrderType::Iceberg,
+        "day" => OrderType::Day,
+        "gtd" => OrderType::Gtd(expire_time.ok_or_else(|| PyValueError::new_err("gtd 订单缺少 expire_time"))?),
+        "market" => OrderType::Market,
+        "gtx" => OrderType::Gtx,
+        "market_to_limit" => OrderType::MarketToLimit,
+        _ => return Err(PyValueError::new_err(format!("无效的 order_type: {}", name))),
+    })
+}
+
+/// 读取必填字段
+fn required<'py, T: FromPyObject<'py>>(record: &Bound<'py, PyDict>, key: &str) -> PyResult<T> {
+    match record.get_item(key)? {
+        Some(value) => value.extract(),
+        None => Err(PyKeyError::new_err(key.to_string())),
+    }
+}
+
+/// 读取可选字段（缺失或为 None 时返回 None）
+fn optional<'py, T: FromPyObject<'py>>(record: &Bound<'py, PyDict>, key: &str) -> PyResult<Option<T>> {
+    match record.get_item(key)? {
+        Some(value) if !value.is_none() => value.extract().map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// 由 dict 构造下单命令
+///
+/// 必填：uid、order_id、side、price、size；可选：symbol、order_type（默认 "gtc"）、reserve_price（默认 price）、
+/// timestamp、client_order_id、stop_price、visible_size、expire_time
+fn place_command(record: &Bound<'_, PyDict>, default_symbol: SymbolId) -> PyResult<OrderCommand> {
+    let price: Price = required(record, "price")?;
+    let expire_time = optional(record, "expire_time")?;
+    let order_type = match optional::<String>(record, "order_type")? {
+        Some(name) => parse_order_type(&name, expire_time)?,
+        None => OrderType::Gtc,
+    };
+    Ok(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: required(record, "uid")?,
+        order_id: required(record, "order_id")?,
+        symbol: optional(record, "symbol")?.unwrap_or(default_symbol),
+        price,
+        reserve_price: optional(record, "reserve_price")?.unwrap_or(price),
+        size: required(record, "size")?,
+        action: parse_action(&required::<String>(record, "side")?)?,
+        order_type,
+        timestamp: optional(record, "timestamp")?.unwrap_or(0),
+        client_order_id: optional(record, "client_order_id")?.unwrap_or(0),
+        stop_price: optional(record, "stop_price")?,
+        visible_size: optional(record, "visible_size")?,
+        expire_time,
+        ..Default::default()
+    })
+}
+
+/// 命令产生的成交，每笔一个 dict
+fn trades<'py>(py: Python<'py>, cmd: &OrderCommand) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    cmd.matcher_events
+        .iter()
+        .enumerate()
+        .filter(|(_, event)| event.event_type == MatcherEventType::Trade)
+        .map(|(index, event)| {
+            // 止损单激活或组合下单产生的成交 taker 为事件记录的订单，否则为命令自身的订单
+            let (taker_order_id, taker_uid, side) = match event.taker_action {
+                Some(action) => (event.taker_order_id, event.taker_uid, action),
+                None => (cmd.order_id, cmd.uid, cmd.action),
+            };
+            let trade = PyDict::new(py);
+            trade.set_item("trade_id", event.trade_id)?;
+            trade.set_item("symbol", cmd.event_symbol(index))?;
+            trade.set_item("taker_order_id", taker_order_id)?;
+            trade.set_item("taker_uid", taker_uid)?;
+            trade.set_item("maker_order_id", event.matched_order_id)?;
+            trade.set_item("maker_uid", event.matched_order_uid)?;
+            trade.set_item("side", action_name(side))?;
+            trade.set_item("price", event.price)?;
+            trade.set_item("size", event.size)?;
+            trade.set_item("timestamp", event.timestamp)?;
+            Ok(trade)
+        })
+        .collect()
+}
+
+/// 命令结果：result_code（CommandResultCode 名称）、order_id 与 trades
+fn command_result<'py>(py: Python<'py>, result_code: CommandResultCode, cmd: &OrderCommand) -> PyResult<Bound<'py, PyDict>> {
+    let result = PyDict::new(py);
+    result.set_item("result_code", format!("{:?}", result_code))?;
+    result.set_item("order_id", cmd.order_id)?;
+    result.set_item("trades", trades(py, cmd)?)?;
+    Ok(result)
+}
+
+/// 深度：按列返回，可直接构造 DataFrame
+fn l2_dict<'py>(py: Python<'py>, book: &L2MarketData) -> PyResult<Bound<'py, PyDict>> {
+    let l2 = PyDict::new(py);
+    l2.set_item("bid_prices", &book.bid_prices)?;
+    l2.set_item("bid_volumes", &book.bid_volumes)?;
+    l2.set_item("ask_prices", &book.ask_prices)?;
+    l2.set_item("ask_volumes", &book.ask_volumes)?;
+    Ok(l2)
+}
+
+/// 单个订单簿（AdvancedOrderBook），不做风控与资金检查
+#[pyclass(name = "OrderBook", module = "matching_core", unsendable)]
+pub struct PyOrderBook {
+    book: AdvancedOrderBook,
+}
+
+#[pymethods]
+impl PyOrderBook {
+    #[new]
+    #[pyo3(signature = (symbol_id = 0, tick_size = 1, lot_size = 1))]
+    fn new(symbol_id: SymbolId, tick_size: i64, lot_size: i64) -> Self {
+        Self {
+            book: AdvancedOrderBook::new(CoreSymbolSpecification {
+                symbol_id,
+                tick_size,
+                lot_size,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// 下单，返回结果 dict
+    fn place<'py>(&mut self, py: Python<'py>, order: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyDict>> {
+        let mut cmd = place_command(order, self.book.get_symbol_spec().symbol_id)?;
+        let code = self.book.new_order(&mut cmd);
+        command_result(py, code, &cmd)
+    }
+
+    /// 依次下单，返回全部成交（被拒绝的订单不中断后续订单）
+    fn place_orders<'py>(&mut self, py: Python<'py>, orders: Vec<Bound<'py, PyDict>>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut all = Vec::new();
+        for order in &orders {
+            let mut cmd = place_command(order, self.book.get_symbol_spec().symbol_id)?;
+            self.book.new_order(&mut cmd);
+            all.extend(trades(py, &cmd)?);
+        }
+        Ok(all)
+    }
+
+    fn cancel<'py>(&mut self, py: Python<'py>, order_id: OrderId, uid: UserId) -> PyResult<Bound<'py, PyDict>> {
+        let mut cmd = OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            order_id,
+            uid,
+            ..Default::default()
+        };
+        let code = self.book.cancel_order(&mut cmd);
+        command_result(py, code, &cmd)
+    }
+
+    /// 改价，新价格可能立即成交
+    fn move_order<'py>(&mut self, py: Python<'py>, order_id: OrderId, uid: UserId, new_price: Price) -> PyResult<Bound<'py, PyDict>> {
+        let mut cmd = OrderCommand {
+            command: OrderCommandType::MoveOrder,
+            order_id,
+            uid,
+            price: new_price,
+            ..Default::default()
+        };
+        let code = self.book.move_order(&mut cmd);
+        command_result(py, code, &cmd)
+    }
+
+    #[pyo3(signature = (depth = 10))]
+    fn l2<'py>(&self, py: Python<'py>, depth: usize) -> PyResult<Bound<'py, PyDict>> {
+        l2_dict(py, &self.book.get_l2_data(depth))
+    }
+
+    /// 买一、卖一的 (价格, 数量)，无挂单的一侧为 None
+    fn best_bid_offer(&self) -> TopOfBook {
+        self.book.best_bid_offer()
+    }
+}
+
+/// 完整撮合引擎（含风控与资金），命令同步处理
+#[pyclass(name = "Exchange", module = "matching_core", unsendable)]
+pub struct PyExchange {
+    core: ExchangeCore,
+}
+
+impl PyExchange {
+    fn submit(&mut self, cmd: OrderCommand) -> OrderCommand {
+        self.core.submit_command(cmd)
+    }
+}
+
+#[pymethods]
+impl PyExchange {
+    #[new]
+    fn new() -> Self {
+        Self { core: ExchangeCore::new(ExchangeConfig::default()) }
+    }
+
+    /// 添加现货交易对，返回结果码名称
+    ///
+    /// 可选关键字参数：base_scale_k、quote_scale_k、taker_fee、maker_fee、tick_size、lot_size、min_size、max_size
+    #[pyo3(signature = (symbol_id, base_currency, quote_currency, **options))]
+    fn add_symbol(
+        &mut self,
+        symbol_id: SymbolId,
+        base_currency: Currency,
+        quote_currency: Currency,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<String> {
+        let mut spec = CoreSymbolSpecification {
+            symbol_id,
+            base_currency,
+            quote_currency,
+            ..Default::default()
+        };
+        if let Some(options) = options {
+            for (key, value) in options {
+                let value: i64 = value.extract()?;
+                match key.extract::<String>()?.as_str() {
+                    "base_scale_k" => spec.base_scale_k = value,
+                    "quote_scale_k" => spec.quote_scale_k = value,
+                    "taker_fee" => spec.taker_fee = value,
+                    "maker_fee" => spec.maker_fee = value,
+                    "tick_size" => spec.tick_size = value,
+                    "lot_size" => spec.lot_size = value,
+                    "min_size" => spec.min_size = value,
+                    "max_size" => spec.max_size = value,
+                    other => return Err(PyKeyError::new_err(other.to_string())),
+                }
+            }
+        }
+        Ok(format!("{:?}", self.core.add_symbol(spec)))
+    }
+
+    fn add_user(&mut self, uid: UserId) -> String {
+        let cmd = self.submit(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        format!("{:?}", cmd.result_code)
+    }
+
+    /// 余额调整（amount 为负表示扣减），transaction_id 用于幂等
+    fn adjust_balance(&mut self, uid: UserId, currency: Currency, amount: i64, transaction_id: u64) -> String {
+        let cmd = self.submit(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: amount,
+            order_id: transaction_id,
+            ..Default::default()
+        });
+        format!("{:?}", cmd.result_code)
+    }
+
+    /// 下单（必须指定 symbol），返回结果 dict
+    fn place<'py>(&mut self, py: Python<'py>, order: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyDict>> {
+        let cmd = self.submit(place_command(order, 0)?);
+        command_result(py, cmd.result_code, &cmd)
+    }
+
+    /// 依次下单，返回全部成交（被拒绝的订单不中断后续订单）
+    fn place_orders<'py>(&mut self, py: Python<'py>, orders: Vec<Bound<'py, PyDict>>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut all = Vec::new();
+        for order in &orders {
+            let cmd = self.submit(place_command(order, 0)?);
+            all.extend(trades(py, &cmd)?);
+        }
+        Ok(all)
+    }
+
+    fn cancel<'py>(&mut self, py: Python<'py>, symbol: SymbolId, order_id: OrderId, uid: UserId) -> PyResult<Bound<'py, PyDict>> {
+        let cmd = self.submit(OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            symbol,
+            order_id,
+            uid,
+            ..Default::default()
+        });
+        command_result(py, cmd.result_code, &cmd)
+    }
+
+    fn move_order<'py>(&mut self, py: Python<'py>, symbol: SymbolId, order_id: OrderId, uid: UserId, new_price: Price) -> PyResult<Bound<'py, PyDict>> {
+        let cmd = self.submit(OrderCommand {
+            command: OrderCommandType::MoveOrder,
+            symbol,
+            order_id,
+            uid,
+            price: new_price,
+            ..Default::default()
+        });
+        command_result(py, cmd.result_code, &cmd)
+    }
+
+    #[pyo3(signature = (symbol, depth = 10))]
+    fn l2<'py>(&mut self, py: Python<'py>, symbol: SymbolId, depth: usize) -> PyResult<Bound<'py, PyDict>> {
+        let cmd = self.submit(OrderCommand {
+            command: OrderCommandType::OrderBookRequest,
+            symbol,
+            size: depth as Size,
+            ..Default::default()
+        });
+        match &cmd.market_data {
+            Some(book) if cmd.result_code == CommandResultCode::Success => l2_dict(py, book),
+            _ => Err(PyValueError::new_err(format!("查询深度失败: {:?}", cmd.result_code))),
+        }
+    }
+}
+
+/// Python 模块入口
+#[pymodule]
+pub fn matching_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyOrderBook>()?;
+    m.add_class::<PyExchange>()?;
+    Ok(())
+}
@@ -0,0 +1,508 @@
+//! 历史行情回放
+//!
+//! 读取外部逐笔委托数据（可配置列的 CSV，或 NASDAQ TotalView-ITCH 5.0 的 A/F/E/C/X/D/U 消息），
+//! 转换为 OrderCommand 提交给引擎，用于贴近真实行情的压测与回测。
+//!
+//! 行情中的挂单以 `maker_uid` 下单；成交消息（E/C）表示有对手方吃单，转换为 `taker_uid` 以成交价
+//! 下的反方向 IOC 订单（按价格时间优先撮合，同价位有多笔挂单时成交对象可能与行情不同）。
+//! 两个用户需事先在引擎中创建并充值。文件开始前已存在的挂单无法还原，引用这些订单的消息被跳过。
+
+use crate::api::*;
+use crate::core::exchange::ExchangeCore;
+use ahash::{AHashMap, AHashSet};
+use std::io::{self, BufRead, Read};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("读取失败: {0}")]
+    Io(#[from] io::Error),
+    #[error("第 {line} 行格式错误: {message}")]
+    Parse { line: usize, message: String },
+    #[error("ITCH 消息不完整")]
+    Truncated,
+}
+
+/// 外部行情中的逐笔委托事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedEvent {
+    Add { timestamp: i64, symbol: SymbolId, order_id: OrderId, action: OrderAction, price: Price, size: Size },
+    /// 挂单全部撤销
+    Cancel { timestamp: i64, order_id: OrderId },
+    /// 挂单部分撤销，size 为撤销数量
+    Reduce { timestamp: i64, order_id: OrderId, size: Size },
+    /// 挂单被动成交，price 为空时按挂单价格
+    Execute { timestamp: i64, order_id: OrderId, size: Size, price: Option<Price> },
+    /// 撤销原挂单并以新订单号挂出（方向、交易对不变）
+    Replace { timestamp: i64, order_id: OrderId, new_order_id: OrderId, price: Price, size: Size },
+}
+
+impl FeedEvent {
+    pub fn timestamp(&self) -> i64 {
+        match *self {
+            FeedEvent::Add { timestamp, .. }
+            | FeedEvent::Cancel { timestamp, .. }
+            | FeedEvent::Reduce { timestamp, .. }
+            | FeedEvent::Execute { timestamp, .. }
+            | FeedEvent::Replace { timestamp, .. } => timestamp,
+        }
+    }
+}
+
+/// CSV 列配置（列号从 0 开始）
+///
+/// event 列取值（不区分大小写）：add/a/new、cancel/delete/d、reduce/x、execute/e/fill、replace/u/modify
+#[derive(Debug, Clone)]
+pub struct CsvSchema {
+    pub delimiter: u8,
+    pub has_header: bool,
+    pub timestamp: usize,
+    pub event: usize,
+    pub order_id: usize,
+    pub side: usize,  // buy/bid/b 或 sell/ask/s
+    pub price: usize,
+    pub size: usize,
+    pub symbol: Option<usize>,       // 未配置时使用 default_symbol
+    pub new_order_id: Option<usize>, // replace 事件的新订单号
+    pub default_symbol: SymbolId,
+    pub price_decimals: u32, // 价格按该小数位数转换为整数（"101.25" 在 2 位时为 10125）
+}
+
+impl Default for CsvSchema {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_header: true,
+            timestamp: 0,
+            event: 1,
+            order_id: 2,
+            side: 3,
+            price: 4,
+            size: 5,
+            symbol: None,
+            new_order_id: None,
+            default_symbol: 0,
+            price_decimals: 0,
+        }
+    }
+}
+
+/// 按 CsvSchema 逐行读取的 CSV 行情（空行跳过）
+pub struct CsvFeed<R> {
+    lines: io::Lines<R>,
+    schema: CsvSchema,
+    line: usize,
+}
+
+impl<R: BufRead> CsvFeed<R> {
+    pub fn new(reader: R, schema: CsvSchema) -> Self {
+        Self { lines: reader.lines(), schema, line: 0 }
+    }
+
+    fn parse_line(&self, line: &str) -> Result<FeedEvent, String> {
+        let fields: Vec<&str> = line.split(self.schema.delimiter as char).map(str::trim).collect();
+        let field = |index: usize| fields.get(index).copied().ok_or_else(|| format!("缺少第 {} 列", index));
+        let number = |index: usize| -> Result<i64, String> {
+            let value = field(index)?;
+            value.parse().map_err(|_| format!("第 {} 列不是整数: {}", index, value))
+        };
+        let timestamp = number(self.schema.timestamp)?;
+        let order_id = number(self.schema.order_id)? as OrderId;
+        let price = || parse_decimal(field(self.schema.price)?, self.schema.price_decimals);
+
+        let event = field(self.schema.event)?.to_ascii_lowercase();
+        Ok(match event.as_str() {
+            "add" | "a" | "new" => FeedEvent::Add {
+                timestamp,
+                symbol: match self.schema.symbol {
+                    Some(index) => number(index)? as SymbolId,
+                    None => self.schema.default_symbol,
+                },
+                order_id,
+                action: parse_side(field(self.schema.side)?)?,
+                price: price()?,
+                size: number(self.schema.size)?,
+            },
+            "cancel" | "delete" | "d" => FeedEvent::Cancel { timestamp, order_id },
+            "reduce" | "x" => FeedEvent::Reduce { timestamp, order_id, size: number(self.schema.size)? },
+            "execute" | "e" | "fill" => FeedEvent::Execute {
+                timestamp,
+                order_id,
+                size: number(self.schema.size)?,
+                price: match fields.get(self.schema.price) {
+                    Some(value) if !value.is_empty() => Some(parse_decimal(value, self.schema.price_decimals)?),
+                    _ => None,
+                },
+            },
+            "replace" | "u" | "modify" => FeedEvent::Replace {
+                timestamp,
+                order_id,
+                new_order_id: number(self.schema.new_order_id.ok_or("未配置 new_order_id 列")?)? as OrderId,
+                price: price()?,
+                size: number(self.schema.size)?,
+            },
+            other => return Err(format!("未知事件类型: {}", other)),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for CsvFeed<R> {
+    type Item = Result<FeedEvent, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            if line.trim().is_empty() || (self.line == 1 && self.schema.has_header) {
+                continue;
+            }
+            return Some(self.parse_line(&line).map_err(|message| ReplayError::Parse { line: self.line, message }));
+        }
+    }
+}
+
+fn parse_side(value: &str) -> Result<OrderAction, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "buy" | "bid" | "b" => Ok(OrderAction::Bid),
+        "sell" | "ask" | "s" => Ok(OrderAction::Ask),
+        _ => Err(format!("无效的方向: {}", value)),
+    }
+}
+
+/// 把十进制字符串按 decimals 位小数转换为整数，多余的小数位视为错误
+fn parse_decimal(value: &str, decimals: u32) -> Result<i64, String> {
+    let invalid = || format!("无效的价格: {}", value);
+    let (int_part, frac_part) = value.split_once('.').unwrap_or((value, ""));
+    if frac_part.len() > decimals as usize || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let scale = 10i64.pow(decimals);
+    let int: i64 = int_part.parse().map_err(|_| invalid())?;
+    let frac: i64 = if frac_part.is_empty() { 0 } else { frac_part.parse::<i64>().map_err(|_| invalid())? };
+    let frac = frac * 10i64.pow(decimals - frac_part.len() as u32);
+    int.checked_mul(scale)
+        .and_then(|v| if value.starts_with('-') { v.checked_sub(frac) } else { v.checked_add(frac) })
+        .ok_or_else(invalid)
+}
+
+/// NASDAQ TotalView-ITCH 5.0 文件（每条消息前为 2 字节大端长度）
+///
+/// 交易对 ID 取 Stock Locate；timestamp 为当日纳秒；价格为 4 位小数的整数。只解析委托相关消息，其余跳过
+pub struct ItchFeed<R> {
+    reader: R,
+    symbols: Option<AHashSet<u16>>,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> ItchFeed<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, symbols: None, buffer: Vec::with_capacity(64) }
+    }
+
+    /// 只回放这些 Stock Locate 的新增委托（其它交易对的后续消息因找不到订单被跳过）
+    pub fn with_symbols(mut self, locates: &[u16]) -> Self {
+        self.symbols = Some(locates.iter().copied().collect());
+        self
+    }
+
+    /// 读取下一条消息到 buffer，文件结束返回 false
+    fn read_message(&mut self) -> Result<bool, ReplayError> {
+        let mut len = [0u8; 2];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        self.buffer.resize(u16::from_be_bytes(len) as usize, 0);
+        self.reader.read_exact(&mut self.buffer).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => ReplayError::Truncated,
+            _ => e.into(),
+        })?;
+        Ok(true)
+    }
+
+    fn decode(&self) -> Result<Option<FeedEvent>, ReplayError> {
+        let msg = &self.buffer;
+        let Some(&kind) = msg.first() else {
+            return Ok(None);
+        };
+        let min_len = match kind {
+            b'A' => 36,
+            b'F' => 40,
+            b'E' => 31,
+            b'C' => 36,
+            b'X' => 23,
+            b'D' => 19,
+            b'U' => 35,
+            _ => return Ok(None),
+        };
+        if msg.len() < min_len {
+            return Err(ReplayError::Truncated);
+        }
+        let locate = be_u64(&msg[1..3]) as u16;
+        let timestamp = be_u64(&msg[5..11]) as i64;
+        let order_id = be_u64(&msg[11..19]);
+        Ok(Some(match kind {
+            b'A' | b'F' => {
+                if self.symbols.as_ref().is_some_and(|symbols| !symbols.contains(&locate)) {
+                    return Ok(None);
+                }
+                FeedEvent::Add {
+                    timestamp,
+                    symbol: locate as SymbolId,
+                    order_id,
+                    action: if msg[19] == b'B' { OrderAction::Bid } else { OrderAction::Ask },
+                    size: be_u64(&msg[20..24]) as Size,
+                    price: be_u64(&msg[32..36]) as Price,
+                }
+            }
+            b'E' => FeedEvent::Execute { timestamp, order_id, size: be_u64(&msg[19..23]) as Size, price: None },
+            b'C' => FeedEvent::Execute {
+                timestamp,
+                order_id,
+                size: be_u64(&msg[19..23]) as Size,
+                price: Some(be_u64(&msg[32..36]) as Price),
+            },
+            b'X' => FeedEvent::Reduce { timestamp, order_id, size: be_u64(&msg[19..23]) as Size },
+            b'D' => FeedEvent::Cancel { timestamp, order_id },
+            _ => FeedEvent::Replace {
+                timestamp,
+                order_id,
+                new_order_id: be_u64(&msg[19..27]),
+                size: be_u64(&msg[27..31]) as Size,
+                price: be_u64(&msg[31..35]) as Price,
+            },
+        }))
+    }
+}
+
+impl<R: Read> Iterator for ItchFeed<R> {
+    type Item = Result<FeedEvent, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.read_message() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+            match self.decode() {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+/// 回放速度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// 不等待，尽快提交
+    AsFastAsPossible,
+    /// 按行情时间间隔等待：tick 为 timestamp 一个单位对应的时长，multiplier 为倍速（2.0 为两倍速）
+    Paced { tick: Duration, multiplier: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayConfig {
+    pub maker_uid: UserId,
+    pub taker_uid: UserId,
+    pub aggressor_order_id_start: OrderId, // 成交消息生成的吃单订单号起点，需与行情订单号不重叠
+    pub speed: ReplaySpeed,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            maker_uid: 1,
+            taker_uid: 2,
+            aggressor_order_id_start: 1 << 62,
+            speed: ReplaySpeed::AsFastAsPossible,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RestingOrder {
+    symbol: SymbolId,
+    action: OrderAction,
+    price: Price,
+    size: Size,
+}
+
+/// 行情事件到引擎命令的转换，记录行情中的挂单以还原撤单、成交所需的交易对、方向与价格
+pub struct FeedTranslator {
+    config: ReplayConfig,
+    orders: AHashMap<OrderId, RestingOrder>,
+    next_aggressor_id: OrderId,
+}
+
+impl FeedTranslator {
+    pub fn new(config: ReplayConfig) -> Self {
+        Self {
+            config,
+            orders: AHashMap::new(),
+            next_aggressor_id: config.aggressor_order_id_start,
+        }
+    }
+
+    /// 转换一个事件，引用未知订单的事件不产生命令
+    pub fn translate(&mut self, event: &FeedEvent, out: &mut Vec<OrderCommand>) {
+        match *event {
+            FeedEvent::Add { timestamp, symbol, order_id, action, price, size } => {
+                self.orders.insert(order_id, RestingOrder { symbol, action, price, size });
+                out.push(self.place(timestamp, order_id, symbol, action, price, size));
+            }
+            FeedEvent::Cancel { timestamp, order_id } => {
+                if let Some(order) = self.orders.remove(&order_id) {
+                    out.push(self.cancel(timestamp, order_id, order.symbol));
+                }
+            }
+            FeedEvent::Reduce { timestamp, order_id, size } => {
+                let Some(order) = self.orders.get_mut(&order_id) else {
+                    return;
+                };
+                order.size -= size;
+                let symbol = order.symbol;
+                if order.size <= 0 {
+                    self.orders.remove(&order_id);
+                    out.push(self.cancel(timestamp, order_id, symbol));
+                } else {
+                    out.push(OrderCommand {
+                        command: OrderCommandType::ReduceOrder,
+                        uid: self.config.maker_uid,
+                        order_id,
+                        symbol,
+                        size,
+                        timestamp,
+                        ..Default::default()
+                    });
+                }
+            }
+            FeedEvent::Execute { timestamp, order_id, size, price } => {
+                let Some(order) = self.orders.get_mut(&order_id) else {
+                    return;
+                };
+                order.size -= size;
+                let order = *order;
+                if order.size <= 0 {
+                    self.orders.remove(&order_id);
+                }
+                let aggressor_id = self.next_aggressor_id;
+                self.next_aggressor_id += 1;
+                let price = price.unwrap_or(order.price);
+                out.push(OrderCommand {
+                    order_type: OrderType::Ioc,
+                    uid: self.config.taker_uid,
+                    ..self.place(timestamp, aggressor_id, order.symbol, order.action.opposite(), price, size)
+                });
+            }
+            FeedEvent::Replace { timestamp, order_id, new_order_id, price, size } => {
+                let Some(order) = self.orders.remove(&order_id) else {
+                    return;
+                };
+                out.push(self.cancel(timestamp, order_id, order.symbol));
+                self.orders.insert(new_order_id, RestingOrder { price, size, ..order });
+                out.push(self.place(timestamp, new_order_id, order.symbol, order.action, price, size));
+            }
+        }
+    }
+
+    /// 当前仍在簿上的行情挂单数
+    pub fn resting_orders(&self) -> usize {
+        self.orders.len()
+    }
+
+    fn place(&self, timestamp: i64, order_id: OrderId, symbol: SymbolId, action: OrderAction, price: Price, size: Size) -> OrderCommand {
+        OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid: self.config.maker_uid,
+            order_id,
+            symbol,
+            price,
+            reserve_price: price,
+            size,
+            action,
+            order_type: OrderType::Gtc,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn cancel(&self, timestamp: i64, order_id: OrderId, symbol: SymbolId) -> OrderCommand {
+        OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            uid: self.config.maker_uid,
+            order_id,
+            symbol,
+            timestamp,
+            ..Default::default()
+        }
+    }
+}
+
+/// 回放统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    pub events: u64,
+    pub commands: u64,
+    pub skipped: u64, // 引用未知订单而跳过的事件数
+    pub elapsed: Duration,
+}
+
+/// 把行情事件流提交给引擎
+pub struct Replayer {
+    translator: FeedTranslator,
+    speed: ReplaySpeed,
+}
+
+impl Replayer {
+    pub fn new(config: ReplayConfig) -> Self {
+        Self { translator: FeedTranslator::new(config), speed: config.speed }
+    }
+
+    /// 回放到行情结束或遇到错误（错误之前的事件已提交）；启动前同步处理，启动后异步提交
+    pub fn run<I>(&mut self, feed: I, core: &mut ExchangeCore) -> Result<ReplayStats, ReplayError>
+    where
+        I: IntoIterator<Item = Result<FeedEvent, ReplayError>>,
+    {
+        let started = Instant::now();
+        let mut stats = ReplayStats::default();
+        let mut first_timestamp = None;
+        let mut commands = Vec::new();
+        for event in feed {
+            let event = event?;
+            stats.events += 1;
+            if let ReplaySpeed::Paced { tick, multiplier } = self.speed {
+                let first = *first_timestamp.get_or_insert(event.timestamp());
+                let offset = tick.mul_f64((event.timestamp() - first).max(0) as f64 / multiplier);
+                if let Some(wait) = offset.checked_sub(started.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+            }
+
+            self.translator.translate(&event, &mut commands);
+            if commands.is_empty() {
+                stats.skipped += 1;
+            }
+            for cmd in commands.drain(..) {
+                core.submit_command(cmd);
+                stats.commands += 1;
+            }
+        }
+        stats.elapsed = started.elapsed();
+        Ok(stats)
+    }
+
+    pub fn translator(&self) -> &FeedTranslator {
+        &self.translator
+    }
+}
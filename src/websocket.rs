@@ -0,0 +1,383 @@
+//! WebSocket 行情推送（feature = "websocket"）
+//!
+//! `MarketDataHub` 作为行情消费者维护各交易对的深度镜像，并按交易对管理订阅：订阅时先推送当前深度快照，
+//! 之后推送序号大于快照的 L2 增量、BBO 与逐笔成交。`WsServer` 通过 WebSocket（JSON 文本帧）对外提供订阅。
+//!
+//! 客户端请求：`{"op":"subscribe","symbols":[100]}`、`{"op":"unsubscribe","symbols":[100]}`
+
+use crate::api::*;
+use crate::core::market_data::MarketDataConsumer;
+use ahash::{AHashMap, AHashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// 推送给客户端的消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed { symbols: Vec<SymbolId> },
+    Unsubscribed { symbols: Vec<SymbolId> },
+    /// 深度快照（买盘价格降序、卖盘价格升序），seq 为快照对应的 L2 增量序号
+    Snapshot { symbol: SymbolId, seq: u64, bids: Vec<(Price, Size)>, asks: Vec<(Price, Size)> },
+    /// L2 增量，volume 为 0 表示档位移除
+    L2 { symbol: SymbolId, seq: u64, side: OrderAction, price: Price, volume: Size },
+    Bbo { symbol: SymbolId, seq: u64, bid: Option<Price>, bid_size: Size, ask: Option<Price>, ask_size: Size },
+    /// 价差组推出的隐含最优价
+    Implied { symbol: SymbolId, seq: u64, bid: Option<Price>, bid_size: Size, ask: Option<Price>, ask_size: Size },
+    Trade { symbol: SymbolId, trade_id: u64, price: Price, size: Size, side: OrderAction, timestamp: i64 },
+    Error { message: String },
+}
+
+/// 客户端请求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { symbols: Vec<SymbolId> },
+    Unsubscribe { symbols: Vec<SymbolId> },
+}
+
+impl From<&MarketDataEvent> for ServerMessage {
+    fn from(event: &MarketDataEvent) -> Self {
+        match event {
+            MarketDataEvent::Trade(tick) => ServerMessage::Trade {
+                symbol: tick.symbol,
+                trade_id: tick.trade_id,
+                price: tick.price,
+                size: tick.size,
+                side: tick.taker_action,
+                timestamp: tick.timestamp,
+            },
+            MarketDataEvent::Bbo(bbo) => ServerMessage::Bbo {
+                symbol: bbo.symbol,
+                seq: bbo.seq,
+                bid: bbo.bid,
+                bid_size: bbo.bid_size,
+                ask: bbo.ask,
+                ask_size: bbo.ask_size,
+            },
+            MarketDataEvent::L2(delta) => ServerMessage::L2 {
+                symbol: delta.symbol,
+                seq: delta.seq,
+                side: delta.action,
+                price: delta.price,
+                volume: delta.volume,
+            },
+            MarketDataEvent::Implied(quote) => ServerMessage::Implied {
+                symbol: quote.symbol,
+                seq: quote.seq,
+                bid: quote.bid,
+                bid_size: quote.bid_size,
+                ask: quote.ask,
+                ask_size: quote.ask_size,
+            },
+        }
+    }
+}
+
+/// 行情中心配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HubConfig {
+    pub snapshot_depth: usize, // 订阅快照每侧的档位数
+    pub queue_capacity: usize, // 每个连接待发送消息上限，超出时断开该连接（慢消费者不阻塞撮合线程）
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_depth: 50,
+            queue_capacity: 4096,
+        }
+    }
+}
+
+/// 由 L2 增量维护的深度镜像
+#[derive(Default)]
+struct BookMirror {
+    seq: u64,
+    bids: BTreeMap<Price, Size>,
+    asks: BTreeMap<Price, Size>,
+}
+
+impl BookMirror {
+    fn apply(&mut self, delta: &L2Delta) {
+        let side = match delta.action {
+            OrderAction::Bid => &mut self.bids,
+            OrderAction::Ask => &mut self.asks,
+        };
+        if delta.volume == 0 {
+            side.remove(&delta.price);
+        } else {
+            side.insert(delta.price, delta.volume);
+        }
+        self.seq = delta.seq;
+    }
+
+    fn snapshot(&self, symbol: SymbolId, depth: usize) -> ServerMessage {
+        ServerMessage::Snapshot {
+            symbol,
+            seq: self.seq,
+            bids: self.bids.iter().rev().take(depth).map(|(&p, &v)| (p, v)).collect(),
+            asks: self.asks.iter().take(depth).map(|(&p, &v)| (p, v)).collect(),
+        }
+    }
+}
+
+struct Subscriber {
+    symbols: AHashSet<SymbolId>,
+    sender: SyncSender<ServerMessage>,
+}
+
+#[derive(Default)]
+struct HubState {
+    books: AHashMap<SymbolId, BookMirror>,
+    subscribers: AHashMap<u64, Subscriber>,
+    next_id: u64,
+}
+
+/// 行情中心：深度镜像 + 按交易对的订阅管理
+///
+/// 深度镜像由注册后收到的 L2 增量构建，需在 ExchangeCore 启动前注册（从快照恢复的挂单不会出现在镜像中）
+pub struct MarketDataHub {
+    config: HubConfig,
+    state: Mutex<HubState>,
+}
+
+/// 注册为行情消费者后由撮合线程更新、连接线程订阅的共享行情中心
+pub type SharedHub = Arc<MarketDataHub>;
+
+impl MarketDataHub {
+    pub fn new(config: HubConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(HubState::default()),
+        }
+    }
+
+    /// 包装为行情消费者，返回共享行情中心与对应回调
+    pub fn into_consumer(self) -> (SharedHub, MarketDataConsumer) {
+        let hub = Arc::new(self);
+        let sink = hub.clone();
+        let consumer: MarketDataConsumer = Arc::new(move |event| sink.on_event(event));
+        (hub, consumer)
+    }
+
+    /// 新连接，返回连接 ID 与待发送消息队列（队列断开表示连接因积压被移除）
+    pub fn connect(&self) -> (u64, Receiver<ServerMessage>) {
+        let (sender, receiver) = mpsc::sync_channel(self.config.queue_capacity);
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.subscribers.insert(id, Subscriber { symbols: AHashSet::new(), sender });
+        (id, receiver)
+    }
+
+    pub fn disconnect(&self, id: u64) {
+        self.state.lock().unwrap().subscribers.remove(&id);
+    }
+
+    /// 订阅交易对：确认后逐个推送快照，与后续增量之间没有遗漏或重复（已订阅的交易对忽略）
+    pub fn subscribe(&self, id: u64, symbols: &[SymbolId]) {
+        let mut state = self.state.lock().unwrap();
+        let HubState { books, subscribers, .. } = &mut *state;
+        let Some(subscriber) = subscribers.get_mut(&id) else {
+            return;
+        };
+        let added: Vec<SymbolId> = symbols.iter().copied().filter(|&symbol| subscriber.symbols.insert(symbol)).collect();
+
+        let mut messages = vec![ServerMessage::Subscribed { symbols: added.clone() }];
+        for symbol in added {
+            let snapshot = match books.get(&symbol) {
+                Some(book) => book.snapshot(symbol, self.config.snapshot_depth),
+                None => BookMirror::default().snapshot(symbol, 0),
+            };
+            messages.push(snapshot);
+        }
+        if messages.into_iter().any(|message| subscriber.sender.try_send(message).is_err()) {
+            subscribers.remove(&id);
+        }
+    }
+
+    pub fn unsubscribe(&self, id: u64, symbols: &[SymbolId]) {
+        let mut state = self.state.lock().unwrap();
+        let Some(subscriber) = state.subscribers.get_mut(&id) else {
+            return;
+        };
+        let removed: Vec<SymbolId> = symbols.iter().copied().filter(|symbol| subscriber.symbols.remove(symbol)).collect();
+        if subscriber.sender.try_send(ServerMessage::Unsubscribed { symbols: removed }).is_err() {
+            state.subscribers.remove(&id);
+        }
+    }
+
+    /// 更新深度镜像并推送给该交易对的订阅者，积压或已断开的连接被移除
+    pub fn on_event(&self, event: &MarketDataEvent) {
+        let symbol = event.symbol();
+        let mut state = self.state.lock().unwrap();
+        if let MarketDataEvent::L2(delta) = event {
+            state.books.entry(symbol).or_default().apply(delta);
+        }
+        let message = ServerMessage::from(event);
+        state.subscribers.retain(|_, subscriber| {
+            !subscriber.symbols.contains(&symbol) || subscriber.sender.try_send(message.clone()).is_ok()
+        });
+    }
+
+    /// 当前连接数
+    pub fn connection_count(&self) -> usize {
+        self.state.lock().unwrap().subscribers.len()
+    }
+}
+
+/// 连接处理结果（tungstenite::Error 较大，装箱返回）
+type WsResult = Result<(), Box<tungstenite::Error>>;
+
+/// 连接线程轮询间隔：检查待发送消息与关闭标志
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// WebSocket 行情服务：每个连接一个线程
+pub struct WsServer {
+    local_addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl WsServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A, hub: SharedHub) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let flag = shutdown.clone();
+        let acceptor = std::thread::Builder::new()
+            .name("ws-market-data".into())
+            .spawn(move || accept_loop(listener, hub, flag))?;
+        Ok(Self { local_addr, shutdown, acceptor: Some(acceptor) })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// 停止接受新连接并关闭已有连接
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+    }
+}
+
+impl Drop for WsServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn accept_loop(listener: TcpListener, hub: SharedHub, shutdown: Arc<AtomicBool>) {
+    let mut connections = Vec::new();
+    while !shutdown.load(Ordering::Acquire) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let hub = hub.clone();
+                let flag = shutdown.clone();
+                let handle = std::thread::Builder::new()
+                    .name(format!("ws-conn-{}", peer))
+                    .spawn(move || {
+                        if let Err(e) = serve_connection(stream, &hub, &flag) {
+                            tracing::debug!("行情连接 {} 关闭: {}", peer, e);
+                        }
+                    });
+                match handle {
+                    Ok(handle) => connections.push(handle),
+                    Err(e) => tracing::warn!("无法启动行情连接线程: {}", e),
+                }
+                connections.retain(|handle: &JoinHandle<()>| !handle.is_finished());
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => tracing::warn!("接受行情连接失败: {}", e),
+        }
+    }
+    for handle in connections {
+        let _ = handle.join();
+    }
+}
+
+fn serve_connection(stream: TcpStream, hub: &MarketDataHub, shutdown: &AtomicBool) -> WsResult {
+    stream.set_nonblocking(false).map_err(tungstenite::Error::Io)?;
+    let mut ws = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => tungstenite::Error::ConnectionClosed,
+    })?;
+    ws.get_mut().set_read_timeout(Some(POLL_INTERVAL)).map_err(tungstenite::Error::Io)?;
+
+    let (id, outgoing) = hub.connect();
+    let result = connection_loop(&mut ws, hub, id, &outgoing, shutdown);
+    hub.disconnect(id);
+    result
+}
+
+fn connection_loop(
+    ws: &mut WebSocket<TcpStream>,
+    hub: &MarketDataHub,
+    id: u64,
+    outgoing: &Receiver<ServerMessage>,
+    shutdown: &AtomicBool,
+) -> WsResult {
+    loop {
+        if shutdown.load(Ordering::Acquire) {
+            ws.close(None)?;
+            return Ok(ws.flush()?);
+        }
+        loop {
+            match outgoing.try_recv() {
+                Ok(message) => send(ws, &message)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // 积压超过上限被行情中心移除
+                    send(ws, &ServerMessage::Error { message: "消息积压，连接已断开".into() })?;
+                    ws.close(None)?;
+                    return Ok(ws.flush()?);
+                }
+            }
+        }
+        ws.flush()?;
+
+        match ws.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(ClientMessage::Subscribe { symbols }) => hub.subscribe(id, &symbols),
+                Ok(ClientMessage::Unsubscribe { symbols }) => hub.unsubscribe(id, &symbols),
+                Err(e) => send(ws, &ServerMessage::Error { message: format!("无效请求: {}", e) })?,
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn send(ws: &mut WebSocket<TcpStream>, message: &ServerMessage) -> WsResult {
+    let text = serde_json::to_string(message).expect("行情消息序列化失败");
+    match ws.write(Message::text(text)) {
+        // 发送缓冲已满时先刷出再重试
+        Err(tungstenite::Error::WriteBufferFull(message)) => {
+            ws.flush()?;
+            Ok(ws.write(message)?)
+        }
+        other => Ok(other?),
+    }
+}
+
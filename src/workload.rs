@@ -0,0 +1,241 @@
+use crate::api::*;
+
+/// 随机订单流生成器（feature = "test-util"），供基准测试、模糊测试与等价性测试复用
+///
+/// 同一 seed 与配置生成完全相同的命令序列。生成器不观察撮合结果，
+/// 撤单 / 改单 / 减量的目标可能已经成交，此时引擎返回的失败结果码属于预期
+pub struct OrderFlowGenerator {
+    config: WorkloadConfig,
+    rng: SplitMix64,
+    next_order_id: OrderId,
+    timestamp: i64,
+    /// 可能仍在挂单的订单（order_id, uid, symbol, action）
+    live: Vec<(OrderId, UserId, SymbolId, OrderAction)>,
+}
+
+/// 订单流配置
+#[derive(Debug, Clone)]
+pub struct WorkloadConfig {
+    pub seed: u64,
+    pub symbols: Vec<SymbolId>,
+    pub uids: Vec<UserId>,
+    /// 下单类型及其权重
+    pub order_types: Vec<(OrderType, u32)>,
+    /// 买单占比
+    pub bid_ratio: f64,
+    /// 中间价（价格围绕其分布）
+    pub mid_price: Price,
+    pub tick_size: Price,
+    pub price: PriceDistribution,
+    /// 下单数量范围（含两端）
+    pub min_size: Size,
+    pub max_size: Size,
+    /// 撤单 / 改价 / 减量命令占全部命令的比例，其余为下单
+    pub cancel_rate: f64,
+    pub move_rate: f64,
+    pub reduce_rate: f64,
+    pub first_order_id: OrderId,
+    pub start_timestamp: i64,
+    pub timestamp_step: i64,
+    /// Gtd 订单的有效期（相对下单时间）
+    pub gtd_lifetime: i64,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            symbols: vec![1],
+            uids: vec![1, 2],
+            order_types: vec![(OrderType::Gtc, 1)],
+            bid_ratio: 0.5,
+            mid_price: 10_000,
+            tick_size: 1,
+            price: PriceDistribution::Uniform { ticks: 10 },
+            min_size: 1,
+            max_size: 10,
+            cancel_rate: 0.0,
+            move_rate: 0.0,
+            reduce_rate: 0.0,
+            first_order_id: 1,
+            start_timestamp: 1,
+            timestamp_step: 1,
+            gtd_lifetime: 1_000,
+        }
+    }
+}
+
+/// 价格相对中间价的偏移分布（单位：tick），正偏移表示更激进（买单更高、卖单更低）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceDistribution {
+    /// 中间价 ± ticks 内均匀分布
+    Uniform { ticks: i64 },
+    /// 正态分布，均值 mean、标准差 std_dev（Box-Muller）
+    Normal { mean: f64, std_dev: f64 },
+    /// 只挂被动价：距中间价 1..=ticks 档，不穿价
+    Passive { ticks: i64 },
+}
+
+impl OrderFlowGenerator {
+    pub fn new(config: WorkloadConfig) -> Self {
+        assert!(!config.symbols.is_empty() && !config.uids.is_empty(), "symbols / uids 不能为空");
+        assert!(config.order_types.iter().any(|&(_, w)| w > 0), "至少一种订单类型权重大于 0");
+        assert!(config.min_size > 0 && config.min_size <= config.max_size, "数量范围无效");
+        Self {
+            rng: SplitMix64(config.seed),
+            next_order_id: config.first_order_id,
+            timestamp: config.start_timestamp,
+            live: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &WorkloadConfig {
+        &self.config
+    }
+
+    /// 可能仍在挂单的订单数量
+    pub fn live_orders(&self) -> usize {
+        self.live.len()
+    }
+
+    /// 生成下一条命令
+    pub fn next_command(&mut self) -> OrderCommand {
+        let timestamp = self.timestamp;
+        self.timestamp += self.config.timestamp_step;
+
+        let roll = self.rng.next_f64();
+        let cancel = self.config.cancel_rate;
+        let moved = cancel + self.config.move_rate;
+        let reduce = moved + self.config.reduce_rate;
+        if !self.live.is_empty() && roll < reduce {
+            let index = self.rng.below(self.live.len() as u64) as usize;
+            let (order_id, uid, symbol, action) = self.live[index];
+            let mut cmd = OrderCommand { uid, order_id, symbol, timestamp, ..Default::default() };
+            if roll < cancel {
+                self.live.swap_remove(index);
+                cmd.command = OrderCommandType::CancelOrder;
+            } else if roll < moved {
+                cmd.command = OrderCommandType::MoveOrder;
+                cmd.price = self.price(action);
+            } else {
+                cmd.command = OrderCommandType::ReduceOrder;
+                cmd.size = self.rng.range(1, self.config.max_size);
+            }
+            return cmd;
+        }
+        self.place(timestamp)
+    }
+
+    /// 生成 n 条命令
+    pub fn take_commands(&mut self, n: usize) -> Vec<OrderCommand> {
+        (0..n).map(|_| self.next_command()).collect()
+    }
+
+    fn price(&mut self, action: OrderAction) -> Price {
+        let offset = match self.config.price {
+            PriceDistribution::Uniform { ticks } => self.rng.range(-ticks, ticks),
+            PriceDistribution::Normal { mean, std_dev } => (mean + std_dev * self.rng.next_gaussian()).round() as i64,
+            PriceDistribution::Passive { ticks } => -self.rng.range(1, ticks.max(1)),
+        };
+        let offset = if action == OrderAction::Bid { offset } else { -offset };
+        (self.config.mid_price + offset * self.config.tick_size).max(self.config.tick_size)
+    }
+
+    fn place(&mut self, timestamp: i64) -> OrderCommand {
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        let uid = self.config.uids[self.rng.below(self.config.uids.len() as u64) as usize];
+        let symbol = self.config.symbols[self.rng.below(self.config.symbols.len() as u64) as usize];
+        let action = if self.rng.next_f64() < self.config.bid_ratio { OrderAction::Bid } else { OrderAction::Ask };
+        let price = self.price(action);
+        let config = &self.config;
+        let size = self.rng.range(config.min_size, config.max_size);
+
+        let total: u64 = config.order_types.iter().map(|&(_, w)| w as u64).sum();
+        let mut pick = self.rng.below(total);
+        let mut order_type = OrderType::Gtc;
+        for &(candidate, weight) in &config.order_types {
+            if pick < weight as u64 {
+                order_type = candidate;
+                break;
+            }
+            pick -= weight as u64;
+        }
+
+        let mut cmd = OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id,
+            symbol,
+            price,
+            reserve_price: price,
+            size,
+            action,
+            order_type,
+            timestamp,
+            ..Default::default()
+        };
+        match order_type {
+            OrderType::Iceberg => cmd.visible_size = Some((size / 4).max(1)),
+            OrderType::StopLimit | OrderType::StopMarket => cmd.stop_price = Some(price),
+            OrderType::Gtd(_) => {
+                let expire = timestamp + config.gtd_lifetime;
+                cmd.order_type = OrderType::Gtd(expire);
+                cmd.expire_time = Some(expire);
+            }
+            _ => {}
+        }
+        if matches!(
+            order_type,
+            OrderType::Gtc | OrderType::PostOnly | OrderType::Iceberg | OrderType::Day | OrderType::Gtd(_)
+                | OrderType::Gtx | OrderType::StopLimit | OrderType::StopMarket | OrderType::MarketToLimit
+        ) {
+            self.live.push((order_id, uid, symbol, action));
+        }
+        cmd
+    }
+}
+
+impl Iterator for OrderFlowGenerator {
+    type Item = OrderCommand;
+
+    fn next(&mut self) -> Option<OrderCommand> {
+        Some(self.next_command())
+    }
+}
+
+/// SplitMix64：序列只取决于 seed，不随外部随机数库版本变化
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [0, n)
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// [low, high]
+    fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + self.below((high - low) as u64 + 1) as i64
+    }
+
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+}
@@ -0,0 +1,146 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::path::PathBuf;
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn config() -> ExchangeConfig {
+    ExchangeConfig {
+        ring_buffer_size: 1024,
+        matching_engines_num: 2,
+        risk_engines_num: 2,
+        ..Default::default()
+    }
+}
+
+fn setup_users(core: &mut ExchangeCore) {
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+}
+
+fn place(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn book_request(symbol: SymbolId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol,
+        size: 10,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_add_symbols_while_running() {
+    let mut core = ExchangeCore::new(config());
+    core.add_symbol(spec(100));
+    core.startup();
+    setup_users(&mut core);
+
+    // 交易对 101 不存在
+    let rejected = core.submit_command_async(place(1001, 1, 101, 1000, 10, OrderAction::Ask)).wait();
+    assert_eq!(rejected.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+
+    // 两个新交易对分属不同撮合分片
+    let added = core.submit_command_async(OrderCommand::add_symbols(&[spec(101), spec(102)])).wait();
+    assert_eq!(added.result_code, CommandResultCode::Success);
+    assert_eq!(core.symbols().unwrap().iter().map(|s| s.symbol_id).collect::<Vec<_>>(), vec![100, 101, 102]);
+
+    for symbol in [101, 102] {
+        let ask = core.submit_command_async(place(1001, symbol as OrderId * 10, symbol, 1000, 10, OrderAction::Ask)).wait();
+        assert_eq!(ask.result_code, CommandResultCode::Success);
+        let bid = core.submit_command_async(place(1002, symbol as OrderId * 10 + 1, symbol, 1000, 4, OrderAction::Bid)).wait();
+        assert_eq!(bid.matcher_events.len(), 1);
+        let book = core.submit_command_async(book_request(symbol)).wait().market_data.unwrap();
+        assert_eq!((book.ask_prices, book.ask_volumes), (vec![1000], vec![6]));
+    }
+
+    // 启动后 add_symbol 同样以命令提交
+    core.add_symbol(spec(103));
+    assert_eq!(core.symbols().unwrap().len(), 4);
+}
+
+#[test]
+fn test_duplicate_or_invalid_symbols_rejected_atomically() {
+    let mut core = ExchangeCore::new(config());
+    core.add_symbol(spec(100));
+
+    let result = core.submit_command(OrderCommand::add_symbols(&[spec(101), spec(100)]));
+    assert_eq!(result.result_code, CommandResultCode::SymbolMgmtSymbolAlreadyExists);
+    let result = core.submit_command(OrderCommand::add_symbols(&[spec(102), spec(102)]));
+    assert_eq!(result.result_code, CommandResultCode::SymbolMgmtSymbolAlreadyExists);
+    // 整批未生效
+    assert_eq!(core.symbols().unwrap().len(), 1);
+    assert_eq!(
+        core.submit_command(book_request(101)).result_code,
+        CommandResultCode::MatchingInvalidOrderBookId
+    );
+
+    let result = core.submit_command(OrderCommand {
+        command: OrderCommandType::BinaryDataCommand,
+        service_flags: BINARY_COMMAND_ADD_SYMBOLS,
+        binary_data: vec![1, 2, 3],
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::BinaryCommandFailed);
+}
+
+#[test]
+fn test_added_symbols_replayed_from_journal() {
+    let dir = std::env::temp_dir().join(format!("matching_core_add_symbol_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let journal: PathBuf = dir.join("exchange.wal");
+
+    let mut core = ExchangeCore::new(config());
+    core.enable_journaling(&journal).unwrap();
+    core.startup();
+    core.add_symbol(spec(101));
+    setup_users(&mut core);
+    core.submit_command(place(1001, 1, 101, 1000, 10, OrderAction::Ask));
+    let expected = core.submit_command_async(book_request(101)).wait().market_data.unwrap();
+    drop(core);
+
+    // 恢复时无需预先添加交易对
+    let mut recovered = ExchangeCore::new(config());
+    recovered.enable_journaling(&journal).unwrap();
+    recovered.recover().unwrap();
+    assert_eq!(recovered.submit_command(book_request(101)).market_data.unwrap(), expected);
+    assert_eq!(recovered.symbols().unwrap().len(), 1);
+}
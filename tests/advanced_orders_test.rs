@@ -13,6 +13,7 @@ fn create_symbol_spec() -> CoreSymbolSpecification {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     }
 }
 
@@ -75,6 +76,52 @@ fn test_post_only_order() {
     assert_eq!(book.get_total_bid_volume(), 5);
 }
 
+#[test]
+fn test_post_only_slide() {
+    let mut book = AdvancedOrderBook::new(CoreSymbolSpecification { tick_size: 5, ..create_symbol_spec() });
+    let order = |uid, order_id, price, action, order_type| OrderCommand {
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size: 5,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    };
+    book.new_order(&mut order(1, 1, 10000, OrderAction::Ask, OrderType::Gtc));
+    book.new_order(&mut order(2, 2, 9980, OrderAction::Bid, OrderType::Gtc));
+
+    // 买单越过卖一：改挂在卖一下方一个最小变动单位，不产生事件
+    let mut bid = OrderCommand { post_only_slide: true, ..order(3, 3, 10010, OrderAction::Bid, OrderType::PostOnly) };
+    book.new_order(&mut bid);
+    assert!(bid.matcher_events.is_empty());
+    assert_eq!(bid.price, 9995);
+    assert_eq!(book.get_order_by_id(3), Some((9995, OrderAction::Bid)));
+    assert_eq!(book.get_open_order(3).unwrap().reserve_price, 10010);
+
+    // 卖单越过买一：改挂在买一上方一个最小变动单位
+    let mut ask = OrderCommand { post_only_slide: true, ..order(4, 4, 9900, OrderAction::Ask, OrderType::PostOnly) };
+    book.new_order(&mut ask);
+    assert!(ask.matcher_events.is_empty());
+    assert_eq!(book.get_order_by_id(4), Some((10000, OrderAction::Ask)));
+    assert_eq!(book.get_level_volume(OrderAction::Ask, 10000), 10);
+
+    // 不交叉时价格不变
+    let mut bid = OrderCommand { post_only_slide: true, ..order(5, 5, 9970, OrderAction::Bid, OrderType::PostOnly) };
+    book.new_order(&mut bid);
+    assert_eq!(book.get_order_by_id(5), Some((9970, OrderAction::Bid)));
+
+    // 未开启滑价仍拒绝
+    let mut bid = order(6, 6, 10000, OrderAction::Bid, OrderType::PostOnly);
+    book.new_order(&mut bid);
+    assert_eq!(bid.matcher_events.len(), 1);
+    assert_eq!(bid.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(book.get_order_by_id(6), None);
+}
+
 #[test]
 fn test_stop_limit_order() {
     let mut book = AdvancedOrderBook::new(create_symbol_spec());
@@ -281,9 +328,14 @@ fn test_gtd_order() {
     };
     book.new_order(&mut bid_cmd2);
     
-    // 应该被拒绝（因为订单已过期）
-    assert_eq!(bid_cmd2.matcher_events.len(), 1);
-    assert_eq!(bid_cmd2.matcher_events[0].event_type, MatcherEventType::Reject);
+    // 应该被拒绝（因为订单已过期），过期的卖单同时移出订单簿并以拒绝事件返还冻结
+    assert_eq!(bid_cmd2.matcher_events.len(), 2);
+    let expired = &bid_cmd2.matcher_events[0];
+    assert_eq!((expired.event_type, expired.matched_order_id, expired.size), (MatcherEventType::Reject, 1, 5));
+    assert_eq!(bid_cmd2.matcher_events[1].event_type, MatcherEventType::Reject);
+    assert_eq!(bid_cmd2.matcher_events[1].maker_action, None);
+    assert_eq!(book.get_total_ask_volume(), 0);
+    assert!(book.get_order_by_id(1).is_none());
 }
 
 #[test]
@@ -453,3 +505,670 @@ fn test_day_order() {
     assert_eq!(book.get_total_bid_volume(), 0);
 }
 
+
+#[test]
+fn test_expire_orders_scan() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    // GTD 卖单（仅通过订单类型指定过期时间）与 GTC 卖单
+    let mut gtd_cmd = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        size: 10,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtd(2000),
+        reserve_price: 10000,
+        timestamp: 1000,
+        ..Default::default()
+    };
+    book.new_order(&mut gtd_cmd);
+
+    let mut gtc_cmd = OrderCommand {
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10100,
+        size: 5,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        reserve_price: 10100,
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut gtc_cmd);
+
+    // 部分成交 3
+    let mut bid_cmd = OrderCommand {
+        uid: 3,
+        order_id: 3,
+        symbol: 1,
+        price: 10000,
+        size: 3,
+        action: OrderAction::Bid,
+        order_type: OrderType::Ioc,
+        reserve_price: 10000,
+        timestamp: 1500,
+        ..Default::default()
+    };
+    book.new_order(&mut bid_cmd);
+
+    let mut events = Vec::new();
+    assert_eq!(book.expire_orders(2000, &mut events), 0);
+    assert!(events.is_empty());
+
+    assert_eq!(book.expire_orders(2001, &mut events), 1);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(events[0].size, 7);
+    assert_eq!(events[0].matched_order_id, 1);
+    assert_eq!(events[0].matched_order_uid, 1);
+    assert_eq!(events[0].maker_action, Some(OrderAction::Ask));
+
+    assert!(book.get_order_by_id(1).is_none());
+    assert_eq!(book.get_ask_buckets_count(), 1);
+    assert_eq!(book.get_total_ask_volume(), 5);
+    assert_eq!(book.get_l2_data(5).ask_prices, vec![10100]);
+}
+
+#[test]
+fn test_expire_untriggered_stop_order() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    let mut stop = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 11000,
+        size: 10,
+        action: OrderAction::Bid,
+        order_type: OrderType::StopLimit,
+        reserve_price: 11000,
+        timestamp: 1000,
+        stop_price: Some(10500),
+        expire_time: Some(2000),
+        ..Default::default()
+    };
+    book.new_order(&mut stop);
+
+    let mut events = Vec::new();
+    assert_eq!(book.expire_orders(3000, &mut events), 1);
+    assert_eq!(events[0].size, 10);
+    assert_eq!(events[0].bidder_hold_price, 11000);
+    assert_eq!(events[0].maker_action, Some(OrderAction::Bid));
+
+    // 已过期的止损单不能再被取消
+    let mut cancel = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        ..Default::default()
+    };
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::MatchingUnknownOrderId);
+}
+
+#[test]
+fn test_iceberg_refresh_loses_priority() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    // 冰山卖单：总量 20，显示 5；其后同价位普通卖单 10
+    let mut iceberg_cmd = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        size: 20,
+        action: OrderAction::Ask,
+        order_type: OrderType::Iceberg,
+        reserve_price: 10000,
+        timestamp: 1000,
+        visible_size: Some(5),
+        ..Default::default()
+    };
+    book.new_order(&mut iceberg_cmd);
+
+    let mut gtc_cmd = OrderCommand {
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10000,
+        size: 10,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        reserve_price: 10000,
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut gtc_cmd);
+    assert_eq!(book.get_l2_data(1).ask_volumes[0], 15);
+
+    // 买 8：冰山切片 5 成交后刷新并排到队尾，剩余 3 与普通卖单成交
+    let mut bid_cmd = OrderCommand {
+        uid: 3,
+        order_id: 3,
+        symbol: 1,
+        price: 10000,
+        size: 8,
+        action: OrderAction::Bid,
+        order_type: OrderType::Ioc,
+        reserve_price: 10000,
+        timestamp: 1002,
+        ..Default::default()
+    };
+    book.new_order(&mut bid_cmd);
+
+    assert_eq!(bid_cmd.matcher_events.len(), 2);
+    assert_eq!(bid_cmd.matcher_events[0].matched_order_id, 1);
+    assert_eq!(bid_cmd.matcher_events[0].size, 5);
+    assert_eq!(bid_cmd.matcher_events[1].matched_order_id, 2);
+    assert_eq!(bid_cmd.matcher_events[1].size, 3);
+
+    assert_eq!(book.get_total_ask_volume(), 22);
+    assert_eq!(book.get_l2_data(1).ask_volumes[0], 12); // 新切片 5 + 普通单 7
+}
+
+#[test]
+fn test_iceberg_replenish_size() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    // 首次显示 10，之后每次刷新显示 4
+    let mut iceberg_cmd = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        size: 30,
+        action: OrderAction::Ask,
+        order_type: OrderType::Iceberg,
+        reserve_price: 10000,
+        timestamp: 1000,
+        visible_size: Some(10),
+        replenish_size: Some(4),
+        ..Default::default()
+    };
+    book.new_order(&mut iceberg_cmd);
+    assert_eq!(book.get_l2_data(1).ask_volumes[0], 10);
+
+    let mut bid_cmd = OrderCommand {
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10000,
+        size: 10,
+        action: OrderAction::Bid,
+        order_type: OrderType::Ioc,
+        reserve_price: 10000,
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut bid_cmd);
+    assert_eq!(book.get_l2_data(1).ask_volumes[0], 4);
+
+    // 买 6：跨越一次刷新，同一冰山单的成交合并为一个事件
+    let mut bid_cmd2 = OrderCommand {
+        uid: 2,
+        order_id: 3,
+        symbol: 1,
+        price: 10000,
+        size: 6,
+        action: OrderAction::Bid,
+        order_type: OrderType::Ioc,
+        reserve_price: 10000,
+        timestamp: 1002,
+        ..Default::default()
+    };
+    book.new_order(&mut bid_cmd2);
+    assert_eq!(bid_cmd2.matcher_events.len(), 1);
+    assert_eq!(bid_cmd2.matcher_events[0].size, 6);
+    assert_eq!(book.get_total_ask_volume(), 14);
+    assert_eq!(book.get_l2_data(1).ask_volumes[0], 2);
+}
+
+#[test]
+fn test_cascading_stop_orders() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    // 买盘：100 / 90 / 80
+    for (order_id, price) in [(1, 100), (2, 90), (3, 80)] {
+        let mut bid_cmd = OrderCommand {
+            uid: 2,
+            order_id,
+            symbol: 1,
+            price,
+            size: 1,
+            action: OrderAction::Bid,
+            order_type: OrderType::Gtc,
+            reserve_price: price,
+            timestamp: 1000,
+            ..Default::default()
+        };
+        book.new_order(&mut bid_cmd);
+    }
+
+    // 止损卖单：成交价跌到 100 时触发 11、12，12 成交于 90 后再触发 10
+    for (order_id, stop_price, price) in [(10, 90, 80), (11, 100, 100), (12, 100, 90)] {
+        let mut stop_cmd = OrderCommand {
+            uid: 1,
+            order_id,
+            symbol: 1,
+            price,
+            size: 1,
+            action: OrderAction::Ask,
+            order_type: OrderType::StopLimit,
+            stop_price: Some(stop_price),
+            timestamp: 1001,
+            ..Default::default()
+        };
+        book.new_order(&mut stop_cmd);
+    }
+
+    let mut ask_cmd = OrderCommand {
+        uid: 3,
+        order_id: 20,
+        symbol: 1,
+        price: 100,
+        size: 1,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        timestamp: 1002,
+        ..Default::default()
+    };
+    book.new_order(&mut ask_cmd);
+
+    // 连锁触发不 panic，买盘全部成交，11 以 100 挂出
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_total_ask_volume(), 1);
+    assert_eq!(book.get_order_by_id(11), Some((100, OrderAction::Ask)));
+}
+
+#[test]
+fn test_move_iceberg_keeps_order_attributes() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    let mut iceberg_cmd = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10100,
+        size: 20,
+        action: OrderAction::Ask,
+        order_type: OrderType::Iceberg,
+        timestamp: 1000,
+        visible_size: Some(5),
+        ..Default::default()
+    };
+    book.new_order(&mut iceberg_cmd);
+
+    let mut bid_cmd = OrderCommand {
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10000,
+        size: 8,
+        action: OrderAction::Bid,
+        order_type: OrderType::Gtc,
+        reserve_price: 10000,
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut bid_cmd);
+
+    // 改价命令只携带新价格，其余字段不应覆盖原订单
+    let mut move_cmd = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        size: -1,
+        visible_size: Some(-5),
+        timestamp: 1002,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::Success);
+    assert_eq!(move_cmd.action, OrderAction::Ask);
+
+    let trades: Size = move_cmd.matcher_events.iter().map(|e| e.size).sum();
+    assert_eq!(trades, 8);
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_total_ask_volume(), 12);
+    assert_eq!(book.get_order_by_id(1), Some((10000, OrderAction::Ask)));
+    // 剩余 12 仍按冰山单挂出，只显示 5
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![5]);
+}
+
+#[test]
+fn test_move_post_only_across_book_rejected() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    let mut ask_cmd = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        size: 10,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        timestamp: 1000,
+        ..Default::default()
+    };
+    book.new_order(&mut ask_cmd);
+
+    let mut bid_cmd = OrderCommand {
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 9900,
+        size: 5,
+        action: OrderAction::Bid,
+        order_type: OrderType::PostOnly,
+        reserve_price: 10000,
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut bid_cmd);
+    assert!(bid_cmd.matcher_events.is_empty());
+
+    // 改价到卖一会吃单，Post-Only 拒绝，不产生成交
+    let mut move_cmd = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10000,
+        timestamp: 1002,
+        ..Default::default()
+    };
+    book.move_order(&mut move_cmd);
+
+    assert_eq!(move_cmd.matcher_events.len(), 1);
+    assert_eq!(move_cmd.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(move_cmd.matcher_events[0].size, 5);
+    assert_eq!(book.get_total_ask_volume(), 10);
+    assert_eq!(book.get_total_bid_volume(), 0);
+}
+
+#[test]
+fn test_move_triggered_stop_limit_keeps_resting() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+    book.new_order(&mut gtx_order(1, 1, 100, 1, OrderAction::Ask, OrderType::Gtc));
+    let mut stop = OrderCommand { stop_price: Some(100), ..gtx_order(2, 10, 105, 5, OrderAction::Bid, OrderType::StopLimit) };
+    book.new_order(&mut stop);
+
+    // 成交价 100 触发止损限价买单，对手盘已空，按限价 105 挂单
+    book.new_order(&mut gtx_order(3, 2, 100, 1, OrderAction::Bid, OrderType::Ioc));
+    assert_eq!(book.get_total_bid_volume(), 5);
+
+    // 触发后的订单按普通限价单改价，仍在订单簿中
+    let mut move_cmd = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 2,
+        order_id: 10,
+        symbol: 1,
+        price: 103,
+        timestamp: 2000,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::Success);
+    let moved = book.get_open_order(10).unwrap();
+    assert_eq!((moved.order_type, moved.price, moved.remaining), (OrderType::Gtc, 103, 5));
+    assert_eq!(book.get_total_bid_volume(), 5);
+
+    let mut ask = gtx_order(4, 3, 103, 5, OrderAction::Ask, OrderType::Gtc);
+    book.new_order(&mut ask);
+    assert_eq!(ask.matcher_events.len(), 1);
+    assert_eq!((ask.matcher_events[0].matched_order_id, ask.matcher_events[0].size), (10, 5));
+    assert_eq!(book.get_total_bid_volume(), 0);
+}
+
+/// 买盘挂单后下止损市价卖单，再用一笔 100 的成交触发
+fn trigger_stop_market(spec: CoreSymbolSpecification, bids: &[(Price, Size)], stop_size: Size) -> AdvancedOrderBook {
+    let mut book = AdvancedOrderBook::new(spec);
+    for (i, &(price, size)) in bids.iter().enumerate() {
+        let mut bid_cmd = OrderCommand {
+            uid: 2,
+            order_id: 1 + i as OrderId,
+            symbol: 1,
+            price,
+            size,
+            action: OrderAction::Bid,
+            order_type: OrderType::Gtc,
+            reserve_price: price,
+            timestamp: 1000,
+            ..Default::default()
+        };
+        book.new_order(&mut bid_cmd);
+    }
+
+    let mut stop_cmd = OrderCommand {
+        uid: 1,
+        order_id: 10,
+        symbol: 1,
+        size: stop_size,
+        action: OrderAction::Ask,
+        order_type: OrderType::StopMarket,
+        stop_price: Some(100),
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut stop_cmd);
+
+    let mut ask_cmd = OrderCommand {
+        uid: 3,
+        order_id: 20,
+        symbol: 1,
+        price: 100,
+        size: 1,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        timestamp: 1002,
+        ..Default::default()
+    };
+    book.new_order(&mut ask_cmd);
+    book
+}
+
+#[test]
+fn test_stop_market_sweeps_without_resting() {
+    let book = trigger_stop_market(create_symbol_spec(), &[(100, 2), (95, 3), (90, 5)], 20);
+
+    // 触发后按市价扫完全部买盘，剩余 11 不挂单
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_total_ask_volume(), 0);
+    assert!(book.get_open_order(10).is_none());
+}
+
+#[test]
+fn test_stop_market_respects_slippage_bound() {
+    let spec = CoreSymbolSpecification {
+        market_max_slippage_bps: 1000,
+        ..create_symbol_spec()
+    };
+    let book = trigger_stop_market(spec, &[(100, 2), (95, 3), (80, 5)], 20);
+
+    // 保护价 100 * (1 - 10%) = 90，80 档不成交
+    assert_eq!(book.get_l2_data(5).bid_prices, vec![80]);
+    assert_eq!(book.get_total_bid_volume(), 5);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
+
+#[test]
+fn test_stop_market_rejected_on_empty_book() {
+    let book = trigger_stop_market(create_symbol_spec(), &[(100, 1)], 5);
+
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_total_ask_volume(), 0);
+    assert!(book.get_open_order(10).is_none());
+}
+
+fn gtx_order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_gtx_order() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+    book.new_order(&mut gtx_order(1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc));
+
+    // 会与卖一成交：整单撤销，不吃单
+    let mut crossing = gtx_order(2, 2, 10000, 5, OrderAction::Bid, OrderType::Gtx);
+    book.new_order(&mut crossing);
+    assert_eq!(crossing.matcher_events.len(), 1);
+    assert_eq!(crossing.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(crossing.matcher_events[0].size, 5);
+    assert_eq!(book.get_total_ask_volume(), 10);
+
+    // 不会成交：挂单，作为 maker 部分成交
+    let mut gtx = gtx_order(2, 3, 9990, 8, OrderAction::Bid, OrderType::Gtx);
+    gtx.reserve_price = 10000;
+    book.new_order(&mut gtx);
+    assert!(gtx.matcher_events.is_empty());
+    book.new_order(&mut gtx_order(3, 4, 9990, 3, OrderAction::Ask, OrderType::Ioc));
+    assert_eq!(book.get_total_bid_volume(), 5);
+
+    // 改价到会成交的价格：剩余 5 被撤销，卖盘不变
+    let mut move_cmd = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 2,
+        order_id: 3,
+        symbol: 1,
+        price: 10000,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::Success);
+    let events: Vec<_> = move_cmd.matcher_events.iter().map(|e| (e.event_type, e.size)).collect();
+    assert_eq!(events, vec![(MatcherEventType::Reject, 5)]);
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_total_ask_volume(), 10);
+    assert!(book.get_open_order(3).is_none());
+}
+
+#[test]
+fn test_reduce_iceberg_keeps_priority() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+    let ask = |order_id: OrderId, size: Size, visible_size: Option<Size>| OrderCommand {
+        uid: order_id,
+        order_id,
+        symbol: 1,
+        price: 10000,
+        size,
+        action: OrderAction::Ask,
+        order_type: if visible_size.is_some() { OrderType::Iceberg } else { OrderType::Gtc },
+        timestamp: 1000 + order_id as i64,
+        visible_size,
+        ..Default::default()
+    };
+    book.new_order(&mut ask(1, 20, Some(5)));
+    book.new_order(&mut ask(2, 10, None));
+
+    let mut reduce = OrderCommand { command: OrderCommandType::ReduceOrder, uid: 1, order_id: 1, symbol: 1, size: 17, ..Default::default() };
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+    let event = &reduce.matcher_events[0];
+    assert_eq!((event.event_type, event.size, event.remaining_size), (MatcherEventType::Reduce, 17, 3));
+    assert_eq!(reduce.action, OrderAction::Ask);
+    // 显示切片收缩到剩余数量
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![13]);
+    assert_eq!(book.get_total_ask_volume(), 13);
+
+    // 减量不丢失时间优先级
+    let mut bid = OrderCommand {
+        uid: 3,
+        order_id: 3,
+        symbol: 1,
+        price: 10000,
+        size: 2,
+        action: OrderAction::Bid,
+        order_type: OrderType::Ioc,
+        reserve_price: 10000,
+        timestamp: 2000,
+        ..Default::default()
+    };
+    book.new_order(&mut bid);
+    assert_eq!(bid.matcher_events[0].matched_order_id, 1);
+    assert_eq!(book.get_open_order(1).map(|order| order.remaining), Some(1));
+}
+
+#[test]
+fn test_reduce_untriggered_stop_order() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+    let mut stop = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 11000,
+        size: 10,
+        action: OrderAction::Bid,
+        order_type: OrderType::StopLimit,
+        reserve_price: 11500,
+        timestamp: 1000,
+        stop_price: Some(10500),
+        ..Default::default()
+    };
+    book.new_order(&mut stop);
+
+    let mut reduce = OrderCommand { command: OrderCommandType::ReduceOrder, uid: 2, order_id: 1, symbol: 1, size: 4, ..Default::default() };
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::MatchingUnauthorizedAction);
+
+    reduce.uid = 1;
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+    let event = &reduce.matcher_events[0];
+    assert_eq!((event.size, event.price, event.bidder_hold_price, event.remaining_size), (4, 11000, 11500, 6));
+
+    let mut reduce = OrderCommand { command: OrderCommandType::ReduceOrder, uid: 1, order_id: 1, symbol: 1, size: 6, ..Default::default() };
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+    assert_eq!(reduce.matcher_events[0].remaining_size, 0);
+    let mut cancel = OrderCommand { uid: 1, order_id: 1, symbol: 1, ..Default::default() };
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::MatchingUnknownOrderId);
+}
+
+#[test]
+fn test_fok_ask_fills_against_best_bids() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec());
+
+    for (order_id, price) in [(1, 9900), (2, 10000)] {
+        let mut bid_cmd = OrderCommand {
+            uid: 1,
+            order_id,
+            symbol: 1,
+            price,
+            size: 5,
+            action: OrderAction::Bid,
+            order_type: OrderType::Gtc,
+            reserve_price: price,
+            timestamp: 1000,
+            ..Default::default()
+        };
+        book.new_order(&mut bid_cmd);
+    }
+
+    // 低于限价的买一档不影响高价档的可成交量
+    let mut fok_cmd = OrderCommand {
+        uid: 2,
+        order_id: 3,
+        symbol: 1,
+        price: 9950,
+        size: 5,
+        action: OrderAction::Ask,
+        order_type: OrderType::Fok,
+        timestamp: 1001,
+        ..Default::default()
+    };
+    book.new_order(&mut fok_cmd);
+
+    assert_eq!(fok_cmd.matcher_events.len(), 1);
+    assert_eq!(fok_cmd.matcher_events[0].event_type, MatcherEventType::Trade);
+    assert_eq!(fok_cmd.matcher_events[0].matched_order_id, 2);
+    assert_eq!(book.get_total_bid_volume(), 5);
+}
@@ -0,0 +1,230 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+const BOOK_KINDS: [OrderBookKind; 4] = [OrderBookKind::Naive, OrderBookKind::Direct, OrderBookKind::DirectOptimized, OrderBookKind::Advanced];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 2,
+        maker_fee: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn amend(uid: UserId, order_id: OrderId, price: Price, size: Size) -> OrderCommand {
+    OrderCommand { command: OrderCommandType::AmendOrder, uid, order_id, symbol: 100, price, size, timestamp: 5000, ..Default::default() }
+}
+
+fn ioc(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand { order_type: OrderType::Ioc, ..order(uid, order_id, price, size, action) }
+}
+
+fn matched(cmd: &OrderCommand) -> Vec<(OrderId, Size)> {
+    cmd.matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| (e.matched_order_id, e.size))
+        .collect()
+}
+
+#[test]
+fn test_size_decrease_keeps_priority() {
+    for kind in BOOK_KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut order(1, 1, 100, 10, OrderAction::Ask));
+        book.new_order(&mut order(2, 2, 100, 10, OrderAction::Ask));
+
+        let mut cmd = amend(1, 1, 0, 4);
+        assert_eq!(book.amend_order(&mut cmd), CommandResultCode::Success, "{:?}", kind);
+        assert_eq!(cmd.matcher_events.len(), 1);
+        let event = &cmd.matcher_events[0];
+        assert_eq!((event.event_type, event.size, event.price, event.remaining_size), (MatcherEventType::Amend, 6, 100, 4));
+        assert_eq!(cmd.action, OrderAction::Ask);
+        assert_eq!(book.get_total_ask_volume(), 14);
+
+        let mut taker = ioc(3, 3, 100, 3, OrderAction::Bid);
+        book.new_order(&mut taker);
+        assert_eq!(matched(&taker), vec![(1, 3)], "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_size_increase_and_price_change_lose_priority() {
+    for kind in BOOK_KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut order(1, 1, 100, 10, OrderAction::Ask));
+        book.new_order(&mut order(2, 2, 100, 10, OrderAction::Ask));
+
+        // 增加数量：重新排到同价位队尾
+        let mut cmd = amend(1, 1, 0, 15);
+        assert_eq!(book.amend_order(&mut cmd), CommandResultCode::Success, "{:?}", kind);
+        assert_eq!((cmd.matcher_events[0].size, cmd.matcher_events[0].remaining_size), (0, 15));
+        let mut taker = ioc(3, 3, 100, 12, OrderAction::Bid);
+        book.new_order(&mut taker);
+        assert_eq!(matched(&taker), vec![(2, 10), (1, 2)], "{:?}", kind);
+
+        // 改价并减量：按新价格重新撮合，改单事件在成交之前
+        book.new_order(&mut order(4, 4, 98, 5, OrderAction::Bid));
+        let mut cmd = amend(1, 1, 98, 8);
+        assert_eq!(book.amend_order(&mut cmd), CommandResultCode::Success);
+        let types: Vec<_> = cmd.matcher_events.iter().map(|e| e.event_type).collect();
+        assert_eq!(types, vec![MatcherEventType::Amend, MatcherEventType::Trade], "{:?}", kind);
+        assert_eq!((cmd.matcher_events[0].size, cmd.matcher_events[0].price), (5, 98));
+        assert_eq!(matched(&cmd), vec![(4, 5)]);
+        assert_eq!(book.get_l2_data(5).ask_prices, vec![98]);
+        assert_eq!(book.get_open_order(1).unwrap().remaining, 3);
+    }
+}
+
+#[test]
+fn test_amend_rejections() {
+    for kind in BOOK_KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut OrderCommand { reserve_price: 105, ..order(1, 1, 100, 10, OrderAction::Bid) });
+
+        assert_eq!(book.amend_order(&mut amend(2, 1, 0, 5)), CommandResultCode::MatchingUnauthorizedAction);
+        assert_eq!(book.amend_order(&mut amend(1, 9, 0, 5)), CommandResultCode::MatchingUnknownOrderId);
+        // 现货买单新价格不能超过冻结价格
+        assert_eq!(book.amend_order(&mut amend(1, 1, 106, 10)), CommandResultCode::RiskInvalidReserveBidPrice, "{:?}", kind);
+        let mut cmd = amend(1, 1, 105, 10);
+        assert_eq!(book.amend_order(&mut cmd), CommandResultCode::Success);
+        assert_eq!(cmd.matcher_events[0].bidder_hold_price, 105);
+        assert_eq!(book.get_open_order(1).map(|o| (o.price, o.reserve_price, o.remaining)), Some((105, 105, 10)));
+    }
+}
+
+fn core_with_users() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(spec());
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for (currency, amount) in [(1, 10_000), (2, 100)] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: amount,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn balance(core: &ExchangeCore, uid: UserId, currency: Currency) -> i64 {
+    core.serialize_state().pipeline_state.risk_engines[0].get_user(uid).unwrap().accounts[&currency]
+}
+
+#[test]
+fn test_amend_adjusts_holds() {
+    let mut core = core_with_users();
+    assert_eq!(core.submit_command(order(1, 1, 100, 10, OrderAction::Bid)).result_code, CommandResultCode::Success);
+    // 买单按 (价格 + taker 手续费) 冻结
+    assert_eq!(balance(&core, 1, 1), 10_000 - 10 * 102);
+
+    let result = core.submit_command(amend(1, 1, 0, 15));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, 1), 10_000 - 15 * 102);
+
+    let result = core.submit_command(amend(1, 1, 90, 6));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    // 改价不改变冻结价，减少的数量按冻结价返还
+    assert_eq!(balance(&core, 1, 1), 10_000 - 6 * 102);
+    core.verify_invariants().unwrap();
+
+    // 余额不足时拒绝增加，订单保持不变
+    let result = core.submit_command(amend(1, 1, 0, 1_000));
+    assert_eq!(result.result_code, CommandResultCode::RiskNsf);
+    assert!(result.matcher_events.is_empty());
+    assert_eq!(balance(&core, 1, 1), 10_000 - 6 * 102);
+
+    // 卖单追加冻结 base
+    core.submit_command(order(2, 2, 120, 10, OrderAction::Ask));
+    assert_eq!(core.submit_command(amend(2, 2, 0, 30)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 2, 2), 70);
+
+    // 改他人的订单与撤单、改价、减量一致，拒绝为 MatchingUnauthorizedAction
+    assert_eq!(core.submit_command(amend(2, 1, 0, 30)).result_code, CommandResultCode::MatchingUnauthorizedAction);
+    assert_eq!(core.submit_command(amend(1, 1, 0, 0)).result_code, CommandResultCode::ValidationInvalidSize);
+
+    // 改价成交后按成交结算
+    assert_eq!(core.submit_command(amend(2, 2, 90, 30)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1, 2), 106);
+    // 买单为 maker，按 maker 手续费结算
+    assert_eq!(balance(&core, 1, 1), 10_000 - 6 * 90 - 6);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_amend_with_unsettled_fills_in_flight() {
+    let mut risk = RiskEngine::new(0, 1);
+    let mut matching = MatchingEngineRouter::new(0, 1);
+    risk.add_symbol(spec());
+    matching.add_symbol(spec());
+    let mut settle = |cmd: &mut OrderCommand, risk: &mut RiskEngine| {
+        risk.pre_process(cmd);
+        matching.process_order(cmd);
+        risk.post_process(cmd);
+    };
+    for uid in [1, 2] {
+        settle(&mut OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() }, &mut risk);
+        for currency in [1, 2] {
+            settle(
+                &mut OrderCommand {
+                    command: OrderCommandType::BalanceAdjustment,
+                    uid,
+                    symbol: currency,
+                    price: 10_000,
+                    order_id: uid * 10 + currency as OrderId,
+                    ..Default::default()
+                },
+                &mut risk,
+            );
+        }
+    }
+    settle(&mut order(1, 1, 100, 10, OrderAction::Ask), &mut risk);
+
+    // 成交尚未在 R2 结算时改单：R1 按记录的剩余 10 只追加冻结 2，撮合按实际剩余 6 最多增加 2
+    let mut taker = ioc(2, 2, 100, 4, OrderAction::Bid);
+    risk.pre_process(&mut taker);
+    matching.process_order(&mut taker);
+    let mut cmd = amend(1, 1, 0, 12);
+    risk.pre_process(&mut cmd);
+    assert_eq!(cmd.amend_hold, 2);
+    matching.process_order(&mut cmd);
+    risk.post_process(&mut taker);
+    risk.post_process(&mut cmd);
+
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!((cmd.matcher_events[0].size, cmd.matcher_events[0].remaining_size), (0, 8));
+    let user = risk.get_user(1).unwrap();
+    assert_eq!(user.open_orders[&1].remaining, 8);
+    assert_eq!(user.accounts[&2], 10_000 - 12);
+    assert_eq!(matching.get_l2_data(100, 1).unwrap().ask_volumes, vec![8]);
+}
@@ -0,0 +1,110 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::risk_engine::RiskLimits;
+
+const HALF_MAX: i64 = i64::MAX / 2;
+const DEPOSIT: i64 = i64::MAX / 8;
+
+fn setup(limits: RiskLimits) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        risk_limits: limits,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 1,
+        maker_fee: 1,
+        ..Default::default()
+    });
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(adjustment(uid, currency, DEPOSIT, uid as OrderId * 10 + currency as OrderId));
+        }
+    }
+    core
+}
+
+fn adjustment(uid: UserId, currency: Currency, amount: i64, transaction_id: OrderId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid,
+        symbol: currency,
+        price: amount,
+        order_id: transaction_id,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn balance(core: &ExchangeCore, uid: UserId, currency: Currency) -> i64 {
+    core.serialize_state().pipeline_state.risk_engines[0].get_user(uid).unwrap().accounts[&currency]
+}
+
+#[test]
+fn test_order_hold_overflow_is_rejected() {
+    let mut core = setup(RiskLimits::default());
+
+    // size * price 超出 i64：拒单且不冻结
+    let rejected = core.submit_command(order(1002, 1, HALF_MAX, 3, OrderAction::Bid));
+    assert_eq!(rejected.result_code, CommandResultCode::RiskAmountOverflow);
+    assert_eq!(balance(&core, 1002, 1), DEPOSIT);
+
+    // 大价格但未溢出的订单正常成交结算
+    let price = DEPOSIT / 4;
+    assert_eq!(core.submit_command(order(1001, 2, price, 2, OrderAction::Ask)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(order(1002, 3, price, 2, OrderAction::Bid)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1001, 1), DEPOSIT + price * 2 - 2);
+    assert_eq!(balance(&core, 1002, 1), DEPOSIT - price * 2 - 2);
+    assert_eq!(balance(&core, 1002, 2), DEPOSIT + 2);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_notional_limit_does_not_wrap() {
+    let mut core = setup(RiskLimits {
+        max_order_notional: 1_000,
+        ..Default::default()
+    });
+    // 金额按 i128 比较，乘积超出 i64 时不会回绕为负数绕过限额
+    let rejected = core.submit_command(order(1001, 1, HALF_MAX, 4, OrderAction::Ask));
+    assert_eq!(rejected.result_code, CommandResultCode::RiskMaxNotionalExceeded);
+}
+
+#[test]
+fn test_balance_adjustment_overflow() {
+    let mut core = setup(RiskLimits::default());
+    let overflow = core.submit_command(adjustment(1001, 1, i64::MAX, 99));
+    assert_eq!(overflow.result_code, CommandResultCode::RiskAmountOverflow);
+    assert_eq!(balance(&core, 1001, 1), DEPOSIT);
+
+    // 溢出的调整未占用交易号，修正金额后可重试
+    assert_eq!(core.submit_command(adjustment(1001, 1, 5, 99)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core, 1001, 1), DEPOSIT + 5);
+    core.verify_invariants().unwrap();
+}
@@ -0,0 +1,152 @@
+use matching_core::api::*;
+use matching_core::core::command_future::PendingResults;
+use matching_core::core::event_pool::EventBufferPool;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::Thread;
+
+/// 极简执行器：唤醒时 unpark 当前线程
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    core
+}
+
+fn add_user(uid: UserId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    }
+}
+
+fn deposit(uid: UserId, currency: Currency, amount: i64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid,
+        symbol: currency,
+        price: amount,
+        order_id: uid as OrderId,
+        ..Default::default()
+    }
+}
+
+fn place(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_async_submit_before_startup_is_ready() {
+    let mut core = setup();
+    let future = core.submit_command_async(add_user(1001));
+    assert!(future.is_ready());
+    assert_eq!(future.wait().result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_wait_returns_processed_command() {
+    let mut core = setup();
+    core.startup();
+
+    for future in [
+        core.submit_command_async(add_user(1001)),
+        core.submit_command_async(deposit(1001, 2, 100)),
+        core.submit_command_async(add_user(1002)),
+        core.submit_command_async(deposit(1002, 1, 100_000)),
+    ] {
+        assert_eq!(future.wait().result_code, CommandResultCode::Success);
+    }
+
+    // 未等待结果的命令不影响后续命令的 future
+    core.submit_command(place(1001, 1, 1000, 30, OrderAction::Ask));
+    let taker = core.submit_command_async(place(1002, 2, 1000, 10, OrderAction::Bid)).wait();
+    assert_eq!(taker.result_code, CommandResultCode::Success);
+    let trades: Vec<(OrderId, Size)> = taker.matcher_events.iter().map(|e| (e.matched_order_id, e.size)).collect();
+    assert_eq!(trades, vec![(1, 10)]);
+
+    let rejected = core.submit_command_async(place(1002, 3, 1000, 1_000, OrderAction::Bid)).wait();
+    assert_eq!(rejected.result_code, CommandResultCode::RiskNsf);
+}
+
+#[test]
+fn test_future_can_be_awaited() {
+    let mut core = setup();
+    core.startup();
+
+    let result = block_on(async {
+        core.submit_command_async(add_user(1001)).await;
+        core.submit_command_async(add_user(1001)).await
+    });
+    assert_eq!(result.result_code, CommandResultCode::UserMgmtUserAlreadyExists);
+
+    let book = block_on(core.submit_command_async(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    }));
+    assert_eq!(book.result_code, CommandResultCode::Success);
+    assert!(book.market_data.unwrap().ask_prices.is_empty());
+}
+
+#[test]
+fn test_wait_completed_blocks_until_results_catch_up() {
+    let pending = Arc::new(PendingResults::new(Arc::new(EventBufferPool::new(16))));
+    let waiter = {
+        let pending = pending.clone();
+        std::thread::spawn(move || pending.wait_completed(1))
+    };
+
+    pending.complete(0, &add_user(1));
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(!waiter.is_finished());
+
+    pending.complete(1, &add_user(2));
+    waiter.join().unwrap();
+    assert_eq!(pending.completed(), 1);
+}
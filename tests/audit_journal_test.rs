@@ -0,0 +1,123 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::journal::ResultJournaler;
+use std::path::PathBuf;
+
+fn paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("matching_core_audit_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    (dir.join("exchange.wal"), dir.join("results.log"))
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    core
+}
+
+fn submit_setup_commands(core: &mut ExchangeCore) -> Vec<OrderCommand> {
+    let mut results = Vec::new();
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        results.push(core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        }));
+        results.push(core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 100_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        }));
+    }
+    results
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_audit_trail_pairs_commands_with_results() {
+    let (journal, results) = paths("pairs");
+    let mut core = setup();
+    core.enable_journaling(&journal).unwrap();
+    core.enable_result_journaling(&results).unwrap();
+
+    let mut expected = submit_setup_commands(&mut core);
+    expected.push(place(&mut core, 1001, 1, 100, 30, OrderAction::Ask, OrderType::Gtc));
+    expected.push(place(&mut core, 1001, 2, 101, 20, OrderAction::Ask, OrderType::Gtc));
+    // 吃掉第一档并部分成交第二档，剩余 IOC 部分被拒绝
+    expected.push(place(&mut core, 1002, 3, 101, 60, OrderAction::Bid, OrderType::Ioc));
+    // 资金不足
+    expected.push(place(&mut core, 1002, 4, 100, 10_000, OrderAction::Bid, OrderType::Gtc));
+
+    let trail = ResultJournaler::read_audit_trail(&journal, &results).unwrap();
+    assert_eq!(trail.len(), expected.len());
+    for (i, ((seq, cmd, outcome), processed)) in trail.iter().zip(&expected).enumerate() {
+        assert_eq!(*seq, i as u64 + 1);
+        assert_eq!(cmd.command, processed.command);
+        assert_eq!(cmd.order_id, processed.order_id);
+        // 命令日志记录的是处理前的命令
+        assert_eq!(cmd.result_code, CommandResultCode::New);
+        assert!(cmd.matcher_events.is_empty());
+
+        assert_eq!(outcome.result_code, processed.result_code);
+        assert_eq!(outcome.matcher_events.len(), processed.matcher_events.len());
+    }
+
+    // 还原成交明细
+    let (_, _, fills) = &trail[6];
+    let trades: Vec<(OrderId, Price, Size)> = fills
+        .matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| (e.matched_order_id, e.price, e.size))
+        .collect();
+    assert_eq!(trades, vec![(1, 100, 30), (2, 101, 20)]);
+    assert!(fills.matcher_events.iter().any(|e| e.event_type == MatcherEventType::Reject && e.size == 10));
+    assert_eq!(trail[7].2.result_code, CommandResultCode::RiskNsf);
+}
+
+#[test]
+fn test_commands_without_results_are_skipped() {
+    let (journal, results) = paths("skipped");
+    let mut core = setup();
+    core.enable_journaling(&journal).unwrap();
+    submit_setup_commands(&mut core);
+
+    // 结果日志在第 5 条命令开始记录
+    core.enable_result_journaling(&results).unwrap();
+    place(&mut core, 1001, 1, 100, 30, OrderAction::Ask, OrderType::Gtc);
+    place(&mut core, 1002, 2, 100, 10, OrderAction::Bid, OrderType::Gtc);
+
+    let outcomes = ResultJournaler::read_outcomes(&results).unwrap();
+    assert_eq!(outcomes.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![5, 6]);
+
+    let trail = ResultJournaler::read_audit_trail(&journal, &results).unwrap();
+    assert_eq!(trail.iter().map(|(seq, cmd, _)| (*seq, cmd.order_id)).collect::<Vec<_>>(), vec![(5, 1), (6, 2)]);
+    assert_eq!(trail[1].2.matcher_events.len(), 1);
+}
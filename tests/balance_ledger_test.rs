@@ -0,0 +1,77 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn adjustment(uid: UserId, currency: Currency, amount: i64, transaction_id: OrderId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid,
+        symbol: currency,
+        price: amount,
+        order_id: transaction_id,
+        timestamp: 1000 + transaction_id as i64,
+        ..Default::default()
+    }
+}
+
+fn setup(risk_engines_num: usize) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        risk_engines_num,
+        ..Default::default()
+    });
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+#[test]
+fn test_duplicate_adjustments_are_not_applied() {
+    let mut core = setup(1);
+    let cases = [
+        (adjustment(1001, 1, 500, 1), CommandResultCode::Success),
+        (adjustment(1001, 1, 500, 1), CommandResultCode::UserMgmtAdjustmentAlreadyApplied),
+        (adjustment(1001, 2, 500, 1), CommandResultCode::UserMgmtAdjustmentConflict),
+        (adjustment(1001, 1, -200, 2), CommandResultCode::Success),
+        // 交易号按用户区分
+        (adjustment(1002, 1, 300, 1), CommandResultCode::Success),
+        (adjustment(9999, 1, 300, 3), CommandResultCode::AuthInvalidUser),
+    ];
+    for (i, (cmd, expected)) in cases.into_iter().enumerate() {
+        assert_eq!(core.submit_command(cmd).result_code, expected, "case {}", i);
+    }
+    core.verify_invariants().unwrap();
+
+    let ledger = core.balance_ledger(1001).unwrap();
+    assert_eq!(
+        ledger,
+        vec![
+            LedgerEntry { transaction_id: 1, currency: 1, amount: 500, balance: 500, timestamp: 1001 },
+            LedgerEntry { transaction_id: 2, currency: 1, amount: -200, balance: 300, timestamp: 1002 },
+        ]
+    );
+    assert!(core.balance_ledger(9999).is_err());
+
+    // 快照恢复后重放的调整不会重复入账
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    let replay = restored.submit_command(adjustment(1001, 1, 500, 1));
+    assert_eq!(replay.result_code, CommandResultCode::UserMgmtAdjustmentAlreadyApplied);
+    assert_eq!(restored.balance_ledger(1001).unwrap(), ledger);
+    restored.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_ledger_query_across_risk_shards() {
+    let mut core = setup(2);
+    for uid in [1001, 1002] {
+        core.submit_command(adjustment(uid, 1, uid as i64, 7));
+    }
+    for uid in [1001, 1002] {
+        let ledger = core.balance_ledger(uid).unwrap();
+        assert_eq!(ledger.iter().map(|e| (e.transaction_id, e.amount)).collect::<Vec<_>>(), vec![(7, uid as i64)]);
+    }
+}
@@ -0,0 +1,147 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 2,
+        maker_fee: 1,
+        ..Default::default()
+    }
+}
+
+/// 交易对 100、101 分属两个撮合分片，用户 1、2 分属两个风控分片
+fn setup(shards: usize) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig { matching_engines_num: shards, risk_engines_num: shards, ..Default::default() });
+    core.add_symbol(spec(100));
+    core.add_symbol(spec(101));
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 10_000,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn leg(order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand { order_id, symbol, price, reserve_price: price, size, action, order_type, ..Default::default() }
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order: OrderCommand) {
+    let order = OrderCommand { command: OrderCommandType::PlaceOrder, uid, timestamp: 100, ..order };
+    assert_eq!(core.submit_command(order).result_code, CommandResultCode::Success);
+}
+
+fn balance(core: &ExchangeCore, uid: UserId, currency: Currency) -> i64 {
+    core.serialize_state()
+        .pipeline_state
+        .risk_engines
+        .iter()
+        .find_map(|engine| engine.get_user(uid).map(|user| user.accounts.get(&currency).copied().unwrap_or(0)))
+        .unwrap()
+}
+
+fn depth(core: &mut ExchangeCore, symbol: SymbolId) -> (Vec<Size>, Vec<Size>) {
+    let book = core.submit_command(OrderCommand { command: OrderCommandType::OrderBookRequest, symbol, size: 5, ..Default::default() });
+    let book = book.market_data.unwrap();
+    (book.ask_volumes, book.bid_volumes)
+}
+
+#[test]
+fn test_basket_legs_match_in_one_pass() {
+    for shards in [1, 2] {
+        let mut core = setup(shards);
+        place(&mut core, 2, leg(1, 100, 100, 5, OrderAction::Ask, OrderType::Gtc));
+        place(&mut core, 2, leg(2, 101, 90, 5, OrderAction::Bid, OrderType::Gtc));
+
+        // 买入 100 的同时卖出 101，第三条腿挂单
+        let basket = OrderCommand::place_basket(
+            1,
+            &[
+                leg(11, 100, 100, 5, OrderAction::Bid, OrderType::Ioc),
+                leg(12, 101, 90, 3, OrderAction::Ask, OrderType::Ioc),
+                leg(13, 101, 120, 4, OrderAction::Ask, OrderType::Gtc),
+            ],
+            200,
+        );
+        let result = core.submit_command(basket);
+        assert_eq!(result.result_code, CommandResultCode::Success, "{} shards", shards);
+        let codes: Vec<_> = result.decode_basket().unwrap().iter().map(|leg| leg.result_code).collect();
+        assert_eq!(codes, vec![CommandResultCode::Success; 3]);
+
+        // 事件以所属的腿为 taker，成交编号按交易对分配
+        let mut trades: Vec<_> = result
+            .matcher_events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| (result.event_symbol(index), event.taker(&result).1, event.matched_order_id, event.size, event.trade_id))
+            .collect();
+        trades.sort();
+        assert_eq!(trades, vec![(100, 11, 1, 5, 1), (101, 12, 2, 3, 1)]);
+
+        assert_eq!(balance(&core, 1, 1), 10_000 - 5 * 100 - 5 * 2 + 3 * 90 - 3 * 2);
+        assert_eq!(balance(&core, 1, 2), 10_000 + 5 - 3 - 4);
+        assert_eq!(balance(&core, 2, 1), 10_000 + 5 * 100 - 5 - 3 * 90 - 3 - 2 * (90 + 2));
+        assert_eq!(depth(&mut core, 101), (vec![4], vec![2]));
+        core.verify_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_basket_rejected_when_any_leg_fails_risk() {
+    for shards in [1, 2] {
+        let mut core = setup(shards);
+        place(&mut core, 2, leg(1, 100, 100, 5, OrderAction::Ask, OrderType::Gtc));
+
+        // 第二条腿余额不足：第一条腿的冻结撤销，两条腿都不进入撮合
+        let basket = OrderCommand::place_basket(
+            1,
+            &[leg(11, 100, 100, 5, OrderAction::Bid, OrderType::Gtc), leg(12, 101, 100, 50_000, OrderAction::Ask, OrderType::Gtc)],
+            200,
+        );
+        let result = core.submit_command(basket);
+        assert_eq!(result.result_code, CommandResultCode::RiskNsf, "{} shards", shards);
+        assert!(result.matcher_events.is_empty());
+        assert_eq!((balance(&core, 1, 1), balance(&core, 1, 2)), (10_000, 10_000));
+        assert_eq!(depth(&mut core, 100), (vec![5], vec![]));
+        assert_eq!(depth(&mut core, 101), (vec![], vec![]));
+
+        // 同一组合内重复的订单号同样整单拒绝
+        let basket = OrderCommand::place_basket(
+            1,
+            &[leg(11, 100, 90, 5, OrderAction::Bid, OrderType::Gtc), leg(11, 101, 90, 5, OrderAction::Bid, OrderType::Gtc)],
+            200,
+        );
+        assert_eq!(core.submit_command(basket).result_code, CommandResultCode::MatchingDuplicateOrderId);
+        assert_eq!(balance(&core, 1, 1), 10_000);
+        core.verify_invariants().unwrap();
+        let state = core.serialize_state();
+        assert!(state.pipeline_state.risk_engines.iter().filter_map(|engine| engine.get_user(1)).all(|user| user.open_orders.is_empty()));
+    }
+}
+
+#[test]
+fn test_basket_validation() {
+    let mut core = setup(1);
+    let empty = OrderCommand::place_basket(1, &[], 200);
+    assert_eq!(core.submit_command(empty).result_code, CommandResultCode::ValidationInvalidBasket);
+    let garbage = OrderCommand { binary_data: vec![1, 2, 3], ..OrderCommand::place_basket(1, &[], 200) };
+    assert_eq!(core.submit_command(garbage).result_code, CommandResultCode::ValidationInvalidBasket);
+
+    let basket = OrderCommand::place_basket(1, &[leg(11, 100, 100, 5, OrderAction::Bid, OrderType::Gtc), leg(12, 101, 100, 0, OrderAction::Ask, OrderType::Gtc)], 200);
+    assert_eq!(core.submit_command(basket).result_code, CommandResultCode::ValidationInvalidSize);
+    assert_eq!(balance(&core, 1, 1), 10_000);
+}
@@ -0,0 +1,107 @@
+use matching_core::api::*;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn router(kind: OrderBookKind) -> MatchingEngineRouter {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.enable_bbo_updates();
+    router.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        order_book: Some(kind),
+        ..Default::default()
+    });
+    router
+}
+
+fn place(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+/// (买一, 买一数量, 卖一, 卖一数量, 序号)
+type Top = (Option<Price>, Size, Option<Price>, Size, u64);
+
+fn drain(router: &mut MatchingEngineRouter) -> Vec<Top> {
+    router
+        .drain_bbo_updates()
+        .into_iter()
+        .map(|bbo| (bbo.bid, bbo.bid_size, bbo.ask, bbo.ask_size, bbo.seq))
+        .collect()
+}
+
+#[test]
+fn test_bbo_updates_on_top_of_book_changes() {
+    for kind in KINDS {
+        let mut router = router(kind);
+        router.process_order(&mut place(1, 1, 101, 5, OrderAction::Ask));
+        assert_eq!(drain(&mut router), vec![(None, 0, Some(101), 5, 1)], "{:?}", kind);
+
+        // 非最优档位变化不发布
+        router.process_order(&mut place(1, 2, 102, 5, OrderAction::Ask));
+        router.process_order(&mut place(2, 3, 99, 5, OrderAction::Bid));
+        router.process_order(&mut place(2, 4, 98, 5, OrderAction::Bid));
+        assert_eq!(drain(&mut router), vec![(Some(99), 5, Some(101), 5, 2)], "{:?}", kind);
+
+        // 最优档位数量变化
+        router.process_order(&mut place(2, 5, 101, 2, OrderAction::Bid));
+        assert_eq!(drain(&mut router), vec![(Some(99), 5, Some(101), 3, 3)], "{:?}", kind);
+
+        // 撤销最优买单后买一退到下一档
+        router.process_order(&mut OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            uid: 2,
+            order_id: 3,
+            symbol: 100,
+            ..Default::default()
+        });
+        assert_eq!(drain(&mut router), vec![(Some(98), 5, Some(101), 3, 4)], "{:?}", kind);
+
+        // 用户暂停撤单同样检测
+        router.process_order(&mut OrderCommand {
+            command: OrderCommandType::SuspendUser,
+            result_code: CommandResultCode::ValidForMatchingEngine,
+            uid: 1,
+            ..Default::default()
+        });
+        assert_eq!(drain(&mut router).last(), Some(&(Some(98), 5, None, 0, 6)), "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_advanced_bbo_uses_displayed_size() {
+    let mut router = router(OrderBookKind::Advanced);
+    router.process_order(&mut OrderCommand {
+        order_type: OrderType::Iceberg,
+        visible_size: Some(2),
+        ..place(1, 1, 101, 10, OrderAction::Ask)
+    });
+    assert_eq!(drain(&mut router), vec![(None, 0, Some(101), 2, 1)]);
+
+    // 冰山单切片成交后刷新，显示数量不变时不发布
+    router.process_order(&mut place(2, 2, 101, 2, OrderAction::Bid));
+    assert!(drain(&mut router).is_empty());
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "debug-invariants")]
+
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::{new_order_book, OrderBook};
+use matching_core::workload::{OrderFlowGenerator, PriceDistribution, WorkloadConfig};
+
+fn spec(order_book: OrderBookKind) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        order_book: Some(order_book),
+        ..Default::default()
+    }
+}
+
+fn workload(seed: u64, order_types: Vec<(OrderType, u32)>) -> OrderFlowGenerator {
+    OrderFlowGenerator::new(WorkloadConfig {
+        seed,
+        uids: vec![1, 2, 3],
+        order_types,
+        price: PriceDistribution::Normal { mean: -2.0, std_dev: 4.0 },
+        cancel_rate: 0.1,
+        move_rate: 0.1,
+        reduce_rate: 0.1,
+        gtd_lifetime: 20,
+        ..Default::default()
+    })
+}
+
+fn apply(book: &mut dyn OrderBook, cmd: &mut OrderCommand) {
+    match cmd.command {
+        OrderCommandType::PlaceOrder => book.new_order(cmd),
+        OrderCommandType::CancelOrder => book.cancel_order(cmd),
+        OrderCommandType::MoveOrder => book.move_order(cmd),
+        _ => book.reduce_order(cmd),
+    };
+}
+
+#[test]
+fn test_invariants_hold_for_all_books() {
+    let basic = vec![(OrderType::Gtc, 6), (OrderType::Ioc, 2), (OrderType::Fok, 1)];
+    let kinds = [
+        OrderBookKind::Naive,
+        OrderBookKind::Direct,
+        OrderBookKind::DirectOptimized,
+        OrderBookKind::DirectOptimizedLadder,
+        OrderBookKind::Advanced,
+    ];
+    for kind in kinds {
+        let mut book = new_order_book(kind, spec(kind));
+        for mut cmd in workload(7, basic.clone()).take(3_000) {
+            apply(book.as_mut(), &mut cmd);
+            if let Err(violation) = book.check_invariants() {
+                panic!("{kind:?}: {violation}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_invariants_hold_for_advanced_order_types() {
+    let order_types = vec![
+        (OrderType::Gtc, 4),
+        (OrderType::Ioc, 1),
+        (OrderType::Iceberg, 2),
+        (OrderType::Gtd(0), 2),
+        (OrderType::PostOnly, 1),
+        (OrderType::StopLimit, 1),
+        (OrderType::Market, 1),
+    ];
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(OrderBookKind::Advanced));
+    for (i, mut cmd) in workload(11, order_types).take(3_000).enumerate() {
+        apply(book.as_mut(), &mut cmd);
+        // 过期扫描少于过期订单产生的频率，撮合时会遇到已过期的挂单
+        if i % 100 == 99 {
+            book.expire_orders(cmd.timestamp, &mut cmd.matcher_events);
+        }
+        book.check_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_expired_maker_does_not_cross_book() {
+    // 撮合遇到已过期的挂单时将其移出订单簿，taker 剩余数量挂出后盘口不交叉
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(spec(OrderBookKind::Advanced));
+    for (uid, currency) in [(1, 1), (2, 2)] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: currency, price: 10_000, order_id: uid, ..Default::default() });
+    }
+    let order = |uid, order_id, price, action, order_type, timestamp| OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        reserve_price: price,
+        size: 5,
+        action,
+        order_type,
+        timestamp,
+        ..Default::default()
+    };
+    let gtd = core.submit_command(order(1, 1, 20, OrderAction::Bid, OrderType::Gtd(100), 0));
+    assert_eq!(gtd.result_code, CommandResultCode::Success);
+    let ask = core.submit_command(order(2, 2, 10, OrderAction::Ask, OrderType::Gtc, 200));
+    assert_eq!(ask.result_code, CommandResultCode::Success);
+
+    let book = core.submit_command(OrderCommand { command: OrderCommandType::OrderBookRequest, symbol: 1, size: 10, ..Default::default() });
+    let l2 = book.market_data.unwrap();
+    assert_eq!((l2.bid_prices.len(), l2.ask_prices, l2.ask_volumes), (0, vec![10], vec![5]));
+    core.verify_invariants().unwrap();
+}
@@ -0,0 +1,166 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_router_publishes_views() {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.add_symbol(spec(100));
+    let views = router.enable_book_views(2);
+    assert_eq!(views.symbols(), vec![100]);
+    assert_eq!(views.get(100).unwrap().best_ask(), None);
+
+    let valid = |cmd: OrderCommand| OrderCommand { result_code: CommandResultCode::ValidForMatchingEngine, ..cmd };
+    router.process_order(&mut valid(order(1, 1, 100, 101, 2, OrderAction::Ask)));
+    router.process_order(&mut valid(order(1, 2, 100, 102, 3, OrderAction::Ask)));
+    router.process_order(&mut valid(order(1, 3, 100, 103, 4, OrderAction::Ask)));
+    router.process_order(&mut valid(order(2, 4, 100, 99, 5, OrderAction::Bid)));
+
+    let view = views.get(100).unwrap();
+    assert_eq!(view.asks, vec![(101, 2), (102, 3)]);
+    assert_eq!(view.best_bid(), Some((99, 5)));
+    assert_eq!(view.l2(10), router.get_l2_data(100, 2).unwrap());
+    assert_eq!(views.stats(100), router.stats(100));
+    assert_eq!(view.stats.total_ask_volume, 9);
+
+    // 只读查询不发布新视图；已取得的视图不随后续命令改变
+    router.process_order(&mut OrderCommand { command: OrderCommandType::OrderBookRequest, symbol: 100, size: 5, ..Default::default() });
+    assert_eq!(views.get(100).unwrap().sequence, view.sequence);
+    router.process_order(&mut valid(order(2, 5, 100, 101, 2, OrderAction::Bid)));
+    assert_eq!(view.best_ask(), Some((101, 2)));
+    let latest = views.get(100).unwrap();
+    assert_eq!(latest.best_ask(), Some((102, 3)));
+    assert!(latest.sequence > view.sequence);
+    assert_eq!(latest.stats.last_trade.map(|t| t.price), Some(101));
+}
+
+#[test]
+fn test_views_follow_symbol_lifecycle() {
+    let mut core = ExchangeCore::new(ExchangeConfig { matching_engines_num: 2, ..Default::default() });
+    let views = core.enable_book_views(10).unwrap();
+    core.add_symbol(spec(100));
+    core.add_symbol(spec(101));
+    assert_eq!(views.symbols(), vec![100, 101]);
+
+    core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid: 1, ..Default::default() });
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid: 1,
+        symbol: 2,
+        price: 1_000,
+        order_id: 1,
+        ..Default::default()
+    });
+    core.submit_command(order(1, 1, 101, 50, 10, OrderAction::Ask));
+    assert_eq!(views.l2(101, 5).unwrap().ask_volumes, vec![10]);
+    assert_eq!(views.l2(100, 5).unwrap().ask_volumes, Vec::<Size>::new());
+
+    // 暂停用户批量撤单同样刷新视图
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SuspendUser,
+        uid: 1,
+        service_flags: SUSPEND_USER_CANCEL_ORDERS,
+        ..Default::default()
+    });
+    assert_eq!(views.get(101).unwrap().best_ask(), None);
+
+    core.submit_command(OrderCommand { command: OrderCommandType::DelistSymbol, symbol: 101, ..Default::default() });
+    assert_eq!(views.symbols(), vec![100]);
+    assert!(views.get(101).is_none());
+
+    core.startup();
+    assert!(core.enable_book_views(10).is_err());
+}
+
+#[test]
+fn test_concurrent_readers_see_consistent_views() {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(spec(100));
+    let views = core.enable_book_views(5).unwrap();
+    core.startup();
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000_000,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+
+    let done = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..3)
+        .map(|_| {
+            let (views, done) = (views.clone(), done.clone());
+            std::thread::spawn(move || {
+                let mut last_sequence = 0;
+                let mut reads = 0;
+                while !done.load(Ordering::Acquire) {
+                    let Some(view) = views.get(100) else { continue };
+                    // 视图整体发布：序号不回退，卖盘升序、买盘降序且不交叉
+                    assert!(view.sequence >= last_sequence);
+                    last_sequence = view.sequence;
+                    assert!(view.asks.windows(2).all(|w| w[0].0 < w[1].0));
+                    assert!(view.bids.windows(2).all(|w| w[0].0 > w[1].0));
+                    if let (Some((ask, _)), Some((bid, _))) = (view.best_ask(), view.best_bid()) {
+                        assert!(bid < ask);
+                    }
+                    reads += 1;
+                }
+                reads
+            })
+        })
+        .collect();
+
+    for order_id in 1..=2_000u64 {
+        let action = if order_id % 2 == 0 { OrderAction::Bid } else { OrderAction::Ask };
+        let price = 1_000 + (order_id % 7) as Price - 3;
+        core.submit_command(order(1 + order_id % 2, order_id, 100, price, 1 + (order_id % 5) as Size, action));
+    }
+    // 启动后命令异步处理：查询结果返回时之前的下单均已处理完毕
+    let l2 = core
+        .submit_command_async(OrderCommand { command: OrderCommandType::OrderBookRequest, symbol: 100, size: 5, ..Default::default() })
+        .wait()
+        .market_data
+        .unwrap();
+    done.store(true, Ordering::Release);
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+    assert!(!l2.ask_prices.is_empty() && !l2.bid_prices.is_empty());
+    assert_eq!(views.l2(100, 5).unwrap(), l2);
+}
@@ -0,0 +1,127 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{OrderBook, NaiveOrderBook, DirectOrderBook, DirectOrderBookOptimized};
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn books() -> Vec<Box<dyn OrderBook>> {
+    vec![
+        Box::new(NaiveOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec())),
+    ]
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn trades(cmd: &OrderCommand) -> Vec<(Price, Size)> {
+    cmd.matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| (e.price, e.size))
+        .collect()
+}
+
+fn rejected(cmd: &OrderCommand) -> Size {
+    cmd.matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::Reject)
+        .map(|e| e.size)
+        .sum()
+}
+
+#[test]
+fn test_ioc_budget_bid_stops_at_budget() {
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, 100, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 2, 110, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 3, 120, 5, OrderAction::Ask, OrderType::Gtc));
+
+        // 预算 1000：5@100 = 500，剩余 500 只够 4@110
+        let mut bid = order(2, 4, 1000, 20, OrderAction::Bid, OrderType::IocBudget);
+        assert_eq!(book.new_order(&mut bid), CommandResultCode::Success);
+
+        let fills = trades(&bid);
+        assert_eq!(fills.iter().map(|(p, s)| p * s).sum::<i64>(), 940);
+        assert_eq!(fills.iter().map(|(_, s)| s).sum::<Size>(), 9);
+        assert_eq!(rejected(&bid), 11);
+        assert_eq!(bid.price, 1000);
+        assert_eq!(bid.size, 20);
+        assert_eq!(book.get_total_ask_volume(), 6);
+    }
+}
+
+#[test]
+fn test_ioc_budget_ask_unsupported() {
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, 100, 5, OrderAction::Bid, OrderType::Gtc));
+
+        // 预算为买入可花费的总金额，卖单没有对应含义
+        let mut ask = order(2, 2, 700, 5, OrderAction::Ask, OrderType::IocBudget);
+        assert_eq!(book.new_order(&mut ask), CommandResultCode::MatchingUnsupportedCommand);
+        assert!(trades(&ask).is_empty());
+        assert_eq!(book.get_total_bid_volume(), 5);
+    }
+}
+
+#[test]
+fn test_fok_budget_ask_fills_when_proceeds_sufficient() {
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, 100, 5, OrderAction::Bid, OrderType::Gtc));
+        book.new_order(&mut order(1, 2, 90, 5, OrderAction::Bid, OrderType::Gtc));
+
+        // 卖 8 可得 5*100 + 3*90 = 770
+        let mut ask = order(2, 3, 780, 8, OrderAction::Ask, OrderType::FokBudget);
+        book.new_order(&mut ask);
+        assert_eq!(rejected(&ask), 8);
+        assert_eq!(book.get_total_bid_volume(), 10);
+
+        let mut ask = order(2, 4, 770, 8, OrderAction::Ask, OrderType::FokBudget);
+        book.new_order(&mut ask);
+        assert_eq!(trades(&ask), vec![(100, 5), (90, 3)]);
+        assert_eq!(rejected(&ask), 0);
+        assert_eq!(book.get_total_bid_volume(), 2);
+    }
+}
+
+#[test]
+fn test_budget_overflow_rejected() {
+    const HALF_MAX: Price = i64::MAX / 2;
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, HALF_MAX, 3, OrderAction::Ask, OrderType::Gtc));
+
+        // 全部成交需要 3 * HALF_MAX，超出 i64：整单拒绝而不是回绕后通过预算校验
+        let mut bid = order(2, 2, i64::MAX, 3, OrderAction::Bid, OrderType::FokBudget);
+        book.new_order(&mut bid);
+        assert!(trades(&bid).is_empty());
+        assert_eq!(rejected(&bid), 3);
+        assert_eq!(book.get_total_ask_volume(), 3);
+    }
+}
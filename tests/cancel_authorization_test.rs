@@ -0,0 +1,151 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn place(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn command(command: OrderCommandType, uid: UserId, order_id: OrderId, price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        command,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_book_rejects_actions_by_other_user() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut place(1, 1, 100, 10, OrderAction::Ask));
+        book.new_order(&mut place(2, 2, 90, 10, OrderAction::Bid));
+        let before = book.get_l2_data(10);
+
+        let mut cancel = command(OrderCommandType::CancelOrder, 2, 1, 0, 0);
+        assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::MatchingUnauthorizedAction, "{:?}", kind);
+        let mut move_cmd = command(OrderCommandType::MoveOrder, 2, 1, 90, 0);
+        assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::MatchingUnauthorizedAction, "{:?}", kind);
+        let mut amend = command(OrderCommandType::AmendOrder, 2, 1, 90, 5);
+        assert_eq!(book.amend_order(&mut amend), CommandResultCode::MatchingUnauthorizedAction, "{:?}", kind);
+        assert!(amend.matcher_events.is_empty());
+        if kind != OrderBookKind::Advanced {
+            let mut reduce = command(OrderCommandType::ReduceOrder, 2, 1, 0, 5);
+            assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::MatchingUnauthorizedAction, "{:?}", kind);
+            assert!(reduce.matcher_events.is_empty());
+        }
+        assert!(cancel.matcher_events.is_empty() && move_cmd.matcher_events.is_empty());
+        assert_eq!(book.get_l2_data(10), before, "{:?}", kind);
+
+        // 订单所有者可以正常撤单
+        let mut cancel = command(OrderCommandType::CancelOrder, 1, 1, 0, 0);
+        assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::Success, "{:?}", kind);
+        assert_eq!(cancel.matcher_events[0].size, 10);
+
+        // 不存在的订单仍返回未知订单号
+        let mut cancel = command(OrderCommandType::CancelOrder, 2, 1, 0, 0);
+        assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::MatchingUnknownOrderId, "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_advanced_book_protects_stop_orders() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    let mut stop = OrderCommand {
+        order_type: OrderType::StopLimit,
+        stop_price: Some(110),
+        ..place(1, 1, 111, 5, OrderAction::Bid)
+    };
+    book.new_order(&mut stop);
+
+    let mut cancel = command(OrderCommandType::CancelOrder, 2, 1, 0, 0);
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::MatchingUnauthorizedAction);
+    let mut move_cmd = command(OrderCommandType::MoveOrder, 2, 1, 105, 0);
+    assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::MatchingUnauthorizedAction);
+    assert_eq!(book.get_open_order(1).map(|o| o.price), Some(111));
+}
+
+#[test]
+fn test_unauthorized_cancel_keeps_funds() {
+    for kind in KINDS {
+        let mut core = ExchangeCore::new(ExchangeConfig {
+            ring_buffer_size: 1024,
+            order_book_kind: kind,
+            ..Default::default()
+        });
+        core.add_symbol(spec());
+        for uid in [1001, 1002] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            });
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: 1,
+                price: 1_000_000,
+                order_id: uid as OrderId,
+                ..Default::default()
+            });
+        }
+        core.submit_command(place(1001, 1, 100, 10, OrderAction::Bid));
+
+        // 其他用户撤单失败，不会拿到原订单冻结的资金
+        let result = core.submit_command(command(OrderCommandType::CancelOrder, 1002, 1, 0, 0));
+        assert_eq!(result.result_code, CommandResultCode::MatchingUnauthorizedAction, "{:?}", kind);
+        assert!(result.matcher_events.is_empty());
+        core.verify_invariants().unwrap();
+
+        // 其他用户改单（包括加量）同样拒绝，不存在的订单仍为未知订单号
+        for size in [5, 20] {
+            let result = core.submit_command(command(OrderCommandType::AmendOrder, 1002, 1, 90, size));
+            assert_eq!(result.result_code, CommandResultCode::MatchingUnauthorizedAction, "{:?}", kind);
+            assert!(result.matcher_events.is_empty());
+        }
+        let result = core.submit_command(command(OrderCommandType::AmendOrder, 1002, 7, 90, 5));
+        assert_eq!(result.result_code, CommandResultCode::MatchingUnknownOrderId);
+        core.verify_invariants().unwrap();
+
+        let result = core.submit_command(command(OrderCommandType::CancelOrder, 1001, 1, 0, 0));
+        assert_eq!(result.result_code, CommandResultCode::Success);
+        assert_eq!(result.matcher_events[0].size, 10);
+        core.verify_invariants().unwrap();
+    }
+}
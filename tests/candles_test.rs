@@ -0,0 +1,98 @@
+use matching_core::api::*;
+use matching_core::core::candles::CandleAggregator;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn tick(symbol: SymbolId, price: Price, size: Size, timestamp: i64) -> TradeTick {
+    TradeTick {
+        symbol,
+        price,
+        size,
+        taker_action: OrderAction::Bid,
+        taker_order_id: 0,
+        taker_uid: 0,
+        maker_order_id: 0,
+        maker_uid: 0,
+        taker_client_order_id: 0,
+        maker_client_order_id: 0,
+        trade_id: 0,
+        sequence: 0,
+        timestamp,
+    }
+}
+
+fn ohlcv(candle: &Candle) -> (i64, Price, Price, Price, Price, Size, u64) {
+    (candle.open_time, candle.open, candle.high, candle.low, candle.close, candle.volume, candle.trade_count)
+}
+
+#[test]
+fn test_aggregates_trades_per_interval() {
+    let mut candles = CandleAggregator::new(&[1_000, 60_000], 2);
+    for (price, size, timestamp) in [(100, 1, 500), (105, 2, 900), (98, 1, 999), (101, 3, 1_000), (99, 1, 3_500)] {
+        candles.on_trade(&tick(1, price, size, timestamp));
+    }
+    candles.on_trade(&tick(2, 50, 7, 1_200));
+
+    // 每个周期只保留最近 2 根，没有成交的周期不生成 K 线
+    let seconds: Vec<_> = candles.recent(1, 1_000, 10).iter().map(ohlcv).collect();
+    assert_eq!(seconds, vec![(1_000, 101, 101, 101, 101, 3, 1), (3_000, 99, 99, 99, 99, 1, 1)]);
+    assert_eq!(candles.recent(1, 1_000, 1).len(), 1);
+    assert_eq!(candles.latest(1, 60_000).as_ref().map(ohlcv), Some((0, 100, 105, 98, 99, 8, 5)));
+    assert_eq!(candles.latest(2, 1_000).map(|c| (c.open_time, c.volume)), Some((1_000, 7)));
+    assert!(candles.recent(1, 5_000, 10).is_empty());
+}
+
+#[test]
+fn test_exchange_core_candles() {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    let candles = core.enable_candles(&[60_000], 10);
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+
+    let place = |uid, order_id, price, action, timestamp| OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size: 2,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp,
+        ..Default::default()
+    };
+    core.submit_command(place(1001, 1, 100, OrderAction::Ask, 10_000));
+    core.submit_command(place(1002, 2, 100, OrderAction::Bid, 20_000));
+    core.submit_command(place(1001, 3, 102, OrderAction::Ask, 70_000));
+    core.submit_command(place(1002, 4, 102, OrderAction::Bid, 80_000));
+
+    let bars: Vec<_> = candles.lock().unwrap().recent(100, 60_000, 10).iter().map(ohlcv).collect();
+    assert_eq!(bars, vec![(0, 100, 100, 100, 100, 2, 1), (60_000, 102, 102, 102, 102, 2, 1)]);
+}
@@ -0,0 +1,164 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+use std::sync::{Arc, Mutex};
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, client_order_id: u64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        client_order_id,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_book_keeps_client_order_id() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut order(1, 1, 100, 10, OrderAction::Ask, 0xABCD));
+        assert_eq!(book.get_open_order(1).map(|o| o.client_order_id), Some(0xABCD), "{:?}", kind);
+
+        // 改价后保留客户端订单号
+        let mut move_cmd = OrderCommand {
+            command: OrderCommandType::MoveOrder,
+            uid: 1,
+            order_id: 1,
+            symbol: 100,
+            price: 101,
+            ..Default::default()
+        };
+        assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::Success, "{:?}", kind);
+        assert_eq!(book.get_open_order(1).map(|o| o.client_order_id), Some(0xABCD), "{:?}", kind);
+
+        // 成交事件携带 maker 的客户端订单号
+        let mut taker = order(2, 2, 101, 4, OrderAction::Bid, 7);
+        book.new_order(&mut taker);
+        let trade = &taker.matcher_events[0];
+        assert_eq!(trade.event_type, MatcherEventType::Trade);
+        assert_eq!((trade.matched_order_id, trade.matched_client_order_id), (1, 0xABCD), "{:?}", kind);
+        assert_eq!(book.get_all_orders().iter().map(|o| o.client_order_id).collect::<Vec<_>>(), vec![0xABCD]);
+    }
+}
+
+#[test]
+fn test_activated_stop_keeps_client_order_id() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut order(1, 1, 100, 5, OrderAction::Bid, 11));
+    let mut stop = OrderCommand {
+        order_type: OrderType::StopLimit,
+        stop_price: Some(100),
+        ..order(2, 10, 95, 3, OrderAction::Ask, 22)
+    };
+    book.new_order(&mut stop);
+    assert_eq!(book.get_open_order(10).map(|o| o.client_order_id), Some(22));
+
+    let mut trigger = order(3, 20, 100, 1, OrderAction::Ask, 33);
+    book.new_order(&mut trigger);
+    let activation = trigger.matcher_events.iter().find(|e| e.taker_action.is_some()).unwrap();
+    assert_eq!((activation.taker_client_order_id, activation.matched_client_order_id), (22, 11));
+}
+
+#[test]
+fn test_expired_stop_and_pending_keep_client_order_id() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    let mut stop = OrderCommand {
+        order_type: OrderType::StopLimit,
+        stop_price: Some(100),
+        expire_time: Some(2000),
+        ..order(2, 10, 95, 3, OrderAction::Ask, 22)
+    };
+    book.new_order(&mut stop);
+    let mut pending = OrderCommand {
+        activate_time: Some(5000),
+        expire_time: Some(2000),
+        ..order(3, 11, 90, 4, OrderAction::Bid, 33)
+    };
+    book.new_order(&mut pending);
+
+    // 未触发的止损单与待生效订单过期时，拒绝事件同样携带客户端订单号
+    let mut events = Vec::new();
+    assert_eq!(book.expire_orders(2001, &mut events), 2);
+    let expired: Vec<_> = events.iter().map(|e| (e.event_type, e.matched_order_id, e.matched_client_order_id)).collect();
+    assert_eq!(expired, vec![(MatcherEventType::Reject, 10, 22), (MatcherEventType::Reject, 11, 33)]);
+}
+
+#[test]
+fn test_client_order_id_in_queries_and_ticks() {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    core.add_market_data_consumer(Arc::new(move |event| {
+        sink.lock().unwrap().push(event.clone());
+    }));
+    core.add_symbol(spec());
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+
+    core.submit_command(order(1001, 1, 100, 10, OrderAction::Ask, 555));
+    let open = core.submit_command(OrderCommand {
+        command: OrderCommandType::UserOrdersRequest,
+        uid: 1001,
+        symbol: 100,
+        ..Default::default()
+    });
+    assert_eq!(open.open_orders.iter().map(|o| (o.order_id, o.client_order_id)).collect::<Vec<_>>(), vec![(1, 555)]);
+
+    core.submit_command(order(1002, 2, 100, 3, OrderAction::Bid, 777));
+    let ticks: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|event| match event {
+            MarketDataEvent::Trade(tick) => Some((tick.taker_client_order_id, tick.maker_client_order_id)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ticks, vec![(777, 555)]);
+}
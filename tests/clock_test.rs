@@ -0,0 +1,205 @@
+use matching_core::api::*;
+use matching_core::core::clock::{Clock, ManualClock, RealTimeClock};
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::snapshot::SnapshotStore;
+use std::sync::Arc;
+
+fn setup(config: ExchangeConfig, clock: Arc<ManualClock>) -> ExchangeCore {
+    let mut core = ExchangeCore::new(config);
+    core.set_clock(clock);
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        base_currency: 2,
+        quote_currency: 1,
+        order_book: Some(OrderBookKind::Advanced),
+        ..Default::default()
+    });
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+/// 不指定 timestamp，由时钟补时间
+fn place(core: &mut ExchangeCore, order_id: OrderId, price: Price, order_type: OrderType) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1002,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size: 5,
+        action: OrderAction::Ask,
+        order_type,
+        ..Default::default()
+    })
+}
+
+fn advance(core: &mut ExchangeCore, to: i64) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::AdvanceTime,
+        timestamp: to,
+        ..Default::default()
+    })
+}
+
+fn ask_prices(core: &mut ExchangeCore) -> Vec<Price> {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    })
+    .market_data
+    .unwrap()
+    .ask_prices
+}
+
+#[test]
+fn test_clocks() {
+    let clock = ManualClock::new(100);
+    assert_eq!(clock.now(), 100);
+    assert_eq!(clock.advance(50), 150);
+    clock.advance_to(120);
+    assert_eq!(clock.now(), 150, "模拟时钟不回退");
+    clock.advance_to(200);
+    assert_eq!(clock.now(), 200);
+
+    let real = RealTimeClock;
+    assert!(real.now() > 1_600_000_000_000);
+    real.advance_to(0);
+    assert!(real.now() > 1_600_000_000_000);
+}
+
+#[test]
+fn test_commands_stamped_by_clock() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let mut core = setup(ExchangeConfig::default(), clock.clone());
+    assert_eq!(core.now(), Some(1_000));
+
+    let cmd = place(&mut core, 1, 100, OrderType::Gtc);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!((cmd.timestamp, cmd.engine_timestamp), (1_000, 1_000));
+
+    clock.advance(500);
+    let cmd = place(&mut core, 2, 101, OrderType::Gtc);
+    assert_eq!(cmd.engine_timestamp, 1_500);
+
+    // 显式指定的 timestamp 保持不变
+    let cmd = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1002,
+        order_id: 2,
+        symbol: 100,
+        timestamp: 1_700,
+        ..Default::default()
+    });
+    assert_eq!(cmd.engine_timestamp, 1_700);
+}
+
+#[test]
+fn test_advance_time_expires_gtd_orders() {
+    let clock = Arc::new(ManualClock::new(100));
+    let mut core = setup(ExchangeConfig::default(), clock.clone());
+    place(&mut core, 1, 100, OrderType::Gtd(500));
+    place(&mut core, 2, 101, OrderType::Gtc);
+
+    let result = advance(&mut core, 500);
+    assert_eq!((result.result_code, result.size), (CommandResultCode::Success, 0));
+    assert_eq!(clock.now(), 500);
+    assert_eq!(ask_prices(&mut core), vec![100, 101]);
+
+    let result = advance(&mut core, 501);
+    assert_eq!(result.size, 1);
+    assert_eq!(ask_prices(&mut core), vec![101]);
+
+    // 过期订单的冻结资金已返还
+    core.verify_invariants().unwrap();
+    // 时钟不回退，再次推进到更早时间不做处理
+    assert_eq!(advance(&mut core, 300).size, 0);
+    assert_eq!(clock.now(), 501);
+}
+
+#[test]
+fn test_day_orders_roll_over() {
+    let clock = Arc::new(ManualClock::new(1_500));
+    let config = ExchangeConfig { trading_day_length: 1_000, ..Default::default() };
+    let mut core = setup(config, clock.clone());
+    place(&mut core, 1, 100, OrderType::Day);
+    place(&mut core, 2, 101, OrderType::Gtc);
+
+    assert_eq!(advance(&mut core, 1_999).size, 0);
+    assert_eq!(ask_prices(&mut core), vec![100, 101]);
+    assert_eq!(advance(&mut core, 2_000).size, 1);
+    assert_eq!(ask_prices(&mut core), vec![101]);
+
+    // 新交易日的 Day 订单在当日结束时到期
+    place(&mut core, 3, 102, OrderType::Day);
+    assert_eq!(advance(&mut core, 2_999).size, 0);
+    assert_eq!(advance(&mut core, 3_000).size, 1);
+    assert_eq!(ask_prices(&mut core), vec![101]);
+}
+
+#[test]
+fn test_advance_time_replays_from_journal() {
+    let dir = std::env::temp_dir().join(format!("matching_core_clock_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let journal = dir.join("exchange.wal");
+
+    let clock = Arc::new(ManualClock::new(100));
+    let mut core = setup(ExchangeConfig::default(), clock.clone());
+    core.enable_journaling(&journal).unwrap();
+    place(&mut core, 1, 100, OrderType::Gtd(500));
+    place(&mut core, 2, 101, OrderType::Gtd(900));
+    advance(&mut core, 600);
+    drop(core);
+
+    // 重放不经过时钟：过期扫描命令及其时间都已写入日志
+    let mut replica = setup(ExchangeConfig::default(), Arc::new(ManualClock::new(0)));
+    replica.replay_journal(&journal).unwrap();
+    assert_eq!(ask_prices(&mut replica), vec![101]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_scheduled_snapshots() {
+    let dir = std::env::temp_dir().join(format!("matching_core_clock_snapshots_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let clock = Arc::new(ManualClock::new(0));
+    let mut core = setup(ExchangeConfig::default(), clock.clone());
+    core.enable_snapshotting(&dir).unwrap();
+    core.schedule_snapshots(1_000);
+    let store = SnapshotStore::new(&dir).unwrap();
+
+    place(&mut core, 1, 100, OrderType::Gtc);
+    assert_eq!(store.get_latest_seq_id().unwrap(), None);
+
+    clock.advance(999);
+    place(&mut core, 2, 101, OrderType::Gtc);
+    assert_eq!(store.get_latest_seq_id().unwrap(), None);
+
+    clock.advance(1);
+    place(&mut core, 3, 102, OrderType::Gtc);
+    let first = store.get_latest_seq_id().unwrap().expect("到期应生成快照");
+    assert_eq!(first, core.last_seq());
+
+    // 推进时间同样触发快照
+    advance(&mut core, 2_000);
+    assert!(store.get_latest_seq_id().unwrap().unwrap() > first);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,95 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::new_order_book;
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_depth_volume_and_imbalance() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        assert_eq!(book.imbalance(5), None);
+        assert_eq!(book.volume_within_bps(OrderAction::Ask, 100), 0);
+
+        // 卖盘 10000×2, 10050×3, 10200×5；买盘 9990×6, 9900×4
+        book.new_order(&mut order(1, 10_000, 2, OrderAction::Ask));
+        book.new_order(&mut order(2, 10_050, 3, OrderAction::Ask));
+        book.new_order(&mut order(3, 10_200, 5, OrderAction::Ask));
+        book.new_order(&mut order(4, 9_990, 6, OrderAction::Bid));
+        book.new_order(&mut order(5, 9_900, 4, OrderAction::Bid));
+
+        assert_eq!(book.depth_volume(OrderAction::Ask, 0), 0, "{:?}", kind);
+        assert_eq!(book.depth_volume(OrderAction::Ask, 2), 5, "{:?}", kind);
+        assert_eq!(book.depth_volume(OrderAction::Ask, 10), 10, "{:?}", kind);
+        assert_eq!(book.depth_volume(OrderAction::Bid, 1), 6, "{:?}", kind);
+
+        // 中间价 9995：+60bp 上限 10054.97，-60bp 下限 9935.03
+        assert_eq!(book.volume_within_bps(OrderAction::Ask, 60), 5, "{:?}", kind);
+        assert_eq!(book.volume_within_bps(OrderAction::Bid, 60), 6, "{:?}", kind);
+        assert_eq!(book.volume_within_bps(OrderAction::Bid, 100), 10, "{:?}", kind);
+
+        assert_eq!(book.imbalance(1), Some(0.5), "{:?}", kind);
+        assert_eq!(book.imbalance(10), Some(0.0), "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_one_sided_book() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut order(1, 10_000, 2, OrderAction::Ask));
+        book.new_order(&mut order(2, 10_100, 3, OrderAction::Ask));
+
+        // 无买盘时以卖一为参考价
+        assert_eq!(book.volume_within_bps(OrderAction::Ask, 0), 2, "{:?}", kind);
+        assert_eq!(book.volume_within_bps(OrderAction::Ask, 100), 5, "{:?}", kind);
+        assert_eq!(book.imbalance(5), Some(-1.0), "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_iceberg_counts_displayed_volume() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut OrderCommand {
+        order_type: OrderType::Iceberg,
+        visible_size: Some(2),
+        ..order(1, 10_000, 10, OrderAction::Ask)
+    });
+    book.new_order(&mut order(2, 9_990, 2, OrderAction::Bid));
+
+    assert_eq!(book.depth_volume(OrderAction::Ask, 5), 2);
+    assert_eq!(book.imbalance(5), Some(0.0));
+}
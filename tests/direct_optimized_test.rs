@@ -0,0 +1,286 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{OrderBook, DirectOrderBook, DirectOrderBookOptimized};
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn place(book: &mut DirectOrderBookOptimized, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    let mut cmd = OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    };
+    book.new_order(&mut cmd);
+    cmd
+}
+
+#[test]
+fn test_reduce_order_partial() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc);
+
+    let mut reduce = OrderCommand {
+        command: OrderCommandType::ReduceOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        size: 4,
+        ..Default::default()
+    };
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+
+    assert_eq!(reduce.matcher_events.len(), 1);
+    assert_eq!(reduce.matcher_events[0].event_type, MatcherEventType::Reduce);
+    assert_eq!(reduce.matcher_events[0].size, 4);
+    assert_eq!(reduce.action, OrderAction::Ask);
+    assert_eq!(book.get_total_ask_volume(), 6);
+    assert_eq!(book.get_l2_data(1).ask_volumes, vec![6]);
+
+    // 剩余数量仍可成交
+    let bid = place(&mut book, 2, 2, 10000, 10, OrderAction::Bid, OrderType::Ioc);
+    assert_eq!(bid.matcher_events[0].size, 6);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
+
+#[test]
+fn test_reduce_order_to_zero_frees_order() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10000, 10, OrderAction::Bid, OrderType::Gtc);
+    place(&mut book, 1, 2, 9900, 5, OrderAction::Bid, OrderType::Gtc);
+
+    let mut reduce = OrderCommand {
+        command: OrderCommandType::ReduceOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        size: 100,
+        ..Default::default()
+    };
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+    assert_eq!(reduce.matcher_events[0].size, 10);
+    assert_eq!(reduce.matcher_events[0].bidder_hold_price, 10000);
+
+    assert!(book.get_order_by_id(1).is_none());
+    assert_eq!(book.get_bid_buckets_count(), 1);
+    assert_eq!(book.get_total_bid_volume(), 5);
+
+    // 最优买价回落到 9900
+    let ask = place(&mut book, 2, 3, 9900, 5, OrderAction::Ask, OrderType::Ioc);
+    assert_eq!(ask.matcher_events.len(), 1);
+    assert_eq!(ask.matcher_events[0].price, 9900);
+}
+
+#[test]
+fn test_reduce_order_validation() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc);
+
+    let mut unknown = OrderCommand {
+        command: OrderCommandType::ReduceOrder,
+        uid: 1,
+        order_id: 99,
+        size: 1,
+        ..Default::default()
+    };
+    assert_eq!(book.reduce_order(&mut unknown), CommandResultCode::MatchingUnknownOrderId);
+
+    let mut zero = OrderCommand {
+        command: OrderCommandType::ReduceOrder,
+        uid: 1,
+        order_id: 1,
+        size: 0,
+        ..Default::default()
+    };
+    assert_eq!(book.reduce_order(&mut zero), CommandResultCode::MatchingInvalidOrderSize);
+    assert_eq!(book.get_total_ask_volume(), 10);
+}
+
+#[test]
+fn test_cancel_then_match_same_level() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc);
+    place(&mut book, 1, 2, 10000, 7, OrderAction::Ask, OrderType::Gtc);
+
+    let mut cancel = OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 2,
+        ..Default::default()
+    };
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::Success);
+    assert_eq!(book.get_total_ask_volume(), 10);
+
+    let bid = place(&mut book, 2, 3, 10000, 10, OrderAction::Bid, OrderType::Ioc);
+    assert_eq!(bid.matcher_events.len(), 1);
+    assert_eq!(bid.matcher_events[0].matched_order_id, 1);
+    assert_eq!(book.get_ask_buckets_count(), 0);
+}
+
+#[test]
+fn test_move_order_relinks_without_cancel_events() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10100, 10, OrderAction::Ask, OrderType::Gtc);
+    place(&mut book, 1, 2, 10200, 5, OrderAction::Ask, OrderType::Gtc);
+
+    let mut mv = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10200,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut mv), CommandResultCode::Success);
+    assert!(mv.matcher_events.is_empty());
+    assert_eq!(mv.action, OrderAction::Ask);
+
+    assert_eq!(book.get_order_by_id(1), Some((10200, OrderAction::Ask)));
+    assert_eq!(book.get_ask_buckets_count(), 1);
+    assert_eq!(book.get_l2_data(5).ask_volumes, vec![15]);
+}
+
+#[test]
+fn test_move_order_crossing_rematches() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10000, 4, OrderAction::Bid, OrderType::Gtc);
+    place(&mut book, 2, 2, 10100, 10, OrderAction::Ask, OrderType::Gtc);
+
+    // 卖单改价到 10000，穿越买一并部分成交
+    let mut mv = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10000,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut mv), CommandResultCode::Success);
+    assert_eq!(mv.matcher_events.len(), 1);
+    assert_eq!(mv.matcher_events[0].event_type, MatcherEventType::Trade);
+    assert_eq!(mv.matcher_events[0].matched_order_id, 1);
+    assert_eq!(mv.matcher_events[0].size, 4);
+
+    // 剩余 6 挂在 10000，原订单 ID 保持不变
+    assert_eq!(book.get_order_by_id(2), Some((10000, OrderAction::Ask)));
+    assert_eq!(book.get_total_ask_volume(), 6);
+    assert_eq!(book.get_bid_buckets_count(), 0);
+}
+
+#[test]
+fn test_move_bid_over_reserve_rejected() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 9900, 10, OrderAction::Bid, OrderType::Gtc);
+
+    let mut mv = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut mv), CommandResultCode::RiskInvalidReserveBidPrice);
+    assert_eq!(book.get_order_by_id(1), Some((9900, OrderAction::Bid)));
+    assert_eq!(book.get_total_bid_volume(), 10);
+}
+
+#[test]
+fn test_fok_rejects_when_insufficient() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc);
+    place(&mut book, 1, 2, 10200, 5, OrderAction::Ask, OrderType::Gtc);
+
+    // 限价 10100 内只有 5
+    let fok = place(&mut book, 2, 3, 10100, 8, OrderAction::Bid, OrderType::Fok);
+    assert_eq!(fok.matcher_events.len(), 1);
+    assert_eq!(fok.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(fok.matcher_events[0].size, 8);
+    assert_eq!(book.get_total_ask_volume(), 10);
+
+    // 限价 10200 内足够，全部成交
+    let fok = place(&mut book, 2, 4, 10200, 8, OrderAction::Bid, OrderType::Fok);
+    assert!(fok.matcher_events.iter().all(|e| e.event_type == MatcherEventType::Trade));
+    assert_eq!(fok.matcher_events.iter().map(|e| e.size).sum::<Size>(), 8);
+    assert_eq!(book.get_total_ask_volume(), 2);
+}
+
+#[test]
+fn test_fok_budget() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    place(&mut book, 1, 1, 100, 5, OrderAction::Ask, OrderType::Gtc);
+    place(&mut book, 1, 2, 110, 5, OrderAction::Ask, OrderType::Gtc);
+
+    // 买 8 需要 5*100 + 3*110 = 830，预算 800 不足
+    let fok = place(&mut book, 2, 3, 800, 8, OrderAction::Bid, OrderType::FokBudget);
+    assert_eq!(fok.matcher_events.len(), 1);
+    assert_eq!(fok.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(book.get_total_ask_volume(), 10);
+
+    let fok = place(&mut book, 2, 4, 830, 8, OrderAction::Bid, OrderType::FokBudget);
+    assert_eq!(fok.matcher_events.len(), 2);
+    assert_eq!(fok.matcher_events[1].price, 110);
+    assert_eq!(fok.matcher_events[1].size, 3);
+    assert_eq!(book.get_total_ask_volume(), 2);
+}
+
+#[test]
+fn test_fok_direct_book() {
+    let mut book = DirectOrderBook::new(create_symbol_spec());
+    let mut ask = OrderCommand {
+        uid: 1,
+        order_id: 1,
+        symbol: 1,
+        price: 10000,
+        size: 5,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        reserve_price: 10000,
+        ..Default::default()
+    };
+    book.new_order(&mut ask);
+
+    let mut fok = OrderCommand {
+        uid: 2,
+        order_id: 2,
+        symbol: 1,
+        price: 10000,
+        size: 6,
+        action: OrderAction::Bid,
+        order_type: OrderType::Fok,
+        reserve_price: 10000,
+        ..Default::default()
+    };
+    assert_eq!(book.new_order(&mut fok), CommandResultCode::Success);
+    assert_eq!(fok.matcher_events.len(), 1);
+    assert_eq!(fok.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(book.get_total_ask_volume(), 5);
+
+    fok.order_id = 3;
+    fok.size = 5;
+    fok.matcher_events.clear();
+    book.new_order(&mut fok);
+    assert_eq!(fok.matcher_events.len(), 1);
+    assert_eq!(fok.matcher_events[0].event_type, MatcherEventType::Trade);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
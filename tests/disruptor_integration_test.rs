@@ -0,0 +1,236 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore, ProducerType, WaitStrategyType};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const PRODUCERS: u64 = 4;
+const DEPOSIT: i64 = 1_000_000;
+
+fn config() -> ExchangeConfig {
+    ExchangeConfig {
+        ring_buffer_size: 1024,
+        matching_engines_num: 2,
+        risk_engines_num: 2,
+        producer_type: ProducerType::Multi,
+        wait_strategy: WaitStrategyType::Yielding,
+        ..Default::default()
+    }
+}
+
+fn journal_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("matching_core_disruptor_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("exchange.wal")
+}
+
+/// 每个生产者线程独占一个交易对与一对买卖用户，最终状态与线程交错无关
+fn symbol(producer: u64) -> SymbolId {
+    100 + producer as SymbolId
+}
+
+fn seller(producer: u64) -> UserId {
+    producer * 10 + 1
+}
+
+fn buyer(producer: u64) -> UserId {
+    producer * 10 + 2
+}
+
+fn new_core() -> ExchangeCore {
+    let mut core = ExchangeCore::new(config());
+    for producer in 0..PRODUCERS {
+        core.add_symbol(CoreSymbolSpecification {
+            symbol_id: symbol(producer),
+            symbol_type: SymbolType::CurrencyExchangePair,
+            base_currency: 2,
+            quote_currency: 1,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn setup_commands() -> Vec<OrderCommand> {
+    let mut commands = Vec::new();
+    for producer in 0..PRODUCERS {
+        for (uid, currency) in [(seller(producer), 2), (buyer(producer), 1)] {
+            commands.push(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+            commands.push(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: DEPOSIT,
+                order_id: uid,
+                ..Default::default()
+            });
+        }
+    }
+    commands
+}
+
+/// 50 张 2 手卖单（1000..=1004 各 10 张），随后 20 张 5 手买单以 1004 全部吃掉
+fn producer_commands(producer: u64) -> Vec<OrderCommand> {
+    let order = |uid, order_id: OrderId, price, size, action| OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: symbol(producer),
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    };
+    let first_id = producer * 1_000;
+    let asks = (0..50).map(|k| order(seller(producer), first_id + k, 1000 + (k % 5) as Price, 2, OrderAction::Ask));
+    let bids = (50..70).map(|k| order(buyer(producer), first_id + k, 1004, 5, OrderAction::Bid));
+    asks.chain(bids).collect()
+}
+
+/// 多个生产者线程并发提交，各自等待结果
+fn run_producers(core: &Arc<Mutex<ExchangeCore>>) -> Vec<Vec<OrderCommand>> {
+    let handles: Vec<_> = (0..PRODUCERS)
+        .map(|producer| {
+            let core = core.clone();
+            std::thread::spawn(move || {
+                let futures: Vec<_> = producer_commands(producer)
+                    .into_iter()
+                    .map(|cmd| core.lock().unwrap().submit_command_async(cmd))
+                    .collect();
+                // 在锁外等待，其他生产者可以继续提交
+                futures.into_iter().map(|future| future.wait()).collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+}
+
+fn balance(core: &ExchangeCore, uid: UserId, currency: Currency) -> i64 {
+    core.serialize_state()
+        .pipeline_state
+        .risk_engines
+        .iter()
+        .find_map(|engine| engine.get_user(uid).map(|user| user.accounts.get(&currency).copied().unwrap_or(0)))
+        .unwrap()
+}
+
+fn trades(cmd: &OrderCommand) -> Vec<(OrderId, Price, Size, u64)> {
+    cmd.matcher_events.iter().map(|e| (e.matched_order_id, e.price, e.size, e.trade_id)).collect()
+}
+
+#[test]
+fn test_multi_producer_commands_settle() {
+    let journal = journal_path("settle");
+    let mut core = new_core();
+    core.enable_journaling(&journal).unwrap();
+    core.startup();
+    for cmd in setup_commands() {
+        assert_eq!(core.submit_command_async(cmd).wait().result_code, CommandResultCode::Success);
+    }
+
+    let core = Arc::new(Mutex::new(core));
+    let results = run_producers(&core);
+    for (producer, results) in results.iter().enumerate() {
+        assert_eq!(results.len(), 70);
+        assert!(results.iter().all(|cmd| cmd.result_code == CommandResultCode::Success));
+        // 每张买单吃掉 5 手
+        for bid in &results[50..] {
+            let filled: Size = bid.matcher_events.iter().filter(|e| e.event_type == MatcherEventType::Trade).map(|e| e.size).sum();
+            assert_eq!(filled, 5, "producer {}", producer);
+        }
+    }
+
+    let mut core = Arc::try_unwrap(core).ok().unwrap().into_inner().unwrap();
+    for producer in 0..PRODUCERS {
+        let book = core
+            .submit_command_async(OrderCommand {
+                command: OrderCommandType::OrderBookRequest,
+                symbol: symbol(producer),
+                size: 10,
+                ..Default::default()
+            })
+            .wait();
+        let book = book.market_data.unwrap();
+        assert!(book.ask_prices.is_empty() && book.bid_prices.is_empty());
+    }
+    drop(core);
+
+    // 启动后无法直接读取状态：重放日志到同步处理的实例后核对余额
+    let mut replayed = new_core();
+    replayed.replay_journal(&journal).unwrap();
+    replayed.verify_invariants().unwrap();
+    let notional = 2 * 10 * (1000 + 1001 + 1002 + 1003 + 1004);
+    for producer in 0..PRODUCERS {
+        assert_eq!(balance(&replayed, seller(producer), 2), DEPOSIT - 100);
+        assert_eq!(balance(&replayed, seller(producer), 1), notional);
+        assert_eq!(balance(&replayed, buyer(producer), 1), DEPOSIT - notional);
+        assert_eq!(balance(&replayed, buyer(producer), 2), 100);
+    }
+}
+
+#[test]
+fn test_results_follow_submission_order_per_producer() {
+    let outputs = Arc::new(Mutex::new(Vec::new()));
+    let mut core = new_core();
+    let sink = outputs.clone();
+    core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+        if cmd.command == OrderCommandType::PlaceOrder {
+            sink.lock().unwrap().push((cmd.order_id, cmd.result_code));
+        }
+    }));
+    core.startup();
+    for cmd in setup_commands() {
+        core.submit_command_async(cmd).wait();
+    }
+
+    let core = Arc::new(Mutex::new(core));
+    let results = run_producers(&core);
+    drop(core);
+
+    // 结果输出覆盖全部命令，且每个生产者的命令按提交顺序输出
+    let outputs = outputs.lock().unwrap().clone();
+    assert_eq!(outputs.len(), (PRODUCERS * 70) as usize);
+    assert!(outputs.iter().all(|&(_, code)| code == CommandResultCode::Success));
+    for producer in 0..PRODUCERS {
+        let ids: Vec<OrderId> = outputs.iter().map(|&(id, _)| id).filter(|id| id / 1_000 == producer).collect();
+        let expected: Vec<OrderId> = producer_commands(producer).iter().map(|cmd| cmd.order_id).collect();
+        assert_eq!(ids, expected);
+        let awaited: Vec<OrderId> = results[producer as usize].iter().map(|cmd| cmd.order_id).collect();
+        assert_eq!(awaited, expected);
+    }
+}
+
+#[test]
+fn test_started_core_matches_synchronous_processing() {
+    let journal = journal_path("equivalence");
+    let mut core = new_core();
+    core.enable_journaling(&journal).unwrap();
+    core.startup();
+    for cmd in setup_commands() {
+        core.submit_command_async(cmd).wait();
+    }
+    let core = Arc::new(Mutex::new(core));
+    let started: Vec<OrderCommand> = run_producers(&core).into_iter().flatten().collect();
+    drop(core);
+
+    // 按日志中的实际交错顺序同步处理，逐条结果一致
+    let mut sync_core = new_core();
+    let mut by_order_id = std::collections::HashMap::new();
+    for cmd in matching_core::core::journal::Journaler::read_commands(&journal).unwrap() {
+        let result = sync_core.submit_command(cmd);
+        if result.command == OrderCommandType::PlaceOrder {
+            by_order_id.insert(result.order_id, result);
+        }
+    }
+    for cmd in &started {
+        let expected = &by_order_id[&cmd.order_id];
+        assert_eq!(cmd.result_code, expected.result_code);
+        assert_eq!(trades(cmd), trades(expected), "order {}", cmd.order_id);
+    }
+    sync_core.verify_invariants().unwrap();
+}
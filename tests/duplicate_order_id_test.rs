@@ -0,0 +1,159 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn setup(order_book_kind: OrderBookKind) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        order_book_kind,
+        ..Default::default()
+    });
+    core.add_symbol(spec());
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+#[test]
+fn test_book_rejects_duplicate_without_touching_book() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut order(1, 1, 100, 10, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 2, 101, 10, OrderAction::Ask, OrderType::Gtc));
+        let before = book.get_l2_data(10);
+
+        // 与挂单同号的新单（可立即成交）被整体拒绝，不撮合也不挂单
+        for order_type in [OrderType::Gtc, OrderType::Ioc, OrderType::FokBudget] {
+            let mut cmd = order(2, 1, 101, 5, OrderAction::Bid, order_type);
+            assert_eq!(book.new_order(&mut cmd), CommandResultCode::MatchingDuplicateOrderId, "{:?}", kind);
+            assert!(cmd.matcher_events.is_empty());
+        }
+        assert_eq!(book.get_l2_data(10), before, "{:?}", kind);
+
+        // 撤单后订单号可再次使用
+        let mut cancel = OrderCommand { command: OrderCommandType::CancelOrder, ..order(1, 1, 0, 0, OrderAction::Ask, OrderType::Gtc) };
+        assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::Success);
+        let mut cmd = order(2, 1, 99, 5, OrderAction::Bid, OrderType::Gtc);
+        assert_eq!(book.new_order(&mut cmd), CommandResultCode::Success, "{:?}", kind);
+        assert_eq!(book.get_order_by_id(1), Some((99, OrderAction::Bid)));
+    }
+}
+
+#[test]
+fn test_advanced_book_rejects_duplicate_of_stop_order() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    let mut stop = OrderCommand { stop_price: Some(110), ..order(1, 1, 111, 5, OrderAction::Bid, OrderType::StopLimit) };
+    assert_eq!(book.new_order(&mut stop), CommandResultCode::Success);
+
+    let mut cmd = order(2, 1, 100, 5, OrderAction::Ask, OrderType::Gtc);
+    assert_eq!(book.new_order(&mut cmd), CommandResultCode::MatchingDuplicateOrderId);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
+
+#[test]
+fn test_duplicate_order_id_keeps_funds_and_original_order() {
+    for kind in KINDS {
+        let mut core = setup(kind);
+        let original = core.submit_command(order(1001, 1, 100, 10, OrderAction::Bid, OrderType::Gtc));
+        assert_eq!(original.result_code, CommandResultCode::Success);
+
+        // 同一用户复用未完成订单号：风控直接拒绝
+        let same_user = core.submit_command(order(1001, 1, 100, 3, OrderAction::Ask, OrderType::Gtc));
+        assert_eq!(same_user.result_code, CommandResultCode::MatchingDuplicateOrderId, "{:?}", kind);
+        assert!(same_user.matcher_events.is_empty());
+
+        // 其他用户使用相同订单号：订单簿拒绝，冻结资金全部返还
+        let other_user = core.submit_command(order(1002, 1, 100, 4, OrderAction::Ask, OrderType::Gtc));
+        assert_eq!(other_user.result_code, CommandResultCode::MatchingDuplicateOrderId, "{:?}", kind);
+        assert!(other_user.matcher_events.iter().all(|e| e.event_type == MatcherEventType::Reject));
+        core.verify_invariants().unwrap();
+
+        // 原订单不受影响，撤单返还全部数量
+        let cancel = core.submit_command(OrderCommand {
+            command: OrderCommandType::CancelOrder,
+            uid: 1001,
+            order_id: 1,
+            symbol: 100,
+            ..Default::default()
+        });
+        assert_eq!(cancel.result_code, CommandResultCode::Success);
+        assert_eq!(cancel.matcher_events.iter().map(|e| e.size).sum::<Size>(), 10);
+        core.verify_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_staged_pipeline_rejects_duplicate_order_id() {
+    let mut core = setup(OrderBookKind::Direct);
+    core.startup();
+    let futures: Vec<_> = [
+        order(1001, 1, 100, 10, OrderAction::Bid, OrderType::Gtc),
+        order(1001, 1, 100, 3, OrderAction::Ask, OrderType::Gtc),
+        order(1002, 1, 100, 4, OrderAction::Ask, OrderType::Ioc),
+        order(1002, 2, 100, 4, OrderAction::Ask, OrderType::Ioc),
+    ]
+    .into_iter()
+    .map(|cmd| core.submit_command_async(cmd))
+    .collect();
+    let codes: Vec<CommandResultCode> = futures.into_iter().map(|future| future.wait().result_code).collect();
+    assert_eq!(
+        codes,
+        vec![
+            CommandResultCode::Success,
+            CommandResultCode::MatchingDuplicateOrderId,
+            CommandResultCode::MatchingDuplicateOrderId,
+            CommandResultCode::Success,
+        ]
+    );
+}
@@ -13,6 +13,7 @@ fn create_symbol_spec() -> CoreSymbolSpecification {
         maker_fee: 0,
         margin_buy: 0,
         margin_sell: 0,
+        ..Default::default()
     }
 }
 
@@ -442,6 +443,7 @@ fn test_cancel_stop_order() {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_all_symbol_types() {
     // 测试所有交易品种类型
     let types = vec![
@@ -464,6 +466,7 @@ fn test_all_symbol_types() {
             maker_fee: 0,
             margin_buy: 0,
             margin_sell: 0,
+            ..Default::default()
         };
         
         let mut book = AdvancedOrderBook::new(spec);
@@ -0,0 +1,148 @@
+use matching_core::api::*;
+use matching_core::core::event_pool::EventBufferPool;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// 统计当前线程的堆分配次数
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+fn setup(order_book_kind: OrderBookKind) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        invariant_check_interval: 0,
+        order_book_kind,
+        event_pool_size: 8,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 100_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price: 1000,
+        reserve_price: 1000,
+        size: 10,
+        action,
+        order_type,
+        ..Default::default()
+    }
+}
+
+/// 挂卖单后被 IOC 买单全部吃掉，归还结果命令
+fn round_trip(core: &mut ExchangeCore, order_id: OrderId) -> usize {
+    let maker = order(1001, order_id, OrderAction::Ask, OrderType::Gtc);
+    let taker = order(1002, order_id + 1, OrderAction::Bid, OrderType::Ioc);
+    let before = allocations();
+    let maker = core.submit_command(maker);
+    let taker = core.submit_command(taker);
+    let allocated = allocations() - before;
+
+    assert_eq!(maker.result_code, CommandResultCode::Success);
+    assert_eq!(taker.matcher_events.len(), 1);
+    core.recycle_command(maker);
+    core.recycle_command(taker);
+    allocated
+}
+
+#[test]
+fn test_sync_matching_reuses_event_buffers() {
+    for kind in [OrderBookKind::Direct, OrderBookKind::DirectOptimized] {
+        let mut core = setup(kind);
+        for round in 0..100 {
+            let allocated = round_trip(&mut core, 10 + round * 2);
+            if round >= 10 {
+                assert_eq!(allocated, 0, "{:?} round {}", kind, round);
+            }
+        }
+        core.verify_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_async_results_use_pooled_buffers() {
+    let mut core = setup(OrderBookKind::Direct);
+    let initial = core.event_pool_stats(); // 启动前的同步命令也从池中取缓冲区
+    core.startup();
+
+    for round in 0..100 {
+        let order_id = 10 + round * 2;
+        let maker = core.submit_command_async(order(1001, order_id, OrderAction::Ask, OrderType::Gtc)).wait();
+        let taker = core.submit_command_async(order(1002, order_id + 1, OrderAction::Bid, OrderType::Ioc)).wait();
+        assert_eq!(taker.matcher_events.len(), 1);
+        assert_eq!(taker.matcher_events[0].matched_order_id, order_id);
+        core.recycle_command(maker);
+        core.recycle_command(taker);
+    }
+
+    let stats = core.event_pool_stats();
+    assert_eq!(stats.acquired - initial.acquired, 200);
+    assert!(stats.allocated - initial.allocated <= 2, "{:?}", stats);
+    assert!(stats.hit_rate() > 0.95);
+}
+
+#[test]
+fn test_pool_keeps_at_most_max_buffers() {
+    let pool = EventBufferPool::new(2);
+    let buffers: Vec<_> = (0..3).map(|_| pool.acquire()).collect();
+    for mut buffer in buffers {
+        buffer.push(MatcherTradeEvent::default());
+        pool.release(buffer);
+    }
+    pool.release(Vec::new()); // 无容量的缓冲区直接忽略
+
+    let stats = pool.stats();
+    assert_eq!((stats.acquired, stats.allocated, stats.released, stats.discarded, stats.pooled), (3, 3, 2, 1, 2));
+    assert!(pool.acquire().is_empty());
+    assert_eq!(pool.stats().allocated, 3);
+}
@@ -0,0 +1,131 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use std::sync::{Arc, Mutex};
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_router_assigns_sequence_and_time() {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.add_symbol(spec(100));
+
+    let mut first = order(1, 1, 100, 5, OrderAction::Ask, 2000);
+    router.process_order(&mut first);
+    assert_eq!((first.sequence, first.engine_timestamp), (1, 2000));
+
+    // 时间戳回退的命令沿用引擎时间；命令先取序号，事件依次递增
+    let mut second = order(2, 2, 101, 3, OrderAction::Bid, 1500);
+    router.process_order(&mut second);
+    assert_eq!((second.sequence, second.engine_timestamp), (2, 2000));
+    assert_eq!(
+        second.matcher_events.iter().map(|e| (e.sequence, e.timestamp)).collect::<Vec<_>>(),
+        vec![(3, 2000)]
+    );
+
+    // 快照恢复后继续递增
+    let mut restored = MatchingEngineRouter::from_state(router.serialize_state());
+    let mut cancel = OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 100,
+        timestamp: 3000,
+        ..Default::default()
+    };
+    restored.process_order(&mut cancel);
+    assert_eq!((cancel.sequence, cancel.engine_timestamp), (4, 3000));
+    assert_eq!(cancel.matcher_events[0].sequence, 5);
+
+    // 不属于撮合的广播命令不占用序号
+    let mut add_user = OrderCommand { command: OrderCommandType::AddUser, uid: 3, ..Default::default() };
+    restored.process_order(&mut add_user);
+    assert_eq!(add_user.sequence, 0);
+}
+
+#[test]
+fn test_trade_ticks_are_sequenced_per_symbol() {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        matching_engines_num: 2,
+        ..Default::default()
+    });
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    core.add_market_data_consumer(Arc::new(move |event| {
+        sink.lock().unwrap().push(event.clone());
+    }));
+    for symbol in [100, 101] {
+        core.add_symbol(spec(symbol));
+    }
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+
+    let mut order_id = 0;
+    for round in 0..3 {
+        for symbol in [100, 101] {
+            order_id += 2;
+            let ask = OrderCommand { symbol, result_code: CommandResultCode::New, ..order(1001, order_id, 100, 1, OrderAction::Ask, 1000 + round) };
+            let bid = OrderCommand { symbol, result_code: CommandResultCode::New, ..order(1002, order_id + 1, 100, 1, OrderAction::Bid, 1000 + round) };
+            core.submit_command(ask);
+            let result = core.submit_command(bid);
+            assert!(result.matcher_events[0].sequence > result.sequence);
+        }
+    }
+
+    for symbol in [100, 101] {
+        let sequences: Vec<u64> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                MarketDataEvent::Trade(tick) if tick.symbol == symbol => Some(tick.sequence),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sequences.len(), 3);
+        assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", sequences);
+    }
+}
@@ -0,0 +1,132 @@
+use matching_core::api::*;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+const QUOTE: Currency = 1;
+const BASE: Currency = 2;
+
+struct Exchange {
+    risk: RiskEngine,
+    matching: MatchingEngineRouter,
+}
+
+impl Exchange {
+    fn new() -> Self {
+        let spec = CoreSymbolSpecification {
+            symbol_id: 100,
+            symbol_type: SymbolType::CurrencyExchangePair,
+            base_currency: BASE,
+            quote_currency: QUOTE,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            taker_fee: 3,
+            maker_fee: 1,
+            margin_buy: 0,
+            margin_sell: 0,
+            ..Default::default()
+        };
+        let mut exchange = Self {
+            risk: RiskEngine::new(0, 1),
+            matching: MatchingEngineRouter::new(0, 1),
+        };
+        exchange.risk.add_symbol(spec.clone());
+        exchange.matching.add_symbol(spec);
+
+        for (uid, currency) in [(1001, QUOTE), (1002, BASE)] {
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            });
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 10_000,
+                order_id: uid as OrderId,
+                ..Default::default()
+            });
+        }
+        exchange
+    }
+
+    fn submit(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        self.risk.pre_process(&mut cmd);
+        self.matching.process_order(&mut cmd);
+        self.risk.post_process(&mut cmd);
+        cmd
+    }
+
+    fn place(&mut self, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) {
+        let result = self.submit(OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id,
+            symbol: 100,
+            price,
+            reserve_price: price,
+            size,
+            action,
+            order_type: OrderType::Gtc,
+            ..Default::default()
+        });
+        assert_eq!(result.result_code, CommandResultCode::Success);
+    }
+
+    fn balance(&self, uid: UserId, currency: Currency) -> i64 {
+        self.risk.get_user(uid).unwrap().accounts.get(&currency).copied().unwrap_or(0)
+    }
+}
+
+#[test]
+fn test_fee_ledger_records_maker_and_taker_fees() {
+    let mut exchange = Exchange::new();
+
+    // 买方 taker：4 * 3 taker 费，卖方 maker：4 * 1 maker 费
+    exchange.place(1002, 1, 100, 10, OrderAction::Ask);
+    exchange.place(1001, 2, 100, 4, OrderAction::Bid);
+
+    // 卖方 taker：5 * 3 taker 费，买方 maker：5 * 1 maker 费
+    exchange.place(1001, 3, 99, 5, OrderAction::Bid);
+    exchange.place(1002, 4, 99, 5, OrderAction::Ask);
+
+    let report = exchange.risk.fee_report();
+    let fees = &report.by_symbol[&100];
+    assert_eq!(fees.currency, QUOTE);
+    assert_eq!(fees.maker_fees, 9);
+    assert_eq!(fees.taker_fees, 27);
+    assert_eq!(report.by_currency[&QUOTE], 36);
+
+    // 挂单中的卖单（6 @ 100）不影响 quote 对账：用户余额 + 手续费 = 初始资金
+    let quote_total = exchange.balance(1001, QUOTE) + exchange.balance(1002, QUOTE);
+    assert_eq!(quote_total + report.by_currency[&QUOTE], 10_000);
+    assert_eq!(exchange.balance(1001, QUOTE), 10_000 - 4 * 100 - 12 - 5 * 99 - 5);
+}
+
+#[test]
+fn test_fee_report_query_and_reset() {
+    let mut exchange = Exchange::new();
+    exchange.place(1002, 1, 100, 10, OrderAction::Ask);
+    exchange.place(1001, 2, 100, 4, OrderAction::Bid);
+
+    let result = exchange.submit(OrderCommand {
+        command: OrderCommandType::BinaryDataQuery,
+        service_flags: BINARY_QUERY_FEE_REPORT,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    let report = FeeReport::decode(&result.binary_data).unwrap();
+    assert_eq!(&report, exchange.risk.fee_report());
+    assert_eq!(report.by_currency[&QUOTE], 16);
+
+    let collected = exchange.risk.reset_fees();
+    assert_eq!(collected, report);
+    assert!(exchange.risk.fee_report().by_symbol.is_empty());
+
+    let result = exchange.submit(OrderCommand {
+        command: OrderCommandType::BinaryDataQuery,
+        service_flags: 999,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::BinaryCommandFailed);
+}
@@ -0,0 +1,157 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+const QUOTE: Currency = 1;
+const BASE: Currency = 2;
+const DEPOSIT: i64 = 1_000_000;
+
+fn spec(taker_fee: i64, maker_fee: i64) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee,
+        maker_fee,
+        ..Default::default()
+    }
+}
+
+fn setup(spec: CoreSymbolSpecification, risk_engines_num: usize) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        risk_engines_num,
+        ..Default::default()
+    });
+    assert_eq!(core.add_symbol(spec), CommandResultCode::Success);
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [QUOTE, BASE] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: DEPOSIT,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn fee_tier(uid: UserId, taker_ratio: i64, maker_ratio: i64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::SetFeeTier,
+        uid,
+        price: taker_ratio,
+        size: maker_ratio,
+        ..Default::default()
+    }
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) {
+    let result = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::Success);
+}
+
+fn balance(core: &ExchangeCore, uid: UserId, currency: Currency) -> i64 {
+    let state = core.serialize_state();
+    let profile = state.pipeline_state.risk_engines.iter().find_map(|risk| risk.get_user(uid)).unwrap();
+    profile.accounts.get(&currency).copied().unwrap_or(0)
+}
+
+fn collected_fees(core: &ExchangeCore) -> (i64, i64) {
+    let state = core.serialize_state();
+    let mut report = FeeReport::default();
+    for risk in &state.pipeline_state.risk_engines {
+        report.merge(risk.fee_report());
+    }
+    report.by_symbol.get(&100).map_or((0, 0), |fees| (fees.maker_fees, fees.taker_fees))
+}
+
+#[test]
+fn test_maker_rebate_on_both_taker_sides() {
+    for risk_engines_num in [1, 2] {
+        let mut core = setup(spec(4, -2), risk_engines_num);
+
+        // Taker 买 => Maker 卖：卖方成交额外加返佣
+        place(&mut core, 1001, 1, 100, 10, OrderAction::Ask);
+        place(&mut core, 1002, 2, 100, 10, OrderAction::Bid);
+        // Taker 卖 => Maker 买：买方返还按 taker 费率冻结的手续费并加返佣
+        place(&mut core, 1001, 3, 90, 5, OrderAction::Bid);
+        place(&mut core, 1002, 4, 90, 5, OrderAction::Ask);
+
+        assert_eq!(balance(&core, 1001, QUOTE), DEPOSIT + 1000 + 20 - 450 + 10);
+        assert_eq!(balance(&core, 1001, BASE), DEPOSIT - 10 + 5);
+        assert_eq!(balance(&core, 1002, QUOTE), DEPOSIT - 1000 - 40 + 450 - 20);
+        assert_eq!(balance(&core, 1002, BASE), DEPOSIT + 10 - 5);
+        assert_eq!(collected_fees(&core), (-30, 60), "risk engines: {}", risk_engines_num);
+        core.verify_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_user_fee_tiers() {
+    let mut core = setup(spec(3, 1), 2);
+    assert_eq!(core.submit_command(fee_tier(1001, 5_000, 0)).result_code, CommandResultCode::Success);
+
+    // 1001 为 taker：3 * 3 * 50% = 4.5，向上取整收 5
+    place(&mut core, 1002, 1, 100, 3, OrderAction::Ask);
+    place(&mut core, 1001, 2, 100, 3, OrderAction::Bid);
+    assert_eq!(balance(&core, 1001, QUOTE), DEPOSIT - 300 - 5);
+    assert_eq!(balance(&core, 1002, QUOTE), DEPOSIT + 300 - 3);
+
+    // 1001 为 maker：免 maker 费，冻结的 taker 手续费全部返还
+    place(&mut core, 1001, 3, 100, 2, OrderAction::Bid);
+    place(&mut core, 1002, 4, 100, 2, OrderAction::Ask);
+    assert_eq!(balance(&core, 1001, QUOTE), DEPOSIT - 300 - 5 - 200);
+    assert_eq!(balance(&core, 1002, QUOTE), DEPOSIT + 300 - 3 + 200 - 6);
+    assert_eq!(collected_fees(&core), (3, 11));
+    core.verify_invariants().unwrap();
+
+    // 快照恢复后保留手续费等级
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    place(&mut restored, 1002, 5, 100, 1, OrderAction::Ask);
+    place(&mut restored, 1001, 6, 100, 1, OrderAction::Bid);
+    assert_eq!(balance(&restored, 1001, QUOTE), DEPOSIT - 300 - 5 - 200 - 100 - 2);
+    restored.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_fee_tier_and_spec_validation() {
+    let mut core = setup(spec(3, 1), 1);
+    let cases = [
+        (fee_tier(1001, FEE_TIER_SCALE + 1, 0), CommandResultCode::ValidationInvalidPrice),
+        (fee_tier(1001, 0, -1), CommandResultCode::ValidationInvalidSize),
+        (fee_tier(9999, 0, 0), CommandResultCode::AuthInvalidUser),
+        (fee_tier(1001, FEE_TIER_SCALE, FEE_TIER_SCALE), CommandResultCode::Success),
+    ];
+    for (i, (cmd, expected)) in cases.into_iter().enumerate() {
+        assert_eq!(core.submit_command(cmd).result_code, expected, "case {}", i);
+    }
+
+    // maker 收费超过 taker 费率时买单冻结不足，返佣超过 taker 费率时交易所亏损
+    for (taker_fee, maker_fee) in [(3, 4), (3, -4), (-1, -1)] {
+        let invalid = CoreSymbolSpecification { symbol_id: 101, ..spec(taker_fee, maker_fee) };
+        assert!(!invalid.is_valid(), "{} {}", taker_fee, maker_fee);
+    }
+    assert!(CoreSymbolSpecification { symbol_id: 101, ..spec(3, -3) }.is_valid());
+}
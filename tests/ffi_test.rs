@@ -0,0 +1,164 @@
+#![cfg(feature = "ffi")]
+
+use matching_core::api::*;
+use matching_core::ffi::*;
+use std::ffi::c_void;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+struct Collected {
+    results: Vec<(i32, i32, u64)>, // command, result_code, order_id
+    trades: Vec<(i64, i64, u64)>,  // price, size, matched_order_id
+}
+
+extern "C" fn collect(user_data: *mut c_void, result: *const McResult) {
+    let collected = unsafe { &mut *(user_data as *mut Collected) };
+    let result = unsafe { &*result };
+    collected.results.push((result.command, result.result_code, result.order_id));
+    let events = unsafe { std::slice::from_raw_parts(result.events, result.events_len) };
+    for event in events.iter().filter(|e| e.event_type == MC_EVENT_TRADE) {
+        collected.trades.push((event.price, event.size, event.matched_order_id));
+    }
+}
+
+fn command(command: i32, uid: u64) -> McCommand {
+    McCommand {
+        command,
+        uid,
+        order_id: 0,
+        symbol: 0,
+        price: 0,
+        reserve_price: 0,
+        size: 0,
+        action: MC_ACTION_ASK,
+        order_type: MC_ORDER_GTC,
+        stop_price: 0,
+        visible_size: 0,
+        expire_time: 0,
+        client_order_id: 0,
+        service_flags: 0,
+        timestamp: 0,
+    }
+}
+
+fn order(uid: u64, order_id: u64, price: i64, size: i64, action: i32) -> McCommand {
+    McCommand {
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        client_order_id: order_id * 10,
+        timestamp: 1000 + order_id as i64,
+        ..command(MC_CMD_PLACE_ORDER, uid)
+    }
+}
+
+unsafe fn setup(startup: bool) -> *mut McExchange {
+    let exchange = mc_exchange_create(ptr::null());
+    let spec = McSymbolSpec {
+        symbol_id: 100,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        tick_size: 1,
+        lot_size: 1,
+        min_size: 0,
+        max_size: 0,
+    };
+    assert_eq!(mc_exchange_add_symbol(exchange, &spec), MC_RESULT_SUCCESS);
+    if startup {
+        assert_eq!(mc_exchange_startup(exchange), MC_OK);
+    }
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        assert_eq!(mc_exchange_submit(exchange, &command(MC_CMD_ADD_USER, uid)), MC_OK);
+        let deposit = McCommand { symbol: currency, price: 1_000_000, order_id: uid, ..command(MC_CMD_BALANCE_ADJUSTMENT, uid) };
+        assert_eq!(mc_exchange_submit(exchange, &deposit), MC_OK);
+    }
+    exchange
+}
+
+unsafe fn poll_all(exchange: *mut McExchange, collected: &mut Collected) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mc_exchange_pending_results(exchange) > 0 {
+        assert!(Instant::now() < deadline, "等待结果超时");
+        let delivered = mc_exchange_poll_results(exchange, Some(collect), collected as *mut Collected as *mut c_void);
+        assert!(delivered >= 0);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_result_code_constants() {
+    assert_eq!(MC_RESULT_SUCCESS, CommandResultCode::Success as i32);
+    let config = mc_exchange_config_default();
+    assert!(config.ring_buffer_size > 0);
+}
+
+#[test]
+fn test_submit_and_poll_results() {
+    for startup in [false, true] {
+        unsafe {
+            let exchange = setup(startup);
+            assert_eq!(mc_exchange_submit(exchange, &order(1002, 1, 100, 10, MC_ACTION_ASK)), MC_OK);
+            assert_eq!(mc_exchange_submit(exchange, &order(1001, 2, 100, 4, MC_ACTION_BID)), MC_OK);
+
+            let mut collected = Collected::default();
+            poll_all(exchange, &mut collected);
+            let codes: Vec<_> = collected.results.iter().map(|r| (r.0, r.1)).collect();
+            assert_eq!(
+                codes,
+                vec![
+                    (MC_CMD_ADD_USER, MC_RESULT_SUCCESS),
+                    (MC_CMD_BALANCE_ADJUSTMENT, MC_RESULT_SUCCESS),
+                    (MC_CMD_ADD_USER, MC_RESULT_SUCCESS),
+                    (MC_CMD_BALANCE_ADJUSTMENT, MC_RESULT_SUCCESS),
+                    (MC_CMD_PLACE_ORDER, MC_RESULT_SUCCESS),
+                    (MC_CMD_PLACE_ORDER, MC_RESULT_SUCCESS),
+                ]
+            );
+            assert_eq!(collected.trades, vec![(100, 4, 1)]);
+
+            let cancel = McCommand { order_id: 1, symbol: 100, ..command(MC_CMD_CANCEL_ORDER, 1002) };
+            assert_eq!(mc_exchange_submit(exchange, &cancel), MC_OK);
+            let mut collected = Collected::default();
+            poll_all(exchange, &mut collected);
+            assert_eq!(collected.results, vec![(MC_CMD_CANCEL_ORDER, MC_RESULT_SUCCESS, 1)]);
+            mc_exchange_destroy(exchange);
+        }
+    }
+}
+
+#[test]
+fn test_invalid_arguments() {
+    unsafe {
+        let exchange = setup(false);
+        assert_eq!(mc_exchange_submit(exchange, &command(99, 1001)), MC_ERR_INVALID_ARGUMENT);
+        assert_eq!(mc_exchange_submit(exchange, &order(1001, 1, 100, 1, 7)), MC_ERR_INVALID_ARGUMENT);
+        let bad_type = McCommand { order_type: 42, ..order(1001, 1, 100, 1, MC_ACTION_BID) };
+        assert_eq!(mc_exchange_submit(exchange, &bad_type), MC_ERR_INVALID_ARGUMENT);
+
+        assert_eq!(mc_exchange_submit(exchange, ptr::null()), MC_ERR_NULL_POINTER);
+        assert_eq!(mc_exchange_submit(ptr::null_mut(), &command(MC_CMD_ADD_USER, 1)), MC_ERR_NULL_POINTER);
+        assert_eq!(mc_exchange_poll_results(exchange, None, ptr::null_mut()), MC_ERR_NULL_POINTER as i64);
+        assert_eq!(mc_exchange_pending_results(ptr::null()), 0);
+
+        // 被拒绝的命令也按顺序返回结果
+        let mut collected = Collected::default();
+        poll_all(exchange, &mut collected);
+        let rejected = McCommand { size: -1, ..order(1001, 5, 100, 1, MC_ACTION_BID) };
+        assert_eq!(mc_exchange_submit(exchange, &rejected), MC_OK);
+        let mut collected = Collected::default();
+        poll_all(exchange, &mut collected);
+        assert_eq!(collected.results.len(), 1);
+        assert_ne!(collected.results[0].1, MC_RESULT_SUCCESS);
+
+        mc_exchange_destroy(exchange);
+        mc_exchange_destroy(ptr::null_mut());
+    }
+}
@@ -0,0 +1,116 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{OrderBook, NaiveOrderBook, DirectOrderBookOptimized};
+use proptest::prelude::*;
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Place { uid: UserId, price: Price, size: Size, action: OrderAction, ioc: bool },
+    Cancel { nth: usize },
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (1..4u64, 98..103i64, 1..20i64, any::<bool>(), prop::bool::weighted(0.2)).prop_map(
+            |(uid, price, size, bid, ioc)| Op::Place {
+                uid,
+                price,
+                size,
+                action: if bid { OrderAction::Bid } else { OrderAction::Ask },
+                ioc,
+            }
+        ),
+        1 => (0..64usize).prop_map(|nth| Op::Cancel { nth }),
+    ]
+}
+
+/// 事件摘要（不比较各实现间含义不同的预留价格）
+fn summary(cmd: &OrderCommand) -> Vec<(MatcherEventType, Size, Price, OrderId, UserId, bool)> {
+    cmd.matcher_events
+        .iter()
+        .map(|e| (e.event_type, e.size, e.price, e.matched_order_id, e.matched_order_uid, e.maker_completed))
+        .collect()
+}
+
+fn run_against_naive(ops: &[Op], simd: bool) -> Result<(), TestCaseError> {
+    let mut naive = NaiveOrderBook::new(create_symbol_spec());
+    let mut optimized = DirectOrderBookOptimized::new(create_symbol_spec());
+    optimized.set_simd_enabled(simd);
+
+    let mut placed: Vec<(OrderId, UserId)> = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        let cmd = match *op {
+            Op::Place { uid, price, size, action, ioc } => {
+                let order_id = i as OrderId + 1;
+                placed.push((order_id, uid));
+                OrderCommand {
+                    command: OrderCommandType::PlaceOrder,
+                    uid,
+                    order_id,
+                    symbol: 1,
+                    price,
+                    size,
+                    action,
+                    order_type: if ioc { OrderType::Ioc } else { OrderType::Gtc },
+                    reserve_price: price,
+                    timestamp: i as i64,
+                    ..Default::default()
+                }
+            }
+            Op::Cancel { nth } => {
+                if placed.is_empty() {
+                    continue;
+                }
+                let (order_id, uid) = placed[nth % placed.len()];
+                OrderCommand {
+                    command: OrderCommandType::CancelOrder,
+                    uid,
+                    order_id,
+                    symbol: 1,
+                    ..Default::default()
+                }
+            }
+        };
+
+        let mut a = cmd.clone();
+        let mut b = cmd;
+        let (ra, rb) = if a.command == OrderCommandType::CancelOrder {
+            (naive.cancel_order(&mut a), optimized.cancel_order(&mut b))
+        } else {
+            (naive.new_order(&mut a), optimized.new_order(&mut b))
+        };
+
+        prop_assert_eq!(ra, rb, "op #{} {:?}", i, op);
+        prop_assert_eq!(summary(&a), summary(&b), "op #{} {:?}", i, op);
+        prop_assert_eq!(naive.get_l2_data(10), optimized.get_l2_data(10), "op #{}", i);
+        prop_assert_eq!(naive.get_l3_data(10, 64, false), optimized.get_l3_data(10, 64, false), "op #{}", i);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn fifo_fill_order_matches_naive(ops in prop::collection::vec(op_strategy(), 1..200)) {
+        run_against_naive(&ops, false)?;
+    }
+
+    #[test]
+    fn fifo_fill_order_matches_naive_simd(ops in prop::collection::vec(op_strategy(), 1..200)) {
+        run_against_naive(&ops, true)?;
+    }
+}
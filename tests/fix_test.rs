@@ -0,0 +1,285 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::fix::*;
+
+fn setup() -> (ExchangeCore, FixTranslator) {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    });
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid,
+            ..Default::default()
+        });
+    }
+
+    let mut fix = FixTranslator::new(1);
+    fix.add_symbol("BTC/USD", 100);
+    (core, fix)
+}
+
+/// 解析以 `|` 代替 SOH 书写的消息
+fn parse(raw: &str) -> Result<FixMessage, FixError> {
+    FixMessage::parse(&raw.replace('|', "\x01"))
+}
+
+/// 转换并提交一条 FIX 消息，返回生成的报告
+fn send(core: &mut ExchangeCore, fix: &mut FixTranslator, raw: &str) -> Vec<FixMessage> {
+    let msg = parse(raw).unwrap();
+    match fix.to_command(&msg, 1000) {
+        Ok(cmd) => {
+            let result = core.submit_command(cmd);
+            fix.execution_reports(&result)
+        }
+        Err(e) => vec![fix.reject(&msg, &e)],
+    }
+}
+
+fn field(msg: &FixMessage, tag: u32) -> &str {
+    msg.get(tag).unwrap_or_default()
+}
+
+/// (MsgType, ClOrdID, ExecType, OrdStatus, CumQty, LeavesQty)
+fn summary(msg: &FixMessage) -> (&str, &str, &str, &str, &str, &str) {
+    (
+        msg.msg_type(),
+        field(msg, tags::CL_ORD_ID),
+        field(msg, tags::EXEC_TYPE),
+        field(msg, tags::ORD_STATUS),
+        field(msg, tags::CUM_QTY),
+        field(msg, tags::LEAVES_QTY),
+    )
+}
+
+#[test]
+fn test_parse_and_encode() {
+    let mut msg = FixMessage::new(msg_type::NEW_ORDER_SINGLE);
+    msg.push(tags::CL_ORD_ID, "A1").push(tags::SYMBOL, "BTC/USD").push(tags::ORDER_QTY, 5);
+    let raw = msg.encode();
+    assert!(raw.starts_with("8=FIX.4.4\x019="));
+    assert!(raw.ends_with('\x01'));
+
+    let parsed = parse(&raw).unwrap();
+    assert_eq!(parsed, msg);
+    assert_eq!(parsed.get(tags::SYMBOL), Some("BTC/USD"));
+
+    // 校验和错误
+    let corrupted = raw.replace("A1", "A2");
+    assert!(matches!(parse(&corrupted), Err(FixError::ChecksumMismatch { .. })));
+
+    assert_eq!(parse("35=D|11=x").unwrap().msg_type(), "D");
+    assert_eq!(parse("11=x|"), Err(FixError::MissingField(tags::MSG_TYPE)));
+    assert!(matches!(parse("35=D|abc"), Err(FixError::Malformed(_))));
+}
+
+#[test]
+fn test_parse_malformed_framing() {
+    // 开头或连续的分隔符是空字段，解析返回错误而不是 panic
+    for raw in ["\x01\x0135=é\x0110=000\x01", "\x0135=D\x01", "35=D\x01\x0111=x\x01", "35=D\x01=\x01", "35=D\x01é=1\x01"] {
+        assert!(matches!(FixMessage::parse(raw), Err(FixError::Malformed(_))), "{:?}", raw);
+    }
+
+    // 校验和按 CheckSum 之前的原始字节计算（包括多字节字符）
+    let raw = "35=é\x0110=000\x01";
+    let expected = "35=é\x01".bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+    assert_eq!(FixMessage::parse(raw), Err(FixError::ChecksumMismatch { expected, actual: "000".into() }));
+    let mut msg = FixMessage::new(msg_type::NEW_ORDER_SINGLE);
+    msg.push(tags::TEXT, "价格 é");
+    assert_eq!(FixMessage::parse(&msg.encode()).unwrap(), msg);
+
+    // `|` 不是分隔符，字段值可以包含
+    let msg = FixMessage::parse("35=D\x0158=a|b\x01").unwrap();
+    assert_eq!(msg.get(tags::TEXT), Some("a|b"));
+}
+
+#[test]
+fn test_results_for_unknown_orders_ignored() {
+    let (_, mut fix) = setup();
+    let trade = MatcherTradeEvent { size: 1, price: 100, matched_order_id: 998, ..Default::default() };
+    for command in [OrderCommandType::PlaceOrder, OrderCommandType::MoveOrder, OrderCommandType::CancelOrder] {
+        let cmd = OrderCommand {
+            command,
+            result_code: CommandResultCode::Success,
+            order_id: 999,
+            matcher_events: vec![trade.clone(), MatcherTradeEvent::new_reject(1, 100, 100)],
+            ..Default::default()
+        };
+        assert!(fix.execution_reports(&cmd).is_empty());
+    }
+}
+
+#[test]
+fn test_new_order_mapping() {
+    let (_, mut fix) = setup();
+
+    let msg = parse("35=D|11=42|1=1001|55=BTC/USD|54=1|38=5|40=2|44=100|59=3").unwrap();
+    let cmd = fix.to_command(&msg, 7).unwrap();
+    assert_eq!(cmd.command, OrderCommandType::PlaceOrder);
+    assert_eq!((cmd.uid, cmd.order_id, cmd.symbol), (1001, 1, 100));
+    assert_eq!((cmd.price, cmd.reserve_price, cmd.size), (100, 100, 5));
+    assert_eq!((cmd.action, cmd.order_type), (OrderAction::Bid, OrderType::Ioc));
+    assert_eq!((cmd.client_order_id, cmd.timestamp), (42, 7));
+    assert_eq!(fix.order_id("42"), Some(1));
+
+    let post_only = parse("35=D|11=43|1=1001|55=100|54=2|38=5|40=2|44=100|18=6").unwrap();
+    let cmd = fix.to_command(&post_only, 7).unwrap();
+    assert_eq!((cmd.symbol, cmd.action, cmd.order_type), (100, OrderAction::Ask, OrderType::PostOnly));
+
+    let stop = parse("35=D|11=44|1=1001|55=BTC/USD|54=2|38=5|40=4|44=90|99=95").unwrap();
+    let cmd = fix.to_command(&stop, 7).unwrap();
+    assert_eq!((cmd.order_type, cmd.stop_price), (OrderType::StopLimit, Some(95)));
+
+    let min_qty = parse("35=D|11=45|1=1001|55=BTC/USD|54=1|38=5|40=2|44=100|59=3|110=4").unwrap();
+    assert_eq!(fix.to_command(&min_qty, 7).unwrap().min_fill_size, Some(4));
+
+    let cases = [
+        ("35=D|11=42|1=1001|55=BTC/USD|54=1|38=5|40=2|44=100", FixError::DuplicateClOrdId("42".into())),
+        ("35=D|11=50|1=1001|55=ETH/USD|54=1|38=5|40=2|44=100", FixError::UnknownSymbol("ETH/USD".into())),
+        ("35=D|11=51|1=1001|55=BTC/USD|54=1|38=5|40=2", FixError::MissingField(tags::PRICE)),
+        ("35=D|11=52|1=1001|55=BTC/USD|54=1|38=5|40=3", FixError::MissingField(tags::STOP_PX)),
+        ("35=D|11=53|1=1001|55=BTC/USD|54=7|38=5|40=2|44=100", FixError::InvalidValue(tags::SIDE, "7".into())),
+        ("35=D|11=54|1=1001|55=BTC/USD|54=1|38=5.5|40=2|44=100", FixError::InvalidValue(tags::ORDER_QTY, "5.5".into())),
+        ("35=A|98=0", FixError::UnsupportedMsgType("A".into())),
+    ];
+    for (raw, expected) in cases {
+        let msg = parse(raw).unwrap();
+        assert_eq!(fix.to_command(&msg, 7).unwrap_err(), expected, "{}", raw);
+    }
+}
+
+#[test]
+fn test_fills_and_cancel_reports() {
+    let (mut core, mut fix) = setup();
+
+    let reports = send(&mut core, &mut fix, "35=D|11=S1|1=1002|55=BTC/USD|54=2|38=10|40=2|44=100");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(summary(&reports[0]), ("8", "S1", "0", "0", "0", "10"));
+    assert_eq!(field(&reports[0], tags::ORDER_ID), "1");
+
+    // 买单部分吃掉卖单：taker 全部成交，maker 部分成交
+    let reports = send(&mut core, &mut fix, "35=D|11=B1|1=1001|55=BTC/USD|54=1|38=4|40=2|44=100");
+    let summaries: Vec<_> = reports.iter().map(summary).collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("8", "B1", "0", "0", "0", "4"),
+            ("8", "B1", "F", "2", "4", "0"),
+            ("8", "S1", "F", "1", "4", "6"),
+        ]
+    );
+    assert_eq!((field(&reports[1], tags::LAST_QTY), field(&reports[1], tags::LAST_PX)), ("4", "100"));
+    assert_eq!(field(&reports[2], tags::AVG_PX), "100");
+    assert_eq!(fix.order_id("B1"), None);
+
+    // 撤单：报告使用撤单请求的 ClOrdID，并带原 ClOrdID
+    let reports = send(&mut core, &mut fix, "35=F|11=C1|41=S1|1=1002|55=BTC/USD|54=2");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(summary(&reports[0]), ("8", "C1", "4", "4", "4", "0"));
+    assert_eq!(field(&reports[0], tags::ORIG_CL_ORD_ID), "S1");
+    assert_eq!(fix.order_id("S1"), None);
+
+    // 已完结订单的撤单被拒绝
+    let reports = send(&mut core, &mut fix, "35=F|11=C2|41=S1|1=1002|55=BTC/USD|54=2");
+    assert_eq!(reports[0].msg_type(), msg_type::ORDER_CANCEL_REJECT);
+    assert_eq!(field(&reports[0], tags::CXL_REJ_RESPONSE_TO), "1");
+    assert_eq!(field(&reports[0], tags::CXL_REJ_REASON), "1");
+}
+
+#[test]
+fn test_ioc_remainder_and_engine_reject() {
+    let (mut core, mut fix) = setup();
+
+    send(&mut core, &mut fix, "35=D|11=S1|1=1002|55=BTC/USD|54=2|38=3|40=2|44=100");
+    let reports = send(&mut core, &mut fix, "35=D|11=B1|1=1001|55=BTC/USD|54=1|38=5|40=2|44=100|59=3");
+    let summaries: Vec<_> = reports.iter().map(summary).collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("8", "B1", "0", "0", "0", "5"),
+            ("8", "B1", "F", "1", "3", "2"),
+            ("8", "S1", "F", "2", "3", "0"),
+            ("8", "B1", "4", "4", "3", "0"),
+        ]
+    );
+
+    // 余额不足：引擎拒绝
+    let reports = send(&mut core, &mut fix, "35=D|11=B2|1=1001|55=BTC/USD|54=1|38=1000000|40=2|44=100");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(summary(&reports[0]), ("8", "B2", "8", "8", "0", "0"));
+    assert_eq!(field(&reports[0], tags::TEXT), "RiskNsf");
+    assert_eq!(fix.order_id("B2"), None);
+}
+
+#[test]
+fn test_cancel_replace() {
+    let (mut core, mut fix) = setup();
+    send(&mut core, &mut fix, "35=D|11=S1|1=1002|55=BTC/USD|54=2|38=10|40=2|44=105");
+
+    // 改价
+    let reports = send(&mut core, &mut fix, "35=G|11=S2|41=S1|1=1002|55=BTC/USD|54=2|38=10|40=2|44=101");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(summary(&reports[0]), ("8", "S2", "5", "5", "0", "10"));
+    assert_eq!((field(&reports[0], tags::ORIG_CL_ORD_ID), field(&reports[0], tags::PRICE)), ("S1", "101"));
+    assert_eq!(fix.order_id("S1"), None);
+    assert_eq!(fix.order_id("S2"), Some(1));
+
+    // 减少数量
+    let reports = send(&mut core, &mut fix, "35=G|11=S3|41=S2|1=1002|55=BTC/USD|54=2|38=6|40=2|44=101");
+    assert_eq!(summary(&reports[0]), ("8", "S3", "5", "5", "0", "6"));
+    assert_eq!(field(&reports[0], tags::ORDER_QTY), "6");
+
+    let book = core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 5,
+        ..Default::default()
+    });
+    let book = book.market_data.unwrap();
+    assert_eq!((book.ask_prices, book.ask_volumes), (vec![101], vec![6]));
+
+    // 增加数量、同时修改价格与数量均不支持
+    for raw in [
+        "35=G|11=S4|41=S3|1=1002|55=BTC/USD|54=2|38=8|40=2|44=101",
+        "35=G|11=S4|41=S3|1=1002|55=BTC/USD|54=2|38=5|40=2|44=102",
+    ] {
+        let reports = send(&mut core, &mut fix, raw);
+        assert_eq!(reports[0].msg_type(), msg_type::ORDER_CANCEL_REJECT);
+        assert_eq!(field(&reports[0], tags::CXL_REJ_RESPONSE_TO), "2");
+        assert_eq!(field(&reports[0], tags::ORDER_ID), "1");
+        assert_eq!(field(&reports[0], tags::ORD_STATUS), "0");
+    }
+
+    // 改价后立即成交
+    send(&mut core, &mut fix, "35=D|11=B1|1=1001|55=BTC/USD|54=1|38=2|40=2|44=99");
+    let reports = send(&mut core, &mut fix, "35=G|11=S5|41=S3|1=1002|55=BTC/USD|54=2|38=6|40=2|44=99");
+    let summaries: Vec<_> = reports.iter().map(summary).collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("8", "S5", "5", "5", "0", "6"),
+            ("8", "S5", "F", "1", "2", "4"),
+            ("8", "B1", "F", "2", "2", "0"),
+        ]
+    );
+}
+
+#[test]
+fn test_unsupported_message_is_business_rejected() {
+    let (mut core, mut fix) = setup();
+    let reports = send(&mut core, &mut fix, "35=H|11=Q1");
+    assert_eq!(reports[0].msg_type(), msg_type::BUSINESS_MESSAGE_REJECT);
+    assert_eq!(field(&reports[0], tags::REF_MSG_TYPE), "H");
+}
@@ -0,0 +1,187 @@
+use matching_core::api::*;
+use matching_core::core::processors::funding::FundingEngine;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+const QUOTE: Currency = 1;
+const INITIAL: i64 = 100_000;
+
+struct Exchange {
+    risk: RiskEngine,
+    matching: MatchingEngineRouter,
+}
+
+impl Exchange {
+    fn new() -> Self {
+        let mut exchange = Self {
+            risk: RiskEngine::new(0, 1),
+            matching: MatchingEngineRouter::new(0, 1),
+        };
+        for (symbol_id, symbol_type) in [(100, SymbolType::PerpetualSwap), (200, SymbolType::FuturesContract)] {
+            let spec = CoreSymbolSpecification {
+                symbol_id,
+                symbol_type,
+                base_currency: 2,
+                quote_currency: QUOTE,
+                base_scale_k: 1,
+                quote_scale_k: 1,
+                margin_buy: 100,
+                margin_sell: 100,
+                funding_interval: 8,
+                ..Default::default()
+            };
+            exchange.risk.add_symbol(spec.clone());
+            exchange.matching.add_symbol(spec);
+        }
+
+        for uid in [1001, 1002] {
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            });
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: QUOTE,
+                price: INITIAL,
+                order_id: uid as OrderId,
+                ..Default::default()
+            });
+        }
+
+        // A 多 10，B 空 10
+        exchange.place(1002, 1, 1000, 10, OrderAction::Ask);
+        exchange.place(1001, 2, 1000, 10, OrderAction::Bid);
+        exchange
+    }
+
+    fn submit(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        self.risk.pre_process(&mut cmd);
+        self.matching.process_order(&mut cmd);
+        self.risk.post_process(&mut cmd);
+        cmd
+    }
+
+    fn place(&mut self, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) {
+        let result = self.submit(OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id,
+            symbol: 100,
+            price,
+            reserve_price: price,
+            size,
+            action,
+            order_type: OrderType::Gtc,
+            ..Default::default()
+        });
+        assert_eq!(result.result_code, CommandResultCode::Success);
+    }
+
+    fn set_mark_price(&mut self, symbol: SymbolId, price: Price) -> CommandResultCode {
+        self.submit(OrderCommand {
+            command: OrderCommandType::SetMarkPrice,
+            symbol,
+            price,
+            ..Default::default()
+        })
+        .result_code
+    }
+
+    fn apply_funding(&mut self, symbol: SymbolId, rate: i64, timestamp: i64) -> CommandResultCode {
+        self.submit(OrderCommand {
+            command: OrderCommandType::ApplyFunding,
+            symbol,
+            price: rate,
+            timestamp,
+            ..Default::default()
+        })
+        .result_code
+    }
+
+    fn balance(&self, uid: UserId) -> i64 {
+        self.risk.get_user(uid).unwrap().accounts.get(&QUOTE).copied().unwrap_or(0)
+    }
+}
+
+#[test]
+fn test_funding_payments() {
+    let mut exchange = Exchange::new();
+    let before = (exchange.balance(1001), exchange.balance(1002));
+
+    assert_eq!(exchange.apply_funding(100, 100, 0), CommandResultCode::RiskMarkPriceNotSet);
+    assert_eq!(exchange.set_mark_price(100, 1000), CommandResultCode::Success);
+
+    // 费率为正：多头支付 10 * 1000 * 0.0002 = 2
+    assert_eq!(exchange.apply_funding(100, 200, 0), CommandResultCode::Success);
+    assert_eq!(exchange.balance(1001), before.0 - 2);
+    assert_eq!(exchange.balance(1002), before.1 + 2);
+
+    // 未到结算时间
+    assert_eq!(exchange.apply_funding(100, 200, 5), CommandResultCode::RiskFundingNotDue);
+    assert_eq!(exchange.balance(1001), before.0 - 2);
+
+    // 费率为负：空头支付
+    assert_eq!(exchange.apply_funding(100, -300, 8), CommandResultCode::Success);
+    assert_eq!(exchange.balance(1001), before.0 - 2 + 3);
+    assert_eq!(exchange.balance(1002), before.1 + 2 - 3);
+    assert_eq!(exchange.risk.funding().get_state(100).unwrap().last_funding_time, Some(8));
+}
+
+#[test]
+fn test_funding_only_for_perpetuals() {
+    let mut exchange = Exchange::new();
+    assert_eq!(exchange.set_mark_price(200, 1000), CommandResultCode::Success);
+    assert_eq!(exchange.apply_funding(200, 100, 0), CommandResultCode::UnsupportedSymbolType);
+    assert_eq!(exchange.set_mark_price(999, 1000), CommandResultCode::InvalidSymbol);
+}
+
+#[test]
+fn test_reference_mark_price_used_for_funding() {
+    let mut exchange = Exchange::new();
+    let index = exchange.submit(OrderCommand::reference_price_update(100, StopTriggerSource::IndexPrice, 1000, 0));
+    assert_eq!(index.result_code, CommandResultCode::Success);
+    assert_eq!(exchange.apply_funding(100, 200, 0), CommandResultCode::RiskMarkPriceNotSet);
+
+    // 推送的标记价同时作为资金费结算价
+    let mark = exchange.submit(OrderCommand::reference_price_update(100, StopTriggerSource::MarkPrice, 1000, 0));
+    assert_eq!(mark.result_code, CommandResultCode::Success);
+    assert_eq!(exchange.risk.funding().get_state(100).unwrap().mark_price, Some(1000));
+    assert_eq!(exchange.apply_funding(100, 200, 0), CommandResultCode::Success);
+}
+
+#[test]
+fn test_funding_payment_rounding() {
+    // 支付方向上取整，收取方向下取整，交易所不会倒贴
+    assert_eq!(FundingEngine::funding_payment(10, 1000, 150, 1), 2);
+    assert_eq!(FundingEngine::funding_payment(-10, 1000, 150, 1), -1);
+    assert_eq!(FundingEngine::funding_payment(0, 1000, 150, 1), 0);
+}
+
+#[test]
+fn test_unrealized_pnl_at_mark_price() {
+    let mut exchange = Exchange::new();
+    let positions = |exchange: &mut Exchange, uid| {
+        let result = exchange.submit(OrderCommand { command: OrderCommandType::BinaryDataQuery, service_flags: BINARY_QUERY_POSITIONS, uid, ..Default::default() });
+        assert_eq!(result.result_code, CommandResultCode::Success);
+        bincode::deserialize::<Vec<PositionReport>>(&result.binary_data).unwrap()
+    };
+    let long = positions(&mut exchange, 1001);
+    assert_eq!(long.len(), 1);
+    assert_eq!((long[0].symbol, long[0].net_position, long[0].open_cost, long[0].unrealized_pnl), (100, 10, 10_000, None));
+
+    // 指数价不影响盈亏，标记价 1050：多头 +500，空头 -500
+    exchange.submit(OrderCommand::reference_price_update(100, StopTriggerSource::IndexPrice, 990, 0));
+    assert_eq!(positions(&mut exchange, 1001)[0].unrealized_pnl, None);
+    exchange.submit(OrderCommand::reference_price_update(100, StopTriggerSource::MarkPrice, 1050, 0));
+    assert_eq!(positions(&mut exchange, 1001)[0].unrealized_pnl, Some(500));
+    let short = positions(&mut exchange, 1002);
+    assert_eq!((short[0].net_position, short[0].open_cost, short[0].mark_price, short[0].unrealized_pnl), (-10, -10_000, Some(1050), Some(-500)));
+    assert_eq!(exchange.risk.reference_prices(100).copied(), Some(ReferencePrices { symbol: 100, mark_price: Some(1050), index_price: Some(990) }));
+
+    // SetMarkPrice 同样更新标记价
+    assert_eq!(exchange.set_mark_price(100, 980), CommandResultCode::Success);
+    let position = exchange.risk.get_user(1001).unwrap().positions[&100].clone();
+    assert_eq!(exchange.risk.unrealized_pnl(&position), Some(-200));
+}
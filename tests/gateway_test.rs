@@ -0,0 +1,215 @@
+#![cfg(feature = "gateway")]
+
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::gateway::*;
+use std::time::Duration;
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    }
+}
+
+fn setup() -> GatewayService {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(spec(100));
+    core.add_symbol(spec(200));
+    let gateway = GatewayService::new(core);
+
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        let result = gateway.execute(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        assert_eq!(result.result_code, CommandResultCode::Success);
+        let reply = gateway.adjust_balance(BalanceAdjustmentRequest {
+            uid,
+            currency,
+            amount: 1_000_000,
+            transaction_id: uid,
+            timestamp: 0,
+        });
+        assert_eq!(reply.result_code, CommandResultCode::Success);
+    }
+    gateway
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: 0,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        client_order_id: order_id * 10,
+        timestamp: 1000 + order_id as i64,
+        assign_order_id: false,
+    }
+}
+
+#[test]
+fn test_request_mapping() {
+    let cmd = OrderCommand::from(order(1001, 7, 100, 105, 3, OrderAction::Bid));
+    assert_eq!(cmd.command, OrderCommandType::PlaceOrder);
+    assert_eq!((cmd.price, cmd.reserve_price, cmd.size, cmd.client_order_id), (105, 105, 3, 70));
+
+    let cmd = OrderCommand::from(MoveOrderRequest { uid: 1001, order_id: 7, symbol: 100, new_price: 110, timestamp: 5 });
+    assert_eq!((cmd.command, cmd.price), (OrderCommandType::MoveOrder, 110));
+
+    let cmd = OrderCommand::from(ReduceOrderRequest { uid: 1001, order_id: 7, symbol: 100, reduce_size: 2, timestamp: 5 });
+    assert_eq!((cmd.command, cmd.size), (OrderCommandType::ReduceOrder, 2));
+
+    let cmd = OrderCommand::from(BalanceAdjustmentRequest { uid: 1001, currency: 1, amount: -50, transaction_id: 9, timestamp: 5 });
+    assert_eq!((cmd.command, cmd.symbol, cmd.price, cmd.order_id), (OrderCommandType::BalanceAdjustment, 1, -50, 9));
+}
+
+#[test]
+fn test_order_lifecycle_through_gateway() {
+    let gateway = setup();
+
+    let reply = gateway.place_order(order(1002, 1, 100, 100, 10, OrderAction::Ask));
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+    let reply = gateway.move_order(MoveOrderRequest { uid: 1002, order_id: 1, symbol: 100, new_price: 101, timestamp: 2000 });
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+
+    let reply = gateway.place_order(order(1001, 2, 100, 101, 4, OrderAction::Bid));
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+    let trade = reply.events.iter().find(|e| e.event_type == MatcherEventType::Trade).unwrap();
+    assert_eq!((trade.size, trade.price, trade.matched_order_id), (4, 101, 1));
+
+    let reply = gateway.reduce_order(ReduceOrderRequest { uid: 1002, order_id: 1, symbol: 100, reduce_size: 2, timestamp: 2001 });
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+
+    let book = gateway.order_book(OrderBookRequest { symbol: 100, depth: 5 });
+    assert_eq!(book.result_code, CommandResultCode::Success);
+    let book = book.book.unwrap();
+    assert_eq!((book.ask_prices, book.ask_volumes), (vec![101], vec![4]));
+
+    let reply = gateway.cancel_order(CancelOrderRequest { uid: 1002, order_id: 1, symbol: 100, timestamp: 2002 });
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+    let reply = gateway.cancel_order(CancelOrderRequest { uid: 1002, order_id: 1, symbol: 100, timestamp: 2003 });
+    assert_eq!(reply.result_code, CommandResultCode::MatchingUnknownOrderId);
+
+    // 引擎分配订单号，应答中返回
+    let reply = gateway.place_order(PlaceOrderRequest { assign_order_id: true, ..order(1002, 0, 100, 105, 1, OrderAction::Ask) });
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+    assert!(reply.order_id >= ASSIGNED_ORDER_ID_BASE);
+    let reply = gateway.cancel_order(CancelOrderRequest { uid: 1002, order_id: reply.order_id, symbol: 100, timestamp: 2004 });
+    assert_eq!(reply.result_code, CommandResultCode::Success);
+
+    // 重复交易号的入金幂等
+    let reply = gateway.adjust_balance(BalanceAdjustmentRequest { uid: 1001, currency: 1, amount: 1_000_000, transaction_id: 1001, timestamp: 0 });
+    assert_eq!(reply.result_code, CommandResultCode::UserMgmtAdjustmentAlreadyApplied);
+}
+
+#[test]
+fn test_market_data_subscription_filters_by_symbol() {
+    let gateway = setup();
+    let only_100 = gateway.subscribe(&[100]);
+    let all = gateway.subscribe(&[]);
+    let dropped = gateway.subscribe(&[200]);
+    drop(dropped);
+    assert_eq!(gateway.subscriber_count(), 3);
+
+    gateway.place_order(order(1002, 1, 200, 100, 5, OrderAction::Ask));
+    gateway.place_order(order(1002, 2, 100, 100, 5, OrderAction::Ask));
+    gateway.place_order(order(1001, 3, 100, 100, 2, OrderAction::Bid));
+
+    let timeout = Duration::from_secs(5);
+    let mut received = Vec::new();
+    while let Ok(event) = only_100.recv_timeout(Duration::from_millis(200)) {
+        received.push(event);
+    }
+    assert!(!received.is_empty());
+    assert!(received.iter().all(|event| event.symbol() == 100));
+    assert!(received.iter().any(|event| matches!(event, MarketDataEvent::Trade(tick) if tick.size == 2 && tick.price == 100)));
+
+    let first = all.recv_timeout(timeout).unwrap();
+    assert_eq!(first.symbol(), 200);
+    let mut total = 1;
+    while all.recv_timeout(Duration::from_millis(200)).is_ok() {
+        total += 1;
+    }
+    assert!(total > received.len());
+
+    // 断开的订阅者在分发时移除
+    assert_eq!(gateway.subscriber_count(), 2);
+}
+
+#[test]
+fn test_grpc_round_trip() {
+    use matching_core::gateway::proto;
+    use std::sync::Arc;
+
+    let gateway = Arc::new(setup());
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async move {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(GrpcGateway::new(gateway).serve(listener));
+
+        let mut client = ExchangeGatewayClient::connect(format!("http://{}", addr)).await.unwrap();
+        let mut stream = client
+            .subscribe_market_data(proto::MarketDataSubscription { symbols: vec![100] })
+            .await
+            .unwrap()
+            .into_inner();
+
+        let ask = proto::PlaceOrderRequest {
+            uid: 1002,
+            order_id: 1,
+            symbol: 100,
+            price: 100,
+            size: 5,
+            action: proto::OrderAction::Ask as i32,
+            order_type: proto::OrderType::Gtc as i32,
+            ..Default::default()
+        };
+        let reply = client.place_order(ask.clone()).await.unwrap().into_inner();
+        assert_eq!(reply.result_code, "Success");
+
+        let bid = proto::PlaceOrderRequest { uid: 1001, order_id: 2, size: 3, action: proto::OrderAction::Bid as i32, ..ask.clone() };
+        let reply = client.place_order(bid).await.unwrap().into_inner();
+        assert_eq!(reply.result_code, "Success");
+        assert_eq!((reply.events[0].event_type.as_str(), reply.events[0].size, reply.events[0].matched_order_id), ("Trade", 3, 1));
+
+        let book = client.get_order_book(proto::OrderBookRequest { symbol: 100, depth: 5 }).await.unwrap().into_inner();
+        assert_eq!((book.ask_prices, book.ask_volumes), (vec![100], vec![2]));
+
+        let reply = client
+            .cancel_order(proto::CancelOrderRequest { uid: 1002, order_id: 1, symbol: 100, timestamp: 0 })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(reply.result_code, "Success");
+
+        // 非法枚举值
+        let invalid = proto::PlaceOrderRequest { order_type: 99, ..ask };
+        let status = client.place_order(invalid).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        // 行情流：挂单、成交、撤单均推送
+        let mut trades = 0;
+        let mut last_bbo = None;
+        while let Ok(Ok(Some(event))) = tokio::time::timeout(Duration::from_millis(500), stream.message()).await {
+            match event.event {
+                Some(proto::market_data_event::Event::Trade(tick)) => {
+                    assert_eq!((tick.symbol, tick.price, tick.size), (100, 100, 3));
+                    trades += 1;
+                }
+                Some(proto::market_data_event::Event::Bbo(bbo)) => last_bbo = Some(bbo),
+                _ => {}
+            }
+        }
+        assert_eq!(trades, 1);
+        assert_eq!(last_bbo.map(|bbo| (bbo.ask, bbo.ask_size)), Some((None, 0)));
+    });
+}
@@ -0,0 +1,214 @@
+use matching_core::api::*;
+use matching_core::core::clock::ManualClock;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+use std::sync::Arc;
+
+fn spec(order_book: OrderBookKind) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        order_book: Some(order_book),
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1_000,
+        ..Default::default()
+    }
+}
+
+fn sweep(timestamp: i64) -> OrderCommand {
+    OrderCommand { command: OrderCommandType::ExpireOrders, symbol: 100, timestamp, ..Default::default() }
+}
+
+#[test]
+fn test_pending_until_activation_time() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(OrderBookKind::Advanced));
+    book.new_order(&mut order(1, 1, 100, 5, OrderAction::Bid));
+
+    // 未到生效时间：不撮合、不显示，但可查询且占用订单号
+    let mut gat = OrderCommand { activate_time: Some(2_000), ..order(2, 2, 100, 3, OrderAction::Ask) };
+    assert_eq!(book.new_order(&mut gat), CommandResultCode::Success);
+    assert!(gat.matcher_events.is_empty());
+    assert_eq!(book.get_total_ask_volume(), 0);
+    assert_eq!(book.get_open_order(2).map(|o| o.remaining), Some(3));
+    assert_eq!(book.get_all_orders().len(), 2);
+    assert_eq!(book.new_order(&mut order(2, 2, 101, 1, OrderAction::Ask)), CommandResultCode::MatchingDuplicateOrderId);
+
+    let mut early = sweep(1_999);
+    book.expire_orders(early.timestamp, &mut early.matcher_events);
+    book.activate_pending_orders(&mut early);
+    assert!(early.matcher_events.is_empty());
+    assert!(book.take_activated_stops().is_empty());
+
+    // 到期激活：事件以被激活的订单为 taker
+    let mut due = sweep(2_000);
+    book.activate_pending_orders(&mut due);
+    assert_eq!(due.matcher_events.len(), 1);
+    let trade = &due.matcher_events[0];
+    assert_eq!((trade.matched_order_id, trade.size, trade.taker(&due)), (1, 3, (2, 2, OrderAction::Ask)));
+    assert_eq!(book.take_activated_stops(), vec![2]);
+    assert_eq!(book.get_total_bid_volume(), 2);
+    assert!(book.get_open_order(2).is_none());
+}
+
+#[test]
+fn test_activated_order_queues_behind_resting_orders() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(OrderBookKind::Advanced));
+    book.new_order(&mut OrderCommand { activate_time: Some(2_000), ..order(1, 1, 100, 3, OrderAction::Ask) });
+    book.new_order(&mut OrderCommand { timestamp: 1_500, ..order(2, 2, 100, 4, OrderAction::Ask) });
+
+    // 激活时间晚于已挂出的订单：按激活时间排队
+    let mut due = sweep(2_000);
+    book.activate_pending_orders(&mut due);
+    let level = &book.get_l3_data(1, 10, false).asks[0];
+    let queue: Vec<_> = level.orders.iter().map(|o| (o.order_id, o.timestamp)).collect();
+    assert_eq!(queue, vec![(2, 1_500), (1, 2_000)]);
+
+    let mut taker = order(3, 3, 100, 4, OrderAction::Bid);
+    book.new_order(&mut taker);
+    assert_eq!(taker.matcher_events[0].matched_order_id, 2);
+    assert_eq!(book.get_open_order(1).map(|o| o.remaining), Some(3));
+}
+
+#[test]
+fn test_cancel_and_expire_while_pending() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(OrderBookKind::Advanced));
+    book.new_order(&mut OrderCommand { activate_time: Some(2_000), ..order(1, 1, 100, 5, OrderAction::Ask) });
+    book.new_order(&mut OrderCommand { activate_time: Some(2_000), expire_time: Some(1_500), ..order(1, 2, 100, 4, OrderAction::Ask) });
+
+    let mut cancel = OrderCommand { command: OrderCommandType::CancelOrder, uid: 2, order_id: 1, symbol: 100, ..Default::default() };
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::MatchingUnauthorizedAction);
+    cancel.uid = 1;
+    assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::Success);
+    assert_eq!((cancel.matcher_events[0].size, cancel.action), (5, OrderAction::Ask));
+
+    // 生效前过期的订单不再激活
+    let mut due = sweep(2_000);
+    assert_eq!(book.expire_orders(due.timestamp, &mut due.matcher_events), 1);
+    book.activate_pending_orders(&mut due);
+    assert_eq!(due.matcher_events.len(), 1);
+    assert_eq!(book.get_all_orders().len(), 0);
+
+    // 其余订单簿不支持 GoodAfterTime
+    let mut direct = new_order_book(OrderBookKind::Direct, spec(OrderBookKind::Direct));
+    let mut gat = OrderCommand { activate_time: Some(2_000), ..order(1, 3, 100, 5, OrderAction::Ask) };
+    assert_eq!(direct.new_order(&mut gat), CommandResultCode::MatchingUnsupportedCommand);
+}
+
+#[test]
+fn test_activated_by_clock() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.set_clock(clock.clone());
+    core.add_symbol(spec(OrderBookKind::Advanced));
+    for (uid, currency) in [(1, 1), (2, 2)] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: currency, price: 1_000, order_id: uid, ..Default::default() });
+    }
+
+    let bid = OrderCommand { timestamp: 0, ..order(1, 1, 10, 5, OrderAction::Bid) };
+    assert_eq!(core.submit_command(bid).result_code, CommandResultCode::Success);
+    let gat = OrderCommand { timestamp: 0, activate_time: Some(1_500), ..order(2, 2, 10, 5, OrderAction::Ask) };
+    assert_eq!(core.submit_command(gat).result_code, CommandResultCode::Success);
+    let pending = OrderCommand { timestamp: 0, activate_time: Some(1_800), ..order(2, 3, 10, 5, OrderAction::Ask) };
+    assert_eq!(core.submit_command(pending).result_code, CommandResultCode::Success);
+
+    // 推进时钟：激活的订单成交，不计入过期数
+    let advance = core.submit_command(OrderCommand { command: OrderCommandType::AdvanceTime, timestamp: 1_500, ..Default::default() });
+    assert_eq!((advance.result_code, advance.size), (CommandResultCode::Success, 0));
+    let state = core.serialize_state();
+    let risk = &state.pipeline_state.risk_engines[0];
+    assert_eq!((risk.get_user(1).unwrap().accounts[&2], risk.get_user(2).unwrap().accounts[&1]), (5, 50));
+
+    // 撤销待生效订单返还冻结
+    let cancel = OrderCommand { command: OrderCommandType::CancelOrder, uid: 2, order_id: 3, symbol: 100, ..Default::default() };
+    assert_eq!(core.submit_command(cancel).result_code, CommandResultCode::Success);
+    let state = core.serialize_state();
+    assert_eq!(state.pipeline_state.risk_engines[0].get_user(2).unwrap().accounts[&2], 995);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_modify_while_pending() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(OrderBookKind::Advanced));
+    book.new_order(&mut OrderCommand { activate_time: Some(2_000), ..order(1, 1, 100, 10, OrderAction::Bid) });
+    let request = |command, price, size| OrderCommand { command, uid: 1, order_id: 1, symbol: 100, price, size, ..Default::default() };
+
+    // 改单：修改暂存命令的价格与数量，不进入订单簿
+    let mut amend = request(OrderCommandType::AmendOrder, 90, 8);
+    assert_eq!(book.amend_order(&mut amend), CommandResultCode::Success);
+    assert_eq!(amend.matcher_events.len(), 1);
+    assert_eq!(amend.matcher_events[0].event_type, MatcherEventType::Amend);
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_open_order(1).map(|o| (o.price, o.remaining)), Some((90, 8)));
+
+    // 价格不变只减量：改单事件携带减少的数量
+    let mut amend = request(OrderCommandType::AmendOrder, 0, 6);
+    assert_eq!(book.amend_order(&mut amend), CommandResultCode::Success);
+    assert_eq!(amend.matcher_events.iter().map(|e| (e.event_type, e.size)).collect::<Vec<_>>(), vec![(MatcherEventType::Amend, 2)]);
+
+    let mut reduce = request(OrderCommandType::ReduceOrder, 0, 2);
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+    assert_eq!((reduce.matcher_events[0].size, reduce.action), (2, OrderAction::Bid));
+    let mut move_cmd = request(OrderCommandType::MoveOrder, 95, 0);
+    assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::Success);
+    let mut over_reserve = request(OrderCommandType::MoveOrder, 101, 0);
+    assert_eq!(book.move_order(&mut over_reserve), CommandResultCode::RiskInvalidReserveBidPrice);
+    let mut other_user = OrderCommand { uid: 2, ..request(OrderCommandType::ReduceOrder, 0, 1) };
+    assert_eq!(book.reduce_order(&mut other_user), CommandResultCode::MatchingUnauthorizedAction);
+    assert_eq!(book.get_open_order(1).map(|o| (o.price, o.remaining)), Some((95, 4)));
+
+    // 激活时按修改后的命令挂出；减至 0 时移除
+    let mut due = sweep(2_000);
+    book.activate_pending_orders(&mut due);
+    assert_eq!(book.get_order_by_id(1), Some((95, OrderAction::Bid)));
+    assert_eq!(book.get_total_bid_volume(), 4);
+
+    book.new_order(&mut OrderCommand { activate_time: Some(3_000), ..order(1, 2, 100, 3, OrderAction::Bid) });
+    let mut reduce = OrderCommand { order_id: 2, ..request(OrderCommandType::ReduceOrder, 0, 5) };
+    assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+    assert_eq!(reduce.matcher_events[0].size, 3);
+    assert!(book.get_open_order(2).is_none());
+}
+
+#[test]
+fn test_modify_pending_conserves_balances() {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(spec(OrderBookKind::Advanced));
+    for (uid, currency) in [(1, 1), (2, 2)] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: currency, price: 1_000, order_id: uid, ..Default::default() });
+    }
+    let request = |command, price, size| OrderCommand { command, uid: 2, order_id: 2, symbol: 100, price, size, timestamp: 1_000, ..Default::default() };
+
+    assert_eq!(core.submit_command(order(1, 1, 10, 20, OrderAction::Bid)).result_code, CommandResultCode::Success);
+    let gat = OrderCommand { activate_time: Some(2_000), ..order(2, 2, 10, 10, OrderAction::Ask) };
+    assert_eq!(core.submit_command(gat).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(request(OrderCommandType::AmendOrder, 0, 8)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(request(OrderCommandType::ReduceOrder, 0, 2)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(request(OrderCommandType::MoveOrder, 9, 0)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(request(OrderCommandType::AmendOrder, 10, 7)).result_code, CommandResultCode::Success);
+    core.verify_invariants().unwrap();
+
+    // 激活后只按修改后的数量成交，减量部分已返还
+    core.submit_command(sweep(2_000));
+    let state = core.serialize_state();
+    let risk = &state.pipeline_state.risk_engines[0];
+    assert_eq!((risk.get_user(2).unwrap().accounts[&2], risk.get_user(2).unwrap().accounts[&1]), (993, 70));
+    core.verify_invariants().unwrap();
+}
@@ -0,0 +1,119 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::journal::ResultJournaler;
+use matching_core::core::processors::grouping::GroupingProcessor;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn add_user(uid: UserId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    }
+}
+
+fn control(command: OrderCommandType) -> OrderCommand {
+    OrderCommand {
+        command,
+        ..Default::default()
+    }
+}
+
+fn config() -> ExchangeConfig {
+    ExchangeConfig {
+        ring_buffer_size: 1024,
+        msgs_in_group_limit: 3,
+        ..Default::default()
+    }
+}
+
+/// 按顺序提交的命令：普通命令按批次分组，边界命令独占一组
+fn commands() -> Vec<OrderCommand> {
+    let mut commands: Vec<OrderCommand> = (1..=4).map(add_user).collect();
+    commands.push(control(OrderCommandType::GroupingControl));
+    commands.extend((5..=8).map(add_user));
+    commands
+}
+
+const EXPECTED_GROUPS: [u64; 9] = [0, 0, 0, 1, 2, 3, 3, 3, 4];
+
+#[test]
+fn test_groups_by_size_and_boundary_commands() {
+    let grouping = GroupingProcessor::new(3);
+    let mut msgs_in_group = 0;
+    let groups: Vec<u64> = commands()
+        .into_iter()
+        .map(|mut cmd| {
+            grouping.process(&mut cmd, &mut msgs_in_group);
+            cmd.events_group
+        })
+        .collect();
+    assert_eq!(groups, EXPECTED_GROUPS);
+    assert_eq!(grouping.group_counter(), 4);
+    assert_eq!(msgs_in_group, 1);
+
+    // 连续的边界命令不会产生空分组
+    let grouping = GroupingProcessor::new(3);
+    let mut msgs_in_group = 0;
+    let mut groups = Vec::new();
+    for command in [OrderCommandType::PersistStateMatching, OrderCommandType::PersistStateRisk, OrderCommandType::AddUser] {
+        let mut cmd = control(command);
+        grouping.process(&mut cmd, &mut msgs_in_group);
+        groups.push(cmd.events_group);
+    }
+    assert_eq!(groups, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_pipeline_assigns_groups_and_handles_grouping_control() {
+    let path = std::env::temp_dir().join(format!("matching_core_grouping_results_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut core = ExchangeCore::new(config());
+    core.enable_result_journaling(&path).unwrap();
+    let results: Vec<OrderCommand> = commands().into_iter().map(|cmd| core.submit_command(cmd)).collect();
+
+    assert_eq!(results.iter().map(|cmd| cmd.events_group).collect::<Vec<_>>(), EXPECTED_GROUPS);
+    assert_eq!(results[4].result_code, CommandResultCode::Success);
+
+    // 结果日志记录每条命令所在分组
+    let outcomes = ResultJournaler::read_outcomes(&path).unwrap();
+    assert_eq!(outcomes.iter().map(|(_, outcome)| outcome.events_group).collect::<Vec<_>>(), EXPECTED_GROUPS);
+}
+
+#[test]
+fn test_staged_pipeline_assigns_same_groups() {
+    let groups = Arc::new(Mutex::new(Vec::new()));
+    let sink = groups.clone();
+    let mut core = ExchangeCore::new(config());
+    core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+        sink.lock().unwrap().push((cmd.events_group, cmd.result_code));
+    }));
+    core.startup();
+    for cmd in commands() {
+        core.submit_command(cmd);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while groups.lock().unwrap().len() < EXPECTED_GROUPS.len() {
+        assert!(Instant::now() < deadline, "等待处理结果超时");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let groups = groups.lock().unwrap();
+    assert_eq!(groups.iter().map(|(group, _)| *group).collect::<Vec<_>>(), EXPECTED_GROUPS);
+    assert_eq!(groups[4].1, CommandResultCode::Success);
+}
+
+#[test]
+fn test_restored_state_continues_from_new_group() {
+    let mut core = ExchangeCore::new(config());
+    for cmd in commands() {
+        core.submit_command(cmd);
+    }
+
+    // 第 4 组尚未结束，恢复后从第 5 组开始
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    assert_eq!(restored.submit_command(add_user(9)).events_group, 5);
+    assert_eq!(core.submit_command(add_user(9)).events_group, 4);
+}
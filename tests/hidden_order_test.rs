@@ -0,0 +1,86 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{new_order_book, OrderBook};
+
+fn spec(hidden_yield: bool) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        hidden_yield,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn hidden(cmd: OrderCommand) -> OrderCommand {
+    OrderCommand { hidden: true, ..cmd }
+}
+
+fn fills(book: &mut dyn OrderBook, order_id: OrderId, price: Price, size: Size) -> Vec<(OrderId, Size)> {
+    let mut taker = OrderCommand { order_type: OrderType::Ioc, ..order(9, order_id, price, size, OrderAction::Bid) };
+    book.new_order(&mut taker);
+    taker.matcher_events.iter().map(|event| (event.matched_order_id, event.size)).collect()
+}
+
+#[test]
+fn test_hidden_orders_not_displayed() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(false));
+    book.new_order(&mut hidden(order(1, 1, 100, 5, OrderAction::Ask)));
+    book.new_order(&mut order(2, 2, 101, 3, OrderAction::Ask));
+    book.new_order(&mut hidden(order(1, 3, 101, 4, OrderAction::Ask)));
+
+    // 只有隐藏单的档位不出现在行情中，混合档位只显示显示订单的数量
+    let l2 = book.get_l2_data(5);
+    assert_eq!((l2.ask_prices, l2.ask_volumes), (vec![101], vec![3]));
+    assert_eq!(book.best_bid_offer(), (None, Some((101, 3))));
+    let l3 = book.get_l3_data(5, 10, false);
+    assert_eq!(l3.asks.len(), 1);
+    assert_eq!((l3.asks[0].volume, l3.asks[0].order_count, l3.asks[0].orders.len()), (3, 1, 1));
+    assert_eq!(book.get_level_volume(OrderAction::Ask, 100), 0);
+    assert_eq!(book.get_total_ask_volume(), 12);
+
+    // 隐藏单仍按价格优先成交
+    assert_eq!(fills(book.as_mut(), 10, 101, 6), vec![(1, 5), (2, 1)]);
+    assert_eq!(book.get_open_order(3).map(|o| o.remaining), Some(4));
+}
+
+#[test]
+fn test_hidden_priority_at_same_price() {
+    // 默认按时间优先；hidden_yield 时同价位显示订单（含刷新后的冰山单）先于隐藏单成交
+    for (hidden_yield, expected) in [(false, vec![(1, 4), (2, 2)]), (true, vec![(2, 3), (3, 2), (1, 1)])] {
+        let mut book = new_order_book(OrderBookKind::Advanced, spec(hidden_yield));
+        book.new_order(&mut hidden(order(1, 1, 100, 4, OrderAction::Ask)));
+        book.new_order(&mut order(2, 2, 100, 3, OrderAction::Ask));
+        book.new_order(&mut OrderCommand { order_type: OrderType::Iceberg, visible_size: Some(1), ..order(3, 3, 100, 2, OrderAction::Ask) });
+        assert_eq!(book.get_level_volume(OrderAction::Ask, 100), 4);
+        assert_eq!(fills(book.as_mut(), 10, 100, 6), expected, "hidden_yield {}", hidden_yield);
+    }
+}
+
+#[test]
+fn test_hidden_validation_and_support() {
+    let iceberg = OrderCommand { order_type: OrderType::Iceberg, visible_size: Some(1), ..hidden(order(1, 1, 100, 5, OrderAction::Ask)) };
+    assert_eq!(validate_command(&iceberg), CommandResultCode::ValidationInvalidVisibleSize);
+    assert_eq!(validate_command(&hidden(order(1, 1, 100, 5, OrderAction::Ask))), CommandResultCode::Success);
+
+    for kind in [OrderBookKind::Naive, OrderBookKind::Direct, OrderBookKind::DirectOptimized] {
+        let mut book = new_order_book(kind, spec(false));
+        assert_eq!(book.new_order(&mut hidden(order(1, 1, 100, 5, OrderAction::Ask))), CommandResultCode::MatchingUnsupportedCommand, "{:?}", kind);
+    }
+}
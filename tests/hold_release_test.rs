@@ -0,0 +1,187 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+const DEPOSIT: [(Currency, i64); 2] = [(1, 10_000), (2, 100)];
+
+fn spec(symbol_id: SymbolId, kind: OrderBookKind, stp_mode: StpMode) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 2,
+        maker_fee: 1,
+        stp_mode,
+        order_book: Some(kind),
+        ..Default::default()
+    }
+}
+
+fn setup(specs: &[CoreSymbolSpecification]) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    for spec in specs {
+        core.add_symbol(spec.clone());
+    }
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for (currency, amount) in DEPOSIT {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: amount,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 100 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn place(core: &mut ExchangeCore, cmd: OrderCommand) {
+    let order_id = cmd.order_id;
+    assert_eq!(core.submit_command(cmd).result_code, CommandResultCode::Success, "order {}", order_id);
+}
+
+/// 没有滞留冻结：对账通过且风控不再跟踪任何挂单，返回用户余额
+fn assert_released(core: &ExchangeCore, uid: UserId) -> Vec<(Currency, i64)> {
+    core.verify_invariants().unwrap();
+    let state = core.serialize_state();
+    let user = state.pipeline_state.risk_engines[0].get_user(uid).unwrap();
+    assert!(user.open_orders.is_empty(), "{:?}", user.open_orders);
+    DEPOSIT.iter().map(|&(currency, _)| (currency, user.accounts[&currency])).collect()
+}
+
+#[test]
+fn test_expiry_releases_holds() {
+    let mut core = setup(&[spec(100, OrderBookKind::Advanced, StpMode::None)]);
+    place(&mut core, order(1, 1, 100, 100, 10, OrderAction::Bid, OrderType::Gtd(500)));
+    place(&mut core, OrderCommand { stop_price: Some(150), expire_time: Some(500), ..order(1, 2, 100, 150, 5, OrderAction::Bid, OrderType::StopLimit) });
+    place(&mut core, OrderCommand { expire_time: Some(500), ..order(2, 3, 100, 120, 6, OrderAction::Ask, OrderType::Gtc) });
+    place(&mut core, order(2, 4, 100, 100, 4, OrderAction::Ask, OrderType::Ioc));
+    core.verify_invariants().unwrap();
+
+    let expired = core.submit_command(OrderCommand { command: OrderCommandType::AdvanceTime, timestamp: 501, ..Default::default() });
+    assert_eq!(expired.size, 3);
+    // 部分成交后过期：只保留成交的结算
+    assert_eq!(assert_released(&core, 1), vec![(1, 10_000 - 4 * 100 - 4), (2, 104)]);
+    assert_eq!(assert_released(&core, 2), vec![(1, 10_000 + 4 * 100 - 4 * 2), (2, 96)]);
+}
+
+#[test]
+fn test_stp_cancels_release_holds() {
+    for kind in [OrderBookKind::Advanced, OrderBookKind::DirectOptimized] {
+        for mode in [StpMode::CancelTaker, StpMode::CancelMaker, StpMode::CancelBoth, StpMode::DecrementAndCancel] {
+            let mut core = setup(&[spec(100, kind, mode)]);
+            place(&mut core, order(1, 1, 100, 100, 10, OrderAction::Bid, OrderType::Gtc));
+            place(&mut core, order(1, 2, 100, 100, 6, OrderAction::Ask, OrderType::Gtc));
+            core.verify_invariants().unwrap_or_else(|e| panic!("{:?} {:?}: {}", kind, mode, e));
+
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::SuspendUser,
+                uid: 1,
+                service_flags: SUSPEND_USER_CANCEL_ORDERS,
+                ..Default::default()
+            });
+            assert_eq!(assert_released(&core, 1), DEPOSIT.to_vec(), "{:?} {:?}", kind, mode);
+        }
+    }
+}
+
+#[test]
+fn test_clear_and_delist_release_holds() {
+    for kind in [OrderBookKind::Advanced, OrderBookKind::Direct] {
+        let mut core = setup(&[spec(100, kind, StpMode::None)]);
+        let seed = |core: &mut ExchangeCore, first: OrderId| {
+            place(core, order(1, first, 100, 100, 10, OrderAction::Bid, OrderType::Gtc));
+            place(core, order(2, first + 1, 100, 110, 10, OrderAction::Ask, OrderType::Gtc));
+            if kind == OrderBookKind::Advanced {
+                place(core, OrderCommand { stop_price: Some(130), ..order(1, first + 2, 100, 130, 3, OrderAction::Bid, OrderType::StopLimit) });
+            }
+        };
+
+        seed(&mut core, 1);
+        let symbol_command = |command| OrderCommand { command, symbol: 100, ..Default::default() };
+        assert_eq!(core.submit_command(symbol_command(OrderCommandType::ClearOrderBook)).result_code, CommandResultCode::Success);
+        assert_eq!(assert_released(&core, 1), DEPOSIT.to_vec(), "{:?}", kind);
+
+        seed(&mut core, 10);
+        assert_eq!(core.submit_command(symbol_command(OrderCommandType::DelistSymbol)).result_code, CommandResultCode::Success);
+        assert_eq!(assert_released(&core, 1), DEPOSIT.to_vec(), "{:?}", kind);
+        assert_eq!(assert_released(&core, 2), DEPOSIT.to_vec(), "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_mass_cancel_releases_holds_across_symbols() {
+    let mut core = setup(&[spec(100, OrderBookKind::Direct, StpMode::None), spec(101, OrderBookKind::Advanced, StpMode::None)]);
+    for (order_id, symbol) in [(1, 100), (2, 101)] {
+        place(&mut core, order(1, order_id, symbol, 100, 10, OrderAction::Bid, OrderType::Gtc));
+        place(&mut core, order(1, order_id + 10, symbol, 120, 10, OrderAction::Ask, OrderType::Gtc));
+    }
+    // 部分成交的挂单按剩余冻结返还
+    place(&mut core, order(2, 30, 100, 100, 3, OrderAction::Ask, OrderType::Ioc));
+
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SuspendUser,
+        uid: 1,
+        service_flags: SUSPEND_USER_CANCEL_ORDERS,
+        ..Default::default()
+    });
+    assert_eq!(assert_released(&core, 1), vec![(1, 10_000 - 3 * 100 - 3), (2, 103)]);
+}
+
+#[test]
+fn test_order_matched_after_delist_is_refunded() {
+    let mut risk = RiskEngine::new(0, 1);
+    let mut matching = MatchingEngineRouter::new(0, 1);
+    let spec = spec(100, OrderBookKind::Direct, StpMode::None);
+    risk.add_symbol(spec.clone());
+    matching.add_symbol(spec);
+    let mut submit = |cmd: &mut OrderCommand, risk: &mut RiskEngine| {
+        risk.pre_process(cmd);
+        matching.process_order(cmd);
+        risk.post_process(cmd);
+    };
+    submit(&mut OrderCommand { command: OrderCommandType::AddUser, uid: 1, ..Default::default() }, &mut risk);
+    submit(
+        &mut OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: 1, price: 10_000, order_id: 1, ..Default::default() },
+        &mut risk,
+    );
+
+    // R1 在下架命令结算前冻结，撮合时订单簿已移除
+    let mut delist = OrderCommand { command: OrderCommandType::DelistSymbol, symbol: 100, ..Default::default() };
+    let mut bid = order(1, 1, 100, 100, 10, OrderAction::Bid, OrderType::Gtc);
+    risk.pre_process(&mut delist);
+    matching.process_order(&mut delist);
+    risk.pre_process(&mut bid);
+    assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000 - 10 * 102);
+    matching.process_order(&mut bid);
+    risk.post_process(&mut delist);
+    risk.post_process(&mut bid);
+
+    assert_eq!(bid.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+    let user = risk.get_user(1).unwrap();
+    assert_eq!(user.accounts[&1], 10_000);
+    assert!(user.open_orders.is_empty());
+}
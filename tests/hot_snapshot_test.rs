@@ -0,0 +1,169 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::snapshot::SnapshotStore;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("matching_core_hot_snapshot_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    (dir.join("exchange.wal"), dir.join("snapshots"))
+}
+
+fn new_core(journal: &PathBuf, snapshots: &PathBuf) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    core.enable_snapshotting(snapshots).unwrap();
+    core.enable_journaling(journal).unwrap();
+    core
+}
+
+fn add_user(core: &mut ExchangeCore, uid: UserId, currency: Currency, amount: i64) {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    });
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid,
+        symbol: currency,
+        price: amount,
+        order_id: uid as OrderId,
+        ..Default::default()
+    });
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    });
+}
+
+fn order_book(core: &mut ExchangeCore) -> L2MarketData {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    })
+    .market_data
+    .unwrap()
+}
+
+fn wait_for_snapshot(snapshots: &PathBuf, seq_id: u64) {
+    let store = SnapshotStore::new(snapshots).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while store.get_latest_seq_id().unwrap() != Some(seq_id) {
+        assert!(Instant::now() < deadline, "等待快照 {} 超时", seq_id);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_snapshot_while_running() {
+    let (journal, snapshots) = paths("running");
+
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let mut core = new_core(&journal, &snapshots);
+    let sink = results.clone();
+    core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+        if cmd.command == OrderCommandType::PersistStateMatching {
+            sink.lock().unwrap().push((cmd.order_id, cmd.result_code));
+        }
+    }));
+    core.startup();
+
+    add_user(&mut core, 1001, 2, 1_000);
+    add_user(&mut core, 1002, 1, 1_000_000);
+    place(&mut core, 1001, 1, 1000, 50, OrderAction::Ask);
+    place(&mut core, 1002, 2, 1000, 20, OrderAction::Bid);
+    place(&mut core, 1001, 3, 1010, 30, OrderAction::Ask);
+
+    // 快照 ID 为快照命令自身的序列号
+    let seq_id = core.create_snapshot().unwrap();
+    assert_eq!(seq_id, 8);
+    assert_eq!(core.last_seq(), 8);
+
+    // 快照之后继续接收命令，无需停机
+    place(&mut core, 1002, 4, 1010, 10, OrderAction::Bid);
+    wait_for_snapshot(&snapshots, seq_id);
+    drop(core);
+    assert_eq!(*results.lock().unwrap(), vec![(8, CommandResultCode::Success)]);
+
+    // 快照 + 日志尾部：4 号买单吃掉 1000 价位 10 手
+    let mut recovered = new_core(&journal, &snapshots);
+    assert_eq!(recovered.recover().unwrap(), 9);
+    let book = order_book(&mut recovered);
+    assert_eq!(book.ask_prices, vec![1000, 1010]);
+    assert_eq!(book.ask_volumes, vec![20, 30]);
+    recovered.verify_invariants().unwrap();
+    drop(recovered);
+
+    // 只加载快照：不包含快照之后的 4 号订单
+    let mut from_snapshot = new_core(&journal, &snapshots);
+    assert!(from_snapshot.load_latest_snapshot().unwrap());
+    assert_eq!(from_snapshot.last_seq(), seq_id);
+    let book = order_book(&mut from_snapshot);
+    assert_eq!(book.ask_prices, vec![1000, 1010]);
+    assert_eq!(book.ask_volumes, vec![30, 30]);
+    from_snapshot.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_persist_failure_reported_in_result_code() {
+    let (journal, snapshots) = paths("failure");
+
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let mut core = new_core(&journal, &snapshots);
+    let sink = results.clone();
+    core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+        sink.lock().unwrap().push((cmd.command, cmd.result_code));
+    }));
+    core.startup();
+
+    // 快照目录被删除后写入失败
+    std::fs::remove_dir_all(&snapshots).unwrap();
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PersistStateRisk,
+        order_id: 1,
+        ..Default::default()
+    });
+    core.create_snapshot().unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while results.lock().unwrap().len() < 2 {
+        assert!(Instant::now() < deadline, "等待快照结果超时");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(
+        *results.lock().unwrap(),
+        vec![
+            (OrderCommandType::PersistStateRisk, CommandResultCode::StatePersistRiskEngineFailed),
+            (OrderCommandType::PersistStateMatching, CommandResultCode::StatePersistMatchingEngineFailed),
+        ]
+    );
+}
@@ -0,0 +1,117 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    }
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig { idempotency_cache_size: 2, ..Default::default() });
+    core.add_symbol(spec());
+    core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid: 1, ..Default::default() });
+    core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: 1, price: 1_000, order_id: 1, ..Default::default() });
+    core
+}
+
+fn bid(order_id: OrderId, client_order_id: u64, size: Size) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1,
+        order_id,
+        symbol: 100,
+        price: 10,
+        reserve_price: 10,
+        size,
+        action: OrderAction::Bid,
+        order_type: OrderType::Gtc,
+        client_order_id,
+        ..Default::default()
+    }
+}
+
+fn quote_balance(core: &ExchangeCore) -> i64 {
+    core.serialize_state().pipeline_state.risk_engines[0].get_user(1).unwrap().accounts[&1]
+}
+
+#[test]
+fn test_duplicate_submission_returns_original_result() {
+    let mut core = setup();
+    assert_eq!(core.submit_command(bid(1, 11, 5)).result_code, CommandResultCode::Success);
+    assert_eq!(quote_balance(&core), 950);
+
+    // 重试（订单号不同）不再下单，返回原订单号与结果
+    let retry = core.submit_command(bid(2, 11, 5));
+    assert_eq!((retry.result_code, retry.order_id), (CommandResultCode::Success, 1));
+    assert_eq!(quote_balance(&core), 950);
+
+    // 未设置 client_order_id 的下单不去重
+    assert_eq!(core.submit_command(bid(5, 0, 1)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(bid(6, 0, 1)).result_code, CommandResultCode::Success);
+    assert_eq!(quote_balance(&core), 930);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_rejected_submission_can_be_retried() {
+    let mut core = setup();
+
+    // 风控拒绝的下单不记录，充值后用同一 client_order_id 重试正常下单
+    let rejected = core.submit_command(bid(1, 11, 500));
+    assert_eq!(rejected.result_code, CommandResultCode::RiskNsf);
+    core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: 1, price: 5_000, order_id: 2, ..Default::default() });
+
+    let retry = core.submit_command(bid(3, 11, 500));
+    assert_eq!((retry.result_code, retry.order_id), (CommandResultCode::Success, 3));
+    assert_eq!(quote_balance(&core), 1_000);
+
+    // 通过风控后再次重试才视为重复提交
+    let duplicate = core.submit_command(bid(4, 11, 500));
+    assert_eq!((duplicate.result_code, duplicate.order_id), (CommandResultCode::Success, 3));
+    assert_eq!(quote_balance(&core), 1_000);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_cache_bounded_and_persisted() {
+    let mut core = setup();
+    for (order_id, client_order_id) in [(1, 11), (2, 12), (3, 13)] {
+        assert_eq!(core.submit_command(bid(order_id, client_order_id, 1)).result_code, CommandResultCode::Success);
+    }
+
+    // 恢复后仍能识别重复提交；超出容量的最早记录已淘汰，重新下单
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    let retry = restored.submit_command(bid(4, 13, 1));
+    assert_eq!((retry.result_code, retry.order_id), (CommandResultCode::Success, 3));
+    let evicted = restored.submit_command(bid(5, 11, 1));
+    assert_eq!((evicted.result_code, evicted.order_id), (CommandResultCode::Success, 5));
+    assert_eq!(quote_balance(&restored), 960);
+}
+
+#[test]
+fn test_duplicate_before_original_settles() {
+    let mut risk = RiskEngine::new(0, 1);
+    risk.set_idempotency_capacity(8);
+    risk.add_symbol(spec());
+    risk.pre_process(&mut OrderCommand { command: OrderCommandType::AddUser, uid: 1, ..Default::default() });
+    risk.pre_process(&mut OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: 1, price: 1_000, order_id: 1, ..Default::default() });
+
+    // 流水线中重试的 R1 早于原命令的 R2
+    let mut original = bid(1, 11, 5);
+    risk.pre_process(&mut original);
+    let mut retry = bid(2, 11, 5);
+    risk.pre_process(&mut retry);
+    assert_eq!((retry.result_code, retry.order_id), (CommandResultCode::DuplicateCommand, 1));
+
+    original.result_code = CommandResultCode::Success;
+    risk.post_process(&mut original);
+    risk.post_process(&mut retry);
+    assert_eq!((retry.result_code, retry.order_id), (CommandResultCode::Success, 1));
+    assert_eq!(risk.get_user(1).unwrap().accounts[&1], 950);
+}
@@ -0,0 +1,283 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::implied::SpreadGroup;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use std::sync::{Arc, Mutex};
+
+const GROUP: SpreadGroup = SpreadGroup { spread: 102, front: 100, back: 101 };
+
+fn spec(symbol_id: SymbolId, symbol_type: SymbolType) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type,
+        base_currency: 2,
+        quote_currency: 1,
+        margin_buy: 100,
+        margin_sell: 100,
+        ..Default::default()
+    }
+}
+
+fn new_router(symbol_type: SymbolType, symbols: &[SymbolId]) -> MatchingEngineRouter {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    for &symbol in symbols {
+        router.add_symbol(spec(symbol, symbol_type));
+    }
+    router.add_spread_group(GROUP);
+    router
+}
+
+fn order(order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid: order_id,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        timestamp: order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn place(router: &mut MatchingEngineRouter, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) {
+    let mut cmd = order(order_id, symbol, price, size, action);
+    router.process_order(&mut cmd);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+}
+
+/// (买一, 买一数量, 卖一, 卖一数量)
+fn implied(router: &MatchingEngineRouter, symbol: SymbolId) -> (Option<Price>, Size, Option<Price>, Size) {
+    let quote = router.implied_quote(symbol).unwrap();
+    (quote.bid, quote.bid_size, quote.ask, quote.ask_size)
+}
+
+#[test]
+fn test_implied_in_and_out_prices() {
+    let mut router = new_router(SymbolType::FuturesContract, &[100, 101, 102, 104, 105]);
+
+    // 隐含入：近月买一 - 远月卖一
+    place(&mut router, 1, 100, 1000, 5, OrderAction::Bid);
+    place(&mut router, 2, 101, 990, 3, OrderAction::Ask);
+    assert_eq!(implied(&router, 102), (Some(10), 3, None, 0));
+    assert_eq!(implied(&router, 100), (None, 0, None, 0));
+
+    // 隐含出：近月卖 = 价差卖一 + 远月卖一，远月买 = 近月买一 - 价差卖一
+    place(&mut router, 3, 102, 15, 2, OrderAction::Ask);
+    assert_eq!(implied(&router, 100), (None, 0, Some(1005), 2));
+    assert_eq!(implied(&router, 101), (Some(985), 2, None, 0));
+
+    // 近月属于两个价差组时取更优的隐含价
+    router.add_spread_group(SpreadGroup { spread: 105, front: 100, back: 104 });
+    place(&mut router, 4, 104, 995, 1, OrderAction::Ask);
+    place(&mut router, 5, 105, 6, 4, OrderAction::Ask);
+    assert_eq!(implied(&router, 100), (None, 0, Some(1001), 1));
+
+    // 撤单后隐含报价随之消失
+    let mut cancel = OrderCommand { command: OrderCommandType::CancelOrder, uid: 2, order_id: 2, symbol: 101, ..Default::default() };
+    router.process_order(&mut cancel);
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    assert_eq!(implied(&router, 102), (None, 0, None, 0));
+    assert_eq!(implied(&router, 100), (None, 0, Some(1001), 1));
+}
+
+#[test]
+fn test_non_positive_implied_prices_published() {
+    let mut router = new_router(SymbolType::FuturesContract, &[100, 101, 102]);
+    place(&mut router, 1, 100, 990, 2, OrderAction::Bid);
+    place(&mut router, 2, 101, 1000, 3, OrderAction::Ask);
+    assert_eq!(implied(&router, 102), (Some(-10), 2, None, 0));
+}
+
+/// (交易对, 事件类型, 数量, 价格, 剩余数量)
+fn events(cmd: &OrderCommand) -> Vec<(SymbolId, MatcherEventType, Size, Price, Size)> {
+    (0..cmd.matcher_events.len())
+        .map(|index| {
+            let event = &cmd.matcher_events[index];
+            (cmd.event_symbol(index), event.event_type, event.size, event.price, event.remaining_size)
+        })
+        .collect()
+}
+
+fn depth(router: &mut MatchingEngineRouter, symbol: SymbolId) -> L2MarketData {
+    let mut request = OrderCommand { command: OrderCommandType::OrderBookRequest, symbol, size: 5, ..Default::default() };
+    router.process_order(&mut request);
+    request.market_data.unwrap()
+}
+
+#[test]
+fn test_implied_in_execution() {
+    let mut router = new_router(SymbolType::FuturesContract, &[100, 101, 102]);
+    place(&mut router, 1, 100, 1000, 5, OrderAction::Ask);
+    place(&mut router, 2, 101, 990, 3, OrderAction::Bid);
+
+    // 价差买单 12 与隐含卖价 10（近月卖一 - 远月买一）相交：买近月、卖远月各 3 手，剩余 1 手挂单
+    let mut cmd = order(3, 102, 12, 4, OrderAction::Bid);
+    router.process_order(&mut cmd);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(
+        events(&cmd),
+        vec![
+            (100, MatcherEventType::Trade, 3, 1000, 0),
+            (101, MatcherEventType::Trade, 3, 990, 0),
+            (102, MatcherEventType::Reduce, 3, 10, 1),
+        ]
+    );
+    assert!(cmd.is_implied_leg(0) && cmd.is_implied_leg(1) && !cmd.is_implied_leg(2));
+    assert_eq!(cmd.matcher_events[0].taker_action, Some(OrderAction::Bid));
+    assert_eq!(cmd.matcher_events[1].taker_action, Some(OrderAction::Ask));
+    assert_eq!((cmd.matcher_events[1].matched_order_id, cmd.matcher_events[1].taker_order_id), (2, 3));
+
+    assert_eq!((depth(&mut router, 100).ask_prices, depth(&mut router, 100).ask_volumes), (vec![1000], vec![2]));
+    assert!(depth(&mut router, 101).bid_prices.is_empty());
+    assert_eq!((depth(&mut router, 102).bid_prices, depth(&mut router, 102).bid_volumes), (vec![12], vec![1]));
+}
+
+#[test]
+fn test_direct_liquidity_has_priority_at_same_price() {
+    let mut router = new_router(SymbolType::FuturesContract, &[100, 101, 102]);
+    place(&mut router, 1, 100, 1000, 5, OrderAction::Ask);
+    place(&mut router, 2, 101, 990, 3, OrderAction::Bid);
+    place(&mut router, 4, 102, 10, 2, OrderAction::Ask);
+
+    let mut cmd = order(5, 102, 12, 4, OrderAction::Bid);
+    router.process_order(&mut cmd);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+    assert_eq!(
+        events(&cmd),
+        vec![
+            (102, MatcherEventType::Trade, 2, 10, 0),
+            (100, MatcherEventType::Trade, 2, 1000, 0),
+            (101, MatcherEventType::Trade, 2, 990, 0),
+            (102, MatcherEventType::Reduce, 2, 10, 0),
+        ]
+    );
+    assert!(!cmd.is_implied_leg(0));
+    assert!(depth(&mut router, 102).bid_prices.is_empty());
+    assert_eq!(implied(&router, 102), (None, 0, Some(10), 1));
+}
+
+#[test]
+fn test_implied_out_execution() {
+    let mut router = new_router(SymbolType::FuturesContract, &[100, 101, 102]);
+    place(&mut router, 2, 101, 990, 3, OrderAction::Ask);
+    place(&mut router, 4, 102, 15, 2, OrderAction::Ask);
+
+    // 近月买单 1010 与隐含卖价 1005（价差卖一 + 远月卖一）相交，买入价差与远月
+    let mut cmd = order(5, 100, 1010, 3, OrderAction::Bid);
+    router.process_order(&mut cmd);
+    assert_eq!(
+        events(&cmd),
+        vec![
+            (102, MatcherEventType::Trade, 2, 15, 0),
+            (101, MatcherEventType::Trade, 2, 990, 0),
+            (100, MatcherEventType::Reduce, 2, 1005, 1),
+        ]
+    );
+    assert_eq!((depth(&mut router, 100).bid_prices, depth(&mut router, 100).bid_volumes), (vec![1010], vec![1]));
+    assert_eq!((depth(&mut router, 101).ask_prices, depth(&mut router, 101).ask_volumes), (vec![990], vec![1]));
+
+    // 不相交时不成交
+    let mut cmd = order(6, 101, 1100, 1, OrderAction::Ask);
+    router.process_order(&mut cmd);
+    assert!(cmd.matcher_events.is_empty());
+}
+
+#[test]
+fn test_implied_requires_futures_books() {
+    let mut router = new_router(SymbolType::CurrencyExchangePair, &[100, 101, 102]);
+    place(&mut router, 1, 100, 1000, 5, OrderAction::Bid);
+    place(&mut router, 2, 101, 990, 3, OrderAction::Ask);
+    assert_eq!(implied(&router, 102), (None, 0, None, 0));
+
+    // 订单簿缺失（价差合约未上市）时同样不计算
+    let mut router = new_router(SymbolType::FuturesContract, &[100, 101]);
+    place(&mut router, 1, 100, 1000, 5, OrderAction::Bid);
+    place(&mut router, 2, 101, 990, 3, OrderAction::Ask);
+    assert_eq!(implied(&router, 102), (None, 0, None, 0));
+    assert!(router.implied_quote(103).is_none());
+}
+
+#[test]
+#[should_panic(expected = "不属于同一撮合分片")]
+fn test_spread_group_must_share_shard() {
+    let mut router = MatchingEngineRouter::new(0, 2);
+    router.add_spread_group(GROUP);
+}
+
+#[test]
+fn test_implied_quotes_published_with_market_data() {
+    let mut core = ExchangeCore::new(ExchangeConfig { spread_groups: vec![GROUP], ..Default::default() });
+    for symbol in [100, 101, 102] {
+        core.add_symbol(spec(symbol, SymbolType::FuturesContract));
+    }
+    let quotes = Arc::new(Mutex::new(Vec::new()));
+    let sink = quotes.clone();
+    core.add_market_data_consumer(Arc::new(move |event: &MarketDataEvent| {
+        if let MarketDataEvent::Implied(quote) = event {
+            sink.lock().unwrap().push(*quote);
+        }
+    }));
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: 1, price: 10_000, order_id: uid, ..Default::default() });
+    }
+
+    let place = |core: &mut ExchangeCore, uid, order_id, symbol, price, action| {
+        let cmd = OrderCommand { result_code: CommandResultCode::New, uid, ..order(order_id, symbol, price, 2, action) };
+        assert_eq!(core.submit_command(cmd).result_code, CommandResultCode::Success);
+    };
+    place(&mut core, 1, 1, 100, 1000, OrderAction::Bid);
+    place(&mut core, 2, 2, 101, 990, OrderAction::Ask);
+
+    let quotes = quotes.lock().unwrap().clone();
+    assert_eq!(quotes.len(), 1);
+    assert_eq!((quotes[0].symbol, quotes[0].bid, quotes[0].bid_size, quotes[0].ask, quotes[0].seq), (102, Some(10), 2, None, 1));
+}
+
+/// (quote 余额, 净持仓)
+fn account(core: &ExchangeCore, uid: UserId, symbol: SymbolId) -> (i64, i64) {
+    let state = core.serialize_state();
+    let user = state.pipeline_state.risk_engines.iter().find_map(|engine| engine.get_user(uid)).unwrap();
+    let net = user.positions.get(&symbol).map_or(0, |position| position.net_position());
+    (user.accounts.get(&1).copied().unwrap_or(0), net)
+}
+
+#[test]
+fn test_implied_execution_settles_leg_positions() {
+    for shards in [1, 2] {
+        let mut core = ExchangeCore::new(ExchangeConfig { spread_groups: vec![GROUP], risk_engines_num: shards, ..Default::default() });
+        for symbol in [100, 101, 102] {
+            core.add_symbol(spec(symbol, SymbolType::FuturesContract));
+        }
+        for uid in [1, 2, 3] {
+            core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+            core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: 1, price: 10_000, order_id: uid, ..Default::default() });
+        }
+        let place = |core: &mut ExchangeCore, uid, order_id, symbol, price, size, action| {
+            let cmd = OrderCommand { result_code: CommandResultCode::New, uid, ..order(order_id, symbol, price, size, action) };
+            core.submit_command(cmd)
+        };
+        assert_eq!(place(&mut core, 1, 1, 100, 1000, 5, OrderAction::Ask).result_code, CommandResultCode::Success);
+        assert_eq!(place(&mut core, 2, 2, 101, 990, 3, OrderAction::Bid).result_code, CommandResultCode::Success);
+
+        // 价差订单每手按隐含腿的保证金（近月买 100 + 远月卖 100）冻结，成交后按腿持仓占用保证金
+        let result = place(&mut core, 3, 3, 102, 12, 4, OrderAction::Bid);
+        assert_eq!(result.result_code, CommandResultCode::Success, "{} shards", shards);
+        assert_eq!(account(&core, 3, 100), (10_000 - 3 * 100 - 3 * 100 - 200, 3));
+        assert_eq!(account(&core, 3, 101).1, -3);
+        assert_eq!(account(&core, 3, 102).1, 0);
+        assert_eq!(account(&core, 1, 100).1, -3);
+        assert_eq!(account(&core, 2, 101).1, 3);
+        core.verify_invariants().unwrap();
+
+        // 撤销剩余挂单后返还全部冻结
+        let cancel = OrderCommand { command: OrderCommandType::CancelOrder, uid: 3, order_id: 3, symbol: 102, ..Default::default() };
+        assert_eq!(core.submit_command(cancel).result_code, CommandResultCode::Success);
+        assert_eq!(account(&core, 3, 100).0, 10_000 - 600);
+        core.verify_invariants().unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::invariants::{BalanceTotals, CurrencyImbalance, StuckHold};
+
+const BASE: Currency = 2;
+const QUOTE: Currency = 1;
+
+fn spot_spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        base_scale_k: 10,
+        quote_scale_k: 3,
+        taker_fee: 7,
+        maker_fee: 2,
+        ..Default::default()
+    }
+}
+
+fn perpetual_spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::PerpetualSwap,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        base_scale_k: 1,
+        quote_scale_k: 2,
+        taker_fee: 3,
+        maker_fee: 1,
+        margin_buy: 150,
+        margin_sell: 120,
+        ..Default::default()
+    }
+}
+
+fn setup(config: ExchangeConfig) -> ExchangeCore {
+    let mut core = ExchangeCore::new(config);
+    core.add_symbol(spot_spec(100));
+    core.add_symbol(perpetual_spec(200));
+
+    for uid in [1001, 1002, 1003] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [BASE, QUOTE] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 10_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_spot_trading_keeps_balances_consistent() {
+    let mut core = setup(ExchangeConfig::default());
+    core.verify_invariants().unwrap();
+
+    place(&mut core, 1001, 1, 100, 1000, 20, OrderAction::Ask);
+    place(&mut core, 1002, 2, 100, 990, 15, OrderAction::Bid);
+    core.verify_invariants().unwrap();
+
+    // 部分成交 + 剩余挂单（买单高于成交价冻结）
+    place(&mut core, 1003, 3, 100, 1010, 30, OrderAction::Bid);
+    core.verify_invariants().unwrap();
+
+    // 卖单吃掉两个买单
+    place(&mut core, 1001, 4, 100, 980, 25, OrderAction::Ask);
+    core.verify_invariants().unwrap();
+
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1002,
+        order_id: 2,
+        symbol: 100,
+        price: 995,
+        ..Default::default()
+    });
+    core.verify_invariants().unwrap();
+
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::ReduceOrder,
+        uid: 1002,
+        order_id: 2,
+        symbol: 100,
+        size: 3,
+        ..Default::default()
+    });
+    core.verify_invariants().unwrap();
+
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1002,
+        order_id: 2,
+        symbol: 100,
+        ..Default::default()
+    });
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_margin_trading_and_funding_keep_balances_consistent() {
+    let mut core = setup(ExchangeConfig::default());
+
+    // 开仓：1001 多、1002 空
+    place(&mut core, 1002, 1, 200, 1000, 10, OrderAction::Ask);
+    place(&mut core, 1001, 2, 200, 1000, 7, OrderAction::Bid);
+    core.verify_invariants().unwrap();
+
+    // 不同价格加仓/平仓，产生已实现盈亏
+    place(&mut core, 1003, 3, 200, 1037, 6, OrderAction::Ask);
+    place(&mut core, 1002, 4, 200, 1040, 9, OrderAction::Bid);
+    core.verify_invariants().unwrap();
+
+    place(&mut core, 1001, 5, 200, 961, 5, OrderAction::Ask);
+    place(&mut core, 1003, 6, 200, 961, 8, OrderAction::Bid);
+    core.verify_invariants().unwrap();
+
+    // 资金费向上取整产生的轧差计入对账
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SetMarkPrice,
+        symbol: 200,
+        price: 1013,
+        ..Default::default()
+    });
+    let result = core.submit_command(OrderCommand {
+        command: OrderCommandType::ApplyFunding,
+        symbol: 200,
+        price: 333,
+        timestamp: 10,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    core.verify_invariants().unwrap();
+
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::SuspendUser,
+        uid: 1003,
+        service_flags: SUSPEND_USER_CANCEL_ORDERS,
+        ..Default::default()
+    });
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_periodic_check_across_shards() {
+    let mut core = setup(ExchangeConfig {
+        matching_engines_num: 2,
+        risk_engines_num: 2,
+        invariant_check_interval: 1,
+        ..Default::default()
+    });
+
+    // 每条命令后自动对账，失败会 panic
+    let mut order_id = 1;
+    for round in 0..20 {
+        for (uid, action) in [(1001, OrderAction::Ask), (1002, OrderAction::Bid), (1003, OrderAction::Ask)] {
+            let price = 1000 + (round * 7 + uid as i64) % 13;
+            place(&mut core, uid, order_id, 100, price, 3 + round % 4, action);
+            place(&mut core, uid, order_id + 1, 200, price, 2 + round % 3, action.opposite());
+            order_id += 2;
+        }
+    }
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_imbalance_is_reported_per_currency() {
+    let mut totals = BalanceTotals::new();
+    totals.add_deposit(QUOTE, 1000);
+    totals.add_accounted(QUOTE, 600);
+    totals.add_accounted(QUOTE, 400);
+    totals.add_deposit(BASE, 50);
+    totals.add_accounted(BASE, 49);
+    totals.add_accounted(3, 5);
+
+    let violation = totals.check().unwrap_err();
+    assert_eq!(
+        violation.imbalances,
+        vec![
+            CurrencyImbalance { currency: BASE, expected: 50, actual: 49 },
+            CurrencyImbalance { currency: 3, expected: 0, actual: 5 },
+        ]
+    );
+}
+
+#[test]
+fn test_stuck_holds_fail_check_even_when_balanced() {
+    let mut totals = BalanceTotals::new();
+    totals.add_deposit(QUOTE, 1000);
+    totals.add_accounted(QUOTE, 1000);
+    let stuck = StuckHold { uid: 1001, order_id: 7, symbol: 100, currency: QUOTE, hold: 300 };
+    totals.add_stuck_hold(stuck.clone());
+
+    let violation = totals.check().unwrap_err();
+    assert!(violation.imbalances.is_empty());
+    assert_eq!(violation.stuck_holds, vec![stuck]);
+}
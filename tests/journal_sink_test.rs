@@ -0,0 +1,134 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::journal::{JournalRecords, JournalSink, Journaler};
+use std::sync::{Arc, Mutex};
+
+/// 内存消息队列：模拟外部日志服务
+#[derive(Clone, Default)]
+struct MemorySink {
+    records: Arc<Mutex<Vec<(u64, OrderCommand)>>>,
+    reject: Arc<Mutex<bool>>,
+}
+
+impl JournalSink for MemorySink {
+    fn append(&mut self, cmd: &OrderCommand) -> anyhow::Result<u64> {
+        if *self.reject.lock().unwrap() {
+            anyhow::bail!("队列不可用");
+        }
+        let mut records = self.records.lock().unwrap();
+        let seq = records.len() as u64 + 1;
+        records.push((seq, cmd.clone()));
+        Ok(seq)
+    }
+
+    fn last_seq(&self) -> u64 {
+        self.records.lock().unwrap().len() as u64
+    }
+
+    fn read_after(&self, after_seq: u64) -> anyhow::Result<JournalRecords> {
+        let records: Vec<_> = self.records.lock().unwrap().iter().filter(|(seq, _)| *seq > after_seq).cloned().collect();
+        Ok(Box::new(records.into_iter().map(Ok)))
+    }
+}
+
+/// 只写的输出端（不支持回读）
+struct WriteOnlySink(u64);
+
+impl JournalSink for WriteOnlySink {
+    fn append(&mut self, _cmd: &OrderCommand) -> anyhow::Result<u64> {
+        self.0 += 1;
+        Ok(self.0)
+    }
+
+    fn last_seq(&self) -> u64 {
+        self.0
+    }
+}
+
+fn add_user(uid: UserId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    }
+}
+
+fn new_core() -> ExchangeCore {
+    ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_custom_sink_records_and_recovers() {
+    let sink = MemorySink::default();
+    let mut core = new_core();
+    core.enable_journal_sink(sink.clone());
+    for uid in 1..=3 {
+        core.submit_command(add_user(uid));
+    }
+    assert_eq!(core.last_seq(), 3);
+    assert_eq!(sink.records.lock().unwrap().iter().map(|(_, cmd)| cmd.uid).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    // 输出端写入失败时序列号不前进，命令被拒绝且不处理
+    *sink.reject.lock().unwrap() = true;
+    assert_eq!(core.submit_command(add_user(4)).result_code, CommandResultCode::JournalWriteFailed);
+    assert_eq!(core.last_seq(), 3);
+    *sink.reject.lock().unwrap() = false;
+    assert_eq!(core.submit_command(add_user(4)).result_code, CommandResultCode::Success);
+
+    // 从输出端回读恢复
+    let mut recovered = new_core();
+    recovered.enable_journal_sink(sink.clone());
+    assert_eq!(recovered.recover().unwrap(), 4);
+    assert_eq!(recovered.submit_command(add_user(4)).result_code, CommandResultCode::UserMgmtUserAlreadyExists);
+    assert_eq!(recovered.submit_command(add_user(5)).result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_failed_append_rejects_pipelined_command() {
+    let sink = MemorySink::default();
+    let mut core = new_core();
+    core.enable_journal_sink(sink.clone());
+    core.startup();
+
+    *sink.reject.lock().unwrap() = true;
+    assert_eq!(core.submit_command_async(add_user(1)).wait().result_code, CommandResultCode::JournalWriteFailed);
+    assert_eq!(core.last_seq(), 0);
+
+    // 被拒绝的命令未进入流水线，恢复写入后同一用户仍可添加
+    *sink.reject.lock().unwrap() = false;
+    assert_eq!(core.submit_command_async(add_user(1)).wait().result_code, CommandResultCode::Success);
+    assert_eq!(core.last_seq(), 1);
+}
+
+#[test]
+fn test_write_only_sink_cannot_recover() {
+    let mut core = new_core();
+    core.enable_journal_sink(WriteOnlySink(0));
+    core.submit_command(add_user(1));
+    assert_eq!(core.last_seq(), 1);
+    assert_eq!(core.compact_journal().unwrap(), 0);
+
+    let mut recovered = new_core();
+    recovered.enable_journal_sink(WriteOnlySink(1));
+    assert!(recovered.recover().is_err());
+}
+
+#[test]
+fn test_file_journal_as_sink() {
+    let dir = std::env::temp_dir().join(format!("matching_core_journal_sink_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("exchange.wal");
+
+    let mut sink: Box<dyn JournalSink> = Box::new(Journaler::new(&path).unwrap());
+    for uid in 1..=4 {
+        assert_eq!(sink.append(&add_user(uid)).unwrap(), uid);
+    }
+    sink.flush().unwrap();
+    assert_eq!(sink.last_seq(), 4);
+    let tail: Vec<u64> = sink.read_after(2).unwrap().map(|record| record.unwrap().0).collect();
+    assert_eq!(tail, vec![3, 4]);
+}
@@ -0,0 +1,362 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::journal::{FsyncPolicy, JournalConfig, JournalReader, Journaler};
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn journal_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("matching_core_journal_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("exchange.wal")
+}
+
+fn add_user(uid: UserId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    }
+}
+
+fn first_seqs(path: &PathBuf) -> Vec<u64> {
+    Journaler::segments(path).unwrap().into_iter().map(|(seq, _)| seq).collect()
+}
+
+#[test]
+fn test_rotate_by_command_count() {
+    let path = journal_path("rotate_count");
+    let config = JournalConfig {
+        segment_max_commands: 3,
+        ..Default::default()
+    };
+
+    let mut journaler = Journaler::with_config(&path, config).unwrap();
+    for uid in 1..=7 {
+        journaler.write_command(&add_user(uid)).unwrap();
+    }
+    assert_eq!(first_seqs(&path), vec![1, 4, 7]);
+    drop(journaler);
+
+    // 重新打开后继续写入最后一个分段
+    let mut journaler = Journaler::with_config(&path, config).unwrap();
+    assert_eq!(journaler.last_seq(), 7);
+    journaler.write_command(&add_user(8)).unwrap();
+    journaler.write_command(&add_user(9)).unwrap();
+    journaler.write_command(&add_user(10)).unwrap();
+    assert_eq!(first_seqs(&path), vec![1, 4, 7, 10]);
+
+    let records = Journaler::read_records(&path).unwrap();
+    assert_eq!(records.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), (1..=10).collect::<Vec<_>>());
+    assert_eq!(records.iter().map(|(_, cmd)| cmd.uid).collect::<Vec<_>>(), (1..=10).collect::<Vec<_>>());
+
+    let tail = Journaler::read_commands_after(&path, 5).unwrap();
+    assert_eq!(tail.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![6, 7, 8, 9, 10]);
+}
+
+#[test]
+fn test_rotate_by_size() {
+    let path = journal_path("rotate_size");
+    let mut journaler = Journaler::with_config(
+        &path,
+        JournalConfig {
+            segment_max_bytes: 1,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // 每个分段至少写入一条记录
+    for uid in 1..=4 {
+        journaler.write_command(&add_user(uid)).unwrap();
+    }
+    assert_eq!(first_seqs(&path), vec![1, 2, 3, 4]);
+    assert_eq!(Journaler::read_commands(&path).unwrap().len(), 4);
+}
+
+#[test]
+fn test_fsync_policies_keep_records_readable() {
+    for (name, policy) in [
+        ("never", FsyncPolicy::Never),
+        ("always", FsyncPolicy::Always),
+        ("every_n", FsyncPolicy::EveryN(4)),
+        ("interval", FsyncPolicy::Interval(Duration::from_secs(3600))),
+    ] {
+        let path = journal_path(&format!("fsync_{}", name));
+        let mut journaler = Journaler::with_config(
+            &path,
+            JournalConfig {
+                fsync_policy: policy,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        for uid in 1..=10 {
+            journaler.write_command(&add_user(uid)).unwrap();
+        }
+        journaler.sync().unwrap();
+        assert_eq!(Journaler::read_commands(&path).unwrap().len(), 10, "{}", name);
+    }
+}
+
+#[test]
+fn test_batched_fsync_defers_writes() {
+    let path = journal_path("fsync_batched");
+    let mut journaler = Journaler::with_config(
+        &path,
+        JournalConfig {
+            fsync_policy: FsyncPolicy::EveryN(3),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    journaler.write_command(&add_user(1)).unwrap();
+    journaler.write_command(&add_user(2)).unwrap();
+    assert!(Journaler::read_records(&path).unwrap().is_empty());
+
+    journaler.write_command(&add_user(3)).unwrap();
+    assert_eq!(Journaler::read_records(&path).unwrap().len(), 3);
+}
+
+#[test]
+fn test_compact_drops_segments_covered_by_snapshot() {
+    let path = journal_path("compact");
+    let mut journaler = Journaler::with_config(
+        &path,
+        JournalConfig {
+            segment_max_commands: 2,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    for uid in 1..=7 {
+        journaler.write_command(&add_user(uid)).unwrap();
+    }
+    assert_eq!(first_seqs(&path), vec![1, 3, 5, 7]);
+
+    // 分段 [3, 4] 包含快照之后的记录 4，需保留
+    assert_eq!(journaler.compact(3).unwrap(), 1);
+    assert_eq!(first_seqs(&path), vec![3, 5, 7]);
+
+    // 当前写入的分段始终保留
+    assert_eq!(journaler.compact(100).unwrap(), 2);
+    assert_eq!(first_seqs(&path), vec![7]);
+    assert_eq!(Journaler::read_commands_after(&path, 6).unwrap().len(), 1);
+}
+
+#[test]
+fn test_exchange_recovers_after_compaction() {
+    let path = journal_path("exchange");
+    let snapshots = path.with_file_name("snapshots");
+    let config = JournalConfig {
+        segment_max_commands: 4,
+        ..Default::default()
+    };
+
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.enable_snapshotting(&snapshots).unwrap();
+    core.enable_journaling_with_config(&path, config).unwrap();
+    for uid in 1..=10 {
+        core.submit_command(add_user(uid));
+    }
+    assert_eq!(core.compact_journal().unwrap(), 0);
+
+    assert_eq!(core.create_snapshot().unwrap(), 10);
+    for uid in 11..=13 {
+        core.submit_command(add_user(uid));
+    }
+    assert_eq!(core.compact_journal().unwrap(), 2);
+    assert_eq!(first_seqs(&path), vec![9, 13]);
+    drop(core);
+
+    let mut recovered = ExchangeCore::new(ExchangeConfig::default());
+    recovered.enable_snapshotting(&snapshots).unwrap();
+    recovered.enable_journaling_with_config(&path, config).unwrap();
+    assert_eq!(recovered.recover().unwrap(), 13);
+
+    // 快照前后的用户都已恢复
+    for uid in [1, 12] {
+        let result = recovered.submit_command(add_user(uid));
+        assert_eq!(result.result_code, CommandResultCode::UserMgmtUserAlreadyExists);
+    }
+}
+
+#[test]
+fn test_legacy_single_file_journal_rejected() {
+    let path = journal_path("legacy");
+    std::fs::write(&path, [16, 0, 0, 0]).unwrap();
+
+    // 升级前的单文件日志不会被静默忽略：打开、读取与恢复均报错
+    let error = Journaler::new(&path).err().unwrap();
+    assert!(error.to_string().contains("旧版单文件日志"), "{}", error);
+    assert!(Journaler::read_records(&path).is_err());
+    assert!(JournalReader::open(&path).is_err());
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    assert!(core.enable_journaling(&path).is_err());
+    assert!(Journaler::segments(&path).is_err());
+
+    // 移走旧文件后可以正常创建分段日志
+    std::fs::rename(&path, path.with_extension("legacy")).unwrap();
+    let mut journaler = Journaler::new(&path).unwrap();
+    assert_eq!(journaler.write_command(&add_user(1)).unwrap(), 1);
+}
+
+fn segment_file(path: &PathBuf, index: usize) -> PathBuf {
+    Journaler::segments(path).unwrap()[index].1.clone()
+}
+
+fn write_users(path: &PathBuf, config: JournalConfig, count: u64) -> Vec<u64> {
+    let mut journaler = Journaler::with_config(path, config).unwrap();
+    let mut sizes = Vec::new();
+    for uid in 1..=count {
+        journaler.write_command(&add_user(uid)).unwrap();
+        sizes.push(std::fs::metadata(segment_file(path, Journaler::segments(path).unwrap().len() - 1)).unwrap().len());
+    }
+    sizes
+}
+
+#[test]
+fn test_torn_tail_is_detected_and_truncated() {
+    let path = journal_path("torn_tail");
+    let sizes = write_users(&path, JournalConfig::default(), 3);
+
+    // 模拟最后一条记录写到一半时崩溃
+    let segment = segment_file(&path, 0);
+    let file = std::fs::OpenOptions::new().write(true).open(&segment).unwrap();
+    file.set_len(sizes[2] - 5).unwrap();
+    drop(file);
+
+    assert!(Journaler::read_records(&path).is_err());
+    assert!(Journaler::new(&path).is_err());
+
+    let scan = Journaler::scan(&path).unwrap();
+    assert_eq!(scan.records.len(), 2);
+    let corruption = scan.corruption.unwrap();
+    assert_eq!(corruption.segment, segment);
+    assert_eq!(corruption.offset, sizes[1]);
+
+    // 截断后可继续追加
+    let scan = Journaler::recover_records(&path, true).unwrap();
+    assert_eq!(scan.records.len(), 2);
+    assert_eq!(std::fs::metadata(&segment).unwrap().len(), sizes[1]);
+
+    let mut journaler = Journaler::new(&path).unwrap();
+    assert_eq!(journaler.last_seq(), 2);
+    assert_eq!(journaler.write_command(&add_user(3)).unwrap(), 3);
+    assert_eq!(Journaler::read_records(&path).unwrap().len(), 3);
+}
+
+#[test]
+fn test_crc_mismatch_stops_replay() {
+    let path = journal_path("crc_mismatch");
+    let sizes = write_users(&path, JournalConfig::default(), 3);
+
+    // 篡改第二条记录的数据
+    let segment = segment_file(&path, 0);
+    let mut bytes = std::fs::read(&segment).unwrap();
+    let idx = (sizes[1] - 1) as usize;
+    bytes[idx] ^= 0xFF;
+    std::fs::write(&segment, bytes).unwrap();
+
+    let scan = Journaler::scan(&path).unwrap();
+    assert_eq!(scan.records.len(), 1);
+    let corruption = scan.corruption.unwrap();
+    assert_eq!(corruption.offset, sizes[0]);
+    assert!(corruption.reason.contains("CRC"));
+    assert!(Journaler::read_commands_after(&path, 0).is_err());
+}
+
+#[test]
+fn test_truncate_corrupted_tail_on_open() {
+    let path = journal_path("truncate_on_open");
+    let sizes = write_users(&path, JournalConfig::default(), 2);
+
+    let segment = segment_file(&path, 0);
+    let mut file = std::fs::OpenOptions::new().append(true).open(&segment).unwrap();
+    std::io::Write::write_all(&mut file, &[1, 2, 3]).unwrap();
+    drop(file);
+
+    let config = JournalConfig {
+        truncate_corrupted_tail: true,
+        ..Default::default()
+    };
+    let journaler = Journaler::with_config(&path, config).unwrap();
+    assert_eq!(journaler.last_seq(), 2);
+    assert_eq!(journaler.truncated_corruption().unwrap().offset, sizes[1]);
+    assert_eq!(std::fs::metadata(&segment).unwrap().len(), sizes[1]);
+}
+
+#[test]
+fn test_recover_truncates_following_segments() {
+    let path = journal_path("truncate_segments");
+    let config = JournalConfig {
+        segment_max_commands: 2,
+        ..Default::default()
+    };
+    write_users(&path, config, 6);
+    assert_eq!(first_seqs(&path), vec![1, 3, 5]);
+
+    // 中间分段损坏：其后的分段不可达，一并删除
+    let segment = segment_file(&path, 1);
+    let len = std::fs::metadata(&segment).unwrap().len();
+    let file = std::fs::OpenOptions::new().write(true).open(&segment).unwrap();
+    file.set_len(len - 1).unwrap();
+    drop(file);
+
+    let scan = Journaler::recover_records(&path, true).unwrap();
+    assert_eq!(scan.records.len(), 3);
+    assert_eq!(scan.corruption.unwrap().segment, segment);
+    assert_eq!(first_seqs(&path), vec![1, 3]);
+    assert_eq!(Journaler::read_records(&path).unwrap().len(), 3);
+}
+
+#[test]
+fn test_streaming_reader_across_segments() {
+    let path = journal_path("streaming");
+    let config = JournalConfig {
+        segment_max_commands: 3,
+        ..Default::default()
+    };
+    write_users(&path, config, 8);
+
+    // 归档命令直接在读取缓冲区中访问
+    let mut reader = JournalReader::open(&path).unwrap();
+    let mut seen = Vec::new();
+    while let Some((seq, archived)) = reader.next_archived().unwrap() {
+        assert!(matches!(archived.command, ArchivedOrderCommandType::AddUser));
+        seen.push((seq, archived.uid));
+    }
+    assert_eq!(seen, (1..=8).map(|uid| (uid, uid)).collect::<Vec<_>>());
+    assert!(reader.next_archived().unwrap().is_none());
+
+    // 跳过快照之前的记录（含整段早于 after_seq 的分段）
+    let tail: Vec<u64> = JournalReader::open_after(&path, 4).unwrap().map(|record| record.unwrap().1.uid).collect();
+    assert_eq!(tail, vec![5, 6, 7, 8]);
+    assert_eq!(JournalReader::open_after(&path, 8).unwrap().count(), 0);
+    assert_eq!(JournalReader::open(journal_path("streaming_empty")).unwrap().count(), 0);
+}
+
+#[test]
+fn test_streaming_reader_stops_at_corruption() {
+    let path = journal_path("streaming_corrupt");
+    let sizes = write_users(&path, JournalConfig::default(), 3);
+
+    let segment = segment_file(&path, 0);
+    let file = std::fs::OpenOptions::new().write(true).open(&segment).unwrap();
+    file.set_len(sizes[2] - 5).unwrap();
+    drop(file);
+
+    // 损坏之前的记录正常返回，随后返回错误并结束
+    let mut reader = JournalReader::open(&path).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap().0, 1);
+    assert_eq!(reader.next().unwrap().unwrap().0, 2);
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+
+    // 重放在损坏处报错，之前的命令已生效
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    assert!(core.replay_journal(&path).is_err());
+    assert_eq!(core.last_seq(), 2);
+    assert_eq!(core.submit_command(add_user(2)).result_code, CommandResultCode::UserMgmtUserAlreadyExists);
+}
@@ -0,0 +1,247 @@
+use matching_core::api::*;
+use matching_core::core::market_data::L2DeltaTracker;
+use matching_core::core::orderbook::{new_order_book, OrderBook, NaiveOrderBook, DirectOrderBook, DirectOrderBookOptimized, AdvancedOrderBook};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::workload::{OrderFlowGenerator, PriceDistribution, WorkloadConfig};
+use std::collections::BTreeMap;
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn books() -> Vec<Box<dyn OrderBook>> {
+    let mut books: Vec<Box<dyn OrderBook>> = vec![
+        Box::new(NaiveOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec())),
+        Box::new(AdvancedOrderBook::new(create_symbol_spec())),
+    ];
+    books.iter_mut().for_each(|book| book.enable_level_updates());
+    books
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn command(command: OrderCommandType, uid: UserId, order_id: OrderId, price: Price, size: Size) -> OrderCommand {
+    OrderCommand {
+        command,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        ..Default::default()
+    }
+}
+
+/// 订阅端：按增量维护本地深度
+#[derive(Default)]
+struct LocalBook {
+    seq: u64,
+    asks: BTreeMap<Price, Size>,
+    bids: BTreeMap<Price, Size>,
+}
+
+impl LocalBook {
+    fn apply(&mut self, delta: &L2Delta) {
+        assert_eq!(delta.seq, self.seq + 1, "增量序号必须连续");
+        self.seq = delta.seq;
+        let side = match delta.action {
+            OrderAction::Ask => &mut self.asks,
+            OrderAction::Bid => &mut self.bids,
+        };
+        match delta.kind {
+            L2DeltaKind::Add => assert!(side.insert(delta.price, delta.volume).is_none()),
+            L2DeltaKind::Update => assert!(side.insert(delta.price, delta.volume).is_some()),
+            L2DeltaKind::Remove => assert!(side.remove(&delta.price).is_some()),
+        }
+    }
+
+    /// 直接应用订单簿产生的档位变化，只输出实际变化的档位
+    fn apply_update(&mut self, update: &LevelUpdate) {
+        let side = match update.action {
+            OrderAction::Ask => &mut self.asks,
+            OrderAction::Bid => &mut self.bids,
+        };
+        assert_ne!(side.get(&update.price).copied().unwrap_or(0), update.volume);
+        if update.volume == 0 {
+            side.remove(&update.price);
+        } else {
+            side.insert(update.price, update.volume);
+        }
+    }
+
+    fn assert_matches(&self, book: &dyn OrderBook) {
+        let l2 = book.get_l2_data(100);
+        let asks: Vec<(Price, Size)> = self.asks.iter().map(|(p, v)| (*p, *v)).collect();
+        let bids: Vec<(Price, Size)> = self.bids.iter().rev().map(|(p, v)| (*p, *v)).collect();
+        assert_eq!(asks, l2.ask_prices.iter().copied().zip(l2.ask_volumes.iter().copied()).collect::<Vec<_>>());
+        assert_eq!(bids, l2.bid_prices.iter().copied().zip(l2.bid_volumes.iter().copied()).collect::<Vec<_>>());
+    }
+}
+
+fn run(book: &mut dyn OrderBook, tracker: &mut L2DeltaTracker, local: &mut LocalBook, mut cmd: OrderCommand) -> Vec<L2Delta> {
+    cmd.result_code = match cmd.command {
+        OrderCommandType::PlaceOrder => book.new_order(&mut cmd),
+        OrderCommandType::CancelOrder => book.cancel_order(&mut cmd),
+        OrderCommandType::MoveOrder => book.move_order(&mut cmd),
+        OrderCommandType::ReduceOrder => book.reduce_order(&mut cmd),
+        _ => unreachable!(),
+    };
+
+    let mut updates = Vec::new();
+    book.take_level_updates(&mut updates);
+    let mut deltas = Vec::new();
+    tracker.on_level_updates(cmd.symbol, &updates, &mut deltas);
+    for delta in &deltas {
+        local.apply(delta);
+    }
+    local.assert_matches(book);
+    deltas
+}
+
+#[test]
+fn test_deltas_rebuild_book() {
+    for mut book in books() {
+        let book = book.as_mut();
+        let mut tracker = L2DeltaTracker::new();
+        let mut local = LocalBook::default();
+
+        let deltas = run(book, &mut tracker, &mut local, order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].kind, L2DeltaKind::Add);
+        assert_eq!((deltas[0].price, deltas[0].volume), (10000, 5));
+
+        let deltas = run(book, &mut tracker, &mut local, order(2, 2, 10000, 3, OrderAction::Ask, OrderType::Gtc));
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].kind, L2DeltaKind::Update);
+        assert_eq!(deltas[0].volume, 8);
+
+        run(book, &mut tracker, &mut local, order(3, 3, 10100, 4, OrderAction::Ask, OrderType::Gtc));
+        run(book, &mut tracker, &mut local, order(4, 4, 9900, 6, OrderAction::Bid, OrderType::Gtc));
+        run(book, &mut tracker, &mut local, order(4, 5, 9800, 2, OrderAction::Bid, OrderType::Gtc));
+
+        // 吃掉两档卖单，剩余挂在 10100 买方
+        let deltas = run(book, &mut tracker, &mut local, order(5, 6, 10100, 14, OrderAction::Bid, OrderType::Gtc));
+        assert!(deltas.iter().any(|d| d.action == OrderAction::Ask && d.price == 10000 && d.kind == L2DeltaKind::Remove));
+        assert!(deltas.iter().any(|d| d.action == OrderAction::Ask && d.price == 10100 && d.kind == L2DeltaKind::Remove));
+        assert!(deltas.iter().any(|d| d.action == OrderAction::Bid && d.price == 10100 && d.kind == L2DeltaKind::Add));
+
+        // 撤单移除档位
+        let deltas = run(book, &mut tracker, &mut local, command(OrderCommandType::CancelOrder, 4, 5, 0, 0));
+        assert_eq!(deltas.len(), 1);
+        assert_eq!((deltas[0].action, deltas[0].price, deltas[0].kind), (OrderAction::Bid, 9800, L2DeltaKind::Remove));
+
+        // 无变化的命令不产生增量
+        let deltas = run(book, &mut tracker, &mut local, command(OrderCommandType::CancelOrder, 4, 999, 0, 0));
+        assert!(deltas.is_empty());
+
+        // 改价：旧档位移除、新档位新增
+        run(book, &mut tracker, &mut local, command(OrderCommandType::MoveOrder, 4, 4, 9950, 0));
+        assert_eq!(tracker.last_seq(1), local.seq);
+    }
+}
+
+#[test]
+fn test_router_l2_deltas() {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.add_symbol(create_symbol_spec());
+
+    let mut cmd = order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc);
+    cmd.result_code = CommandResultCode::ValidForMatchingEngine;
+    router.process_order(&mut cmd);
+    // 未开启时不产生增量
+    assert!(router.drain_l2_deltas().is_empty());
+
+    router.enable_l2_deltas();
+    let mut cmd = order(2, 2, 10000, 3, OrderAction::Ask, OrderType::Gtc);
+    cmd.result_code = CommandResultCode::ValidForMatchingEngine;
+    router.process_order(&mut cmd);
+    let deltas = router.drain_l2_deltas();
+    assert_eq!(deltas.len(), 1);
+    assert_eq!(deltas[0].seq, 1);
+    // 跟踪器开启前的档位视为新增
+    assert_eq!((deltas[0].kind, deltas[0].volume), (L2DeltaKind::Add, 8));
+
+    let mut cmd = command(OrderCommandType::CancelOrder, 1, 1, 0, 0);
+    router.process_order(&mut cmd);
+    let deltas = router.drain_l2_deltas();
+    assert_eq!(deltas.len(), 1);
+    assert_eq!((deltas[0].seq, deltas[0].kind, deltas[0].volume), (2, L2DeltaKind::Update, 3));
+}
+
+#[test]
+fn test_level_updates_follow_book() {
+    let kinds = [
+        (OrderBookKind::Naive, vec![(OrderType::Gtc, 6), (OrderType::Ioc, 2), (OrderType::Fok, 1)]),
+        (OrderBookKind::Direct, vec![(OrderType::Gtc, 6), (OrderType::Ioc, 2), (OrderType::Fok, 1)]),
+        (OrderBookKind::DirectOptimized, vec![(OrderType::Gtc, 6), (OrderType::Ioc, 2), (OrderType::Fok, 1)]),
+        (OrderBookKind::DirectOptimizedLadder, vec![(OrderType::Gtc, 6), (OrderType::Ioc, 2), (OrderType::Fok, 1)]),
+        (
+            OrderBookKind::Advanced,
+            vec![(OrderType::Gtc, 4), (OrderType::Ioc, 1), (OrderType::Iceberg, 2), (OrderType::Gtd(0), 2), (OrderType::StopLimit, 1)],
+        ),
+    ];
+    for (kind, order_types) in kinds {
+        let mut book = new_order_book(kind, CoreSymbolSpecification { order_book: Some(kind), ..create_symbol_spec() });
+        book.enable_level_updates();
+        let workload = OrderFlowGenerator::new(WorkloadConfig {
+            seed: 5,
+            uids: vec![1, 2, 3],
+            order_types,
+            price: PriceDistribution::Normal { mean: -2.0, std_dev: 4.0 },
+            cancel_rate: 0.1,
+            move_rate: 0.1,
+            reduce_rate: 0.1,
+            gtd_lifetime: 20,
+            ..Default::default()
+        });
+
+        let mut local = LocalBook::default();
+        let mut updates = Vec::new();
+        for (i, mut cmd) in workload.take(2_000).enumerate() {
+            // Advanced 订单簿上部分订单为隐藏单，只有隐藏单的档位不产生更新
+            cmd.hidden = kind == OrderBookKind::Advanced && cmd.order_type == OrderType::Gtc && i % 5 == 0;
+            match cmd.command {
+                OrderCommandType::PlaceOrder => book.new_order(&mut cmd),
+                OrderCommandType::CancelOrder => book.cancel_order(&mut cmd),
+                OrderCommandType::MoveOrder => book.move_order(&mut cmd),
+                _ => book.reduce_order(&mut cmd),
+            };
+            if i % 50 == 49 {
+                book.expire_orders(cmd.timestamp, &mut cmd.matcher_events);
+            }
+
+            updates.clear();
+            book.take_level_updates(&mut updates);
+            updates.iter().for_each(|update| local.apply_update(update));
+            local.assert_matches(book.as_ref());
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{OrderBook, NaiveOrderBook, DirectOrderBook, DirectOrderBookOptimized, AdvancedOrderBook};
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn books() -> Vec<Box<dyn OrderBook>> {
+    vec![
+        Box::new(NaiveOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec())),
+        Box::new(AdvancedOrderBook::new(create_symbol_spec())),
+    ]
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_l3_levels_in_time_priority() {
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(2, 2, 10000, 6, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(3, 3, 10000, 7, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 4, 10100, 8, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(4, 5, 9900, 2, OrderAction::Bid, OrderType::Gtc));
+        book.new_order(&mut order(4, 6, 9800, 3, OrderAction::Bid, OrderType::Gtc));
+
+        // 部分成交最早的卖单
+        book.new_order(&mut order(5, 7, 10000, 2, OrderAction::Bid, OrderType::Ioc));
+
+        let l3 = book.get_l3_data(10, 10, false);
+        assert_eq!(l3.asks.len(), 2);
+        assert_eq!(l3.asks[0].price, 10000);
+        assert_eq!(l3.asks[0].volume, 16);
+        assert_eq!(l3.asks[0].order_count, 3);
+        assert_eq!(
+            l3.asks[0].orders,
+            vec![
+                L3Order { order_id: 1, uid: Some(1), price: 10000, remaining: 3, timestamp: 1001 },
+                L3Order { order_id: 2, uid: Some(2), price: 10000, remaining: 6, timestamp: 1002 },
+                L3Order { order_id: 3, uid: Some(3), price: 10000, remaining: 7, timestamp: 1003 },
+            ]
+        );
+        assert_eq!(l3.bids.iter().map(|l| l.price).collect::<Vec<_>>(), vec![9900, 9800]);
+    }
+}
+
+#[test]
+fn test_l3_limits_and_uid_masking() {
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(2, 2, 10000, 6, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(3, 3, 10000, 7, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 4, 10100, 8, OrderAction::Ask, OrderType::Gtc));
+
+        let l3 = book.get_l3_data(1, 2, true);
+        assert_eq!(l3.asks.len(), 1);
+        assert_eq!(l3.asks[0].order_count, 3);
+        assert_eq!(l3.asks[0].orders.len(), 2);
+        assert_eq!(l3.asks[0].orders[1].order_id, 2);
+        assert!(l3.asks[0].orders.iter().all(|o| o.uid.is_none()));
+        assert!(l3.bids.is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{OrderBook, NaiveOrderBook, DirectOrderBook, DirectOrderBookOptimized, AdvancedOrderBook};
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn books() -> Vec<Box<dyn OrderBook>> {
+    vec![
+        Box::new(NaiveOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec())),
+        Box::new(AdvancedOrderBook::new(create_symbol_spec())),
+    ]
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_trade_events_flag_completed_makers() {
+    for mut book in books() {
+        book.new_order(&mut order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 2, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+
+        // 第一笔吃完 maker 1，第二笔部分成交 maker 2
+        let mut bid = order(2, 3, 10000, 7, OrderAction::Bid, OrderType::Ioc);
+        book.new_order(&mut bid);
+
+        assert_eq!(bid.matcher_events.len(), 2);
+        assert_eq!(bid.matcher_events[0].matched_order_id, 1);
+        assert!(bid.matcher_events[0].maker_completed);
+        assert_eq!(bid.matcher_events[1].matched_order_id, 2);
+        assert!(!bid.matcher_events[1].maker_completed);
+
+        let mut bid = order(2, 4, 10000, 3, OrderAction::Bid, OrderType::Ioc);
+        book.new_order(&mut bid);
+        assert!(bid.matcher_events[0].maker_completed);
+        assert!(book.get_order_by_id(2).is_none());
+    }
+}
@@ -0,0 +1,72 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn setup(risk_engines_num: usize) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig { risk_engines_num, ..Default::default() });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    });
+    for (uid, currency) in [(1, 2), (2, 1)] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: currency, price: 1_000, order_id: uid, ..Default::default() });
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) {
+    let cmd = OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    };
+    assert_eq!(core.submit_command(cmd).result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_maker_volume_accumulated_per_epoch() {
+    let mut core = setup(2);
+    place(&mut core, 1, 1, 10, 5, OrderAction::Ask);
+    place(&mut core, 1, 2, 11, 5, OrderAction::Ask);
+    place(&mut core, 2, 3, 11, 7, OrderAction::Bid);
+
+    // 只统计 maker 一方
+    let report = core.maker_volume(1).unwrap();
+    let volume = MakerVolume { volume: 7, notional: 72, trades: 2 };
+    assert_eq!((report.epoch, report.current.get(&100), report.previous.len()), (0, Some(&volume), 0));
+    assert!(core.maker_volume(2).unwrap().current.is_empty());
+    assert!(core.maker_volume(3).is_err());
+
+    // 重置后当前周期结转为上一周期
+    assert_eq!(core.reset_maker_volume(), CommandResultCode::Success);
+    place(&mut core, 2, 4, 11, 1, OrderAction::Bid);
+    let report = core.maker_volume(1).unwrap();
+    assert_eq!(report.epoch, 1);
+    assert_eq!(report.current.get(&100), Some(&MakerVolume { volume: 1, notional: 11, trades: 1 }));
+    assert_eq!(report.previous.get(&100), Some(&volume));
+}
+
+#[test]
+fn test_maker_volume_persisted() {
+    let mut core = setup(1);
+    place(&mut core, 2, 1, 10, 3, OrderAction::Bid);
+    place(&mut core, 1, 2, 10, 2, OrderAction::Ask);
+    core.reset_maker_volume();
+    place(&mut core, 1, 3, 10, 1, OrderAction::Ask);
+
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    let report = restored.maker_volume(2).unwrap();
+    assert_eq!(report.epoch, 1);
+    assert_eq!(report.current.get(&100), Some(&MakerVolume { volume: 1, notional: 10, trades: 1 }));
+    assert_eq!(report.previous.get(&100), Some(&MakerVolume { volume: 2, notional: 20, trades: 1 }));
+}
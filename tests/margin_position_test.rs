@@ -0,0 +1,160 @@
+use matching_core::api::*;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+use matching_core::core::users::SymbolPositionRecord;
+
+const QUOTE: Currency = 1;
+const BASE: Currency = 2;
+const INITIAL: i64 = 100_000;
+
+struct Exchange {
+    risk: RiskEngine,
+    matching: MatchingEngineRouter,
+}
+
+impl Exchange {
+    fn new(margin_buy: i64, margin_sell: i64) -> Self {
+        let spec = CoreSymbolSpecification {
+            symbol_id: 100,
+            symbol_type: SymbolType::PerpetualSwap,
+            base_currency: BASE,
+            quote_currency: QUOTE,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            taker_fee: 2,
+            maker_fee: 1,
+            margin_buy,
+            margin_sell,
+            ..Default::default()
+        };
+        let mut exchange = Self {
+            risk: RiskEngine::new(0, 1),
+            matching: MatchingEngineRouter::new(0, 1),
+        };
+        exchange.risk.add_symbol(spec.clone());
+        exchange.matching.add_symbol(spec);
+
+        for uid in [1001, 1002] {
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            });
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: QUOTE,
+                price: INITIAL,
+                order_id: uid as OrderId,
+                ..Default::default()
+            });
+        }
+        exchange
+    }
+
+    fn submit(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        self.risk.pre_process(&mut cmd);
+        self.matching.process_order(&mut cmd);
+        self.risk.post_process(&mut cmd);
+        cmd
+    }
+
+    fn place(&mut self, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> CommandResultCode {
+        self.submit(OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id,
+            symbol: 100,
+            price,
+            reserve_price: price,
+            size,
+            action,
+            order_type: OrderType::Gtc,
+            ..Default::default()
+        })
+        .result_code
+    }
+
+    fn balance(&self, uid: UserId, currency: Currency) -> i64 {
+        self.risk.get_user(uid).unwrap().accounts.get(&currency).copied().unwrap_or(0)
+    }
+
+    fn net_position(&self, uid: UserId) -> i64 {
+        self.risk.get_user(uid).unwrap().positions.get(&100).map_or(0, |p| p.net_position())
+    }
+}
+
+#[test]
+fn test_margin_positions_and_pnl() {
+    let mut exchange = Exchange::new(100, 120);
+
+    // 开仓：B 卖空 10 @ 1000，A 买多 10 @ 1000
+    assert_eq!(exchange.place(1002, 1, 1000, 10, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(exchange.place(1001, 2, 1000, 10, OrderAction::Bid), CommandResultCode::Success);
+
+    assert_eq!(exchange.net_position(1001), 10);
+    assert_eq!(exchange.net_position(1002), -10);
+    // 不交换 base 币，只冻结保证金与手续费
+    assert_eq!(exchange.balance(1001, BASE), 0);
+    assert_eq!(exchange.balance(1001, QUOTE), INITIAL - 10 * (100 + 2));
+    assert_eq!(exchange.balance(1002, QUOTE), INITIAL - 10 * (120 + 2) + 10); // maker 返还多冻结的手续费
+
+    // 平仓：A 挂卖 4 @ 1100，B 买入平空
+    assert_eq!(exchange.place(1001, 3, 1100, 4, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(exchange.place(1002, 4, 1100, 4, OrderAction::Bid), CommandResultCode::Success);
+
+    assert_eq!(exchange.net_position(1001), 6);
+    assert_eq!(exchange.net_position(1002), -6);
+    assert_eq!(exchange.risk.get_user(1001).unwrap().positions[&100].profit, 400);
+    assert_eq!(exchange.risk.get_user(1002).unwrap().positions[&100].profit, -400);
+
+    // 资金守恒：余额 + 持仓保证金 + 手续费 = 初始资金
+    let held_margin = 6 * 100 + 6 * 120;
+    let fees = exchange.risk.fee_report().by_currency[&QUOTE];
+    assert_eq!(fees, 10 * 2 + 10 + 4 * 2 + 4);
+    assert_eq!(
+        exchange.balance(1001, QUOTE) + exchange.balance(1002, QUOTE) + held_margin + fees,
+        2 * INITIAL
+    );
+}
+
+#[test]
+fn test_margin_cancel_refund() {
+    let mut exchange = Exchange::new(100, 120);
+    assert_eq!(exchange.place(1001, 1, 900, 5, OrderAction::Bid), CommandResultCode::Success);
+    assert_eq!(exchange.balance(1001, QUOTE), INITIAL - 5 * 102);
+
+    let result = exchange.submit(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1001,
+        order_id: 1,
+        symbol: 100,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(exchange.balance(1001, QUOTE), INITIAL);
+    assert!(exchange.risk.get_user(1001).unwrap().positions.is_empty());
+}
+
+#[test]
+fn test_margin_trading_disabled() {
+    let mut exchange = Exchange::new(100, 0);
+    assert_eq!(exchange.place(1001, 1, 900, 5, OrderAction::Ask), CommandResultCode::RiskMarginTradingDisabled);
+    assert_eq!(exchange.place(1001, 2, 900, 5, OrderAction::Bid), CommandResultCode::Success);
+    assert_eq!(exchange.place(1002, 3, 900, 1_000, OrderAction::Bid), CommandResultCode::RiskNsf);
+}
+
+#[test]
+fn test_position_flip() {
+    let mut position = SymbolPositionRecord::new(1, 100, QUOTE);
+    assert_eq!(position.update_position(OrderAction::Bid, 4, 1000), (0, 0));
+    assert_eq!(position.update_position(OrderAction::Bid, 2, 1006), (0, 0));
+    assert_eq!(position.net_position(), 6);
+
+    // 卖出 10：平多 6（均价 1002），反手开空 4 @ 1100
+    assert_eq!(position.update_position(OrderAction::Ask, 10, 1100), (6, 6 * (1100 - 1002)));
+    assert_eq!(position.net_position(), -4);
+    assert_eq!(position.direction, -1);
+    assert_eq!(position.open_price_short, 4 * 1100);
+    assert_eq!(position.open_price_long, 0);
+}
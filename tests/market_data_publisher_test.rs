@@ -0,0 +1,111 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::sync::{Arc, Mutex};
+
+fn setup() -> (ExchangeCore, Arc<Mutex<Vec<MarketDataEvent>>>) {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    core.add_market_data_consumer(Arc::new(move |event| {
+        sink.lock().unwrap().push(event.clone());
+    }));
+
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    });
+
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+
+    (core, received)
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_publisher_fans_out_book_updates() {
+    let (mut core, received) = setup();
+
+    let result = place(&mut core, 1002, 1, 100, 5, OrderAction::Ask);
+    assert_eq!(result.result_code, CommandResultCode::Success);
+
+    let events = std::mem::take(&mut *received.lock().unwrap());
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], MarketDataEvent::L2(d) if d.kind == L2DeltaKind::Add && d.price == 100 && d.volume == 5));
+    assert_eq!(
+        events[1],
+        MarketDataEvent::Bbo(BboUpdate { symbol: 100, bid: None, bid_size: 0, ask: Some(100), ask_size: 5, seq: 1 })
+    );
+
+    // 更差价格挂单：只有 L2 增量，BBO 不变
+    place(&mut core, 1002, 2, 105, 5, OrderAction::Ask);
+    let events = std::mem::take(&mut *received.lock().unwrap());
+    assert_eq!(events.len(), 1);
+    assert!(matches!(&events[0], MarketDataEvent::L2(d) if d.seq == 2 && d.price == 105));
+}
+
+#[test]
+fn test_publisher_trade_ticks() {
+    let (mut core, received) = setup();
+    place(&mut core, 1002, 1, 100, 5, OrderAction::Ask);
+    received.lock().unwrap().clear();
+
+    let result = place(&mut core, 1001, 2, 100, 3, OrderAction::Bid);
+    assert_eq!(result.result_code, CommandResultCode::Success);
+
+    let events = std::mem::take(&mut *received.lock().unwrap());
+    let trades: Vec<&TradeTick> = events
+        .iter()
+        .filter_map(|e| match e {
+            MarketDataEvent::Trade(t) => Some(t),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].price, 100);
+    assert_eq!(trades[0].size, 3);
+    assert_eq!(trades[0].taker_action, OrderAction::Bid);
+    assert_eq!((trades[0].taker_order_id, trades[0].maker_order_id), (2, 1));
+    assert_eq!((trades[0].taker_uid, trades[0].maker_uid), (1001, 1002));
+
+    assert!(events.iter().any(|e| matches!(e, MarketDataEvent::L2(d) if d.kind == L2DeltaKind::Update && d.volume == 2)));
+    assert!(events.contains(&MarketDataEvent::Bbo(BboUpdate { symbol: 100, bid: None, bid_size: 0, ask: Some(100), ask_size: 2, seq: 2 })));
+}
@@ -0,0 +1,210 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::{OrderBook, AdvancedOrderBook, DirectOrderBookOptimized};
+
+fn create_symbol_spec(max_slippage_bps: i64) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        market_max_slippage_bps: max_slippage_bps,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn seed_asks(book: &mut dyn OrderBook) {
+    book.new_order(&mut order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+    book.new_order(&mut order(1, 2, 10050, 5, OrderAction::Ask, OrderType::Gtc));
+    book.new_order(&mut order(1, 3, 10200, 5, OrderAction::Ask, OrderType::Gtc));
+}
+
+fn check_market_sweep(book: &mut dyn OrderBook) {
+    seed_asks(book);
+
+    // 预留价高于全部卖盘的市价买单扫完全部卖盘，剩余部分拒绝且不挂单
+    let mut market = order(2, 10, 0, 20, OrderAction::Bid, OrderType::Market);
+    market.reserve_price = 20000;
+    book.new_order(&mut market);
+
+    let trades: Vec<_> = market.matcher_events.iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| (e.price, e.size))
+        .collect();
+    assert_eq!(trades, vec![(10000, 5), (10050, 5), (10200, 5)]);
+
+    let last = market.matcher_events.last().unwrap();
+    assert_eq!(last.event_type, MatcherEventType::Reject);
+    assert_eq!(last.size, 5);
+    assert_eq!(book.get_total_ask_volume(), 0);
+    assert_eq!(book.get_total_bid_volume(), 0);
+}
+
+fn check_market_band(book: &mut dyn OrderBook) {
+    seed_asks(book);
+
+    // 滑点 100bp：保护价 10100（低于预留价），不吃 10200 档
+    let mut market = order(2, 10, 0, 20, OrderAction::Bid, OrderType::Market);
+    market.reserve_price = 20000;
+    book.new_order(&mut market);
+
+    let filled: Size = market.matcher_events.iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| e.size)
+        .sum();
+    assert_eq!(filled, 10);
+    assert_eq!(book.get_total_ask_volume(), 5);
+    assert_eq!(book.get_total_bid_volume(), 0);
+}
+
+#[test]
+fn test_market_order_sweeps_advanced() {
+    check_market_sweep(&mut AdvancedOrderBook::new(create_symbol_spec(0)));
+}
+
+#[test]
+fn test_market_order_sweeps_direct_optimized() {
+    check_market_sweep(&mut DirectOrderBookOptimized::new(create_symbol_spec(0)));
+}
+
+#[test]
+fn test_market_order_price_band_advanced() {
+    check_market_band(&mut AdvancedOrderBook::new(create_symbol_spec(100)));
+}
+
+#[test]
+fn test_market_order_price_band_direct_optimized() {
+    check_market_band(&mut DirectOrderBookOptimized::new(create_symbol_spec(100)));
+}
+
+#[test]
+fn test_market_order_empty_book_rejected() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(0));
+    let mut market = order(2, 1, 0, 10, OrderAction::Ask, OrderType::Market);
+    book.new_order(&mut market);
+
+    assert_eq!(market.matcher_events.len(), 1);
+    assert_eq!(market.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(market.matcher_events[0].size, 10);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
+
+#[test]
+fn test_market_bid_capped_by_reserve_price() {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec(0));
+    seed_asks(&mut book);
+
+    // 现货买单预留价 10050，不能吃到 10200
+    let mut market = order(2, 10, 0, 20, OrderAction::Bid, OrderType::Market);
+    market.reserve_price = 10050;
+    book.new_order(&mut market);
+
+    let filled: Size = market.matcher_events.iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| e.size)
+        .sum();
+    assert_eq!(filled, 10);
+    assert_eq!(book.get_total_ask_volume(), 5);
+}
+
+#[test]
+fn test_market_to_limit_rests_at_best_price() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(0));
+    seed_asks(&mut book);
+
+    // 只在卖一 10000 成交，剩余 7 以 10000 按 GTC 挂出
+    let mut mtl = order(2, 10, 0, 12, OrderAction::Bid, OrderType::MarketToLimit);
+    mtl.reserve_price = 10200;
+    book.new_order(&mut mtl);
+
+    let trades: Vec<_> = mtl.matcher_events.iter().map(|e| (e.event_type, e.price, e.size)).collect();
+    assert_eq!(trades, vec![(MatcherEventType::Trade, 10000, 5)]);
+    assert_eq!(book.get_l2_data(1).bid_prices, vec![10000]);
+    assert_eq!(book.get_l2_data(1).bid_volumes, vec![7]);
+    assert_eq!(book.get_l2_data(1).ask_prices, vec![10050]);
+    let open = book.get_open_order(10).unwrap();
+    assert_eq!((open.price, open.order_type), (10000, OrderType::Gtc));
+}
+
+#[test]
+fn test_market_to_limit_rejected() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(0));
+
+    // 对手盘为空
+    let mut mtl = order(2, 10, 0, 5, OrderAction::Ask, OrderType::MarketToLimit);
+    book.new_order(&mut mtl);
+    assert_eq!(mtl.matcher_events.len(), 1);
+    assert_eq!(mtl.matcher_events[0].event_type, MatcherEventType::Reject);
+
+    // 现货买单的预留价低于卖一，剩余部分无法按卖一挂出
+    seed_asks(&mut book);
+    let mut mtl = order(2, 11, 0, 5, OrderAction::Bid, OrderType::MarketToLimit);
+    mtl.reserve_price = 9999;
+    book.new_order(&mut mtl);
+    assert_eq!(mtl.matcher_events.len(), 1);
+    assert_eq!(mtl.matcher_events[0].event_type, MatcherEventType::Reject);
+    assert_eq!(mtl.matcher_events[0].size, 5);
+    assert_eq!(book.get_total_ask_volume(), 15);
+    assert_eq!(book.get_total_bid_volume(), 0);
+
+    // 其他订单簿不支持
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec(0));
+    let mut mtl = order(2, 10, 0, 5, OrderAction::Ask, OrderType::MarketToLimit);
+    assert_eq!(book.new_order(&mut mtl), CommandResultCode::MatchingUnsupportedCommand);
+}
+
+#[test]
+fn test_spot_market_bid_cannot_overdraw() {
+    for kind in [OrderBookKind::Advanced, OrderBookKind::DirectOptimized] {
+        let mut core = ExchangeCore::new(ExchangeConfig { order_book_kind: kind, ..Default::default() });
+        core.add_symbol(create_symbol_spec(0));
+        for (uid, currency, amount) in [(1, 0, 1_000), (2, 1, 100_000)] {
+            core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: amount,
+                order_id: uid,
+                ..Default::default()
+            });
+        }
+        let ask = OrderCommand { reserve_price: 500, ..order(1, 1, 500, 1_000, OrderAction::Ask, OrderType::Gtc) };
+        assert_eq!(core.submit_command(ask).result_code, CommandResultCode::Success);
+
+        // 未给出预留价格的现货市价买单无法限定花费，R1 直接拒绝
+        for order_type in [OrderType::Market, OrderType::MarketToLimit] {
+            let market = order(2, 2, 0, 1_000, OrderAction::Bid, order_type);
+            assert_eq!(core.submit_command(market).result_code, CommandResultCode::RiskInvalidReserveBidPrice, "{:?}", kind);
+        }
+
+        // 预留价格超出余额时拒绝；可负担的预留价格限定成交上限
+        let market = OrderCommand { reserve_price: 500, ..order(2, 3, 0, 1_000, OrderAction::Bid, OrderType::Market) };
+        assert_eq!(core.submit_command(market).result_code, CommandResultCode::RiskNsf);
+        let market = OrderCommand { reserve_price: 500, ..order(2, 4, 0, 200, OrderAction::Bid, OrderType::Market) };
+        assert_eq!(core.submit_command(market).result_code, CommandResultCode::Success);
+
+        let state = core.serialize_state();
+        let user = state.pipeline_state.risk_engines.iter().find_map(|engine| engine.get_user(2)).unwrap();
+        assert_eq!((user.accounts[&0], user.accounts[&1]), (200, 0));
+        core.verify_invariants().unwrap();
+    }
+}
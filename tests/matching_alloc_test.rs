@@ -0,0 +1,88 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{DirectOrderBookOptimized, OrderBook};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// 统计当前线程的堆分配次数
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+fn ask(order_id: OrderId, price: Price) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1001,
+        order_id,
+        symbol: 100,
+        price,
+        size: 10,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    }
+}
+
+fn taker(events: Vec<MatcherTradeEvent>) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 2001,
+        order_id: 100_000,
+        symbol: 100,
+        price: 10_009,
+        reserve_price: 10_009,
+        size: 800,
+        action: OrderAction::Bid,
+        order_type: OrderType::Ioc,
+        matcher_events: events,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_sweep_is_allocation_free_after_warmup() {
+    for use_simd in [true, false] {
+        let mut book = DirectOrderBookOptimized::new(CoreSymbolSpecification::default());
+        book.set_simd_enabled(use_simd);
+        let mut events = Vec::with_capacity(128);
+
+        // 第一轮预热临时缓冲区
+        for round in 0..2 {
+            for order_id in 0..80 {
+                book.new_order(&mut ask(order_id, 10_000 + (order_id / 8) as Price));
+            }
+
+            let mut taker = taker(events);
+            let before = allocations();
+            book.new_order(&mut taker);
+            let allocated = allocations() - before;
+
+            assert_eq!(taker.matcher_events.len(), 80);
+            assert_eq!(book.get_total_ask_volume(), 0);
+            if round == 1 {
+                assert_eq!(allocated, 0, "use_simd={}", use_simd);
+            }
+            events = taker.matcher_events;
+            events.clear();
+        }
+    }
+}
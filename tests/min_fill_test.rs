@@ -0,0 +1,152 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::{new_order_book, OrderBook};
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn min_fill(cmd: OrderCommand, size: Size) -> OrderCommand {
+    OrderCommand { min_fill_size: Some(size), ..cmd }
+}
+
+/// 卖盘：100 x 3、101 x 4
+fn book() -> Box<dyn OrderBook> {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut order(1, 1, 100, 3, OrderAction::Ask, OrderType::Gtc));
+    book.new_order(&mut order(1, 2, 101, 4, OrderAction::Ask, OrderType::Gtc));
+    book
+}
+
+fn events(cmd: &OrderCommand) -> Vec<(MatcherEventType, Size)> {
+    cmd.matcher_events.iter().map(|event| (event.event_type, event.size)).collect()
+}
+
+#[test]
+fn test_ioc_min_fill() {
+    let mut book = book();
+    // 限价内只有 7，不足 8：整单拒绝，订单簿不变
+    let mut cmd = min_fill(order(2, 10, 101, 10, OrderAction::Bid, OrderType::Ioc), 8);
+    assert_eq!(book.new_order(&mut cmd), CommandResultCode::Success);
+    assert_eq!(events(&cmd), vec![(MatcherEventType::Reject, 10)]);
+    assert_eq!(book.get_total_ask_volume(), 7);
+
+    // 满足最小成交数量后按普通 IOC 撮合
+    let mut cmd = min_fill(order(2, 11, 101, 10, OrderAction::Bid, OrderType::Ioc), 7);
+    book.new_order(&mut cmd);
+    assert_eq!(events(&cmd), vec![(MatcherEventType::Trade, 3), (MatcherEventType::Trade, 4), (MatcherEventType::Reject, 3)]);
+    assert_eq!(book.get_total_ask_volume(), 0);
+}
+
+#[test]
+fn test_resting_order_min_fill() {
+    let mut book = book();
+    // 没有可成交的对手盘：原样挂出
+    let mut cmd = min_fill(order(2, 10, 99, 5, OrderAction::Bid, OrderType::Gtc), 5);
+    book.new_order(&mut cmd);
+    assert!(cmd.matcher_events.is_empty());
+    assert_eq!(book.get_open_order(10).map(|o| o.remaining), Some(5));
+
+    // 可成交 3，不足 4：挂出会与卖盘交叉，整单拒绝
+    let mut cmd = min_fill(order(2, 11, 100, 10, OrderAction::Bid, OrderType::Gtc), 4);
+    book.new_order(&mut cmd);
+    assert_eq!(events(&cmd), vec![(MatcherEventType::Reject, 10)]);
+    assert!(book.get_open_order(11).is_none());
+
+    // 满足后成交并挂出剩余；挂单之后的成交不受最小成交数量限制
+    let mut cmd = min_fill(order(2, 12, 100, 10, OrderAction::Bid, OrderType::Gtc), 3);
+    book.new_order(&mut cmd);
+    assert_eq!(events(&cmd), vec![(MatcherEventType::Trade, 3)]);
+    let mut taker = order(3, 13, 100, 1, OrderAction::Ask, OrderType::Ioc);
+    book.new_order(&mut taker);
+    assert_eq!(events(&taker), vec![(MatcherEventType::Trade, 1)]);
+    assert_eq!(book.get_open_order(12).map(|o| o.remaining), Some(6));
+}
+
+#[test]
+fn test_expired_makers_not_counted() {
+    let mut book = book();
+    // 101 x 4 在 2000 过期，尚未被过期扫描移除
+    let mut gtd = order(1, 3, 101, 4, OrderAction::Ask, OrderType::Gtd(2000));
+    book.new_order(&mut gtd);
+
+    // 未过期的只有 3 + 4，过期的 4 不计入：FOK 与最小成交数量整单拒绝，不产生成交
+    let mut fok = order(2, 10, 101, 8, OrderAction::Bid, OrderType::Fok);
+    fok.timestamp = 2001;
+    book.new_order(&mut fok);
+    assert_eq!(events(&fok), vec![(MatcherEventType::Reject, 8)]);
+
+    let mut cmd = min_fill(order(2, 11, 101, 10, OrderAction::Bid, OrderType::Ioc), 8);
+    cmd.timestamp = 2001;
+    book.new_order(&mut cmd);
+    assert_eq!(events(&cmd), vec![(MatcherEventType::Reject, 10)]);
+    assert_eq!(book.get_total_ask_volume(), 11);
+}
+
+#[test]
+fn test_min_fill_validation_and_support() {
+    let cases = [
+        (min_fill(order(2, 10, 100, 5, OrderAction::Bid, OrderType::Ioc), 6), CommandResultCode::ValidationInvalidMinFillSize),
+        (min_fill(order(2, 10, 100, 5, OrderAction::Bid, OrderType::Ioc), 0), CommandResultCode::ValidationInvalidMinFillSize),
+        (min_fill(order(2, 10, 100, 5, OrderAction::Bid, OrderType::PostOnly), 1), CommandResultCode::ValidationInvalidMinFillSize),
+        (min_fill(order(2, 10, 100, 5, OrderAction::Bid, OrderType::Gtc), 5), CommandResultCode::Success),
+    ];
+    for (i, (cmd, expected)) in cases.into_iter().enumerate() {
+        assert_eq!(validate_command(&cmd), expected, "case {}", i);
+    }
+
+    for kind in [OrderBookKind::Naive, OrderBookKind::Direct, OrderBookKind::DirectOptimized] {
+        let mut book = new_order_book(kind, spec());
+        let mut cmd = min_fill(order(2, 10, 100, 5, OrderAction::Bid, OrderType::Gtc), 1);
+        assert_eq!(book.new_order(&mut cmd), CommandResultCode::MatchingUnsupportedCommand, "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_rejected_min_fill_releases_hold() {
+    let mut core = ExchangeCore::new(ExchangeConfig { order_book_kind: OrderBookKind::Advanced, ..Default::default() });
+    core.add_symbol(spec());
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core.submit_command(order(1, 1, 100, 3, OrderAction::Ask, OrderType::Gtc));
+
+    let result = core.submit_command(min_fill(order(2, 2, 100, 5, OrderAction::Bid, OrderType::Ioc), 4));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(events(&result), vec![(MatcherEventType::Reject, 5)]);
+    let state = core.serialize_state();
+    assert_eq!(state.pipeline_state.risk_engines[0].get_user(2).unwrap().accounts[&1], 1_000);
+    core.verify_invariants().unwrap();
+}
@@ -0,0 +1,121 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{DirectOrderBookOptimized, OrderBook, OrderBookState};
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        reserve_price: price + 5,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn restore(book: &dyn OrderBook) -> Box<dyn OrderBook> {
+    let bytes = bincode::serialize(&book.serialize_state()).unwrap();
+    match bincode::deserialize(&bytes).unwrap() {
+        OrderBookState::DirectOptimized(book) => Box::new(book),
+        _ => panic!("快照类型错误"),
+    }
+}
+
+fn populated_book() -> DirectOrderBookOptimized {
+    let mut book = DirectOrderBookOptimized::new(create_symbol_spec());
+    for (id, price, size, action) in [
+        (1, 101, 10, OrderAction::Ask),
+        (2, 101, 20, OrderAction::Ask),
+        (3, 103, 5, OrderAction::Ask),
+        (4, 99, 15, OrderAction::Bid),
+        (5, 98, 25, OrderAction::Bid),
+        (6, 99, 7, OrderAction::Bid),
+    ] {
+        book.new_order(&mut order(1000 + id as UserId, id, price, size, action));
+    }
+    // 部分成交 1 号卖单
+    book.new_order(&mut order(2000, 7, 101, 4, OrderAction::Bid));
+    book
+}
+
+#[test]
+fn test_snapshot_keeps_resting_orders() {
+    let book = populated_book();
+    let restored = restore(&book);
+
+    assert_eq!(restored.get_l2_data(10), book.get_l2_data(10));
+    assert_eq!(restored.get_total_ask_volume(), 31);
+    assert_eq!(restored.get_total_bid_volume(), 47);
+    for order_id in 1..=7 {
+        assert_eq!(restored.get_open_order(order_id), book.get_open_order(order_id));
+    }
+    let partially_filled = restored.get_open_order(1).unwrap();
+    assert_eq!((partially_filled.size, partially_filled.remaining, partially_filled.reserve_price), (10, 6, 106));
+}
+
+#[test]
+fn test_restored_book_matches_identically() {
+    let mut book = populated_book();
+    let mut restored = restore(&book);
+
+    // 同价位时间优先：先成交 1 号剩余，再 2 号，再 3 号
+    let mut taker = order(3000, 10, 103, 30, OrderAction::Bid);
+    let mut restored_taker = taker.clone();
+    book.new_order(&mut taker);
+    restored.new_order(&mut restored_taker);
+
+    let fills = |cmd: &OrderCommand| -> Vec<(OrderId, Price, Size)> {
+        cmd.matcher_events.iter().map(|e| (e.matched_order_id, e.price, e.size)).collect()
+    };
+    assert_eq!(fills(&restored_taker), fills(&taker));
+    assert_eq!(fills(&restored_taker), vec![(1, 101, 6), (2, 101, 20), (3, 103, 4)]);
+
+    // 撤单、改价在恢复后的订单簿上正常工作
+    let mut cancel = OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1006,
+        order_id: 6,
+        symbol: 1,
+        ..Default::default()
+    };
+    assert_eq!(restored.cancel_order(&mut cancel), CommandResultCode::Success);
+    assert_eq!(restored.get_level_volume(OrderAction::Bid, 99), 15);
+
+    let mut move_cmd = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1005,
+        order_id: 5,
+        symbol: 1,
+        price: 100,
+        ..Default::default()
+    };
+    assert_eq!(restored.move_order(&mut move_cmd), CommandResultCode::Success);
+    assert_eq!(restored.get_l2_data(1).bid_prices, vec![100]);
+}
+
+#[test]
+fn test_empty_book_roundtrip() {
+    let book = DirectOrderBookOptimized::new(create_symbol_spec());
+    let mut restored = restore(&book);
+    assert_eq!(restored.get_ask_buckets_count(), 0);
+    assert_eq!(restored.get_bid_buckets_count(), 0);
+
+    restored.new_order(&mut order(1, 1, 100, 10, OrderAction::Ask));
+    assert_eq!(restored.get_total_ask_volume(), 10);
+}
@@ -0,0 +1,183 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+const CALL: SymbolId = 100;
+const PUT: SymbolId = 101;
+const SPOT: SymbolId = 102;
+
+/// 期权按持仓保证金结算：多头每手保证金 50，空头每手 200，行权价 100
+fn spec(symbol_id: SymbolId, symbol_type: SymbolType) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type,
+        base_currency: 2,
+        quote_currency: 1,
+        margin_buy: 50,
+        margin_sell: 200,
+        strike_price: 100,
+        ..Default::default()
+    }
+}
+
+fn setup(shards: usize) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig { risk_engines_num: shards, ..Default::default() });
+    core.add_symbol(spec(CALL, SymbolType::CallOption));
+    core.add_symbol(spec(PUT, SymbolType::PutOption));
+    core.add_symbol(spec(SPOT, SymbolType::CurrencyExchangePair));
+    for uid in [1, 2, 3] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: 1,
+            price: 10_000,
+            order_id: uid,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn exercise(uid: UserId, symbol: SymbolId, size: Size) -> OrderCommand {
+    OrderCommand { command: OrderCommandType::ExerciseOption, uid, symbol, size, ..Default::default() }
+}
+
+fn expire(symbol: SymbolId, settlement_price: Price) -> OrderCommand {
+    OrderCommand { command: OrderCommandType::ExpireSeries, symbol, price: settlement_price, ..Default::default() }
+}
+
+fn index_price(symbol: SymbolId, price: Price) -> OrderCommand {
+    OrderCommand::reference_price_update(symbol, StopTriggerSource::IndexPrice, price, 0)
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 100,
+        ..Default::default()
+    }
+}
+
+/// (quote 余额, 净持仓)
+fn account(core: &ExchangeCore, uid: UserId, symbol: SymbolId) -> (i64, i64) {
+    let state = core.serialize_state();
+    let user = state.pipeline_state.risk_engines.iter().find_map(|engine| engine.get_user(uid)).unwrap();
+    let net = user.positions.get(&symbol).map_or(0, |position| position.net_position());
+    (user.accounts.get(&1).copied().unwrap_or(0), net)
+}
+
+#[test]
+fn test_exercise_option() {
+    for shards in [1, 2] {
+        let mut core = setup(shards);
+        // 用户 2、3 分别卖出 6、4 手看涨，用户 1 以权利金 5 买入 10 手
+        assert_eq!(core.submit_command(order(2, 1, CALL, 5, 6, OrderAction::Ask)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(order(3, 2, CALL, 5, 4, OrderAction::Ask)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(order(1, 3, CALL, 5, 10, OrderAction::Bid)).result_code, CommandResultCode::Success);
+
+        // 没有指数价不能行权；行权不接受用户指定的结算价
+        assert_eq!(core.submit_command(exercise(1, CALL, 5)).result_code, CommandResultCode::RiskIndexPriceNotSet);
+        let chosen = OrderCommand { price: 1_000, ..exercise(1, CALL, 5) };
+        assert_eq!(core.submit_command(chosen).result_code, CommandResultCode::ValidationInvalidPrice);
+        assert_eq!(account(&core, 1, CALL), (10_000 - 10 * 50, 10));
+
+        // 指数价 130：每手内在价值 30，行权 5 手按空头持仓比例指派 3、2 手
+        core.submit_command(index_price(CALL, 130));
+        let result = core.submit_command(exercise(1, CALL, 5));
+        assert_eq!(result.result_code, CommandResultCode::Success, "{} shards", shards);
+        assert_eq!(result.price, 130);
+        assert_eq!(account(&core, 1, CALL), (10_000 - 5 * 50 + 5 * 25, 5));
+        assert_eq!(account(&core, 2, CALL), (10_000 - 3 * 200 - 3 * 25, -3));
+        assert_eq!(account(&core, 3, CALL), (10_000 - 2 * 200 - 2 * 25, -2));
+        core.verify_invariants().unwrap();
+
+        // 按比例取整的余数按用户号顺序指派
+        assert_eq!(core.submit_command(exercise(1, CALL, 2)).result_code, CommandResultCode::Success);
+        assert_eq!((account(&core, 2, CALL).1, account(&core, 3, CALL).1), (-1, -2));
+
+        assert_eq!(core.submit_command(exercise(1, CALL, 6)).result_code, CommandResultCode::RiskInsufficientPosition);
+        assert_eq!(core.submit_command(exercise(2, CALL, 1)).result_code, CommandResultCode::RiskInsufficientPosition);
+        assert_eq!(core.submit_command(exercise(1, PUT, 1)).result_code, CommandResultCode::RiskIndexPriceNotSet);
+        assert_eq!(core.submit_command(exercise(1, SPOT, 1)).result_code, CommandResultCode::UnsupportedSymbolType);
+        assert_eq!(core.submit_command(exercise(1, 999, 1)).result_code, CommandResultCode::InvalidSymbol);
+        assert_eq!(core.submit_command(exercise(1, CALL, 0)).result_code, CommandResultCode::ValidationInvalidSize);
+        core.submit_command(index_price(CALL, 100));
+        assert_eq!(core.submit_command(exercise(1, CALL, 1)).result_code, CommandResultCode::RiskOptionOutOfTheMoney);
+
+        // 行权后剩余持仓到期结算，全部余额守恒
+        assert_eq!(core.submit_command(expire(CALL, 130)).result_code, CommandResultCode::Success);
+        let total: i64 = [1, 2, 3].iter().map(|&uid| account(&core, uid, CALL).0).sum();
+        assert_eq!(total, 30_000);
+        assert_eq!(account(&core, 1, CALL), (10_000 + 10 * 25, 0));
+        core.verify_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_expire_series_settles_all_holders() {
+    for shards in [1, 2] {
+        let mut core = setup(shards);
+        assert_eq!(core.submit_command(order(1, 1, PUT, 5, 3, OrderAction::Ask)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(order(2, 2, PUT, 5, 1, OrderAction::Bid)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(order(3, 3, PUT, 5, 2, OrderAction::Bid)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(order(2, 4, PUT, 4, 2, OrderAction::Bid)).result_code, CommandResultCode::Success);
+
+        // 结算价 80：撤销剩余挂单，每手内在价值 20，空头向多头支付 20 - 5，另一系列不受影响
+        assert_eq!(core.submit_command(expire(PUT, 80)).result_code, CommandResultCode::Success, "{} shards", shards);
+        assert_eq!(account(&core, 1, PUT), (10_000 - 3 * 15, 0));
+        assert_eq!(account(&core, 2, PUT), (10_000 + 15, 0));
+        assert_eq!(account(&core, 3, PUT), (10_000 + 2 * 15, 0));
+        core.verify_invariants().unwrap();
+
+        let book = core.submit_command(OrderCommand { command: OrderCommandType::OrderBookRequest, symbol: PUT, size: 5, ..Default::default() });
+        assert_eq!(book.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+        assert_eq!(core.submit_command(order(1, 5, PUT, 5, 1, OrderAction::Bid)).result_code, CommandResultCode::MatchingInvalidOrderBookId);
+        assert_eq!(core.submit_command(expire(PUT, 80)).result_code, CommandResultCode::InvalidSymbol);
+
+        // 虚值到期：多头损失权利金，由空头收取
+        assert_eq!(core.submit_command(order(2, 6, CALL, 5, 2, OrderAction::Ask)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(order(1, 7, CALL, 5, 2, OrderAction::Bid)).result_code, CommandResultCode::Success);
+        assert_eq!(core.submit_command(expire(CALL, 90)).result_code, CommandResultCode::Success);
+        assert_eq!(account(&core, 1, CALL), (10_000 - 3 * 15 - 2 * 5, 0));
+        assert_eq!(account(&core, 2, CALL), (10_000 + 15 + 2 * 5, 0));
+        let total: i64 = [1, 2, 3].iter().map(|&uid| account(&core, uid, CALL).0).sum();
+        assert_eq!(total, 30_000);
+        core.verify_invariants().unwrap();
+        assert_eq!(core.submit_command(expire(SPOT, 90)).result_code, CommandResultCode::UnsupportedSymbolType);
+    }
+}
+
+#[test]
+fn test_expiring_series_rejects_orders_before_settlement() {
+    let mut risk = RiskEngine::new(0, 1);
+    risk.add_symbol(spec(CALL, SymbolType::CallOption));
+    risk.pre_process(&mut OrderCommand { command: OrderCommandType::AddUser, uid: 1, ..Default::default() });
+    let mut deposit = OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: 1, price: 1_000, order_id: 1, ..Default::default() };
+    risk.pre_process(&mut deposit);
+    risk.pre_process(&mut index_price(CALL, 130));
+
+    // R1 受理到期后、R2 结算前的新单与行权均被拒绝
+    let mut expiry = expire(CALL, 130);
+    risk.pre_process(&mut expiry);
+    assert_eq!(expiry.result_code, CommandResultCode::ValidForMatchingEngine);
+    let mut place = order(1, 1, CALL, 5, 1, OrderAction::Bid);
+    risk.pre_process(&mut place);
+    assert_eq!(place.result_code, CommandResultCode::InvalidSymbol);
+    let mut early = exercise(1, CALL, 1);
+    risk.pre_process(&mut early);
+    assert_eq!(early.result_code, CommandResultCode::InvalidSymbol);
+
+    expiry.result_code = CommandResultCode::Success;
+    risk.post_process(&mut expiry);
+    assert_eq!(risk.get_user(1).unwrap().accounts[&1], 1_000);
+    assert!(risk.reference_prices(CALL).is_none());
+}
@@ -0,0 +1,133 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore, ExchangeState};
+use matching_core::core::orderbook::OrderBookState;
+
+fn spec(symbol_id: SymbolId, order_book: Option<OrderBookKind>) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        order_book,
+        ..Default::default()
+    }
+}
+
+fn setup_users(core: &mut ExchangeCore) {
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+/// 各交易对订单簿的实现类型
+fn book_kinds(state: &ExchangeState) -> Vec<(SymbolId, OrderBookKind)> {
+    let mut kinds: Vec<(SymbolId, OrderBookKind)> = state
+        .pipeline_state
+        .matching_engines
+        .iter()
+        .flat_map(|engine| engine.order_books.iter())
+        .map(|(symbol, book)| {
+            let kind = match book {
+                OrderBookState::Naive(_) => OrderBookKind::Naive,
+                OrderBookState::Direct(_) => OrderBookKind::Direct,
+                OrderBookState::DirectOptimized(_) => OrderBookKind::DirectOptimized,
+                OrderBookState::Advanced(_) => OrderBookKind::Advanced,
+                OrderBookState::DirectOptimizedLadder(_) => OrderBookKind::DirectOptimizedLadder,
+            };
+            (*symbol, kind)
+        })
+        .collect();
+    kinds.sort_by_key(|(symbol, _)| *symbol);
+    kinds
+}
+
+#[test]
+fn test_configured_order_book_kind_trades() {
+    for kind in [
+        OrderBookKind::Naive,
+        OrderBookKind::Direct,
+        OrderBookKind::DirectOptimized,
+        OrderBookKind::Advanced,
+        OrderBookKind::DirectOptimizedLadder,
+    ] {
+        let mut core = ExchangeCore::new(ExchangeConfig {
+            order_book_kind: kind,
+            ..Default::default()
+        });
+        core.add_symbol(spec(100, None));
+        setup_users(&mut core);
+        assert_eq!(book_kinds(&core.serialize_state()), vec![(100, kind)]);
+
+        core.submit_command(order(1001, 1, 100, 1000, 10, OrderAction::Ask, OrderType::Gtc));
+        let taker = core.submit_command(order(1002, 2, 100, 1000, 4, OrderAction::Bid, OrderType::Gtc));
+        let trades: Vec<(OrderId, Size)> = taker.matcher_events.iter().map(|e| (e.matched_order_id, e.size)).collect();
+        assert_eq!(trades, vec![(1, 4)], "{:?}", kind);
+        core.verify_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_symbol_overrides_default_kind() {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        matching_engines_num: 2,
+        ..Default::default()
+    });
+    core.add_symbol(spec(100, None));
+    core.add_symbol(spec(101, Some(OrderBookKind::Advanced)));
+    setup_users(&mut core);
+    assert_eq!(
+        book_kinds(&core.serialize_state()),
+        vec![(100, OrderBookKind::Direct), (101, OrderBookKind::Advanced)]
+    );
+
+    // 高级订单簿支持市价单
+    core.submit_command(order(1001, 1, 101, 1000, 10, OrderAction::Ask, OrderType::Gtc));
+    let market = core.submit_command(order(1002, 2, 101, 1000, 4, OrderAction::Bid, OrderType::Market));
+    assert_eq!(market.matcher_events.len(), 1);
+    assert_eq!(market.matcher_events[0].event_type, MatcherEventType::Trade);
+
+    // 恢复后新增的交易对仍使用配置的默认实现
+    let mut restored = ExchangeCore::from_state(ExchangeState {
+        config: ExchangeConfig {
+            matching_engines_num: 2,
+            order_book_kind: OrderBookKind::Naive,
+            ..Default::default()
+        },
+        ..core.serialize_state()
+    });
+    restored.add_symbol(spec(102, None));
+    assert_eq!(
+        book_kinds(&restored.serialize_state()),
+        vec![(100, OrderBookKind::Direct), (101, OrderBookKind::Advanced), (102, OrderBookKind::Naive)]
+    );
+}
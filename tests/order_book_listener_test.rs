@@ -0,0 +1,140 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::listener::{OrderBookListener, OrderRemoval};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Seen {
+    Trade(OrderId, OrderId, Size), // taker, maker, 数量
+    Added(OrderId),
+    Removed(UserId, OrderId, OrderRemoval),
+    Level(OrderAction, Price, Size),
+}
+
+struct Recorder(Arc<Mutex<Vec<Seen>>>);
+
+impl OrderBookListener for Recorder {
+    fn on_trade(&mut self, cmd: &OrderCommand, event: &MatcherTradeEvent) {
+        self.0.lock().unwrap().push(Seen::Trade(event.taker(cmd).1, event.matched_order_id, event.size));
+    }
+
+    fn on_order_added(&mut self, _cmd: &OrderCommand, order: &OpenOrder) {
+        self.0.lock().unwrap().push(Seen::Added(order.order_id));
+    }
+
+    fn on_order_removed(&mut self, _cmd: &OrderCommand, uid: UserId, order_id: OrderId, reason: OrderRemoval) {
+        self.0.lock().unwrap().push(Seen::Removed(uid, order_id, reason));
+    }
+
+    fn on_level_changed(&mut self, _cmd: &OrderCommand, delta: &L2Delta) {
+        self.0.lock().unwrap().push(Seen::Level(delta.action, delta.price, delta.volume));
+    }
+}
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn take(seen: &Arc<Mutex<Vec<Seen>>>) -> Vec<Seen> {
+    std::mem::take(&mut *seen.lock().unwrap())
+}
+
+#[test]
+fn test_listener_sees_order_lifecycle() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.add_symbol(spec());
+    router.add_listener(Box::new(Recorder(seen.clone())));
+
+    router.process_order(&mut order(1, 1, 100, 5, OrderAction::Ask));
+    router.process_order(&mut order(2, 2, 101, 3, OrderAction::Ask));
+    assert_eq!(
+        take(&seen),
+        vec![Seen::Added(1), Seen::Level(OrderAction::Ask, 100, 5), Seen::Added(2), Seen::Level(OrderAction::Ask, 101, 3)]
+    );
+
+    // taker 全部成交不进入订单簿
+    router.process_order(&mut order(3, 3, 101, 6, OrderAction::Bid));
+    assert_eq!(
+        take(&seen),
+        vec![
+            Seen::Trade(3, 1, 5),
+            Seen::Trade(3, 2, 1),
+            Seen::Removed(1, 1, OrderRemoval::Filled),
+            Seen::Level(OrderAction::Ask, 100, 0),
+            Seen::Level(OrderAction::Ask, 101, 2),
+        ]
+    );
+
+    let mut cancel = OrderCommand { command: OrderCommandType::CancelOrder, uid: 2, order_id: 2, symbol: 100, ..Default::default() };
+    router.process_order(&mut cancel);
+    assert_eq!(take(&seen), vec![Seen::Removed(2, 2, OrderRemoval::Cancelled), Seen::Level(OrderAction::Ask, 101, 0)]);
+
+    // 未改变订单簿的命令不回调
+    let mut cancel = OrderCommand { result_code: CommandResultCode::ValidForMatchingEngine, ..cancel };
+    router.process_order(&mut cancel);
+    assert_eq!(cancel.result_code, CommandResultCode::MatchingUnknownOrderId);
+    assert!(take(&seen).is_empty());
+}
+
+#[test]
+fn test_listener_registered_per_shard() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let shards = Arc::new(Mutex::new(Vec::new()));
+    let mut core = ExchangeCore::new(ExchangeConfig { matching_engines_num: 2, ..Default::default() });
+    core.add_symbol(spec());
+    let (sink, created) = (seen.clone(), shards.clone());
+    core.add_order_book_listener(move |shard_id| {
+        created.lock().unwrap().push(shard_id);
+        Box::new(Recorder(sink.clone()))
+    });
+    assert_eq!(*shards.lock().unwrap(), vec![0, 1]);
+
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: 2, price: 100, order_id: uid, ..Default::default() });
+    }
+    for (order_id, price) in [(1, 100), (2, 101)] {
+        let cmd = OrderCommand { result_code: CommandResultCode::New, ..order(1, order_id, price, 2, OrderAction::Ask) };
+        assert_eq!(core.submit_command(cmd).result_code, CommandResultCode::Success);
+    }
+    take(&seen);
+
+    // 批量撤单（暂停用户）逐单回调
+    let suspend = OrderCommand { command: OrderCommandType::SuspendUser, uid: 1, service_flags: SUSPEND_USER_CANCEL_ORDERS, ..Default::default() };
+    let result = core.submit_command(suspend);
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(
+        take(&seen),
+        vec![
+            Seen::Removed(1, 1, OrderRemoval::Purged),
+            Seen::Level(OrderAction::Ask, 100, 0),
+            Seen::Removed(1, 2, OrderRemoval::Purged),
+            Seen::Level(OrderAction::Ask, 101, 0),
+        ]
+    );
+}
@@ -0,0 +1,92 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    });
+
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) {
+    let result = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::Success);
+}
+
+fn order_book_request(core: &mut ExchangeCore, symbol: SymbolId, depth: Size) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol,
+        size: depth,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_order_book_request_returns_snapshot() {
+    let mut core = setup();
+    place(&mut core, 1002, 1, 101, 5, OrderAction::Ask);
+    place(&mut core, 1002, 2, 102, 6, OrderAction::Ask);
+    place(&mut core, 1002, 3, 103, 7, OrderAction::Ask);
+    place(&mut core, 1001, 4, 99, 3, OrderAction::Bid);
+    place(&mut core, 1001, 5, 98, 4, OrderAction::Bid);
+
+    let result = order_book_request(&mut core, 100, 2);
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    let l2 = result.market_data.expect("应答应包含深度数据");
+    assert_eq!(l2.ask_prices, vec![101, 102]);
+    assert_eq!(l2.ask_volumes, vec![5, 6]);
+    assert_eq!(l2.bid_prices, vec![99, 98]);
+    assert_eq!(l2.bid_volumes, vec![3, 4]);
+
+    // 超过实际档位数的深度返回全部档位
+    let l2 = order_book_request(&mut core, 100, Size::MAX).market_data.unwrap();
+    assert_eq!(l2.ask_prices.len(), 3);
+    assert_eq!(l2.bid_prices.len(), 2);
+}
+
+#[test]
+fn test_order_book_request_unknown_symbol() {
+    let mut core = setup();
+    let result = order_book_request(&mut core, 999, 10);
+    assert_eq!(result.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+    assert!(result.market_data.is_none());
+}
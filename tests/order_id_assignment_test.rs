@@ -0,0 +1,73 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    }
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig { risk_engines_num: 2, ..Default::default() });
+    core.add_symbol(spec());
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid, symbol: 2, price: 100, order_id: uid, ..Default::default() });
+    }
+    core
+}
+
+/// 请求分配订单号的卖单（传入的 order_id 被忽略）
+fn assigned_ask(uid: UserId, price: Price) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id: 7,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size: 1,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        assign_order_id: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_assigned_order_ids_unique_per_shard() {
+    let mut core = setup();
+    let mut ids = Vec::new();
+    for (uid, price) in [(1, 100), (2, 101), (1, 102)] {
+        let result = core.submit_command(assigned_ask(uid, price));
+        assert_eq!(result.result_code, CommandResultCode::Success);
+        ids.push(result.order_id);
+    }
+    // uid 1 属于风控分片 1，uid 2 属于分片 0
+    let base = ASSIGNED_ORDER_ID_BASE;
+    assert_eq!(ids, vec![base + 2 + 1, base + 2, base + 4 + 1]);
+    let orders = core.submit_command(OrderCommand { command: OrderCommandType::UserOrdersRequest, uid: 1, symbol: 100, ..Default::default() });
+    assert_eq!(orders.open_orders.iter().map(|order| order.order_id).collect::<Vec<_>>(), vec![ids[0], ids[2]]);
+
+    let cancel = core.submit_command(OrderCommand { command: OrderCommandType::CancelOrder, uid: 1, order_id: ids[0], symbol: 100, ..Default::default() });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_assigned_order_ids_continue_after_recovery() {
+    let mut core = setup();
+    let first = core.submit_command(assigned_ask(2, 100)).order_id;
+
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    let second = restored.submit_command(assigned_ask(2, 101));
+    assert_eq!(second.result_code, CommandResultCode::Success);
+    assert_eq!(second.order_id, first + 2);
+
+    // 未请求分配时沿用调用方的订单号
+    let own = restored.submit_command(OrderCommand { assign_order_id: false, ..assigned_ask(2, 102) });
+    assert_eq!((own.result_code, own.order_id), (CommandResultCode::Success, 7));
+}
@@ -0,0 +1,132 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::new_order_book;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_stats_all_kinds() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        assert_eq!(book.stats().last_trade, None);
+        assert_eq!(book.stats().ask_price_range, None);
+
+        book.new_order(&mut order(1, 1, 101, 2, OrderAction::Ask));
+        book.new_order(&mut order(1, 2, 103, 3, OrderAction::Ask));
+        book.new_order(&mut order(1, 3, 105, 1, OrderAction::Ask));
+        book.new_order(&mut order(2, 4, 99, 4, OrderAction::Bid));
+        book.new_order(&mut OrderCommand { reserve_price: 110, ..order(2, 5, 98, 1, OrderAction::Bid) });
+        book.new_order(&mut order(2, 6, 101, 1, OrderAction::Bid));
+
+        let stats = book.stats();
+        assert_eq!(stats.symbol, 100);
+        assert_eq!(stats.order_count, 5, "{:?}", kind);
+        assert_eq!((stats.ask_levels, stats.bid_levels), (3, 2), "{:?}", kind);
+        assert_eq!((stats.total_ask_volume, stats.total_bid_volume), (5, 5), "{:?}", kind);
+        assert_eq!((stats.visible_ask_volume, stats.visible_bid_volume), (5, 5), "{:?}", kind);
+        assert_eq!(stats.ask_price_range, Some((101, 105)), "{:?}", kind);
+        assert_eq!(stats.bid_price_range, Some((98, 99)), "{:?}", kind);
+        assert_eq!(stats.last_trade, Some(LastTrade { price: 101, size: 1, timestamp: 1006 }), "{:?}", kind);
+
+        // 改价成交同样更新最近成交
+        let mut moved = OrderCommand {
+            command: OrderCommandType::MoveOrder,
+            uid: 2,
+            order_id: 5,
+            symbol: 100,
+            price: 101,
+            timestamp: 2000,
+            ..Default::default()
+        };
+        assert_eq!(book.move_order(&mut moved), CommandResultCode::Success, "{:?}", kind);
+        let stats = book.stats();
+        assert_eq!(stats.last_trade, Some(LastTrade { price: 101, size: 1, timestamp: 2000 }), "{:?}", kind);
+        assert_eq!(stats.ask_price_range, Some((103, 105)), "{:?}", kind);
+
+        // 无成交的命令不改变最近成交
+        book.new_order(&mut order(1, 7, 110, 1, OrderAction::Ask));
+        assert_eq!(book.stats().last_trade.map(|t| t.timestamp), Some(2000), "{:?}", kind);
+
+        let pool = book.stats().pool;
+        match kind {
+            OrderBookKind::DirectOptimized | OrderBookKind::DirectOptimizedLadder => {
+                let pool = pool.expect("预分配订单池");
+                assert_eq!(pool.used, 4);
+                assert!(pool.ratio() > 0.0 && pool.ratio() < 1.0);
+            }
+            _ => assert_eq!(pool, None, "{:?}", kind),
+        }
+    }
+}
+
+#[test]
+fn test_advanced_stats_iceberg_and_stops() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut OrderCommand {
+        order_type: OrderType::Iceberg,
+        visible_size: Some(2),
+        ..order(1, 1, 101, 10, OrderAction::Ask)
+    });
+    book.new_order(&mut OrderCommand {
+        order_type: OrderType::StopLimit,
+        stop_price: Some(120),
+        ..order(2, 2, 121, 1, OrderAction::Bid)
+    });
+
+    let stats = book.stats();
+    assert_eq!((stats.order_count, stats.stop_order_count), (1, 1));
+    assert_eq!((stats.total_ask_volume, stats.visible_ask_volume), (10, 2));
+    assert_eq!(stats.bid_price_range, None);
+}
+
+#[test]
+fn test_router_stats_survive_snapshot() {
+    for kind in KINDS {
+        let mut router = MatchingEngineRouter::new(0, 1);
+        router.add_symbol(CoreSymbolSpecification { order_book: Some(kind), ..spec() });
+        router.process_order(&mut order(1, 1, 100, 5, OrderAction::Ask));
+        router.process_order(&mut order(2, 2, 100, 2, OrderAction::Bid));
+
+        let stats = router.stats(100).unwrap();
+        assert_eq!(stats.last_trade, Some(LastTrade { price: 100, size: 2, timestamp: 1002 }), "{:?}", kind);
+        assert_eq!(router.stats(999), None);
+
+        let restored = MatchingEngineRouter::from_state(router.serialize_state());
+        assert_eq!(restored.stats(100), Some(stats), "{:?}", kind);
+    }
+}
@@ -0,0 +1,143 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{
+    BTreePriceIndex, DirectOrderBookOptimized, OrderBook, OrderBookState, PriceBucket, PriceIndex, PriceLadder,
+};
+
+fn bucket(price: Price) -> PriceBucket {
+    PriceBucket {
+        price,
+        volume: 1,
+        head: 0,
+        tail: 0,
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+/// 确定性伪随机命令序列：挂单、即时成交、撤单、改价、减量
+fn commands(count: usize) -> Vec<OrderCommand> {
+    let mut seed: u64 = 42;
+    let mut next = move |bound: u64| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) % bound
+    };
+
+    let mut result = Vec::with_capacity(count);
+    for order_id in 1..=count as OrderId {
+        let uid = 1000 + next(5) as UserId;
+        let action = if next(2) == 0 { OrderAction::Ask } else { OrderAction::Bid };
+        let price = 1_000 + next(60) as Price - 30;
+        let cmd = match next(10) {
+            0..=5 => order(uid, order_id, price, 1 + next(20) as Size, action, OrderType::Gtc),
+            6 => order(uid, order_id, price, 1 + next(80) as Size, action, OrderType::Ioc),
+            7 => OrderCommand {
+                command: OrderCommandType::CancelOrder,
+                order_id: 1 + next(order_id),
+                ..order(uid, 0, 0, 0, action, OrderType::Gtc)
+            },
+            8 => OrderCommand {
+                command: OrderCommandType::MoveOrder,
+                order_id: 1 + next(order_id),
+                ..order(uid, 0, price, 0, action, OrderType::Gtc)
+            },
+            _ => OrderCommand {
+                command: OrderCommandType::ReduceOrder,
+                order_id: 1 + next(order_id),
+                ..order(uid, 0, 0, 1 + next(5) as Size, action, OrderType::Gtc)
+            },
+        };
+        result.push(cmd);
+    }
+    result
+}
+
+fn execute(book: &mut dyn OrderBook, cmd: &mut OrderCommand) -> CommandResultCode {
+    match cmd.command {
+        OrderCommandType::PlaceOrder => book.new_order(cmd),
+        OrderCommandType::CancelOrder => book.cancel_order(cmd),
+        OrderCommandType::MoveOrder => book.move_order(cmd),
+        OrderCommandType::ReduceOrder => book.reduce_order(cmd),
+        _ => unreachable!(),
+    }
+}
+
+fn events(cmd: &OrderCommand) -> Vec<(MatcherEventType, OrderId, Price, Size)> {
+    cmd.matcher_events.iter().map(|e| (e.event_type, e.matched_order_id, e.price, e.size)).collect()
+}
+
+#[test]
+fn test_ladder_matches_btree_index() {
+    for use_simd in [true, false] {
+        let mut btree = DirectOrderBookOptimized::<BTreePriceIndex>::with_price_index(CoreSymbolSpecification::default());
+        let mut ladder = DirectOrderBookOptimized::<PriceLadder>::with_price_index(CoreSymbolSpecification::default());
+        btree.set_simd_enabled(use_simd);
+        ladder.set_simd_enabled(use_simd);
+
+        for cmd in commands(3000) {
+            let (mut a, mut b) = (cmd.clone(), cmd);
+            assert_eq!(execute(&mut btree, &mut a), execute(&mut ladder, &mut b));
+            assert_eq!(events(&a), events(&b), "order {}", a.order_id);
+            assert_eq!(btree.get_l2_data(100), ladder.get_l2_data(100));
+        }
+        assert_eq!(btree.get_ask_buckets_count(), ladder.get_ask_buckets_count());
+        assert_eq!(btree.get_bid_buckets_count(), ladder.get_bid_buckets_count());
+    }
+}
+
+#[test]
+fn test_ladder_grows_in_both_directions() {
+    let mut ladder = PriceLadder::default();
+    for price in [1_000, 5_000, 10, 1_001, 0] {
+        ladder.insert(bucket(price));
+    }
+    let prices = |ladder: &PriceLadder| ladder.iter().map(|b| b.price).collect::<Vec<_>>();
+    assert_eq!(prices(&ladder), vec![0, 10, 1_000, 1_001, 5_000]);
+    assert_eq!(ladder.len(), 5);
+
+    assert_eq!(ladder.next_above(None).map(|b| b.price), Some(0));
+    assert_eq!(ladder.next_above(Some(10)).map(|b| b.price), Some(1_000));
+    assert_eq!(ladder.next_above(Some(5_000)).map(|b| b.price), None);
+    assert_eq!(ladder.next_below(None).map(|b| b.price), Some(5_000));
+    assert_eq!(ladder.next_below(Some(1_000)).map(|b| b.price), Some(10));
+    assert_eq!(ladder.next_below(Some(0)).map(|b| b.price), None);
+
+    // 删除两端档位后有效范围收紧
+    assert!(ladder.remove(0).is_some());
+    assert!(ladder.remove(5_000).is_some());
+    assert!(ladder.remove(5_000).is_none());
+    assert_eq!(prices(&ladder), vec![10, 1_000, 1_001]);
+    assert_eq!(ladder.next_below(None).map(|b| b.price), Some(1_001));
+    assert_eq!(ladder.get(1_000).map(|b| b.price), Some(1_000));
+    assert!(ladder.get(999).is_none());
+    assert!(ladder.get(-1).is_none());
+}
+
+#[test]
+fn test_ladder_snapshot_roundtrip() {
+    let mut book = DirectOrderBookOptimized::<PriceLadder>::with_price_index(CoreSymbolSpecification::default());
+    for cmd in commands(500).iter_mut() {
+        execute(&mut book, cmd);
+    }
+
+    let bytes = bincode::serialize(&book.serialize_state()).unwrap();
+    let restored: Box<dyn OrderBook> = match bincode::deserialize(&bytes).unwrap() {
+        OrderBookState::DirectOptimizedLadder(book) => Box::new(book),
+        _ => panic!("快照类型错误"),
+    };
+    assert_eq!(restored.get_l2_data(100), book.get_l2_data(100));
+    assert_eq!(restored.get_total_bid_volume(), book.get_total_bid_volume());
+}
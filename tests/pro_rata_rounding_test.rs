@@ -0,0 +1,141 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{new_order_book, OrderBook, OrderBookState};
+
+fn spec(matching_algorithm: MatchingAlgorithm, pro_rata_rounding: ProRataRounding) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        matching_algorithm,
+        pro_rata_rounding,
+        ..Default::default()
+    }
+}
+
+fn ask(order_id: OrderId, size: Size) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: order_id as UserId,
+        order_id,
+        symbol: 100,
+        price: 100,
+        reserve_price: 100,
+        size,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        timestamp: order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn book_with(algorithm: MatchingAlgorithm, rounding: ProRataRounding, sizes: &[Size]) -> Box<dyn OrderBook> {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(algorithm, rounding));
+    for (i, &size) in sizes.iter().enumerate() {
+        book.new_order(&mut ask(i as OrderId + 1, size));
+    }
+    book
+}
+
+/// taker 买入 quantity，返回各 maker（按挂单顺序）的成交量
+fn allocations(book: &mut dyn OrderBook, makers: usize, quantity: Size) -> Vec<Size> {
+    let mut taker = OrderCommand { uid: 99, order_id: 99, action: OrderAction::Bid, order_type: OrderType::Ioc, ..ask(99, quantity) };
+    book.new_order(&mut taker);
+    let mut filled = vec![0; makers];
+    for event in taker.matcher_events.iter().filter(|e| e.event_type == MatcherEventType::Trade) {
+        filled[event.matched_order_id as usize - 1] += event.size;
+    }
+    filled
+}
+
+/// 参考实现：按比例向下取整，剩余按规则分配
+fn expected(sizes: &[Size], quantity: Size, rounding: ProRataRounding) -> Vec<Size> {
+    let total: Size = sizes.iter().sum();
+    let quantity = quantity.min(total);
+    let mut result: Vec<Size> = sizes.iter().map(|&size| quantity * size / total).collect();
+    let mut residual = quantity - result.iter().sum::<Size>();
+    match rounding {
+        ProRataRounding::TimePriority => {
+            for (allocation, &size) in result.iter_mut().zip(sizes) {
+                let extra = residual.min(size - *allocation);
+                *allocation += extra;
+                residual -= extra;
+            }
+        }
+        ProRataRounding::LargestRemainder => {
+            while residual > 0 {
+                // 余数最大者（相同时取先挂单者）补 1
+                let remainder = |i: usize| if result[i] * total < quantity * sizes[i] { quantity * sizes[i] % total } else { -1 };
+                let best = (0..sizes.len()).max_by_key(|&i| (remainder(i), std::cmp::Reverse(i))).unwrap();
+                result[best] += 1;
+                residual -= 1;
+            }
+        }
+    }
+    result
+}
+
+/// 长度 1..=3、每笔 1..=4 手的全部挂单组合
+fn all_queues() -> Vec<Vec<Size>> {
+    let mut queues: Vec<Vec<Size>> = vec![vec![]];
+    let mut all = Vec::new();
+    for _ in 0..3 {
+        queues = queues.iter().flat_map(|queue| (1..=4).map(move |size| [queue.clone(), vec![size]].concat())).collect();
+        all.extend(queues.iter().cloned());
+    }
+    all
+}
+
+#[test]
+fn test_rounding_exhaustive() {
+    for rounding in [ProRataRounding::TimePriority, ProRataRounding::LargestRemainder] {
+        for sizes in all_queues() {
+            let total: Size = sizes.iter().sum();
+            for quantity in 1..=total {
+                let mut book = book_with(MatchingAlgorithm::ProRata, rounding, &sizes);
+                let filled = allocations(book.as_mut(), sizes.len(), quantity);
+                assert_eq!(filled, expected(&sizes, quantity, rounding), "{:?} {:?} {}", rounding, sizes, quantity);
+                // 最大余数法满足份额约束：每笔为比例份额向下或向上取整
+                if rounding == ProRataRounding::LargestRemainder {
+                    for (&allocation, &size) in filled.iter().zip(&sizes) {
+                        let floor = quantity * size / total;
+                        assert!(allocation == floor || allocation == floor + 1, "{:?} {}", sizes, quantity);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rounding_rules_differ() {
+    // 份额 1.5 / 2.5 / 1.0（取整 1 / 2 / 1，剩余 1）：余数相同时两种规则都给最早的订单
+    let sizes = [6, 10, 4];
+    for (rounding, result) in [(ProRataRounding::TimePriority, vec![2, 2, 1]), (ProRataRounding::LargestRemainder, vec![2, 2, 1])] {
+        let mut book = book_with(MatchingAlgorithm::ProRata, rounding, &sizes);
+        assert_eq!(allocations(book.as_mut(), 3, 5), result);
+    }
+    // 份额 0.3 / 0.6 / 2.1（取整 0 / 0 / 2，剩余 1）：时间优先给队首，最大余数给余数 0.6 的订单
+    let sizes = [1, 2, 7];
+    for (rounding, result) in [(ProRataRounding::TimePriority, vec![1, 0, 2]), (ProRataRounding::LargestRemainder, vec![0, 1, 2])] {
+        let mut book = book_with(MatchingAlgorithm::ProRata, rounding, &sizes);
+        assert_eq!(allocations(book.as_mut(), 3, 3), result);
+    }
+    // FIFO+比例：队首之外的数量同样按取整规则分配
+    let mut book = book_with(MatchingAlgorithm::FifoProRata, ProRataRounding::LargestRemainder, &[2, 1, 2, 7]);
+    assert_eq!(allocations(book.as_mut(), 4, 5), vec![2, 0, 1, 2]);
+}
+
+#[test]
+fn test_allocation_reproducible_after_restore() {
+    let sizes = [3, 5, 7, 11];
+    let mut book = book_with(MatchingAlgorithm::ProRata, ProRataRounding::LargestRemainder, &sizes);
+    let bytes = bincode::serialize(&book.serialize_state()).unwrap();
+    let first = allocations(book.as_mut(), sizes.len(), 13);
+
+    let Ok(OrderBookState::Advanced(mut restored)) = bincode::deserialize::<OrderBookState>(&bytes) else {
+        panic!("恢复订单簿失败");
+    };
+    assert_eq!(restored.get_symbol_spec().pro_rata_rounding, ProRataRounding::LargestRemainder);
+    assert_eq!(allocations(&mut restored, sizes.len(), 13), first);
+}
@@ -0,0 +1,97 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{new_order_book, OrderBook};
+use std::collections::BTreeMap;
+
+fn spec(matching_algorithm: MatchingAlgorithm) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        matching_algorithm,
+        ..Default::default()
+    }
+}
+
+fn ask(order_id: OrderId, size: Size) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: order_id as UserId,
+        order_id,
+        symbol: 100,
+        price: 100,
+        reserve_price: 100,
+        size,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        timestamp: order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn book_with(algorithm: MatchingAlgorithm, sizes: &[Size]) -> Box<dyn OrderBook> {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(algorithm));
+    for (i, &size) in sizes.iter().enumerate() {
+        book.new_order(&mut ask(i as OrderId + 1, size));
+    }
+    book
+}
+
+/// taker 买入 size，返回各 maker 的成交量（按订单号）
+fn fills(book: &mut dyn OrderBook, size: Size) -> Vec<(OrderId, Size)> {
+    let mut taker = OrderCommand { uid: 99, order_id: 99, action: OrderAction::Bid, order_type: OrderType::Ioc, ..ask(99, size) };
+    book.new_order(&mut taker);
+    let mut by_order = BTreeMap::new();
+    for event in taker.matcher_events.iter().filter(|e| e.event_type == MatcherEventType::Trade) {
+        *by_order.entry(event.matched_order_id).or_insert(0) += event.size;
+    }
+    by_order.into_iter().collect()
+}
+
+#[test]
+fn test_pro_rata_allocation() {
+    // 按比例整除
+    let mut book = book_with(MatchingAlgorithm::ProRata, &[10, 30, 60]);
+    assert_eq!(fills(book.as_mut(), 50), vec![(1, 5), (2, 15), (3, 30)]);
+
+    // 向下取整后的剩余按队列顺序分配
+    let mut book = book_with(MatchingAlgorithm::ProRata, &[10, 30, 60]);
+    assert_eq!(fills(book.as_mut(), 7), vec![(1, 1), (2, 2), (3, 4)]);
+
+    // FIFO 不变
+    let mut book = book_with(MatchingAlgorithm::Fifo, &[10, 30, 60]);
+    assert_eq!(fills(book.as_mut(), 50), vec![(1, 10), (2, 30), (3, 10)]);
+}
+
+#[test]
+fn test_fifo_pro_rata_top_order_priority() {
+    let mut book = book_with(MatchingAlgorithm::FifoProRata, &[10, 30, 60]);
+    assert_eq!(fills(book.as_mut(), 20), vec![(1, 10), (2, 4), (3, 6)]);
+    // 队首订单成交完毕后，新的队首优先
+    assert_eq!(fills(book.as_mut(), 40), vec![(2, 26), (3, 14)]);
+    assert_eq!(book.get_total_ask_volume(), 40);
+}
+
+#[test]
+fn test_pro_rata_iceberg_rounds() {
+    // 冰山单每轮只按当前切片参与分配，刷新后参与下一轮，taker 不会在仍有挂单的价位上挂出
+    let mut book = new_order_book(OrderBookKind::Advanced, spec(MatchingAlgorithm::ProRata));
+    book.new_order(&mut OrderCommand { order_type: OrderType::Iceberg, visible_size: Some(2), ..ask(1, 20) });
+    book.new_order(&mut ask(2, 8));
+    assert_eq!(fills(book.as_mut(), 15), vec![(1, 7), (2, 8)]);
+    assert_eq!(book.get_total_ask_volume(), 13);
+
+    let mut taker = OrderCommand { uid: 99, order_id: 98, action: OrderAction::Bid, ..ask(98, 20) };
+    book.new_order(&mut taker);
+    assert_eq!((book.get_total_ask_volume(), book.get_total_bid_volume()), (0, 7));
+}
+
+#[test]
+fn test_algorithm_requires_advanced_book() {
+    let pro_rata = spec(MatchingAlgorithm::ProRata);
+    assert!(pro_rata.is_valid());
+    assert_eq!(pro_rata.order_book_kind(OrderBookKind::Direct), OrderBookKind::Advanced);
+    assert!(!CoreSymbolSpecification { order_book: Some(OrderBookKind::Direct), ..pro_rata.clone() }.is_valid());
+    assert!(CoreSymbolSpecification { order_book: Some(OrderBookKind::Advanced), ..pro_rata }.is_valid());
+    assert_eq!(spec(MatchingAlgorithm::Fifo).order_book_kind(OrderBookKind::Direct), OrderBookKind::Direct);
+}
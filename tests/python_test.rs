@@ -0,0 +1,94 @@
+#![cfg(feature = "python")]
+
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CStr;
+
+/// 在嵌入的解释器中导入模块并执行脚本（脚本内用 assert 检查）
+fn run(script: &CStr) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = pyo3::wrap_pymodule!(matching_core::python::matching_core)(py);
+        let globals = PyDict::new(py);
+        globals.set_item("mc", module).unwrap();
+        if let Err(e) = py.run(script, Some(&globals), None) {
+            e.print(py);
+            panic!("Python 脚本失败: {}", e);
+        }
+    });
+}
+
+#[test]
+fn test_order_book_from_records() {
+    run(c_str!(
+        r#"
+book = mc.OrderBook(symbol_id=100)
+records = [
+    {"uid": 1, "order_id": 1, "side": "sell", "price": 101, "size": 5},
+    {"uid": 1, "order_id": 2, "side": "sell", "price": 102, "size": 5},
+    {"uid": 2, "order_id": 3, "side": "bid", "price": 99, "size": 4},
+    {"uid": 2, "order_id": 4, "side": "buy", "price": 102, "size": 7, "order_type": "ioc", "timestamp": 10},
+]
+trades = book.place_orders(records)
+assert [(t["maker_order_id"], t["price"], t["size"]) for t in trades] == [(1, 101, 5), (2, 102, 2)], trades
+assert all(t["taker_order_id"] == 4 and t["side"] == "bid" for t in trades)
+
+l2 = book.l2(5)
+assert l2 == {"bid_prices": [99], "bid_volumes": [4], "ask_prices": [102], "ask_volumes": [3]}, l2
+assert book.best_bid_offer() == ((99, 4), (102, 3))
+
+assert book.cancel(3, 2)["result_code"] == "Success"
+assert book.cancel(3, 2)["result_code"] != "Success"
+result = book.move_order(2, 1, 98)
+assert result["result_code"] == "Success" and result["trades"] == []
+
+try:
+    book.place({"uid": 1, "order_id": 9, "side": "hold", "price": 1, "size": 1})
+    raise AssertionError("应拒绝无效方向")
+except ValueError:
+    pass
+try:
+    book.place({"uid": 1, "order_id": 9, "side": "bid", "size": 1})
+    raise AssertionError("应要求 price")
+except KeyError:
+    pass
+"#
+    ));
+}
+
+#[test]
+fn test_exchange_round_trip() {
+    run(c_str!(
+        r#"
+ex = mc.Exchange()
+assert ex.add_symbol(100, 2, 1, tick_size=1) == "Success"
+for uid, currency in [(1001, 1), (1002, 2)]:
+    assert ex.add_user(uid) == "Success"
+    assert ex.adjust_balance(uid, currency, 1_000_000, uid) == "Success"
+
+result = ex.place({"uid": 1002, "order_id": 1, "symbol": 100, "side": "ask", "price": 100, "size": 10})
+assert result["result_code"] == "Success" and result["trades"] == []
+trades = ex.place_orders([
+    {"uid": 1001, "order_id": 2, "symbol": 100, "side": "bid", "price": 100, "size": 4, "client_order_id": 7},
+    {"uid": 1001, "order_id": 3, "symbol": 100, "side": "bid", "price": 100, "size": 1},
+])
+assert [(t["taker_order_id"], t["maker_order_id"], t["size"]) for t in trades] == [(2, 1, 4), (3, 1, 1)], trades
+assert [t["trade_id"] for t in trades] == [1, 2]
+
+assert ex.l2(100)["ask_volumes"] == [5]
+assert ex.move_order(100, 1, 1002, 105)["result_code"] == "Success"
+assert ex.l2(100, depth=1)["ask_prices"] == [105]
+assert ex.cancel(100, 1, 1002)["result_code"] == "Success"
+assert ex.l2(100)["ask_prices"] == []
+
+# 风控拒绝不影响后续订单
+assert ex.place({"uid": 1001, "order_id": 4, "symbol": 100, "side": "bid", "price": 100, "size": 10**9})["result_code"] == "RiskNsf"
+try:
+    ex.add_symbol(200, 2, 1, unknown=1)
+    raise AssertionError("应拒绝未知参数")
+except KeyError:
+    pass
+"#
+    ));
+}
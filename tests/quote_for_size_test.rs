@@ -0,0 +1,83 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::new_order_book;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+
+const KINDS: [OrderBookKind; 5] = [
+    OrderBookKind::Naive,
+    OrderBookKind::Direct,
+    OrderBookKind::DirectOptimized,
+    OrderBookKind::Advanced,
+    OrderBookKind::DirectOptimizedLadder,
+];
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn quote(filled: Size, notional: i64, average_price: Price, worst_price: Option<Price>) -> SizeQuote {
+    SizeQuote { filled, notional, average_price, worst_price }
+}
+
+#[test]
+fn test_quote_walks_opposite_side() {
+    for kind in KINDS {
+        let mut book = new_order_book(kind, spec());
+        book.new_order(&mut order(1, 1, 100, 2, OrderAction::Ask));
+        book.new_order(&mut order(1, 2, 101, 3, OrderAction::Ask));
+        book.new_order(&mut order(1, 3, 99, 4, OrderAction::Bid));
+        book.new_order(&mut order(1, 4, 97, 1, OrderAction::Bid));
+        let before = book.get_l2_data(10);
+
+        // 买入 4：2@100 + 2@101，均价向上取整
+        assert_eq!(book.quote_for_size(OrderAction::Bid, 4), quote(4, 402, 101, Some(101)), "{:?}", kind);
+        // 卖出 5：4@99 + 1@97，均价向下取整
+        assert_eq!(book.quote_for_size(OrderAction::Ask, 5), quote(5, 493, 98, Some(97)), "{:?}", kind);
+        // 深度不足时返回实际可成交数量
+        assert_eq!(book.quote_for_size(OrderAction::Bid, 10), quote(5, 503, 101, Some(101)), "{:?}", kind);
+        assert_eq!(book.quote_for_size(OrderAction::Ask, 0), SizeQuote::default());
+        assert_eq!(book.get_l2_data(10), before, "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_quote_includes_hidden_iceberg_volume() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut OrderCommand {
+        order_type: OrderType::Iceberg,
+        visible_size: Some(1),
+        ..order(1, 1, 100, 5, OrderAction::Ask)
+    });
+    assert_eq!(book.quote_for_size(OrderAction::Bid, 3), quote(3, 300, 100, Some(100)));
+}
+
+#[test]
+fn test_router_quote() {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.add_symbol(spec());
+    assert_eq!(router.quote_for_size(100, OrderAction::Bid, 1), Some(SizeQuote::default()));
+    assert_eq!(router.quote_for_size(999, OrderAction::Bid, 1), None);
+}
@@ -0,0 +1,153 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::rate_limiter::RateLimitConfig;
+
+fn config(per_symbol: bool) -> ExchangeConfig {
+    ExchangeConfig {
+        ring_buffer_size: 1024,
+        rate_limit: Some(RateLimitConfig {
+            capacity: 3,
+            refill_interval: 100,
+            per_symbol,
+        }),
+        ..Default::default()
+    }
+}
+
+fn setup(config: ExchangeConfig) -> ExchangeCore {
+    let mut core = ExchangeCore::new(config);
+    for symbol_id in [100, 101] {
+        core.add_symbol(CoreSymbolSpecification {
+            symbol_id,
+            symbol_type: SymbolType::CurrencyExchangePair,
+            base_currency: 2,
+            quote_currency: 1,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+/// 开户与入金（不受限流影响）
+fn setup_users() -> Vec<OrderCommand> {
+    let mut commands = Vec::new();
+    for (uid, currency) in [(1001, 2), (1002, 2)] {
+        commands.push(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for i in 0..5 {
+            commands.push(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000,
+                order_id: uid as OrderId * 10 + i,
+                ..Default::default()
+            });
+        }
+    }
+    commands
+}
+
+fn ask(uid: UserId, order_id: OrderId, symbol: SymbolId, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price: 1000 + order_id as Price,
+        reserve_price: 1000 + order_id as Price,
+        size: 10,
+        action: OrderAction::Ask,
+        order_type: OrderType::Gtc,
+        timestamp,
+        ..Default::default()
+    }
+}
+
+/// 用户 1001 突发 4 笔后被限流，1002 不受影响；时间推进后补充令牌
+fn orders() -> Vec<OrderCommand> {
+    vec![
+        ask(1001, 1, 100, 1000),
+        ask(1001, 2, 100, 1000),
+        ask(1001, 3, 100, 1010),
+        ask(1001, 4, 100, 1050),
+        ask(1002, 5, 100, 1050),
+        ask(1001, 6, 100, 1099),
+        ask(1001, 7, 100, 1100),
+        ask(1001, 8, 100, 1150),
+        ask(1001, 9, 101, 1150),
+    ]
+}
+
+const EXPECTED: [bool; 9] = [true, true, true, false, true, false, true, false, false];
+
+fn accepted(results: &[OrderCommand]) -> Vec<bool> {
+    results.iter().map(|cmd| cmd.result_code != CommandResultCode::RateLimitExceeded).collect()
+}
+
+#[test]
+fn test_token_bucket_per_uid() {
+    let mut core = setup(config(false));
+    for cmd in setup_users() {
+        assert_eq!(core.submit_command(cmd).result_code, CommandResultCode::Success);
+    }
+    let results: Vec<OrderCommand> = orders().into_iter().map(|cmd| core.submit_command(cmd)).collect();
+    assert_eq!(accepted(&results), EXPECTED);
+
+    // 被限流的订单不冻结资金、不进入订单簿
+    assert!(results[3].matcher_events.is_empty());
+    core.verify_invariants().unwrap();
+    let book = core
+        .submit_command(OrderCommand {
+            command: OrderCommandType::OrderBookRequest,
+            symbol: 100,
+            size: 10,
+            ..Default::default()
+        })
+        .market_data
+        .unwrap();
+    assert_eq!(book.ask_prices, vec![1001, 1002, 1003, 1005, 1007]);
+
+    // 撤单同样受限
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1001,
+        order_id: 1,
+        symbol: 100,
+        timestamp: 1150,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::RateLimitExceeded);
+}
+
+#[test]
+fn test_per_symbol_buckets() {
+    let mut core = setup(config(true));
+    for cmd in setup_users() {
+        core.submit_command(cmd);
+    }
+    let results: Vec<OrderCommand> = orders().into_iter().map(|cmd| core.submit_command(cmd)).collect();
+    let mut expected = EXPECTED;
+    expected[8] = true; // 交易对 101 有独立的令牌桶
+    assert_eq!(accepted(&results), expected);
+}
+
+#[test]
+fn test_staged_pipeline_limits_same_commands() {
+    let mut core = setup(config(false));
+    core.startup();
+    for cmd in setup_users() {
+        core.submit_command(cmd);
+    }
+    let futures: Vec<_> = orders().into_iter().map(|cmd| core.submit_command_async(cmd)).collect();
+    let results: Vec<OrderCommand> = futures.into_iter().map(|future| future.wait()).collect();
+    assert_eq!(accepted(&results), EXPECTED);
+    for (cmd, accepted) in results.iter().zip(EXPECTED) {
+        assert_eq!(cmd.result_code == CommandResultCode::Success, accepted);
+    }
+}
@@ -0,0 +1,156 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::journal::Journaler;
+use std::path::PathBuf;
+
+fn create_symbol_spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+/// 每个测试使用独立的日志文件与快照目录
+fn paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("matching_core_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    (dir.join("exchange.wal"), dir.join("snapshots"))
+}
+
+fn new_core(journal: &PathBuf, snapshots: &PathBuf) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(create_symbol_spec(100));
+    core.enable_snapshotting(snapshots).unwrap();
+    core.enable_journaling(journal).unwrap();
+    core
+}
+
+fn add_user(core: &mut ExchangeCore, uid: UserId, currency: Currency, amount: i64) {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid,
+        ..Default::default()
+    });
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid,
+        symbol: currency,
+        price: amount,
+        order_id: uid as OrderId,
+        ..Default::default()
+    });
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+}
+
+fn order_book(core: &mut ExchangeCore) -> L2MarketData {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    })
+    .market_data
+    .unwrap()
+}
+
+#[test]
+fn test_journal_records_have_sequence_numbers() {
+    let (journal, _) = paths("journal_seq");
+
+    let mut journaler = Journaler::new(&journal).unwrap();
+    assert_eq!(journaler.last_seq(), 0);
+    for uid in 1..=3 {
+        let seq = journaler
+            .write_command(&OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(seq, uid);
+    }
+    drop(journaler);
+
+    // 重新打开后序列号连续
+    let mut journaler = Journaler::new(&journal).unwrap();
+    assert_eq!(journaler.last_seq(), 3);
+    assert_eq!(journaler.write_command(&OrderCommand::default()).unwrap(), 4);
+
+    let tail = Journaler::read_commands_after(&journal, 2).unwrap();
+    assert_eq!(tail.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(tail[0].1.uid, 3);
+    assert_eq!(Journaler::read_commands(&journal).unwrap().len(), 4);
+}
+
+#[test]
+fn test_recover_replays_journal_tail_after_snapshot() {
+    let (journal, snapshots) = paths("recover_tail");
+
+    let mut core = new_core(&journal, &snapshots);
+    add_user(&mut core, 1001, 2, 80);
+    add_user(&mut core, 1002, 1, 1_000_000);
+    place(&mut core, 1001, 1, 1000, 50, OrderAction::Ask);
+
+    let snapshot_seq = core.create_snapshot().unwrap();
+    assert_eq!(snapshot_seq, 5);
+
+    // 快照之后的命令只在日志中
+    place(&mut core, 1002, 2, 1000, 20, OrderAction::Bid);
+    place(&mut core, 1001, 3, 1010, 30, OrderAction::Ask);
+    let expected_book = order_book(&mut core);
+    let expected_seq = core.last_seq();
+    drop(core);
+
+    let mut recovered = new_core(&journal, &snapshots);
+    assert_eq!(recovered.recover().unwrap(), expected_seq);
+    assert_eq!(recovered.last_seq(), expected_seq);
+    assert_eq!(order_book(&mut recovered), expected_book);
+    recovered.verify_invariants().unwrap();
+
+    // 快照之前的入金没有被重复重放：1001 的 base 已全部挂出
+    let result = place(&mut recovered, 1001, 4, 1020, 1, OrderAction::Ask);
+    assert_eq!(result.result_code, CommandResultCode::RiskNsf);
+
+    // 恢复后继续写日志，序列号连续
+    assert_eq!(recovered.last_seq(), expected_seq + 2);
+    assert_eq!(Journaler::read_records(&journal).unwrap().last().unwrap().0, expected_seq + 2);
+}
+
+#[test]
+fn test_recover_without_snapshot_replays_full_journal() {
+    let (journal, snapshots) = paths("recover_full");
+
+    let mut core = new_core(&journal, &snapshots);
+    add_user(&mut core, 1001, 2, 1_000);
+    add_user(&mut core, 1002, 1, 1_000_000);
+    place(&mut core, 1001, 1, 1000, 50, OrderAction::Ask);
+    place(&mut core, 1002, 2, 1000, 20, OrderAction::Bid);
+    let expected_book = order_book(&mut core);
+    drop(core);
+
+    let mut recovered = new_core(&journal, &snapshots);
+    assert_eq!(recovered.recover().unwrap(), 7);
+    assert_eq!(order_book(&mut recovered), expected_book);
+    recovered.verify_invariants().unwrap();
+}
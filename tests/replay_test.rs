@@ -0,0 +1,203 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::replay::*;
+use std::io::Cursor;
+use std::time::Duration;
+
+const MAKER: UserId = 1;
+const TAKER: UserId = 2;
+
+fn setup(symbol_id: SymbolId) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    });
+    for uid in [MAKER, TAKER] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000_000,
+                order_id: uid * 10 + currency as u64,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn order_book(core: &mut ExchangeCore, symbol: SymbolId) -> L2MarketData {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol,
+        size: 10,
+        ..Default::default()
+    })
+    .market_data
+    .unwrap()
+}
+
+#[test]
+fn test_csv_feed_parsing() {
+    let csv = "ts,type,id,side,price,qty\n\
+               1,add,1,buy,100.25,10\n\
+               \n\
+               2,E,1,,,4\n\
+               3,fill,1,,100.5,1\n\
+               4,X,1,,,2\n\
+               5,D,1,,,\n";
+    let schema = CsvSchema { default_symbol: 7, price_decimals: 2, ..Default::default() };
+    let events: Vec<_> = CsvFeed::new(Cursor::new(csv), schema).collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![
+            FeedEvent::Add { timestamp: 1, symbol: 7, order_id: 1, action: OrderAction::Bid, price: 10025, size: 10 },
+            FeedEvent::Execute { timestamp: 2, order_id: 1, size: 4, price: None },
+            FeedEvent::Execute { timestamp: 3, order_id: 1, size: 1, price: Some(10050) },
+            FeedEvent::Reduce { timestamp: 4, order_id: 1, size: 2 },
+            FeedEvent::Cancel { timestamp: 5, order_id: 1 },
+        ]
+    );
+
+    // 自定义列顺序、分隔符与交易对列
+    let schema = CsvSchema {
+        delimiter: b';',
+        has_header: false,
+        timestamp: 5,
+        event: 0,
+        order_id: 1,
+        side: 2,
+        price: 3,
+        size: 4,
+        symbol: Some(6),
+        new_order_id: Some(7),
+        ..Default::default()
+    };
+    let events: Vec<_> = CsvFeed::new(Cursor::new("u;3;s;105;6;9;200;4\n"), schema).collect::<Result<_, _>>().unwrap();
+    assert_eq!(events, vec![FeedEvent::Replace { timestamp: 9, order_id: 3, new_order_id: 4, price: 105, size: 6 }]);
+
+    let err = CsvFeed::new(Cursor::new("h\n1,add,1,hold,1,1\n"), CsvSchema::default()).next().unwrap().unwrap_err();
+    assert!(matches!(err, ReplayError::Parse { line: 2, .. }), "{:?}", err);
+    let err = CsvFeed::new(Cursor::new("1,add,1,buy,1.5,1\n"), CsvSchema { has_header: false, ..Default::default() })
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(err, ReplayError::Parse { line: 1, .. }), "价格小数位超出配置: {:?}", err);
+}
+
+#[test]
+fn test_csv_replay_against_engine() {
+    let csv = "ts,type,id,side,price,qty\n\
+               1,add,1,sell,101,10\n\
+               2,add,2,sell,102,5\n\
+               3,add,3,buy,99,8\n\
+               4,execute,1,,,4\n\
+               5,reduce,3,,,3\n\
+               6,cancel,2,,,\n\
+               7,cancel,999,,,\n";
+    let mut core = setup(100);
+    let config = ReplayConfig { maker_uid: MAKER, taker_uid: TAKER, ..Default::default() };
+    let mut replayer = Replayer::new(config);
+    let stats = replayer
+        .run(CsvFeed::new(Cursor::new(csv), CsvSchema { default_symbol: 100, ..Default::default() }), &mut core)
+        .unwrap();
+    assert_eq!((stats.events, stats.commands, stats.skipped), (7, 6, 1));
+    assert_eq!(replayer.translator().resting_orders(), 2);
+
+    let book = order_book(&mut core, 100);
+    assert_eq!((book.ask_prices, book.ask_volumes), (vec![101], vec![6]));
+    assert_eq!((book.bid_prices, book.bid_volumes), (vec![99], vec![5]));
+    core.verify_invariants().unwrap();
+}
+
+/// ITCH 5.0 消息（含 2 字节长度前缀）
+fn itch(kind: u8, locate: u16, timestamp: u64, body: &[u8]) -> Vec<u8> {
+    let mut msg = vec![kind];
+    msg.extend_from_slice(&locate.to_be_bytes());
+    msg.extend_from_slice(&0u16.to_be_bytes());
+    msg.extend_from_slice(&timestamp.to_be_bytes()[2..]);
+    msg.extend_from_slice(body);
+    let mut framed = (msg.len() as u16).to_be_bytes().to_vec();
+    framed.extend(msg);
+    framed
+}
+
+fn itch_add(locate: u16, timestamp: u64, order_id: u64, side: u8, shares: u32, price: u32) -> Vec<u8> {
+    let mut body = order_id.to_be_bytes().to_vec();
+    body.push(side);
+    body.extend_from_slice(&shares.to_be_bytes());
+    body.extend_from_slice(b"TEST    ");
+    body.extend_from_slice(&price.to_be_bytes());
+    itch(b'A', locate, timestamp, &body)
+}
+
+#[test]
+fn test_itch_replay() {
+    let mut data = Vec::new();
+    data.extend(itch(b'S', 0, 1, b"O")); // 系统事件，跳过
+    data.extend(itch_add(100, 10, 1, b'S', 300, 1_000_000));
+    data.extend(itch_add(100, 11, 2, b'B', 200, 990_000));
+    data.extend(itch_add(5, 12, 3, b'B', 100, 500_000)); // 其它交易对
+    // E：order 1 成交 100
+    let mut body = 1u64.to_be_bytes().to_vec();
+    body.extend_from_slice(&100u32.to_be_bytes());
+    body.extend_from_slice(&1u64.to_be_bytes());
+    data.extend(itch(b'E', 100, 20, &body));
+    // X：order 2 部分撤销 50
+    let mut body = 2u64.to_be_bytes().to_vec();
+    body.extend_from_slice(&50u32.to_be_bytes());
+    data.extend(itch(b'X', 100, 21, &body));
+    // U：order 2 改为 order 4，价格 995000 数量 120
+    let mut body = 2u64.to_be_bytes().to_vec();
+    body.extend_from_slice(&4u64.to_be_bytes());
+    body.extend_from_slice(&120u32.to_be_bytes());
+    body.extend_from_slice(&995_000u32.to_be_bytes());
+    data.extend(itch(b'U', 100, 22, &body));
+    // D：删除其它交易对的 order 3（已被过滤，跳过）
+    data.extend(itch(b'D', 5, 23, &3u64.to_be_bytes()));
+
+    let feed = ItchFeed::new(Cursor::new(data.clone())).with_symbols(&[100]);
+    let events: Vec<_> = feed.collect::<Result<_, _>>().unwrap();
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[0], FeedEvent::Add { timestamp: 10, symbol: 100, order_id: 1, action: OrderAction::Ask, price: 1_000_000, size: 300 });
+    assert_eq!(events[2], FeedEvent::Execute { timestamp: 20, order_id: 1, size: 100, price: None });
+
+    let mut core = setup(100);
+    let mut replayer = Replayer::new(ReplayConfig { maker_uid: MAKER, taker_uid: TAKER, ..Default::default() });
+    let stats = replayer.run(ItchFeed::new(Cursor::new(data.clone())).with_symbols(&[100]), &mut core).unwrap();
+    assert_eq!((stats.events, stats.skipped), (6, 1));
+    let book = order_book(&mut core, 100);
+    assert_eq!((book.ask_prices, book.ask_volumes), (vec![1_000_000], vec![200]));
+    assert_eq!((book.bid_prices, book.bid_volumes), (vec![995_000], vec![120]));
+
+    // 截断的消息
+    data.truncate(data.len() - 3);
+    let result: Result<Vec<_>, _> = ItchFeed::new(Cursor::new(data)).collect();
+    assert!(matches!(result, Err(ReplayError::Truncated)));
+}
+
+#[test]
+fn test_paced_replay() {
+    let csv = "1000,add,1,sell,101,1\n1030,add,2,sell,102,1\n1060,cancel,1,,,\n";
+    let schema = CsvSchema { has_header: false, default_symbol: 100, ..Default::default() };
+    let mut core = setup(100);
+    let config = ReplayConfig {
+        maker_uid: MAKER,
+        taker_uid: TAKER,
+        speed: ReplaySpeed::Paced { tick: Duration::from_millis(1), multiplier: 2.0 },
+        ..Default::default()
+    };
+    let stats = Replayer::new(config).run(CsvFeed::new(Cursor::new(csv), schema), &mut core).unwrap();
+    // 行情跨度 60 个单位（60ms），两倍速约 30ms
+    assert!(stats.elapsed >= Duration::from_millis(30), "{:?}", stats.elapsed);
+    assert_eq!(stats.commands, 3);
+}
@@ -0,0 +1,166 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::replication::{self, Replica, ReplicatedCommand, ReplicationReceiver, ReplicationTransport};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+fn new_core() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 2,
+        maker_fee: 1,
+        ..Default::default()
+    });
+    core
+}
+
+fn setup_users(core: &mut ExchangeCore) {
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> CommandResultCode {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+    .result_code
+}
+
+fn order_book(core: &mut ExchangeCore) -> L2MarketData {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    })
+    .market_data
+    .unwrap()
+}
+
+/// 可控的传输：记录发送的命令，按需投递给副本
+#[derive(Clone, Default)]
+struct ManualLink {
+    queue: Arc<Mutex<VecDeque<ReplicatedCommand>>>,
+    broken: Arc<Mutex<bool>>,
+}
+
+impl ReplicationTransport for ManualLink {
+    fn send(&mut self, record: &ReplicatedCommand) -> anyhow::Result<()> {
+        if *self.broken.lock().unwrap() {
+            anyhow::bail!("链路中断");
+        }
+        self.queue.lock().unwrap().push_back(record.clone());
+        Ok(())
+    }
+}
+
+impl ReplicationReceiver for ManualLink {
+    fn try_recv(&mut self) -> anyhow::Result<Option<ReplicatedCommand>> {
+        Ok(self.queue.lock().unwrap().pop_front())
+    }
+}
+
+#[test]
+fn test_replica_follows_primary_and_takes_over() {
+    let mut primary = new_core();
+    let (transport, receiver) = replication::channel();
+    primary.add_replica(Box::new(transport));
+    let mut replica = Replica::new(new_core(), Box::new(receiver));
+
+    setup_users(&mut primary);
+    place(&mut primary, 1001, 1, 100, 10, OrderAction::Ask);
+    place(&mut primary, 1002, 2, 100, 4, OrderAction::Bid);
+    place(&mut primary, 1002, 3, 99, 5, OrderAction::Bid);
+
+    let primary_seq = primary.last_seq();
+    assert_eq!(primary.replication_status()[0].sent_seq, primary_seq);
+    assert_eq!(replica.status().lag(primary_seq), primary_seq);
+    assert_eq!(replica.poll().unwrap() as u64, primary_seq);
+    assert_eq!(replica.status().lag(primary_seq), 0);
+    assert_eq!(replica.status().received_seq, primary_seq);
+    replica.core().verify_invariants().unwrap();
+
+    // 主节点下线前发出的命令在提升时应用
+    place(&mut primary, 1001, 4, 101, 3, OrderAction::Ask);
+    let expected_book = order_book(&mut primary);
+    let expected_seq = primary.last_seq();
+    drop(primary);
+
+    let mut promoted = replica.promote().unwrap();
+    assert_eq!(promoted.last_seq(), expected_seq);
+    assert_eq!(order_book(&mut promoted), expected_book);
+
+    // 提升后继续接收新命令
+    assert_eq!(place(&mut promoted, 1002, 5, 101, 8, OrderAction::Bid), CommandResultCode::Success);
+    let book = order_book(&mut promoted);
+    assert_eq!((book.ask_prices, book.ask_volumes), (vec![101], vec![1]));
+    promoted.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_duplicates_skipped_and_gaps_rejected() {
+    let link = ManualLink::default();
+    let mut primary = new_core();
+    primary.add_replica(Box::new(link.clone()));
+    let mut replica = Replica::new(new_core(), Box::new(link.clone()));
+
+    setup_users(&mut primary);
+    let sent: Vec<ReplicatedCommand> = link.queue.lock().unwrap().iter().cloned().collect();
+    assert_eq!(sent.iter().map(|r| r.seq).collect::<Vec<_>>(), (1..=6).collect::<Vec<_>>());
+
+    // 重复投递的命令跳过
+    link.queue.lock().unwrap().extend(sent[..3].iter().cloned());
+    assert_eq!(replica.poll().unwrap(), 6);
+    assert_eq!(replica.status().applied_seq, 6);
+
+    // 丢失一条命令后报告序列号不连续
+    place(&mut primary, 1001, 1, 100, 10, OrderAction::Ask);
+    place(&mut primary, 1002, 2, 100, 4, OrderAction::Bid);
+    link.queue.lock().unwrap().pop_front();
+    assert!(replica.poll().is_err());
+    assert_eq!(replica.status(), replication::ReplicaStatus { applied_seq: 6, received_seq: 8 });
+
+    // 发送失败后停止向该副本复制
+    *link.broken.lock().unwrap() = true;
+    place(&mut primary, 1001, 3, 101, 1, OrderAction::Ask);
+    let status = &primary.replication_status()[0];
+    assert_eq!(status.sent_seq, 8);
+    assert!(status.error.is_some());
+    *link.broken.lock().unwrap() = false;
+    place(&mut primary, 1001, 4, 102, 1, OrderAction::Ask);
+    assert_eq!(primary.replication_status()[0].sent_seq, 8);
+}
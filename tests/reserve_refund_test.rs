@@ -0,0 +1,237 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{OrderBook, NaiveOrderBook, DirectOrderBook, DirectOrderBookOptimized, AdvancedOrderBook};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+
+fn create_symbol_spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 3,
+        maker_fee: 1,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn books() -> Vec<Box<dyn OrderBook>> {
+    vec![
+        Box::new(NaiveOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBook::new(create_symbol_spec())),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec())),
+        Box::new(AdvancedOrderBook::new(create_symbol_spec())),
+    ]
+}
+
+fn bid(uid: UserId, order_id: OrderId, price: Price, reserve_price: Price, size: Size, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        reserve_price,
+        size,
+        action: OrderAction::Bid,
+        order_type,
+        ..Default::default()
+    }
+}
+
+fn command(command: OrderCommandType, uid: UserId, order_id: OrderId, size: Size) -> OrderCommand {
+    OrderCommand {
+        command,
+        uid,
+        order_id,
+        symbol: 1,
+        size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_cancel_and_reduce_events_carry_reserve_price() {
+    for mut book in books() {
+        book.new_order(&mut bid(1, 1, 100, 110, 10, OrderType::Gtc));
+
+        let mut reduce = command(OrderCommandType::ReduceOrder, 1, 1, 4);
+        assert_eq!(book.reduce_order(&mut reduce), CommandResultCode::Success);
+        assert_eq!(reduce.matcher_events[0].event_type, MatcherEventType::Reduce);
+        assert_eq!(reduce.matcher_events[0].bidder_hold_price, 110);
+        assert_eq!(reduce.matcher_events[0].remaining_size, 6);
+
+        let mut cancel = command(OrderCommandType::CancelOrder, 1, 1, 0);
+        assert_eq!(book.cancel_order(&mut cancel), CommandResultCode::Success);
+        assert_eq!(cancel.matcher_events.len(), 1);
+        assert_eq!(cancel.matcher_events[0].bidder_hold_price, 110);
+        assert_eq!(cancel.matcher_events[0].remaining_size, 0);
+        assert_eq!(cancel.action, OrderAction::Bid);
+    }
+}
+
+#[test]
+fn test_ioc_remainder_reject_carries_reserve_price() {
+    for mut book in books() {
+        let mut ioc = bid(1, 1, 100, 110, 10, OrderType::Ioc);
+        book.new_order(&mut ioc);
+        let reject = ioc.matcher_events.last().expect("IOC 剩余部分应被拒绝");
+        assert_eq!(reject.event_type, MatcherEventType::Reject);
+        assert_eq!(reject.bidder_hold_price, 110);
+    }
+}
+
+const BOOK_KINDS: [OrderBookKind; 4] = [OrderBookKind::Naive, OrderBookKind::Direct, OrderBookKind::DirectOptimized, OrderBookKind::Advanced];
+
+/// 用户 1 入金 10_000 quote 的 R1 → ME → R2 流水线
+fn engines(kind: OrderBookKind) -> (RiskEngine, MatchingEngineRouter) {
+    let mut risk = RiskEngine::new(0, 1);
+    let mut matching = MatchingEngineRouter::new(0, 1);
+    matching.set_order_book_kind(kind);
+    risk.add_symbol(create_symbol_spec());
+    matching.add_symbol(create_symbol_spec());
+    submit(&mut risk, &mut matching, command(OrderCommandType::AddUser, 1, 0, 0));
+    submit(
+        &mut risk,
+        &mut matching,
+        OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: 1, price: 10_000, order_id: 1, ..Default::default() },
+    );
+    (risk, matching)
+}
+
+fn submit(risk: &mut RiskEngine, matching: &mut MatchingEngineRouter, mut cmd: OrderCommand) -> OrderCommand {
+    risk.pre_process(&mut cmd);
+    matching.process_order(&mut cmd);
+    risk.post_process(&mut cmd);
+    cmd
+}
+
+#[test]
+fn test_cancel_refunds_full_hold() {
+    for kind in BOOK_KINDS {
+        let (mut risk, mut matching) = engines(kind);
+        let mut submit = |cmd| submit(&mut risk, &mut matching, cmd);
+
+        assert_eq!(submit(bid(1, 1, 100, 110, 10, OrderType::Gtc)).result_code, CommandResultCode::Success);
+        assert_eq!(submit(command(OrderCommandType::ReduceOrder, 1, 1, 4)).result_code, CommandResultCode::Success);
+        assert_eq!(submit(command(OrderCommandType::CancelOrder, 1, 1, 0)).result_code, CommandResultCode::Success);
+
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000, "{:?}", kind);
+        assert!(risk.get_user(1).unwrap().open_orders.is_empty());
+    }
+}
+
+#[test]
+fn test_reduce_refunds_reduced_hold() {
+    for kind in BOOK_KINDS {
+        let (mut risk, mut matching) = engines(kind);
+        submit(&mut risk, &mut matching, bid(1, 1, 100, 110, 10, OrderType::Gtc));
+
+        // 减量按冻结价格与 taker 手续费返还：4 × (110 + 3)，挂单剩余 6
+        let reduce = submit(&mut risk, &mut matching, command(OrderCommandType::ReduceOrder, 1, 1, 4));
+        assert_eq!(reduce.result_code, CommandResultCode::Success);
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000 - 6 * 113, "{:?}", kind);
+        assert_eq!(risk.get_user(1).unwrap().open_orders[&1].remaining, 6);
+
+        // 减量超过剩余数量时按剩余数量移除订单
+        let reduce = submit(&mut risk, &mut matching, command(OrderCommandType::ReduceOrder, 1, 1, 15));
+        assert_eq!((reduce.matcher_events[0].size, reduce.matcher_events[0].remaining_size), (6, 0));
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000, "{:?}", kind);
+        assert!(risk.get_user(1).unwrap().open_orders.is_empty());
+        let cancel = submit(&mut risk, &mut matching, command(OrderCommandType::CancelOrder, 1, 1, 0));
+        assert_eq!(cancel.result_code, CommandResultCode::MatchingUnknownOrderId);
+    }
+}
+
+#[test]
+fn test_budget_bid_refunds_exact_budget_hold() {
+    for kind in BOOK_KINDS {
+        let (mut risk, mut matching) = engines(kind);
+        submit(&mut risk, &mut matching, command(OrderCommandType::AddUser, 2, 0, 0));
+        submit(&mut risk, &mut matching, OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 2, symbol: 2, price: 100, order_id: 2, ..Default::default() });
+        let ask = |order_id, price, size| OrderCommand { uid: 2, action: OrderAction::Ask, ..bid(2, order_id, price, price, size, OrderType::Gtc) };
+        submit(&mut risk, &mut matching, ask(1, 100, 5));
+        submit(&mut risk, &mut matching, ask(2, 110, 5));
+
+        // 预算单按总预算冻结，reserve_price 不限制成交价：8 手成交 5×100 + 3×110 = 830，手续费 8×3
+        let fok = submit(&mut risk, &mut matching, bid(1, 10, 1_000, 100, 8, OrderType::FokBudget));
+        if kind == OrderBookKind::Advanced {
+            // 高级订单簿不支持预算单，整体拒绝并返还预算冻结
+            assert_eq!(fok.result_code, CommandResultCode::MatchingUnsupportedCommand);
+            assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000);
+            assert!(risk.get_user(1).unwrap().open_orders.is_empty());
+            continue;
+        }
+        assert_eq!(fok.result_code, CommandResultCode::Success, "{:?}", kind);
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000 - 830 - 24, "{:?}", kind);
+        assert_eq!(risk.get_user(1).unwrap().accounts[&2], 8);
+
+        // 预算不足时整单拒绝，全额返还
+        let balance = risk.get_user(1).unwrap().accounts[&1];
+        submit(&mut risk, &mut matching, bid(1, 11, 200, 110, 2, OrderType::FokBudget));
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], balance, "{:?}", kind);
+
+        // IOC 预算单：250 只够 2×110，剩余 3 手拒绝
+        submit(&mut risk, &mut matching, bid(1, 12, 250, 0, 5, OrderType::IocBudget));
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], balance - 220 - 6, "{:?}", kind);
+        assert_eq!(risk.get_user(1).unwrap().accounts[&2], 10);
+        assert!(risk.get_user(1).unwrap().open_orders.is_empty());
+        assert_eq!(risk.get_user(2).unwrap().accounts[&1], 830 + 220 - 10);
+
+        // 余额不足以冻结总预算时拒绝
+        let nsf = submit(&mut risk, &mut matching, bid(1, 13, 100_000, 0, 1, OrderType::IocBudget));
+        assert_eq!(nsf.result_code, CommandResultCode::RiskNsf);
+    }
+}
+
+#[test]
+fn test_refunds_use_recorded_hold_after_fee_change() {
+    for kind in BOOK_KINDS {
+        let (mut risk, mut matching) = engines(kind);
+        submit(&mut risk, &mut matching, bid(1, 1, 100, 110, 10, OrderType::Gtc));
+        let record = risk.get_user(1).unwrap().open_orders[&1];
+        assert_eq!((record.currency, record.hold), (1, 10 * 113));
+
+        // 冻结记录随快照保存
+        let restored: RiskEngine = bincode::deserialize(&bincode::serialize(&risk).unwrap()).unwrap();
+        assert_eq!(restored.get_user(1).unwrap().open_orders[&1], record);
+
+        // 下单后调整 taker 费率：减量与撤单按记录的冻结返还，而不是按新费率重新计算
+        risk.add_symbol(CoreSymbolSpecification { taker_fee: 5, ..create_symbol_spec() });
+        submit(&mut risk, &mut matching, command(OrderCommandType::ReduceOrder, 1, 1, 4));
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000 - 6 * 113, "{:?}", kind);
+        assert_eq!(risk.get_user(1).unwrap().open_orders[&1].hold, 6 * 113);
+        submit(&mut risk, &mut matching, command(OrderCommandType::CancelOrder, 1, 1, 0));
+        assert_eq!(risk.get_user(1).unwrap().accounts[&1], 10_000, "{:?}", kind);
+    }
+}
+
+#[test]
+fn test_maker_fill_releases_recorded_hold() {
+    for kind in BOOK_KINDS {
+        let (mut risk, mut matching) = engines(kind);
+        submit(&mut risk, &mut matching, command(OrderCommandType::AddUser, 2, 0, 0));
+        submit(
+            &mut risk,
+            &mut matching,
+            OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 2, symbol: 2, price: 100, order_id: 2, ..Default::default() },
+        );
+        submit(&mut risk, &mut matching, bid(1, 1, 100, 110, 10, OrderType::Gtc));
+        risk.add_symbol(CoreSymbolSpecification { taker_fee: 5, ..create_symbol_spec() });
+
+        // 卖单分两次吃掉买单：买方按记录释放 10 × 113，支付 10 × 100 与 maker 手续费 10 × 1
+        for order_id in [2, 3] {
+            let ask = OrderCommand { uid: 2, action: OrderAction::Ask, ..bid(2, order_id, 100, 100, 5, OrderType::Ioc) };
+            assert_eq!(submit(&mut risk, &mut matching, ask).result_code, CommandResultCode::Success);
+        }
+        let buyer = risk.get_user(1).unwrap();
+        assert_eq!(buyer.accounts[&1], 10_000 - 1_000 - 10, "{:?}", kind);
+        assert_eq!(buyer.accounts[&2], 10);
+        assert!(buyer.open_orders.is_empty());
+        assert!(risk.get_user(2).unwrap().open_orders.is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::risk_engine::RiskLimits;
+
+fn setup(limits: RiskLimits) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        risk_limits: limits,
+        ..Default::default()
+    });
+    for symbol_id in [100, 101] {
+        core.add_symbol(CoreSymbolSpecification {
+            symbol_id,
+            symbol_type: SymbolType::CurrencyExchangePair,
+            base_currency: 2,
+            quote_currency: 1,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            ..Default::default()
+        });
+    }
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 10_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn ask(order_id: OrderId, symbol: SymbolId, price: Price) -> OrderCommand {
+    order(1001, order_id, symbol, price, 10, OrderAction::Ask, OrderType::Gtc)
+}
+
+#[test]
+fn test_order_size_and_notional_limits() {
+    let mut core = setup(RiskLimits {
+        max_order_size: 100,
+        max_order_notional: 50_000,
+        ..Default::default()
+    });
+    for (order_id, price, size, order_type, expected) in [
+        (1, 100, 101, OrderType::Gtc, CommandResultCode::RiskMaxOrderSizeExceeded),
+        (2, 600, 100, OrderType::Gtc, CommandResultCode::RiskMaxNotionalExceeded),
+        (3, 60_000, 10, OrderType::FokBudget, CommandResultCode::RiskMaxNotionalExceeded),
+        (4, 500, 100, OrderType::Gtc, CommandResultCode::Success),
+    ] {
+        let result = core.submit_command(order(1002, order_id, 100, price, size, OrderAction::Bid, order_type));
+        assert_eq!(result.result_code, expected, "order {}", order_id);
+    }
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_open_order_count_limits() {
+    let mut core = setup(RiskLimits {
+        max_open_orders_per_user: 3,
+        max_open_orders_per_symbol: 2,
+        ..Default::default()
+    });
+
+    assert_eq!(core.submit_command(ask(1, 100, 1000)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(ask(2, 100, 1010)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(ask(3, 100, 1020)).result_code, CommandResultCode::RiskSymbolOrderLimitExceeded);
+    assert_eq!(core.submit_command(ask(4, 101, 1000)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(ask(5, 101, 1010)).result_code, CommandResultCode::RiskUserOrderLimitExceeded);
+
+    // 撤单释放名额
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1001,
+        order_id: 4,
+        symbol: 101,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(ask(6, 101, 1010)).result_code, CommandResultCode::Success);
+
+    // 全部成交的挂单释放名额，部分成交的仍然占用
+    let taker = core.submit_command(order(1002, 7, 100, 1010, 15, OrderAction::Bid, OrderType::Gtc));
+    assert_eq!(taker.matcher_events.len(), 2);
+    assert_eq!(core.submit_command(ask(8, 101, 1020)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(ask(9, 101, 1030)).result_code, CommandResultCode::RiskUserOrderLimitExceeded);
+
+    // 未挂单的即时成交订单不占名额
+    for order_id in 10..15 {
+        let ioc = core.submit_command(order(1002, order_id, 101, 900, 1, OrderAction::Bid, OrderType::Ioc));
+        assert_eq!(ioc.result_code, CommandResultCode::Success);
+    }
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_limits_apply_to_in_flight_orders() {
+    let mut core = setup(RiskLimits {
+        max_open_orders_per_user: 3,
+        ..Default::default()
+    });
+    core.startup();
+
+    let futures: Vec<_> = (1..=5).map(|order_id| core.submit_command_async(ask(order_id, 100, 1000 + order_id as Price))).collect();
+    let codes: Vec<CommandResultCode> = futures.into_iter().map(|future| future.wait().result_code).collect();
+    assert_eq!(
+        codes,
+        vec![
+            CommandResultCode::Success,
+            CommandResultCode::Success,
+            CommandResultCode::Success,
+            CommandResultCode::RiskUserOrderLimitExceeded,
+            CommandResultCode::RiskUserOrderLimitExceeded,
+        ]
+    );
+}
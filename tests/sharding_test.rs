@@ -0,0 +1,132 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+
+const SYMBOLS: [SymbolId; 4] = [100, 101, 102, 103];
+
+fn setup(matching_engines_num: usize) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        matching_engines_num,
+        ..Default::default()
+    });
+    for symbol_id in SYMBOLS {
+        core.add_symbol(CoreSymbolSpecification {
+            symbol_id,
+            symbol_type: SymbolType::CurrencyExchangePair,
+            base_currency: 2,
+            quote_currency: 1,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            ..Default::default()
+        });
+    }
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_shard_for_symbol() {
+    assert_eq!(MatchingEngineRouter::shard_for_symbol(100, 1), 0);
+    assert_eq!(MatchingEngineRouter::shard_for_symbol(103, 1), 0);
+    assert_eq!(MatchingEngineRouter::shard_for_symbol(101, 2), 1);
+    assert_eq!(MatchingEngineRouter::shard_for_symbol(102, 4), 2);
+    assert_eq!(MatchingEngineRouter::shard_for_symbol(103, 4), 3);
+}
+
+#[test]
+fn test_each_shard_owns_only_its_symbols() {
+    let core = setup(4);
+    let state = core.serialize_state();
+    assert_eq!(state.pipeline_state.matching_engines.len(), 4);
+    for engine in &state.pipeline_state.matching_engines {
+        let symbols: Vec<SymbolId> = engine.order_books.keys().copied().collect();
+        assert_eq!(symbols, vec![SYMBOLS[engine.shard_id]]);
+    }
+}
+
+#[test]
+fn test_sharded_matching_equals_single_shard() {
+    let mut single = setup(1);
+    let mut sharded = setup(4);
+
+    let trades = |cmd: &OrderCommand| -> Vec<(OrderId, Price, Size)> {
+        cmd.matcher_events.iter().map(|e| (e.matched_order_id, e.price, e.size)).collect()
+    };
+
+    let mut order_id = 1;
+    for round in 0..10 {
+        for symbol in SYMBOLS {
+            let price = 1000 + (round * 3 + symbol as i64) % 7;
+            for (uid, action) in [(1001, OrderAction::Ask), (1002, OrderAction::Bid)] {
+                let expected = place(&mut single, uid, order_id, symbol, price + round % 2, 5 + round % 3, action);
+                let actual = place(&mut sharded, uid, order_id, symbol, price + round % 2, 5 + round % 3, action);
+                assert_eq!(actual.result_code, expected.result_code);
+                assert_eq!(trades(&actual), trades(&expected));
+                order_id += 1;
+            }
+        }
+    }
+
+    for symbol in SYMBOLS {
+        for command in [OrderCommandType::OrderBookRequest, OrderCommandType::UserOrdersRequest] {
+            let request = OrderCommand {
+                command,
+                uid: 1001,
+                symbol,
+                size: 10,
+                ..Default::default()
+            };
+            let expected = single.submit_command(request.clone());
+            let actual = sharded.submit_command(request);
+            assert_eq!(actual.result_code, CommandResultCode::Success);
+            assert_eq!(actual.market_data, expected.market_data);
+            assert_eq!(actual.open_orders, expected.open_orders);
+        }
+    }
+    sharded.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_unknown_symbol_rejected() {
+    let mut core = setup(4);
+    let result = place(&mut core, 1001, 1, 105, 1000, 1, OrderAction::Ask);
+    assert_eq!(result.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+
+    let result = core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 105,
+        size: 10,
+        ..Default::default()
+    });
+    assert_eq!(result.result_code, CommandResultCode::MatchingInvalidOrderBookId);
+}
@@ -0,0 +1,177 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::snapshot::{SnapshotCompression, SnapshotConfig, SnapshotStore};
+use std::path::PathBuf;
+
+fn paths(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("matching_core_snapshot_compression_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    (dir.join("exchange.wal"), dir.join("snapshots"))
+}
+
+fn new_core(journal: &PathBuf, snapshots: &PathBuf, config: SnapshotConfig) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    core.enable_snapshotting_with_config(snapshots, config).unwrap();
+    core.enable_journaling(journal).unwrap();
+    core
+}
+
+fn populate(core: &mut ExchangeCore, orders: u64) {
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    for order_id in 1..=orders {
+        let (uid, action, price) = match order_id % 2 {
+            0 => (1001, OrderAction::Ask, 2000 + order_id as Price),
+            _ => (1002, OrderAction::Bid, 1000 - order_id as Price),
+        };
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid,
+            order_id,
+            symbol: 100,
+            price,
+            reserve_price: price,
+            size: 10,
+            action,
+            order_type: OrderType::Gtc,
+            timestamp: 1000 + order_id as i64,
+            ..Default::default()
+        });
+    }
+}
+
+fn order_book(core: &mut ExchangeCore) -> L2MarketData {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 1000,
+        ..Default::default()
+    })
+    .market_data
+    .unwrap()
+}
+
+fn snapshot_files(snapshots: &PathBuf) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(snapshots)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_compressed_snapshot_round_trip() {
+    let mut sizes = Vec::new();
+    for (name, compression) in [("plain", SnapshotCompression::None), ("lz4", SnapshotCompression::Lz4)] {
+        let (journal, snapshots) = paths(name);
+        let config = SnapshotConfig { compression, ..Default::default() };
+        let mut core = new_core(&journal, &snapshots, config);
+        populate(&mut core, 200);
+        let seq_id = core.create_snapshot().unwrap();
+        let expected = order_book(&mut core);
+
+        let files = snapshot_files(&snapshots);
+        assert_eq!(files.len(), 1);
+        sizes.push(std::fs::metadata(snapshots.join(&files[0])).unwrap().len());
+
+        let mut restored = new_core(&journal, &snapshots, config);
+        assert!(restored.load_latest_snapshot().unwrap());
+        assert_eq!(restored.last_seq(), seq_id);
+        assert_eq!(order_book(&mut restored), expected);
+        restored.verify_invariants().unwrap();
+    }
+    assert!(sizes[1] < sizes[0], "{:?}", sizes);
+}
+
+#[test]
+fn test_async_snapshot_while_running() {
+    let (journal, snapshots) = paths("async");
+    let config = SnapshotConfig {
+        compression: SnapshotCompression::Lz4,
+        async_write: true,
+    };
+    let mut core = new_core(&journal, &snapshots, config);
+    core.startup();
+    populate(&mut core, 20);
+    // 处理线程只序列化状态，结果返回时快照可能尚未落盘
+    let seq_id = core.last_seq() + 1;
+    let persisted = core
+        .submit_command_async(OrderCommand {
+            command: OrderCommandType::PersistStateMatching,
+            order_id: seq_id,
+            ..Default::default()
+        })
+        .wait();
+    assert_eq!(persisted.result_code, CommandResultCode::Success);
+    populate(&mut core, 0);
+
+    // 等待后台写入完成后快照可见，且没有残留的临时文件
+    core.flush_snapshots().unwrap();
+    assert_eq!(SnapshotStore::new(&snapshots).unwrap().get_latest_seq_id().unwrap(), Some(seq_id));
+    assert_eq!(snapshot_files(&snapshots), vec![format!("snapshot_{}.bin.lz4", seq_id)]);
+    drop(core);
+
+    let mut recovered = new_core(&journal, &snapshots, config);
+    assert_eq!(recovered.recover().unwrap(), seq_id + 4);
+    assert_eq!(order_book(&mut recovered).ask_prices.len(), 10);
+    recovered.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_store_reads_mixed_formats() {
+    let (journal, snapshots) = paths("mixed");
+    let mut core = new_core(&journal, &snapshots, SnapshotConfig::default());
+    populate(&mut core, 4);
+    let plain = core.create_snapshot().unwrap();
+
+    let async_store = SnapshotStore::with_config(
+        &snapshots,
+        SnapshotConfig {
+            compression: SnapshotCompression::Lz4,
+            async_write: true,
+        },
+    )
+    .unwrap();
+    let state = core.serialize_state();
+    async_store.save_snapshot(&state, plain + 1).unwrap();
+    async_store.flush().unwrap();
+    std::fs::write(snapshots.join(format!("snapshot_{}.bin.tmp", plain + 2)), b"partial").unwrap();
+
+    let store = SnapshotStore::new(&snapshots).unwrap();
+    assert_eq!(store.get_latest_seq_id().unwrap(), Some(plain + 1));
+    assert_eq!(store.load_snapshot(plain).unwrap().seq_id, plain);
+    assert_eq!(store.load_snapshot(plain + 1).unwrap().seq_id, plain);
+
+    // 后台写入失败在 flush 时报告
+    std::fs::remove_dir_all(&snapshots).unwrap();
+    async_store.save_snapshot(&state, plain + 3).unwrap();
+    assert!(async_store.flush().is_err());
+    assert!(async_store.flush().is_ok());
+}
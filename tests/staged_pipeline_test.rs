@@ -0,0 +1,258 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore, WaitStrategyType};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const SYMBOLS: [SymbolId; 2] = [100, 101];
+const USERS: [UserId; 4] = [1001, 1002, 1003, 1004];
+
+type Outcome = (OrderCommandType, OrderId, CommandResultCode, Vec<(OrderId, Price, Size)>);
+
+fn config() -> ExchangeConfig {
+    ExchangeConfig {
+        ring_buffer_size: 1024,
+        matching_engines_num: 2,
+        risk_engines_num: 2,
+        ..Default::default()
+    }
+}
+
+/// 创建核心并注册结果收集器
+fn new_core(config: ExchangeConfig) -> (ExchangeCore, Arc<Mutex<Vec<Outcome>>>) {
+    let mut core = ExchangeCore::new(config);
+    for symbol_id in SYMBOLS {
+        core.add_symbol(CoreSymbolSpecification {
+            symbol_id,
+            symbol_type: SymbolType::CurrencyExchangePair,
+            base_currency: 2,
+            quote_currency: 1,
+            base_scale_k: 1,
+            quote_scale_k: 1,
+            taker_fee: 1,
+            ..Default::default()
+        });
+    }
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+    let sink = outcomes.clone();
+    core.set_result_consumer(Arc::new(move |cmd: &OrderCommand| {
+        let trades = cmd
+            .matcher_events
+            .iter()
+            .filter(|e| e.event_type == MatcherEventType::Trade)
+            .map(|e| (e.matched_order_id, e.price, e.size))
+            .collect();
+        sink.lock().unwrap().push((cmd.command, cmd.order_id, cmd.result_code, trades));
+    }));
+    (core, outcomes)
+}
+
+fn commands() -> Vec<OrderCommand> {
+    let mut commands = Vec::new();
+    for uid in USERS {
+        commands.push(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            commands.push(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 100_000_000,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+
+    let mut order_id = 1;
+    for round in 0..50_i64 {
+        for symbol in SYMBOLS {
+            for (i, uid) in USERS.into_iter().enumerate() {
+                let action = if (round + i as i64) % 2 == 0 { OrderAction::Ask } else { OrderAction::Bid };
+                let price = 1000 + (round * 7 + i as i64 * 3) % 11;
+                commands.push(OrderCommand {
+                    command: OrderCommandType::PlaceOrder,
+                    uid,
+                    order_id,
+                    symbol,
+                    price,
+                    reserve_price: price,
+                    size: 1 + (round + i as i64) % 5,
+                    action,
+                    order_type: OrderType::Gtc,
+                    timestamp: order_id as i64,
+                    ..Default::default()
+                });
+                if round % 5 == 4 {
+                    commands.push(OrderCommand {
+                        command: OrderCommandType::CancelOrder,
+                        uid,
+                        order_id: order_id - 8,
+                        symbol,
+                        ..Default::default()
+                    });
+                }
+                order_id += 1;
+            }
+        }
+    }
+    commands
+}
+
+fn wait_for(outcomes: &Arc<Mutex<Vec<Outcome>>>, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while outcomes.lock().unwrap().len() < count {
+        assert!(Instant::now() < deadline, "等待处理结果超时");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_staged_pipeline_matches_synchronous_processing() {
+    let (mut sync_core, expected) = new_core(config());
+    let (mut staged_core, actual) = new_core(config());
+    staged_core.startup();
+
+    let commands = commands();
+    for cmd in &commands {
+        sync_core.submit_command(cmd.clone());
+        staged_core.submit_command(cmd.clone());
+    }
+    wait_for(&actual, commands.len());
+    drop(staged_core);
+
+    let expected = expected.lock().unwrap();
+    let actual = actual.lock().unwrap();
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+        assert_eq!(actual, expected);
+    }
+    assert!(actual.iter().any(|(_, _, _, trades)| !trades.is_empty()));
+}
+
+#[test]
+fn test_wait_strategies_and_core_pinning() {
+    let commands = commands();
+    let (mut sync_core, expected) = new_core(config());
+    for cmd in &commands {
+        sync_core.submit_command(cmd.clone());
+    }
+
+    for wait_strategy in [
+        WaitStrategyType::BusySpin,
+        WaitStrategyType::Yielding,
+        WaitStrategyType::Blocking,
+        WaitStrategyType::Sleeping,
+        WaitStrategyType::Adaptive,
+    ] {
+        let (mut core, actual) = new_core(ExchangeConfig {
+            wait_strategy,
+            consumer_cores: vec![0, 0],
+            ..config()
+        });
+        core.startup();
+        for cmd in &commands {
+            core.submit_command(cmd.clone());
+        }
+        wait_for(&actual, commands.len());
+        drop(core);
+        assert!(*actual.lock().unwrap() == *expected.lock().unwrap(), "{:?}", wait_strategy);
+    }
+}
+
+#[test]
+fn test_market_data_published_from_matching_stage() {
+    let run = |startup: bool| {
+        let (mut core, outcomes) = new_core(config());
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+        let sink = ticks.clone();
+        core.add_market_data_consumer(Arc::new(move |event: &MarketDataEvent| {
+            if let MarketDataEvent::Trade(tick) = event {
+                sink.lock().unwrap().push((tick.symbol, tick.taker_order_id, tick.maker_order_id, tick.size));
+            }
+        }));
+        if startup {
+            core.startup();
+        }
+        let commands = commands();
+        for cmd in &commands {
+            core.submit_command(cmd.clone());
+        }
+        wait_for(&outcomes, commands.len());
+        drop(core);
+        let mut ticks = ticks.lock().unwrap().clone();
+        // 不同分片的行情相对顺序不确定
+        ticks.sort_unstable();
+        ticks
+    };
+
+    let expected = run(false);
+    assert!(!expected.is_empty());
+    assert_eq!(run(true), expected);
+}
+
+fn snapshot_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("matching_core_staged_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_snapshot_across_shards_while_running() {
+    let snapshots = snapshot_dir("snapshot");
+    let commands = commands();
+    let half = commands.len() / 2;
+
+    let (mut core, outcomes) = new_core(config());
+    core.enable_snapshotting(&snapshots).unwrap();
+    core.startup();
+    for cmd in &commands[..half] {
+        core.submit_command(cmd.clone());
+    }
+    let seq_id = core.create_snapshot().unwrap();
+    for cmd in &commands[half..] {
+        core.submit_command(cmd.clone());
+    }
+    wait_for(&outcomes, commands.len() + 1);
+    drop(core);
+    assert_eq!(outcomes.lock().unwrap()[half].2, CommandResultCode::Success);
+
+    // 快照等价于同步处理前半部分命令后的状态
+    let (mut expected, _) = new_core(config());
+    for cmd in &commands[..half] {
+        expected.submit_command(cmd.clone());
+    }
+    let (mut restored, _) = new_core(config());
+    restored.enable_snapshotting(&snapshots).unwrap();
+    assert!(restored.load_latest_snapshot().unwrap());
+    assert_eq!(restored.last_seq(), seq_id);
+    restored.verify_invariants().unwrap();
+
+    for symbol in SYMBOLS {
+        let request = OrderCommand {
+            command: OrderCommandType::OrderBookRequest,
+            symbol,
+            size: 20,
+            ..Default::default()
+        };
+        assert_eq!(
+            restored.submit_command(request.clone()).market_data,
+            expected.submit_command(request).market_data
+        );
+    }
+    for uid in USERS {
+        let request = OrderCommand {
+            command: OrderCommandType::UserOrdersRequest,
+            uid,
+            symbol: SYMBOLS[1],
+            ..Default::default()
+        };
+        assert_eq!(
+            restored.submit_command(request.clone()).open_orders,
+            expected.submit_command(request).open_orders
+        );
+    }
+}
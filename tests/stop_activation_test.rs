@@ -0,0 +1,240 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+use std::sync::{Arc, Mutex};
+
+const BASE: Currency = 2;
+const QUOTE: Currency = 1;
+const INITIAL: i64 = 100_000;
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: BASE,
+        quote_currency: QUOTE,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        order_book: Some(OrderBookKind::Advanced),
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn stop(uid: UserId, order_id: OrderId, order_type: OrderType, price: Price, stop_price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        order_type,
+        stop_price: Some(stop_price),
+        ..order(uid, order_id, price, size, action)
+    }
+}
+
+/// 成交事件的 taker 用户（止损单激活的成交归属被激活订单的用户）
+fn trade_takers(cmd: &OrderCommand) -> Vec<(UserId, Price, Size)> {
+    cmd.matcher_events
+        .iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| (e.taker(cmd).0, e.price, e.size))
+        .collect()
+}
+
+#[test]
+fn test_stop_triggered_inside_sweep() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    for (i, price) in [100, 99, 98].into_iter().enumerate() {
+        book.new_order(&mut order(1, 1 + i as OrderId, price, 1, OrderAction::Bid));
+    }
+    book.new_order(&mut stop(2, 10, OrderType::StopLimit, 97, 99, 1, OrderAction::Ask));
+    assert_eq!(book.get_total_ask_volume(), 0);
+
+    // 卖单以 95 扫过 100、99：按实际成交价触发卖止损，激活成交追加在本次命令之后
+    let mut sweep = order(3, 20, 95, 2, OrderAction::Ask);
+    book.new_order(&mut sweep);
+    assert_eq!(trade_takers(&sweep), vec![(3, 100, 1), (3, 99, 1), (2, 98, 1)]);
+    assert_eq!(book.get_total_bid_volume(), 0);
+    assert_eq!(book.get_total_ask_volume(), 0);
+    assert!(book.get_open_order(10).is_none());
+}
+
+#[test]
+fn test_stop_triggered_by_resting_quote() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut order(1, 1, 110, 5, OrderAction::Ask));
+    book.new_order(&mut stop(2, 10, OrderType::StopLimit, 110, 105, 2, OrderAction::Bid));
+
+    // 未成交的挂单把买一推到触发价之上，止损单随即激活并吃掉卖单
+    let mut bid = order(3, 2, 104, 1, OrderAction::Bid);
+    book.new_order(&mut bid);
+    assert!(bid.matcher_events.is_empty());
+    let mut bid = OrderCommand { reserve_price: 110, ..order(3, 3, 106, 1, OrderAction::Bid) };
+    book.new_order(&mut bid);
+    assert_eq!(trade_takers(&bid), vec![(2, 110, 2)]);
+    assert_eq!(bid.matcher_events[0].taker(&bid), (2, 10, OrderAction::Bid));
+    assert_eq!(book.get_level_volume(OrderAction::Ask, 110), 3);
+
+    // 改价使卖一降到卖止损触发价，止损单激活并卖给买一
+    book.new_order(&mut stop(2, 11, OrderType::StopLimit, 100, 109, 1, OrderAction::Ask));
+    let mut move_cmd = OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1,
+        order_id: 1,
+        symbol: 100,
+        price: 109,
+        ..Default::default()
+    };
+    assert_eq!(book.move_order(&mut move_cmd), CommandResultCode::Success);
+    assert_eq!(trade_takers(&move_cmd), vec![(2, 106, 1)]);
+    assert_eq!(book.get_level_volume(OrderAction::Bid, 106), 0);
+}
+
+struct Exchange {
+    risk: RiskEngine,
+    matching: MatchingEngineRouter,
+}
+
+impl Exchange {
+    fn new() -> Self {
+        let mut exchange = Self {
+            risk: RiskEngine::new(0, 1),
+            matching: MatchingEngineRouter::new(0, 1),
+        };
+        exchange.risk.add_symbol(spec());
+        exchange.matching.add_symbol(spec());
+        for uid in [1, 2, 3] {
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::AddUser,
+                uid,
+                ..Default::default()
+            });
+            for currency in [BASE, QUOTE] {
+                exchange.submit(OrderCommand {
+                    command: OrderCommandType::BalanceAdjustment,
+                    uid,
+                    symbol: currency,
+                    price: INITIAL,
+                    order_id: uid as OrderId * 10 + currency as OrderId,
+                    ..Default::default()
+                });
+            }
+        }
+        exchange
+    }
+
+    fn submit(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        self.risk.pre_process(&mut cmd);
+        self.matching.process_order(&mut cmd);
+        self.risk.post_process(&mut cmd);
+        cmd
+    }
+
+    fn balance(&self, uid: UserId, currency: Currency) -> i64 {
+        self.risk.get_user(uid).unwrap().accounts.get(&currency).copied().unwrap_or(0)
+    }
+}
+
+#[test]
+fn test_activation_fills_settled_for_stop_owner() {
+    let mut exchange = Exchange::new();
+    exchange.submit(order(1, 1, 100, 5, OrderAction::Bid));
+    exchange.submit(order(1, 2, 99, 5, OrderAction::Bid));
+    let placed = exchange.submit(stop(2, 10, OrderType::StopMarket, 0, 100, 6, OrderAction::Ask));
+    assert_eq!(placed.result_code, CommandResultCode::Success);
+
+    // 1 手成交触发止损市价卖单：4 手成交于 100，2 手成交于 99
+    let trigger = exchange.submit(order(3, 20, 100, 1, OrderAction::Ask));
+    assert_eq!(trigger.result_code, CommandResultCode::Success);
+    assert_eq!(trade_takers(&trigger), vec![(3, 100, 1), (2, 100, 4), (2, 99, 2)]);
+
+    assert_eq!(exchange.balance(2, BASE), INITIAL - 6);
+    assert_eq!(exchange.balance(2, QUOTE), INITIAL + 4 * 100 + 2 * 99);
+    assert_eq!(exchange.balance(3, QUOTE), INITIAL + 100);
+    assert_eq!(exchange.balance(1, BASE), INITIAL + 7);
+    assert!(exchange.risk.get_user(2).unwrap().open_orders.is_empty());
+}
+
+#[test]
+fn test_activation_through_exchange_core() {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    core.add_market_data_consumer(Arc::new(move |event| {
+        sink.lock().unwrap().push(event.clone());
+    }));
+    core.add_symbol(spec());
+    for uid in [1, 2, 3] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [BASE, QUOTE] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: INITIAL,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+
+    core.submit_command(order(1, 1, 100, 2, OrderAction::Bid));
+    core.submit_command(stop(2, 10, OrderType::StopLimit, 95, 100, 5, OrderAction::Ask));
+    received.lock().unwrap().clear();
+
+    // 止损限价卖单成交 1 手后剩余 4 手以 95 挂出
+    core.submit_command(order(3, 20, 100, 1, OrderAction::Ask));
+    let events = std::mem::take(&mut *received.lock().unwrap());
+    let ticks: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            MarketDataEvent::Trade(tick) => Some((tick.taker_uid, tick.taker_order_id, tick.price, tick.size)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ticks, vec![(3, 20, 100, 1), (2, 10, 100, 1)]);
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, MarketDataEvent::L2(d) if d.action == OrderAction::Ask && d.price == 95 && d.volume == 4)));
+
+    let open = core.submit_command(OrderCommand {
+        command: OrderCommandType::UserOrdersRequest,
+        uid: 2,
+        symbol: 100,
+        ..Default::default()
+    });
+    assert_eq!(open.open_orders.iter().map(|o| (o.order_id, o.price)).collect::<Vec<_>>(), vec![(10, 95)]);
+    core.verify_invariants().unwrap();
+
+    // 激活后的挂单可以正常撤销并返还剩余冻结
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 2,
+        order_id: 10,
+        symbol: 100,
+        ..Default::default()
+    });
+    assert_eq!(cancel.result_code, CommandResultCode::Success);
+    core.verify_invariants().unwrap();
+}
@@ -0,0 +1,163 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::new_order_book;
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn stop(uid: UserId, order_id: OrderId, price: Price, stop_price: Price, action: OrderAction, source: StopTriggerSource) -> OrderCommand {
+    OrderCommand {
+        order_type: OrderType::StopLimit,
+        stop_price: Some(stop_price),
+        stop_trigger: source,
+        ..order(uid, order_id, price, 1, action)
+    }
+}
+
+#[test]
+fn test_stop_triggers_on_own_price_source() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut order(2, 1, 120, 10, OrderAction::Ask));
+    book.new_order(&mut stop(1, 10, 120, 110, OrderAction::Bid, StopTriggerSource::MarkPrice));
+    book.new_order(&mut stop(1, 11, 120, 110, OrderAction::Bid, StopTriggerSource::LastPrice));
+
+    // 成交价 115 只触发以最新成交价为来源的止损单
+    book.new_order(&mut order(3, 2, 115, 1, OrderAction::Ask));
+    book.new_order(&mut order(4, 3, 115, 1, OrderAction::Bid));
+    assert_eq!(book.get_level_volume(OrderAction::Ask, 120), 9);
+    assert!(book.get_open_order(10).is_some());
+    assert!(book.get_open_order(11).is_none());
+
+    // 指数价与未达到触发价的标记价都不会触发
+    let mut cmd = OrderCommand { symbol: 100, ..Default::default() };
+    book.update_reference_price(StopTriggerSource::IndexPrice, 130, &mut cmd);
+    book.update_reference_price(StopTriggerSource::MarkPrice, 105, &mut cmd);
+    assert_eq!(book.get_level_volume(OrderAction::Ask, 120), 9);
+
+    book.update_reference_price(StopTriggerSource::MarkPrice, 110, &mut cmd);
+    assert_eq!(book.get_level_volume(OrderAction::Ask, 120), 8);
+    assert!(book.get_open_order(10).is_none());
+}
+
+#[test]
+fn test_sell_stop_triggers_on_index_price() {
+    let mut book = new_order_book(OrderBookKind::Advanced, spec());
+    book.new_order(&mut stop(1, 10, 90, 95, OrderAction::Ask, StopTriggerSource::IndexPrice));
+
+    let mut cmd = OrderCommand { symbol: 100, ..Default::default() };
+    book.update_reference_price(StopTriggerSource::MarkPrice, 90, &mut cmd);
+    book.update_reference_price(StopTriggerSource::IndexPrice, 96, &mut cmd);
+    assert_eq!(book.get_total_ask_volume(), 0);
+
+    // 指数价跌破触发价：止损限价卖单以 90 挂出
+    book.update_reference_price(StopTriggerSource::IndexPrice, 95, &mut cmd);
+    assert_eq!(book.get_l2_data(1).ask_prices, vec![90]);
+    assert_eq!(book.get_open_order(10).map(|o| o.price), Some(90));
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        order_book_kind: OrderBookKind::Advanced,
+        ..Default::default()
+    });
+    core.add_symbol(spec());
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid: 1001,
+        ..Default::default()
+    });
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid: 1001,
+        symbol: 1,
+        price: 1_000_000,
+        order_id: 1,
+        ..Default::default()
+    });
+    core
+}
+
+fn best_bid(core: &mut ExchangeCore) -> Option<Price> {
+    let result = core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 1,
+        ..Default::default()
+    });
+    result.market_data.unwrap().bid_prices.first().copied()
+}
+
+#[test]
+fn test_reference_price_update_command() {
+    let mut core = setup();
+    let placed = core.submit_command(stop(1001, 1, 100, 105, OrderAction::Bid, StopTriggerSource::MarkPrice));
+    assert_eq!(placed.result_code, CommandResultCode::Success);
+
+    let update = |source, price| OrderCommand::reference_price_update(100, source, price, 2000);
+    let invalid = [
+        (update(StopTriggerSource::LastPrice, 105), CommandResultCode::ValidationInvalidTriggerSource),
+        (OrderCommand { service_flags: 7, ..update(StopTriggerSource::MarkPrice, 105) }, CommandResultCode::ValidationInvalidTriggerSource),
+        (update(StopTriggerSource::MarkPrice, 0), CommandResultCode::ValidationInvalidPrice),
+        (OrderCommand { symbol: 999, ..update(StopTriggerSource::MarkPrice, 105) }, CommandResultCode::InvalidSymbol),
+    ];
+    for (i, (cmd, expected)) in invalid.into_iter().enumerate() {
+        assert_eq!(core.submit_command(cmd).result_code, expected, "case {}", i);
+    }
+    assert_eq!(best_bid(&mut core), None);
+
+    // 停牌期间不激活止损单
+    core.submit_command(OrderCommand::set_session_state(100, TradingSessionState::Halted));
+    let halted = core.submit_command(update(StopTriggerSource::MarkPrice, 105));
+    assert_eq!(halted.result_code, CommandResultCode::MatchingSessionRejected);
+    assert_eq!(best_bid(&mut core), None);
+
+    core.submit_command(OrderCommand::set_session_state(100, TradingSessionState::ContinuousTrading));
+    assert_eq!(core.submit_command(update(StopTriggerSource::IndexPrice, 105)).result_code, CommandResultCode::Success);
+    assert_eq!(best_bid(&mut core), None);
+    assert_eq!(core.submit_command(update(StopTriggerSource::MarkPrice, 105)).result_code, CommandResultCode::Success);
+    assert_eq!(best_bid(&mut core), Some(100));
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_reference_prices_query_survives_snapshot() {
+    let mut core = setup();
+    assert!(core.reference_prices().unwrap().is_empty());
+    core.submit_command(OrderCommand::reference_price_update(100, StopTriggerSource::MarkPrice, 105, 2000));
+    core.submit_command(OrderCommand::reference_price_update(100, StopTriggerSource::IndexPrice, 99, 2000));
+    let expected = vec![ReferencePrices { symbol: 100, mark_price: Some(105), index_price: Some(99) }];
+    assert_eq!(core.reference_prices().unwrap(), expected);
+
+    // 快照恢复后参考价格保留，订单簿仍按恢复的标记价判断止损单
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    assert_eq!(restored.reference_prices().unwrap(), expected);
+    let placed = restored.submit_command(stop(1001, 1, 100, 105, OrderAction::Bid, StopTriggerSource::MarkPrice));
+    assert_eq!(placed.result_code, CommandResultCode::Success);
+    assert_eq!(best_bid(&mut restored), Some(100));
+}
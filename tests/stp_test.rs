@@ -0,0 +1,181 @@
+use matching_core::api::*;
+use matching_core::core::orderbook::{new_order_book, OrderBook, AdvancedOrderBook, DirectOrderBookOptimized};
+
+fn create_symbol_spec(stp_mode: StpMode) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 1,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 0,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        stp_mode,
+        ..Default::default()
+    }
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 1,
+        price,
+        size,
+        action,
+        order_type,
+        reserve_price: price,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn books(stp_mode: StpMode) -> Vec<Box<dyn OrderBook>> {
+    vec![
+        Box::new(AdvancedOrderBook::new(create_symbol_spec(stp_mode))),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec(stp_mode))),
+    ]
+}
+
+fn trade_size(cmd: &OrderCommand) -> Size {
+    cmd.matcher_events.iter()
+        .filter(|e| e.event_type == MatcherEventType::Trade)
+        .map(|e| e.size)
+        .sum()
+}
+
+#[test]
+fn test_stp_none_allows_self_trade() {
+    for mut book in books(StpMode::None) {
+        book.new_order(&mut order(1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc));
+        let mut bid = order(1, 2, 10000, 10, OrderAction::Bid, OrderType::Gtc);
+        book.new_order(&mut bid);
+        assert_eq!(trade_size(&bid), 10);
+    }
+}
+
+#[test]
+fn test_stp_cancel_taker() {
+    for mut book in books(StpMode::CancelTaker) {
+        book.new_order(&mut order(2, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(1, 2, 10050, 10, OrderAction::Ask, OrderType::Gtc));
+
+        let mut bid = order(1, 3, 10050, 20, OrderAction::Bid, OrderType::Gtc);
+        book.new_order(&mut bid);
+
+        // 先与他人成交 5，遇到自己的挂单后撤销剩余 15，不挂单
+        assert_eq!(trade_size(&bid), 5);
+        let last = bid.matcher_events.last().unwrap();
+        assert_eq!(last.event_type, MatcherEventType::Reject);
+        assert_eq!(last.size, 15);
+        assert_eq!(book.get_total_ask_volume(), 10);
+        assert_eq!(book.get_total_bid_volume(), 0);
+    }
+}
+
+#[test]
+fn test_stp_cancel_maker() {
+    for mut book in books(StpMode::CancelMaker) {
+        book.new_order(&mut order(1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(2, 2, 10100, 10, OrderAction::Ask, OrderType::Gtc));
+
+        let mut bid = order(1, 3, 10100, 6, OrderAction::Bid, OrderType::Gtc);
+        book.new_order(&mut bid);
+
+        let reduce = &bid.matcher_events[0];
+        assert_eq!(reduce.event_type, MatcherEventType::Reduce);
+        assert_eq!(reduce.matched_order_id, 1);
+        assert_eq!(reduce.matched_order_uid, 1);
+        assert_eq!(reduce.size, 10);
+
+        assert_eq!(trade_size(&bid), 6);
+        assert_eq!(book.get_total_ask_volume(), 4);
+    }
+}
+
+#[test]
+fn test_stp_cancel_both() {
+    for mut book in books(StpMode::CancelBoth) {
+        book.new_order(&mut order(1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc));
+        let mut bid = order(1, 2, 10000, 4, OrderAction::Bid, OrderType::Gtc);
+        book.new_order(&mut bid);
+
+        assert_eq!(trade_size(&bid), 0);
+        assert_eq!(bid.matcher_events.len(), 2);
+        assert_eq!(bid.matcher_events[0].event_type, MatcherEventType::Reduce);
+        assert_eq!(bid.matcher_events[0].size, 10);
+        assert_eq!(bid.matcher_events[1].event_type, MatcherEventType::Reject);
+        assert_eq!(bid.matcher_events[1].size, 4);
+        assert_eq!(book.get_total_ask_volume(), 0);
+        assert_eq!(book.get_total_bid_volume(), 0);
+    }
+}
+
+#[test]
+fn test_stp_decrement_and_cancel() {
+    for mut book in books(StpMode::DecrementAndCancel) {
+        book.new_order(&mut order(1, 1, 10000, 4, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(2, 2, 10050, 10, OrderAction::Ask, OrderType::Gtc));
+
+        // taker 10 与自己的 maker 4 相遇：双方扣减 4，maker 撤销，taker 剩余 6 与他人成交
+        let mut bid = order(1, 3, 10050, 10, OrderAction::Bid, OrderType::Gtc);
+        book.new_order(&mut bid);
+
+        assert_eq!(bid.matcher_events[0].event_type, MatcherEventType::Reduce);
+        assert_eq!(bid.matcher_events[0].size, 4);
+        assert_eq!(bid.matcher_events[1].event_type, MatcherEventType::Reject);
+        assert_eq!(bid.matcher_events[1].size, 4);
+        assert_eq!(trade_size(&bid), 6);
+        assert_eq!(book.get_total_ask_volume(), 4);
+        assert_eq!(book.get_total_bid_volume(), 0);
+    }
+}
+
+#[test]
+fn test_stp_command_overrides_symbol_default() {
+    for mut book in books(StpMode::None) {
+        book.new_order(&mut order(1, 1, 10000, 10, OrderAction::Ask, OrderType::Gtc));
+        let mut bid = order(1, 2, 10000, 10, OrderAction::Bid, OrderType::Ioc);
+        bid.stp_mode = StpMode::CancelTaker;
+        book.new_order(&mut bid);
+
+        assert_eq!(trade_size(&bid), 0);
+        assert_eq!(book.get_total_ask_volume(), 10);
+    }
+}
+
+#[test]
+fn test_stp_fok_counts_only_other_users_liquidity() {
+    for mut book in books(StpMode::CancelMaker) {
+        book.new_order(&mut order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(2, 2, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+
+        // 自己的卖单会被 STP 撤销，他人只有 5：FOK 整单拒绝，不成交也不撤销 maker
+        let mut fok = order(1, 3, 10000, 10, OrderAction::Bid, OrderType::Fok);
+        book.new_order(&mut fok);
+
+        assert_eq!(fok.matcher_events.len(), 1);
+        assert_eq!(fok.matcher_events[0].event_type, MatcherEventType::Reject);
+        assert_eq!(fok.matcher_events[0].size, 10);
+        assert_eq!(book.get_total_ask_volume(), 10);
+    }
+}
+
+#[test]
+fn test_stp_fok_budget_counts_only_other_users_liquidity() {
+    for kind in [OrderBookKind::DirectOptimized, OrderBookKind::DirectOptimizedLadder] {
+        let mut book = new_order_book(kind, create_symbol_spec(StpMode::CancelMaker));
+        book.new_order(&mut order(1, 1, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+        book.new_order(&mut order(2, 2, 10000, 5, OrderAction::Ask, OrderType::Gtc));
+
+        // 预算足够买 10，但自己的卖单会被 STP 撤销，他人只有 5：整单拒绝，不部分成交
+        let mut fok = order(1, 3, 100_000, 10, OrderAction::Bid, OrderType::FokBudget);
+        book.new_order(&mut fok);
+
+        assert_eq!(trade_size(&fok), 0);
+        assert_eq!(fok.matcher_events.len(), 1);
+        assert_eq!(fok.matcher_events[0].event_type, MatcherEventType::Reject);
+        assert_eq!(fok.matcher_events[0].size, 10);
+        assert_eq!(book.get_total_ask_volume(), 10);
+    }
+}
@@ -0,0 +1,130 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::surveillance::{SurveillanceAlert, SurveillanceConfig, SurveillanceConsumer, SurveillanceProcessor};
+use std::sync::{Arc, Mutex};
+
+const CONFIG: SurveillanceConfig = SurveillanceConfig { wash_window: 10, cancel_window: 100, min_orders: 4, max_cancel_pct: 50 };
+
+fn spec() -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        ..Default::default()
+    }
+}
+
+fn collector() -> (Arc<Mutex<Vec<SurveillanceAlert>>>, SurveillanceConsumer) {
+    let alerts = Arc::new(Mutex::new(Vec::new()));
+    let sink = alerts.clone();
+    (alerts, Arc::new(move |alert: &SurveillanceAlert| sink.lock().unwrap().push(alert.clone())))
+}
+
+fn new_router(consumer: SurveillanceConsumer) -> MatchingEngineRouter {
+    let mut router = MatchingEngineRouter::new(0, 1);
+    router.add_symbol(spec());
+    router.add_listener(Box::new(SurveillanceProcessor::new(CONFIG, consumer)));
+    router
+}
+
+fn order(uid: UserId, order_id: OrderId, action: OrderAction, timestamp: i64) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid,
+        order_id,
+        symbol: 100,
+        price: 100,
+        reserve_price: 100,
+        size: 1,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp,
+        ..Default::default()
+    }
+}
+
+fn cancel(router: &mut MatchingEngineRouter, uid: UserId, order_id: OrderId, timestamp: i64) {
+    let mut cmd = OrderCommand { command: OrderCommandType::CancelOrder, uid, order_id, symbol: 100, timestamp, ..Default::default() };
+    router.process_order(&mut cmd);
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+}
+
+fn take(alerts: &Arc<Mutex<Vec<SurveillanceAlert>>>) -> Vec<SurveillanceAlert> {
+    std::mem::take(&mut *alerts.lock().unwrap())
+}
+
+#[test]
+fn test_self_match_and_wash_trade() {
+    let (alerts, consumer) = collector();
+    let mut router = new_router(consumer);
+
+    router.process_order(&mut OrderCommand { size: 2, ..order(1, 1, OrderAction::Ask, 1) });
+    router.process_order(&mut order(1, 2, OrderAction::Bid, 2));
+    let self_match = SurveillanceAlert::SelfMatch { symbol: 100, uid: 1, taker_order_id: 2, maker_order_id: 1, price: 100, size: 1, timestamp: 2 };
+    assert_eq!(take(&alerts), vec![self_match]);
+
+    // 用户 1 在 t=3 卖出、t=5 以同价买回
+    router.process_order(&mut order(2, 3, OrderAction::Bid, 3));
+    router.process_order(&mut order(3, 4, OrderAction::Ask, 4));
+    assert!(take(&alerts).is_empty());
+    router.process_order(&mut order(1, 5, OrderAction::Bid, 5));
+    let wash = SurveillanceAlert::WashTrade { symbol: 100, uid: 1, price: 100, buy_order_id: 5, sell_order_id: 1, timestamp: 5 };
+    assert_eq!(take(&alerts), vec![wash]);
+
+    // 超出窗口的反向成交不告警
+    router.process_order(&mut order(2, 6, OrderAction::Ask, 20));
+    router.process_order(&mut order(3, 7, OrderAction::Bid, 21));
+    assert!(take(&alerts).is_empty());
+}
+
+#[test]
+fn test_excessive_cancels() {
+    let (alerts, consumer) = collector();
+    let mut router = new_router(consumer);
+
+    for order_id in 1..=4 {
+        router.process_order(&mut order(1, order_id, OrderAction::Ask, order_id as i64));
+    }
+    cancel(&mut router, 1, 1, 10);
+    cancel(&mut router, 1, 2, 11);
+    assert!(take(&alerts).is_empty());
+    cancel(&mut router, 1, 3, 12);
+    cancel(&mut router, 1, 4, 13);
+    let alert = SurveillanceAlert::ExcessiveCancels { symbol: 100, uid: 1, placed: 4, cancelled: 3, window_start: 1, timestamp: 12 };
+    assert_eq!(take(&alerts), vec![alert]);
+
+    // 新窗口：批量撤单不计入撤单率
+    for order_id in 5..=8 {
+        router.process_order(&mut order(1, order_id, OrderAction::Ask, 200 + order_id as i64));
+    }
+    router.process_order(&mut OrderCommand {
+        command: OrderCommandType::SuspendUser,
+        result_code: CommandResultCode::ValidForMatchingEngine,
+        uid: 1,
+        timestamp: 210,
+        ..Default::default()
+    });
+    assert!(router.get_user_orders(1, None).is_empty());
+    assert!(take(&alerts).is_empty());
+}
+
+#[test]
+fn test_exchange_surveillance_consumer() {
+    let (alerts, consumer) = collector();
+    let mut core = ExchangeCore::new(ExchangeConfig { matching_engines_num: 2, ..Default::default() });
+    core.add_symbol(spec());
+    core.enable_surveillance(CONFIG, consumer);
+    core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid: 1, ..Default::default() });
+    for (currency, order_id) in [(1, 1), (2, 2)] {
+        core.submit_command(OrderCommand { command: OrderCommandType::BalanceAdjustment, uid: 1, symbol: currency, price: 1_000, order_id, ..Default::default() });
+    }
+
+    for (order_id, action) in [(1, OrderAction::Ask), (2, OrderAction::Bid)] {
+        let cmd = OrderCommand { result_code: CommandResultCode::New, ..order(1, order_id, action, order_id as i64) };
+        assert_eq!(core.submit_command(cmd).result_code, CommandResultCode::Success);
+    }
+    assert!(matches!(take(&alerts)[..], [SurveillanceAlert::SelfMatch { uid: 1, taker_order_id: 2, maker_order_id: 1, .. }]));
+}
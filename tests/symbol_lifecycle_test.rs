@@ -0,0 +1,206 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use std::path::PathBuf;
+
+fn spot_spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        order_book: Some(OrderBookKind::Advanced),
+        ..Default::default()
+    }
+}
+
+fn new_core() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        matching_engines_num: 2,
+        ..Default::default()
+    });
+    core.add_symbol(spot_spec(100));
+    core.add_symbol(spot_spec(101));
+    for uid in [1, 2] {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, action: OrderAction) -> CommandResultCode {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size: 5,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    })
+    .result_code
+}
+
+fn symbol_command(command: OrderCommandType, symbol: SymbolId) -> OrderCommand {
+    OrderCommand { command, symbol, timestamp: 5000, ..Default::default() }
+}
+
+fn order_book(core: &mut ExchangeCore, symbol: SymbolId) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol,
+        size: 10,
+        ..Default::default()
+    })
+}
+
+/// 双方在两个交易对挂单，另含一张未触发的止损单
+fn seed_orders(core: &mut ExchangeCore) {
+    assert_eq!(place(core, 1, 1, 100, 110, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(place(core, 2, 2, 100, 90, OrderAction::Bid), CommandResultCode::Success);
+    assert_eq!(place(core, 1, 3, 101, 120, OrderAction::Ask), CommandResultCode::Success);
+    let stop = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 2,
+        order_id: 4,
+        symbol: 100,
+        price: 130,
+        reserve_price: 130,
+        stop_price: Some(125),
+        size: 3,
+        action: OrderAction::Bid,
+        order_type: OrderType::StopLimit,
+        timestamp: 1004,
+        ..Default::default()
+    });
+    assert_eq!(stop.result_code, CommandResultCode::Success);
+}
+
+#[test]
+fn test_clear_order_book_refunds_and_keeps_symbol() {
+    let mut core = new_core();
+    seed_orders(&mut core);
+
+    let result = core.submit_command(symbol_command(OrderCommandType::ClearOrderBook, 100));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    let cancelled: Vec<_> = result.open_orders.iter().map(|order| order.order_id).collect();
+    assert_eq!(cancelled, vec![1, 2, 4]);
+    assert!(result.matcher_events.iter().all(|e| e.maker_completed && e.maker_action.is_some()));
+    core.verify_invariants().unwrap();
+
+    let l2 = order_book(&mut core, 100).market_data.unwrap();
+    assert!(l2.ask_prices.is_empty() && l2.bid_prices.is_empty());
+    // 其他交易对不受影响
+    assert_eq!(order_book(&mut core, 101).market_data.unwrap().ask_prices, vec![120]);
+
+    // 交易对保留，可以继续交易
+    assert_eq!(place(&mut core, 1, 5, 100, 100, OrderAction::Ask), CommandResultCode::Success);
+    let trade = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 2,
+        order_id: 6,
+        symbol: 100,
+        price: 100,
+        reserve_price: 100,
+        size: 5,
+        action: OrderAction::Bid,
+        order_type: OrderType::Gtc,
+        timestamp: 6000,
+        ..Default::default()
+    });
+    assert_eq!(trade.matcher_events.len(), 1);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_delist_symbol() {
+    let mut core = new_core();
+    seed_orders(&mut core);
+
+    let result = core.submit_command(symbol_command(OrderCommandType::DelistSymbol, 100));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(result.matcher_events.len(), 3);
+    core.verify_invariants().unwrap();
+
+    let symbols: Vec<_> = core.symbols().unwrap().iter().map(|spec| spec.symbol_id).collect();
+    assert_eq!(symbols, vec![101]);
+    // 与从未上架的交易对返回相同结果
+    let unknown = place(&mut core, 1, 5, 998, 100, OrderAction::Ask);
+    assert_ne!(unknown, CommandResultCode::Success);
+    assert_eq!(place(&mut core, 1, 5, 100, 100, OrderAction::Ask), unknown);
+    assert_eq!(order_book(&mut core, 100).result_code, order_book(&mut core, 998).result_code);
+    assert_eq!(
+        core.submit_command(symbol_command(OrderCommandType::DelistSymbol, 100)).result_code,
+        CommandResultCode::InvalidSymbol
+    );
+
+    // 下架后可以重新上架
+    assert_eq!(core.add_symbol(spot_spec(100)), CommandResultCode::Success);
+    assert_eq!(place(&mut core, 1, 6, 100, 100, OrderAction::Ask), CommandResultCode::Success);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_delist_rejected_with_open_positions() {
+    let mut core = new_core();
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 200,
+        symbol_type: SymbolType::FuturesContract,
+        margin_buy: 10,
+        margin_sell: 10,
+        ..spot_spec(200)
+    });
+    assert_eq!(place(&mut core, 1, 1, 200, 100, OrderAction::Ask), CommandResultCode::Success);
+
+    // 仅有挂单时可以清空
+    let cleared = core.submit_command(symbol_command(OrderCommandType::ClearOrderBook, 200));
+    assert_eq!(cleared.result_code, CommandResultCode::Success);
+    core.verify_invariants().unwrap();
+
+    assert_eq!(place(&mut core, 1, 2, 200, 100, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(place(&mut core, 2, 3, 200, 100, OrderAction::Bid), CommandResultCode::Success);
+    assert_eq!(
+        core.submit_command(symbol_command(OrderCommandType::DelistSymbol, 200)).result_code,
+        CommandResultCode::SymbolMgmtOpenPositions
+    );
+    assert!(core.symbols().unwrap().iter().any(|spec| spec.symbol_id == 200));
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_lifecycle_commands_replay_from_journal() {
+    let dir = std::env::temp_dir().join(format!("matching_core_symbol_lifecycle_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let journal: PathBuf = dir.join("exchange.wal");
+
+    let mut core = new_core();
+    core.enable_journaling(&journal).unwrap();
+    seed_orders(&mut core);
+    core.submit_command(symbol_command(OrderCommandType::ClearOrderBook, 100));
+    assert_eq!(place(&mut core, 1, 5, 100, 105, OrderAction::Ask), CommandResultCode::Success);
+    core.submit_command(symbol_command(OrderCommandType::DelistSymbol, 101));
+    let expected = order_book(&mut core, 100).market_data;
+    drop(core);
+
+    let mut replayed = new_core();
+    replayed.replay_journal(&journal).unwrap();
+    assert_eq!(order_book(&mut replayed, 100).market_data, expected);
+    let symbols: Vec<_> = replayed.symbols().unwrap().iter().map(|spec| spec.symbol_id).collect();
+    assert_eq!(symbols, vec![100]);
+    replayed.verify_invariants().unwrap();
+}
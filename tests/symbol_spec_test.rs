@@ -0,0 +1,122 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        tick_size: 5,
+        lot_size: 10,
+        min_size: 20,
+        max_size: 1000,
+        ..Default::default()
+    }
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    assert_eq!(core.add_symbol(spec(100)), CommandResultCode::Success);
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 10_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_price_and_size_granularity() {
+    let mut core = setup();
+    for (price, size, expected) in [
+        (1003, 50, CommandResultCode::RiskInvalidPriceStep),
+        (1005, 55, CommandResultCode::RiskInvalidLotSize),
+        (1005, 10, CommandResultCode::RiskOrderSizeTooSmall),
+        (1005, 1010, CommandResultCode::RiskOrderSizeTooLarge),
+        (1005, 20, CommandResultCode::Success),
+        (1005, 1000, CommandResultCode::Success),
+    ] {
+        let result = core.submit_command(order(1001, price as OrderId * 10_000 + size as OrderId, price, size, OrderAction::Ask, OrderType::Gtc));
+        assert_eq!(result.result_code, expected, "price={} size={}", price, size);
+    }
+
+    // 被拒绝的订单不冻结资金
+    core.verify_invariants().unwrap();
+    let book = core
+        .submit_command(OrderCommand {
+            command: OrderCommandType::OrderBookRequest,
+            symbol: 100,
+            size: 10,
+            ..Default::default()
+        })
+        .market_data
+        .unwrap();
+    assert_eq!(book.ask_volumes, vec![1020]);
+}
+
+#[test]
+fn test_stop_and_iceberg_fields_checked() {
+    let mut core = setup();
+
+    let mut stop = order(1002, 1, 1100, 20, OrderAction::Bid, OrderType::StopLimit);
+    stop.stop_price = Some(1052);
+    assert_eq!(core.submit_command(stop).result_code, CommandResultCode::RiskInvalidPriceStep);
+
+    let mut iceberg = order(1001, 2, 1100, 100, OrderAction::Ask, OrderType::Iceberg);
+    iceberg.visible_size = Some(25);
+    assert_eq!(core.submit_command(iceberg).result_code, CommandResultCode::RiskInvalidLotSize);
+
+    // 市价单不校验价格步长（数量仍需符合手数）
+    let market = core.submit_command(order(1002, 3, 1001, 20, OrderAction::Bid, OrderType::Market));
+    assert_ne!(market.result_code, CommandResultCode::RiskInvalidPriceStep);
+    let market = core.submit_command(order(1002, 4, 1001, 25, OrderAction::Bid, OrderType::Market));
+    assert_eq!(market.result_code, CommandResultCode::RiskInvalidLotSize);
+}
+
+#[test]
+fn test_invalid_specification_rejected() {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    for invalid in [
+        CoreSymbolSpecification { tick_size: 0, ..spec(1) },
+        CoreSymbolSpecification { lot_size: -1, ..spec(2) },
+        CoreSymbolSpecification { min_size: 100, max_size: 50, ..spec(3) },
+        CoreSymbolSpecification { base_scale_k: 0, ..spec(4) },
+    ] {
+        assert_eq!(core.add_symbol(invalid.clone()), CommandResultCode::SymbolMgmtInvalidSpecification);
+        let result = core.submit_command(OrderCommand::add_symbols(&[spec(10), invalid]));
+        assert_eq!(result.result_code, CommandResultCode::SymbolMgmtInvalidSpecification);
+    }
+    assert!(core.symbols().unwrap().is_empty());
+
+    // 默认配置不限制步长与上下限
+    assert!(CoreSymbolSpecification::default().is_valid());
+}
@@ -0,0 +1,79 @@
+use matching_core::api::*;
+use matching_core::core::affinity::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn placement(core: Option<usize>, name: Option<&str>) -> ThreadPlacement {
+    ThreadPlacement { core, name: name.map(str::to_string), nice: None }
+}
+
+#[test]
+fn test_pipeline_threads_report_placements() {
+    let config = ExchangeConfig {
+        matching_engines_num: 2,
+        risk_engines_num: 2,
+        consumer_cores: vec![0, 0, 0],
+        thread_affinity: ThreadAffinityConfig {
+            matching: vec![placement(Some(0), Some("match-a")), placement(None, Some("match-b"))],
+            results: ThreadPlacement { nice: Some(5), ..Default::default() },
+            journal: placement(Some(0), None),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut core = ExchangeCore::new(config);
+    core.startup();
+    // 线程在启动时（或处理第一条命令前）登记放置
+    core.submit_command_async(OrderCommand { command: OrderCommandType::Nop, ..Default::default() }).wait();
+
+    let reports = core.thread_placements();
+    let roles: Vec<_> = reports.iter().map(|report| (report.role, report.shard)).collect();
+    assert_eq!(
+        roles,
+        vec![
+            (ThreadRole::Grouping, 0),
+            (ThreadRole::RiskPre, 0),
+            (ThreadRole::RiskPre, 1),
+            (ThreadRole::Matching, 0),
+            (ThreadRole::Matching, 1),
+            (ThreadRole::RiskPost, 0),
+            (ThreadRole::RiskPost, 1),
+            (ThreadRole::Results, 0),
+            (ThreadRole::Journal, 0),
+        ]
+    );
+
+    let report = |role, shard| reports.iter().find(|r| r.role == role && r.shard == shard).unwrap();
+    // consumer_cores 依次分配给分组与 R1 两个分片，之后的线程不绑定
+    assert_eq!(report(ThreadRole::Grouping, 0).core, Some(0));
+    assert_eq!(report(ThreadRole::RiskPre, 1).core, Some(0));
+    assert_eq!(report(ThreadRole::Matching, 0).core, Some(0));
+    assert_eq!(report(ThreadRole::Matching, 1).core, None);
+    assert_eq!(report(ThreadRole::RiskPost, 0).core, None);
+    assert_eq!(report(ThreadRole::Journal, 0).core, Some(0));
+    for r in reports.iter().filter(|r| r.core.is_some()) {
+        assert!(r.errors.is_empty(), "{:?}", r);
+        if cfg!(target_os = "linux") {
+            assert_eq!(r.current_cpu, Some(0), "{:?}", r);
+        }
+    }
+
+    // 调低优先级（增大 nice）不需要权限
+    if cfg!(target_os = "linux") {
+        assert_eq!(report(ThreadRole::Results, 0).nice, Some(5));
+    } else {
+        assert_eq!(report(ThreadRole::Results, 0).errors.len(), 1);
+    }
+}
+
+#[test]
+fn test_default_config_has_no_journal_placement() {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    assert!(core.thread_placements().is_empty());
+    core.startup();
+    core.submit_command_async(OrderCommand { command: OrderCommandType::Nop, ..Default::default() }).wait();
+
+    let reports = core.thread_placements();
+    assert_eq!(reports.len(), 5);
+    assert!(reports.iter().all(|r| r.core.is_none() && r.nice.is_none() && r.errors.is_empty()));
+    assert!(reports.iter().all(|r| r.role != ThreadRole::Journal));
+}
@@ -0,0 +1,90 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    }
+}
+
+fn order(symbol: SymbolId, uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    for symbol in [100, 101] {
+        core.add_symbol(spec(symbol));
+    }
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn trade_ids(cmd: &OrderCommand) -> Vec<u64> {
+    cmd.matcher_events.iter().filter(|e| e.event_type == MatcherEventType::Trade).map(|e| e.trade_id).collect()
+}
+
+#[test]
+fn test_trade_ids_per_symbol_survive_recovery() {
+    let mut core = setup();
+    core.submit_command(order(100, 1001, 1, 100, 1, OrderAction::Ask));
+    core.submit_command(order(100, 1001, 2, 101, 1, OrderAction::Ask));
+    let sweep = core.submit_command(order(100, 1002, 3, 101, 3, OrderAction::Bid));
+    assert_eq!(trade_ids(&sweep), vec![1, 2]);
+
+    // 各交易对独立编号；撤单事件不占用成交编号
+    core.submit_command(order(101, 1001, 4, 100, 1, OrderAction::Ask));
+    let other = core.submit_command(order(101, 1002, 5, 100, 1, OrderAction::Bid));
+    assert_eq!(trade_ids(&other), vec![1]);
+    let cancel = core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1002,
+        order_id: 3,
+        symbol: 100,
+        ..Default::default()
+    });
+    assert_eq!(cancel.matcher_events[0].trade_id, 0);
+
+    // 恢复后继续递增，不会重复
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    restored.submit_command(order(100, 1001, 6, 100, 1, OrderAction::Ask));
+    let after = restored.submit_command(order(100, 1002, 7, 100, 1, OrderAction::Bid));
+    assert_eq!(trade_ids(&after), vec![3]);
+}
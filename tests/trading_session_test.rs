@@ -0,0 +1,191 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+fn config() -> ExchangeConfig {
+    ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    }
+}
+
+fn setup(mut core: ExchangeCore) -> ExchangeCore {
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    for (uid, currency) in [(1001, 2), (1002, 1)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn order(uid: UserId, order_id: OrderId, price: Price, size: Size, action: OrderAction, order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn cancel(uid: UserId, order_id: OrderId) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid,
+        order_id,
+        symbol: 100,
+        ..Default::default()
+    }
+}
+
+fn book_request() -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    }
+}
+
+fn set_state(core: &mut ExchangeCore, state: TradingSessionState) -> CommandResultCode {
+    core.submit_command(OrderCommand::set_session_state(100, state)).result_code
+}
+
+#[test]
+fn test_halt_and_resume_without_restart() {
+    let mut core = setup(ExchangeCore::new(config()));
+    core.submit_command(order(1001, 1, 1000, 10, OrderAction::Ask, OrderType::Gtc));
+    core.submit_command(order(1001, 2, 1010, 10, OrderAction::Ask, OrderType::Gtc));
+    assert_eq!(set_state(&mut core, TradingSessionState::Halted), CommandResultCode::Success);
+
+    // 停牌期间拒绝新单与改价，冻结资金全部返还
+    let rejected = core.submit_command(order(1002, 3, 1000, 5, OrderAction::Bid, OrderType::Gtc));
+    assert_eq!(rejected.result_code, CommandResultCode::MatchingSessionRejected);
+    assert_eq!(rejected.matcher_events.len(), 1);
+    assert_eq!(rejected.matcher_events[0].event_type, MatcherEventType::Reject);
+    let moved = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1001,
+        order_id: 1,
+        symbol: 100,
+        price: 990,
+        ..Default::default()
+    });
+    assert_eq!(moved.result_code, CommandResultCode::MatchingSessionRejected);
+    core.verify_invariants().unwrap();
+
+    // 撤单仍然允许
+    assert_eq!(core.submit_command(cancel(1001, 2)).result_code, CommandResultCode::Success);
+    let book = core.submit_command(book_request()).market_data.unwrap();
+    assert_eq!((book.ask_prices, book.ask_volumes), (vec![1000], vec![10]));
+
+    assert_eq!(set_state(&mut core, TradingSessionState::ContinuousTrading), CommandResultCode::Success);
+    let taker = core.submit_command(order(1002, 4, 1000, 5, OrderAction::Bid, OrderType::Gtc));
+    assert_eq!(taker.result_code, CommandResultCode::Success);
+    assert_eq!(taker.matcher_events.len(), 1);
+    assert_eq!(taker.matcher_events[0].event_type, MatcherEventType::Trade);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_pre_open_accepts_only_resting_orders() {
+    let mut core = setup(ExchangeCore::new(config()));
+    assert_eq!(set_state(&mut core, TradingSessionState::PreOpen), CommandResultCode::Success);
+
+    assert_eq!(core.submit_command(order(1001, 1, 1000, 10, OrderAction::Ask, OrderType::Gtc)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(order(1002, 2, 990, 10, OrderAction::Bid, OrderType::Gtc)).result_code, CommandResultCode::Success);
+    // 会成交的限价单与即时成交订单都被拒绝
+    for (order_id, price, order_type) in [(3, 1000, OrderType::Gtc), (4, 1000, OrderType::Ioc)] {
+        let result = core.submit_command(order(1002, order_id, price, 5, OrderAction::Bid, order_type));
+        assert_eq!(result.result_code, CommandResultCode::MatchingSessionRejected);
+    }
+    core.verify_invariants().unwrap();
+
+    assert_eq!(set_state(&mut core, TradingSessionState::ContinuousTrading), CommandResultCode::Success);
+    let taker = core.submit_command(order(1002, 5, 1000, 5, OrderAction::Bid, OrderType::Ioc));
+    assert_eq!(taker.matcher_events.iter().map(|e| (e.matched_order_id, e.size)).collect::<Vec<_>>(), vec![(1, 5)]);
+}
+
+#[test]
+fn test_close_only_and_closed() {
+    let mut core = setup(ExchangeCore::new(config()));
+    core.submit_command(order(1001, 1, 1000, 10, OrderAction::Ask, OrderType::Gtc));
+    assert_eq!(set_state(&mut core, TradingSessionState::CloseOnly), CommandResultCode::Success);
+
+    // 只允许不挂单的即时成交订单
+    let gtc = core.submit_command(order(1002, 2, 1000, 5, OrderAction::Bid, OrderType::Gtc));
+    assert_eq!(gtc.result_code, CommandResultCode::MatchingSessionRejected);
+    let ioc = core.submit_command(order(1002, 3, 1000, 5, OrderAction::Bid, OrderType::Ioc));
+    assert_eq!(ioc.result_code, CommandResultCode::Success);
+    assert_eq!(ioc.matcher_events.len(), 1);
+
+    assert_eq!(set_state(&mut core, TradingSessionState::Closed), CommandResultCode::Success);
+    let rejected = core.submit_command(order(1002, 4, 1000, 5, OrderAction::Bid, OrderType::Ioc));
+    assert_eq!(rejected.result_code, CommandResultCode::MatchingSessionRejected);
+    // 收盘后不能直接停牌或只平仓
+    for state in [TradingSessionState::Halted, TradingSessionState::CloseOnly] {
+        assert_eq!(set_state(&mut core, state), CommandResultCode::MatchingInvalidSessionTransition);
+    }
+    assert_eq!(set_state(&mut core, TradingSessionState::PreOpen), CommandResultCode::Success);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_invalid_session_commands() {
+    let mut core = setup(ExchangeCore::new(config()));
+    let unknown = core.submit_command(OrderCommand::set_session_state(101, TradingSessionState::Halted));
+    assert_eq!(unknown.result_code, CommandResultCode::InvalidSymbol);
+
+    let invalid = core.submit_command(OrderCommand {
+        command: OrderCommandType::SetSessionState,
+        symbol: 100,
+        service_flags: 99,
+        ..Default::default()
+    });
+    assert_eq!(invalid.result_code, CommandResultCode::MatchingInvalidSessionTransition);
+}
+
+#[test]
+fn test_session_state_restored_and_staged() {
+    let mut core = setup(ExchangeCore::new(config()));
+    set_state(&mut core, TradingSessionState::Halted);
+
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    let rejected = restored.submit_command(order(1001, 1, 1000, 10, OrderAction::Ask, OrderType::Gtc));
+    assert_eq!(rejected.result_code, CommandResultCode::MatchingSessionRejected);
+
+    // Disruptor 流水线中同样生效
+    let mut staged = setup(ExchangeCore::new(config()));
+    staged.startup();
+    let halted = staged.submit_command_async(OrderCommand::set_session_state(100, TradingSessionState::Halted)).wait();
+    assert_eq!(halted.result_code, CommandResultCode::Success);
+    let rejected = staged.submit_command_async(order(1001, 1, 1000, 10, OrderAction::Ask, OrderType::Gtc)).wait();
+    assert_eq!(rejected.result_code, CommandResultCode::MatchingSessionRejected);
+    staged.submit_command_async(OrderCommand::set_session_state(100, TradingSessionState::ContinuousTrading)).wait();
+    let accepted = staged.submit_command_async(order(1001, 2, 1000, 10, OrderAction::Ask, OrderType::Gtc)).wait();
+    assert_eq!(accepted.result_code, CommandResultCode::Success);
+}
@@ -0,0 +1,157 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::core::orderbook::{OrderBook, NaiveOrderBook, DirectOrderBook, DirectOrderBookOptimized, AdvancedOrderBook};
+
+fn create_symbol_spec(symbol_id: SymbolId) -> CoreSymbolSpecification {
+    CoreSymbolSpecification {
+        symbol_id,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        taker_fee: 0,
+        maker_fee: 0,
+        margin_buy: 0,
+        margin_sell: 0,
+        ..Default::default()
+    }
+}
+
+fn books() -> Vec<Box<dyn OrderBook>> {
+    vec![
+        Box::new(NaiveOrderBook::new(create_symbol_spec(1))),
+        Box::new(DirectOrderBook::new(create_symbol_spec(1))),
+        Box::new(DirectOrderBookOptimized::new(create_symbol_spec(1))),
+        Box::new(AdvancedOrderBook::new(create_symbol_spec(1))),
+    ]
+}
+
+fn order(uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    }
+}
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(create_symbol_spec(100));
+    core.add_symbol(create_symbol_spec(200));
+
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    core
+}
+
+fn user_orders(core: &mut ExchangeCore, uid: UserId, symbol: SymbolId) -> OrderCommand {
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::UserOrdersRequest,
+        uid,
+        symbol,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_get_open_order() {
+    for mut book in books() {
+        book.new_order(&mut order(7, 1, 1, 10000, 10, OrderAction::Ask));
+        book.new_order(&mut order(8, 2, 1, 10000, 4, OrderAction::Bid));
+
+        let open = book.get_open_order(1).expect("部分成交的订单仍在挂单");
+        assert_eq!(open.uid, 7);
+        assert_eq!(open.symbol, 1);
+        assert_eq!(open.action, OrderAction::Ask);
+        assert_eq!(open.order_type, OrderType::Gtc);
+        assert_eq!((open.price, open.size, open.remaining), (10000, 10, 6));
+        assert!(book.get_open_order(2).is_none());
+    }
+}
+
+#[test]
+fn test_get_open_order_untriggered_stop() {
+    let mut book = AdvancedOrderBook::new(create_symbol_spec(1));
+    let mut stop = order(7, 1, 1, 11000, 5, OrderAction::Bid);
+    stop.order_type = OrderType::StopLimit;
+    stop.stop_price = Some(10500);
+    book.new_order(&mut stop);
+
+    let open = book.get_open_order(1).unwrap();
+    assert_eq!(open.order_type, OrderType::StopLimit);
+    assert_eq!(open.remaining, 5);
+}
+
+#[test]
+fn test_user_orders_request() {
+    let mut core = setup();
+    core.submit_command(order(1002, 1, 100, 101, 5, OrderAction::Ask));
+    core.submit_command(order(1002, 2, 100, 102, 6, OrderAction::Ask));
+    core.submit_command(order(1002, 3, 200, 103, 7, OrderAction::Ask));
+    core.submit_command(order(1001, 4, 100, 99, 3, OrderAction::Bid));
+
+    let result = user_orders(&mut core, 1002, 100);
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    let ids: Vec<OrderId> = result.open_orders.iter().map(|o| o.order_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+
+    // 吃掉订单 1 并部分成交订单 2
+    core.submit_command(order(1001, 5, 100, 102, 7, OrderAction::Bid));
+    let result = user_orders(&mut core, 1002, 100);
+    assert_eq!(result.open_orders.len(), 1);
+    assert_eq!(result.open_orders[0].order_id, 2);
+    assert_eq!(result.open_orders[0].remaining, 4);
+
+    // 撤单后不再返回
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::CancelOrder,
+        uid: 1002,
+        order_id: 2,
+        symbol: 100,
+        ..Default::default()
+    });
+    assert!(user_orders(&mut core, 1002, 100).open_orders.is_empty());
+
+    let result = user_orders(&mut core, 1002, 200);
+    assert_eq!(result.open_orders.len(), 1);
+    assert_eq!(result.open_orders[0].order_id, 3);
+
+    let result = user_orders(&mut core, 1001, 100);
+    let ids: Vec<OrderId> = result.open_orders.iter().map(|o| o.order_id).collect();
+    assert_eq!(ids, vec![4]);
+
+    assert_eq!(user_orders(&mut core, 1002, 999).result_code, CommandResultCode::MatchingInvalidOrderBookId);
+}
+
+#[test]
+fn test_user_orders_survive_state_restore() {
+    let mut core = setup();
+    core.submit_command(order(1002, 1, 100, 101, 5, OrderAction::Ask));
+
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    let result = user_orders(&mut restored, 1002, 100);
+    assert_eq!(result.open_orders.len(), 1);
+    assert_eq!(result.open_orders[0].order_id, 1);
+}
@@ -0,0 +1,150 @@
+use matching_core::api::*;
+use matching_core::core::processors::matching_engine::MatchingEngineRouter;
+use matching_core::core::processors::risk_engine::RiskEngine;
+use matching_core::core::users::UserStatus;
+
+struct Exchange {
+    risk: RiskEngine,
+    matching: MatchingEngineRouter,
+}
+
+impl Exchange {
+    fn new() -> Self {
+        let mut exchange = Self {
+            risk: RiskEngine::new(0, 1),
+            matching: MatchingEngineRouter::new(0, 1),
+        };
+        for symbol_id in [100, 200] {
+            let spec = CoreSymbolSpecification {
+                symbol_id,
+                symbol_type: SymbolType::CurrencyExchangePair,
+                base_currency: symbol_id,
+                quote_currency: 1,
+                base_scale_k: 1,
+                quote_scale_k: 1,
+                taker_fee: 0,
+                maker_fee: 0,
+                margin_buy: 0,
+                margin_sell: 0,
+                ..Default::default()
+            };
+            exchange.risk.add_symbol(spec.clone());
+            exchange.matching.add_symbol(spec);
+        }
+
+        exchange.submit(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid: 1001,
+            ..Default::default()
+        });
+        for currency in [1, 100, 200] {
+            exchange.submit(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid: 1001,
+                symbol: currency,
+                price: 1_000,
+                order_id: currency as OrderId,
+                ..Default::default()
+            });
+        }
+        exchange
+    }
+
+    fn submit(&mut self, mut cmd: OrderCommand) -> OrderCommand {
+        self.risk.pre_process(&mut cmd);
+        self.matching.process_order(&mut cmd);
+        self.risk.post_process(&mut cmd);
+        cmd
+    }
+
+    fn place(&mut self, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) -> CommandResultCode {
+        self.submit(OrderCommand {
+            command: OrderCommandType::PlaceOrder,
+            uid: 1001,
+            order_id,
+            symbol,
+            price,
+            reserve_price: price,
+            size,
+            action,
+            order_type: OrderType::Gtc,
+            ..Default::default()
+        })
+        .result_code
+    }
+
+    fn balance(&self, currency: Currency) -> i64 {
+        self.risk.get_user(1001).unwrap().accounts.get(&currency).copied().unwrap_or(0)
+    }
+}
+
+fn user_command(command: OrderCommandType, service_flags: i32) -> OrderCommand {
+    OrderCommand {
+        command,
+        uid: 1001,
+        service_flags,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_suspend_rejects_new_orders() {
+    let mut exchange = Exchange::new();
+    assert_eq!(exchange.place(1, 100, 10, 5, OrderAction::Ask), CommandResultCode::Success);
+
+    let result = exchange.submit(user_command(OrderCommandType::SuspendUser, 0));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(exchange.risk.get_user(1001).unwrap().status, UserStatus::Suspended);
+
+    assert_eq!(exchange.place(2, 100, 10, 5, OrderAction::Ask), CommandResultCode::AuthUserSuspended);
+    assert_eq!(exchange.balance(100), 995);
+
+    // 未要求撤单时挂单保留
+    assert_eq!(exchange.matching.get_user_orders(1001, None).len(), 1);
+
+    assert_eq!(
+        exchange.submit(user_command(OrderCommandType::SuspendUser, 0)).result_code,
+        CommandResultCode::UserMgmtUserAlreadySuspended
+    );
+
+    assert_eq!(exchange.submit(user_command(OrderCommandType::ResumeUser, 0)).result_code, CommandResultCode::Success);
+    assert_eq!(
+        exchange.submit(user_command(OrderCommandType::ResumeUser, 0)).result_code,
+        CommandResultCode::UserMgmtUserNotSuspended
+    );
+    assert_eq!(exchange.place(2, 100, 10, 5, OrderAction::Ask), CommandResultCode::Success);
+}
+
+#[test]
+fn test_suspend_with_mass_cancel() {
+    let mut exchange = Exchange::new();
+    assert_eq!(exchange.place(1, 100, 10, 5, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(exchange.place(2, 100, 11, 7, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(exchange.place(3, 200, 20, 3, OrderAction::Ask), CommandResultCode::Success);
+    assert_eq!(exchange.balance(100), 988);
+    assert_eq!(exchange.balance(200), 997);
+
+    let result = exchange.submit(user_command(OrderCommandType::SuspendUser, SUSPEND_USER_CANCEL_ORDERS));
+    assert_eq!(result.result_code, CommandResultCode::Success);
+    assert_eq!(result.open_orders.len(), 3);
+    assert_eq!(result.matcher_events.len(), 3);
+
+    // 冻结资金按各自交易对返还
+    assert_eq!(exchange.balance(100), 1_000);
+    assert_eq!(exchange.balance(200), 1_000);
+    assert!(exchange.matching.get_user_orders(1001, None).is_empty());
+    assert_eq!(exchange.matching.get_l2_data(100, 10).unwrap().ask_prices.len(), 0);
+
+    assert_eq!(
+        exchange.submit(user_command(OrderCommandType::SuspendUser, SUSPEND_USER_CANCEL_ORDERS)).result_code,
+        CommandResultCode::UserMgmtUserAlreadySuspended
+    );
+}
+
+#[test]
+fn test_suspend_unknown_user() {
+    let mut exchange = Exchange::new();
+    let mut cmd = user_command(OrderCommandType::SuspendUser, 0);
+    cmd.uid = 9999;
+    assert_eq!(exchange.submit(cmd).result_code, CommandResultCode::AuthInvalidUser);
+}
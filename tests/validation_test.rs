@@ -0,0 +1,261 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use proptest::prelude::*;
+
+fn setup(order_book_kind: OrderBookKind) -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        order_book_kind,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: 1,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    for uid in [1001, 1002] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000_000,
+                order_id: uid as OrderId * 10 + currency as OrderId,
+                ..Default::default()
+            });
+        }
+    }
+    core
+}
+
+fn order(order_type: OrderType) -> OrderCommand {
+    OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1001,
+        order_id: 1,
+        symbol: 100,
+        price: 1000,
+        reserve_price: 1000,
+        size: 10,
+        action: OrderAction::Bid,
+        order_type,
+        timestamp: 5000,
+        ..Default::default()
+    }
+}
+
+fn malformed_orders() -> Vec<(OrderCommand, CommandResultCode)> {
+    vec![
+        (OrderCommand { size: 0, ..order(OrderType::Gtc) }, CommandResultCode::ValidationInvalidSize),
+        (OrderCommand { size: -5, ..order(OrderType::Ioc) }, CommandResultCode::ValidationInvalidSize),
+        (OrderCommand { price: -1, ..order(OrderType::Gtc) }, CommandResultCode::ValidationInvalidPrice),
+        (OrderCommand { price: 0, ..order(OrderType::Gtc) }, CommandResultCode::ValidationInvalidPrice),
+        (OrderCommand { price: 0, ..order(OrderType::FokBudget) }, CommandResultCode::ValidationInvalidPrice),
+        (OrderCommand { price: -1, ..order(OrderType::Market) }, CommandResultCode::ValidationInvalidPrice),
+        (OrderCommand { reserve_price: -1, ..order(OrderType::Gtc) }, CommandResultCode::ValidationInvalidReservePrice),
+        (order(OrderType::StopLimit), CommandResultCode::ValidationMissingStopPrice),
+        (order(OrderType::StopMarket), CommandResultCode::ValidationMissingStopPrice),
+        (
+            OrderCommand { stop_price: Some(0), ..order(OrderType::StopLimit) },
+            CommandResultCode::ValidationInvalidStopPrice,
+        ),
+        (order(OrderType::Iceberg), CommandResultCode::ValidationMissingVisibleSize),
+        (
+            OrderCommand { visible_size: Some(11), ..order(OrderType::Iceberg) },
+            CommandResultCode::ValidationInvalidVisibleSize,
+        ),
+        (
+            OrderCommand { visible_size: Some(5), replenish_size: Some(0), ..order(OrderType::Iceberg) },
+            CommandResultCode::ValidationInvalidVisibleSize,
+        ),
+        (order(OrderType::Gtd(4999)), CommandResultCode::ValidationOrderExpired),
+        (
+            OrderCommand { expire_time: Some(100), ..order(OrderType::Gtc) },
+            CommandResultCode::ValidationOrderExpired,
+        ),
+        (
+            OrderCommand { action: OrderAction::Ask, ..order(OrderType::IocBudget) },
+            CommandResultCode::ValidationInvalidAction,
+        ),
+    ]
+}
+
+#[test]
+fn test_malformed_orders_rejected_before_risk() {
+    let mut core = setup(OrderBookKind::Advanced);
+    for (i, (cmd, expected)) in malformed_orders().into_iter().enumerate() {
+        let result = core.submit_command(cmd);
+        assert_eq!(result.result_code, expected, "case {}", i);
+        assert!(result.matcher_events.is_empty());
+    }
+
+    // 校验失败的订单不冻结资金、不进入订单簿
+    core.verify_invariants().unwrap();
+    let book = core.submit_command(OrderCommand {
+        command: OrderCommandType::OrderBookRequest,
+        symbol: 100,
+        size: 10,
+        ..Default::default()
+    });
+    assert!(book.market_data.unwrap().bid_prices.is_empty());
+}
+
+#[test]
+fn test_staged_pipeline_validates_same_commands() {
+    let mut core = setup(OrderBookKind::Advanced);
+    core.startup();
+    let (commands, expected): (Vec<_>, Vec<_>) = malformed_orders().into_iter().unzip();
+    let futures: Vec<_> = commands.into_iter().map(|cmd| core.submit_command_async(cmd)).collect();
+    for (future, expected) in futures.into_iter().zip(expected) {
+        let result = future.wait();
+        assert_eq!(result.result_code, expected);
+        assert!(result.matcher_events.is_empty());
+    }
+}
+
+#[test]
+fn test_well_formed_orders_pass_validation() {
+    let mut core = setup(OrderBookKind::Advanced);
+    let cases = [
+        order(OrderType::Gtc),
+        OrderCommand { stop_price: Some(1200), ..order(OrderType::StopLimit) },
+        OrderCommand { visible_size: Some(5), ..order(OrderType::Iceberg) },
+        order(OrderType::Gtd(5000)),
+        OrderCommand { price: 0, ..order(OrderType::Market) },
+    ];
+    for (i, cmd) in cases.into_iter().enumerate() {
+        let result = core.submit_command(OrderCommand { order_id: i as OrderId + 1, ..cmd });
+        assert_eq!(result.result_code, CommandResultCode::Success, "case {}", i);
+    }
+
+    let move_to_zero = core.submit_command(OrderCommand {
+        command: OrderCommandType::MoveOrder,
+        uid: 1001,
+        order_id: 1,
+        symbol: 100,
+        price: 0,
+        ..Default::default()
+    });
+    assert_eq!(move_to_zero.result_code, CommandResultCode::ValidationInvalidPrice);
+    let reduce_negative = core.submit_command(OrderCommand {
+        command: OrderCommandType::ReduceOrder,
+        uid: 1001,
+        order_id: 1,
+        symbol: 100,
+        size: -3,
+        ..Default::default()
+    });
+    assert_eq!(reduce_negative.result_code, CommandResultCode::ValidationInvalidSize);
+    core.verify_invariants().unwrap();
+}
+
+fn order_type_strategy() -> impl Strategy<Value = OrderType> {
+    prop_oneof![
+        Just(OrderType::Gtc),
+        Just(OrderType::Ioc),
+        Just(OrderType::Fok),
+        Just(OrderType::FokBudget),
+        Just(OrderType::IocBudget),
+        Just(OrderType::PostOnly),
+        Just(OrderType::StopLimit),
+        Just(OrderType::StopMarket),
+        Just(OrderType::Iceberg),
+        Just(OrderType::Day),
+        (-100..10_000i64).prop_map(OrderType::Gtd),
+        Just(OrderType::Market),
+        Just(OrderType::Gtx),
+        Just(OrderType::MarketToLimit),
+    ]
+}
+
+prop_compose! {
+    fn command_strategy()(
+        command in prop_oneof![
+            6 => Just(OrderCommandType::PlaceOrder),
+            1 => Just(OrderCommandType::MoveOrder),
+            1 => Just(OrderCommandType::CancelOrder),
+            1 => Just(OrderCommandType::ReduceOrder),
+        ],
+        uid in 1001..1003u64,
+        order_id in 0..80u64,
+        price in -10..1100i64,
+        reserve_price in -10..1100i64,
+        size in -5..50i64,
+        bid in any::<bool>(),
+        order_type in order_type_strategy(),
+        stop_price in proptest::option::of(-10..1100i64),
+        visible_size in proptest::option::of(-5..50i64),
+        replenish_size in proptest::option::of(-5..50i64),
+        expire_time in proptest::option::of(-100..10_000i64),
+        timestamp in 0..5000i64,
+    ) -> OrderCommand {
+        OrderCommand {
+            command,
+            uid,
+            order_id,
+            symbol: 100,
+            price,
+            reserve_price,
+            size,
+            action: if bid { OrderAction::Bid } else { OrderAction::Ask },
+            order_type,
+            stop_price,
+            visible_size,
+            replenish_size,
+            expire_time,
+            timestamp,
+            ..Default::default()
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// 任意字段组合都不会 panic，校验失败的命令返回对应结果码且不产生事件
+    #[test]
+    fn prop_arbitrary_commands_never_panic(commands in prop::collection::vec(command_strategy(), 1..80)) {
+        let mut core = setup(OrderBookKind::Advanced);
+        for (i, mut cmd) in commands.into_iter().enumerate() {
+            // 新订单使用唯一订单号，撤单/改价/减量随机指向已有订单
+            if cmd.command == OrderCommandType::PlaceOrder {
+                cmd.order_id = 100 + i as OrderId;
+            } else {
+                cmd.order_id += 100;
+            }
+            let expected = validate_command(&cmd);
+            let result = core.submit_command(cmd);
+            if is_validation_error(expected) {
+                prop_assert_eq!(result.result_code, expected);
+                prop_assert!(result.matcher_events.is_empty());
+            }
+        }
+    }
+
+    /// 校验失败的命令不进入风控与撮合：资金守恒且订单簿为空
+    #[test]
+    fn prop_rejected_commands_leave_no_trace(commands in prop::collection::vec(command_strategy(), 1..80)) {
+        let mut core = setup(OrderBookKind::Advanced);
+        for cmd in commands.into_iter().filter(|cmd| is_validation_error(validate_command(cmd))) {
+            core.submit_command(cmd);
+        }
+        prop_assert!(core.verify_invariants().is_ok());
+        let book = core.submit_command(OrderCommand {
+            command: OrderCommandType::OrderBookRequest,
+            symbol: 100,
+            size: 10,
+            ..Default::default()
+        });
+        let book = book.market_data.unwrap();
+        prop_assert!(book.bid_prices.is_empty() && book.ask_prices.is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "websocket")]
+
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::websocket::*;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+
+fn setup(config: HubConfig) -> (ExchangeCore, SharedHub) {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    let (hub, consumer) = MarketDataHub::new(config).into_consumer();
+    core.add_market_data_consumer(consumer);
+
+    for symbol_id in [100, 200] {
+        core.add_symbol(CoreSymbolSpecification {
+            symbol_id,
+            base_currency: 2,
+            quote_currency: 1,
+            ..Default::default()
+        });
+    }
+    for (uid, currency) in [(1001, 1), (1002, 2)] {
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::AddUser,
+            uid,
+            ..Default::default()
+        });
+        core.submit_command(OrderCommand {
+            command: OrderCommandType::BalanceAdjustment,
+            uid,
+            symbol: currency,
+            price: 1_000_000,
+            order_id: uid as OrderId,
+            ..Default::default()
+        });
+    }
+    (core, hub)
+}
+
+fn place(core: &mut ExchangeCore, uid: UserId, order_id: OrderId, symbol: SymbolId, price: Price, size: Size, action: OrderAction) {
+    let cmd = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid,
+        order_id,
+        symbol,
+        price,
+        reserve_price: price,
+        size,
+        action,
+        order_type: OrderType::Gtc,
+        timestamp: 1000 + order_id as i64,
+        ..Default::default()
+    });
+    assert_eq!(cmd.result_code, CommandResultCode::Success);
+}
+
+fn drain(receiver: &Receiver<ServerMessage>) -> Vec<ServerMessage> {
+    receiver.try_iter().collect()
+}
+
+#[test]
+fn test_snapshot_on_subscribe() {
+    let (mut core, hub) = setup(HubConfig::default());
+    place(&mut core, 1002, 1, 100, 101, 5, OrderAction::Ask);
+    place(&mut core, 1002, 2, 100, 102, 7, OrderAction::Ask);
+    place(&mut core, 1001, 3, 100, 99, 4, OrderAction::Bid);
+    place(&mut core, 1001, 4, 100, 98, 6, OrderAction::Bid);
+
+    let (id, receiver) = hub.connect();
+    hub.subscribe(id, &[100]);
+    let messages = drain(&receiver);
+    assert_eq!(messages[0], ServerMessage::Subscribed { symbols: vec![100] });
+    let ServerMessage::Snapshot { symbol, seq, bids, asks } = &messages[1] else {
+        panic!("应推送快照: {:?}", messages[1]);
+    };
+    assert_eq!(*symbol, 100);
+    assert_eq!(bids, &vec![(99, 4), (98, 6)]);
+    assert_eq!(asks, &vec![(101, 5), (102, 7)]);
+    let snapshot_seq = *seq;
+
+    // 快照之后的增量序号连续
+    place(&mut core, 1001, 5, 100, 101, 2, OrderAction::Bid);
+    let messages = drain(&receiver);
+    let l2: Vec<_> = messages.iter().filter(|m| matches!(m, ServerMessage::L2 { .. })).collect();
+    assert_eq!(l2.len(), 1);
+    assert_eq!(
+        l2[0],
+        &ServerMessage::L2 { symbol: 100, seq: snapshot_seq + 1, side: OrderAction::Ask, price: 101, volume: 3 }
+    );
+    assert!(messages.iter().any(|m| matches!(m, ServerMessage::Trade { price: 101, size: 2, .. })));
+    assert!(messages.iter().any(|m| matches!(m, ServerMessage::Bbo { ask: Some(101), ask_size: 3, .. })));
+
+    // 重复订阅只确认，不再推送快照；空交易对的快照为空
+    hub.subscribe(id, &[100, 200]);
+    let messages = drain(&receiver);
+    assert_eq!(messages[0], ServerMessage::Subscribed { symbols: vec![200] });
+    assert_eq!(messages[1], ServerMessage::Snapshot { symbol: 200, seq: 0, bids: vec![], asks: vec![] });
+    assert_eq!(messages.len(), 2);
+}
+
+#[test]
+fn test_symbol_filtering_and_unsubscribe() {
+    let (mut core, hub) = setup(HubConfig::default());
+    let (id, receiver) = hub.connect();
+    hub.subscribe(id, &[200]);
+    drain(&receiver);
+
+    place(&mut core, 1002, 1, 100, 101, 5, OrderAction::Ask);
+    assert!(drain(&receiver).is_empty());
+
+    place(&mut core, 1002, 2, 200, 50, 5, OrderAction::Ask);
+    assert!(drain(&receiver).iter().all(|m| matches!(m, ServerMessage::L2 { symbol: 200, .. } | ServerMessage::Bbo { symbol: 200, .. })));
+
+    hub.unsubscribe(id, &[200, 300]);
+    assert_eq!(drain(&receiver), vec![ServerMessage::Unsubscribed { symbols: vec![200] }]);
+    place(&mut core, 1002, 3, 200, 51, 5, OrderAction::Ask);
+    assert!(drain(&receiver).is_empty());
+
+    hub.disconnect(id);
+    assert_eq!(hub.connection_count(), 0);
+}
+
+#[test]
+fn test_slow_consumer_removed() {
+    let (mut core, hub) = setup(HubConfig { snapshot_depth: 10, queue_capacity: 4 });
+    let (slow, slow_receiver) = hub.connect();
+    let (fast, fast_receiver) = hub.connect();
+    hub.subscribe(slow, &[100]);
+    hub.subscribe(fast, &[100]);
+    drain(&fast_receiver);
+
+    for order_id in 1..=3 {
+        place(&mut core, 1002, order_id, 100, 100 + order_id as Price, 1, OrderAction::Ask);
+        assert!(!drain(&fast_receiver).is_empty());
+    }
+    assert_eq!(hub.connection_count(), 1);
+    // 已入队的消息仍可读出，之后通道断开
+    assert_eq!(drain(&slow_receiver).len(), 4);
+    assert!(slow_receiver.recv().is_err());
+}
+
+#[test]
+fn test_websocket_end_to_end() {
+    let (mut core, hub) = setup(HubConfig::default());
+    place(&mut core, 1002, 1, 100, 101, 5, OrderAction::Ask);
+
+    let server = WsServer::bind("127.0.0.1:0", hub.clone()).unwrap();
+    let (mut ws, _) = tungstenite::connect(format!("ws://{}", server.local_addr())).unwrap();
+    let request = serde_json::to_string(&ClientMessage::Subscribe { symbols: vec![100] }).unwrap();
+    ws.send(Message::text(request)).unwrap();
+
+    let read = |ws: &mut tungstenite::WebSocket<_>| loop {
+        if let Message::Text(text) = ws.read().unwrap() {
+            return serde_json::from_str::<ServerMessage>(&text).unwrap();
+        }
+    };
+    assert_eq!(read(&mut ws), ServerMessage::Subscribed { symbols: vec![100] });
+    let ServerMessage::Snapshot { asks, .. } = read(&mut ws) else {
+        panic!("应推送快照");
+    };
+    assert_eq!(asks, vec![(101, 5)]);
+
+    place(&mut core, 1002, 2, 100, 103, 2, OrderAction::Ask);
+    assert!(matches!(read(&mut ws), ServerMessage::L2 { price: 103, volume: 2, .. }));
+
+    ws.send(Message::text("not json")).unwrap();
+    assert!(matches!(read(&mut ws), ServerMessage::Error { .. }));
+
+    ws.close(None).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while hub.connection_count() > 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(hub.connection_count(), 0);
+    server.shutdown();
+}
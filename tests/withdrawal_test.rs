@@ -0,0 +1,109 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+
+const QUOTE: Currency = 1;
+
+fn setup() -> ExchangeCore {
+    let mut core = ExchangeCore::new(ExchangeConfig {
+        ring_buffer_size: 1024,
+        ..Default::default()
+    });
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        symbol_type: SymbolType::CurrencyExchangePair,
+        base_currency: 2,
+        quote_currency: QUOTE,
+        base_scale_k: 1,
+        quote_scale_k: 1,
+        ..Default::default()
+    });
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::AddUser,
+        uid: 1001,
+        ..Default::default()
+    });
+    core.submit_command(OrderCommand {
+        command: OrderCommandType::BalanceAdjustment,
+        uid: 1001,
+        symbol: QUOTE,
+        price: 1_000,
+        order_id: 1,
+        ..Default::default()
+    });
+    core
+}
+
+fn withdrawal(command: OrderCommandType, transaction_id: OrderId, amount: i64) -> OrderCommand {
+    OrderCommand {
+        command,
+        uid: 1001,
+        symbol: QUOTE,
+        price: amount,
+        order_id: transaction_id,
+        ..Default::default()
+    }
+}
+
+fn balance(core: &ExchangeCore) -> i64 {
+    core.serialize_state().pipeline_state.risk_engines[0].get_user(1001).unwrap().accounts[&QUOTE]
+}
+
+#[test]
+fn test_hold_confirm_and_release() {
+    let mut core = setup();
+    assert_eq!(core.submit_command(withdrawal(OrderCommandType::HoldFunds, 10, 300)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(withdrawal(OrderCommandType::HoldFunds, 11, 200)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core), 500);
+    core.verify_invariants().unwrap();
+
+    // 冻结的资金不能用于下单
+    let order = core.submit_command(OrderCommand {
+        command: OrderCommandType::PlaceOrder,
+        uid: 1001,
+        order_id: 20,
+        symbol: 100,
+        price: 100,
+        reserve_price: 100,
+        size: 6,
+        action: OrderAction::Bid,
+        order_type: OrderType::Gtc,
+        ..Default::default()
+    });
+    assert_eq!(order.result_code, CommandResultCode::RiskNsf);
+
+    assert_eq!(core.submit_command(withdrawal(OrderCommandType::ConfirmWithdrawal, 10, 0)).result_code, CommandResultCode::Success);
+    assert_eq!(core.submit_command(withdrawal(OrderCommandType::ReleaseHold, 11, 0)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&core), 700);
+    core.verify_invariants().unwrap();
+}
+
+#[test]
+fn test_withdrawal_commands_are_idempotent() {
+    let mut core = setup();
+    let cases = [
+        (withdrawal(OrderCommandType::HoldFunds, 10, 300), CommandResultCode::Success),
+        // 重试不会重复冻结
+        (withdrawal(OrderCommandType::HoldFunds, 10, 300), CommandResultCode::Success),
+        (withdrawal(OrderCommandType::HoldFunds, 10, 301), CommandResultCode::WithdrawalTransactionConflict),
+        (withdrawal(OrderCommandType::HoldFunds, 11, 701), CommandResultCode::RiskNsf),
+        (withdrawal(OrderCommandType::HoldFunds, 12, 0), CommandResultCode::ValidationInvalidPrice),
+        (withdrawal(OrderCommandType::ConfirmWithdrawal, 12, 0), CommandResultCode::WithdrawalUnknownTransaction),
+        (withdrawal(OrderCommandType::ConfirmWithdrawal, 10, 0), CommandResultCode::Success),
+        // 重复确认不会重复扣款，已确认的冻结不能再取消
+        (withdrawal(OrderCommandType::ConfirmWithdrawal, 10, 0), CommandResultCode::Success),
+        (withdrawal(OrderCommandType::ReleaseHold, 10, 0), CommandResultCode::WithdrawalTransactionConflict),
+        (OrderCommand { uid: 9999, ..withdrawal(OrderCommandType::HoldFunds, 13, 1) }, CommandResultCode::AuthInvalidUser),
+    ];
+    for (i, (cmd, expected)) in cases.into_iter().enumerate() {
+        assert_eq!(core.submit_command(cmd).result_code, expected, "case {}", i);
+    }
+    assert_eq!(balance(&core), 700);
+    core.verify_invariants().unwrap();
+
+    // 快照恢复后仍然识别已完结的交易号
+    let mut restored = ExchangeCore::from_state(core.serialize_state());
+    assert_eq!(restored.submit_command(withdrawal(OrderCommandType::ConfirmWithdrawal, 10, 0)).result_code, CommandResultCode::Success);
+    assert_eq!(restored.submit_command(withdrawal(OrderCommandType::HoldFunds, 10, 300)).result_code, CommandResultCode::Success);
+    assert_eq!(balance(&restored), 700);
+    restored.verify_invariants().unwrap();
+}
@@ -0,0 +1,112 @@
+use matching_core::api::*;
+use matching_core::core::exchange::{ExchangeConfig, ExchangeCore};
+use matching_core::workload::*;
+
+fn mixed_config(seed: u64) -> WorkloadConfig {
+    WorkloadConfig {
+        seed,
+        symbols: vec![100],
+        uids: vec![1, 2, 3],
+        order_types: vec![
+            (OrderType::Gtc, 60),
+            (OrderType::Ioc, 20),
+            (OrderType::Iceberg, 10),
+            (OrderType::Gtd(0), 10),
+        ],
+        cancel_rate: 0.2,
+        move_rate: 0.1,
+        reduce_rate: 0.05,
+        ..Default::default()
+    }
+}
+
+fn key(cmd: &OrderCommand) -> (OrderCommandType, UserId, OrderId, Price, Size, OrderAction, OrderType, i64) {
+    (cmd.command, cmd.uid, cmd.order_id, cmd.price, cmd.size, cmd.action, cmd.order_type, cmd.timestamp)
+}
+
+#[test]
+fn test_same_seed_is_reproducible() {
+    let a: Vec<_> = OrderFlowGenerator::new(mixed_config(7)).take(2_000).map(|c| key(&c)).collect();
+    let b: Vec<_> = OrderFlowGenerator::new(mixed_config(7)).take_commands(2_000).iter().map(key).collect();
+    let c: Vec<_> = OrderFlowGenerator::new(mixed_config(8)).take(2_000).map(|c| key(&c)).collect();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_order_mix_ratios() {
+    let commands = OrderFlowGenerator::new(mixed_config(1)).take_commands(20_000);
+    let count = |f: &dyn Fn(&OrderCommand) -> bool| commands.iter().filter(|c| f(c)).count() as f64;
+    let places = count(&|c| c.command == OrderCommandType::PlaceOrder);
+    let cancels = count(&|c| c.command == OrderCommandType::CancelOrder);
+    let bids = count(&|c| c.command == OrderCommandType::PlaceOrder && c.action == OrderAction::Bid);
+    let iocs = count(&|c| c.order_type == OrderType::Ioc);
+
+    assert!((cancels / 20_000.0 - 0.2).abs() < 0.02, "撤单占比 {}", cancels / 20_000.0);
+    assert!((bids / places - 0.5).abs() < 0.03);
+    assert!((iocs / places - 0.2).abs() < 0.03);
+
+    for cmd in commands.iter().filter(|c| c.command == OrderCommandType::PlaceOrder) {
+        assert!((1..=10).contains(&cmd.size));
+        assert!((9_990..=10_010).contains(&cmd.price));
+        match cmd.order_type {
+            OrderType::Iceberg => assert!(cmd.visible_size.is_some()),
+            OrderType::Gtd(expire) => assert_eq!(cmd.expire_time, Some(expire)),
+            _ => {}
+        }
+    }
+    // 订单号连续递增
+    let ids: Vec<_> = commands.iter().filter(|c| c.command == OrderCommandType::PlaceOrder).map(|c| c.order_id).collect();
+    assert!(ids.windows(2).all(|w| w[1] == w[0] + 1));
+}
+
+#[test]
+fn test_passive_prices_never_cross() {
+    let config = WorkloadConfig {
+        price: PriceDistribution::Passive { ticks: 5 },
+        tick_size: 10,
+        ..Default::default()
+    };
+    for cmd in OrderFlowGenerator::new(config).take(1_000) {
+        match cmd.action {
+            OrderAction::Bid => assert!((9_950..10_000).contains(&cmd.price)),
+            _ => assert!((10_010..=10_050).contains(&cmd.price)),
+        }
+        assert_eq!(cmd.price % 10, 0);
+    }
+}
+
+#[test]
+fn test_generated_flow_against_engine() {
+    let mut core = ExchangeCore::new(ExchangeConfig::default());
+    core.add_symbol(CoreSymbolSpecification {
+        symbol_id: 100,
+        base_currency: 2,
+        quote_currency: 1,
+        order_book: Some(OrderBookKind::Advanced),
+        ..Default::default()
+    });
+    for uid in 1..=3 {
+        core.submit_command(OrderCommand { command: OrderCommandType::AddUser, uid, ..Default::default() });
+        for currency in [1, 2] {
+            core.submit_command(OrderCommand {
+                command: OrderCommandType::BalanceAdjustment,
+                uid,
+                symbol: currency,
+                price: 1_000_000_000,
+                order_id: uid * 10 + currency as u64,
+                ..Default::default()
+            });
+        }
+    }
+
+    let mut config = mixed_config(3);
+    config.reduce_rate = 0.0; // 高级订单簿不支持减量
+    let mut trades = 0;
+    for cmd in OrderFlowGenerator::new(config).take(5_000) {
+        let result = core.submit_command(cmd);
+        trades += result.matcher_events.iter().filter(|e| e.event_type == MatcherEventType::Trade).count();
+    }
+    assert!(trades > 0);
+    core.verify_invariants().unwrap();
+}